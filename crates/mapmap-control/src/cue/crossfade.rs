@@ -17,6 +17,8 @@ pub enum FadeCurve {
 pub struct Crossfade {
     start_time: Instant,
     duration: Duration,
+    fade_out: Duration,
+    delay: Duration,
     curve: FadeCurve,
     from_cue_id: u32,
     to_cue_id: u32,
@@ -25,29 +27,82 @@ pub struct Crossfade {
 impl Crossfade {
     /// Create a new crossfade
     pub fn new(from_cue_id: u32, to_cue_id: u32, duration: Duration, curve: FadeCurve) -> Self {
+        Self::new_at(from_cue_id, to_cue_id, duration, curve, Instant::now())
+    }
+
+    pub(super) fn new_at(
+        from_cue_id: u32,
+        to_cue_id: u32,
+        duration: Duration,
+        curve: FadeCurve,
+        start_time: Instant,
+    ) -> Self {
         Self {
-            start_time: Instant::now(),
+            start_time,
             duration,
+            fade_out: duration,
+            delay: Duration::ZERO,
             curve,
             from_cue_id,
             to_cue_id,
         }
     }
 
+    /// Use a separate duration for values that decrease
+    pub fn with_fade_out(mut self, fade_out: Duration) -> Self {
+        self.fade_out = fade_out;
+        self
+    }
+
+    /// Wait before the fade starts
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
     /// Get the current progress (0.0 to 1.0)
     pub fn progress(&self) -> f32 {
-        let elapsed = self.start_time.elapsed();
-        if elapsed >= self.duration {
-            return 1.0;
-        }
+        self.progress_at(Instant::now())
+    }
 
-        let linear_progress = elapsed.as_secs_f32() / self.duration.as_secs_f32();
-        self.curve.apply(linear_progress)
+    pub(super) fn progress_at(&self, now: Instant) -> f32 {
+        self.progress_over(self.duration, now)
+    }
+
+    /// Get the progress for values that increase (fade-in time)
+    pub fn fade_in_progress(&self) -> f32 {
+        self.fade_in_progress_at(Instant::now())
+    }
+
+    pub(super) fn fade_in_progress_at(&self, now: Instant) -> f32 {
+        self.progress_over(self.duration, now)
+    }
+
+    /// Get the progress for values that decrease (fade-out time)
+    pub fn fade_out_progress(&self) -> f32 {
+        self.fade_out_progress_at(Instant::now())
+    }
+
+    pub(super) fn fade_out_progress_at(&self, now: Instant) -> f32 {
+        self.progress_over(self.fade_out, now)
     }
 
     /// Check if the crossfade is complete
     pub fn is_complete(&self) -> bool {
-        self.start_time.elapsed() >= self.duration
+        self.is_complete_at(Instant::now())
+    }
+
+    pub(super) fn is_complete_at(&self, now: Instant) -> bool {
+        self.elapsed_at(now) >= self.delay + self.duration.max(self.fade_out)
+    }
+
+    /// Check if the delay has passed and values have started moving
+    pub fn has_started(&self) -> bool {
+        self.has_started_at(Instant::now())
+    }
+
+    pub(super) fn has_started_at(&self, now: Instant) -> bool {
+        self.elapsed_at(now) >= self.delay
     }
 
     /// Get the source cue ID
@@ -69,6 +124,34 @@ impl Crossfade {
     pub fn duration(&self) -> Duration {
         self.duration
     }
+
+    /// Get the fade-out duration
+    pub fn fade_out(&self) -> Duration {
+        self.fade_out
+    }
+
+    /// Get the delay
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    fn elapsed_at(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.start_time)
+    }
+
+    fn progress_over(&self, duration: Duration, now: Instant) -> f32 {
+        if !self.has_started_at(now) {
+            return 0.0;
+        }
+
+        let elapsed = self.elapsed_at(now) - self.delay;
+        if elapsed >= duration {
+            return 1.0;
+        }
+
+        let linear_progress = elapsed.as_secs_f32() / duration.as_secs_f32();
+        self.curve.apply(linear_progress)
+    }
 }
 
 impl FadeCurve {
@@ -94,7 +177,8 @@ impl FadeCurve {
 
 /// Interpolate between two values using a progress value (0.0 to 1.0)
 pub fn interpolate_f32(from: f32, to: f32, progress: f32) -> f32 {
-    from + (to - from) * progress
+    // Weighted form so the end points are hit exactly
+    from * (1.0 - progress) + to * progress
 }
 
 /// Interpolate between two positions
@@ -165,15 +249,40 @@ mod tests {
 
     #[test]
     fn test_crossfade_progress() {
-        let crossfade = Crossfade::new(0, 1, Duration::from_millis(100), FadeCurve::Linear);
+        let start = Instant::now();
+        let crossfade =
+            Crossfade::new_at(0, 1, Duration::from_millis(100), FadeCurve::Linear, start);
+
+        assert_eq!(crossfade.progress_at(start), 0.0);
+        assert_eq!(
+            crossfade.progress_at(start + Duration::from_millis(50)),
+            0.5
+        );
+        assert!(!crossfade.is_complete_at(start + Duration::from_millis(99)));
+
+        assert!(crossfade.is_complete_at(start + Duration::from_millis(100)));
+        assert_eq!(
+            crossfade.progress_at(start + Duration::from_millis(150)),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_crossfade_split_timing() {
+        let start = Instant::now();
+        let crossfade =
+            Crossfade::new_at(0, 1, Duration::from_millis(20), FadeCurve::Linear, start)
+                .with_fade_out(Duration::from_secs(60))
+                .with_delay(Duration::from_millis(10));
 
-        // Should start at 0
-        let initial_progress = crossfade.progress();
-        assert!((0.0..0.1).contains(&initial_progress));
+        let waiting = start + Duration::from_millis(5);
+        assert!(!crossfade.has_started_at(waiting));
+        assert_eq!(crossfade.fade_in_progress_at(waiting), 0.0);
 
-        // Wait and check progress increases
-        std::thread::sleep(Duration::from_millis(150));
-        assert!(crossfade.is_complete());
-        assert_eq!(crossfade.progress(), 1.0);
+        let later = start + Duration::from_millis(50);
+        assert!(crossfade.has_started_at(later));
+        assert_eq!(crossfade.fade_in_progress_at(later), 1.0);
+        assert!(crossfade.fade_out_progress_at(later) < 0.1);
+        assert!(!crossfade.is_complete_at(later));
     }
 }
@@ -2,12 +2,13 @@
 //!
 //! A cue is a snapshot of the entire project state at a point in time.
 
+use mapmap_core::AppState;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::Duration;
 
 use super::crossfade::FadeCurve;
-use super::triggers::{MidiTrigger, TimeTrigger};
+use super::triggers::{KeyTrigger, MidiTrigger, OscTrigger, TimeTrigger, TimecodeTrigger};
 
 /// A cue stores a complete snapshot of project state
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,21 +20,57 @@ pub struct Cue {
     // State snapshots
     pub layer_states: HashMap<u32, LayerState>,
     pub paint_states: HashMap<u32, PaintState>,
-    pub effect_states: HashMap<u32, EffectState>,
+    #[serde(with = "effect_entries")]
+    pub effect_states: HashMap<EffectKey, EffectState>,
     pub global_state: GlobalState,
+    /// Module driving the show while this cue is active
+    #[serde(default)]
+    pub active_module: Option<u64>,
+
+    /// Block cue: stores hard values and stops tracking from earlier cues
+    #[serde(default)]
+    pub block: bool,
 
     // Transition settings
+    /// Fade time for values that increase (and for all values unless a
+    /// separate fade-out time is set)
     pub fade_duration: Duration,
+    /// Fade time for values that decrease
+    #[serde(default)]
+    pub fade_out_duration: Option<Duration>,
+    /// Wait before the fade starts
+    #[serde(default)]
+    pub delay: Duration,
     pub fade_curve: FadeCurve,
+    /// What happens to values this cue does not store
+    #[serde(default)]
+    pub missing_values: MissingValueMode,
 
     // Triggers
     pub auto_follow: Option<Duration>, // Auto-advance after duration
     pub midi_trigger: Option<MidiTrigger>,
     pub time_trigger: Option<TimeTrigger>,
+    #[serde(default)]
+    pub osc_trigger: Option<OscTrigger>,
+    #[serde(default)]
+    pub key_trigger: Option<KeyTrigger>,
+    /// Fire when chased timecode reaches this position
+    #[serde(default)]
+    pub timecode_trigger: Option<TimecodeTrigger>,
+}
+
+/// How a cue treats parameters it does not store
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MissingValueMode {
+    /// Leave the parameter at its current value
+    #[default]
+    Keep,
+    /// Fade the parameter back to its default value
+    FadeToDefault,
 }
 
 /// Snapshot of a layer's state
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LayerState {
     pub opacity: f32,
     pub visible: bool,
@@ -43,22 +80,29 @@ pub struct LayerState {
 }
 
 /// Snapshot of a paint's state
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PaintState {
     pub parameters: HashMap<String, f32>,
 }
 
+/// Key of an effect in [`Cue::effect_states`]: (layer ID, effect ID)
+///
+/// Effect IDs are only unique within a layer's effect chain.
+pub type EffectKey = (u64, u64);
+
 /// Snapshot of an effect's state
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EffectState {
     pub enabled: bool,
     pub parameters: HashMap<String, f32>,
 }
 
 /// Global playback state
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GlobalState {
-    pub playback_speed: f32,
+    /// Master speed, left untouched if the cue does not store it
+    #[serde(default)]
+    pub playback_speed: Option<f32>,
     pub playback_position: f32,
     pub output_brightness: HashMap<u32, f32>,
 }
@@ -66,7 +110,7 @@ pub struct GlobalState {
 impl Default for GlobalState {
     fn default() -> Self {
         Self {
-            playback_speed: 1.0,
+            playback_speed: None,
             playback_position: 0.0,
             output_brightness: HashMap::new(),
         }
@@ -84,11 +128,19 @@ impl Cue {
             paint_states: HashMap::new(),
             effect_states: HashMap::new(),
             global_state: GlobalState::default(),
+            active_module: None,
+            block: false,
             fade_duration: Duration::from_secs(2),
+            fade_out_duration: None,
+            delay: Duration::ZERO,
             fade_curve: FadeCurve::Linear,
+            missing_values: MissingValueMode::Keep,
             auto_follow: None,
             midi_trigger: None,
             time_trigger: None,
+            osc_trigger: None,
+            key_trigger: None,
+            timecode_trigger: None,
         }
     }
 
@@ -106,6 +158,29 @@ impl Cue {
         self
     }
 
+    /// Set a separate fade duration for values that decrease
+    pub fn with_fade_out_duration(mut self, duration: Duration) -> Self {
+        self.fade_out_duration = Some(duration);
+        self
+    }
+
+    /// Set the delay before the fade starts
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Set how parameters missing from this cue are handled
+    pub fn with_missing_values(mut self, mode: MissingValueMode) -> Self {
+        self.missing_values = mode;
+        self
+    }
+
+    /// Get the fade duration for values that decrease
+    pub fn fade_out(&self) -> Duration {
+        self.fade_out_duration.unwrap_or(self.fade_duration)
+    }
+
     /// Set the fade curve
     pub fn with_fade_curve(mut self, curve: FadeCurve) -> Self {
         self.fade_curve = curve;
@@ -129,8 +204,8 @@ impl Cue {
     }
 
     /// Add an effect state snapshot
-    pub fn add_effect_state(&mut self, effect_id: u32, state: EffectState) {
-        self.effect_states.insert(effect_id, state);
+    pub fn add_effect_state(&mut self, key: EffectKey, state: EffectState) {
+        self.effect_states.insert(key, state);
     }
 
     /// Check if this cue has any state
@@ -139,6 +214,220 @@ impl Cue {
             && self.paint_states.is_empty()
             && self.effect_states.is_empty()
     }
+
+    /// Remove all stored values, keeping metadata and transition settings
+    pub fn clear_states(&mut self) {
+        self.layer_states.clear();
+        self.paint_states.clear();
+        self.effect_states.clear();
+        self.global_state = GlobalState::default();
+        self.active_module = None;
+        self.block = false;
+    }
+
+    /// Record a cue from the live project state
+    ///
+    /// Captures every layer, the effect chain of each layer, paint playback
+    /// parameters, output brightness and the active module.
+    pub fn capture(id: u32, name: String, state: &AppState) -> Self {
+        let mut cue = Self::new(id, name);
+
+        for layer in state.layer_manager.layers() {
+            let transform = &layer.transform;
+            cue.add_layer_state(
+                layer.id as u32,
+                LayerState::new(
+                    layer.opacity,
+                    layer.visible,
+                    (transform.position.x, transform.position.y),
+                    transform.rotation.z.to_degrees(),
+                    transform.scale.x,
+                ),
+            );
+
+            for effect in &layer.effect_chain.effects {
+                let mut effect_state = EffectState::new(effect.enabled);
+                effect_state.set_parameter("intensity".to_string(), effect.intensity);
+                for (param, value) in &effect.parameters {
+                    effect_state.set_parameter(param.clone(), *value);
+                }
+                cue.add_effect_state((layer.id, effect.id), effect_state);
+            }
+        }
+
+        for paint in state.paint_manager.paints() {
+            let mut paint_state = PaintState::new();
+            paint_state.set_parameter("opacity".to_string(), paint.opacity);
+            paint_state.set_parameter("rate".to_string(), paint.rate);
+            cue.add_paint_state(paint.id as u32, paint_state);
+        }
+
+        let composition = &state.layer_manager.composition;
+        cue.global_state.playback_speed = Some(composition.master_speed);
+        for output in state.output_manager.outputs() {
+            cue.global_state
+                .output_brightness
+                .insert(output.id as u32, output.color_calibration.brightness);
+        }
+
+        cue.active_module = state.module_manager.active_module();
+        cue
+    }
+
+    /// Reduce this cue to the values that differ from `previous`
+    ///
+    /// `previous` should be the tracked state of the preceding cue. The result
+    /// keeps this cue's metadata, transition settings and triggers.
+    pub fn changes_from(&self, previous: &Cue) -> Cue {
+        let mut delta = Cue {
+            layer_states: HashMap::new(),
+            paint_states: HashMap::new(),
+            effect_states: HashMap::new(),
+            global_state: GlobalState {
+                playback_speed: None,
+                output_brightness: HashMap::new(),
+                ..self.global_state.clone()
+            },
+            active_module: None,
+            block: false,
+            ..self.clone()
+        };
+
+        for (id, state) in &self.layer_states {
+            if previous.layer_states.get(id) != Some(state) {
+                delta.layer_states.insert(*id, state.clone());
+            }
+        }
+
+        for (id, state) in &self.paint_states {
+            let parameters = changed_parameters(
+                &state.parameters,
+                previous.paint_states.get(id).map(|p| &p.parameters),
+            );
+            if !parameters.is_empty() {
+                delta.paint_states.insert(*id, PaintState { parameters });
+            }
+        }
+
+        for (id, state) in &self.effect_states {
+            let prev = previous.effect_states.get(id);
+            let parameters = changed_parameters(&state.parameters, prev.map(|e| &e.parameters));
+            if !parameters.is_empty() || prev.map(|e| e.enabled) != Some(state.enabled) {
+                delta.effect_states.insert(
+                    *id,
+                    EffectState {
+                        enabled: state.enabled,
+                        parameters,
+                    },
+                );
+            }
+        }
+
+        if self.global_state.playback_speed != previous.global_state.playback_speed {
+            delta.global_state.playback_speed = self.global_state.playback_speed;
+        }
+
+        for (id, brightness) in &self.global_state.output_brightness {
+            if previous.global_state.output_brightness.get(id) != Some(brightness) {
+                delta
+                    .global_state
+                    .output_brightness
+                    .insert(*id, *brightness);
+            }
+        }
+
+        if self.active_module != previous.active_module {
+            delta.active_module = self.active_module;
+        }
+
+        delta
+    }
+
+    /// Apply the values stored in `next` on top of this cue
+    ///
+    /// Values not stored in `next` track through unchanged. If `next` is a
+    /// block cue, the tracked state is replaced entirely.
+    pub fn track(&mut self, next: &Cue) {
+        if next.block {
+            self.layer_states = next.layer_states.clone();
+            self.paint_states = next.paint_states.clone();
+            self.effect_states = next.effect_states.clone();
+            self.global_state = next.global_state.clone();
+            self.active_module = next.active_module;
+            return;
+        }
+
+        for (id, state) in &next.layer_states {
+            self.layer_states.insert(*id, state.clone());
+        }
+
+        for (id, state) in &next.paint_states {
+            self.paint_states
+                .entry(*id)
+                .or_default()
+                .parameters
+                .extend(state.parameters.iter().map(|(k, v)| (k.clone(), *v)));
+        }
+
+        for (id, state) in &next.effect_states {
+            let tracked = self
+                .effect_states
+                .entry(*id)
+                .or_insert_with(|| EffectState::new(state.enabled));
+            tracked.enabled = state.enabled;
+            tracked
+                .parameters
+                .extend(state.parameters.iter().map(|(k, v)| (k.clone(), *v)));
+        }
+
+        if next.global_state.playback_speed.is_some() {
+            self.global_state.playback_speed = next.global_state.playback_speed;
+        }
+        self.global_state.playback_position = next.global_state.playback_position;
+        self.global_state.output_brightness.extend(
+            next.global_state
+                .output_brightness
+                .iter()
+                .map(|(k, v)| (*k, *v)),
+        );
+
+        if next.active_module.is_some() {
+            self.active_module = next.active_module;
+        }
+    }
+}
+
+/// Serializes effect states as a list of entries, since JSON map keys must be strings
+mod effect_entries {
+    use super::{EffectKey, EffectState};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        states: &HashMap<EffectKey, EffectState>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(states.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<EffectKey, EffectState>, D::Error> {
+        let entries = Vec::<(EffectKey, EffectState)>::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+/// Collect the parameters whose values differ from the previous snapshot
+fn changed_parameters(
+    current: &HashMap<String, f32>,
+    previous: Option<&HashMap<String, f32>>,
+) -> HashMap<String, f32> {
+    current
+        .iter()
+        .filter(|(name, value)| previous.and_then(|p| p.get(*name)) != Some(*value))
+        .map(|(name, value)| (name.clone(), *value))
+        .collect()
 }
 
 impl LayerState {
@@ -242,16 +531,56 @@ mod tests {
         assert_eq!(state.parameters.get("speed"), Some(&0.5));
     }
 
+    #[test]
+    fn test_capture() {
+        let mut state = AppState::default();
+        let layer_id = state.layer_manager.create_layer("Layer");
+        let layer = state.layer_manager.get_layer_mut(layer_id).unwrap();
+        layer.opacity = 0.5;
+        let effect_id = layer.effect_chain.add_effect(mapmap_core::EffectType::Blur);
+        let module_id = state.module_manager.create_module("Show".to_string());
+        state.module_manager.set_active_module(Some(module_id));
+
+        let cue = Cue::capture(1, "Captured".to_string(), &state);
+        assert_eq!(cue.layer_states[&(layer_id as u32)].opacity, 0.5);
+        assert_eq!(cue.active_module, Some(module_id));
+
+        let effect = &cue.effect_states[&(layer_id, effect_id)];
+        assert_eq!(effect.parameters.get("radius"), Some(&5.0));
+    }
+
+    #[test]
+    fn test_changes_from_and_track() {
+        let mut previous = Cue::new(0, "Previous".to_string());
+        previous.add_layer_state(0, LayerState::default_visible());
+        previous.add_layer_state(1, LayerState::default_visible());
+
+        let mut current = previous.clone();
+        current.id = 1;
+        current.add_layer_state(1, LayerState::new(0.5, true, (0.0, 0.0), 0.0, 1.0));
+
+        let delta = current.changes_from(&previous);
+        assert_eq!(delta.layer_states.len(), 1);
+        assert!(delta.layer_states.contains_key(&1));
+
+        let mut tracked = previous.clone();
+        tracked.track(&delta);
+        assert_eq!(tracked.layer_states, current.layer_states);
+    }
+
     #[test]
     fn test_serialization() {
-        let cue = Cue::new(0, "Test".to_string())
+        let mut cue = Cue::new(0, "Test".to_string())
             .with_fade_duration(Duration::from_secs(3))
             .with_fade_curve(FadeCurve::EaseInOut);
+        cue.add_effect_state((1, 0x1_0000), EffectState::new(false));
+        cue.add_effect_state((0x1_0001, 0), EffectState::new(true));
 
         let json = serde_json::to_string(&cue).unwrap();
         let deserialized: Cue = serde_json::from_str(&json).unwrap();
 
         assert_eq!(deserialized.id, cue.id);
         assert_eq!(deserialized.fade_duration, cue.fade_duration);
+        assert_eq!(deserialized.effect_states, cue.effect_states);
     }
 }
@@ -1,12 +1,26 @@
 //! Cue list management
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use mapmap_core::AppState;
 
 use super::crossfade::Crossfade;
 use super::cue::Cue;
 
+use crate::timecode::{TimecodeRate, TimecodeUpdate};
 use crate::{error::ControlError, Result};
 
+/// How recorded values are stored in a cue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordMode {
+    /// Store only values that changed from the previous cue; unchanged values
+    /// track through from earlier cues
+    #[default]
+    Track,
+    /// Store every value as a hard value, blocking tracking from earlier cues
+    Block,
+}
+
 /// Cue list state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CueListState {
@@ -22,6 +36,10 @@ pub struct CueList {
     next_cue: Option<u32>,
     state: CueListState,
     current_crossfade: Option<Crossfade>,
+    /// Wrap around to the first cue after the last one
+    looping: bool,
+    /// When the current cue was reached (for auto-follow)
+    cue_reached: Option<Instant>,
 }
 
 impl CueList {
@@ -33,13 +51,27 @@ impl CueList {
             next_cue: None,
             state: CueListState::Idle,
             current_crossfade: None,
+            looping: false,
+            cue_reached: None,
         }
     }
 
+    /// Set whether the list wraps around after the last cue
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+        self.update_next_cue();
+    }
+
+    /// Check if the list wraps around after the last cue
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
     /// Add a cue to the list
     pub fn add_cue(&mut self, cue: Cue) {
         self.cues.push(cue);
         self.sort_cues();
+        self.update_next_cue();
     }
 
     /// Remove a cue by ID
@@ -76,23 +108,113 @@ impl CueList {
         self.next_cue
     }
 
+    /// Record a cue from the live project state
+    ///
+    /// If a cue with this ID already exists it is updated in place and renamed,
+    /// keeping its transition settings and triggers.
+    pub fn record_cue(&mut self, id: u32, name: String, state: &AppState, mode: RecordMode) {
+        if self.get_cue(id).is_some() {
+            let _ = self.update_cue(id, state, mode);
+            if let Some(cue) = self.get_cue_mut(id) {
+                cue.name = name;
+            }
+            return;
+        }
+
+        let cue = self.recorded(Cue::capture(id, name, state), mode);
+        self.add_cue(cue);
+    }
+
+    /// Update an existing cue from the live project state
+    pub fn update_cue(&mut self, id: u32, state: &AppState, mode: RecordMode) -> Result<()> {
+        let existing = self
+            .get_cue(id)
+            .ok_or_else(|| ControlError::TargetNotFound(format!("Cue {} not found", id)))?;
+
+        let captured = Cue::capture(id, existing.name.clone(), state);
+        let snapshot = Cue {
+            layer_states: captured.layer_states,
+            paint_states: captured.paint_states,
+            effect_states: captured.effect_states,
+            global_state: captured.global_state,
+            active_module: captured.active_module,
+            block: false,
+            ..existing.clone()
+        };
+
+        let updated = self.recorded(snapshot, mode);
+        if let Some(cue) = self.get_cue_mut(id) {
+            *cue = updated;
+        }
+        Ok(())
+    }
+
+    /// Get the full state a cue produces once tracked values are resolved
+    ///
+    /// Walks the list from the first cue (or the last block cue) up to `id`,
+    /// applying each cue's stored values in order.
+    pub fn tracked_state(&self, id: u32) -> Option<Cue> {
+        let index = self.cues.iter().position(|c| c.id == id)?;
+        let mut tracked = self.cues[index].clone();
+        tracked.clear_states();
+        for cue in &self.cues[..=index] {
+            tracked.track(cue);
+        }
+        Some(tracked)
+    }
+
+    /// Turn a full snapshot into the cue that gets stored for `mode`
+    fn recorded(&self, snapshot: Cue, mode: RecordMode) -> Cue {
+        match mode {
+            RecordMode::Block => Cue {
+                block: true,
+                ..snapshot
+            },
+            RecordMode::Track => match self.cues.iter().rev().find(|c| c.id < snapshot.id) {
+                Some(previous) => {
+                    let previous = self
+                        .tracked_state(previous.id)
+                        .unwrap_or_else(|| previous.clone());
+                    snapshot.changes_from(&previous)
+                }
+                None => snapshot,
+            },
+        }
+    }
+
     /// Go to a specific cue
     pub fn goto_cue(&mut self, id: u32, fade_duration: Option<Duration>) -> Result<()> {
+        self.goto_cue_at(id, fade_duration, Instant::now())
+    }
+
+    pub(super) fn goto_cue_at(
+        &mut self,
+        id: u32,
+        fade_duration: Option<Duration>,
+        now: Instant,
+    ) -> Result<()> {
         let cue = self
             .get_cue(id)
             .ok_or_else(|| ControlError::TargetNotFound(format!("Cue {} not found", id)))?;
 
         let duration = fade_duration.unwrap_or(cue.fade_duration);
+        let fade_out = fade_duration.unwrap_or_else(|| cue.fade_out());
+        let delay = cue.delay;
         let curve = cue.fade_curve;
 
         if let Some(current) = self.current_cue {
             // Start crossfade from current to target
-            self.current_crossfade = Some(Crossfade::new(current, id, duration, curve));
+            self.current_crossfade = Some(
+                Crossfade::new_at(current, id, duration, curve, now)
+                    .with_fade_out(fade_out)
+                    .with_delay(delay),
+            );
             self.state = CueListState::Crossfading;
         } else {
             // No current cue, just set it
             self.current_cue = Some(id);
             self.state = CueListState::Playing;
+            self.cue_reached = Some(now);
         }
 
         self.update_next_cue();
@@ -135,17 +257,75 @@ impl CueList {
         }
     }
 
+    /// Follow a timecode update, firing cues whose timecode trigger was reached
+    ///
+    /// When timecode runs forward, the last cue passed is played with its own
+    /// fade. After a jump the list snaps to the last cue at or before the new
+    /// position so the show lands in the state it would have had there.
+    /// Returns the ID of the cue that was fired, if any.
+    pub fn chase_timecode(&mut self, update: &TimecodeUpdate, rate: TimecodeRate) -> Option<u32> {
+        let triggers = self
+            .cues
+            .iter()
+            .filter_map(|c| c.timecode_trigger.map(|t| (t.frame(rate), c.id)));
+
+        let (id, fade) = match *update {
+            TimecodeUpdate::Advanced { from, to } => {
+                let (_, id) = triggers
+                    .filter(|(frame, _)| (from + 1..=to).contains(frame))
+                    .max_by_key(|(frame, _)| *frame)?;
+                (id, None)
+            }
+            TimecodeUpdate::Jumped { to } => {
+                let (_, id) = triggers
+                    .filter(|(frame, _)| *frame <= to)
+                    .max_by_key(|(frame, _)| *frame)?;
+                let target = self
+                    .current_crossfade
+                    .as_ref()
+                    .map(|c| c.to_cue_id())
+                    .or(self.current_cue);
+                if target == Some(id) {
+                    return None;
+                }
+                (id, Some(Duration::ZERO))
+            }
+        };
+
+        self.goto_cue(id, fade).ok().map(|_| id)
+    }
+
     /// Update the cue list (call this regularly to handle crossfades)
     pub fn update(&mut self) {
+        self.update_at(Instant::now())
+    }
+
+    pub(super) fn update_at(&mut self, now: Instant) {
         if let Some(crossfade) = &self.current_crossfade {
-            if crossfade.is_complete() {
+            if crossfade.is_complete_at(now) {
                 // Crossfade complete, update current cue
                 self.current_cue = Some(crossfade.to_cue_id());
                 self.current_crossfade = None;
                 self.state = CueListState::Playing;
+                self.cue_reached = Some(now);
                 self.update_next_cue();
             }
         }
+
+        // Auto-follow to the next cue once the current one has been held long enough
+        if self.state == CueListState::Playing {
+            let follow = self
+                .current_cue
+                .and_then(|id| self.get_cue(id))
+                .and_then(|cue| cue.auto_follow);
+            if let (Some(follow), Some(reached)) = (follow, self.cue_reached) {
+                if now.saturating_duration_since(reached) >= follow {
+                    if let Some(next_id) = self.next_cue {
+                        let _ = self.goto_cue_at(next_id, None, now);
+                    }
+                }
+            }
+        }
     }
 
     /// Get the current crossfade state
@@ -165,6 +345,7 @@ impl CueList {
         self.next_cue = None;
         self.state = CueListState::Idle;
         self.current_crossfade = None;
+        self.cue_reached = None;
     }
 
     /// Get the number of cues
@@ -188,6 +369,8 @@ impl CueList {
             if let Some(current_index) = self.cues.iter().position(|c| c.id == current_id) {
                 if current_index + 1 < self.cues.len() {
                     self.next_cue = Some(self.cues[current_index + 1].id);
+                } else if self.looping {
+                    self.next_cue = self.cues.first().map(|c| c.id);
                 } else {
                     self.next_cue = None;
                 }
@@ -304,4 +487,124 @@ mod tests {
         assert_eq!(list.current_cue(), Some(1));
         assert!(list.current_crossfade().is_none());
     }
+
+    #[test]
+    fn test_looping_auto_follow() {
+        let mut list = CueList::new();
+        list.add_cue(
+            Cue::new(0, "A".to_string())
+                .with_fade_duration(Duration::ZERO)
+                .with_auto_follow(Duration::ZERO),
+        );
+        list.add_cue(
+            Cue::new(1, "B".to_string())
+                .with_fade_duration(Duration::ZERO)
+                .with_auto_follow(Duration::ZERO),
+        );
+        list.set_looping(true);
+
+        list.goto_cue(1, None).unwrap();
+        assert_eq!(list.next_cue(), Some(0));
+
+        // Auto-follow wraps around to the first cue
+        list.update();
+        list.update();
+        assert_eq!(list.current_cue(), Some(0));
+    }
+
+    fn state_with_layers(opacities: &[f32]) -> AppState {
+        let mut state = AppState::default();
+        for (i, opacity) in opacities.iter().enumerate() {
+            let id = state.layer_manager.create_layer(format!("Layer {}", i));
+            state.layer_manager.get_layer_mut(id).unwrap().opacity = *opacity;
+        }
+        state
+    }
+
+    #[test]
+    fn test_record_tracks_changes_only() {
+        let mut list = CueList::new();
+        let mut state = state_with_layers(&[1.0, 1.0]);
+        list.record_cue(1, "Cue 1".to_string(), &state, RecordMode::Track);
+
+        state.layer_manager.get_layer_mut(2).unwrap().opacity = 0.25;
+        list.record_cue(2, "Cue 2".to_string(), &state, RecordMode::Track);
+
+        let cue2 = list.get_cue(2).unwrap();
+        assert_eq!(cue2.layer_states.len(), 1);
+        assert_eq!(cue2.layer_states[&2].opacity, 0.25);
+
+        // Layer 1 tracks through from cue 1
+        let tracked = list.tracked_state(2).unwrap();
+        assert_eq!(tracked.layer_states[&1].opacity, 1.0);
+        assert_eq!(tracked.layer_states[&2].opacity, 0.25);
+    }
+
+    #[test]
+    fn test_record_block_stores_hard_values() {
+        let mut list = CueList::new();
+        let state = state_with_layers(&[1.0, 0.5]);
+        list.record_cue(1, "Cue 1".to_string(), &state, RecordMode::Track);
+        list.record_cue(2, "Cue 2".to_string(), &state, RecordMode::Block);
+
+        let cue2 = list.get_cue(2).unwrap();
+        assert!(cue2.block);
+        assert_eq!(cue2.layer_states.len(), 2);
+    }
+
+    #[test]
+    fn test_update_cue_keeps_settings() {
+        let mut list = CueList::new();
+        list.add_cue(Cue::new(1, "Opening".to_string()).with_fade_duration(Duration::from_secs(5)));
+
+        let state = state_with_layers(&[0.75]);
+        list.update_cue(1, &state, RecordMode::Track).unwrap();
+
+        let cue = list.get_cue(1).unwrap();
+        assert_eq!(cue.name, "Opening");
+        assert_eq!(cue.fade_duration, Duration::from_secs(5));
+        assert_eq!(cue.layer_states[&1].opacity, 0.75);
+
+        assert!(list.update_cue(9, &state, RecordMode::Track).is_err());
+
+        // Re-recording replaces the name but keeps the transition settings
+        list.record_cue(1, "Finale".to_string(), &state, RecordMode::Track);
+        let cue = list.get_cue(1).unwrap();
+        assert_eq!(cue.name, "Finale");
+        assert_eq!(cue.fade_duration, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_chase_timecode() {
+        use crate::cue::TimecodeTrigger;
+        use crate::timecode::Timecode;
+
+        let rate = TimecodeRate::FPS_25;
+        let mut list = CueList::new();
+        for (id, seconds) in [(1, 1), (2, 5), (3, 10)] {
+            let mut cue = Cue::new(id, format!("Cue {}", id));
+            cue.timecode_trigger = Some(TimecodeTrigger::new(
+                Timecode::new(0, 0, seconds, 0, rate).unwrap(),
+            ));
+            list.add_cue(cue);
+        }
+
+        // Running through 00:00:01:00 fires cue 1
+        let fired = list.chase_timecode(&TimecodeUpdate::Advanced { from: 20, to: 26 }, rate);
+        assert_eq!(fired, Some(1));
+        assert_eq!(list.current_cue(), Some(1));
+
+        // Nothing between triggers
+        let fired = list.chase_timecode(&TimecodeUpdate::Advanced { from: 26, to: 30 }, rate);
+        assert_eq!(fired, None);
+
+        // Jumping to 00:00:07:00 lands on cue 2 without a fade
+        let fired = list.chase_timecode(&TimecodeUpdate::Jumped { to: 175 }, rate);
+        assert_eq!(fired, Some(2));
+        assert_eq!(list.current_crossfade().unwrap().duration(), Duration::ZERO);
+
+        // Jumping within the same cue does not re-fire it
+        let fired = list.chase_timecode(&TimecodeUpdate::Jumped { to: 200 }, rate);
+        assert_eq!(fired, None);
+    }
 }
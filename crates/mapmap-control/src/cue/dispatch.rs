@@ -0,0 +1,211 @@
+//! Cue trigger dispatching
+//!
+//! The dispatcher checks incoming control events against the triggers of
+//! every cue in every list and fires the cues that match. A debounce window
+//! stops a cue from re-firing on repeated messages, e.g. a button that sends
+//! several values per press.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "midi")]
+use crate::midi::MidiMessage;
+use crate::shortcuts::{Key, Modifiers};
+use crate::target::ControlValue;
+
+use super::cue::Cue;
+use super::stack::{CueListId, CueStack};
+
+/// An input event that can fire cue triggers
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriggerEvent {
+    /// Incoming MIDI message
+    #[cfg(feature = "midi")]
+    Midi(MidiMessage),
+    /// Incoming OSC message with its first argument
+    Osc {
+        address: String,
+        value: Option<ControlValue>,
+    },
+    /// Key press
+    Key { key: Key, modifiers: Modifiers },
+    /// Periodic check of time-of-day triggers
+    Clock,
+}
+
+impl TriggerEvent {
+    /// Check if this event fires a cue
+    pub fn fires(&self, cue: &Cue) -> bool {
+        match self {
+            #[cfg(feature = "midi")]
+            TriggerEvent::Midi(message) => cue
+                .midi_trigger
+                .as_ref()
+                .is_some_and(|t| t.matches(message)),
+            TriggerEvent::Osc { address, value } => cue
+                .osc_trigger
+                .as_ref()
+                .is_some_and(|t| t.matches(address, value.as_ref())),
+            TriggerEvent::Key { key, modifiers } => cue
+                .key_trigger
+                .as_ref()
+                .is_some_and(|t| t.matches(*key, modifiers)),
+            TriggerEvent::Clock => cue.time_trigger.is_some_and(|t| t.matches_now()),
+        }
+    }
+}
+
+/// Fires cues whose triggers match incoming events
+#[derive(Debug)]
+pub struct TriggerDispatcher {
+    debounce: Duration,
+    last_fired: HashMap<(CueListId, u32), Instant>,
+}
+
+impl TriggerDispatcher {
+    /// Create a dispatcher with a 250 ms debounce window
+    pub fn new() -> Self {
+        Self {
+            debounce: Duration::from_millis(250),
+            last_fired: HashMap::new(),
+        }
+    }
+
+    /// Set the debounce window
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Set the debounce window
+    pub fn set_debounce(&mut self, debounce: Duration) {
+        self.debounce = debounce;
+    }
+
+    /// Get the debounce window
+    pub fn debounce(&self) -> Duration {
+        self.debounce
+    }
+
+    /// Fire matching cues, returning the `(list, cue)` pairs that fired
+    ///
+    /// At most one cue fires per list: the first matching cue in list order.
+    pub fn dispatch(
+        &mut self,
+        event: &TriggerEvent,
+        stack: &mut CueStack,
+    ) -> Vec<(CueListId, u32)> {
+        self.dispatch_at(event, stack, Instant::now())
+    }
+
+    fn dispatch_at(
+        &mut self,
+        event: &TriggerEvent,
+        stack: &mut CueStack,
+        now: Instant,
+    ) -> Vec<(CueListId, u32)> {
+        // Time-of-day triggers match for a whole second
+        let window = match event {
+            TriggerEvent::Clock => self.debounce.max(Duration::from_secs(1)),
+            _ => self.debounce,
+        };
+
+        let matches: Vec<(CueListId, u32)> = stack
+            .slots()
+            .iter()
+            .filter_map(|slot| {
+                slot.list
+                    .cues()
+                    .iter()
+                    .find(|cue| event.fires(cue))
+                    .map(|cue| (slot.id, cue.id))
+            })
+            .collect();
+
+        let mut fired = Vec::new();
+        for key in matches {
+            let debounced = self
+                .last_fired
+                .get(&key)
+                .is_some_and(|last| now.saturating_duration_since(*last) < window);
+            if debounced {
+                continue;
+            }
+            self.last_fired.insert(key, now);
+
+            let (list_id, cue_id) = key;
+            if stack.goto(list_id, cue_id).is_ok() {
+                fired.push(key);
+            }
+        }
+        fired
+    }
+}
+
+impl Default for TriggerDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cue::{KeyTrigger, OscTrigger};
+
+    fn stack_with_triggers() -> CueStack {
+        let mut stack = CueStack::new();
+        let mut go = Cue::new(1, "Go".to_string());
+        go.osc_trigger = Some(OscTrigger::with_value(
+            "/show/go".to_string(),
+            "1".to_string(),
+        ));
+        let mut blackout = Cue::new(2, "Blackout".to_string());
+        blackout.key_trigger = Some(KeyTrigger::new(Key::B, Modifiers::ctrl()));
+        stack.main_mut().add_cue(go);
+        stack.main_mut().add_cue(blackout);
+        stack
+    }
+
+    #[test]
+    fn test_osc_trigger_fires_cue() {
+        let mut stack = stack_with_triggers();
+        let mut dispatcher = TriggerDispatcher::new();
+
+        // Button release value does not match
+        let release = TriggerEvent::Osc {
+            address: "/show/go".to_string(),
+            value: Some(ControlValue::Float(0.0)),
+        };
+        assert!(dispatcher.dispatch(&release, &mut stack).is_empty());
+
+        let press = TriggerEvent::Osc {
+            address: "/show/go".to_string(),
+            value: Some(ControlValue::Float(1.0)),
+        };
+        assert_eq!(
+            dispatcher.dispatch(&press, &mut stack),
+            vec![(CueStack::MAIN, 1)]
+        );
+        assert_eq!(stack.main().current_cue(), Some(1));
+    }
+
+    #[test]
+    fn test_debounce() {
+        let mut stack = stack_with_triggers();
+        let mut dispatcher = TriggerDispatcher::new().with_debounce(Duration::from_millis(100));
+        let event = TriggerEvent::Key {
+            key: Key::B,
+            modifiers: Modifiers::ctrl(),
+        };
+
+        let start = Instant::now();
+        assert_eq!(dispatcher.dispatch_at(&event, &mut stack, start).len(), 1);
+        let repeat = start + Duration::from_millis(50);
+        assert!(dispatcher
+            .dispatch_at(&event, &mut stack, repeat)
+            .is_empty());
+        let later = start + Duration::from_millis(150);
+        assert_eq!(dispatcher.dispatch_at(&event, &mut stack, later).len(), 1);
+    }
+}
@@ -6,9 +6,12 @@
 //! ## Features
 //!
 //! - **Cues**: Snapshots of complete project state
+//! - **Recording**: Capture cues from the live project with track/block semantics
 //! - **Crossfades**: Smooth transitions with configurable curves
-//! - **Triggers**: MIDI, OSC, and time-based cue activation
+//! - **Playback**: Blends cue values into the project state every frame
+//! - **Triggers**: MIDI, OSC, time-based and timecode cue activation
 //! - **Auto-follow**: Automatic progression through cue list
+//! - **Multiple lists**: Independent playheads merged HTP/LTP with submasters
 //!
 //! ## Example Usage
 //!
@@ -45,14 +48,22 @@
 //! - `EaseInOut`: Slow start and end, fast middle
 //! - `Exponential`: Exponential curve
 //!
+//! Values that increase use the cue's fade duration, values that decrease use
+//! its fade-out duration. An optional delay holds the fade before it starts.
+//!
 //! ## Triggers
 //!
 //! Cues can be triggered by:
-//! - **MIDI**: Note, CC, or Program Change messages
-//! - **OSC**: OSC address patterns
+//! - **MIDI**: Note (with velocity threshold), CC, or Program Change messages
+//! - **OSC**: OSC address, optionally matching the first argument
+//! - **Keyboard**: Key presses with modifiers
 //! - **Time**: Specific time of day
+//! - **Timecode**: SMPTE position from chased MTC or LTC
 //! - **Auto-follow**: Automatic progression after a delay
 //!
+//! [`TriggerDispatcher`] checks incoming events against every cue's triggers,
+//! with a debounce window against repeated messages.
+//!
 //! ```rust
 //! use mapmap_control::cue::{Cue, triggers::MidiTrigger};
 //! use std::time::Duration;
@@ -65,9 +76,17 @@ pub mod crossfade;
 #[allow(clippy::module_inception)]
 pub mod cue;
 pub mod cue_list;
+pub mod dispatch;
+pub mod playback;
+pub mod stack;
 pub mod triggers;
 
 pub use crossfade::{interpolate_f32, interpolate_position, Crossfade, FadeCurve};
-pub use cue::{Cue, EffectState, GlobalState, LayerState, PaintState};
-pub use cue_list::{CueList, CueListState};
-pub use triggers::{MidiTrigger, MidiTriggerType, OscTrigger, TimeTrigger};
+pub use cue::{Cue, EffectKey, EffectState, GlobalState, LayerState, MissingValueMode, PaintState};
+pub use cue_list::{CueList, CueListState, RecordMode};
+pub use dispatch::{TriggerDispatcher, TriggerEvent};
+pub use playback::{apply_blend, blend_cues, write_cue, CuePlayback};
+pub use stack::{CueListId, CueListSlot, CueStack};
+pub use triggers::{
+    KeyTrigger, MidiTrigger, MidiTriggerType, OscTrigger, TimeTrigger, TimecodeTrigger,
+};
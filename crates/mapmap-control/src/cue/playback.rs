@@ -0,0 +1,401 @@
+//! Cue playback engine
+//!
+//! Applies the active cue and any running crossfade to the project state.
+
+use mapmap_core::{AppState, Effect};
+use std::time::Instant;
+
+use super::crossfade::interpolate_f32;
+use super::cue::{Cue, EffectState, LayerState, MissingValueMode, PaintState};
+use super::cue_list::CueList;
+
+/// Blends cue values into the project state every frame
+#[derive(Default)]
+pub struct CuePlayback {
+    /// Live values captured when the running fade started
+    fade_start: Option<Cue>,
+    /// Source and destination of the running fade
+    active_fade: Option<(u32, u32)>,
+    /// Cue whose values have been applied in full
+    applied_cue: Option<u32>,
+}
+
+impl CuePlayback {
+    /// Create a new playback engine
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply the cue list to the project state (call this every frame)
+    ///
+    /// While a crossfade runs, values are blended from the live state at the
+    /// start of the fade towards the tracked state of the destination cue.
+    /// Once a cue is reached its values are applied once, leaving manual
+    /// changes alone until the next cue is triggered.
+    pub fn apply(&mut self, cue_list: &CueList, state: &mut AppState) {
+        self.apply_at(cue_list, state, Instant::now())
+    }
+
+    fn apply_at(&mut self, cue_list: &CueList, state: &mut AppState, now: Instant) {
+        if let Some(values) = self.output_at(cue_list, state, now) {
+            write_cue(&values, state);
+        }
+    }
+
+    /// Compute the values the cue list wants written this frame
+    ///
+    /// Returns `None` when nothing changed since the last call.
+    pub fn output(&mut self, cue_list: &CueList, state: &AppState) -> Option<Cue> {
+        self.output_at(cue_list, state, Instant::now())
+    }
+
+    fn output_at(&mut self, cue_list: &CueList, state: &AppState, now: Instant) -> Option<Cue> {
+        if let Some(crossfade) = cue_list.current_crossfade() {
+            let fade = (crossfade.from_cue_id(), crossfade.to_cue_id());
+            if self.active_fade != Some(fade) {
+                self.fade_start = Some(Cue::capture(fade.0, String::new(), state));
+                self.active_fade = Some(fade);
+            }
+            self.applied_cue = None;
+
+            if !crossfade.has_started_at(now) {
+                return None;
+            }
+
+            let start = self.fade_start.as_ref()?;
+            let target = cue_list.tracked_state(fade.1)?;
+            return Some(blend_cues(
+                start,
+                &target,
+                crossfade.fade_in_progress_at(now),
+                crossfade.fade_out_progress_at(now),
+                state,
+            ));
+        }
+
+        self.active_fade = None;
+        self.fade_start = None;
+
+        let current = cue_list.current_cue()?;
+        if self.applied_cue == Some(current) {
+            return None;
+        }
+        self.applied_cue = Some(current);
+
+        let target = cue_list.tracked_state(current)?;
+        let start = Cue::capture(current, String::new(), state);
+        Some(blend_cues(&start, &target, 1.0, 1.0, state))
+    }
+
+    /// Forget the applied cue so it is applied again on the next frame
+    pub fn reset(&mut self) {
+        self.fade_start = None;
+        self.active_fade = None;
+        self.applied_cue = None;
+    }
+}
+
+/// Blend from `start` towards `target` and write the result into `state`
+///
+/// Values that increase use `fade_in` progress, values that decrease use
+/// `fade_out` progress.
+pub fn apply_blend(start: &Cue, target: &Cue, fade_in: f32, fade_out: f32, state: &mut AppState) {
+    let values = blend_cues(start, target, fade_in, fade_out, state);
+    write_cue(&values, state);
+}
+
+/// Blend from `start` towards `target` without touching the project state
+///
+/// The returned cue holds every value that should be written this frame.
+/// `state` supplies current values and defaults for parameters the target
+/// does not store.
+pub fn blend_cues(start: &Cue, target: &Cue, fade_in: f32, fade_out: f32, state: &AppState) -> Cue {
+    let to_default = target.missing_values == MissingValueMode::FadeToDefault;
+    let blend = |from: f32, to: f32| {
+        let progress = if to >= from { fade_in } else { fade_out };
+        interpolate_f32(from, to, progress)
+    };
+    // Switches turn on as soon as the fade starts and off once it completes
+    let switch = |from: bool, to: bool| to || (from && fade_out < 1.0);
+
+    let mut values = Cue::new(target.id, target.name.clone());
+
+    for layer in state.layer_manager.layers() {
+        let key = layer.id as u32;
+        let to = match target.layer_states.get(&key) {
+            Some(to) => Some(to.clone()),
+            None if to_default => Some(LayerState::default_visible()),
+            None => None,
+        };
+        if let Some(to) = to {
+            let from = start.layer_states.get(&key).unwrap_or(&to);
+            values.add_layer_state(
+                key,
+                LayerState::new(
+                    blend(from.opacity, to.opacity),
+                    switch(from.visible, to.visible),
+                    (
+                        blend(from.position.0, to.position.0),
+                        blend(from.position.1, to.position.1),
+                    ),
+                    blend(from.rotation, to.rotation),
+                    blend(from.scale, to.scale),
+                ),
+            );
+        }
+
+        for effect in &layer.effect_chain.effects {
+            let key = (layer.id, effect.id);
+            let to = match target.effect_states.get(&key) {
+                Some(to) => Some(to.clone()),
+                None if to_default => Some(default_effect_state(effect)),
+                None => None,
+            };
+            let Some(to) = to else {
+                continue;
+            };
+            let from = start.effect_states.get(&key).unwrap_or(&to);
+
+            let mut effect_state = EffectState::new(switch(from.enabled, to.enabled));
+            for (name, value) in &to.parameters {
+                let current = if name == "intensity" {
+                    effect.intensity
+                } else {
+                    effect.get_param(name, *value)
+                };
+                let from_value = from.parameters.get(name).copied().unwrap_or(current);
+                effect_state.set_parameter(name.clone(), blend(from_value, *value));
+            }
+            values.add_effect_state(key, effect_state);
+        }
+    }
+
+    for paint in state.paint_manager.paints() {
+        let key = paint.id as u32;
+        let to = match target.paint_states.get(&key) {
+            Some(to) => Some(to.clone()),
+            None if to_default => Some(default_paint_state()),
+            None => None,
+        };
+        let Some(to) = to else {
+            continue;
+        };
+        let from = start.paint_states.get(&key).unwrap_or(&to);
+
+        let mut paint_state = PaintState::new();
+        for (name, value) in &to.parameters {
+            let current = match name.as_str() {
+                "opacity" => paint.opacity,
+                "rate" => paint.rate,
+                _ => continue,
+            };
+            let from_value = from.parameters.get(name).copied().unwrap_or(current);
+            paint_state.set_parameter(name.clone(), blend(from_value, *value));
+        }
+        values.add_paint_state(key, paint_state);
+    }
+
+    let from_global = &start.global_state;
+    let to_global = &target.global_state;
+    let to_speed = match to_global.playback_speed {
+        Some(to) => Some(to),
+        None if to_default => Some(1.0),
+        None => None,
+    };
+    values.global_state.playback_speed = to_speed.map(|to| {
+        let from = from_global
+            .playback_speed
+            .unwrap_or(state.layer_manager.composition.master_speed);
+        blend(from, to)
+    });
+    values.global_state.playback_position = to_global.playback_position;
+
+    for output in state.output_manager.outputs() {
+        let key = output.id as u32;
+        let to = match to_global.output_brightness.get(&key) {
+            Some(to) => *to,
+            None if to_default => 0.0,
+            None => continue,
+        };
+        let from = from_global
+            .output_brightness
+            .get(&key)
+            .copied()
+            .unwrap_or(output.color_calibration.brightness);
+        values
+            .global_state
+            .output_brightness
+            .insert(key, blend(from, to));
+    }
+
+    values.active_module = target.active_module;
+    values
+}
+
+/// Write the values stored in a cue into the project state
+///
+/// Parameters the cue does not store are left untouched.
+pub fn write_cue(values: &Cue, state: &mut AppState) {
+    let layer_ids: Vec<u64> = state.layer_manager.layers().iter().map(|l| l.id).collect();
+    for layer_id in layer_ids {
+        let Some(layer) = state.layer_manager.get_layer_mut(layer_id) else {
+            continue;
+        };
+
+        if let Some(layer_state) = values.layer_states.get(&(layer_id as u32)) {
+            let transform = &mut layer.transform;
+            layer.opacity = layer_state.opacity;
+            layer.visible = layer_state.visible;
+            transform.position.x = layer_state.position.0;
+            transform.position.y = layer_state.position.1;
+            transform.rotation.z = layer_state.rotation.to_radians();
+
+            // Cues store a uniform scale; keep the layer's aspect
+            let aspect = if transform.scale.x != 0.0 {
+                transform.scale.y / transform.scale.x
+            } else {
+                1.0
+            };
+            transform.scale.x = layer_state.scale;
+            transform.scale.y = layer_state.scale * aspect;
+        }
+
+        for effect in &mut layer.effect_chain.effects {
+            let Some(effect_state) = values.effect_states.get(&(layer_id, effect.id)) else {
+                continue;
+            };
+            effect.enabled = effect_state.enabled;
+            for (name, value) in &effect_state.parameters {
+                if name == "intensity" {
+                    effect.intensity = *value;
+                } else {
+                    effect.set_param(name, *value);
+                }
+            }
+        }
+    }
+
+    for paint in state.paint_manager.paints_mut() {
+        let Some(paint_state) = values.paint_states.get(&(paint.id as u32)) else {
+            continue;
+        };
+        for (name, value) in &paint_state.parameters {
+            match name.as_str() {
+                "opacity" => paint.opacity = *value,
+                "rate" => paint.rate = *value,
+                _ => {}
+            }
+        }
+    }
+
+    let global = &values.global_state;
+    if let Some(speed) = global.playback_speed {
+        state.layer_manager.composition.set_master_speed(speed);
+    }
+
+    for (output_id, brightness) in &global.output_brightness {
+        if let Some(output) = state.output_manager.get_output_mut(*output_id as u64) {
+            output.color_calibration.brightness = *brightness;
+        }
+    }
+
+    if values.active_module.is_some() {
+        state.module_manager.set_active_module(values.active_module);
+    }
+}
+
+/// Default values for an effect that is missing from a cue
+fn default_effect_state(effect: &Effect) -> EffectState {
+    let mut state = EffectState::new(true);
+    state.set_parameter("intensity".to_string(), 1.0);
+    for (name, value) in Effect::new(effect.id, effect.effect_type).parameters {
+        state.set_parameter(name, value);
+    }
+    state
+}
+
+/// Default values for a paint that is missing from a cue
+fn default_paint_state() -> PaintState {
+    let mut state = PaintState::new();
+    state.set_parameter("opacity".to_string(), 1.0);
+    state.set_parameter("rate".to_string(), 1.0);
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn state_with_layer(opacity: f32) -> AppState {
+        let mut state = AppState::default();
+        let id = state.layer_manager.create_layer("Layer");
+        state.layer_manager.get_layer_mut(id).unwrap().opacity = opacity;
+        state
+    }
+
+    #[test]
+    fn test_apply_blend_split_timing() {
+        let mut state = state_with_layer(1.0);
+        let start = Cue::capture(0, String::new(), &state);
+
+        let mut target = Cue::new(1, "Target".to_string());
+        target.add_layer_state(1, LayerState::new(0.0, true, (100.0, 0.0), 0.0, 1.0));
+
+        // Opacity decreases (fade-out), position increases (fade-in)
+        apply_blend(&start, &target, 1.0, 0.5, &mut state);
+        let layer = state.layer_manager.get_layer(1).unwrap();
+        assert_eq!(layer.opacity, 0.5);
+        assert_eq!(layer.transform.position.x, 100.0);
+    }
+
+    #[test]
+    fn test_missing_values() {
+        let mut state = state_with_layer(0.3);
+        state.layer_manager.composition.set_master_speed(2.0);
+        let start = Cue::capture(0, String::new(), &state);
+        let target = Cue::new(1, "Empty".to_string());
+
+        apply_blend(&start, &target, 1.0, 1.0, &mut state);
+        assert_eq!(state.layer_manager.get_layer(1).unwrap().opacity, 0.3);
+        assert_eq!(state.layer_manager.composition.master_speed, 2.0);
+
+        let target = target.with_missing_values(MissingValueMode::FadeToDefault);
+        apply_blend(&start, &target, 1.0, 1.0, &mut state);
+        assert_eq!(state.layer_manager.get_layer(1).unwrap().opacity, 1.0);
+        assert_eq!(state.layer_manager.composition.master_speed, 1.0);
+    }
+
+    #[test]
+    fn test_playback_crossfade() {
+        let mut state = state_with_layer(1.0);
+        let mut list = CueList::new();
+
+        let mut cue1 = Cue::new(1, "Bright".to_string());
+        cue1.add_layer_state(1, LayerState::default_visible());
+        let mut cue2 =
+            Cue::new(2, "Dark".to_string()).with_fade_duration(Duration::from_millis(50));
+        cue2.add_layer_state(1, LayerState::new(0.0, false, (0.0, 0.0), 0.0, 1.0));
+        list.add_cue(cue1);
+        list.add_cue(cue2);
+
+        let start = Instant::now();
+        let mut playback = CuePlayback::new();
+        list.goto_cue_at(1, None, start).unwrap();
+        playback.apply_at(&list, &mut state, start);
+        assert_eq!(state.layer_manager.get_layer(1).unwrap().opacity, 1.0);
+
+        list.goto_cue_at(2, None, start).unwrap();
+        playback.apply_at(&list, &mut state, start + Duration::from_millis(25));
+        let layer = state.layer_manager.get_layer(1).unwrap();
+        assert_eq!(layer.opacity, 0.5);
+        assert!(layer.visible);
+
+        let done = start + Duration::from_millis(80);
+        list.update_at(done);
+        playback.apply_at(&list, &mut state, done);
+        let layer = state.layer_manager.get_layer(1).unwrap();
+        assert_eq!(layer.opacity, 0.0);
+        assert!(!layer.visible);
+    }
+}
@@ -0,0 +1,333 @@
+//! Multiple cue lists with independent playheads
+//!
+//! Each list in a [`CueStack`] has its own current cue, crossfade and
+//! submaster level. Their outputs are merged before being written to the
+//! project: layer and paint opacity use HTP (highest takes precedence),
+//! everything else uses LTP (latest takes precedence).
+
+use mapmap_core::AppState;
+
+use super::cue::Cue;
+use super::cue_list::CueList;
+use super::playback::{write_cue, CuePlayback};
+use crate::timecode::{TimecodeRate, TimecodeUpdate};
+use crate::{error::ControlError, Result};
+
+/// Identifier of a cue list inside a [`CueStack`]
+pub type CueListId = u32;
+
+/// A cue list with its own playhead and submaster
+pub struct CueListSlot {
+    pub id: CueListId,
+    pub name: String,
+    pub list: CueList,
+    /// Submaster level (0.0-1.0) scaling this list's contribution
+    submaster: f32,
+    playback: CuePlayback,
+    /// Values produced by the list the last time it changed
+    last_output: Option<Cue>,
+    /// Cue the list was last heading to
+    last_target: Option<u32>,
+    /// Activation order for LTP merging
+    activated: u64,
+}
+
+impl CueListSlot {
+    fn new(id: CueListId, name: String) -> Self {
+        Self {
+            id,
+            name,
+            list: CueList::new(),
+            submaster: 1.0,
+            playback: CuePlayback::new(),
+            last_output: None,
+            last_target: None,
+            activated: 0,
+        }
+    }
+
+    /// Get the submaster level
+    pub fn submaster(&self) -> f32 {
+        self.submaster
+    }
+}
+
+/// Runs several cue lists at once and merges their output
+pub struct CueStack {
+    slots: Vec<CueListSlot>,
+    next_id: CueListId,
+    activation_counter: u64,
+    dirty: bool,
+}
+
+impl CueStack {
+    /// ID of the main cue list, which always exists
+    pub const MAIN: CueListId = 0;
+
+    /// Create a stack containing only the main cue list
+    pub fn new() -> Self {
+        Self {
+            slots: vec![CueListSlot::new(Self::MAIN, "Main".to_string())],
+            next_id: Self::MAIN + 1,
+            activation_counter: 0,
+            dirty: false,
+        }
+    }
+
+    /// Add a new, empty cue list
+    pub fn add_list(&mut self, name: String) -> CueListId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.slots.push(CueListSlot::new(id, name));
+        id
+    }
+
+    /// Remove a cue list (the main list cannot be removed)
+    pub fn remove_list(&mut self, id: CueListId) -> Option<CueList> {
+        if id == Self::MAIN {
+            return None;
+        }
+        let index = self.slots.iter().position(|s| s.id == id)?;
+        self.dirty = true;
+        Some(self.slots.remove(index).list)
+    }
+
+    /// Get all cue lists
+    pub fn slots(&self) -> &[CueListSlot] {
+        &self.slots
+    }
+
+    /// Get a cue list by ID
+    pub fn list(&self, id: CueListId) -> Option<&CueList> {
+        self.slot(id).map(|s| &s.list)
+    }
+
+    /// Get a mutable cue list by ID
+    pub fn list_mut(&mut self, id: CueListId) -> Option<&mut CueList> {
+        self.slot_mut(id).map(|s| &mut s.list)
+    }
+
+    /// Get the main cue list
+    pub fn main(&self) -> &CueList {
+        &self.slots[0].list
+    }
+
+    /// Get the main cue list mutably
+    pub fn main_mut(&mut self) -> &mut CueList {
+        &mut self.slots[0].list
+    }
+
+    /// Set a list's submaster level (clamped to 0.0-1.0)
+    pub fn set_submaster(&mut self, id: CueListId, level: f32) -> Result<()> {
+        let slot = self.slot_mut(id).ok_or_else(|| list_not_found(id))?;
+        slot.submaster = level.clamp(0.0, 1.0);
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Get a list's submaster level
+    pub fn submaster(&self, id: CueListId) -> Option<f32> {
+        self.slot(id).map(|s| s.submaster)
+    }
+
+    /// Advance a list to its next cue
+    pub fn go(&mut self, id: CueListId) -> Result<()> {
+        self.slot_mut(id)
+            .ok_or_else(|| list_not_found(id))?
+            .list
+            .next()
+    }
+
+    /// Step a list back to its previous cue
+    pub fn back(&mut self, id: CueListId) -> Result<()> {
+        self.slot_mut(id)
+            .ok_or_else(|| list_not_found(id))?
+            .list
+            .prev()
+    }
+
+    /// Jump a list to a specific cue
+    pub fn goto(&mut self, id: CueListId, cue_id: u32) -> Result<()> {
+        self.slot_mut(id)
+            .ok_or_else(|| list_not_found(id))?
+            .list
+            .goto_cue(cue_id, None)
+    }
+
+    /// Update all cue lists (call this regularly to handle crossfades)
+    pub fn update(&mut self) {
+        for slot in &mut self.slots {
+            slot.list.update();
+        }
+    }
+
+    /// Chase timecode on every cue list
+    pub fn chase_timecode(&mut self, update: &TimecodeUpdate, rate: TimecodeRate) {
+        for slot in &mut self.slots {
+            slot.list.chase_timecode(update, rate);
+        }
+    }
+
+    /// Merge the output of all cue lists into the project state
+    pub fn apply(&mut self, state: &mut AppState) {
+        for slot in &mut self.slots {
+            let target = slot
+                .list
+                .current_crossfade()
+                .map(|c| c.to_cue_id())
+                .or(slot.list.current_cue());
+            if target.is_some() && target != slot.last_target {
+                self.activation_counter += 1;
+                slot.activated = self.activation_counter;
+            }
+            slot.last_target = target;
+
+            if let Some(values) = slot.playback.output(&slot.list, state) {
+                slot.last_output = Some(values);
+                self.dirty = true;
+            }
+        }
+
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+
+        write_cue(&self.merged(), state);
+    }
+
+    /// Merge the last output of every list (HTP for opacity, LTP otherwise)
+    fn merged(&self) -> Cue {
+        let mut order: Vec<&CueListSlot> = self.slots.iter().collect();
+        order.sort_by_key(|s| s.activated);
+
+        let mut merged = Cue::new(0, String::new());
+        for slot in order {
+            let Some(values) = &slot.last_output else {
+                continue;
+            };
+            let level = slot.submaster;
+
+            for (id, layer) in &values.layer_states {
+                let mut layer = layer.clone();
+                layer.opacity *= level;
+                match merged.layer_states.get_mut(id) {
+                    Some(previous) => {
+                        let opacity = layer.opacity.max(previous.opacity);
+                        let visible = layer.visible || previous.visible;
+                        if level > 0.0 {
+                            *previous = layer;
+                        }
+                        previous.opacity = opacity;
+                        previous.visible = visible;
+                    }
+                    None => {
+                        merged.layer_states.insert(*id, layer);
+                    }
+                }
+            }
+
+            for (id, paint) in &values.paint_states {
+                let entry = merged.paint_states.entry(*id).or_default();
+                for (name, value) in &paint.parameters {
+                    if name == "opacity" {
+                        let scaled = value * level;
+                        let value = entry.parameters.get(name).map_or(scaled, |v| v.max(scaled));
+                        entry.parameters.insert(name.clone(), value);
+                    } else if level > 0.0 {
+                        entry.parameters.insert(name.clone(), *value);
+                    }
+                }
+            }
+
+            // Lists pulled down to zero only contribute HTP values
+            if level <= 0.0 {
+                continue;
+            }
+
+            merged.track(&Cue {
+                layer_states: Default::default(),
+                paint_states: Default::default(),
+                block: false,
+                ..values.clone()
+            });
+        }
+        merged
+    }
+
+    fn slot(&self, id: CueListId) -> Option<&CueListSlot> {
+        self.slots.iter().find(|s| s.id == id)
+    }
+
+    fn slot_mut(&mut self, id: CueListId) -> Option<&mut CueListSlot> {
+        self.slots.iter_mut().find(|s| s.id == id)
+    }
+}
+
+impl Default for CueStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn list_not_found(id: CueListId) -> ControlError {
+    ControlError::TargetNotFound(format!("Cue list {} not found", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cue::LayerState;
+    use std::time::Duration;
+
+    fn opacity_cue(id: u32, layer_id: u32, opacity: f32) -> Cue {
+        let mut cue = Cue::new(id, format!("Cue {}", id)).with_fade_duration(Duration::ZERO);
+        cue.add_layer_state(
+            layer_id,
+            LayerState::new(opacity, true, (0.0, 0.0), 0.0, 1.0),
+        );
+        cue
+    }
+
+    #[test]
+    fn test_main_list_always_exists() {
+        let mut stack = CueStack::new();
+        assert_eq!(stack.slots().len(), 1);
+        assert!(stack.remove_list(CueStack::MAIN).is_none());
+
+        let ambient = stack.add_list("Ambient".to_string());
+        assert!(stack.list(ambient).is_some());
+        assert!(stack.remove_list(ambient).is_some());
+    }
+
+    #[test]
+    fn test_htp_opacity_merge() {
+        let mut state = AppState::default();
+        let layer_id = state.layer_manager.create_layer("Layer") as u32;
+
+        let mut stack = CueStack::new();
+        let ambient = stack.add_list("Ambient".to_string());
+        stack.main_mut().add_cue(opacity_cue(1, layer_id, 0.4));
+        stack
+            .list_mut(ambient)
+            .unwrap()
+            .add_cue(opacity_cue(1, layer_id, 0.8));
+
+        stack.goto(CueStack::MAIN, 1).unwrap();
+        stack.goto(ambient, 1).unwrap();
+        stack.apply(&mut state);
+        assert_eq!(state.layer_manager.layers()[0].opacity, 0.8);
+
+        // Pulling the ambient submaster down lets the main list win
+        stack.set_submaster(ambient, 0.25).unwrap();
+        stack.apply(&mut state);
+        assert_eq!(state.layer_manager.layers()[0].opacity, 0.4);
+    }
+
+    #[test]
+    fn test_unknown_list() {
+        let mut stack = CueStack::new();
+        assert!(stack.go(7).is_err());
+        assert!(stack.set_submaster(7, 0.5).is_err());
+    }
+}
@@ -6,6 +6,9 @@ use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "midi")]
 use crate::midi::MidiMessage;
+use crate::shortcuts::{Key, Modifiers};
+use crate::target::ControlValue;
+use crate::timecode::{Timecode, TimecodeRate, TimecodeUpdate};
 
 /// MIDI trigger configuration
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -14,6 +17,9 @@ pub struct MidiTrigger {
     pub channel: u8,
     /// Trigger type
     pub trigger_type: MidiTriggerType,
+    /// Minimum note velocity that fires the trigger
+    #[serde(default)]
+    pub min_velocity: u8,
 }
 
 /// Types of MIDI triggers
@@ -33,6 +39,7 @@ impl MidiTrigger {
         Self {
             channel,
             trigger_type: MidiTriggerType::Note { note },
+            min_velocity: 0,
         }
     }
 
@@ -41,6 +48,7 @@ impl MidiTrigger {
         Self {
             channel,
             trigger_type: MidiTriggerType::ControlChange { controller, value },
+            min_velocity: 0,
         }
     }
 
@@ -49,9 +57,16 @@ impl MidiTrigger {
         Self {
             channel,
             trigger_type: MidiTriggerType::ProgramChange { program },
+            min_velocity: 0,
         }
     }
 
+    /// Only fire note triggers at or above a velocity
+    pub fn with_min_velocity(mut self, velocity: u8) -> Self {
+        self.min_velocity = velocity;
+        self
+    }
+
     /// Check if a MIDI message matches this trigger
     #[cfg(feature = "midi")]
     pub fn matches(&self, message: &MidiMessage) -> bool {
@@ -61,9 +76,9 @@ impl MidiTrigger {
                 MidiMessage::NoteOn {
                     channel,
                     note: msg_note,
-                    ..
+                    velocity,
                 },
-            ) => *channel == self.channel && *msg_note == *note,
+            ) => *channel == self.channel && *msg_note == *note && *velocity >= self.min_velocity,
 
             (
                 MidiTriggerType::ControlChange { controller, value },
@@ -136,6 +151,33 @@ impl TimeTrigger {
     }
 }
 
+/// Timecode trigger: fires when chased timecode reaches a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TimecodeTrigger {
+    /// Position at which the cue fires
+    pub timecode: Timecode,
+}
+
+impl TimecodeTrigger {
+    /// Create a new timecode trigger
+    pub fn new(timecode: Timecode) -> Self {
+        Self { timecode }
+    }
+
+    /// Frame position of this trigger at the source's frame rate
+    pub fn frame(&self, rate: TimecodeRate) -> u64 {
+        self.timecode.frames_at(rate)
+    }
+
+    /// Check if timecode ran through this trigger during an update
+    pub fn matches(&self, update: &TimecodeUpdate, rate: TimecodeRate) -> bool {
+        match *update {
+            TimecodeUpdate::Advanced { from, to } => (from + 1..=to).contains(&self.frame(rate)),
+            TimecodeUpdate::Jumped { to } => self.frame(rate) == to,
+        }
+    }
+}
+
 /// OSC trigger pattern
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct OscTrigger {
@@ -166,6 +208,51 @@ impl OscTrigger {
     pub fn matches_address(&self, address: &str) -> bool {
         self.address == address
     }
+
+    /// Check if an OSC message matches this trigger's address and value
+    pub fn matches(&self, address: &str, value: Option<&ControlValue>) -> bool {
+        if !self.matches_address(address) {
+            return false;
+        }
+        match (&self.value, value) {
+            (None, _) => true,
+            (Some(expected), Some(value)) => value_matches(expected, value),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Compare an incoming value with a trigger's expected value
+fn value_matches(expected: &str, value: &ControlValue) -> bool {
+    match value {
+        ControlValue::String(s) => s == expected,
+        ControlValue::Bool(b) if expected.parse::<bool>().is_ok() => {
+            expected.parse::<bool>() == Ok(*b)
+        }
+        _ => match (value.as_float(), expected.parse::<f32>()) {
+            (Some(actual), Ok(expected)) => (actual - expected).abs() < 1e-4,
+            _ => false,
+        },
+    }
+}
+
+/// Keyboard trigger
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KeyTrigger {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+impl KeyTrigger {
+    /// Create a new keyboard trigger
+    pub fn new(key: Key, modifiers: Modifiers) -> Self {
+        Self { key, modifiers }
+    }
+
+    /// Check if a key press matches this trigger
+    pub fn matches(&self, key: Key, modifiers: &Modifiers) -> bool {
+        self.key == key && self.modifiers == *modifiers
+    }
 }
 
 #[cfg(test)]
@@ -219,6 +306,24 @@ mod tests {
         assert!(!trigger.matches(&wrong_channel));
     }
 
+    #[cfg(feature = "midi")]
+    #[test]
+    fn test_midi_velocity_threshold() {
+        let trigger = MidiTrigger::note(0, 60).with_min_velocity(64);
+        let soft = MidiMessage::NoteOn {
+            channel: 0,
+            note: 60,
+            velocity: 20,
+        };
+        let hard = MidiMessage::NoteOn {
+            channel: 0,
+            note: 60,
+            velocity: 100,
+        };
+        assert!(!trigger.matches(&soft));
+        assert!(trigger.matches(&hard));
+    }
+
     #[test]
     fn test_time_trigger() {
         let trigger = TimeTrigger::new(12, 30, 0);
@@ -237,6 +342,17 @@ mod tests {
         assert!(TimeTrigger::new(0, 0, 60).is_none());
     }
 
+    #[test]
+    fn test_timecode_trigger() {
+        let rate = TimecodeRate::FPS_25;
+        let trigger = TimecodeTrigger::new(Timecode::new(0, 0, 1, 0, rate).unwrap());
+        assert_eq!(trigger.frame(rate), 25);
+
+        assert!(trigger.matches(&TimecodeUpdate::Advanced { from: 24, to: 26 }, rate));
+        assert!(!trigger.matches(&TimecodeUpdate::Advanced { from: 25, to: 26 }, rate));
+        assert!(trigger.matches(&TimecodeUpdate::Jumped { to: 25 }, rate));
+    }
+
     #[test]
     fn test_osc_trigger() {
         let trigger = OscTrigger::new("/mapmap/cue/1".to_string());
@@ -249,4 +365,25 @@ mod tests {
         let trigger = OscTrigger::with_value("/mapmap/cue/1".to_string(), "1.0".to_string());
         assert_eq!(trigger.value, Some("1.0".to_string()));
     }
+
+    #[test]
+    fn test_osc_trigger_value_match() {
+        let trigger = OscTrigger::with_value("/go".to_string(), "1".to_string());
+        assert!(trigger.matches("/go", Some(&ControlValue::Float(1.0))));
+        assert!(trigger.matches("/go", Some(&ControlValue::Int(1))));
+        assert!(trigger.matches("/go", Some(&ControlValue::Bool(true))));
+        assert!(!trigger.matches("/go", Some(&ControlValue::Float(0.0))));
+        assert!(!trigger.matches("/go", None));
+
+        let any = OscTrigger::new("/go".to_string());
+        assert!(any.matches("/go", None));
+        assert!(!any.matches("/stop", None));
+    }
+
+    #[test]
+    fn test_key_trigger() {
+        let trigger = KeyTrigger::new(Key::Space, Modifiers::shift());
+        assert!(trigger.matches(Key::Space, &Modifiers::shift()));
+        assert!(!trigger.matches(Key::Space, &Modifiers::new()));
+    }
 }
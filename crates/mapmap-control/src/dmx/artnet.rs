@@ -2,7 +2,8 @@
 //!
 //! Art-Net is a UDP-based protocol for transmitting DMX512 over Ethernet.
 
-use std::net::{SocketAddr, UdpSocket};
+use std::collections::HashMap;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
 use std::time::{Duration, Instant};
 
 use crate::{error::ControlError, Result};
@@ -11,8 +12,10 @@ use crate::{error::ControlError, Result};
 pub struct ArtNetSender {
     socket: UdpSocket,
     universe: u16,
-    sequence: u8,
-    last_send: Instant,
+    /// Sequence number of the next packet per universe
+    sequence: HashMap<u16, u8>,
+    /// Last send time per universe, for rate limiting
+    last_send: HashMap<u16, Instant>,
     min_interval: Duration,
 }
 
@@ -40,8 +43,8 @@ impl ArtNetSender {
         Ok(Self {
             socket,
             universe,
-            sequence: 0,
-            last_send: Instant::now(),
+            sequence: HashMap::new(),
+            last_send: HashMap::new(),
             min_interval: Duration::from_millis(1000 / 30), // 30Hz refresh rate
         })
     }
@@ -52,24 +55,69 @@ impl ArtNetSender {
     /// * `channels` - 512 DMX channel values
     /// * `target` - Destination address
     pub fn send_dmx(&mut self, channels: &[u8; 512], target: &str) -> Result<()> {
-        // Rate limiting (30Hz max)
+        // Rate limiting (30Hz max per universe)
         let now = Instant::now();
-        let elapsed = now.duration_since(self.last_send);
-        if elapsed < self.min_interval {
-            return Ok(());
+        if let Some(last) = self.last_send.get(&self.universe) {
+            if now.duration_since(*last) < self.min_interval {
+                return Ok(());
+            }
         }
 
         let packet = self.build_artnet_packet(channels);
 
         self.socket.send_to(&packet, target)?;
-        self.sequence = self.sequence.wrapping_add(1);
-        self.last_send = now;
+        self.advance_sequence(self.universe);
+        self.last_send.insert(self.universe, now);
 
         tracing::trace!("Sent Art-Net DMX packet for universe {}", self.universe);
 
         Ok(())
     }
 
+    /// Send DMX data for a specific universe without rate limiting
+    ///
+    /// Used by callers that pace output themselves, such as the DMX engine.
+    pub fn send_universe(
+        &mut self,
+        universe: u16,
+        channels: &[u8; 512],
+        target: impl ToSocketAddrs,
+    ) -> Result<()> {
+        let previous = self.universe;
+        self.universe = universe;
+        let packet = self.build_artnet_packet(channels);
+        self.universe = previous;
+
+        self.socket.send_to(&packet, target)?;
+        self.advance_sequence(universe);
+        Ok(())
+    }
+
+    /// Step the sequence number of `universe` after a packet was sent
+    ///
+    /// Receivers reorder packets by sequence per universe, so every universe
+    /// counts on its own.
+    fn advance_sequence(&mut self, universe: u16) {
+        let sequence = self.sequence.entry(universe).or_insert(0);
+        *sequence = sequence.wrapping_add(1);
+    }
+
+    /// Send an ArtSync packet so receivers output buffered universes together
+    pub fn send_sync(&mut self, target: impl ToSocketAddrs) -> Result<()> {
+        self.socket.send_to(&Self::build_sync_packet(), target)?;
+        Ok(())
+    }
+
+    /// Build an ArtSync packet (OpSync)
+    fn build_sync_packet() -> Vec<u8> {
+        let mut packet = vec![0u8; 14];
+        packet[0..8].copy_from_slice(b"Art-Net\0");
+        packet[8..10].copy_from_slice(&0x5200u16.to_le_bytes());
+        packet[10..12].copy_from_slice(&14u16.to_be_bytes());
+        // Aux1 and Aux2 stay zero
+        packet
+    }
+
     /// Build an Art-Net DMX packet (OpDmx)
     fn build_artnet_packet(&self, channels: &[u8; 512]) -> Vec<u8> {
         let mut packet = vec![0u8; 18 + 512];
@@ -84,7 +132,7 @@ impl ArtNetSender {
         packet[10..12].copy_from_slice(&14u16.to_be_bytes());
 
         // Sequence
-        packet[12] = self.sequence;
+        packet[12] = self.sequence.get(&self.universe).copied().unwrap_or(0);
 
         // Physical (0)
         packet[13] = 0;
@@ -126,8 +174,8 @@ mod tests {
         let sender = ArtNetSender {
             socket: UdpSocket::bind("0.0.0.0:0").unwrap(),
             universe: 0,
-            sequence: 0,
-            last_send: Instant::now(),
+            sequence: HashMap::new(),
+            last_send: HashMap::new(),
             min_interval: Duration::from_millis(33),
         };
 
@@ -177,19 +225,32 @@ mod tests {
     #[test]
     fn test_sequence_increment() {
         let mut sender = ArtNetSender::new(0, "255.255.255.255:6454").unwrap();
-
+        let target = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let target = target.local_addr().unwrap();
         let channels = [0u8; 512];
 
-        // First packet
-        let packet1 = sender.build_artnet_packet(&channels);
-        let seq1 = packet1[12];
-
-        sender.sequence = sender.sequence.wrapping_add(1);
+        let seq1 = sender.build_artnet_packet(&channels)[12];
+        sender.send_universe(0, &channels, target).unwrap();
+        let seq2 = sender.build_artnet_packet(&channels)[12];
+        assert_eq!(seq2, seq1.wrapping_add(1));
 
-        // Second packet
-        let packet2 = sender.build_artnet_packet(&channels);
-        let seq2 = packet2[12];
+        // Other universes keep their own count
+        sender.send_universe(0, &channels, target).unwrap();
+        sender.set_universe(1);
+        assert_eq!(sender.build_artnet_packet(&channels)[12], 0);
+        sender.send_universe(1, &channels, target).unwrap();
+        assert_eq!(sender.build_artnet_packet(&channels)[12], 1);
+        sender.set_universe(0);
+        assert_eq!(sender.build_artnet_packet(&channels)[12], 2);
+    }
 
-        assert_eq!(seq2, seq1.wrapping_add(1));
+    #[test]
+    fn test_sync_packet() {
+        let packet = ArtNetSender::build_sync_packet();
+        assert_eq!(packet.len(), 14);
+        assert_eq!(&packet[0..8], b"Art-Net\0");
+        assert_eq!(packet[8], 0x00);
+        assert_eq!(packet[9], 0x52);
+        assert_eq!(packet[11], 14);
     }
 }
@@ -0,0 +1,451 @@
+//! Art-Net node discovery (ArtPoll / ArtPollReply)
+//!
+//! Nodes answer an ArtPoll with one ArtPollReply per group of up to four
+//! ports, listing the port-addresses (universes) they output. Discovery keeps
+//! that list up to date so each universe can be unicast only to the nodes
+//! that use it instead of being broadcast to the whole network.
+
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::{error::ControlError, Result};
+
+/// Standard Art-Net UDP port
+pub const ARTNET_PORT: u16 = 6454;
+
+const OP_POLL: u16 = 0x2000;
+const OP_POLL_REPLY: u16 = 0x2100;
+const POLL_REPLY_LEN: usize = 239;
+
+/// Build an ArtPoll packet asking nodes to reply
+pub fn artpoll_packet() -> Vec<u8> {
+    let mut packet = vec![0u8; 14];
+    packet[0..8].copy_from_slice(b"Art-Net\0");
+    packet[8..10].copy_from_slice(&OP_POLL.to_le_bytes());
+    packet[10..12].copy_from_slice(&14u16.to_be_bytes());
+    // Flags: send ArtPollReply whenever node conditions change
+    packet[12] = 0x02;
+    // DiagPriority: low
+    packet[13] = 0x10;
+    packet
+}
+
+/// Contents of an ArtPollReply
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtPollReply {
+    /// Address the node receives Art-Net on
+    pub address: SocketAddrV4,
+    pub short_name: String,
+    pub long_name: String,
+    /// Port-addresses (universes) of the node's DMX outputs
+    pub outputs: Vec<u16>,
+    /// Which group of ports this reply describes (1-based)
+    pub bind_index: u8,
+}
+
+impl ArtPollReply {
+    /// Parse an ArtPollReply, returning `None` for any other packet
+    pub fn parse(packet: &[u8]) -> Option<Self> {
+        if packet.len() < 207 || &packet[0..8] != b"Art-Net\0" {
+            return None;
+        }
+        if u16::from_le_bytes([packet[8], packet[9]]) != OP_POLL_REPLY {
+            return None;
+        }
+
+        let ip = Ipv4Addr::new(packet[10], packet[11], packet[12], packet[13]);
+        let port = u16::from_le_bytes([packet[14], packet[15]]);
+        let net = (packet[18] & 0x7F) as u16;
+        let sub_net = (packet[19] & 0x0F) as u16;
+        let num_ports = (packet[173] as usize).min(4);
+
+        let outputs = (0..num_ports)
+            .filter(|&i| packet[174 + i] & 0x80 != 0)
+            .map(|i| (net << 8) | (sub_net << 4) | (packet[190 + i] & 0x0F) as u16)
+            .collect();
+
+        Some(Self {
+            address: SocketAddrV4::new(ip, if port == 0 { ARTNET_PORT } else { port }),
+            short_name: read_string(&packet[26..44]),
+            long_name: read_string(&packet[44..108]),
+            outputs,
+            bind_index: packet.get(211).copied().filter(|i| *i != 0).unwrap_or(1),
+        })
+    }
+
+    /// Encode as an ArtPollReply packet
+    ///
+    /// All outputs must share the net and sub-net of the first output.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut packet = vec![0u8; POLL_REPLY_LEN];
+        packet[0..8].copy_from_slice(b"Art-Net\0");
+        packet[8..10].copy_from_slice(&OP_POLL_REPLY.to_le_bytes());
+        packet[10..14].copy_from_slice(&self.address.ip().octets());
+        packet[14..16].copy_from_slice(&self.address.port().to_le_bytes());
+
+        let first = self.outputs.first().copied().unwrap_or(0);
+        packet[18] = ((first >> 8) & 0x7F) as u8;
+        packet[19] = ((first >> 4) & 0x0F) as u8;
+        write_string(&mut packet[26..44], &self.short_name);
+        write_string(&mut packet[44..108], &self.long_name);
+
+        let ports = self.outputs.len().min(4);
+        packet[173] = ports as u8;
+        for (i, universe) in self.outputs.iter().take(4).enumerate() {
+            // DMX512 port that can output
+            packet[174 + i] = 0x80;
+            packet[182 + i] = 0x80;
+            packet[190 + i] = (universe & 0x0F) as u8;
+        }
+        packet[211] = self.bind_index;
+        packet
+    }
+}
+
+fn read_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn write_string(bytes: &mut [u8], value: &str) {
+    let len = value.len().min(bytes.len() - 1);
+    bytes[..len].copy_from_slice(&value.as_bytes()[..len]);
+}
+
+/// A node found on the network
+#[derive(Debug, Clone)]
+pub struct ArtNetNode {
+    pub address: SocketAddrV4,
+    pub short_name: String,
+    pub long_name: String,
+    /// Output universes across all of the node's port groups
+    pub universes: Vec<u16>,
+    pub last_seen: Instant,
+    /// The node stopped answering polls
+    pub stale: bool,
+    /// Outputs per bind index
+    groups: HashMap<u8, Vec<u16>>,
+}
+
+/// Change in the set of known nodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeEvent {
+    /// A node replied for the first time, or again after going stale
+    Online(SocketAddrV4),
+    /// A node stopped replying to polls
+    Stale(SocketAddrV4),
+}
+
+/// Finds Art-Net nodes and routes universes to them
+pub struct ArtNetDiscovery {
+    socket: UdpSocket,
+    poll_target: SocketAddr,
+    nodes: HashMap<SocketAddrV4, ArtNetNode>,
+    poll_interval: Duration,
+    stale_timeout: Duration,
+    last_poll: Option<Instant>,
+}
+
+impl ArtNetDiscovery {
+    /// Listen for replies on the Art-Net port and poll `poll_target`
+    /// (typically "255.255.255.255:6454" or a directed broadcast)
+    pub fn new(poll_target: &str) -> Result<Self> {
+        Self::bind(&format!("0.0.0.0:{}", ARTNET_PORT), poll_target)
+    }
+
+    /// Listen for replies on `bind_address` and poll `poll_target`
+    pub fn bind(bind_address: &str, poll_target: &str) -> Result<Self> {
+        let poll_target = poll_target
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| ControlError::DmxError("Invalid ArtPoll target".to_string()))?;
+
+        let socket = UdpSocket::bind(bind_address)?;
+        socket.set_broadcast(true)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            poll_target,
+            nodes: HashMap::new(),
+            poll_interval: Duration::from_millis(2500),
+            stale_timeout: Duration::from_secs(10),
+            last_poll: None,
+        })
+    }
+
+    /// Set how often to send ArtPoll
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Set how long a node may stay silent before it is reported stale
+    pub fn with_stale_timeout(mut self, timeout: Duration) -> Self {
+        self.stale_timeout = timeout;
+        self
+    }
+
+    /// Get the local address replies are received on
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Send an ArtPoll now
+    pub fn poll(&mut self) -> Result<()> {
+        self.socket.send_to(&artpoll_packet(), self.poll_target)?;
+        self.last_poll = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Poll when due, read replies and report nodes coming and going
+    ///
+    /// Call regularly, e.g. once per frame.
+    pub fn update(&mut self) -> Result<Vec<NodeEvent>> {
+        let now = Instant::now();
+        let poll_due = match self.last_poll {
+            Some(last) => now.duration_since(last) >= self.poll_interval,
+            None => true,
+        };
+        if poll_due {
+            self.poll()?;
+        }
+
+        let mut buffer = [0u8; 1024];
+        let mut events = Vec::new();
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((len, source)) => {
+                    if let Some(reply) = ArtPollReply::parse(&buffer[..len]) {
+                        events.extend(self.handle_reply(reply, source, now));
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        events.extend(self.expire(now));
+        Ok(events)
+    }
+
+    fn handle_reply(
+        &mut self,
+        mut reply: ArtPollReply,
+        source: SocketAddr,
+        now: Instant,
+    ) -> Option<NodeEvent> {
+        // Nodes that do not fill in their IP are reached at the sender address
+        if reply.address.ip().is_unspecified() {
+            if let SocketAddr::V4(source) = source {
+                reply.address = SocketAddrV4::new(*source.ip(), reply.address.port());
+            }
+        }
+
+        let node = self
+            .nodes
+            .entry(reply.address)
+            .or_insert_with(|| ArtNetNode {
+                address: reply.address,
+                short_name: String::new(),
+                long_name: String::new(),
+                universes: Vec::new(),
+                last_seen: now,
+                stale: true,
+                groups: HashMap::new(),
+            });
+
+        node.short_name = reply.short_name;
+        node.long_name = reply.long_name;
+        node.last_seen = now;
+        node.groups.insert(reply.bind_index, reply.outputs);
+        let mut universes: Vec<u16> = node.groups.values().flatten().copied().collect();
+        universes.sort_unstable();
+        universes.dedup();
+        node.universes = universes;
+
+        if node.stale {
+            node.stale = false;
+            tracing::info!(
+                "Art-Net node '{}' online at {}",
+                node.short_name,
+                node.address
+            );
+            return Some(NodeEvent::Online(node.address));
+        }
+        None
+    }
+
+    fn expire(&mut self, now: Instant) -> Vec<NodeEvent> {
+        let mut events = Vec::new();
+        for node in self.nodes.values_mut() {
+            if !node.stale && now.duration_since(node.last_seen) > self.stale_timeout {
+                node.stale = true;
+                tracing::warn!(
+                    "Art-Net node '{}' at {} stopped responding",
+                    node.short_name,
+                    node.address
+                );
+                events.push(NodeEvent::Stale(node.address));
+            }
+        }
+        events
+    }
+
+    /// Get every known node, including stale ones
+    pub fn nodes(&self) -> impl Iterator<Item = &ArtNetNode> {
+        self.nodes.values()
+    }
+
+    /// Get nodes that stopped responding
+    pub fn stale_nodes(&self) -> impl Iterator<Item = &ArtNetNode> {
+        self.nodes.values().filter(|n| n.stale)
+    }
+
+    /// Forget nodes that stopped responding
+    pub fn remove_stale(&mut self) {
+        self.nodes.retain(|_, n| !n.stale);
+    }
+
+    /// Get the live nodes that output `universe`
+    pub fn targets_for(&self, universe: u16) -> Vec<SocketAddr> {
+        self.nodes
+            .values()
+            .filter(|n| !n.stale && n.universes.contains(&universe))
+            .map(|n| SocketAddr::V4(n.address))
+            .collect()
+    }
+
+    /// Map every universe to the live nodes that output it
+    pub fn routes(&self) -> HashMap<u16, Vec<SocketAddr>> {
+        let mut routes: HashMap<u16, Vec<SocketAddr>> = HashMap::new();
+        for node in self.nodes.values().filter(|n| !n.stale) {
+            for universe in &node.universes {
+                routes
+                    .entry(*universe)
+                    .or_default()
+                    .push(SocketAddr::V4(node.address));
+            }
+        }
+        // Stable order so route maps can be compared between updates
+        for nodes in routes.values_mut() {
+            nodes.sort_unstable();
+        }
+        routes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmx::ArtNetSender;
+
+    /// A loopback stand-in for an Art-Net node
+    fn fake_node(outputs: Vec<u16>) -> (UdpSocket, ArtPollReply) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let SocketAddr::V4(address) = socket.local_addr().unwrap() else {
+            unreachable!()
+        };
+        let reply = ArtPollReply {
+            address,
+            short_name: "Wall".to_string(),
+            long_name: "LED wall node".to_string(),
+            outputs,
+            bind_index: 1,
+        };
+        (socket, reply)
+    }
+
+    fn answer_poll(node: &UdpSocket, reply: &ArtPollReply) {
+        let mut buffer = [0u8; 64];
+        let (len, from) = node.recv_from(&mut buffer).unwrap();
+        assert_eq!(&buffer[..len], &artpoll_packet()[..]);
+        node.send_to(&reply.to_bytes(), from).unwrap();
+    }
+
+    fn wait_for_events(discovery: &mut ArtNetDiscovery) -> Vec<NodeEvent> {
+        for _ in 0..100 {
+            let events = discovery.update().unwrap();
+            if !events.is_empty() {
+                return events;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        Vec::new()
+    }
+
+    #[test]
+    fn test_poll_reply_roundtrip() {
+        let reply = ArtPollReply {
+            address: "10.0.0.5:6454".parse().unwrap(),
+            short_name: "Node".to_string(),
+            long_name: "Four port node".to_string(),
+            outputs: vec![0x123, 0x124, 0x125, 0x126],
+            bind_index: 2,
+        };
+        assert_eq!(ArtPollReply::parse(&reply.to_bytes()), Some(reply));
+        assert!(ArtPollReply::parse(&artpoll_packet()).is_none());
+    }
+
+    #[test]
+    fn test_discover_and_route() {
+        let (node, reply) = fake_node(vec![3, 4]);
+        let target = node.local_addr().unwrap().to_string();
+        let mut discovery = ArtNetDiscovery::bind("127.0.0.1:0", &target)
+            .unwrap()
+            .with_poll_interval(Duration::from_secs(60));
+
+        discovery.poll().unwrap();
+        answer_poll(&node, &reply);
+        let events = wait_for_events(&mut discovery);
+        assert_eq!(events, vec![NodeEvent::Online(reply.address)]);
+
+        let node_addr = SocketAddr::V4(reply.address);
+        assert_eq!(discovery.targets_for(3), vec![node_addr]);
+        assert!(discovery.targets_for(5).is_empty());
+
+        // Unicast a routed universe to the node
+        let mut sender = ArtNetSender::new(0, "127.0.0.1:6454").unwrap();
+        for target in discovery.targets_for(4) {
+            sender.send_universe(4, &[7u8; 512], target).unwrap();
+        }
+        let mut buffer = [0u8; 600];
+        let (len, _) = node.recv_from(&mut buffer).unwrap();
+        assert_eq!(len, 18 + 512);
+        assert_eq!(u16::from_le_bytes([buffer[14], buffer[15]]), 4);
+    }
+
+    #[test]
+    fn test_stale_node() {
+        let (node, reply) = fake_node(vec![1]);
+        let target = node.local_addr().unwrap().to_string();
+        let mut discovery = ArtNetDiscovery::bind("127.0.0.1:0", &target)
+            .unwrap()
+            .with_poll_interval(Duration::from_secs(60))
+            .with_stale_timeout(Duration::from_millis(50));
+
+        discovery.poll().unwrap();
+        answer_poll(&node, &reply);
+        assert_eq!(
+            wait_for_events(&mut discovery),
+            vec![NodeEvent::Online(reply.address)]
+        );
+
+        // No further replies
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(
+            wait_for_events(&mut discovery),
+            vec![NodeEvent::Stale(reply.address)]
+        );
+        assert_eq!(discovery.stale_nodes().count(), 1);
+        assert!(discovery.routes().is_empty());
+
+        discovery.remove_stale();
+        assert_eq!(discovery.nodes().count(), 0);
+    }
+}
@@ -0,0 +1,511 @@
+//! Multi-universe DMX engine
+//!
+//! The engine owns the channel data for any number of universes, keeps a
+//! patch of fixtures with overlap checking and merges additional sources
+//! such as pixel mapping on top. A background thread sends the merged
+//! universes at a steady refresh rate, independent of the render frame
+//! rate, and follows each refresh with ArtSync or an E1.31 sync packet so
+//! receivers output every universe together.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use super::{ArtNetSender, Fixture, SacnSender};
+use crate::{error::ControlError, Result};
+
+/// Channel data for a set of universes
+pub type UniverseMap = HashMap<u16, [u8; 512]>;
+
+/// How a source combines with the engine's own channel data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeMode {
+    /// Highest takes precedence, channel by channel
+    #[default]
+    Htp,
+    /// Latest takes precedence: the source replaces the universes it provides
+    Ltp,
+}
+
+struct DmxSource {
+    name: String,
+    mode: MergeMode,
+    universes: UniverseMap,
+}
+
+/// Network outputs driven by the engine
+#[derive(Default)]
+struct DmxOutputs {
+    artnet: Option<(ArtNetSender, String)>,
+    /// Unicast targets per universe; universes without a node, or all of
+    /// them when unset, go to the broadcast target
+    artnet_routes: Option<HashMap<u16, Vec<SocketAddr>>>,
+    sacn: Option<SacnSender>,
+    sync: bool,
+}
+
+impl DmxOutputs {
+    /// Send every universe and the sync packets
+    ///
+    /// A failed send does not stop the others; the failures are reported
+    /// together afterwards.
+    fn send(&mut self, universes: &UniverseMap) -> Result<()> {
+        let mut ids: Vec<u16> = universes.keys().copied().collect();
+        ids.sort_unstable();
+
+        let mut errors: Vec<String> = Vec::new();
+        let mut check = |result: Result<()>| {
+            if let Err(e) = result {
+                errors.push(e.to_string());
+            }
+        };
+
+        if let Some((sender, target)) = &mut self.artnet {
+            let mut nodes: Vec<SocketAddr> = Vec::new();
+            let mut broadcast = false;
+            for id in &ids {
+                match self
+                    .artnet_routes
+                    .as_ref()
+                    .and_then(|routes| routes.get(id))
+                {
+                    Some(routed) if !routed.is_empty() => {
+                        for node in routed {
+                            check(sender.send_universe(*id, &universes[id], node));
+                            if !nodes.contains(node) {
+                                nodes.push(*node);
+                            }
+                        }
+                    }
+                    _ => {
+                        check(sender.send_universe(*id, &universes[id], target.as_str()));
+                        broadcast = true;
+                    }
+                }
+            }
+            if self.sync {
+                for node in &nodes {
+                    check(sender.send_sync(node));
+                }
+                if broadcast {
+                    check(sender.send_sync(target.as_str()));
+                }
+            }
+        }
+        if let Some(sender) = &mut self.sacn {
+            // sACN universes start at 1
+            for id in ids.iter().filter(|id| **id != 0) {
+                check(sender.send_universe(*id, &universes[id]));
+            }
+            if self.sync && sender.sync_address() != 0 {
+                check(sender.send_sync());
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ControlError::DmxError(errors.join("; ")))
+        }
+    }
+}
+
+/// Owns and outputs multiple DMX universes
+pub struct DmxEngine {
+    patch: Vec<Fixture>,
+    universes: UniverseMap,
+    sources: Vec<DmxSource>,
+    /// Latest merged output read by the output thread
+    frame: Arc<Mutex<UniverseMap>>,
+    outputs: Arc<Mutex<DmxOutputs>>,
+    refresh_rate: u32,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DmxEngine {
+    /// Create an engine refreshing at 44 Hz (the DMX512 maximum)
+    pub fn new() -> Self {
+        Self {
+            patch: Vec::new(),
+            universes: HashMap::new(),
+            sources: Vec::new(),
+            frame: Arc::new(Mutex::new(HashMap::new())),
+            outputs: Arc::new(Mutex::new(DmxOutputs::default())),
+            refresh_rate: 44,
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Set the output refresh rate in Hz
+    pub fn with_refresh_rate(mut self, hz: u32) -> Self {
+        self.refresh_rate = hz.clamp(1, 1000);
+        self
+    }
+
+    /// Get the output refresh rate in Hz
+    pub fn refresh_rate(&self) -> u32 {
+        self.refresh_rate
+    }
+
+    /// Output over Art-Net to `target` (e.g. "255.255.255.255:6454")
+    pub fn enable_artnet(&mut self, target: &str) -> Result<()> {
+        let sender = ArtNetSender::new(0, target)?;
+        self.lock_outputs().artnet = Some((sender, target.to_string()));
+        Ok(())
+    }
+
+    /// Unicast Art-Net universes to the given nodes instead of broadcasting
+    ///
+    /// Universes without a node are still sent to the broadcast target.
+    /// Usually fed from [`ArtNetDiscovery::routes`](super::ArtNetDiscovery::routes).
+    /// `None` returns to broadcasting every universe.
+    pub fn set_artnet_routes(&mut self, routes: Option<HashMap<u16, Vec<SocketAddr>>>) {
+        self.lock_outputs().artnet_routes = routes;
+    }
+
+    /// Get the Art-Net unicast routes
+    pub fn artnet_routes(&self) -> Option<HashMap<u16, Vec<SocketAddr>>> {
+        self.lock_outputs().artnet_routes.clone()
+    }
+
+    /// Output over sACN with the given source name
+    pub fn enable_sacn(&mut self, source_name: &str) -> Result<()> {
+        let sender = SacnSender::new(1, source_name)?;
+        self.lock_outputs().sacn = Some(sender);
+        Ok(())
+    }
+
+    /// Follow each refresh with ArtSync and, if `sacn_sync_address` is not
+    /// zero, an E1.31 sync packet on that universe
+    pub fn set_sync(&mut self, enabled: bool, sacn_sync_address: u16) -> Result<()> {
+        let mut outputs = self.lock_outputs();
+        if let Some(sender) = &mut outputs.sacn {
+            sender.set_sync_address(if enabled { sacn_sync_address } else { 0 })?;
+        }
+        outputs.sync = enabled;
+        Ok(())
+    }
+
+    /// Check if synchronized output is enabled
+    pub fn is_sync_enabled(&self) -> bool {
+        self.lock_outputs().sync
+    }
+
+    fn lock_outputs(&self) -> std::sync::MutexGuard<'_, DmxOutputs> {
+        self.outputs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Patch a fixture, rejecting address overlaps on the same universe
+    pub fn patch(&mut self, fixture: Fixture) -> Result<()> {
+        if fixture.profile.channels.is_empty() {
+            return Err(ControlError::DmxError(format!(
+                "Fixture '{}' has no channels",
+                fixture.name
+            )));
+        }
+        if fixture.start_address == 0 || fixture.end_address() > 512 {
+            return Err(ControlError::DmxError(format!(
+                "Fixture '{}' at {}-{} does not fit in a universe (1-512)",
+                fixture.name,
+                fixture.start_address,
+                fixture.end_address()
+            )));
+        }
+        if self.patch.iter().any(|f| f.id == fixture.id) {
+            return Err(ControlError::DmxError(format!(
+                "Fixture id {} is already patched",
+                fixture.id
+            )));
+        }
+        if let Some(other) = self.patch.iter().find(|f| {
+            f.universe == fixture.universe
+                && f.start_address <= fixture.end_address()
+                && fixture.start_address <= f.end_address()
+        }) {
+            return Err(ControlError::DmxError(format!(
+                "Fixture '{}' overlaps '{}' on universe {}",
+                fixture.name, other.name, fixture.universe
+            )));
+        }
+
+        self.universes.entry(fixture.universe).or_insert([0; 512]);
+        self.patch.push(fixture);
+        Ok(())
+    }
+
+    /// Remove a fixture from the patch
+    pub fn unpatch(&mut self, id: u32) -> Option<Fixture> {
+        let index = self.patch.iter().position(|f| f.id == id)?;
+        Some(self.patch.remove(index))
+    }
+
+    /// Get the patched fixtures
+    pub fn fixtures(&self) -> &[Fixture] {
+        &self.patch
+    }
+
+    /// Get a patched fixture by id
+    pub fn fixture(&self, id: u32) -> Option<&Fixture> {
+        self.patch.iter().find(|f| f.id == id)
+    }
+
+    /// Set the color of a patched fixture
+    pub fn set_rgb(&mut self, id: u32, r: u8, g: u8, b: u8) -> Result<()> {
+        let fixture = self
+            .fixture(id)
+            .cloned()
+            .ok_or_else(|| ControlError::DmxError(format!("Fixture {} is not patched", id)))?;
+        fixture.set_rgb(self.universe_mut(fixture.universe), r, g, b);
+        Ok(())
+    }
+
+    /// Set the dimmer of a patched fixture
+    pub fn set_dimmer(&mut self, id: u32, value: u8) -> Result<()> {
+        let fixture = self
+            .fixture(id)
+            .cloned()
+            .ok_or_else(|| ControlError::DmxError(format!("Fixture {} is not patched", id)))?;
+        fixture.set_dimmer(self.universe_mut(fixture.universe), value);
+        Ok(())
+    }
+
+    /// Get the engine's own channel data for a universe, creating it if needed
+    pub fn universe_mut(&mut self, universe: u16) -> &mut [u8; 512] {
+        self.universes.entry(universe).or_insert([0; 512])
+    }
+
+    /// Get the engine's own channel data for a universe
+    pub fn universe(&self, universe: u16) -> Option<&[u8; 512]> {
+        self.universes.get(&universe)
+    }
+
+    /// Add or replace a named source merged on top of the engine's data
+    pub fn set_source(&mut self, name: &str, mode: MergeMode, universes: UniverseMap) {
+        match self.sources.iter_mut().find(|s| s.name == name) {
+            Some(source) => {
+                source.mode = mode;
+                source.universes = universes;
+            }
+            None => self.sources.push(DmxSource {
+                name: name.to_string(),
+                mode,
+                universes,
+            }),
+        }
+    }
+
+    /// Remove a named source
+    pub fn remove_source(&mut self, name: &str) {
+        self.sources.retain(|s| s.name != name);
+    }
+
+    /// Merge the engine's data with every source, in the order they were added
+    pub fn merged(&self) -> UniverseMap {
+        let mut merged = self.universes.clone();
+        for source in &self.sources {
+            for (id, channels) in &source.universes {
+                let target = merged.entry(*id).or_insert([0; 512]);
+                match source.mode {
+                    MergeMode::Htp => {
+                        for (out, value) in target.iter_mut().zip(channels) {
+                            *out = (*out).max(*value);
+                        }
+                    }
+                    MergeMode::Ltp => *target = *channels,
+                }
+            }
+        }
+        merged
+    }
+
+    /// Publish the current merged state to the output thread
+    pub fn commit(&mut self) {
+        let merged = self.merged();
+        *self.frame.lock().unwrap_or_else(|e| e.into_inner()) = merged;
+    }
+
+    /// Merge and send once on the calling thread (when not running)
+    pub fn send_now(&mut self) -> Result<()> {
+        let merged = self.merged();
+        self.lock_outputs().send(&merged)
+    }
+
+    /// Start sending at the refresh rate on a background thread
+    pub fn start(&mut self) -> Result<()> {
+        if self.is_running() {
+            return Ok(());
+        }
+        self.commit();
+        self.running.store(true, Ordering::Release);
+
+        let frame = self.frame.clone();
+        let outputs = self.outputs.clone();
+        let running = self.running.clone();
+        let interval = Duration::from_secs_f64(1.0 / self.refresh_rate as f64);
+
+        let handle = thread::Builder::new()
+            .name("dmx-output".to_string())
+            .spawn(move || {
+                let start = Instant::now();
+                let mut tick: u32 = 0;
+                while running.load(Ordering::Acquire) {
+                    let universes = frame.lock().unwrap_or_else(|e| e.into_inner()).clone();
+                    let result = outputs
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .send(&universes);
+                    if let Err(e) = result {
+                        tracing::warn!("DMX output failed: {}", e);
+                    }
+
+                    // Schedule against the start time so the rate does not drift
+                    tick = tick.wrapping_add(1);
+                    let deadline = start + interval * tick;
+                    let now = Instant::now();
+                    if deadline > now {
+                        thread::sleep(deadline - now);
+                    } else {
+                        // Fell behind: skip missed refreshes
+                        tick = (now.duration_since(start).as_secs_f64() / interval.as_secs_f64())
+                            as u32;
+                    }
+                }
+            })
+            .map_err(|e| {
+                self.running.store(false, Ordering::Release);
+                ControlError::DmxError(format!("Failed to start DMX output thread: {}", e))
+            })?;
+
+        self.thread = Some(handle);
+        tracing::info!("DMX engine started at {} Hz", self.refresh_rate);
+        Ok(())
+    }
+
+    /// Stop the output thread
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        if let Some(handle) = self.thread.take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// Check if the output thread is running
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+}
+
+impl Default for DmxEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for DmxEngine {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmx::FixtureProfile;
+
+    fn par(id: u32, universe: u16, start_address: u16) -> Fixture {
+        Fixture::new(
+            id,
+            format!("Par {}", id),
+            FixtureProfile::rgb_par(),
+            universe,
+            start_address,
+        )
+    }
+
+    #[test]
+    fn test_patch_overlap() {
+        let mut engine = DmxEngine::new();
+        engine.patch(par(1, 0, 1)).unwrap();
+        let end = engine.fixture(1).unwrap().end_address();
+
+        // Overlapping the first fixture on the same universe
+        assert!(engine.patch(par(2, 0, end)).is_err());
+        // Same addresses on another universe are fine
+        engine.patch(par(2, 1, 1)).unwrap();
+        // Adjacent is fine
+        engine.patch(par(3, 0, end + 1)).unwrap();
+        // Duplicate id
+        assert!(engine.patch(par(3, 2, 1)).is_err());
+        // Past the end of the universe
+        assert!(engine.patch(par(4, 0, 512)).is_err());
+
+        assert!(engine.unpatch(1).is_some());
+        engine.patch(par(5, 0, 1)).unwrap();
+        assert_eq!(engine.fixtures().len(), 3);
+    }
+
+    #[test]
+    fn test_merge_sources() {
+        let mut engine = DmxEngine::new();
+        engine.patch(par(1, 0, 1)).unwrap();
+        engine.set_rgb(1, 200, 10, 0).unwrap();
+
+        let mut pixels = [0u8; 512];
+        pixels[0] = 50;
+        pixels[1] = 100;
+        engine.set_source("pixels", MergeMode::Htp, HashMap::from([(0, pixels)]));
+        let merged = engine.merged();
+        assert_eq!(&merged[&0][..3], &[200, 100, 0]);
+
+        engine.set_source("pixels", MergeMode::Ltp, HashMap::from([(0, pixels)]));
+        assert_eq!(&engine.merged()[&0][..3], &[50, 100, 0]);
+
+        engine.remove_source("pixels");
+        assert_eq!(&engine.merged()[&0][..3], &[200, 10, 0]);
+    }
+
+    #[test]
+    fn test_send_continues_after_error() {
+        let node = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        node.set_read_timeout(Some(Duration::from_millis(500)))
+            .unwrap();
+        let node_addr = node.local_addr().unwrap();
+
+        let mut engine = DmxEngine::new();
+        engine.enable_artnet("127.0.0.1:6454").unwrap();
+        engine.set_sync(true, 0).unwrap();
+        // An IPv4 socket cannot send to the IPv6 node, which must not keep
+        // universe 2 and the sync packet from going out
+        let unreachable: SocketAddr = "[::1]:6454".parse().unwrap();
+        engine.set_artnet_routes(Some(HashMap::from([
+            (1, vec![unreachable]),
+            (2, vec![node_addr]),
+        ])));
+        engine.universe_mut(1);
+        engine.universe_mut(2);
+        assert!(engine.send_now().is_err());
+
+        let mut buf = [0u8; 600];
+        let (len, _) = node.recv_from(&mut buf).unwrap();
+        assert_eq!(len, 18 + 512);
+        assert_eq!(u16::from_le_bytes([buf[14], buf[15]]), 2);
+        let (len, _) = node.recv_from(&mut buf).unwrap();
+        assert_eq!(len, 14);
+        assert_eq!(buf[9], 0x52);
+    }
+
+    #[test]
+    fn test_start_stop() {
+        let mut engine = DmxEngine::new().with_refresh_rate(100);
+        engine.start().unwrap();
+        assert!(engine.is_running());
+        engine.commit();
+        engine.stop();
+        assert!(!engine.is_running());
+    }
+}
@@ -7,6 +7,9 @@ use serde::{Deserialize, Serialize};
 pub struct FixtureProfile {
     pub name: String,
     pub manufacturer: String,
+    /// Mode name for fixtures with several channel layouts
+    #[serde(default)]
+    pub mode: String,
     pub channels: Vec<FixtureChannel>,
 }
 
@@ -16,6 +19,57 @@ pub struct FixtureChannel {
     pub name: String,
     pub channel_type: ChannelType,
     pub default_value: u8,
+    /// Index of the channel carrying the low byte of a 16-bit value
+    #[serde(default)]
+    pub fine_channel: Option<usize>,
+    /// This channel is the low byte of another channel
+    #[serde(default)]
+    pub is_fine: bool,
+    /// Named DMX ranges such as wheel slots or strobe speeds
+    #[serde(default)]
+    pub capabilities: Vec<ChannelCapability>,
+}
+
+impl FixtureChannel {
+    /// Create an 8-bit channel without capabilities
+    pub fn new(name: &str, channel_type: ChannelType) -> Self {
+        Self {
+            name: name.to_string(),
+            channel_type,
+            default_value: 0,
+            fine_channel: None,
+            is_fine: false,
+            capabilities: Vec::new(),
+        }
+    }
+
+    /// Find the capability covering a DMX value
+    pub fn capability_at(&self, value: u8) -> Option<&ChannelCapability> {
+        self.capabilities
+            .iter()
+            .find(|c| (c.dmx_start..=c.dmx_end).contains(&value))
+    }
+
+    /// Find the capability selecting a wheel slot
+    pub fn wheel_slot(&self, slot: u32) -> Option<&ChannelCapability> {
+        self.capabilities
+            .iter()
+            .find(|c| c.wheel_slot == Some(slot))
+    }
+}
+
+/// A named DMX range of a channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelCapability {
+    pub dmx_start: u8,
+    pub dmx_end: u8,
+    pub name: String,
+    /// Wheel slot selected by this range (1-based)
+    #[serde(default)]
+    pub wheel_slot: Option<u32>,
+    /// Color shown by this range, e.g. a color wheel filter
+    #[serde(default)]
+    pub color: Option<[u8; 3]>,
 }
 
 /// Type of DMX channel
@@ -27,11 +81,19 @@ pub enum ChannelType {
     Blue,
     Amber,
     White,
+    Cyan,
+    Magenta,
+    Yellow,
+    Uv,
     Pan,
     Tilt,
     ColorWheel,
     Gobo,
+    Prism,
     Shutter,
+    Zoom,
+    Focus,
+    Iris,
     Speed,
     Generic,
 }
@@ -42,11 +104,8 @@ impl FixtureProfile {
         Self {
             name: "Generic Dimmer".to_string(),
             manufacturer: "Generic".to_string(),
-            channels: vec![FixtureChannel {
-                name: "Dimmer".to_string(),
-                channel_type: ChannelType::Dimmer,
-                default_value: 0,
-            }],
+            mode: String::new(),
+            channels: vec![FixtureChannel::new("Dimmer", ChannelType::Dimmer)],
         }
     }
 
@@ -55,22 +114,11 @@ impl FixtureProfile {
         Self {
             name: "RGB Par".to_string(),
             manufacturer: "Generic".to_string(),
+            mode: String::new(),
             channels: vec![
-                FixtureChannel {
-                    name: "Red".to_string(),
-                    channel_type: ChannelType::Red,
-                    default_value: 0,
-                },
-                FixtureChannel {
-                    name: "Green".to_string(),
-                    channel_type: ChannelType::Green,
-                    default_value: 0,
-                },
-                FixtureChannel {
-                    name: "Blue".to_string(),
-                    channel_type: ChannelType::Blue,
-                    default_value: 0,
-                },
+                FixtureChannel::new("Red", ChannelType::Red),
+                FixtureChannel::new("Green", ChannelType::Green),
+                FixtureChannel::new("Blue", ChannelType::Blue),
             ],
         }
     }
@@ -80,27 +128,12 @@ impl FixtureProfile {
         Self {
             name: "RGBA Par".to_string(),
             manufacturer: "Generic".to_string(),
+            mode: String::new(),
             channels: vec![
-                FixtureChannel {
-                    name: "Red".to_string(),
-                    channel_type: ChannelType::Red,
-                    default_value: 0,
-                },
-                FixtureChannel {
-                    name: "Green".to_string(),
-                    channel_type: ChannelType::Green,
-                    default_value: 0,
-                },
-                FixtureChannel {
-                    name: "Blue".to_string(),
-                    channel_type: ChannelType::Blue,
-                    default_value: 0,
-                },
-                FixtureChannel {
-                    name: "Amber".to_string(),
-                    channel_type: ChannelType::Amber,
-                    default_value: 0,
-                },
+                FixtureChannel::new("Red", ChannelType::Red),
+                FixtureChannel::new("Green", ChannelType::Green),
+                FixtureChannel::new("Blue", ChannelType::Blue),
+                FixtureChannel::new("Amber", ChannelType::Amber),
             ],
         }
     }
@@ -110,27 +143,12 @@ impl FixtureProfile {
         Self {
             name: "RGBW Par".to_string(),
             manufacturer: "Generic".to_string(),
+            mode: String::new(),
             channels: vec![
-                FixtureChannel {
-                    name: "Red".to_string(),
-                    channel_type: ChannelType::Red,
-                    default_value: 0,
-                },
-                FixtureChannel {
-                    name: "Green".to_string(),
-                    channel_type: ChannelType::Green,
-                    default_value: 0,
-                },
-                FixtureChannel {
-                    name: "Blue".to_string(),
-                    channel_type: ChannelType::Blue,
-                    default_value: 0,
-                },
-                FixtureChannel {
-                    name: "White".to_string(),
-                    channel_type: ChannelType::White,
-                    default_value: 0,
-                },
+                FixtureChannel::new("Red", ChannelType::Red),
+                FixtureChannel::new("Green", ChannelType::Green),
+                FixtureChannel::new("Blue", ChannelType::Blue),
+                FixtureChannel::new("White", ChannelType::White),
             ],
         }
     }
@@ -182,15 +200,61 @@ impl Fixture {
         value: u8,
     ) {
         for (i, channel) in self.profile.channels.iter().enumerate() {
-            if channel.channel_type == channel_type {
-                let addr = (self.start_address as usize + i).saturating_sub(1);
-                if addr < 512 {
-                    dmx_data[addr] = value;
+            if channel.channel_type == channel_type && !channel.is_fine {
+                self.write(dmx_data, i, value);
+            }
+        }
+    }
+
+    /// Set a 16-bit channel value by type
+    ///
+    /// 8-bit channels of the type receive the high byte.
+    pub fn set_channel_value_16(
+        &self,
+        dmx_data: &mut [u8; 512],
+        channel_type: ChannelType,
+        value: u16,
+    ) {
+        for (i, channel) in self.profile.channels.iter().enumerate() {
+            if channel.channel_type == channel_type && !channel.is_fine {
+                self.write(dmx_data, i, (value >> 8) as u8);
+                if let Some(fine) = channel.fine_channel {
+                    self.write(dmx_data, fine, value as u8);
                 }
             }
         }
     }
 
+    /// Select a wheel slot on channels of the given type
+    ///
+    /// Returns false if no such channel has the slot.
+    pub fn set_wheel_slot(
+        &self,
+        dmx_data: &mut [u8; 512],
+        channel_type: ChannelType,
+        slot: u32,
+    ) -> bool {
+        let mut found = false;
+        for (i, channel) in self.profile.channels.iter().enumerate() {
+            if channel.channel_type != channel_type {
+                continue;
+            }
+            if let Some(capability) = channel.wheel_slot(slot) {
+                self.write(dmx_data, i, capability.dmx_start);
+                found = true;
+            }
+        }
+        found
+    }
+
+    /// Write the value of the channel at `index` in the profile
+    fn write(&self, dmx_data: &mut [u8; 512], index: usize, value: u8) {
+        let addr = (self.start_address as usize + index).saturating_sub(1);
+        if addr < 512 {
+            dmx_data[addr] = value;
+        }
+    }
+
     /// Set RGB values
     pub fn set_rgb(&self, dmx_data: &mut [u8; 512], r: u8, g: u8, b: u8) {
         self.set_channel_value(dmx_data, ChannelType::Red, r);
@@ -257,4 +321,39 @@ mod tests {
         assert_eq!(dmx_data[1], 128); // Green
         assert_eq!(dmx_data[2], 64); // Blue
     }
+
+    #[test]
+    fn test_fixture_16bit_and_wheel_slot() {
+        let mut pan = FixtureChannel::new("Pan", ChannelType::Pan);
+        pan.fine_channel = Some(1);
+        let mut pan_fine = FixtureChannel::new("Pan fine", ChannelType::Pan);
+        pan_fine.is_fine = true;
+        let mut wheel = FixtureChannel::new("Color", ChannelType::ColorWheel);
+        wheel.capabilities.push(ChannelCapability {
+            dmx_start: 20,
+            dmx_end: 29,
+            name: "Blue".to_string(),
+            wheel_slot: Some(3),
+            color: Some([0, 0, 255]),
+        });
+        let profile = FixtureProfile {
+            name: "Spot".to_string(),
+            manufacturer: "Generic".to_string(),
+            mode: "Extended".to_string(),
+            channels: vec![pan, pan_fine, wheel],
+        };
+        let fixture = Fixture::new(0, "Spot".to_string(), profile, 0, 10);
+
+        let mut dmx_data = [0u8; 512];
+        fixture.set_channel_value_16(&mut dmx_data, ChannelType::Pan, 0x1234);
+        assert_eq!(&dmx_data[9..11], &[0x12, 0x34]);
+
+        // 8-bit writes leave the fine byte alone
+        fixture.set_channel_value(&mut dmx_data, ChannelType::Pan, 0xFF);
+        assert_eq!(&dmx_data[9..11], &[0xFF, 0x34]);
+
+        assert!(fixture.set_wheel_slot(&mut dmx_data, ChannelType::ColorWheel, 3));
+        assert_eq!(dmx_data[11], 20);
+        assert!(!fixture.set_wheel_slot(&mut dmx_data, ChannelType::ColorWheel, 9));
+    }
 }
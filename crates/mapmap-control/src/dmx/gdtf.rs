@@ -0,0 +1,388 @@
+//! GDTF fixture import
+//!
+//! A `.gdtf` file is a zip archive whose `description.xml` declares the
+//! fixture's wheels and DMX modes. Each DMX channel lists its byte offsets
+//! (two or more for 16-bit channels) and a set of channel functions whose
+//! DMX ranges become the channel's capabilities. Only the first DMX break is
+//! imported.
+
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+use roxmltree::{Document, Node};
+
+use super::{ChannelCapability, ChannelType, FixtureChannel, FixtureDefinition, FixtureProfile};
+use crate::{error::ControlError, Result};
+
+/// Wheel slots by wheel name: (slot name, slot color)
+type Wheels<'a> = HashMap<&'a str, Vec<(&'a str, Option<[u8; 3]>)>>;
+
+/// Parse a `.gdtf` archive
+pub fn parse_archive<R: Read + Seek>(reader: R) -> Result<FixtureDefinition> {
+    let mut archive = zip::ZipArchive::new(reader)
+        .map_err(|e| ControlError::DmxError(format!("Invalid GDTF archive: {}", e)))?;
+    let mut description = archive
+        .by_name("description.xml")
+        .map_err(|e| ControlError::DmxError(format!("GDTF archive has no description: {}", e)))?;
+    let mut xml = String::new();
+    description.read_to_string(&mut xml)?;
+    parse(&xml)
+}
+
+/// Parse a GDTF `description.xml`
+pub fn parse(xml: &str) -> Result<FixtureDefinition> {
+    let document = Document::parse(xml)
+        .map_err(|e| ControlError::DmxError(format!("Invalid GDTF description: {}", e)))?;
+    let fixture_type = document
+        .descendants()
+        .find(|n| n.has_tag_name("FixtureType"))
+        .ok_or_else(|| ControlError::DmxError("GDTF description has no FixtureType".to_string()))?;
+
+    let name = fixture_type.attribute("Name").unwrap_or("Unnamed");
+    let manufacturer = fixture_type.attribute("Manufacturer").unwrap_or("Unknown");
+
+    let mut wheels: Wheels = HashMap::new();
+    for wheel in fixture_type
+        .descendants()
+        .filter(|n| n.has_tag_name("Wheel"))
+    {
+        let slots = elements(wheel, "Slot")
+            .map(|slot| {
+                (
+                    slot.attribute("Name").unwrap_or(""),
+                    slot.attribute("Color").and_then(cie_to_rgb),
+                )
+            })
+            .collect();
+        wheels.insert(wheel.attribute("Name").unwrap_or(""), slots);
+    }
+
+    let modes: Vec<FixtureProfile> = fixture_type
+        .descendants()
+        .filter(|n| n.has_tag_name("DMXMode"))
+        .map(|mode| FixtureProfile {
+            name: name.to_string(),
+            manufacturer: manufacturer.to_string(),
+            mode: mode.attribute("Name").unwrap_or("Default").to_string(),
+            channels: build_mode(mode, &wheels),
+        })
+        .filter(|profile| !profile.channels.is_empty())
+        .collect();
+
+    if modes.is_empty() {
+        return Err(ControlError::DmxError(format!(
+            "GDTF fixture '{}' has no DMX modes",
+            name
+        )));
+    }
+
+    Ok(FixtureDefinition {
+        manufacturer: manufacturer.to_string(),
+        name: name.to_string(),
+        modes,
+        source: None,
+    })
+}
+
+fn elements<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    tag: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |n| n.has_tag_name(tag))
+}
+
+fn build_mode(mode: Node, wheels: &Wheels) -> Vec<FixtureChannel> {
+    // Offset (1-based) -> channel
+    let mut slots: HashMap<usize, FixtureChannel> = HashMap::new();
+
+    for channel in mode.descendants().filter(|n| n.has_tag_name("DMXChannel")) {
+        if channel.attribute("DMXBreak").unwrap_or("1") != "1" {
+            continue;
+        }
+        let offsets: Vec<usize> = channel
+            .attribute("Offset")
+            .unwrap_or("")
+            .split(',')
+            .filter_map(|o| o.trim().parse().ok())
+            .filter(|o| *o >= 1)
+            .collect();
+        let Some(&coarse) = offsets.first() else {
+            // Virtual channel without a DMX address
+            continue;
+        };
+
+        let logical = channel
+            .descendants()
+            .find(|n| n.has_tag_name("LogicalChannel"));
+        let functions: Vec<Node> = channel
+            .descendants()
+            .filter(|n| n.has_tag_name("ChannelFunction"))
+            .collect();
+        let attribute = logical
+            .and_then(|l| l.attribute("Attribute"))
+            .or_else(|| functions.first().and_then(|f| f.attribute("Attribute")))
+            .unwrap_or("Channel");
+        let channel_type = attribute_type(attribute);
+
+        let mut result = FixtureChannel::new(attribute, channel_type);
+        result.default_value = channel
+            .attribute("Default")
+            .or_else(|| functions.first().and_then(|f| f.attribute("Default")))
+            .and_then(dmx_value)
+            .unwrap_or(0);
+        result.capabilities = capabilities(&functions, wheels);
+        if let Some(&fine) = offsets.get(1) {
+            result.fine_channel = Some(fine - 1);
+        }
+        slots.insert(coarse, result);
+
+        for &offset in offsets.iter().skip(1) {
+            let mut fine = FixtureChannel::new(&format!("{} fine", attribute), channel_type);
+            fine.is_fine = true;
+            slots.insert(offset, fine);
+        }
+    }
+
+    let count = slots.keys().max().copied().unwrap_or(0);
+    (1..=count)
+        .map(|offset| {
+            slots
+                .remove(&offset)
+                .unwrap_or_else(|| FixtureChannel::new("Unused", ChannelType::Generic))
+        })
+        .collect()
+}
+
+/// Turn channel functions and their channel sets into named DMX ranges
+fn capabilities(functions: &[Node], wheels: &Wheels) -> Vec<ChannelCapability> {
+    // Ranges run until the next one starts
+    let mut entries: Vec<ChannelCapability> = Vec::new();
+
+    for function in functions {
+        let function_name = function
+            .attribute("Name")
+            .or_else(|| function.attribute("Attribute"))
+            .unwrap_or("");
+        let wheel = function.attribute("Wheel").and_then(|w| wheels.get(w));
+        let start = function
+            .attribute("DMXFrom")
+            .and_then(dmx_value)
+            .unwrap_or(0);
+
+        let sets: Vec<Node> = elements(*function, "ChannelSet").collect();
+        if sets.is_empty() {
+            entries.push(ChannelCapability {
+                dmx_start: start,
+                dmx_end: 255,
+                name: function_name.to_string(),
+                wheel_slot: None,
+                color: None,
+            });
+            continue;
+        }
+        for set in sets {
+            let slot = set
+                .attribute("WheelSlotIndex")
+                .and_then(|i| i.parse::<u32>().ok())
+                .filter(|i| *i >= 1);
+            let slot_info = slot.and_then(|i| wheel?.get(i as usize - 1));
+            let name = match set.attribute("Name") {
+                Some(name) if !name.is_empty() => name,
+                _ => slot_info.map(|s| s.0).unwrap_or(function_name),
+            };
+            entries.push(ChannelCapability {
+                dmx_start: set
+                    .attribute("DMXFrom")
+                    .and_then(dmx_value)
+                    .unwrap_or(start),
+                dmx_end: 255,
+                name: name.to_string(),
+                wheel_slot: slot,
+                color: slot_info.and_then(|s| s.1),
+            });
+        }
+    }
+
+    entries.sort_by_key(|c| c.dmx_start);
+    let starts: Vec<u8> = entries.iter().map(|c| c.dmx_start).collect();
+    let mut capabilities = Vec::with_capacity(entries.len());
+    for (i, mut capability) in entries.into_iter().enumerate() {
+        if let Some(&next) = starts.get(i + 1) {
+            // Ranges narrower than one coarse step are dropped
+            if next <= capability.dmx_start {
+                continue;
+            }
+            capability.dmx_end = next - 1;
+        }
+        capabilities.push(capability);
+    }
+    capabilities
+}
+
+/// Parse a GDTF DMX value ("value/bytes") as a coarse 8-bit value
+fn dmx_value(value: &str) -> Option<u8> {
+    let (value, bytes) = value.split_once('/')?;
+    let value: u64 = value.trim().parse().ok()?;
+    let bytes: u32 = bytes.trim().parse().ok()?;
+    let shift = 8 * bytes.clamp(1, 4).saturating_sub(1);
+    Some((value >> shift).min(255) as u8)
+}
+
+fn attribute_type(attribute: &str) -> ChannelType {
+    match attribute {
+        "Dimmer" => return ChannelType::Dimmer,
+        "Pan" => return ChannelType::Pan,
+        "Tilt" => return ChannelType::Tilt,
+        "ColorAdd_R" | "ColorRGB_Red" => return ChannelType::Red,
+        "ColorAdd_G" | "ColorRGB_Green" => return ChannelType::Green,
+        "ColorAdd_B" | "ColorRGB_Blue" => return ChannelType::Blue,
+        "ColorAdd_A" => return ChannelType::Amber,
+        "ColorAdd_W" | "ColorAdd_WW" | "ColorAdd_CW" => return ChannelType::White,
+        "ColorAdd_C" | "ColorSub_C" => return ChannelType::Cyan,
+        "ColorAdd_M" | "ColorSub_M" => return ChannelType::Magenta,
+        "ColorAdd_Y" | "ColorSub_Y" => return ChannelType::Yellow,
+        "ColorAdd_UV" => return ChannelType::Uv,
+        "Zoom" => return ChannelType::Zoom,
+        _ => {}
+    }
+
+    let wheel_number = |prefix: &str| {
+        attribute
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+    };
+    if wheel_number("Color") {
+        ChannelType::ColorWheel
+    } else if wheel_number("Gobo") {
+        ChannelType::Gobo
+    } else if wheel_number("Prism") {
+        ChannelType::Prism
+    } else if attribute.starts_with("Shutter") || attribute.starts_with("StrobeFrequency") {
+        ChannelType::Shutter
+    } else if wheel_number("Focus") {
+        ChannelType::Focus
+    } else if attribute.starts_with("Iris") {
+        ChannelType::Iris
+    } else if attribute.contains("Speed") {
+        ChannelType::Speed
+    } else {
+        ChannelType::Generic
+    }
+}
+
+/// Convert a GDTF color ("x,y,Y" in CIE 1931) to sRGB
+fn cie_to_rgb(color: &str) -> Option<[u8; 3]> {
+    let mut parts = color.split(',').map(|p| p.trim().parse::<f32>());
+    let (x, y, luminance) = (
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+    );
+    if y <= 0.0 {
+        return Some([0, 0, 0]);
+    }
+
+    let big_y = luminance / 100.0;
+    let big_x = x * big_y / y;
+    let big_z = (1.0 - x - y) * big_y / y;
+
+    let linear = [
+        3.2406 * big_x - 1.5372 * big_y - 0.4986 * big_z,
+        -0.9689 * big_x + 1.8758 * big_y + 0.0415 * big_z,
+        0.0557 * big_x - 0.2040 * big_y + 1.0570 * big_z,
+    ];
+    Some(linear.map(|c| {
+        let c = c.clamp(0.0, 1.0);
+        let encoded = if c <= 0.003_130_8 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        };
+        (encoded * 255.0).round() as u8
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESCRIPTION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<GDTF DataVersion="1.1">
+  <FixtureType Name="Wash 7" Manufacturer="Acme" ShortName="W7">
+    <Wheels>
+      <Wheel Name="Color1">
+        <Slot Name="Open" Color="0.3127,0.3290,100.0"/>
+        <Slot Name="Deep Red" Color="0.6400,0.3300,21.26"/>
+      </Wheel>
+    </Wheels>
+    <DMXModes>
+      <DMXMode Name="Standard" Geometry="Body">
+        <DMXChannels>
+          <DMXChannel DMXBreak="1" Offset="1,2" Geometry="Yoke">
+            <LogicalChannel Attribute="Pan">
+              <ChannelFunction Name="Pan" Attribute="Pan" DMXFrom="0/1" Default="32768/2"/>
+            </LogicalChannel>
+          </DMXChannel>
+          <DMXChannel DMXBreak="1" Offset="4" Geometry="Head">
+            <LogicalChannel Attribute="Color1">
+              <ChannelFunction Name="Color1" Attribute="Color1" DMXFrom="0/1" Wheel="Color1">
+                <ChannelSet Name="" DMXFrom="0/1" WheelSlotIndex="1"/>
+                <ChannelSet Name="" DMXFrom="16/1" WheelSlotIndex="2"/>
+              </ChannelFunction>
+              <ChannelFunction Name="Spin" Attribute="Color1WheelSpin" DMXFrom="128/1"/>
+            </LogicalChannel>
+          </DMXChannel>
+          <DMXChannel DMXBreak="1" Offset="None">
+            <LogicalChannel Attribute="Dimmer"/>
+          </DMXChannel>
+          <DMXChannel DMXBreak="2" Offset="1">
+            <LogicalChannel Attribute="Dimmer"/>
+          </DMXChannel>
+        </DMXChannels>
+      </DMXMode>
+    </DMXModes>
+  </FixtureType>
+</GDTF>"#;
+
+    #[test]
+    fn test_parse_description() {
+        let definition = parse(DESCRIPTION).unwrap();
+        assert_eq!(definition.manufacturer, "Acme");
+        assert_eq!(definition.name, "Wash 7");
+
+        let mode = definition.mode("Standard").unwrap();
+        // Pan, Pan fine, unused gap, Color1
+        assert_eq!(mode.channel_count(), 4);
+        assert_eq!(mode.channels[0].channel_type, ChannelType::Pan);
+        assert_eq!(mode.channels[0].default_value, 128);
+        assert_eq!(mode.channels[0].fine_channel, Some(1));
+        assert!(mode.channels[1].is_fine);
+        assert_eq!(mode.channels[2].channel_type, ChannelType::Generic);
+
+        let wheel = &mode.channels[3];
+        assert_eq!(wheel.channel_type, ChannelType::ColorWheel);
+        let red = wheel.wheel_slot(2).unwrap();
+        assert_eq!((red.dmx_start, red.dmx_end), (16, 127));
+        assert_eq!(red.name, "Deep Red");
+        assert_eq!(red.color, Some([255, 0, 0]));
+        assert_eq!(wheel.capability_at(200).unwrap().name, "Spin");
+    }
+
+    #[test]
+    fn test_parse_archive() {
+        use std::io::{Cursor, Write};
+
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buffer);
+            zip.start_file("description.xml", zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(DESCRIPTION.as_bytes()).unwrap();
+            zip.finish().unwrap();
+        }
+        buffer.set_position(0);
+
+        let definition = parse_archive(buffer).unwrap();
+        assert_eq!(definition.modes.len(), 1);
+    }
+}
@@ -0,0 +1,194 @@
+//! Fixture library
+//!
+//! Loads fixture definitions from a local directory of Open Fixture Library
+//! JSON files and GDTF files, and searches them by manufacturer and name.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{gdtf, ofl, FixtureProfile};
+use crate::{error::ControlError, Result};
+
+/// A fixture type with all of its DMX modes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureDefinition {
+    pub manufacturer: String,
+    pub name: String,
+    /// One profile per DMX mode
+    pub modes: Vec<FixtureProfile>,
+    /// File the definition was loaded from
+    #[serde(default)]
+    pub source: Option<PathBuf>,
+}
+
+impl FixtureDefinition {
+    /// Get a mode by name (case-insensitive)
+    pub fn mode(&self, name: &str) -> Option<&FixtureProfile> {
+        self.modes
+            .iter()
+            .find(|m| m.mode.eq_ignore_ascii_case(name))
+    }
+
+    /// Load a definition from an OFL `.json`, a `.gdtf` archive or a GDTF
+    /// `description.xml`
+    ///
+    /// OFL files do not name their manufacturer; it is taken from the name
+    /// of the containing directory, as in the OFL repository layout.
+    pub fn load(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        let mut definition = match extension.as_deref() {
+            Some("json") => {
+                let manufacturer = path
+                    .parent()
+                    .and_then(|p| p.file_name())
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("Unknown");
+                ofl::parse(&fs::read_to_string(path)?, manufacturer)?
+            }
+            Some("gdtf") => gdtf::parse_archive(fs::File::open(path)?)?,
+            Some("xml") => gdtf::parse(&fs::read_to_string(path)?)?,
+            _ => {
+                return Err(ControlError::DmxError(format!(
+                    "Unsupported fixture file: {}",
+                    path.display()
+                )))
+            }
+        };
+        definition.source = Some(path.to_path_buf());
+        Ok(definition)
+    }
+}
+
+/// A searchable collection of fixture definitions
+#[derive(Debug, Clone, Default)]
+pub struct FixtureLibrary {
+    definitions: Vec<FixtureDefinition>,
+}
+
+impl FixtureLibrary {
+    /// Create an empty library
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every fixture file below `dir`
+    ///
+    /// Files that fail to parse are logged and skipped.
+    pub fn load_dir(dir: &Path) -> Result<Self> {
+        let mut library = Self::new();
+        library.scan(dir)?;
+        library.definitions.sort_by(|a, b| {
+            (a.manufacturer.to_lowercase(), a.name.to_lowercase())
+                .cmp(&(b.manufacturer.to_lowercase(), b.name.to_lowercase()))
+        });
+        tracing::info!(
+            "Loaded {} fixture definitions from {}",
+            library.definitions.len(),
+            dir.display()
+        );
+        Ok(library)
+    }
+
+    fn scan(&mut self, dir: &Path) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.scan(&path)?;
+                continue;
+            }
+            if !is_fixture_file(&path) {
+                continue;
+            }
+            match FixtureDefinition::load(&path) {
+                Ok(definition) => self.definitions.push(definition),
+                Err(e) => tracing::warn!("Skipping fixture {}: {}", path.display(), e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a definition
+    pub fn add(&mut self, definition: FixtureDefinition) {
+        self.definitions.push(definition);
+    }
+
+    /// Get all definitions
+    pub fn definitions(&self) -> &[FixtureDefinition] {
+        &self.definitions
+    }
+
+    /// Find definitions whose manufacturer or name contain every word of
+    /// `query` (case-insensitive)
+    pub fn search(&self, query: &str) -> Vec<&FixtureDefinition> {
+        let terms: Vec<String> = query.split_whitespace().map(|t| t.to_lowercase()).collect();
+        self.definitions
+            .iter()
+            .filter(|d| {
+                let haystack = format!("{} {}", d.manufacturer, d.name).to_lowercase();
+                terms.iter().all(|t| haystack.contains(t.as_str()))
+            })
+            .collect()
+    }
+
+    /// Get a definition by exact manufacturer and name (case-insensitive)
+    pub fn find(&self, manufacturer: &str, name: &str) -> Option<&FixtureDefinition> {
+        self.definitions.iter().find(|d| {
+            d.manufacturer.eq_ignore_ascii_case(manufacturer) && d.name.eq_ignore_ascii_case(name)
+        })
+    }
+}
+
+fn is_fixture_file(path: &Path) -> bool {
+    let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let lower = file_name.to_ascii_lowercase();
+    // OFL keeps manufacturer metadata next to the fixtures
+    if lower == "manufacturers.json" || lower == "register.json" {
+        return false;
+    }
+    lower.ends_with(".json") || lower.ends_with(".gdtf") || lower == "description.xml"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAR: &str = r#"{
+        "name": "Quad Par",
+        "availableChannels": {
+            "Red": { "capability": { "type": "ColorIntensity", "color": "Red" } },
+            "Green": { "capability": { "type": "ColorIntensity", "color": "Green" } },
+            "Blue": { "capability": { "type": "ColorIntensity", "color": "Blue" } },
+            "White": { "capability": { "type": "ColorIntensity", "color": "White" } }
+        },
+        "modes": [{ "name": "4-channel", "channels": ["Red", "Green", "Blue", "White"] }]
+    }"#;
+
+    #[test]
+    fn test_load_and_search_dir() {
+        let dir = std::env::temp_dir().join(format!("mapmap-fixtures-{}", std::process::id()));
+        let manufacturer = dir.join("acme");
+        fs::create_dir_all(&manufacturer).unwrap();
+        fs::write(manufacturer.join("quad-par.json"), PAR).unwrap();
+        fs::write(dir.join("manufacturers.json"), "{}").unwrap();
+        fs::write(manufacturer.join("broken.json"), "not json").unwrap();
+
+        let library = FixtureLibrary::load_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(library.definitions().len(), 1);
+        assert_eq!(library.search("acme par").len(), 1);
+        assert!(library.search("moving head").is_empty());
+
+        let definition = library.find("ACME", "quad par").unwrap();
+        let mode = definition.mode("4-Channel").unwrap();
+        assert_eq!(mode.channel_count(), 4);
+    }
+}
@@ -8,6 +8,8 @@
 //! - Uses UDP broadcast (255.255.255.255:6454)
 //! - Supports 32768 universes
 //! - Includes sequence numbering
+//! - [`ArtNetDiscovery`] finds nodes with ArtPoll so universes can be unicast
+//!   to the nodes that output them
 //!
 //! ## sACN (E1.31)
 //!
@@ -16,6 +18,25 @@
 //! - Supports 63999 universes
 //! - Includes priority and synchronization
 //!
+//! ## Fixture Library
+//!
+//! Besides the built-in profiles, fixture definitions can be imported from
+//! Open Fixture Library JSON and GDTF files. [`FixtureLibrary`] loads and
+//! searches a local directory of them; every DMX mode becomes a
+//! [`FixtureProfile`] with 16-bit fine channels and capability ranges.
+//!
+//! ## DMX Engine
+//!
+//! [`DmxEngine`] owns many universes, patches fixtures across them with
+//! overlap checks and sends them at a steady rate on its own thread, with
+//! ArtSync or E1.31 synchronization so large LED walls update without tearing.
+//! `ControlManager::init_artnet` and `init_sacn` start it for the application.
+//!
+//! ## Pixel Mapping
+//!
+//! [`PixelMapper`] places fixtures and LED strips on the output canvas and
+//! samples their colors from the rendered composition every frame.
+//!
 //! ## Example Usage
 //!
 //! ```rust,no_run
@@ -72,10 +93,22 @@
 
 pub mod artnet;
 pub mod channels;
+pub mod discovery;
+pub mod engine;
 pub mod fixtures;
+pub mod gdtf;
+pub mod library;
+pub mod ofl;
+pub mod pixel_map;
 pub mod sacn;
 
 pub use artnet::ArtNetSender;
 pub use channels::{ChannelAssignment, DmxChannel};
-pub use fixtures::{ChannelType, Fixture, FixtureChannel, FixtureProfile};
+pub use discovery::{ArtNetDiscovery, ArtNetNode, ArtPollReply, NodeEvent};
+pub use engine::{DmxEngine, MergeMode, UniverseMap};
+pub use fixtures::{ChannelCapability, ChannelType, Fixture, FixtureChannel, FixtureProfile};
+pub use library::{FixtureDefinition, FixtureLibrary};
+pub use pixel_map::{
+    ColorCorrection, PixelFrame, PixelMappedFixture, PixelMapper, PixelShape, SampleMode,
+};
 pub use sacn::SacnSender;
@@ -0,0 +1,392 @@
+//! Open Fixture Library (OFL) JSON import
+//!
+//! Reads the fixture format used by open-fixture-library.org: channels are
+//! declared once in `availableChannels` and every mode lists them by key,
+//! including the aliases of their fine (16-bit) bytes. Matrix fixtures that
+//! build modes from template channels are not supported.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::{ChannelCapability, ChannelType, FixtureChannel, FixtureDefinition, FixtureProfile};
+use crate::{error::ControlError, Result};
+
+/// Parse an OFL fixture file
+pub fn parse(json: &str, manufacturer: &str) -> Result<FixtureDefinition> {
+    let root: Value = serde_json::from_str(json)?;
+    let name = root
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| ControlError::DmxError("OFL fixture has no name".to_string()))?;
+
+    let empty = serde_json::Map::new();
+    let available = root
+        .get("availableChannels")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    let wheels = root
+        .get("wheels")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+
+    // Fine channel alias -> (coarse channel key, byte index)
+    let mut fine_aliases: HashMap<&str, (&str, usize)> = HashMap::new();
+    for (key, channel) in available {
+        let aliases = channel.get("fineChannelAliases").and_then(Value::as_array);
+        for (i, alias) in aliases.into_iter().flatten().enumerate() {
+            if let Some(alias) = alias.as_str() {
+                fine_aliases.insert(alias, (key.as_str(), i + 1));
+            }
+        }
+    }
+
+    let mut modes = Vec::new();
+    for mode in root
+        .get("modes")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let mode_name = mode
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("Default");
+        match build_mode(mode, available, wheels, &fine_aliases) {
+            Ok(channels) => modes.push(FixtureProfile {
+                name: name.to_string(),
+                manufacturer: manufacturer.to_string(),
+                mode: mode_name.to_string(),
+                channels,
+            }),
+            Err(e) => tracing::warn!("Skipping mode '{}' of {}: {}", mode_name, name, e),
+        }
+    }
+
+    if modes.is_empty() {
+        return Err(ControlError::DmxError(format!(
+            "OFL fixture '{}' has no supported modes",
+            name
+        )));
+    }
+
+    Ok(FixtureDefinition {
+        manufacturer: manufacturer.to_string(),
+        name: name.to_string(),
+        modes,
+        source: None,
+    })
+}
+
+fn build_mode(
+    mode: &Value,
+    available: &serde_json::Map<String, Value>,
+    wheels: &serde_json::Map<String, Value>,
+    fine_aliases: &HashMap<&str, (&str, usize)>,
+) -> Result<Vec<FixtureChannel>> {
+    let keys = mode
+        .get("channels")
+        .and_then(Value::as_array)
+        .ok_or_else(|| ControlError::DmxError("mode has no channels".to_string()))?;
+
+    let mut channels: Vec<FixtureChannel> = Vec::with_capacity(keys.len());
+    // Coarse channel key -> index in this mode
+    let mut coarse_index: HashMap<&str, usize> = HashMap::new();
+    let mut fine_links: Vec<(&str, usize)> = Vec::new();
+
+    for key in keys {
+        let key = match key {
+            Value::Null => {
+                channels.push(FixtureChannel::new("Unused", ChannelType::Generic));
+                continue;
+            }
+            Value::String(key) => key.as_str(),
+            _ => {
+                return Err(ControlError::DmxError(
+                    "matrix channel insertion is not supported".to_string(),
+                ))
+            }
+        };
+
+        if let Some(channel) = available.get(key) {
+            coarse_index.insert(key, channels.len());
+            channels.push(build_channel(key, channel, wheels));
+        } else if let Some(&(coarse, byte)) = fine_aliases.get(key) {
+            let coarse_type = available
+                .get(coarse)
+                .map(|c| channel_type(coarse, c, wheels))
+                .unwrap_or(ChannelType::Generic);
+            let mut fine = FixtureChannel::new(key, coarse_type);
+            fine.is_fine = true;
+            if byte == 1 {
+                fine_links.push((coarse, channels.len()));
+            }
+            channels.push(fine);
+        } else {
+            return Err(ControlError::DmxError(format!("unknown channel '{}'", key)));
+        }
+    }
+
+    for (coarse, fine) in fine_links {
+        if let Some(&index) = coarse_index.get(coarse) {
+            channels[index].fine_channel = Some(fine);
+        }
+    }
+    Ok(channels)
+}
+
+fn build_channel(
+    key: &str,
+    channel: &Value,
+    wheels: &serde_json::Map<String, Value>,
+) -> FixtureChannel {
+    let mut result = FixtureChannel::new(key, channel_type(key, channel, wheels));
+
+    // Values may be given in 16 or 24 bit resolution
+    let shift = match channel.get("dmxValueResolution").and_then(Value::as_str) {
+        Some("16bit") => 8,
+        Some("24bit") => 16,
+        _ => 0,
+    };
+    result.default_value = match channel.get("defaultValue") {
+        Some(Value::Number(n)) => (n.as_u64().unwrap_or(0) >> shift).min(255) as u8,
+        Some(Value::String(s)) => s
+            .strip_suffix('%')
+            .and_then(|p| p.trim().parse::<f32>().ok())
+            .map(|p| (p.clamp(0.0, 100.0) * 2.55).round() as u8)
+            .unwrap_or(0),
+        _ => 0,
+    };
+
+    let single = channel.get("capability").into_iter();
+    let multiple = channel
+        .get("capabilities")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten();
+    for capability in single.chain(multiple) {
+        // A single `capability` covers the whole channel and has no range
+        let (dmx_start, dmx_end) = match capability.get("dmxRange").and_then(Value::as_array) {
+            Some(range) => {
+                let bound = |i: usize| {
+                    range
+                        .get(i)
+                        .and_then(Value::as_u64)
+                        .map(|v| (v >> shift).min(255) as u8)
+                };
+                let (Some(start), Some(end)) = (bound(0), bound(1)) else {
+                    continue;
+                };
+                (start, end)
+            }
+            None => (0, 255),
+        };
+
+        let wheel_slot = capability
+            .get("slotNumber")
+            .and_then(Value::as_f64)
+            .filter(|n| n.fract() == 0.0 && *n >= 1.0)
+            .map(|n| n as u32);
+        let slot = wheel_slot.and_then(|n| {
+            let wheel = capability
+                .get("wheel")
+                .and_then(Value::as_str)
+                .unwrap_or(key);
+            wheels
+                .get(wheel)?
+                .get("slots")?
+                .as_array()?
+                .get(n as usize - 1)
+        });
+
+        let colors = capability
+            .get("colors")
+            .or_else(|| slot.and_then(|s| s.get("colors")))
+            .and_then(Value::as_array);
+        let color = colors
+            .and_then(|c| c.first())
+            .and_then(Value::as_str)
+            .and_then(parse_hex_color);
+
+        let name = capability
+            .get("comment")
+            .and_then(Value::as_str)
+            .or_else(|| slot.and_then(|s| s.get("name")).and_then(Value::as_str))
+            .or_else(|| slot.and_then(|s| s.get("type")).and_then(Value::as_str))
+            .or_else(|| capability.get("type").and_then(Value::as_str))
+            .unwrap_or("")
+            .to_string();
+
+        result.capabilities.push(ChannelCapability {
+            dmx_start,
+            dmx_end,
+            name,
+            wheel_slot,
+            color,
+        });
+    }
+    result
+}
+
+/// Derive the channel type from the channel's capability types
+fn channel_type(
+    key: &str,
+    channel: &Value,
+    wheels: &serde_json::Map<String, Value>,
+) -> ChannelType {
+    let single = channel.get("capability").into_iter();
+    let multiple = channel
+        .get("capabilities")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten();
+
+    single
+        .chain(multiple)
+        .map(|capability| capability_type(key, capability, wheels))
+        .find(|t| *t != ChannelType::Generic)
+        .unwrap_or(ChannelType::Generic)
+}
+
+fn capability_type(
+    key: &str,
+    capability: &Value,
+    wheels: &serde_json::Map<String, Value>,
+) -> ChannelType {
+    let kind = capability.get("type").and_then(Value::as_str).unwrap_or("");
+    match kind {
+        "Intensity" => ChannelType::Dimmer,
+        "ColorIntensity" => match capability.get("color").and_then(Value::as_str) {
+            Some("Red") => ChannelType::Red,
+            Some("Green") => ChannelType::Green,
+            Some("Blue") => ChannelType::Blue,
+            Some("Amber") => ChannelType::Amber,
+            Some("White" | "Warm White" | "Cold White") => ChannelType::White,
+            Some("Cyan") => ChannelType::Cyan,
+            Some("Magenta") => ChannelType::Magenta,
+            Some("Yellow") => ChannelType::Yellow,
+            Some("UV") => ChannelType::Uv,
+            _ => ChannelType::Generic,
+        },
+        "Pan" | "PanContinuous" => ChannelType::Pan,
+        "Tilt" | "TiltContinuous" => ChannelType::Tilt,
+        "WheelSlot" | "WheelShake" | "WheelRotation" | "WheelSlotRotation" => {
+            let wheel = capability
+                .get("wheel")
+                .and_then(Value::as_str)
+                .unwrap_or(key);
+            let slots = wheels
+                .get(wheel)
+                .and_then(|w| w.get("slots"))
+                .and_then(Value::as_array);
+            let has_slot = |slot_type: &str| {
+                slots.into_iter().flatten().any(|s| {
+                    s.get("type")
+                        .and_then(Value::as_str)
+                        .is_some_and(|t| t.starts_with(slot_type))
+                })
+            };
+            if has_slot("Gobo") {
+                ChannelType::Gobo
+            } else if has_slot("Prism") {
+                ChannelType::Prism
+            } else {
+                ChannelType::ColorWheel
+            }
+        }
+        "Prism" | "PrismRotation" => ChannelType::Prism,
+        "ShutterStrobe" | "StrobeSpeed" | "StrobeDuration" => ChannelType::Shutter,
+        "Zoom" => ChannelType::Zoom,
+        "Focus" => ChannelType::Focus,
+        "Iris" | "IrisEffect" => ChannelType::Iris,
+        "PanTiltSpeed" | "Speed" | "EffectSpeed" => ChannelType::Speed,
+        _ => ChannelType::Generic,
+    }
+}
+
+fn parse_hex_color(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([byte(0)?, byte(2)?, byte(4)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPOT: &str = r##"{
+        "name": "Spot 150",
+        "availableChannels": {
+            "Pan": {
+                "fineChannelAliases": ["Pan fine"],
+                "defaultValue": 32768,
+                "dmxValueResolution": "16bit",
+                "capability": { "type": "Pan", "angleStart": "0deg", "angleEnd": "540deg" }
+            },
+            "Dimmer": { "capability": { "type": "Intensity" } },
+            "Color Wheel": {
+                "capabilities": [
+                    { "dmxRange": [0, 9], "type": "WheelSlot", "slotNumber": 1 },
+                    { "dmxRange": [10, 19], "type": "WheelSlot", "slotNumber": 2 },
+                    { "dmxRange": [20, 255], "type": "WheelRotation", "speedStart": "slow CW", "speedEnd": "fast CW" }
+                ]
+            }
+        },
+        "wheels": {
+            "Color Wheel": {
+                "slots": [
+                    { "type": "Open" },
+                    { "type": "Color", "name": "Red", "colors": ["#ff0000"] }
+                ]
+            }
+        },
+        "modes": [
+            { "name": "Basic", "channels": ["Pan", "Dimmer", "Color Wheel"] },
+            { "name": "Extended", "channels": ["Pan", "Pan fine", "Dimmer", null, "Color Wheel"] },
+            { "name": "Pixel", "channels": ["Red $pixelKey"] }
+        ]
+    }"##;
+
+    #[test]
+    fn test_parse_modes_and_fine_channels() {
+        let definition = parse(SPOT, "acme").unwrap();
+        assert_eq!(definition.name, "Spot 150");
+        // The matrix mode is skipped
+        assert_eq!(definition.modes.len(), 2);
+
+        let basic = definition.mode("Basic").unwrap();
+        assert_eq!(basic.channel_count(), 3);
+        assert_eq!(basic.channels[0].channel_type, ChannelType::Pan);
+        assert_eq!(basic.channels[0].default_value, 128);
+        assert_eq!(basic.channels[0].fine_channel, None);
+
+        let extended = definition.mode("Extended").unwrap();
+        assert_eq!(extended.channel_count(), 5);
+        assert_eq!(extended.channels[0].fine_channel, Some(1));
+        assert!(extended.channels[1].is_fine);
+        assert_eq!(extended.channels[3].channel_type, ChannelType::Generic);
+    }
+
+    #[test]
+    fn test_wheel_capabilities() {
+        let definition = parse(SPOT, "acme").unwrap();
+        let wheel = &definition.mode("Basic").unwrap().channels[2];
+        assert_eq!(wheel.channel_type, ChannelType::ColorWheel);
+
+        let red = wheel.wheel_slot(2).unwrap();
+        assert_eq!((red.dmx_start, red.dmx_end), (10, 19));
+        assert_eq!(red.name, "Red");
+        assert_eq!(red.color, Some([255, 0, 0]));
+        assert_eq!(wheel.capability_at(100).unwrap().name, "WheelRotation");
+
+        // A single capability spans the whole channel
+        let dimmer = &definition.mode("Basic").unwrap().channels[1];
+        assert_eq!(dimmer.capabilities.len(), 1);
+        let intensity = dimmer.capability_at(200).unwrap();
+        assert_eq!((intensity.dmx_start, intensity.dmx_end), (0, 255));
+        assert_eq!(intensity.name, "Intensity");
+    }
+}
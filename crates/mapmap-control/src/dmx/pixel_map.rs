@@ -0,0 +1,496 @@
+//! Pixel mapping: drive DMX fixtures and LED strips from rendered video
+//!
+//! Fixtures are placed on the output canvas in normalized coordinates
+//! (0.0-1.0, matching `OutputManager` canvas regions). Every frame their
+//! colors are sampled from a CPU copy of the composition and written through
+//! the fixture profile into DMX universes.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+use super::artnet::ArtNetSender;
+use super::fixtures::{ChannelType, Fixture};
+use super::sacn::SacnSender;
+use crate::Result;
+
+/// Where a pixel-mapped fixture sits on the canvas
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PixelShape {
+    /// A single fixture sampled at one point
+    Point { position: (f32, f32) },
+    /// An LED strip with `pixel_count` pixels spread evenly along a polyline
+    Polyline {
+        points: Vec<(f32, f32)>,
+        pixel_count: u32,
+    },
+}
+
+impl PixelShape {
+    /// Canvas positions of every pixel
+    pub fn pixel_positions(&self) -> Vec<(f32, f32)> {
+        match self {
+            PixelShape::Point { position } => vec![*position],
+            PixelShape::Polyline {
+                points,
+                pixel_count,
+            } => polyline_positions(points, *pixel_count),
+        }
+    }
+}
+
+/// How a pixel's color is taken from the image
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SampleMode {
+    /// Nearest image pixel
+    Point,
+    /// Average of a square area with the given half-size (normalized)
+    Area { radius: f32 },
+}
+
+impl Default for SampleMode {
+    fn default() -> Self {
+        SampleMode::Area { radius: 0.01 }
+    }
+}
+
+/// Per-fixture color correction
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorCorrection {
+    /// Output gamma, 1.0 passes colors through (around 2.2 compensates for
+    /// the linear response of most LEDs)
+    pub gamma: f32,
+    /// Red, green and blue gains for white balance
+    pub white_balance: (f32, f32, f32),
+    /// Overall brightness (0.0-1.0)
+    pub brightness: f32,
+}
+
+impl Default for ColorCorrection {
+    fn default() -> Self {
+        Self {
+            gamma: 1.0,
+            white_balance: (1.0, 1.0, 1.0),
+            brightness: 1.0,
+        }
+    }
+}
+
+impl ColorCorrection {
+    /// Correct an 8-bit RGB color
+    pub fn apply(&self, rgb: [u8; 3]) -> [u8; 3] {
+        let gains = [
+            self.white_balance.0,
+            self.white_balance.1,
+            self.white_balance.2,
+        ];
+        let mut out = [0u8; 3];
+        for i in 0..3 {
+            let value = rgb[i] as f32 / 255.0;
+            let value = value.powf(self.gamma.max(0.01)) * gains[i] * self.brightness;
+            out[i] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        out
+    }
+}
+
+/// A fixture or LED strip placed on the canvas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PixelMappedFixture {
+    /// Patch of the first pixel; further pixels follow on consecutive
+    /// addresses and continue in the next universe when one is full
+    pub fixture: Fixture,
+    pub shape: PixelShape,
+    #[serde(default)]
+    pub sample_mode: SampleMode,
+    #[serde(default)]
+    pub correction: ColorCorrection,
+    pub enabled: bool,
+}
+
+impl PixelMappedFixture {
+    /// Create a pixel-mapped fixture
+    pub fn new(fixture: Fixture, shape: PixelShape) -> Self {
+        Self {
+            fixture,
+            shape,
+            sample_mode: SampleMode::default(),
+            correction: ColorCorrection::default(),
+            enabled: true,
+        }
+    }
+
+    /// Set the sampling mode
+    pub fn with_sample_mode(mut self, mode: SampleMode) -> Self {
+        self.sample_mode = mode;
+        self
+    }
+
+    /// Set the color correction
+    pub fn with_correction(mut self, correction: ColorCorrection) -> Self {
+        self.correction = correction;
+        self
+    }
+
+    /// DMX patch (universe, start address) of pixel `index`
+    pub fn pixel_address(&self, index: u32) -> (u16, u16) {
+        let width = self.fixture.profile.channel_count().max(1) as u32;
+        let start = self.fixture.start_address.max(1) as u32;
+        let first_universe = (512 - (start - 1).min(512)) / width;
+        let per_universe = (512 / width).max(1);
+
+        if index < first_universe {
+            (self.fixture.universe, (start + index * width) as u16)
+        } else {
+            let rest = index - first_universe;
+            let universe = self.fixture.universe as u32 + 1 + rest / per_universe;
+            (universe as u16, (1 + (rest % per_universe) * width) as u16)
+        }
+    }
+}
+
+/// A CPU copy of the composition, tightly packed RGBA8
+#[derive(Debug, Clone, Copy)]
+pub struct PixelFrame<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub data: &'a [u8],
+}
+
+impl<'a> PixelFrame<'a> {
+    /// Wrap RGBA8 pixel data
+    pub fn new(width: u32, height: u32, data: &'a [u8]) -> Self {
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Sample the color at a normalized canvas position
+    pub fn sample(&self, position: (f32, f32), mode: SampleMode) -> [u8; 3] {
+        if self.width == 0 || self.height == 0 {
+            return [0; 3];
+        }
+        let to_pixel = |v: f32, size: u32| ((v.clamp(0.0, 1.0) * size as f32) as u32).min(size - 1);
+        let x = to_pixel(position.0, self.width);
+        let y = to_pixel(position.1, self.height);
+
+        let (rx, ry) = match mode {
+            SampleMode::Point => (0, 0),
+            SampleMode::Area { radius } => (
+                (radius * self.width as f32) as u32,
+                (radius * self.height as f32) as u32,
+            ),
+        };
+
+        let mut sum = [0u32; 3];
+        let mut count = 0u32;
+        for py in y.saturating_sub(ry)..=(y + ry).min(self.height - 1) {
+            for px in x.saturating_sub(rx)..=(x + rx).min(self.width - 1) {
+                let i = ((py * self.width + px) * 4) as usize;
+                if let Some(pixel) = self.data.get(i..i + 3) {
+                    sum[0] += pixel[0] as u32;
+                    sum[1] += pixel[1] as u32;
+                    sum[2] += pixel[2] as u32;
+                    count += 1;
+                }
+            }
+        }
+
+        if count == 0 {
+            return [0; 3];
+        }
+        [
+            (sum[0] / count) as u8,
+            (sum[1] / count) as u8,
+            (sum[2] / count) as u8,
+        ]
+    }
+}
+
+/// Samples the composition for every mapped fixture and builds DMX universes
+#[derive(Debug, Default)]
+pub struct PixelMapper {
+    fixtures: Vec<PixelMappedFixture>,
+    universes: HashMap<u16, [u8; 512]>,
+}
+
+impl PixelMapper {
+    /// Create an empty pixel mapper
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a fixture
+    pub fn add_fixture(&mut self, fixture: PixelMappedFixture) {
+        self.fixtures.push(fixture);
+    }
+
+    /// Remove a fixture by ID
+    pub fn remove_fixture(&mut self, id: u32) -> Option<PixelMappedFixture> {
+        let index = self.fixtures.iter().position(|f| f.fixture.id == id)?;
+        Some(self.fixtures.remove(index))
+    }
+
+    /// Get all fixtures
+    pub fn fixtures(&self) -> &[PixelMappedFixture] {
+        &self.fixtures
+    }
+
+    /// Get a mutable fixture by ID
+    pub fn fixture_mut(&mut self, id: u32) -> Option<&mut PixelMappedFixture> {
+        self.fixtures.iter_mut().find(|f| f.fixture.id == id)
+    }
+
+    /// Sample a frame and write every fixture into its universes
+    pub fn process(&mut self, frame: &PixelFrame) {
+        for data in self.universes.values_mut() {
+            data.fill(0);
+        }
+
+        for mapped in self.fixtures.iter().filter(|f| f.enabled) {
+            for (index, position) in mapped.shape.pixel_positions().into_iter().enumerate() {
+                let rgb = mapped
+                    .correction
+                    .apply(frame.sample(position, mapped.sample_mode));
+                let (universe, address) = mapped.pixel_address(index as u32);
+
+                let pixel = Fixture {
+                    universe,
+                    start_address: address,
+                    ..mapped.fixture.clone()
+                };
+                let data = self.universes.entry(universe).or_insert([0u8; 512]);
+                write_color(&pixel, data, rgb);
+            }
+        }
+    }
+
+    /// Universes produced by the last `process` call
+    pub fn universes(&self) -> &HashMap<u16, [u8; 512]> {
+        &self.universes
+    }
+
+    /// Send every universe via Art-Net
+    ///
+    /// Universes with nodes in `routes` are unicast to those nodes, the others
+    /// are sent to `target`.
+    pub fn send_artnet(
+        &self,
+        sender: &mut ArtNetSender,
+        target: &str,
+        routes: &HashMap<u16, Vec<SocketAddr>>,
+    ) -> Result<()> {
+        let previous = sender.universe();
+        for (universe, data) in &self.universes {
+            match routes.get(universe) {
+                Some(nodes) if !nodes.is_empty() => {
+                    for node in nodes {
+                        sender.send_universe(*universe, data, node)?;
+                    }
+                }
+                _ => {
+                    sender.set_universe(*universe);
+                    sender.send_dmx(data, target)?;
+                }
+            }
+        }
+        sender.set_universe(previous);
+        Ok(())
+    }
+
+    /// Send every universe via sACN (universe 0 is not valid in sACN and is skipped)
+    pub fn send_sacn(&self, sender: &mut SacnSender) -> Result<()> {
+        let previous = sender.universe();
+        for (universe, data) in self.universes.iter().filter(|(u, _)| **u > 0) {
+            sender.set_universe(*universe)?;
+            sender.send_dmx(data)?;
+        }
+        sender.set_universe(previous)
+    }
+}
+
+/// Write a color through a fixture profile
+///
+/// Fixtures with a white channel take the common part of R, G and B as
+/// white; fixtures with a dimmer are driven at full.
+fn write_color(fixture: &Fixture, data: &mut [u8; 512], rgb: [u8; 3]) {
+    let has = |t: ChannelType| fixture.profile.channels.iter().any(|c| c.channel_type == t);
+
+    if has(ChannelType::White) {
+        let white = rgb[0].min(rgb[1]).min(rgb[2]);
+        fixture.set_rgbw(data, rgb[0] - white, rgb[1] - white, rgb[2] - white, white);
+    } else {
+        fixture.set_rgb(data, rgb[0], rgb[1], rgb[2]);
+    }
+    if has(ChannelType::Dimmer) {
+        let rgb_channels =
+            has(ChannelType::Red) || has(ChannelType::Green) || has(ChannelType::Blue);
+        let level = if rgb_channels {
+            255
+        } else {
+            // Single-channel dimmers follow luminance
+            (0.2126 * rgb[0] as f32 + 0.7152 * rgb[1] as f32 + 0.0722 * rgb[2] as f32) as u8
+        };
+        fixture.set_dimmer(data, level);
+    }
+}
+
+/// Spread `count` points evenly along a polyline, ends included
+fn polyline_positions(points: &[(f32, f32)], count: u32) -> Vec<(f32, f32)> {
+    match (points, count) {
+        (_, 0) | ([], _) => return Vec::new(),
+        ([only], _) => return vec![*only; count as usize],
+        (_, 1) => return vec![points[0]],
+        _ => {}
+    }
+
+    let lengths: Vec<f32> = points
+        .windows(2)
+        .map(|w| ((w[1].0 - w[0].0).powi(2) + (w[1].1 - w[0].1).powi(2)).sqrt())
+        .collect();
+    let total: f32 = lengths.iter().sum();
+
+    (0..count)
+        .map(|i| {
+            let mut distance = total * i as f32 / (count - 1) as f32;
+            for (segment, length) in lengths.iter().enumerate() {
+                if distance <= *length || segment == lengths.len() - 1 {
+                    let t = if *length > 0.0 {
+                        (distance / length).min(1.0)
+                    } else {
+                        0.0
+                    };
+                    let (a, b) = (points[segment], points[segment + 1]);
+                    return (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+                }
+                distance -= length;
+            }
+            points[points.len() - 1]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmx::FixtureProfile;
+
+    /// 4x1 image: red, green, blue, white
+    fn test_frame() -> Vec<u8> {
+        vec![
+            255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255,
+        ]
+    }
+
+    #[test]
+    fn test_polyline_positions() {
+        let positions = polyline_positions(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)], 5);
+        assert_eq!(positions.len(), 5);
+        assert_eq!(positions[0], (0.0, 0.0));
+        assert_eq!(positions[2], (1.0, 0.0));
+        assert_eq!(positions[4], (1.0, 1.0));
+    }
+
+    #[test]
+    fn test_area_sampling() {
+        let data = test_frame();
+        let frame = PixelFrame::new(4, 1, &data);
+        assert_eq!(frame.sample((0.1, 0.5), SampleMode::Point), [255, 0, 0]);
+        // Half-size of one pixel around pixel 1 averages red, green and blue
+        let mixed = frame.sample((0.3, 0.5), SampleMode::Area { radius: 0.25 });
+        assert_eq!(mixed, [85, 85, 85]);
+    }
+
+    #[test]
+    fn test_strip_spans_universes() {
+        let fixture = Fixture::new(1, "Strip".to_string(), FixtureProfile::rgb_par(), 0, 507);
+        let strip = PixelMappedFixture::new(
+            fixture,
+            PixelShape::Polyline {
+                points: vec![(0.0, 0.5), (1.0, 0.5)],
+                pixel_count: 4,
+            },
+        )
+        .with_sample_mode(SampleMode::Point);
+
+        // Two pixels fit in universe 0, the rest continue in universe 1
+        assert_eq!(strip.pixel_address(0), (0, 507));
+        assert_eq!(strip.pixel_address(1), (0, 510));
+        assert_eq!(strip.pixel_address(2), (1, 1));
+        assert_eq!(strip.pixel_address(3), (1, 4));
+
+        let mut mapper = PixelMapper::new();
+        mapper.add_fixture(strip);
+        let data = test_frame();
+        mapper.process(&PixelFrame::new(4, 1, &data));
+
+        let universes = mapper.universes();
+        assert_eq!(&universes[&0][506..509], &[255, 0, 0]);
+        assert_eq!(&universes[&0][509..512], &[0, 255, 0]);
+        assert_eq!(&universes[&1][0..3], &[0, 0, 255]);
+        assert_eq!(&universes[&1][3..6], &[255, 255, 255]);
+    }
+
+    #[test]
+    fn test_send_artnet_routes() {
+        use std::net::UdpSocket;
+
+        let bind = || {
+            let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+            socket
+                .set_read_timeout(Some(std::time::Duration::from_millis(500)))
+                .unwrap();
+            socket
+        };
+        let (node, fallback) = (bind(), bind());
+
+        let fixture = Fixture::new(1, "Strip".to_string(), FixtureProfile::rgb_par(), 0, 507);
+        let strip = PixelMappedFixture::new(
+            fixture,
+            PixelShape::Polyline {
+                points: vec![(0.0, 0.5), (1.0, 0.5)],
+                pixel_count: 4,
+            },
+        );
+        let mut mapper = PixelMapper::new();
+        mapper.add_fixture(strip);
+        let data = test_frame();
+        mapper.process(&PixelFrame::new(4, 1, &data));
+
+        // Universe 1 has a discovered node, universe 0 falls back to the target
+        let routes = HashMap::from([(1, vec![node.local_addr().unwrap()])]);
+        let mut sender = ArtNetSender::new(0, "127.0.0.1:6454").unwrap();
+        let target = fallback.local_addr().unwrap().to_string();
+        mapper.send_artnet(&mut sender, &target, &routes).unwrap();
+
+        let mut buf = [0u8; 600];
+        let (len, _) = node.recv_from(&mut buf).unwrap();
+        assert_eq!(len, 18 + 512);
+        assert_eq!(u16::from_le_bytes([buf[14], buf[15]]), 1);
+        fallback.recv_from(&mut buf).unwrap();
+        assert_eq!(u16::from_le_bytes([buf[14], buf[15]]), 0);
+    }
+
+    #[test]
+    fn test_color_correction_and_white() {
+        let correction = ColorCorrection {
+            gamma: 1.0,
+            white_balance: (1.0, 0.5, 1.0),
+            brightness: 1.0,
+        };
+        assert_eq!(correction.apply([255, 255, 255]), [255, 128, 255]);
+
+        let gamma = ColorCorrection {
+            gamma: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(gamma.apply([128, 0, 255]), [64, 0, 255]);
+
+        let fixture = Fixture::new(1, "RGBW".to_string(), FixtureProfile::rgbw_par(), 0, 1);
+        let mut data = [0u8; 512];
+        write_color(&fixture, &mut data, [200, 100, 100]);
+        assert_eq!(&data[0..4], &[100, 0, 0, 100]);
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! sACN (Streaming ACN) is a protocol for transmitting DMX512 over IP multicast.
 
+use std::collections::HashMap;
 use std::net::UdpSocket;
 use std::time::{Duration, Instant};
 use uuid::Uuid;
@@ -12,11 +13,16 @@ use crate::{error::ControlError, Result};
 pub struct SacnSender {
     socket: UdpSocket,
     universe: u16,
-    sequence: u8,
+    /// Sequence number of the next packet per universe
+    sequence: HashMap<u16, u8>,
     priority: u8,
+    /// Universe used for E1.31 synchronization packets (0 = no sync)
+    sync_address: u16,
+    sync_sequence: u8,
     source_name: String,
     cid: [u8; 16], // Component ID (UUID)
-    last_send: Instant,
+    /// Last send time per universe, for rate limiting
+    last_send: HashMap<u16, Instant>,
     min_interval: Duration,
 }
 
@@ -27,12 +33,7 @@ impl SacnSender {
     /// * `universe` - sACN universe (1-63999)
     /// * `source_name` - Source name (up to 64 characters)
     pub fn new(universe: u16, source_name: &str) -> Result<Self> {
-        if universe == 0 || universe > 63999 {
-            return Err(ControlError::DmxError(format!(
-                "Invalid sACN universe: {} (must be 1-63999)",
-                universe
-            )));
-        }
+        validate_universe(universe)?;
 
         let socket = UdpSocket::bind("0.0.0.0:0")?;
         socket.set_multicast_loop_v4(false)?;
@@ -46,11 +47,13 @@ impl SacnSender {
         Ok(Self {
             socket,
             universe,
-            sequence: 0,
+            sequence: HashMap::new(),
             priority: 100, // Default priority
+            sync_address: 0,
+            sync_sequence: 0,
             source_name: source_name.to_string(),
             cid,
-            last_send: Instant::now(),
+            last_send: HashMap::new(),
             min_interval: Duration::from_millis(1000 / 30), // 30Hz refresh rate
         })
     }
@@ -60,31 +63,92 @@ impl SacnSender {
     /// # Arguments
     /// * `channels` - 512 DMX channel values
     pub fn send_dmx(&mut self, channels: &[u8; 512]) -> Result<()> {
-        // Rate limiting (30Hz max)
+        // Rate limiting (30Hz max per universe)
         let now = Instant::now();
-        let elapsed = now.duration_since(self.last_send);
-        if elapsed < self.min_interval {
-            return Ok(());
+        if let Some(last) = self.last_send.get(&self.universe) {
+            if now.duration_since(*last) < self.min_interval {
+                return Ok(());
+            }
         }
 
         let packet = self.build_sacn_packet(channels);
 
-        // Calculate multicast address: 239.255.0.0 + universe
-        let multicast_addr = format!(
-            "239.255.{}.{}:5568",
-            (self.universe >> 8) & 0xFF,
-            self.universe & 0xFF
-        );
-
-        self.socket.send_to(&packet, multicast_addr)?;
-        self.sequence = self.sequence.wrapping_add(1);
-        self.last_send = now;
+        self.socket
+            .send_to(&packet, multicast_address(self.universe))?;
+        self.advance_sequence(self.universe);
+        self.last_send.insert(self.universe, now);
 
         tracing::trace!("Sent sACN DMX packet for universe {}", self.universe);
 
         Ok(())
     }
 
+    /// Send DMX data for a specific universe without rate limiting
+    ///
+    /// Used by callers that pace output themselves, such as the DMX engine.
+    pub fn send_universe(&mut self, universe: u16, channels: &[u8; 512]) -> Result<()> {
+        validate_universe(universe)?;
+        let previous = self.universe;
+        self.universe = universe;
+        let packet = self.build_sacn_packet(channels);
+        self.universe = previous;
+
+        self.socket.send_to(&packet, multicast_address(universe))?;
+        self.advance_sequence(universe);
+        Ok(())
+    }
+
+    /// Step the sequence number of `universe` after a packet was sent
+    ///
+    /// Receivers discard out-of-order packets by sequence per universe, so
+    /// every universe counts on its own.
+    fn advance_sequence(&mut self, universe: u16) {
+        let sequence = self.sequence.entry(universe).or_insert(0);
+        *sequence = sequence.wrapping_add(1);
+    }
+
+    /// Send an E1.31 synchronization packet to the sync address
+    ///
+    /// Receivers hold data packets carrying this sync address until the
+    /// sync packet arrives, then output all universes at once.
+    pub fn send_sync(&mut self) -> Result<()> {
+        if self.sync_address == 0 {
+            return Err(ControlError::DmxError(
+                "sACN sync address is not set".to_string(),
+            ));
+        }
+        let packet = self.build_sync_packet();
+        self.socket
+            .send_to(&packet, multicast_address(self.sync_address))?;
+        self.sync_sequence = self.sync_sequence.wrapping_add(1);
+        Ok(())
+    }
+
+    /// Build an E1.31 universe synchronization packet
+    fn build_sync_packet(&self) -> Vec<u8> {
+        let mut packet = vec![0u8; 49];
+
+        // Root Layer
+        packet[0..2].copy_from_slice(&0x0010u16.to_be_bytes());
+        packet[4..16].copy_from_slice(&[
+            0x41, 0x53, 0x43, 0x2d, 0x45, 0x31, 0x2e, 0x31, 0x37, 0x00, 0x00, 0x00,
+        ]);
+        packet[16..18].copy_from_slice(&(0x7000u16 | (49 - 16)).to_be_bytes());
+        // Vector: VECTOR_ROOT_E131_EXTENDED
+        packet[18..22].copy_from_slice(&0x00000008u32.to_be_bytes());
+        packet[22..38].copy_from_slice(&self.cid);
+
+        // Synchronization Framing Layer
+        packet[38..40].copy_from_slice(&(0x7000u16 | (49 - 38)).to_be_bytes());
+        // Vector: VECTOR_E131_EXTENDED_SYNCHRONIZATION
+        packet[40..44].copy_from_slice(&0x00000001u32.to_be_bytes());
+        packet[44] = self.sync_sequence;
+        packet[45..47].copy_from_slice(&self.sync_address.to_be_bytes());
+        // Reserved (2 bytes) stay zero
+
+        packet
+    }
+
     /// Build an sACN packet
     fn build_sacn_packet(&self, channels: &[u8; 512]) -> Vec<u8> {
         let mut packet = vec![0u8; 638]; // Full E1.31 packet size
@@ -142,11 +206,11 @@ impl SacnSender {
         offset += 1;
 
         // Synchronization Address (16-bit) - 0 for no sync
-        packet[offset..offset + 2].copy_from_slice(&0x0000u16.to_be_bytes());
+        packet[offset..offset + 2].copy_from_slice(&self.sync_address.to_be_bytes());
         offset += 2;
 
         // Sequence Number (1 byte)
-        packet[offset] = self.sequence;
+        packet[offset] = self.sequence.get(&self.universe).copied().unwrap_or(0);
         offset += 1;
 
         // Options (1 byte) - 0 for none
@@ -201,16 +265,25 @@ impl SacnSender {
 
     /// Set the universe
     pub fn set_universe(&mut self, universe: u16) -> Result<()> {
-        if universe == 0 || universe > 63999 {
-            return Err(ControlError::DmxError(format!(
-                "Invalid sACN universe: {} (must be 1-63999)",
-                universe
-            )));
-        }
+        validate_universe(universe)?;
         self.universe = universe;
         Ok(())
     }
 
+    /// Set the synchronization universe (0 disables sync)
+    pub fn set_sync_address(&mut self, universe: u16) -> Result<()> {
+        if universe != 0 {
+            validate_universe(universe)?;
+        }
+        self.sync_address = universe;
+        Ok(())
+    }
+
+    /// Get the synchronization universe
+    pub fn sync_address(&self) -> u16 {
+        self.sync_address
+    }
+
     /// Set the priority (0-200, default 100)
     pub fn set_priority(&mut self, priority: u8) {
         self.priority = priority.min(200);
@@ -222,6 +295,26 @@ impl SacnSender {
     }
 }
 
+/// Check that a universe is in the valid sACN range
+fn validate_universe(universe: u16) -> Result<()> {
+    if universe == 0 || universe > 63999 {
+        return Err(ControlError::DmxError(format!(
+            "Invalid sACN universe: {} (must be 1-63999)",
+            universe
+        )));
+    }
+    Ok(())
+}
+
+/// Multicast address for a universe: 239.255.0.0 + universe
+fn multicast_address(universe: u16) -> String {
+    format!(
+        "239.255.{}.{}:5568",
+        (universe >> 8) & 0xFF,
+        universe & 0xFF
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -282,12 +375,32 @@ mod tests {
         let packet1 = sender.build_sacn_packet(&channels);
         let seq1 = packet1[111]; // Sequence is at offset 111
 
-        sender.sequence = sender.sequence.wrapping_add(1);
+        sender.advance_sequence(1);
 
         // Second packet
         let packet2 = sender.build_sacn_packet(&channels);
         let seq2 = packet2[111];
 
         assert_eq!(seq2, seq1.wrapping_add(1));
+
+        // Other universes keep their own count
+        sender.set_universe(2).unwrap();
+        assert_eq!(sender.build_sacn_packet(&channels)[111], 0);
+    }
+
+    #[test]
+    fn test_sync_address() {
+        let mut sender = SacnSender::new(1, "Test").unwrap();
+        assert!(sender.send_sync().is_err());
+        sender.set_sync_address(7000).unwrap();
+
+        let data = sender.build_sacn_packet(&[0u8; 512]);
+        assert_eq!(&data[109..111], &7000u16.to_be_bytes());
+
+        let sync = sender.build_sync_packet();
+        assert_eq!(sync.len(), 49);
+        assert_eq!(&sync[18..22], &0x00000008u32.to_be_bytes());
+        assert_eq!(&sync[40..44], &0x00000001u32.to_be_bytes());
+        assert_eq!(&sync[45..47], &7000u16.to_be_bytes());
     }
 }
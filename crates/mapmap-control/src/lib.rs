@@ -6,6 +6,7 @@
 //! - **DMX**: Art-Net and sACN output for lighting control
 //! - **Web API**: REST API and WebSocket for remote control
 //! - **Cue System**: Automated shows with crossfades and triggers
+//! - **Timecode**: MTC and LTC chase for timecode-driven cues
 //!
 //! ## Feature Flags
 //!
@@ -31,6 +32,7 @@
 //! - [`dmx`]: DMX output via Art-Net and sACN
 //! - [`web`]: Web API and WebSocket
 //! - [`cue`]: Cue system for show automation
+//! - [`timecode`]: SMPTE timecode decoding and chase
 //! - [`shortcuts`]: Keyboard shortcuts and macros
 //! - [`target`]: Control target abstraction
 //! - [`error`]: Error types
@@ -54,6 +56,7 @@ pub mod web;
 
 pub mod cue;
 pub mod shortcuts;
+pub mod timecode;
 
 // Re-exports
 pub use error::{ControlError, Result};
@@ -71,11 +74,12 @@ pub use osc::{OscClient, OscMapping, OscServer};
 #[cfg(feature = "http-api")]
 pub use web::{WebServer, WebServerConfig};
 
-pub use cue::{Cue, CueList, FadeCurve, LayerState};
+pub use cue::{Cue, CueList, CueStack, FadeCurve, LayerState};
 pub use shortcuts::{
-    Action, Key, KeyBindings, Macro, MacroPlayer, MacroRecorder, Modifiers, Shortcut,
-    ShortcutContext,
+    Action, Key, KeyBindings, Macro, MacroCondition, MacroPlayer, MacroRecorder, MacroStep,
+    Modifiers, Shortcut, ShortcutContext,
 };
+pub use timecode::{Timecode, TimecodeRate, TimecodeSource};
 
 #[cfg(test)]
 mod tests {
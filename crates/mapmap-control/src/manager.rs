@@ -6,20 +6,30 @@
 //! Refactored to remove legacy learn modes and use simplified mapping.
 
 use crate::error::{ControlError, Result};
-use crate::shortcuts::{Action, Key, KeyBindings, Modifiers};
+use crate::shortcuts::{
+    Action, Key, KeyBindings, MacroContext, MacroOutput, MacroPlayer, MacroRecorder, Modifiers,
+    PlaybackState,
+};
 use crate::target::{ControlTarget, ControlValue};
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use tracing::{info, warn};
 
 #[cfg(feature = "midi")]
 use crate::midi::MidiInputHandler;
 
-use crate::cue::CueList;
-use crate::dmx::{ArtNetSender, SacnSender};
+use crate::cue::{CueList, CueListState, CueStack, TriggerDispatcher, TriggerEvent};
+use crate::dmx::{ArtNetDiscovery, DmxEngine, MergeMode, PixelFrame, PixelMapper};
+use crate::timecode::TimecodeSource;
+use mapmap_core::AppState;
 
 #[cfg(feature = "osc")]
 use crate::osc::{OscClient, OscMapping, OscServer};
 
+#[cfg(feature = "http-api")]
+use crate::web::WebCommand;
+
 /// Unified control system manager
 pub struct ControlManager {
     #[cfg(feature = "midi")]
@@ -32,11 +42,34 @@ pub struct ControlManager {
     #[cfg(feature = "osc")]
     pub osc_mapping: OscMapping,
 
-    pub artnet_sender: Option<ArtNetSender>,
-    pub sacn_sender: Option<SacnSender>,
-
-    pub cue_list: CueList,
+    /// Multi-universe DMX output with its own refresh rate; started by
+    /// `init_artnet` and `init_sacn`
+    pub dmx_engine: DmxEngine,
+    /// Art-Net node discovery; routes engine universes by unicast when set
+    pub artnet_discovery: Option<ArtNetDiscovery>,
+    /// Routes last handed to the DMX engine
+    artnet_routes: Option<HashMap<u16, Vec<SocketAddr>>>,
+    /// Fixtures and LED strips driven from the rendered composition
+    pub pixel_mapper: PixelMapper,
+
+    pub cue_stack: CueStack,
+    /// Fires cues from MIDI, OSC, keyboard and time-of-day triggers
+    pub cue_triggers: TriggerDispatcher,
     pub key_bindings: KeyBindings,
+    /// Records actions and control changes into macros
+    pub macro_recorder: MacroRecorder,
+    /// Macros currently playing
+    running_macros: Vec<MacroPlayer>,
+    /// Last value applied to each control target
+    control_values: HashMap<ControlTarget, ControlValue>,
+    /// Beats registered so far, if a beat source is connected
+    beat_count: Option<u64>,
+    /// External timecode chased by cues with timecode triggers
+    pub timecode: TimecodeSource,
+
+    /// Requests from the web API
+    #[cfg(feature = "http-api")]
+    web_commands: Option<tokio::sync::mpsc::UnboundedReceiver<WebCommand>>,
 
     /// Event callback for control changes
     #[allow(clippy::type_complexity)]
@@ -56,11 +89,22 @@ impl ControlManager {
             #[cfg(feature = "osc")]
             osc_mapping: OscMapping::new(),
 
-            artnet_sender: None,
-            sacn_sender: None,
+            dmx_engine: DmxEngine::new(),
+            artnet_discovery: None,
+            artnet_routes: None,
+            pixel_mapper: PixelMapper::new(),
 
-            cue_list: CueList::new(),
+            cue_stack: CueStack::new(),
+            cue_triggers: TriggerDispatcher::new(),
             key_bindings: KeyBindings::new(),
+            macro_recorder: MacroRecorder::new(),
+            running_macros: Vec::new(),
+            control_values: HashMap::new(),
+            beat_count: None,
+            timecode: TimecodeSource::new(),
+
+            #[cfg(feature = "http-api")]
+            web_commands: None,
 
             control_callback: None,
         }
@@ -127,40 +171,43 @@ impl ControlManager {
         self.osc_clients.retain(|c| c.destination_str() != addr);
     }
 
-    /// Initialize Art-Net sender
-    pub fn init_artnet(&mut self, universe: u16, target: &str) -> Result<()> {
+    /// Send `universes` over Art-Net to `target` and start the DMX engine
+    ///
+    /// Pixel-mapped universes are sent as well. Universes of nodes found by
+    /// Art-Net discovery are unicast to those nodes instead of `target`.
+    pub fn init_artnet(&mut self, universes: &[u16], target: &str) -> Result<()> {
         info!(
-            "Initializing Art-Net sender for universe {} to {}",
-            universe, target
+            "Initializing Art-Net output of universes {:?} to {}",
+            universes, target
         );
-        match ArtNetSender::new(universe, target) {
-            Ok(sender) => {
-                self.artnet_sender = Some(sender);
-                Ok(())
-            }
-            Err(e) => {
-                warn!("Art-Net sender initialization failed: {}", e);
-                Err(e)
-            }
+        if let Err(e) = self.dmx_engine.enable_artnet(target) {
+            warn!("Art-Net output initialization failed: {}", e);
+            return Err(e);
         }
+        self.start_dmx_engine(universes)
     }
 
-    /// Initialize sACN sender
-    pub fn init_sacn(&mut self, universe: u16, source_name: &str) -> Result<()> {
+    /// Send `universes` over sACN and start the DMX engine
+    ///
+    /// Pixel-mapped universes are sent as well.
+    pub fn init_sacn(&mut self, universes: &[u16], source_name: &str) -> Result<()> {
         info!(
-            "Initializing sACN sender for universe {} with source {}",
-            universe, source_name
+            "Initializing sACN output of universes {:?} with source {}",
+            universes, source_name
         );
-        match SacnSender::new(universe, source_name) {
-            Ok(sender) => {
-                self.sacn_sender = Some(sender);
-                Ok(())
-            }
-            Err(e) => {
-                warn!("sACN sender initialization failed: {}", e);
-                Err(e)
-            }
+        if let Err(e) = self.dmx_engine.enable_sacn(source_name) {
+            warn!("sACN output initialization failed: {}", e);
+            return Err(e);
+        }
+        self.start_dmx_engine(universes)
+    }
+
+    fn start_dmx_engine(&mut self, universes: &[u16]) -> Result<()> {
+        for universe in universes {
+            self.dmx_engine.universe_mut(*universe);
         }
+        self.dmx_engine.commit();
+        self.dmx_engine.start()
     }
 
     /// Update all control systems (call every frame)
@@ -173,8 +220,164 @@ impl ControlManager {
         #[cfg(feature = "osc")]
         self.process_osc_messages();
 
+        // Answer web API requests
+        #[cfg(feature = "http-api")]
+        self.process_web_commands();
+
+        // Time-of-day cue triggers
+        self.dispatch_trigger(TriggerEvent::Clock);
+
+        // Chase timecode
+        if let Some(update) = self.timecode.update() {
+            if let Some(rate) = self.timecode.frame_rate() {
+                self.cue_stack.chase_timecode(&update, rate);
+            }
+        }
+
         // Update cue system
-        self.cue_list.update();
+        self.cue_stack.update();
+
+        // Step running macros
+        self.update_macros();
+
+        // Track Art-Net nodes and route universes to them
+        self.update_artnet_discovery();
+    }
+
+    /// Start Art-Net node discovery, polling `poll_target`
+    ///
+    /// Once a node is found, the DMX engine unicasts each universe only to
+    /// the nodes that output it. Until then, and for universes no node
+    /// outputs, it keeps broadcasting.
+    pub fn init_artnet_discovery(&mut self, poll_target: &str) -> Result<()> {
+        let discovery = ArtNetDiscovery::new(poll_target)?;
+        info!("Art-Net discovery polling {}", poll_target);
+        self.artnet_discovery = Some(discovery);
+        Ok(())
+    }
+
+    fn update_artnet_discovery(&mut self) {
+        let Some(discovery) = &mut self.artnet_discovery else {
+            return;
+        };
+        if let Err(e) = discovery.update() {
+            warn!("Art-Net discovery failed: {}", e);
+            return;
+        }
+
+        // Nodes can change their outputs without going on- or offline, so
+        // compare the routes themselves
+        let routes = Some(discovery.routes()).filter(|routes| !routes.is_empty());
+        if routes != self.artnet_routes {
+            self.dmx_engine.set_artnet_routes(routes.clone());
+            self.artnet_routes = routes;
+        }
+    }
+
+    /// Answer requests from the web API, see [`WebServer::take_commands`](crate::WebServer::take_commands)
+    #[cfg(feature = "http-api")]
+    pub fn set_web_commands(&mut self, commands: tokio::sync::mpsc::UnboundedReceiver<WebCommand>) {
+        self.web_commands = Some(commands);
+    }
+
+    #[cfg(feature = "http-api")]
+    fn process_web_commands(&mut self) {
+        let Some(commands) = &mut self.web_commands else {
+            return;
+        };
+        let mut pending = Vec::new();
+        while let Ok(command) = commands.try_recv() {
+            pending.push(command);
+        }
+        for command in pending {
+            match command {
+                WebCommand::RunMacro { name, reply } => {
+                    // The request may have been dropped by the client
+                    let _ = reply.send(self.run_macro(&name));
+                }
+            }
+        }
+    }
+
+    /// Start a stored macro by name
+    pub fn run_macro(&mut self, name: &str) -> Result<()> {
+        let macro_def = self
+            .key_bindings
+            .get_macro(name)
+            .cloned()
+            .ok_or_else(|| ControlError::TargetNotFound(format!("Macro {}", name)))?;
+        info!("Running macro {}", name);
+        let mut player = MacroPlayer::new();
+        player.play_macro(macro_def);
+        self.running_macros.push(player);
+        Ok(())
+    }
+
+    /// Stop every running macro
+    pub fn stop_macros(&mut self) {
+        self.running_macros.clear();
+    }
+
+    /// Get the names of the running macros
+    pub fn running_macros(&self) -> Vec<&str> {
+        self.running_macros
+            .iter()
+            .filter_map(|p| p.current_macro_name())
+            .collect()
+    }
+
+    /// Count a beat from the beat detector or tap tempo
+    ///
+    /// Macros wait on these for their beat-synced steps.
+    pub fn register_beat(&mut self) {
+        self.beat_count = Some(self.beat_count.map_or(1, |b| b + 1));
+    }
+
+    fn update_macros(&mut self) {
+        if self.running_macros.is_empty() {
+            return;
+        }
+        let mut players = std::mem::take(&mut self.running_macros);
+        let mut outputs = Vec::new();
+        {
+            let context = ManagerMacroContext {
+                cue_stack: &self.cue_stack,
+                control_values: &self.control_values,
+                beat_count: self.beat_count,
+            };
+            for player in &mut players {
+                outputs.extend(player.update_with(&context));
+            }
+        }
+        players.retain(|p| p.get_state() != PlaybackState::Idle);
+        // Macros started by these outputs were pushed meanwhile
+        players.append(&mut self.running_macros);
+        self.running_macros = players;
+
+        // Macro output is not recorded into another macro
+        for output in outputs {
+            match output {
+                MacroOutput::Action(action) => self.run_action(action),
+                MacroOutput::Control(target, value) => self.set_control(target, value),
+            }
+        }
+    }
+
+    /// Fire any cues whose triggers match an event
+    pub fn dispatch_trigger(&mut self, event: TriggerEvent) {
+        for (list, cue) in self.cue_triggers.dispatch(&event, &mut self.cue_stack) {
+            info!("Cue {} in list {} triggered by {:?}", cue, list, event);
+        }
+    }
+
+    /// Feed audio input to the LTC decoder (no-op unless LTC is enabled)
+    pub fn process_ltc_audio(&mut self, samples: &[f32]) {
+        self.timecode.process_ltc_samples(samples);
+    }
+
+    /// Apply cue playback to the project state (call every frame after `update`)
+    pub fn apply_cues(&mut self, state: &mut AppState) {
+        self.cue_stack.apply(state);
     }
 
     /// Process MIDI messages
@@ -182,9 +385,13 @@ impl ControlManager {
     fn process_midi_messages(&mut self) {
         // Collect messages to process to avoid borrow checker issues
         let mut controls_to_apply = Vec::new();
+        let mut trigger_events = Vec::new();
 
         if let Some(midi_input) = &self.midi_input {
             while let Some(message) = midi_input.poll_message() {
+                self.timecode.process_midi_message(&message);
+                trigger_events.push(TriggerEvent::Midi(message));
+
                 // Learn mode removed. Directly map.
 
                 // Get mapping and collect control values
@@ -200,12 +407,17 @@ impl ControlManager {
         for (target, value) in controls_to_apply {
             self.apply_control(target, value);
         }
+
+        for event in trigger_events {
+            self.dispatch_trigger(event);
+        }
     }
 
     /// Process OSC messages
     #[cfg(feature = "osc")]
     fn process_osc_messages(&mut self) {
         let mut controls_to_apply = Vec::new();
+        let mut trigger_events = Vec::new();
 
         if let Some(osc_server) = &mut self.osc_server {
             while let Some(packet) = osc_server.poll_packet() {
@@ -213,7 +425,19 @@ impl ControlManager {
 
                 // Try to map and apply the control
                 if let rosc::OscPacket::Message(msg) = packet {
-                    if let Some(target) = self.osc_mapping.get(&msg.addr) {
+                    trigger_events.push(TriggerEvent::Osc {
+                        address: msg.addr.clone(),
+                        value: crate::osc::types::osc_to_control_value(&msg.args).ok(),
+                    });
+
+                    // Explicit mappings win; otherwise fall back to the built-in address space
+                    let target = self
+                        .osc_mapping
+                        .get(&msg.addr)
+                        .cloned()
+                        .or_else(|| crate::osc::parse_osc_address(&msg.addr).ok());
+
+                    if let Some(target) = target {
                         let value_result = match target {
                             ControlTarget::LayerPosition(_) => {
                                 crate::osc::types::osc_to_vec2(&msg.args)
@@ -222,7 +446,7 @@ impl ControlManager {
                         };
 
                         if let Ok(value) = value_result {
-                            controls_to_apply.push((target.clone(), value));
+                            controls_to_apply.push((target, value));
                         }
                     }
                 }
@@ -232,12 +456,47 @@ impl ControlManager {
         for (target, value) in controls_to_apply {
             self.apply_control(target, value);
         }
+
+        for event in trigger_events {
+            self.dispatch_trigger(event);
+        }
     }
 
     /// Apply a control change
     pub fn apply_control(&mut self, target: ControlTarget, value: ControlValue) {
+        self.macro_recorder
+            .record_control(target.clone(), value.clone());
+        self.set_control(target, value);
+    }
+
+    fn set_control(&mut self, target: ControlTarget, value: ControlValue) {
         info!("Control change: {:?} = {:?}", target, value);
 
+        // Cue list targets are handled here rather than by the application
+        let cue_result = match &target {
+            ControlTarget::CueListSubmaster(id) => value
+                .as_float()
+                .map(|level| self.cue_stack.set_submaster(*id, level)),
+            ControlTarget::CueListGo(id) => value
+                .as_bool()
+                .filter(|pressed| *pressed)
+                .map(|_| self.cue_stack.go(*id)),
+            ControlTarget::CueListBack(id) => value
+                .as_bool()
+                .filter(|pressed| *pressed)
+                .map(|_| self.cue_stack.back(*id)),
+            ControlTarget::Macro(name) => value
+                .as_bool()
+                .filter(|pressed| *pressed)
+                .map(|_| self.run_macro(name)),
+            _ => None,
+        };
+        if let Some(Err(e)) = cue_result {
+            warn!("Control {:?} failed: {}", target, e);
+        }
+
+        self.control_values.insert(target.clone(), value.clone());
+
         // Call the control callback if set
         if let Some(callback) = &self.control_callback {
             if let Ok(mut cb) = callback.lock() {
@@ -260,17 +519,26 @@ impl ControlManager {
 
     /// Execute an action
     pub fn execute_action(&mut self, action: Action) {
-        info!("Executing action: {:?}", action);
+        self.macro_recorder.record_action(action.clone());
+        self.run_action(action);
+    }
 
+    fn run_action(&mut self, action: Action) {
+        info!("Executing action: {:?}", action);
         match action {
             Action::NextCue => {
-                let _ = self.cue_list.next();
+                let _ = self.cue_stack.main_mut().next();
             }
             Action::PrevCue => {
-                let _ = self.cue_list.prev();
+                let _ = self.cue_stack.main_mut().prev();
             }
             Action::GotoCue(id) => {
-                let _ = self.cue_list.goto_cue(id, None);
+                let _ = self.cue_stack.main_mut().goto_cue(id, None);
+            }
+            Action::ExecuteMacro(name) => {
+                if let Err(e) = self.run_macro(&name) {
+                    warn!("Failed to run macro: {}", e);
+                }
             }
             _ => {
                 // Other actions would be handled by the application
@@ -284,30 +552,40 @@ impl ControlManager {
         if let Some(action) = self.key_bindings.find_action(key, modifiers) {
             self.execute_action(action);
         }
+        self.dispatch_trigger(TriggerEvent::Key {
+            key,
+            modifiers: modifiers.clone(),
+        });
     }
 
-    /// Send DMX data via Art-Net
-    pub fn send_artnet(&mut self, channels: &[u8; 512], target: &str) -> Result<()> {
-        if let Some(sender) = &mut self.artnet_sender {
-            sender.send_dmx(channels, target)?;
-        } else {
-            // No error if not initialized, just ignore? Or error?
-            // User requested robustness. If Art-Net not init, maybe we shouldn't fail hard.
-            // But returning Err allows caller to know.
+    /// Set the channels of a universe, sent by the DMX engine on its next
+    /// refresh
+    pub fn send_dmx(&mut self, universe: u16, channels: &[u8; 512]) -> Result<()> {
+        if !self.dmx_engine.is_running() {
             return Err(ControlError::DmxError(
-                "Art-Net not initialized".to_string(),
+                "DMX output not initialized".to_string(),
             ));
         }
+        *self.dmx_engine.universe_mut(universe) = *channels;
+        self.dmx_engine.commit();
         Ok(())
     }
 
-    /// Send DMX data via sACN
-    pub fn send_sacn(&mut self, channels: &[u8; 512]) -> Result<()> {
-        if let Some(sender) = &mut self.sacn_sender {
-            sender.send_dmx(channels)?;
-        } else {
-            return Err(ControlError::DmxError("sACN not initialized".to_string()));
+    /// Sample a rendered frame for pixel mapping and merge it into the DMX
+    /// engine, which sends it at its own refresh rate
+    ///
+    /// Does nothing until a DMX output is initialized.
+    pub fn send_pixel_map(&mut self, frame: &PixelFrame) -> Result<()> {
+        if self.pixel_mapper.fixtures().is_empty() || !self.dmx_engine.is_running() {
+            return Ok(());
         }
+        self.pixel_mapper.process(frame);
+        self.dmx_engine.set_source(
+            "pixel_map",
+            MergeMode::Htp,
+            self.pixel_mapper.universes().clone(),
+        );
+        self.dmx_engine.commit();
         Ok(())
     }
 
@@ -322,9 +600,39 @@ impl ControlManager {
         ]
     }
 
-    /// Get a mutable reference to the cue list
+    /// Get the main cue list
+    pub fn cue_list(&self) -> &CueList {
+        self.cue_stack.main()
+    }
+
+    /// Get a mutable reference to the main cue list
     pub fn cue_list_mut(&mut self) -> &mut CueList {
-        &mut self.cue_list
+        self.cue_stack.main_mut()
+    }
+}
+
+/// Application state seen by running macros
+struct ManagerMacroContext<'a> {
+    cue_stack: &'a CueStack,
+    control_values: &'a HashMap<ControlTarget, ControlValue>,
+    beat_count: Option<u64>,
+}
+
+impl MacroContext for ManagerMacroContext<'_> {
+    fn value(&self, target: &ControlTarget) -> Option<ControlValue> {
+        self.control_values.get(target).cloned()
+    }
+
+    fn beat_count(&self) -> Option<u64> {
+        self.beat_count
+    }
+
+    fn current_cue(&self) -> Option<u32> {
+        self.cue_stack.main().current_cue()
+    }
+
+    fn cue_fading(&self) -> bool {
+        self.cue_stack.main().state() == CueListState::Crossfading
     }
 }
 
@@ -379,11 +687,11 @@ mod tests {
         let mut manager = ControlManager::new();
 
         // Add some dummy cues to test navigation
-        manager.cue_list.add_cue(
+        manager.cue_list_mut().add_cue(
             crate::cue::Cue::new(1, "Cue 1".to_string())
                 .with_fade_duration(std::time::Duration::from_millis(0)),
         );
-        manager.cue_list.add_cue(
+        manager.cue_list_mut().add_cue(
             crate::cue::Cue::new(2, "Cue 2".to_string())
                 .with_fade_duration(std::time::Duration::from_millis(0)),
         );
@@ -391,16 +699,240 @@ mod tests {
         // Test Goto 1
         manager.execute_action(Action::GotoCue(1));
         manager.update();
-        assert_eq!(manager.cue_list.current_cue(), Some(1));
+        assert_eq!(manager.cue_list().current_cue(), Some(1));
 
         // Test Next
         manager.execute_action(Action::NextCue);
         manager.update();
-        assert_eq!(manager.cue_list.current_cue(), Some(2));
+        assert_eq!(manager.cue_list().current_cue(), Some(2));
 
         // Test Prev
         manager.execute_action(Action::PrevCue);
         manager.update();
-        assert_eq!(manager.cue_list.current_cue(), Some(1));
+        assert_eq!(manager.cue_list().current_cue(), Some(1));
+    }
+
+    #[test]
+    fn test_cue_list_controls() {
+        let mut manager = ControlManager::new();
+        let ambient = manager.cue_stack.add_list("Ambient".to_string());
+        let list = manager.cue_stack.list_mut(ambient).unwrap();
+        list.add_cue(
+            crate::cue::Cue::new(1, "Cue 1".to_string())
+                .with_fade_duration(std::time::Duration::from_millis(0)),
+        );
+
+        manager.apply_control(ControlTarget::CueListGo(ambient), ControlValue::Bool(true));
+        manager.apply_control(
+            ControlTarget::CueListSubmaster(ambient),
+            ControlValue::Float(0.5),
+        );
+        manager.update();
+
+        assert_eq!(
+            manager.cue_stack.list(ambient).unwrap().current_cue(),
+            Some(1)
+        );
+        assert_eq!(manager.cue_stack.submaster(ambient), Some(0.5));
+        assert_eq!(manager.cue_list().current_cue(), None);
+    }
+
+    #[test]
+    fn test_key_trigger_fires_cue() {
+        let mut manager = ControlManager::new();
+        let mut cue = crate::cue::Cue::new(5, "Finale".to_string());
+        cue.key_trigger = Some(crate::cue::KeyTrigger::new(Key::F5, Modifiers::new()));
+        manager.cue_list_mut().add_cue(cue);
+
+        manager.handle_key_press(Key::F6, &Modifiers::new());
+        assert_eq!(manager.cue_list().current_cue(), None);
+
+        manager.handle_key_press(Key::F5, &Modifiers::new());
+        assert_eq!(manager.cue_list().current_cue(), Some(5));
+    }
+
+    #[test]
+    fn test_macro_sets_controls_and_fires_cues() {
+        use crate::shortcuts::{Macro, MacroCondition, MacroStep};
+
+        let mut manager = ControlManager::new();
+        manager.cue_list_mut().add_cue(
+            crate::cue::Cue::new(1, "Cue 1".to_string())
+                .with_fade_duration(std::time::Duration::from_millis(0)),
+        );
+        manager.key_bindings.add_macro(Macro {
+            name: "Intro".to_string(),
+            description: String::new(),
+            actions: Vec::new(),
+            created_at: String::new(),
+            steps: vec![
+                MacroStep::Set {
+                    target: ControlTarget::MasterOpacity,
+                    value: ControlValue::Float(0.25),
+                },
+                MacroStep::If {
+                    condition: MacroCondition::Below {
+                        target: ControlTarget::MasterOpacity,
+                        value: 0.5,
+                    },
+                    then: vec![MacroStep::Action(Action::GotoCue(1))],
+                    otherwise: Vec::new(),
+                },
+                MacroStep::WaitForCue,
+            ],
+        });
+        assert!(manager.run_macro("Missing").is_err());
+
+        manager.apply_control(
+            ControlTarget::Macro("Intro".to_string()),
+            ControlValue::Bool(true),
+        );
+        assert_eq!(manager.running_macros(), vec!["Intro"]);
+
+        manager.update();
+        manager.update();
+        assert_eq!(manager.cue_list().current_cue(), Some(1));
+        assert!(manager.running_macros().is_empty());
+    }
+
+    #[test]
+    fn test_macro_waits_for_cue_fade() {
+        use crate::shortcuts::{Macro, MacroStep};
+        use std::time::Duration;
+
+        let mut manager = ControlManager::new();
+        manager.cue_list_mut().add_cue(
+            crate::cue::Cue::new(1, "Cue 1".to_string()).with_fade_duration(Duration::ZERO),
+        );
+        manager.cue_list_mut().add_cue(
+            crate::cue::Cue::new(2, "Cue 2".to_string())
+                .with_fade_duration(Duration::from_secs(10)),
+        );
+        manager.execute_action(Action::GotoCue(1));
+        manager.key_bindings.add_macro(Macro {
+            name: "Fade".to_string(),
+            description: String::new(),
+            actions: Vec::new(),
+            created_at: String::new(),
+            steps: vec![MacroStep::Action(Action::GotoCue(2)), MacroStep::WaitForCue],
+        });
+
+        manager.run_macro("Fade").unwrap();
+        manager.update();
+        manager.update();
+        assert_eq!(manager.cue_list().state(), CueListState::Crossfading);
+        assert_eq!(manager.running_macros(), vec!["Fade"]);
+    }
+
+    #[test]
+    fn test_macro_output_is_not_recorded() {
+        use crate::shortcuts::{Macro, MacroStep};
+
+        let mut manager = ControlManager::new();
+        manager.key_bindings.add_macro(Macro {
+            name: "Dim".to_string(),
+            description: String::new(),
+            actions: Vec::new(),
+            created_at: String::new(),
+            steps: vec![
+                MacroStep::Set {
+                    target: ControlTarget::MasterOpacity,
+                    value: ControlValue::Float(0.5),
+                },
+                MacroStep::Action(Action::NextCue),
+            ],
+        });
+
+        manager.macro_recorder.start_recording();
+        manager.run_macro("Dim").unwrap();
+        manager.update();
+        assert_eq!(manager.macro_recorder.action_count(), 0);
+
+        manager.apply_control(ControlTarget::MasterBlackout, ControlValue::Bool(true));
+        assert_eq!(manager.macro_recorder.action_count(), 1);
+    }
+
+    #[test]
+    fn test_dmx_goes_through_engine() {
+        use std::net::UdpSocket;
+        use std::time::Duration;
+
+        let node = UdpSocket::bind("127.0.0.1:0").unwrap();
+        node.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        let target = node.local_addr().unwrap().to_string();
+
+        let mut manager = ControlManager::new();
+        assert!(manager.send_dmx(3, &[0; 512]).is_err());
+        manager.init_artnet(&[3], &target).unwrap();
+        assert!(manager.dmx_engine.is_running());
+
+        let mut channels = [0u8; 512];
+        channels[0] = 255;
+        manager.send_dmx(3, &channels).unwrap();
+
+        let mut buffer = [0u8; 600];
+        for _ in 0..100 {
+            let (len, _) = node.recv_from(&mut buffer).unwrap();
+            if len == 18 + 512 && buffer[18] == 255 {
+                assert_eq!(u16::from_le_bytes([buffer[14], buffer[15]]), 3);
+                return;
+            }
+        }
+        panic!("universe 3 was not sent");
+    }
+
+    #[test]
+    fn test_artnet_routes_follow_node_outputs() {
+        use crate::dmx::ArtPollReply;
+        use std::net::UdpSocket;
+        use std::time::Duration;
+
+        let node = UdpSocket::bind("127.0.0.1:0").unwrap();
+        node.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        let SocketAddr::V4(address) = node.local_addr().unwrap() else {
+            unreachable!()
+        };
+        let mut reply = ArtPollReply {
+            address,
+            short_name: "Wall".to_string(),
+            long_name: "LED wall node".to_string(),
+            outputs: vec![1],
+            bind_index: 1,
+        };
+
+        let mut manager = ControlManager::new();
+        manager.artnet_discovery = Some(
+            ArtNetDiscovery::bind("127.0.0.1:0", &address.to_string())
+                .unwrap()
+                .with_poll_interval(Duration::from_secs(60)),
+        );
+
+        // Broadcast until a node is found
+        manager.update_artnet_discovery();
+        assert_eq!(manager.dmx_engine.artnet_routes(), None);
+
+        let mut buffer = [0u8; 64];
+        let (_, from) = node.recv_from(&mut buffer).unwrap();
+        let wait_for_routes = |manager: &mut ControlManager, universe: u16| {
+            for _ in 0..100 {
+                manager.update_artnet_discovery();
+                let routes = manager.dmx_engine.artnet_routes().unwrap_or_default();
+                if routes.contains_key(&universe) {
+                    return routes;
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            panic!("no route for universe {}", universe);
+        };
+
+        node.send_to(&reply.to_bytes(), from).unwrap();
+        let routes = wait_for_routes(&mut manager, 1);
+        assert_eq!(routes[&1], vec![SocketAddr::V4(address)]);
+
+        // The node moves to another universe without going offline
+        reply.outputs = vec![2];
+        node.send_to(&reply.to_bytes(), from).unwrap();
+        let routes = wait_for_routes(&mut manager, 2);
+        assert!(!routes.contains_key(&1));
     }
 }
@@ -78,12 +78,14 @@ impl MidiLearnState {
     /// Process an incoming MIDI message during learn mode
     pub fn process_message(&mut self, message: MidiMessage) -> bool {
         if let Self::WaitingForInput { target_element, .. } = self {
-            // Ignore clock and transport messages
+            // Ignore clock, transport and timecode messages
             match message {
                 MidiMessage::Clock
                 | MidiMessage::Start
                 | MidiMessage::Stop
-                | MidiMessage::Continue => {
+                | MidiMessage::Continue
+                | MidiMessage::TimecodeQuarterFrame { .. }
+                | MidiMessage::TimecodeFullFrame { .. } => {
                     return false;
                 }
                 _ => {}
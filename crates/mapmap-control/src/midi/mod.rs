@@ -65,6 +65,20 @@ pub enum MidiMessage {
     Start,
     Stop,
     Continue,
+    /// MIDI Time Code quarter frame (piece 0-7, 4-bit value)
+    TimecodeQuarterFrame {
+        piece: u8,
+        value: u8,
+    },
+    /// MIDI Time Code full frame (sent when the transport locates)
+    TimecodeFullFrame {
+        hours: u8,
+        minutes: u8,
+        seconds: u8,
+        frames: u8,
+        /// Frame rate code (0 = 24, 1 = 25, 2 = 29.97 drop-frame, 3 = 30)
+        rate: u8,
+    },
 }
 
 impl MidiMessage {
@@ -85,6 +99,27 @@ impl MidiMessage {
             _ => {}
         }
 
+        // MIDI Time Code
+        match bytes {
+            [0xF1, data, ..] => {
+                return Some(MidiMessage::TimecodeQuarterFrame {
+                    piece: (data >> 4) & 0x07,
+                    value: data & 0x0F,
+                });
+            }
+            [0xF0, 0x7F, _, 0x01, 0x01, hours, minutes, seconds, frames, 0xF7] => {
+                return Some(MidiMessage::TimecodeFullFrame {
+                    hours: hours & 0x1F,
+                    minutes: *minutes,
+                    seconds: *seconds,
+                    frames: *frames,
+                    rate: (hours >> 5) & 0x03,
+                });
+            }
+            [0xF0, ..] => return None,
+            _ => {}
+        }
+
         // Channel messages (need at least 2 bytes)
         if bytes.len() < 2 {
             return None;
@@ -199,6 +234,27 @@ impl MidiMessage {
             MidiMessage::Start => vec![0xFA],
             MidiMessage::Stop => vec![0xFC],
             MidiMessage::Continue => vec![0xFB],
+            MidiMessage::TimecodeQuarterFrame { piece, value } => {
+                vec![0xF1, ((piece & 0x07) << 4) | (value & 0x0F)]
+            }
+            MidiMessage::TimecodeFullFrame {
+                hours,
+                minutes,
+                seconds,
+                frames,
+                rate,
+            } => vec![
+                0xF0,
+                0x7F,
+                0x7F,
+                0x01,
+                0x01,
+                ((rate & 0x03) << 5) | (hours & 0x1F),
+                *minutes,
+                *seconds,
+                *frames,
+                0xF7,
+            ],
         }
     }
 }
@@ -265,4 +321,26 @@ mod tests {
         let msg = MidiMessage::Clock;
         assert_eq!(msg.to_bytes(), vec![0xF8]);
     }
+
+    #[test]
+    fn test_timecode_messages() {
+        let msg = MidiMessage::from_bytes(&[0xF1, 0x35]);
+        assert_eq!(
+            msg,
+            Some(MidiMessage::TimecodeQuarterFrame { piece: 3, value: 5 })
+        );
+        assert_eq!(msg.unwrap().to_bytes(), vec![0xF1, 0x35]);
+
+        let full = MidiMessage::TimecodeFullFrame {
+            hours: 1,
+            minutes: 2,
+            seconds: 3,
+            frames: 4,
+            rate: 1,
+        };
+        assert_eq!(MidiMessage::from_bytes(&full.to_bytes()), Some(full));
+
+        // Other SysEx is not a channel message
+        assert_eq!(MidiMessage::from_bytes(&[0xF0, 0x43, 0x10, 0xF7]), None);
+    }
 }
@@ -17,6 +17,9 @@ use crate::{error::ControlError, ControlTarget, Result};
 /// - `/mapmap/playback/speed` - Playback speed
 /// - `/mapmap/playback/position` - Playback position
 /// - `/mapmap/output/{id}/brightness` - Output brightness
+/// - `/mapmap/cuelist/{id}/submaster` - Cue list submaster (0.0-1.0)
+/// - `/mapmap/cuelist/{id}/go` - Advance cue list
+/// - `/mapmap/cuelist/{id}/back` - Step cue list back
 pub fn parse_osc_address(address: &str) -> Result<ControlTarget> {
     let parts: Vec<&str> = address.trim_start_matches('/').split('/').collect();
 
@@ -41,6 +44,13 @@ pub fn parse_osc_address(address: &str) -> Result<ControlTarget> {
         "effect" => parse_effect_address(&parts[2..]),
         "playback" => parse_playback_address(&parts[2..]),
         "output" => parse_output_address(&parts[2..]),
+        "cuelist" => parse_cue_list_address(&parts[2..]),
+        "macro" => match parts.get(2) {
+            Some(name) if !name.is_empty() => Ok(ControlTarget::Macro(name.to_string())),
+            _ => Err(ControlError::InvalidMessage(
+                "Missing macro name".to_string(),
+            )),
+        },
         _ => Err(ControlError::InvalidMessage(format!(
             "Unknown OSC category: {}",
             parts[1]
@@ -180,6 +190,34 @@ fn parse_output_address(parts: &[&str]) -> Result<ControlTarget> {
     }
 }
 
+fn parse_cue_list_address(parts: &[&str]) -> Result<ControlTarget> {
+    if parts.is_empty() {
+        return Err(ControlError::InvalidMessage(
+            "Missing cue list ID".to_string(),
+        ));
+    }
+
+    let list_id: u32 = parts[0]
+        .parse()
+        .map_err(|_| ControlError::InvalidMessage(format!("Invalid cue list ID: {}", parts[0])))?;
+
+    if parts.len() < 2 {
+        return Err(ControlError::InvalidMessage(
+            "Missing cue list parameter".to_string(),
+        ));
+    }
+
+    match parts[1] {
+        "submaster" => Ok(ControlTarget::CueListSubmaster(list_id)),
+        "go" => Ok(ControlTarget::CueListGo(list_id)),
+        "back" => Ok(ControlTarget::CueListBack(list_id)),
+        _ => Err(ControlError::InvalidMessage(format!(
+            "Unknown cue list parameter: {}",
+            parts[1]
+        ))),
+    }
+}
+
 /// Generate OSC address from control target
 pub fn control_target_to_address(target: &ControlTarget) -> String {
     match target {
@@ -202,6 +240,10 @@ pub fn control_target_to_address(target: &ControlTarget) -> String {
         }
         ControlTarget::MasterOpacity => "/mapmap/master/opacity".to_string(),
         ControlTarget::MasterBlackout => "/mapmap/master/blackout".to_string(),
+        ControlTarget::CueListSubmaster(id) => format!("/mapmap/cuelist/{}/submaster", id),
+        ControlTarget::CueListGo(id) => format!("/mapmap/cuelist/{}/go", id),
+        ControlTarget::CueListBack(id) => format!("/mapmap/cuelist/{}/back", id),
+        ControlTarget::Macro(name) => format!("/mapmap/macro/{}", name),
         ControlTarget::Custom(name) => format!("/mapmap/custom/{}", name),
     }
 }
@@ -372,4 +414,24 @@ mod tests {
         assert!(parse_osc_address("/mapmap/master").is_err());
         assert!(parse_osc_address("/mapmap/master/unknown").is_err());
     }
+
+    #[test]
+    fn test_round_trip_cue_list_targets() {
+        let targets = vec![
+            ControlTarget::CueListSubmaster(2),
+            ControlTarget::CueListGo(2),
+            ControlTarget::CueListBack(0),
+            ControlTarget::Macro("intro".to_string()),
+        ];
+
+        for target in targets {
+            let address = control_target_to_address(&target);
+            let parsed = parse_osc_address(&address).unwrap();
+            assert_eq!(parsed, target);
+        }
+
+        assert!(parse_osc_address("/mapmap/cuelist/1").is_err());
+        assert!(parse_osc_address("/mapmap/cuelist/1/unknown").is_err());
+        assert!(parse_osc_address("/mapmap/macro").is_err());
+    }
 }
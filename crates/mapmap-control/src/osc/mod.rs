@@ -15,6 +15,9 @@
 //! /mapmap/playback/speed           [f32: speed multiplier]
 //! /mapmap/playback/position        [f32: 0.0-1.0]
 //! /mapmap/output/{id}/brightness   [f32: 0.0-1.0]
+//! /mapmap/cuelist/{id}/submaster   [f32: 0.0-1.0]
+//! /mapmap/cuelist/{id}/go          [bool]
+//! /mapmap/cuelist/{id}/back        [bool]
 //! ```
 //!
 //! ## Example Usage
@@ -1,5 +1,6 @@
 //! Keyboard shortcut bindings manager
 
+use super::conflicts::{self, ShortcutConflict};
 use super::{Action, DefaultShortcuts, Key, Macro, Modifiers, Shortcut, ShortcutContext};
 use crate::error::Result;
 use serde::{Deserialize, Serialize};
@@ -14,6 +15,43 @@ pub struct KeyBindings {
     context: ShortcutContext,
 }
 
+/// How imported shortcuts that collide with existing ones are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergePolicy {
+    /// Keep the existing binding and skip the imported one
+    #[default]
+    KeepExisting,
+    /// Replace the existing binding with the imported one
+    Replace,
+    /// Keep both; the result is reported as ambiguous
+    KeepBoth,
+}
+
+/// Outcome of merging a shortcut set into the current bindings
+#[derive(Debug, Clone, Default)]
+pub struct MergeReport {
+    /// Number of imported shortcuts that were added
+    pub added: usize,
+    /// Existing shortcuts replaced by imported ones
+    pub replaced: Vec<Shortcut>,
+    /// Imported shortcuts that were not applied because of a collision
+    pub skipped: Vec<Shortcut>,
+    /// Names of imported macros that collided with existing ones
+    pub macro_conflicts: Vec<String>,
+    /// Conflicts remaining after the merge
+    pub conflicts: Vec<ShortcutConflict>,
+}
+
+impl MergeReport {
+    /// Check if the merge left nothing for the user to review
+    pub fn is_clean(&self) -> bool {
+        self.replaced.is_empty()
+            && self.skipped.is_empty()
+            && self.macro_conflicts.is_empty()
+            && self.conflicts.is_empty()
+    }
+}
+
 /// Serializable key bindings for save/load
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyBindingsData {
@@ -51,28 +89,50 @@ impl KeyBindings {
     }
 
     /// Find action for a key press
+    ///
+    /// The binding of the most specific context in the current context's
+    /// chain wins.
     pub fn find_action(&self, key: Key, modifiers: &Modifiers) -> Option<Action> {
-        for shortcut in &self.shortcuts {
-            if !shortcut.enabled {
-                continue;
-            }
+        self.resolve(key, modifiers, self.context)
+            .map(|shortcut| shortcut.action.clone())
+    }
 
-            // Check context match
-            let context_match = match shortcut.context {
-                ShortcutContext::Global => true,
-                _ => shortcut.context == self.context,
-            };
+    /// Find the shortcut that handles a key press in a context
+    pub fn resolve(
+        &self,
+        key: Key,
+        modifiers: &Modifiers,
+        context: ShortcutContext,
+    ) -> Option<&Shortcut> {
+        conflicts::resolve(&self.shortcuts, key, modifiers, context).map(|i| &self.shortcuts[i])
+    }
 
-            if !context_match {
-                continue;
-            }
+    /// Get the shortcuts that are effective in a context, including inherited
+    /// ones that are not overridden
+    pub fn resolved_shortcuts(&self, context: ShortcutContext) -> Vec<&Shortcut> {
+        conflicts::resolved_bindings(&self.shortcuts, context)
+            .into_iter()
+            .map(|i| &self.shortcuts[i])
+            .collect()
+    }
 
-            if shortcut.matches(key, modifiers) {
-                return Some(shortcut.action.clone());
-            }
-        }
+    /// Report every ambiguous, shadowed or redundant binding
+    pub fn conflicts(&self) -> Vec<ShortcutConflict> {
+        conflicts::find_conflicts(&self.shortcuts)
+    }
 
-        None
+    /// Report the conflicts a shortcut would cause if it were added
+    ///
+    /// Indices refer to the current shortcuts, with the new shortcut at
+    /// `get_shortcuts().len()`.
+    pub fn check_shortcut(&self, shortcut: &Shortcut) -> Vec<ShortcutConflict> {
+        let index = self.shortcuts.len();
+        let mut shortcuts = self.shortcuts.clone();
+        shortcuts.push(shortcut.clone());
+        conflicts::find_conflicts(&shortcuts)
+            .into_iter()
+            .filter(|c| c.shortcuts.contains(&index))
+            .collect()
     }
 
     /// Add a new shortcut
@@ -112,14 +172,10 @@ impl KeyBindings {
             .collect()
     }
 
-    /// Check if a key combination is already bound
+    /// Check if a key combination is already bound in a context or one it
+    /// inherits from
     pub fn is_key_bound(&self, key: Key, modifiers: &Modifiers, context: ShortcutContext) -> bool {
-        self.shortcuts.iter().any(|s| {
-            s.enabled
-                && s.key == key
-                && s.modifiers == *modifiers
-                && (s.context == context || s.context == ShortcutContext::Global)
-        })
+        self.resolve(key, modifiers, context).is_some()
     }
 
     /// Add a macro
@@ -208,6 +264,78 @@ impl KeyBindings {
     }
 }
 
+impl KeyBindings {
+    /// Merge an exported shortcut set into the current bindings
+    pub fn merge(&mut self, data: KeyBindingsData, policy: MergePolicy) -> MergeReport {
+        let mut report = MergeReport::default();
+
+        for incoming in data.shortcuts {
+            let existing = self.shortcuts.iter().position(|s| {
+                s.enabled
+                    && s.context == incoming.context
+                    && s.key == incoming.key
+                    && s.modifiers == incoming.modifiers
+            });
+            match existing {
+                None => {
+                    self.shortcuts.push(incoming);
+                    report.added += 1;
+                }
+                Some(i) if self.shortcuts[i].action == incoming.action => {}
+                Some(i) => match policy {
+                    MergePolicy::KeepExisting => report.skipped.push(incoming),
+                    MergePolicy::Replace => {
+                        report
+                            .replaced
+                            .push(std::mem::replace(&mut self.shortcuts[i], incoming));
+                    }
+                    MergePolicy::KeepBoth => {
+                        self.shortcuts.push(incoming);
+                        report.added += 1;
+                    }
+                },
+            }
+        }
+
+        for (name, macro_def) in data.macros {
+            if self.macros.contains_key(&name) {
+                report.macro_conflicts.push(name.clone());
+                if policy != MergePolicy::Replace {
+                    continue;
+                }
+            }
+            self.macros.insert(name, macro_def);
+        }
+        report.macro_conflicts.sort();
+
+        report.conflicts = self.conflicts();
+        info!(
+            "Merged shortcuts: {} added, {} replaced, {} skipped, {} conflicts",
+            report.added,
+            report.replaced.len(),
+            report.skipped.len(),
+            report.conflicts.len()
+        );
+        report
+    }
+
+    /// Merge shortcuts exported with [`to_json`](Self::to_json)
+    pub fn merge_json(&mut self, json: &str, policy: MergePolicy) -> Result<MergeReport> {
+        let data: KeyBindingsData = serde_json::from_str(json)?;
+        Ok(self.merge(data, policy))
+    }
+
+    /// Merge shortcuts from a JSON file
+    pub fn merge_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        policy: MergePolicy,
+    ) -> Result<MergeReport> {
+        let json = std::fs::read_to_string(path)?;
+        self.merge_json(&json, policy)
+    }
+}
+
 impl Default for KeyBindings {
     fn default() -> Self {
         Self::new()
@@ -283,6 +411,7 @@ mod tests {
             description: "Test".to_string(),
             actions: vec![],
             created_at: "2024-01-01T00:00:00Z".to_string(),
+            steps: Vec::new(),
         };
 
         bindings.add_macro(macro_def.clone());
@@ -301,4 +430,72 @@ mod tests {
 
         assert_eq!(bindings.get_shortcuts().len(), loaded.get_shortcuts().len());
     }
+
+    #[test]
+    fn test_context_inheritance() {
+        let mut bindings = KeyBindings::empty();
+        bindings.add_shortcut(Shortcut::new(
+            Key::Delete,
+            Modifiers::new(),
+            Action::Delete,
+            ShortcutContext::Editor,
+            "Delete".to_string(),
+        ));
+        bindings.add_shortcut(Shortcut::new(
+            Key::Delete,
+            Modifiers::new(),
+            Action::DeleteLayer,
+            ShortcutContext::LayerPanel,
+            "Delete layer".to_string(),
+        ));
+
+        bindings.set_context(ShortcutContext::Timeline);
+        assert_eq!(
+            bindings.find_action(Key::Delete, &Modifiers::new()),
+            Some(Action::Delete)
+        );
+        bindings.set_context(ShortcutContext::LayerPanel);
+        assert_eq!(
+            bindings.find_action(Key::Delete, &Modifiers::new()),
+            Some(Action::DeleteLayer)
+        );
+        assert_eq!(bindings.conflicts().len(), 1);
+    }
+
+    #[test]
+    fn test_merge_with_conflicts() {
+        let mut bindings = KeyBindings::new();
+        let mut imported = KeyBindings::empty();
+        imported.add_shortcut(Shortcut::new(
+            Key::Space,
+            Modifiers::new(),
+            Action::NextCue,
+            ShortcutContext::Global,
+            "Go".to_string(),
+        ));
+        imported.add_shortcut(Shortcut::new(
+            Key::G,
+            Modifiers::new(),
+            Action::NextCue,
+            ShortcutContext::Global,
+            "Go".to_string(),
+        ));
+        let json = imported.to_json().unwrap();
+
+        let report = bindings
+            .merge_json(&json, MergePolicy::KeepExisting)
+            .unwrap();
+        assert_eq!(report.added, 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(
+            bindings.find_action(Key::Space, &Modifiers::new()),
+            Some(Action::TogglePlayPause)
+        );
+
+        let report = bindings.merge_json(&json, MergePolicy::KeepBoth).unwrap();
+        assert_eq!(report.added, 1);
+        assert_eq!(report.conflicts.len(), 1);
+        assert!(!report.is_clean());
+    }
 }
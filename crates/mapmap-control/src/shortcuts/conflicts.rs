@@ -0,0 +1,264 @@
+//! Shortcut context layering and conflict detection
+//!
+//! Contexts inherit the shortcuts of their parent context, ending at
+//! [`ShortcutContext::Global`]. When a key combination is bound in several
+//! contexts of the same chain, the most specific context wins. Within one
+//! context the first enabled shortcut wins.
+
+use super::{Key, Modifiers, Shortcut, ShortcutContext};
+use serde::{Deserialize, Serialize};
+
+impl ShortcutContext {
+    /// All contexts, from least to most specific
+    pub const ALL: [ShortcutContext; 6] = [
+        ShortcutContext::Global,
+        ShortcutContext::MainWindow,
+        ShortcutContext::OutputWindow,
+        ShortcutContext::Editor,
+        ShortcutContext::Timeline,
+        ShortcutContext::LayerPanel,
+    ];
+
+    /// Get the context this one inherits shortcuts from
+    pub fn parent(&self) -> Option<ShortcutContext> {
+        match self {
+            ShortcutContext::Global => None,
+            ShortcutContext::MainWindow | ShortcutContext::OutputWindow => {
+                Some(ShortcutContext::Global)
+            }
+            ShortcutContext::Editor => Some(ShortcutContext::MainWindow),
+            ShortcutContext::Timeline | ShortcutContext::LayerPanel => {
+                Some(ShortcutContext::Editor)
+            }
+        }
+    }
+
+    /// Get this context followed by its ancestors, ending at `Global`
+    pub fn chain(&self) -> Vec<ShortcutContext> {
+        let mut chain = vec![*self];
+        while let Some(parent) = chain.last().and_then(|c| c.parent()) {
+            chain.push(parent);
+        }
+        chain
+    }
+
+    /// Check if shortcuts of `other` are active in this context
+    pub fn inherits(&self, other: ShortcutContext) -> bool {
+        self.chain().contains(&other)
+    }
+
+    /// Number of ancestors (0 for `Global`)
+    pub fn depth(&self) -> usize {
+        self.chain().len() - 1
+    }
+}
+
+/// How two or more bindings of one key combination collide
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictKind {
+    /// Same context, different actions; only the first one ever fires
+    Ambiguous,
+    /// A more specific context overrides an inherited binding
+    Shadowed,
+    /// The same action is bound more than once
+    Redundant,
+}
+
+/// A set of bindings that share a key combination
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShortcutConflict {
+    pub kind: ConflictKind,
+    pub key: Key,
+    pub modifiers: Modifiers,
+    /// Context where the conflict takes effect
+    pub context: ShortcutContext,
+    /// Indices of the shortcuts involved; the first one wins
+    pub shortcuts: Vec<usize>,
+}
+
+/// Find the shortcut that handles a key press in `context`
+pub fn resolve(
+    shortcuts: &[Shortcut],
+    key: Key,
+    modifiers: &Modifiers,
+    context: ShortcutContext,
+) -> Option<usize> {
+    context.chain().into_iter().find_map(|layer| {
+        shortcuts
+            .iter()
+            .position(|s| s.context == layer && s.matches(key, modifiers))
+    })
+}
+
+/// Get the indices of every shortcut that is reachable in `context`, one per
+/// key combination, ordered as in `shortcuts`
+pub fn resolved_bindings(shortcuts: &[Shortcut], context: ShortcutContext) -> Vec<usize> {
+    let mut resolved: Vec<usize> = shortcuts
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.enabled && context.inherits(s.context))
+        .filter_map(|(_, s)| resolve(shortcuts, s.key, &s.modifiers, context))
+        .collect();
+    resolved.sort_unstable();
+    resolved.dedup();
+    resolved
+}
+
+/// Report every key combination that is bound more than once in a context
+/// chain
+pub fn find_conflicts(shortcuts: &[Shortcut]) -> Vec<ShortcutConflict> {
+    let mut conflicts = Vec::new();
+    let enabled: Vec<usize> = (0..shortcuts.len())
+        .filter(|&i| shortcuts[i].enabled)
+        .collect();
+
+    for (n, &i) in enabled.iter().enumerate() {
+        let a = &shortcuts[i];
+        let same_combo =
+            |j: &&usize| shortcuts[**j].key == a.key && shortcuts[**j].modifiers == a.modifiers;
+
+        // Report a context group once, from its first binding
+        let earlier_in_context = enabled[..n]
+            .iter()
+            .filter(same_combo)
+            .any(|&j| shortcuts[j].context == a.context);
+        if !earlier_in_context {
+            let group: Vec<usize> = enabled[n..]
+                .iter()
+                .filter(same_combo)
+                .filter(|&&j| shortcuts[j].context == a.context)
+                .copied()
+                .collect();
+            if group.len() > 1 {
+                let ambiguous = group.iter().any(|&j| shortcuts[j].action != a.action);
+                conflicts.push(ShortcutConflict {
+                    kind: if ambiguous {
+                        ConflictKind::Ambiguous
+                    } else {
+                        ConflictKind::Redundant
+                    },
+                    key: a.key,
+                    modifiers: a.modifiers.clone(),
+                    context: a.context,
+                    shortcuts: group,
+                });
+            }
+        }
+
+        // Bindings of the same combination in ancestor contexts
+        for &j in enabled.iter().filter(same_combo) {
+            let b = &shortcuts[j];
+            if b.context == a.context || !a.context.inherits(b.context) {
+                continue;
+            }
+            conflicts.push(ShortcutConflict {
+                kind: if b.action == a.action {
+                    ConflictKind::Redundant
+                } else {
+                    ConflictKind::Shadowed
+                },
+                key: a.key,
+                modifiers: a.modifiers.clone(),
+                context: a.context,
+                shortcuts: vec![i, j],
+            });
+        }
+    }
+
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shortcuts::{Action, DefaultShortcuts};
+
+    fn shortcut(key: Key, action: Action, context: ShortcutContext) -> Shortcut {
+        Shortcut::new(key, Modifiers::new(), action, context, String::new())
+    }
+
+    #[test]
+    fn test_context_chain() {
+        assert_eq!(
+            ShortcutContext::Timeline.chain(),
+            vec![
+                ShortcutContext::Timeline,
+                ShortcutContext::Editor,
+                ShortcutContext::MainWindow,
+                ShortcutContext::Global,
+            ]
+        );
+        assert!(ShortcutContext::Timeline.inherits(ShortcutContext::Editor));
+        assert!(!ShortcutContext::Editor.inherits(ShortcutContext::Timeline));
+        assert!(!ShortcutContext::OutputWindow.inherits(ShortcutContext::MainWindow));
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let shortcuts = vec![
+            shortcut(Key::Delete, Action::Stop, ShortcutContext::Global),
+            shortcut(Key::Delete, Action::Delete, ShortcutContext::Editor),
+            shortcut(
+                Key::Delete,
+                Action::DeleteLayer,
+                ShortcutContext::LayerPanel,
+            ),
+        ];
+
+        assert_eq!(
+            resolve(
+                &shortcuts,
+                Key::Delete,
+                &Modifiers::new(),
+                ShortcutContext::Timeline
+            ),
+            Some(1)
+        );
+        assert_eq!(
+            resolve(
+                &shortcuts,
+                Key::Delete,
+                &Modifiers::new(),
+                ShortcutContext::LayerPanel
+            ),
+            Some(2)
+        );
+        assert_eq!(
+            resolve(
+                &shortcuts,
+                Key::Delete,
+                &Modifiers::new(),
+                ShortcutContext::OutputWindow
+            ),
+            Some(0)
+        );
+        assert_eq!(
+            resolved_bindings(&shortcuts, ShortcutContext::Editor),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let shortcuts = vec![
+            shortcut(Key::T, Action::Play, ShortcutContext::Editor),
+            shortcut(Key::T, Action::Pause, ShortcutContext::Editor),
+            shortcut(Key::T, Action::Stop, ShortcutContext::Timeline),
+            shortcut(Key::T, Action::Stop, ShortcutContext::OutputWindow),
+        ];
+
+        let conflicts = find_conflicts(&shortcuts);
+        assert_eq!(conflicts.len(), 3);
+        assert_eq!(conflicts[0].kind, ConflictKind::Ambiguous);
+        assert_eq!(conflicts[0].shortcuts, vec![0, 1]);
+        assert_eq!(conflicts[1].kind, ConflictKind::Shadowed);
+        assert_eq!(conflicts[1].context, ShortcutContext::Timeline);
+        assert_eq!(conflicts[1].shortcuts, vec![2, 0]);
+        assert_eq!(conflicts[2].shortcuts, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_defaults_have_no_conflicts() {
+        assert!(find_conflicts(&DefaultShortcuts::all()).is_empty());
+    }
+}
@@ -1,8 +1,13 @@
 //! Macro recording and playback system
+//!
+//! A macro is either a flat list of timed [`Action`]s or a program of
+//! [`MacroStep`]s that can also set and ramp control targets, wait for beats
+//! or cue completion, loop and branch.
 
 use super::Action;
+use crate::target::{ControlTarget, ControlValue};
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 /// A recorded macro
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +16,9 @@ pub struct Macro {
     pub description: String,
     pub actions: Vec<MacroAction>,
     pub created_at: String,
+    /// Step program; when not empty it is played instead of `actions`
+    #[serde(default)]
+    pub steps: Vec<MacroStep>,
 }
 
 /// A single action within a macro
@@ -21,6 +29,109 @@ pub struct MacroAction {
     pub delay: Duration,
 }
 
+/// A step of a macro program
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MacroStep {
+    /// Run an application action
+    Action(Action),
+    /// Set a control target to a value
+    Set {
+        target: ControlTarget,
+        value: ControlValue,
+    },
+    /// Ramp a control target to `to` over `duration`, starting from `from`
+    /// or the target's current value
+    Ramp {
+        target: ControlTarget,
+        from: Option<f32>,
+        to: f32,
+        duration: Duration,
+    },
+    /// Wait for a fixed time
+    Wait(Duration),
+    /// Wait for a number of beats (skipped without a beat clock)
+    WaitBeats(u32),
+    /// Wait until the main cue list finishes its crossfade
+    ///
+    /// Yields the update first, so a crossfade started by the steps before
+    /// it is waited for.
+    WaitForCue,
+    /// Run the steps `count` times
+    Loop { count: u32, steps: Vec<MacroStep> },
+    /// Run `then` if the condition holds, `otherwise` if not
+    If {
+        condition: MacroCondition,
+        then: Vec<MacroStep>,
+        #[serde(default)]
+        otherwise: Vec<MacroStep>,
+    },
+}
+
+/// A condition checked by [`MacroStep::If`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MacroCondition {
+    /// The target's current value is above a threshold
+    Above { target: ControlTarget, value: f32 },
+    /// The target's current value is below a threshold
+    Below { target: ControlTarget, value: f32 },
+    /// The target's current value is on (non-zero)
+    IsOn(ControlTarget),
+    /// The main cue list is on the given cue
+    CueActive(u32),
+    /// The inner condition does not hold
+    Not(Box<MacroCondition>),
+}
+
+impl MacroCondition {
+    /// Evaluate against the current application state
+    pub fn evaluate(&self, context: &dyn MacroContext) -> bool {
+        let float = |target: &ControlTarget| context.value(target).and_then(|v| v.as_float());
+        match self {
+            MacroCondition::Above { target, value } => float(target).is_some_and(|v| v > *value),
+            MacroCondition::Below { target, value } => float(target).is_some_and(|v| v < *value),
+            MacroCondition::IsOn(target) => context
+                .value(target)
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            MacroCondition::CueActive(id) => context.current_cue() == Some(*id),
+            MacroCondition::Not(inner) => !inner.evaluate(context),
+        }
+    }
+}
+
+/// Application state a macro can wait on or branch on
+pub trait MacroContext {
+    /// Current value of a control target, if known
+    fn value(&self, _target: &ControlTarget) -> Option<ControlValue> {
+        None
+    }
+
+    /// Beats counted so far, if a beat clock is running
+    fn beat_count(&self) -> Option<u64> {
+        None
+    }
+
+    /// Current cue of the main cue list
+    fn current_cue(&self) -> Option<u32> {
+        None
+    }
+
+    /// Check if a cue crossfade is in progress
+    fn cue_fading(&self) -> bool {
+        false
+    }
+}
+
+/// A context without any application state
+impl MacroContext for () {}
+
+/// Something a running macro wants the application to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroOutput {
+    Action(Action),
+    Control(ControlTarget, ControlValue),
+}
+
 /// Macro recorder state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RecordingState {
@@ -41,6 +152,9 @@ pub enum PlaybackState {
 pub struct MacroRecorder {
     state: RecordingState,
     recorded_actions: Vec<MacroAction>,
+    /// Actions and control changes as a step program
+    recorded_steps: Vec<MacroStep>,
+    has_controls: bool,
     last_action_time: Option<std::time::Instant>,
 }
 
@@ -49,6 +163,8 @@ impl MacroRecorder {
         Self {
             state: RecordingState::Idle,
             recorded_actions: Vec::new(),
+            recorded_steps: Vec::new(),
+            has_controls: false,
             last_action_time: None,
         }
     }
@@ -56,7 +172,13 @@ impl MacroRecorder {
     /// Start recording a macro
     pub fn start_recording(&mut self) {
         self.state = RecordingState::Recording;
+        self.clear();
+    }
+
+    fn clear(&mut self) {
         self.recorded_actions.clear();
+        self.recorded_steps.clear();
+        self.has_controls = false;
         self.last_action_time = None;
     }
 
@@ -68,19 +190,25 @@ impl MacroRecorder {
 
         self.state = RecordingState::Idle;
 
-        if self.recorded_actions.is_empty() {
+        if self.recorded_steps.is_empty() {
             return None;
         }
 
+        // Recordings of plain actions keep the simple action list
+        let (actions, steps) = if self.has_controls {
+            (Vec::new(), std::mem::take(&mut self.recorded_steps))
+        } else {
+            (std::mem::take(&mut self.recorded_actions), Vec::new())
+        };
         let macro_def = Macro {
             name,
             description,
-            actions: self.recorded_actions.clone(),
+            actions,
             created_at: chrono::Utc::now().to_rfc3339(),
+            steps,
         };
 
-        self.recorded_actions.clear();
-        self.last_action_time = None;
+        self.clear();
 
         Some(macro_def)
     }
@@ -103,15 +231,32 @@ impl MacroRecorder {
     /// Cancel recording
     pub fn cancel_recording(&mut self) {
         self.state = RecordingState::Idle;
-        self.recorded_actions.clear();
-        self.last_action_time = None;
+        self.clear();
     }
 
     /// Record an action
     pub fn record_action(&mut self, action: Action) {
-        if self.state != RecordingState::Recording {
+        let Some(delay) = self.next_delay() else {
+            return;
+        };
+        self.recorded_steps.push(MacroStep::Action(action.clone()));
+        self.recorded_actions.push(MacroAction { action, delay });
+    }
+
+    /// Record a control change
+    pub fn record_control(&mut self, target: ControlTarget, value: ControlValue) {
+        if self.next_delay().is_none() {
             return;
         }
+        self.recorded_steps.push(MacroStep::Set { target, value });
+        self.has_controls = true;
+    }
+
+    /// Time since the previous recorded event, adding it as a wait step
+    fn next_delay(&mut self) -> Option<Duration> {
+        if self.state != RecordingState::Recording {
+            return None;
+        }
 
         let now = std::time::Instant::now();
         let delay = if let Some(last_time) = self.last_action_time {
@@ -119,9 +264,11 @@ impl MacroRecorder {
         } else {
             Duration::ZERO
         };
-
-        self.recorded_actions.push(MacroAction { action, delay });
+        if !delay.is_zero() {
+            self.recorded_steps.push(MacroStep::Wait(delay));
+        }
         self.last_action_time = Some(now);
+        Some(delay)
     }
 
     /// Get current recording state
@@ -129,9 +276,12 @@ impl MacroRecorder {
         self.state
     }
 
-    /// Get number of recorded actions
+    /// Get number of recorded actions and control changes
     pub fn action_count(&self) -> usize {
-        self.recorded_actions.len()
+        self.recorded_steps
+            .iter()
+            .filter(|s| !matches!(s, MacroStep::Wait(_)))
+            .count()
     }
 }
 
@@ -141,12 +291,71 @@ impl Default for MacroRecorder {
     }
 }
 
+/// Steps a single update may run before yielding, so that loops without
+/// waits cannot stall the caller
+const MAX_STEPS_PER_UPDATE: usize = 10_000;
+
+/// A list of steps being run
+struct Frame {
+    steps: Vec<MacroStep>,
+    index: usize,
+    /// Passes left, including the current one
+    remaining: u32,
+}
+
+/// What the player is waiting for before running the next step
+enum Pending {
+    Until(Instant),
+    Beats(u64),
+    Cue,
+    Ramp {
+        target: ControlTarget,
+        from: f32,
+        to: f32,
+        start: Instant,
+        duration: Duration,
+    },
+}
+
+/// Sees values set earlier in the same update before the application has
+/// applied them
+struct Overlay<'a> {
+    context: &'a dyn MacroContext,
+    outputs: &'a [MacroOutput],
+}
+
+impl MacroContext for Overlay<'_> {
+    fn value(&self, target: &ControlTarget) -> Option<ControlValue> {
+        self.outputs
+            .iter()
+            .rev()
+            .find_map(|output| match output {
+                MacroOutput::Control(t, value) if t == target => Some(value.clone()),
+                _ => None,
+            })
+            .or_else(|| self.context.value(target))
+    }
+
+    fn beat_count(&self) -> Option<u64> {
+        self.context.beat_count()
+    }
+
+    fn current_cue(&self) -> Option<u32> {
+        self.context.current_cue()
+    }
+
+    fn cue_fading(&self) -> bool {
+        self.context.cue_fading()
+    }
+}
+
 /// Macro player
 pub struct MacroPlayer {
     state: PlaybackState,
     current_macro: Option<Macro>,
-    current_action_index: usize,
-    action_start_time: Option<std::time::Instant>,
+    frames: Vec<Frame>,
+    pending: Option<Pending>,
+    paused_at: Option<Instant>,
 }
 
 impl MacroPlayer {
@@ -154,31 +363,39 @@ impl MacroPlayer {
         Self {
             state: PlaybackState::Idle,
             current_macro: None,
-            current_action_index: 0,
-            action_start_time: None,
+            frames: Vec::new(),
+            pending: None,
+            paused_at: None,
         }
     }
 
     /// Start playing a macro
     pub fn play_macro(&mut self, macro_def: Macro) {
+        self.frames = vec![Frame {
+            steps: macro_def.program(),
+            index: 0,
+            remaining: 1,
+        }];
         self.current_macro = Some(macro_def);
-        self.current_action_index = 0;
+        self.pending = None;
+        self.paused_at = None;
         self.state = PlaybackState::Playing;
-        self.action_start_time = Some(std::time::Instant::now());
     }
 
     /// Stop playback
     pub fn stop(&mut self) {
         self.state = PlaybackState::Idle;
         self.current_macro = None;
-        self.current_action_index = 0;
-        self.action_start_time = None;
+        self.frames.clear();
+        self.pending = None;
+        self.paused_at = None;
     }
 
     /// Pause playback
     pub fn pause(&mut self) {
         if self.state == PlaybackState::Playing {
             self.state = PlaybackState::Paused;
+            self.paused_at = Some(Instant::now());
         }
     }
 
@@ -186,37 +403,190 @@ impl MacroPlayer {
     pub fn resume(&mut self) {
         if self.state == PlaybackState::Paused {
             self.state = PlaybackState::Playing;
-            self.action_start_time = Some(std::time::Instant::now());
+            // Timed waits and ramps continue where they left off
+            if let Some(paused_at) = self.paused_at.take() {
+                let paused = paused_at.elapsed();
+                match &mut self.pending {
+                    Some(Pending::Until(until)) => *until += paused,
+                    Some(Pending::Ramp { start, .. }) => *start += paused,
+                    _ => {}
+                }
+            }
         }
     }
 
     /// Update playback and return next action to execute
+    ///
+    /// Control steps are skipped; use [`update_with`](Self::update_with) to
+    /// receive them.
     pub fn update(&mut self) -> Option<Action> {
+        let mut outputs = Vec::new();
+        self.run(&(), Instant::now(), &mut outputs, true);
+        outputs.into_iter().find_map(|output| match output {
+            MacroOutput::Action(action) => Some(action),
+            MacroOutput::Control(..) => None,
+        })
+    }
+
+    /// Run every step that is due and return what the application should do
+    pub fn update_with(&mut self, context: &dyn MacroContext) -> Vec<MacroOutput> {
+        let mut outputs = Vec::new();
+        self.run(context, Instant::now(), &mut outputs, false);
+        outputs
+    }
+
+    fn run(
+        &mut self,
+        context: &dyn MacroContext,
+        now: Instant,
+        outputs: &mut Vec<MacroOutput>,
+        single_action: bool,
+    ) {
         if self.state != PlaybackState::Playing {
-            return None;
+            return;
         }
 
-        let macro_def = self.current_macro.as_ref()?;
+        for _ in 0..MAX_STEPS_PER_UPDATE {
+            if !self.resolve_pending(context, now, outputs) {
+                return;
+            }
 
-        if self.current_action_index >= macro_def.actions.len() {
-            // Playback complete
-            self.stop();
-            return None;
+            let Some(frame) = self.frames.last_mut() else {
+                // Playback complete
+                self.stop();
+                return;
+            };
+            if frame.index >= frame.steps.len() {
+                if frame.remaining > 1 {
+                    frame.remaining -= 1;
+                    frame.index = 0;
+                } else {
+                    self.frames.pop();
+                }
+                continue;
+            }
+
+            let step = frame.steps[frame.index].clone();
+            frame.index += 1;
+            if self.start_step(step, context, now, outputs) && single_action {
+                return;
+            }
+            // Actions only run once the update returns, so the cue list is
+            // checked from the next update on
+            if matches!(self.pending, Some(Pending::Cue)) {
+                return;
+            }
         }
+    }
 
-        let action = &macro_def.actions[self.current_action_index];
-        let start_time = self.action_start_time?;
-        let elapsed = std::time::Instant::now().duration_since(start_time);
+    /// Check the pending wait; returns true once the next step may run
+    fn resolve_pending(
+        &mut self,
+        context: &dyn MacroContext,
+        now: Instant,
+        outputs: &mut Vec<MacroOutput>,
+    ) -> bool {
+        let done = match &self.pending {
+            None => return true,
+            Some(Pending::Until(until)) => now >= *until,
+            Some(Pending::Beats(target)) => context.beat_count().map_or(true, |b| b >= *target),
+            Some(Pending::Cue) => !context.cue_fading(),
+            Some(Pending::Ramp {
+                target,
+                from,
+                to,
+                start,
+                duration,
+            }) => {
+                let t = if duration.is_zero() {
+                    1.0
+                } else {
+                    (now.saturating_duration_since(*start).as_secs_f32() / duration.as_secs_f32())
+                        .min(1.0)
+                };
+                outputs.push(MacroOutput::Control(
+                    target.clone(),
+                    ControlValue::Float(from + (to - from) * t),
+                ));
+                t >= 1.0
+            }
+        };
+        if done {
+            self.pending = None;
+        }
+        done
+    }
 
-        if elapsed >= action.delay {
-            // Time to execute this action
-            let result = action.action.clone();
-            self.current_action_index += 1;
-            self.action_start_time = Some(std::time::Instant::now());
-            Some(result)
-        } else {
-            None
+    /// Start a step; returns true if it produced an action
+    fn start_step(
+        &mut self,
+        step: MacroStep,
+        context: &dyn MacroContext,
+        now: Instant,
+        outputs: &mut Vec<MacroOutput>,
+    ) -> bool {
+        match step {
+            MacroStep::Action(action) => {
+                outputs.push(MacroOutput::Action(action));
+                return true;
+            }
+            MacroStep::Set { target, value } => {
+                outputs.push(MacroOutput::Control(target, value));
+            }
+            MacroStep::Ramp {
+                target,
+                from,
+                to,
+                duration,
+            } => {
+                let context = Overlay { context, outputs };
+                let from = from
+                    .or_else(|| context.value(&target).and_then(|v| v.as_float()))
+                    .unwrap_or(0.0);
+                self.pending = Some(Pending::Ramp {
+                    target,
+                    from,
+                    to,
+                    start: now,
+                    duration,
+                });
+            }
+            MacroStep::Wait(duration) => self.pending = Some(Pending::Until(now + duration)),
+            MacroStep::WaitBeats(beats) => {
+                if let Some(count) = context.beat_count() {
+                    self.pending = Some(Pending::Beats(count + beats as u64));
+                }
+            }
+            MacroStep::WaitForCue => self.pending = Some(Pending::Cue),
+            MacroStep::Loop { count, steps } => {
+                if count > 0 && !steps.is_empty() {
+                    self.frames.push(Frame {
+                        steps,
+                        index: 0,
+                        remaining: count,
+                    });
+                }
+            }
+            MacroStep::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let steps = if condition.evaluate(&Overlay { context, outputs }) {
+                    then
+                } else {
+                    otherwise
+                };
+                if !steps.is_empty() {
+                    self.frames.push(Frame {
+                        steps,
+                        index: 0,
+                        remaining: 1,
+                    });
+                }
+            }
         }
+        false
     }
 
     /// Get current playback state
@@ -224,16 +594,16 @@ impl MacroPlayer {
         self.state
     }
 
-    /// Get current playback progress (0.0-1.0)
+    /// Get the name of the playing macro
+    pub fn current_macro_name(&self) -> Option<&str> {
+        self.current_macro.as_ref().map(|m| m.name.as_str())
+    }
+
+    /// Get current playback progress (0.0-1.0) through the top-level steps
     pub fn get_progress(&self) -> f32 {
-        if let Some(macro_def) = &self.current_macro {
-            if !macro_def.actions.is_empty() {
-                self.current_action_index as f32 / macro_def.actions.len() as f32
-            } else {
-                0.0
-            }
-        } else {
-            0.0
+        match self.frames.first() {
+            Some(frame) if !frame.steps.is_empty() => frame.index as f32 / frame.steps.len() as f32,
+            _ => 0.0,
         }
     }
 }
@@ -256,8 +626,43 @@ impl Macro {
     }
 
     /// Get total duration of the macro
+    ///
+    /// Counts fixed waits and ramps; waits for beats or cues are not known
+    /// in advance.
     pub fn total_duration(&self) -> Duration {
-        self.actions.iter().map(|a| a.delay).sum()
+        if self.steps.is_empty() {
+            return self.actions.iter().map(|a| a.delay).sum();
+        }
+        fn duration(steps: &[MacroStep]) -> Duration {
+            steps
+                .iter()
+                .map(|step| match step {
+                    MacroStep::Wait(d) => *d,
+                    MacroStep::Ramp { duration, .. } => *duration,
+                    MacroStep::Loop { count, steps } => duration(steps) * *count,
+                    MacroStep::If {
+                        then, otherwise, ..
+                    } => duration(then).max(duration(otherwise)),
+                    _ => Duration::ZERO,
+                })
+                .sum()
+        }
+        duration(&self.steps)
+    }
+
+    /// Get the steps to play, converting a plain action list if needed
+    pub fn program(&self) -> Vec<MacroStep> {
+        if !self.steps.is_empty() {
+            return self.steps.clone();
+        }
+        let mut steps = Vec::with_capacity(self.actions.len() * 2);
+        for action in &self.actions {
+            if !action.delay.is_zero() {
+                steps.push(MacroStep::Wait(action.delay));
+            }
+            steps.push(MacroStep::Action(action.action.clone()));
+        }
+        steps
     }
 }
 
@@ -322,6 +727,7 @@ mod tests {
                 },
             ],
             created_at: "2024-01-01T00:00:00Z".to_string(),
+            steps: Vec::new(),
         };
 
         let mut player = MacroPlayer::new();
@@ -344,6 +750,7 @@ mod tests {
                 delay: Duration::ZERO,
             }],
             created_at: "2024-01-01T00:00:00Z".to_string(),
+            steps: Vec::new(),
         };
 
         let json = macro_def.to_json().unwrap();
@@ -352,4 +759,193 @@ mod tests {
         assert_eq!(macro_def.name, loaded.name);
         assert_eq!(macro_def.actions.len(), loaded.actions.len());
     }
+
+    struct TestContext {
+        opacity: f32,
+        beats: u64,
+    }
+
+    impl MacroContext for TestContext {
+        fn value(&self, target: &ControlTarget) -> Option<ControlValue> {
+            (*target == ControlTarget::MasterOpacity).then_some(ControlValue::Float(self.opacity))
+        }
+
+        fn beat_count(&self) -> Option<u64> {
+            Some(self.beats)
+        }
+    }
+
+    fn program(steps: Vec<MacroStep>) -> Macro {
+        Macro {
+            name: "Program".to_string(),
+            description: String::new(),
+            actions: Vec::new(),
+            created_at: String::new(),
+            steps,
+        }
+    }
+
+    #[test]
+    fn test_loop_and_branch() {
+        let blackout = ControlTarget::MasterBlackout;
+        let macro_def = program(vec![
+            MacroStep::Loop {
+                count: 3,
+                steps: vec![MacroStep::Action(Action::NextCue)],
+            },
+            MacroStep::If {
+                condition: MacroCondition::Above {
+                    target: ControlTarget::MasterOpacity,
+                    value: 0.5,
+                },
+                then: vec![MacroStep::Set {
+                    target: blackout.clone(),
+                    value: ControlValue::Bool(true),
+                }],
+                otherwise: vec![MacroStep::Action(Action::Stop)],
+            },
+        ]);
+
+        let mut player = MacroPlayer::new();
+        player.play_macro(macro_def.clone());
+        let context = TestContext {
+            opacity: 0.8,
+            beats: 0,
+        };
+        let outputs = player.update_with(&context);
+        assert_eq!(
+            outputs,
+            vec![
+                MacroOutput::Action(Action::NextCue),
+                MacroOutput::Action(Action::NextCue),
+                MacroOutput::Action(Action::NextCue),
+                MacroOutput::Control(blackout, ControlValue::Bool(true)),
+            ]
+        );
+        assert_eq!(player.get_state(), PlaybackState::Idle);
+
+        player.play_macro(macro_def);
+        let context = TestContext {
+            opacity: 0.2,
+            beats: 0,
+        };
+        let outputs = player.update_with(&context);
+        assert_eq!(outputs.last(), Some(&MacroOutput::Action(Action::Stop)));
+    }
+
+    #[test]
+    fn test_ramp_and_wait_for_beats() {
+        let target = ControlTarget::MasterOpacity;
+        let mut player = MacroPlayer::new();
+        player.play_macro(program(vec![
+            MacroStep::WaitBeats(2),
+            MacroStep::Ramp {
+                target: target.clone(),
+                from: None,
+                to: 1.0,
+                duration: Duration::from_millis(100),
+            },
+        ]));
+
+        let start = Instant::now();
+        let mut context = TestContext {
+            opacity: 0.5,
+            beats: 10,
+        };
+        let mut outputs = Vec::new();
+        player.run(&context, start, &mut outputs, false);
+        assert!(outputs.is_empty());
+
+        // Ramp starts from the current value once two beats have passed
+        context.beats = 12;
+        player.run(&context, start, &mut outputs, false);
+        assert_eq!(
+            outputs,
+            vec![MacroOutput::Control(
+                target.clone(),
+                ControlValue::Float(0.5)
+            )]
+        );
+
+        outputs.clear();
+        player.run(
+            &context,
+            start + Duration::from_millis(50),
+            &mut outputs,
+            false,
+        );
+        assert_eq!(
+            outputs,
+            vec![MacroOutput::Control(
+                target.clone(),
+                ControlValue::Float(0.75)
+            )]
+        );
+
+        outputs.clear();
+        player.run(
+            &context,
+            start + Duration::from_millis(200),
+            &mut outputs,
+            false,
+        );
+        assert_eq!(
+            outputs,
+            vec![MacroOutput::Control(target, ControlValue::Float(1.0))]
+        );
+        assert_eq!(player.get_state(), PlaybackState::Idle);
+    }
+
+    struct FadingContext(bool);
+
+    impl MacroContext for FadingContext {
+        fn cue_fading(&self) -> bool {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_wait_for_cue_after_action() {
+        let mut player = MacroPlayer::new();
+        player.play_macro(program(vec![
+            MacroStep::Action(Action::GotoCue(2)),
+            MacroStep::WaitForCue,
+            MacroStep::Action(Action::Stop),
+        ]));
+
+        // The fade has not started when the action is handed out
+        let outputs = player.update_with(&FadingContext(false));
+        assert_eq!(outputs, vec![MacroOutput::Action(Action::GotoCue(2))]);
+        assert_eq!(player.get_state(), PlaybackState::Playing);
+
+        assert!(player.update_with(&FadingContext(true)).is_empty());
+        assert_eq!(player.get_state(), PlaybackState::Playing);
+
+        let outputs = player.update_with(&FadingContext(false));
+        assert_eq!(outputs, vec![MacroOutput::Action(Action::Stop)]);
+        assert_eq!(player.get_state(), PlaybackState::Idle);
+    }
+
+    #[test]
+    fn test_record_controls() {
+        let mut recorder = MacroRecorder::new();
+        recorder.start_recording();
+        recorder.record_action(Action::Play);
+        recorder.record_control(ControlTarget::MasterOpacity, ControlValue::Float(0.5));
+        assert_eq!(recorder.action_count(), 2);
+
+        let macro_def = recorder
+            .stop_recording("Mixed".to_string(), String::new())
+            .unwrap();
+        assert!(macro_def.actions.is_empty());
+        assert_eq!(
+            macro_def.steps.last(),
+            Some(&MacroStep::Set {
+                target: ControlTarget::MasterOpacity,
+                value: ControlValue::Float(0.5)
+            })
+        );
+        let json = macro_def.to_json().unwrap();
+        assert_eq!(Macro::from_json(&json).unwrap().steps, macro_def.steps);
+    }
 }
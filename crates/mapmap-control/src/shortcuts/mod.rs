@@ -1,10 +1,12 @@
 //! Keyboard shortcuts and macro system
 
 mod bindings;
+pub mod conflicts;
 mod macros;
 #[allow(clippy::module_inception)]
 mod shortcuts;
 
 pub use bindings::*;
+pub use conflicts::{ConflictKind, ShortcutConflict};
 pub use macros::*;
 pub use shortcuts::*;
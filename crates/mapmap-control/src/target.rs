@@ -33,6 +33,14 @@ pub enum ControlTarget {
     MasterOpacity,
     /// Master blackout
     MasterBlackout,
+    /// Cue list submaster level (list_id, level: 0.0-1.0)
+    CueListSubmaster(u32),
+    /// Advance a cue list to its next cue (list_id)
+    CueListGo(u32),
+    /// Step a cue list back to its previous cue (list_id)
+    CueListBack(u32),
+    /// Run a stored macro (name)
+    Macro(String),
     /// Custom parameter (name)
     Custom(String),
 }
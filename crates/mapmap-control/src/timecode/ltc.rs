@@ -0,0 +1,213 @@
+//! Linear Timecode (LTC) decoding from audio
+
+use super::{Timecode, TimecodeRate};
+
+/// Sync word closing every LTC frame (bits 64-79)
+const SYNC_WORD: u128 = 0x3FFD;
+
+/// Decodes LTC from mono audio samples
+///
+/// LTC is biphase-mark encoded: every bit starts with a level transition and
+/// a `1` has an extra transition half way through. The decoder measures the
+/// time between zero crossings, tracks the bit period as the tape speed drifts
+/// and looks for the sync word that ends each 80-bit frame.
+#[derive(Debug, Clone)]
+pub struct LtcDecoder {
+    sample_rate: u32,
+    rate: TimecodeRate,
+    /// Estimated samples per bit
+    bit_period: f32,
+    /// Hysteresis threshold for zero crossings
+    threshold: f32,
+    level_high: bool,
+    samples_since_edge: u32,
+    half_bit_pending: bool,
+    bits: u128,
+}
+
+impl LtcDecoder {
+    /// Create a decoder for audio at `sample_rate` carrying timecode at `rate`
+    pub fn new(sample_rate: u32, rate: TimecodeRate) -> Self {
+        Self {
+            sample_rate,
+            rate,
+            bit_period: sample_rate as f32 / (rate.fps() as f32 * 80.0),
+            threshold: 0.02,
+            level_high: false,
+            samples_since_edge: 0,
+            half_bit_pending: false,
+            bits: 0,
+        }
+    }
+
+    /// Get the audio sample rate
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Get the expected frame rate
+    pub fn frame_rate(&self) -> TimecodeRate {
+        self.rate
+    }
+
+    /// Decode a block of samples, returning every frame that completed
+    ///
+    /// Positions are compensated for the one frame it takes to receive them.
+    pub fn process_samples(&mut self, samples: &[f32]) -> Vec<Timecode> {
+        let mut frames = Vec::new();
+
+        for &sample in samples {
+            self.samples_since_edge = self.samples_since_edge.saturating_add(1);
+
+            let crossed = if self.level_high {
+                sample < -self.threshold
+            } else {
+                sample > self.threshold
+            };
+            if !crossed {
+                continue;
+            }
+            self.level_high = !self.level_high;
+
+            let interval = self.samples_since_edge as f32;
+            self.samples_since_edge = 0;
+            if let Some(timecode) = self.edge(interval) {
+                frames.push(timecode);
+            }
+        }
+
+        frames
+    }
+
+    /// Handle a level transition `interval` samples after the previous one
+    fn edge(&mut self, interval: f32) -> Option<Timecode> {
+        if interval < self.bit_period * 0.25 || interval > self.bit_period * 1.5 {
+            // Noise or signal dropout
+            self.half_bit_pending = false;
+            return None;
+        }
+
+        if interval < self.bit_period * 0.75 {
+            self.bit_period = self.bit_period * 0.95 + interval * 2.0 * 0.05;
+            if !self.half_bit_pending {
+                self.half_bit_pending = true;
+                return None;
+            }
+            self.half_bit_pending = false;
+            self.push_bit(true)
+        } else {
+            self.bit_period = self.bit_period * 0.95 + interval * 0.05;
+            self.half_bit_pending = false;
+            self.push_bit(false)
+        }
+    }
+
+    fn push_bit(&mut self, bit: bool) -> Option<Timecode> {
+        self.bits = (self.bits << 1) | bit as u128;
+        if self.bits & 0xFFFF != SYNC_WORD {
+            return None;
+        }
+
+        // Data bit `n` (transmitted n-th) sits at position 79 - n
+        let bits = self.bits;
+        let field = |start: u32, len: u32| -> u8 {
+            (0..len).fold(0u8, |value, i| {
+                value | ((((bits >> (79 - start - i)) & 1) as u8) << i)
+            })
+        };
+
+        let frames = field(0, 4) + 10 * field(8, 2);
+        let drop_frame = field(10, 1) == 1;
+        let seconds = field(16, 4) + 10 * field(24, 3);
+        let minutes = field(32, 4) + 10 * field(40, 3);
+        let hours = field(48, 4) + 10 * field(56, 2);
+
+        let rate = match (drop_frame, self.rate) {
+            (true, rate) if rate.supports_drop_frame() => rate.with_drop_frame(true),
+            (true, _) => TimecodeRate::FPS_29_97_DF,
+            (false, rate) => rate.with_drop_frame(false),
+        };
+        Timecode::new(hours, minutes, seconds, frames, rate).map(|t| t.offset(1))
+    }
+}
+
+/// Encode a timecode as the 80 LTC bits in transmission order
+pub fn ltc_frame_bits(timecode: &Timecode) -> [bool; 80] {
+    let mut bits = [false; 80];
+    let mut set = |start: usize, len: usize, value: u8| {
+        for i in 0..len {
+            bits[start + i] = (value >> i) & 1 == 1;
+        }
+    };
+
+    set(0, 4, timecode.frames % 10);
+    set(8, 2, timecode.frames / 10);
+    set(10, 1, timecode.rate.is_drop_frame() as u8);
+    set(16, 4, timecode.seconds % 10);
+    set(24, 3, timecode.seconds / 10);
+    set(32, 4, timecode.minutes % 10);
+    set(40, 3, timecode.minutes / 10);
+    set(48, 4, timecode.hours % 10);
+    set(56, 2, timecode.hours / 10);
+    for i in 0..16 {
+        bits[64 + i] = (SYNC_WORD >> (15 - i)) & 1 == 1;
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Biphase-mark encode consecutive frames into audio samples
+    fn encode(start: Timecode, count: i64, samples_per_bit: usize) -> Vec<f32> {
+        let mut level = 0.5f32;
+        let mut samples = Vec::new();
+        for n in 0..count {
+            for bit in ltc_frame_bits(&start.offset(n)) {
+                level = -level;
+                if bit {
+                    samples.extend(std::iter::repeat(level).take(samples_per_bit / 2));
+                    level = -level;
+                    samples.extend(std::iter::repeat(level).take(samples_per_bit / 2));
+                } else {
+                    samples.extend(std::iter::repeat(level).take(samples_per_bit));
+                }
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn test_decode_ltc() {
+        // 48 kHz at 25 fps is 24 samples per bit
+        let start = Timecode::new(10, 59, 59, 20, TimecodeRate::FPS_25).unwrap();
+        let samples = encode(start, 8, 24);
+
+        let mut decoder = LtcDecoder::new(48_000, TimecodeRate::FPS_25);
+        let mut decoded = Vec::new();
+        for block in samples.chunks(512) {
+            decoded.extend(decoder.process_samples(block));
+        }
+
+        // The last bit of a frame is only complete once the next frame starts
+        assert_eq!(decoded.len(), 7);
+        let last = *decoded.last().unwrap();
+        assert_eq!(last, start.offset(7));
+        assert_eq!(last.to_string(), "11:00:00:02");
+    }
+
+    #[test]
+    fn test_decode_drop_frame_flag() {
+        // 48 kHz at 29.97 fps is ~20 samples per bit
+        let start = Timecode::new(0, 0, 59, 27, TimecodeRate::FPS_29_97_DF).unwrap();
+        let samples = encode(start, 4, 20);
+
+        let mut decoder = LtcDecoder::new(48_000, TimecodeRate::FPS_30);
+        let decoded = decoder.process_samples(&samples);
+
+        let last = *decoded.last().unwrap();
+        assert_eq!(last.rate, TimecodeRate::FPS_29_97_DF);
+        assert_eq!(last.to_string(), "00:01:00;02");
+    }
+}
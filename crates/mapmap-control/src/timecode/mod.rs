@@ -0,0 +1,59 @@
+//! SMPTE timecode input
+//!
+//! This module decodes external timecode so cues can be chased to a show
+//! clock running on another machine.
+//!
+//! ## Sources
+//!
+//! - **MTC**: MIDI Time Code quarter frames and full-frame locates
+//! - **LTC**: Linear Timecode decoded from an audio input
+//!
+//! ## Frame rates
+//!
+//! MTC carries 24, 25, 29.97 drop-frame and 30 fps. Positions use the
+//! [`Timecode`] type from `mapmap-core`, which is also what `mapmap-io`
+//! stamps output frames with, so a chased position can drive the output clock.
+
+mod ltc;
+mod mtc;
+mod source;
+
+pub use ltc::*;
+pub use mtc::*;
+pub use source::*;
+
+pub use mapmap_core::timecode::{Timecode, TimecodeRate};
+
+/// Decode the rate code used by MTC (0 = 24, 1 = 25, 2 = 29.97df, 3 = 30)
+pub fn mtc_rate(code: u8) -> TimecodeRate {
+    match code & 0x03 {
+        0 => TimecodeRate::FPS_24,
+        1 => TimecodeRate::FPS_25,
+        2 => TimecodeRate::FPS_29_97_DF,
+        _ => TimecodeRate::FPS_30,
+    }
+}
+
+/// Encode a rate as an MTC rate code, using the nearest code MTC can carry
+pub fn mtc_rate_code(rate: TimecodeRate) -> u8 {
+    match rate.nominal_fps() {
+        _ if rate.is_drop_frame() => 2,
+        0..=24 => 0,
+        25 => 1,
+        _ => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mtc_rate_codes() {
+        for code in 0..4 {
+            assert_eq!(mtc_rate_code(mtc_rate(code)), code);
+        }
+        assert_eq!(mtc_rate_code(TimecodeRate::FPS_23_976), 0);
+        assert_eq!(mtc_rate_code(TimecodeRate::FPS_29_97), 3);
+    }
+}
@@ -0,0 +1,152 @@
+//! MIDI Time Code decoding
+
+use super::{mtc_rate, mtc_rate_code, Timecode};
+
+#[cfg(feature = "midi")]
+use crate::midi::MidiMessage;
+
+/// Assembles MIDI Time Code quarter frames into timecode positions
+///
+/// A full position is spread over eight quarter-frame messages sent across
+/// two frames, so each complete sequence is two frames old by the time the
+/// last piece arrives. The decoder compensates for that delay.
+#[derive(Debug, Clone, Default)]
+pub struct MtcDecoder {
+    pieces: [u8; 8],
+    /// Bit set of the pieces received since the last piece 0
+    received: u8,
+    last_piece: Option<u8>,
+}
+
+impl MtcDecoder {
+    /// Create a new decoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one quarter frame, returning a position when a sequence completes
+    pub fn quarter_frame(&mut self, piece: u8, value: u8) -> Option<Timecode> {
+        let piece = piece & 0x07;
+
+        // Pieces must arrive in order; anything else restarts the sequence
+        let expected = self.last_piece.map_or(0, |p| (p + 1) % 8);
+        if piece != expected || piece == 0 {
+            self.received = 0;
+        }
+        self.last_piece = Some(piece);
+        if piece != 0 && self.received == 0 {
+            return None;
+        }
+
+        self.pieces[piece as usize] = value & 0x0F;
+        self.received |= 1 << piece;
+
+        if piece != 7 || self.received != 0xFF {
+            return None;
+        }
+
+        let p = &self.pieces;
+        let rate = mtc_rate(p[7] >> 1);
+        let timecode = Timecode::new(
+            ((p[7] & 0x01) << 4) | p[6],
+            (p[5] << 4) | p[4],
+            (p[3] << 4) | p[2],
+            (p[1] << 4) | p[0],
+            rate,
+        )?;
+        Some(timecode.offset(2))
+    }
+
+    /// Decode a full-frame locate message
+    pub fn full_frame(
+        &mut self,
+        hours: u8,
+        minutes: u8,
+        seconds: u8,
+        frames: u8,
+        rate: u8,
+    ) -> Option<Timecode> {
+        self.received = 0;
+        self.last_piece = None;
+        Timecode::new(hours, minutes, seconds, frames, mtc_rate(rate))
+    }
+
+    /// Feed a MIDI message, returning a position if it completes one
+    #[cfg(feature = "midi")]
+    pub fn process_message(&mut self, message: &MidiMessage) -> Option<Timecode> {
+        match *message {
+            MidiMessage::TimecodeQuarterFrame { piece, value } => self.quarter_frame(piece, value),
+            MidiMessage::TimecodeFullFrame {
+                hours,
+                minutes,
+                seconds,
+                frames,
+                rate,
+            } => self.full_frame(hours, minutes, seconds, frames, rate),
+            _ => None,
+        }
+    }
+}
+
+/// Split a timecode into the eight MTC quarter-frame values
+pub fn mtc_quarter_frames(timecode: &Timecode) -> [u8; 8] {
+    [
+        timecode.frames & 0x0F,
+        timecode.frames >> 4,
+        timecode.seconds & 0x0F,
+        timecode.seconds >> 4,
+        timecode.minutes & 0x0F,
+        timecode.minutes >> 4,
+        timecode.hours & 0x0F,
+        (timecode.hours >> 4) | (mtc_rate_code(timecode.rate) << 1),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timecode::TimecodeRate;
+
+    #[test]
+    fn test_quarter_frame_sequence() {
+        let sent = Timecode::new(1, 23, 45, 10, TimecodeRate::FPS_25).unwrap();
+        let mut decoder = MtcDecoder::new();
+
+        let pieces = mtc_quarter_frames(&sent);
+        let mut decoded = None;
+        for (piece, value) in pieces.iter().enumerate() {
+            decoded = decoder.quarter_frame(piece as u8, *value);
+        }
+
+        // Compensated for the two frames it takes to send a sequence
+        assert_eq!(decoded, Some(sent.offset(2)));
+    }
+
+    #[test]
+    fn test_sequence_starts_at_piece_zero() {
+        let sent = Timecode::new(0, 0, 1, 0, TimecodeRate::FPS_30).unwrap();
+        let pieces = mtc_quarter_frames(&sent);
+        let mut decoder = MtcDecoder::new();
+
+        // Joining mid-sequence yields nothing until the next piece 0
+        for piece in 4..8 {
+            assert!(decoder
+                .quarter_frame(piece, pieces[piece as usize])
+                .is_none());
+        }
+        for piece in 0..7 {
+            assert!(decoder
+                .quarter_frame(piece, pieces[piece as usize])
+                .is_none());
+        }
+        assert!(decoder.quarter_frame(7, pieces[7]).is_some());
+    }
+
+    #[test]
+    fn test_full_frame() {
+        let mut decoder = MtcDecoder::new();
+        let timecode = decoder.full_frame(10, 0, 0, 0, 2).unwrap();
+        assert_eq!(timecode.rate, TimecodeRate::FPS_29_97_DF);
+        assert_eq!(timecode.to_string(), "10:00:00;00");
+    }
+}
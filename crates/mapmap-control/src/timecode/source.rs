@@ -0,0 +1,328 @@
+//! Timecode source with chase, freewheel and jump handling
+
+use std::time::{Duration, Instant};
+
+use super::{LtcDecoder, MtcDecoder, Timecode, TimecodeRate};
+
+#[cfg(feature = "midi")]
+use crate::midi::MidiMessage;
+
+/// Lock state of a timecode source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimecodeState {
+    /// No timecode is being received
+    Stopped,
+    /// Following incoming timecode
+    Chasing,
+    /// Timecode dropped out; running on the internal clock
+    Freewheeling,
+}
+
+/// How the show position moved since the last update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimecodeUpdate {
+    /// Timecode ran forward normally through frames `from` (exclusive) to `to`
+    Advanced { from: u64, to: u64 },
+    /// Timecode locked or located to a new position
+    Jumped { to: u64 },
+}
+
+impl TimecodeUpdate {
+    /// Frame position after the update
+    pub fn position(&self) -> u64 {
+        match *self {
+            TimecodeUpdate::Advanced { to, .. } | TimecodeUpdate::Jumped { to } => to,
+        }
+    }
+}
+
+/// Follows external MTC or LTC and reports position changes to chase cues
+pub struct TimecodeSource {
+    mtc: MtcDecoder,
+    ltc: Option<LtcDecoder>,
+    /// Last received position and when it arrived
+    anchor: Option<(Timecode, Instant)>,
+    /// Frame position reported by the last update
+    reported: Option<u64>,
+    jump_pending: bool,
+    /// Time without timecode before the signal counts as lost
+    signal_timeout: Duration,
+    /// How long to keep running after the signal is lost
+    freewheel: Duration,
+    /// Position changes larger than this many frames are treated as jumps
+    jump_threshold: u64,
+}
+
+impl TimecodeSource {
+    /// Create a source that listens for MTC only
+    pub fn new() -> Self {
+        Self {
+            mtc: MtcDecoder::new(),
+            ltc: None,
+            anchor: None,
+            reported: None,
+            jump_pending: false,
+            signal_timeout: Duration::from_millis(100),
+            freewheel: Duration::from_secs(2),
+            jump_threshold: 10,
+        }
+    }
+
+    /// Set how long to keep running after timecode drops out
+    pub fn with_freewheel(mut self, freewheel: Duration) -> Self {
+        self.freewheel = freewheel;
+        self
+    }
+
+    /// Set the smallest position change (in frames) treated as a jump
+    pub fn with_jump_threshold(mut self, frames: u64) -> Self {
+        self.jump_threshold = frames.max(1);
+        self
+    }
+
+    /// Set how long the signal may be silent before freewheeling starts
+    pub fn with_signal_timeout(mut self, timeout: Duration) -> Self {
+        self.signal_timeout = timeout;
+        self
+    }
+
+    /// Set how long to keep running after timecode drops out
+    pub fn set_freewheel(&mut self, freewheel: Duration) {
+        self.freewheel = freewheel;
+    }
+
+    /// Get the freewheel duration
+    pub fn freewheel(&self) -> Duration {
+        self.freewheel
+    }
+
+    /// Start decoding LTC from audio at `sample_rate`
+    pub fn enable_ltc(&mut self, sample_rate: u32, rate: TimecodeRate) {
+        self.ltc = Some(LtcDecoder::new(sample_rate, rate));
+    }
+
+    /// Stop decoding LTC
+    pub fn disable_ltc(&mut self) {
+        self.ltc = None;
+    }
+
+    /// Check if LTC decoding is enabled
+    pub fn is_ltc_enabled(&self) -> bool {
+        self.ltc.is_some()
+    }
+
+    /// Get the LTC decoder, if enabled
+    pub fn ltc_decoder(&self) -> Option<&LtcDecoder> {
+        self.ltc.as_ref()
+    }
+
+    /// Feed mono audio samples to the LTC decoder (no-op unless enabled)
+    pub fn process_ltc_samples(&mut self, samples: &[f32]) {
+        let Some(ltc) = &mut self.ltc else {
+            return;
+        };
+        let now = Instant::now();
+        for timecode in ltc.process_samples(samples) {
+            self.receive_at(timecode, now);
+        }
+    }
+
+    /// Feed a MIDI message to the MTC decoder
+    #[cfg(feature = "midi")]
+    pub fn process_midi_message(&mut self, message: &MidiMessage) {
+        match *message {
+            MidiMessage::TimecodeQuarterFrame { piece, value } => {
+                self.process_quarter_frame(piece, value)
+            }
+            MidiMessage::TimecodeFullFrame {
+                hours,
+                minutes,
+                seconds,
+                frames,
+                rate,
+            } => {
+                if let Some(timecode) = self.mtc.full_frame(hours, minutes, seconds, frames, rate) {
+                    self.receive(timecode);
+                    // A full frame is always an explicit locate
+                    self.jump_pending = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Feed one MTC quarter frame
+    pub fn process_quarter_frame(&mut self, piece: u8, value: u8) {
+        if let Some(timecode) = self.mtc.quarter_frame(piece, value) {
+            self.receive(timecode);
+        }
+    }
+
+    /// Feed a decoded timecode position
+    pub fn receive(&mut self, timecode: Timecode) {
+        self.receive_at(timecode, Instant::now());
+    }
+
+    fn receive_at(&mut self, timecode: Timecode, now: Instant) {
+        if let Some(expected) = self.frames_at(now) {
+            let received = timecode.to_frames();
+            if received.abs_diff(expected) > self.jump_threshold {
+                self.jump_pending = true;
+            }
+        }
+        self.anchor = Some((timecode, now));
+    }
+
+    /// Get the lock state
+    pub fn state(&self) -> TimecodeState {
+        self.state_at(Instant::now())
+    }
+
+    fn state_at(&self, now: Instant) -> TimecodeState {
+        let Some((_, received)) = self.anchor else {
+            return TimecodeState::Stopped;
+        };
+        let silence = now.saturating_duration_since(received);
+        if silence <= self.signal_timeout {
+            TimecodeState::Chasing
+        } else if silence <= self.signal_timeout + self.freewheel {
+            TimecodeState::Freewheeling
+        } else {
+            TimecodeState::Stopped
+        }
+    }
+
+    /// Get the frame rate of the incoming timecode
+    pub fn frame_rate(&self) -> Option<TimecodeRate> {
+        self.anchor.map(|(timecode, _)| timecode.rate)
+    }
+
+    /// Get the current position (interpolated between received frames)
+    pub fn current(&self) -> Option<Timecode> {
+        let now = Instant::now();
+        let (timecode, _) = self.anchor?;
+        self.frames_at(now)
+            .map(|frames| Timecode::from_frames(frames, timecode.rate))
+    }
+
+    fn frames_at(&self, now: Instant) -> Option<u64> {
+        if self.state_at(now) == TimecodeState::Stopped {
+            return None;
+        }
+        let (timecode, received) = self.anchor?;
+        let elapsed = now.saturating_duration_since(received).as_secs_f64();
+        let frames = timecode.to_frames() + (elapsed * timecode.rate.fps()) as u64;
+        Some(frames % timecode.rate.frames_per_day())
+    }
+
+    /// Report how the position moved since the last call (call every frame)
+    pub fn update(&mut self) -> Option<TimecodeUpdate> {
+        self.update_at(Instant::now())
+    }
+
+    fn update_at(&mut self, now: Instant) -> Option<TimecodeUpdate> {
+        let Some(position) = self.frames_at(now) else {
+            self.reported = None;
+            return None;
+        };
+
+        let update = match self.reported {
+            Some(previous) if !self.jump_pending && position == previous => None,
+            Some(previous)
+                if !self.jump_pending
+                    && position > previous
+                    && position - previous <= self.jump_threshold =>
+            {
+                Some(TimecodeUpdate::Advanced {
+                    from: previous,
+                    to: position,
+                })
+            }
+            // Small backwards steps are jitter between MTC and the interpolation
+            Some(previous)
+                if !self.jump_pending
+                    && position < previous
+                    && previous - position <= self.jump_threshold =>
+            {
+                return None;
+            }
+            _ => Some(TimecodeUpdate::Jumped { to: position }),
+        };
+
+        self.jump_pending = false;
+        self.reported = Some(position);
+        update
+    }
+}
+
+impl Default for TimecodeSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tc(seconds: u8, frames: u8) -> Timecode {
+        Timecode::new(1, 0, seconds, frames, TimecodeRate::FPS_25).unwrap()
+    }
+
+    #[test]
+    fn test_chase_and_jump() {
+        let mut source = TimecodeSource::new();
+        let start = Instant::now();
+        assert!(source.update_at(start).is_none());
+
+        // First lock is a jump to the received position
+        source.receive_at(tc(0, 0), start);
+        assert_eq!(
+            source.update_at(start),
+            Some(TimecodeUpdate::Jumped {
+                to: tc(0, 0).to_frames()
+            })
+        );
+
+        // Normal playback advances frame by frame
+        let later = start + Duration::from_millis(40);
+        source.receive_at(tc(0, 1), later);
+        assert_eq!(
+            source.update_at(later),
+            Some(TimecodeUpdate::Advanced {
+                from: tc(0, 0).to_frames(),
+                to: tc(0, 1).to_frames()
+            })
+        );
+
+        // A locate far away is a jump
+        source.receive_at(tc(30, 0), later);
+        assert_eq!(
+            source.update_at(later),
+            Some(TimecodeUpdate::Jumped {
+                to: tc(30, 0).to_frames()
+            })
+        );
+    }
+
+    #[test]
+    fn test_freewheel_then_stop() {
+        let mut source = TimecodeSource::new().with_freewheel(Duration::from_secs(1));
+        let start = Instant::now();
+        source.receive_at(tc(0, 0), start);
+        source.update_at(start);
+
+        // Signal lost: keep counting on the internal clock
+        let dropout = start + Duration::from_millis(500);
+        assert_eq!(source.state_at(dropout), TimecodeState::Freewheeling);
+        assert_eq!(
+            source.update_at(dropout).map(|u| u.position()),
+            Some(tc(0, 12).to_frames())
+        );
+
+        // Freewheel expired
+        let expired = start + Duration::from_secs(2);
+        assert_eq!(source.state_at(expired), TimecodeState::Stopped);
+        assert!(source.update_at(expired).is_none());
+    }
+}
@@ -81,6 +81,25 @@ pub fn extract_api_key(headers: &http::HeaderMap, query: Option<&str>) -> Option
     None
 }
 
+/// Check whether a request may access the API
+pub fn authorize(auth: &AuthConfig, headers: &http::HeaderMap, query: Option<&str>) -> bool {
+    !auth.is_enabled() || extract_api_key(headers, query).is_some_and(|key| auth.validate(&key))
+}
+
+/// Reject requests without a valid API key
+#[cfg(feature = "http-api")]
+pub(crate) async fn check_auth(
+    state: &super::server::AppState,
+    headers: &http::HeaderMap,
+    uri: &http::Uri,
+) -> Result<(), http::StatusCode> {
+    if authorize(&*state.auth.read().await, headers, uri.query()) {
+        Ok(())
+    } else {
+        Err(http::StatusCode::UNAUTHORIZED)
+    }
+}
+
 fn parse_api_key_from_query(query: &str) -> Option<String> {
     for param in query.split('&') {
         if let Some((key, value)) = param.split_once('=') {
@@ -129,6 +148,17 @@ mod tests {
         assert_eq!(key, Some("test_key".to_string()));
     }
 
+    #[test]
+    fn test_authorize() {
+        let headers = http::HeaderMap::new();
+        assert!(authorize(&AuthConfig::new(), &headers, None));
+
+        let auth = AuthConfig::with_keys(vec!["test_key".to_string()]);
+        assert!(!authorize(&auth, &headers, None));
+        assert!(!authorize(&auth, &headers, Some("api_key=wrong")));
+        assert!(authorize(&auth, &headers, Some("api_key=test_key")));
+    }
+
     #[test]
     fn test_extract_query_param() {
         let headers = http::HeaderMap::new();
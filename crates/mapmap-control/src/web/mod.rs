@@ -10,8 +10,23 @@
 //! - `PATCH /api/layers/:id` - Update layer parameters
 //! - `GET /api/paints` - List all paints
 //! - `GET /api/effects` - List all effects
+//! - `POST /api/macros/:name/run` - Run a stored macro (404 if unknown)
 //! - `GET /ws` - WebSocket connection for real-time updates
 //!
+//! ## Output Previews
+//!
+//! - `GET /preview` - List the previews, `"composition"` and output ids
+//! - `GET /preview/:target` - MJPEG stream of the composition or an output
+//! - `GET /preview/:target/snapshot` - Newest frame of a target as a JPEG
+//!
+//! Requests that change the show, such as running a macro, are answered by the
+//! control manager; hand it [`WebServer::take_commands`] with
+//! `ControlManager::set_web_commands`.
+//!
+//! Frames are published to the [`PreviewHub`] returned by
+//! [`WebServer::previews`], usually through its [`PreviewHub::sink`]; see
+//! [`preview`] for details.
+//!
 //! ## WebSocket Messages
 //!
 //! ### Client to Server
@@ -57,7 +72,9 @@
 //!
 //! ## Authentication
 //!
-//! The web API supports optional authentication via API keys. Keys can be provided via:
+//! The web API supports optional authentication via API keys. The server binds
+//! to `127.0.0.1` by default; binding to any other address requires a key.
+//! Keys can be provided via:
 //! - `Authorization: Bearer <token>` header
 //! - `X-API-Key: <key>` header
 //! - `?api_key=<key>` query parameter
@@ -71,6 +88,7 @@
 
 pub mod auth;
 pub mod handlers;
+pub mod preview;
 pub mod routes;
 pub mod server;
 pub mod websocket;
@@ -79,8 +97,11 @@ pub use auth::AuthConfig;
 pub use handlers::{
     ApiResponse, LayerInfo, StatusResponse, UpdateLayerRequest, UpdateParameterRequest,
 };
+pub use preview::{PreviewQuery, PreviewTarget, MJPEG_BOUNDARY};
 pub use server::{WebServer, WebServerConfig};
 pub use websocket::{WsClientMessage, WsServerMessage};
 
 #[cfg(feature = "http-api")]
-pub use server::AppState;
+pub use preview::{PreviewFrame, PreviewHub};
+#[cfg(feature = "http-api")]
+pub use server::{AppState, WebCommand};
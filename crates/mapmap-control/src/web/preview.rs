@@ -0,0 +1,557 @@
+//! MJPEG output previews
+//!
+//! The render loop publishes JPEG frames of the composition and of each
+//! output to a [`PreviewHub`], usually by feeding the sink from
+//! [`PreviewHub::sink`] like any other output sink, and the web server serves
+//! them to monitors that only need to see what an output shows:
+//!
+//! - `GET /preview` - List the previews, `"composition"` and output ids
+//! - `GET /preview/:target` - `multipart/x-mixed-replace` MJPEG stream,
+//!   usable directly as an `<img>` source
+//! - `GET /preview/:target/snapshot` - The newest frame as a single JPEG
+//!
+//! `:target` is either `composition` or an output id.
+//!
+//! The stream accepts `?fps=<rate>` to limit the frame rate of a single
+//! client below the rate frames are published at. Both endpoints use the
+//! API key authentication of the web server; browsers can pass the key as
+//! `?api_key=<key>`.
+
+#[cfg(feature = "http-api")]
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Json, Response},
+};
+#[cfg(feature = "http-api")]
+use futures::Stream;
+use mapmap_core::OutputId;
+#[cfg(feature = "http-api")]
+use mapmap_io::PreviewSink;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "http-api")]
+use std::collections::HashMap;
+#[cfg(feature = "http-api")]
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+#[cfg(feature = "http-api")]
+use std::sync::{Arc, RwLock};
+#[cfg(feature = "http-api")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "http-api")]
+use tokio::sync::watch;
+
+#[cfg(feature = "http-api")]
+use super::auth::check_auth;
+#[cfg(feature = "http-api")]
+use super::handlers::ApiResponse;
+#[cfg(feature = "http-api")]
+use super::server::AppState;
+
+/// Multipart boundary between MJPEG frames
+pub const MJPEG_BOUNDARY: &str = "mapflowframe";
+
+/// What a preview shows
+///
+/// Serialized as `"composition"` or the output id, as in the preview URLs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PreviewTarget {
+    /// The composition, before any output transforms
+    Composition,
+    /// A single output
+    Output(OutputId),
+}
+
+impl From<OutputId> for PreviewTarget {
+    fn from(output_id: OutputId) -> Self {
+        Self::Output(output_id)
+    }
+}
+
+impl fmt::Display for PreviewTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Composition => write!(f, "composition"),
+            Self::Output(output_id) => write!(f, "{}", output_id),
+        }
+    }
+}
+
+impl FromStr for PreviewTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "composition" {
+            return Ok(Self::Composition);
+        }
+        s.parse()
+            .map(Self::Output)
+            .map_err(|_| format!("Invalid preview target '{}'", s))
+    }
+}
+
+impl TryFrom<String> for PreviewTarget {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<PreviewTarget> for String {
+    fn from(target: PreviewTarget) -> Self {
+        target.to_string()
+    }
+}
+
+/// A JPEG encoded preview frame
+#[derive(Debug, Clone)]
+#[cfg(feature = "http-api")]
+pub struct PreviewFrame {
+    /// Per-target frame number, starting at 1
+    pub sequence: u64,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// JPEG data
+    pub jpeg: Bytes,
+}
+
+/// Latest preview frame of the composition and every output
+///
+/// Cloning is cheap and every clone shares the same frames. Publishing never
+/// blocks on clients; a client that falls behind skips to the newest frame.
+#[derive(Clone, Default)]
+#[cfg(feature = "http-api")]
+pub struct PreviewHub {
+    targets: Arc<RwLock<HashMap<PreviewTarget, watch::Sender<PreviewFrame>>>>,
+}
+
+#[cfg(feature = "http-api")]
+impl PreviewHub {
+    /// Create an empty hub
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a sink that publishes the frames it is fed to `targets`
+    ///
+    /// The sink scales, rate limits and encodes the frames itself, so it can
+    /// be fed every rendered frame like any other output sink. Each frame is
+    /// encoded once and shared by all `targets`.
+    pub fn sink(&self, targets: Vec<PreviewTarget>) -> PreviewSink {
+        let hub = self.clone();
+        PreviewSink::new("Web Preview").on_frame(move |image| {
+            let jpeg = Bytes::from(image.jpeg.to_vec());
+            for target in &targets {
+                hub.publish(*target, image.width, image.height, jpeg.clone());
+            }
+        })
+    }
+
+    /// Publish a JPEG frame of the composition or an output
+    pub fn publish(
+        &self,
+        target: impl Into<PreviewTarget>,
+        width: u32,
+        height: u32,
+        jpeg: impl Into<Bytes>,
+    ) {
+        let target = target.into();
+        let jpeg = jpeg.into();
+        let mut targets = self.targets.write().unwrap_or_else(|e| e.into_inner());
+        match targets.get(&target) {
+            Some(sender) => {
+                sender.send_modify(|frame| {
+                    frame.sequence += 1;
+                    frame.width = width;
+                    frame.height = height;
+                    frame.jpeg = jpeg;
+                });
+            }
+            None => {
+                let frame = PreviewFrame {
+                    sequence: 1,
+                    width,
+                    height,
+                    jpeg,
+                };
+                targets.insert(target, watch::channel(frame).0);
+            }
+        }
+    }
+
+    /// Remove a preview, ending its streams
+    pub fn remove(&self, target: impl Into<PreviewTarget>) -> bool {
+        self.targets
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&target.into())
+            .is_some()
+    }
+
+    /// Targets with a preview, the composition first and then outputs in
+    /// ascending order
+    pub fn targets(&self) -> Vec<PreviewTarget> {
+        let mut targets: Vec<_> = self
+            .targets
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .copied()
+            .collect();
+        targets.sort_unstable();
+        targets
+    }
+
+    /// Newest frame of a target
+    pub fn latest(&self, target: impl Into<PreviewTarget>) -> Option<PreviewFrame> {
+        self.targets
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&target.into())
+            .map(|sender| sender.borrow().clone())
+    }
+
+    /// Subscribe to the frames of a target
+    pub fn subscribe(
+        &self,
+        target: impl Into<PreviewTarget>,
+    ) -> Option<watch::Receiver<PreviewFrame>> {
+        self.targets
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&target.into())
+            .map(watch::Sender::subscribe)
+    }
+}
+
+/// Query parameters of the preview endpoints
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PreviewQuery {
+    /// Maximum frames per second for this client
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fps: Option<f32>,
+}
+
+impl PreviewQuery {
+    /// Minimum time between two frames sent to this client
+    pub fn min_interval(&self) -> std::time::Duration {
+        self.fps
+            .filter(|fps| fps.is_finite() && *fps > 0.0)
+            .map(|fps| std::time::Duration::from_secs_f32(1.0 / fps.max(0.1)))
+            .unwrap_or_default()
+    }
+}
+
+/// GET /preview - List the targets with a preview
+#[cfg(feature = "http-api")]
+pub async fn list_previews(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    uri: Uri,
+) -> Result<Json<ApiResponse<Vec<PreviewTarget>>>, StatusCode> {
+    check_auth(&state, &headers, &uri).await?;
+    Ok(Json(ApiResponse::success(state.previews.targets())))
+}
+
+/// GET /preview/:target - MJPEG stream of the composition or an output
+#[cfg(feature = "http-api")]
+pub async fn preview_stream(
+    Path(target): Path<PreviewTarget>,
+    Query(query): Query<PreviewQuery>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    uri: Uri,
+) -> Result<Response, StatusCode> {
+    check_auth(&state, &headers, &uri).await?;
+    let receiver = state
+        .previews
+        .subscribe(target)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    tracing::debug!("MJPEG preview client connected to {}", target);
+    let body = Body::from_stream(mjpeg_stream(receiver, query.min_interval()));
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                format!("multipart/x-mixed-replace; boundary={}", MJPEG_BOUNDARY),
+            ),
+            (header::CACHE_CONTROL, "no-cache, no-store".to_string()),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// GET /preview/:target/snapshot - Newest frame of the composition or an
+/// output as a JPEG
+#[cfg(feature = "http-api")]
+pub async fn preview_snapshot(
+    Path(target): Path<PreviewTarget>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    uri: Uri,
+) -> Result<Response, StatusCode> {
+    check_auth(&state, &headers, &uri).await?;
+    let frame = state.previews.latest(target).ok_or(StatusCode::NOT_FOUND)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/jpeg"),
+            (header::CACHE_CONTROL, "no-cache, no-store"),
+        ],
+        frame.jpeg,
+    )
+        .into_response())
+}
+
+/// Multipart body part carrying one frame
+#[cfg(feature = "http-api")]
+fn mjpeg_part(frame: &PreviewFrame) -> Bytes {
+    let header = format!(
+        "--{}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+        MJPEG_BOUNDARY,
+        frame.jpeg.len()
+    );
+    let mut part = Vec::with_capacity(header.len() + frame.jpeg.len() + 2);
+    part.extend_from_slice(header.as_bytes());
+    part.extend_from_slice(&frame.jpeg);
+    part.extend_from_slice(b"\r\n");
+    part.into()
+}
+
+/// Stream of multipart parts, starting with the current frame and then at
+/// most one frame per `min_interval`
+#[cfg(feature = "http-api")]
+fn mjpeg_stream(
+    receiver: watch::Receiver<PreviewFrame>,
+    min_interval: Duration,
+) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    futures::stream::unfold(
+        (receiver, None::<Instant>),
+        move |(mut receiver, last_sent)| async move {
+            if let Some(last_sent) = last_sent {
+                // Ends the stream once the preview is removed
+                receiver.changed().await.ok()?;
+                let due = last_sent + min_interval;
+                if Instant::now() < due {
+                    tokio::time::sleep_until(due.into()).await;
+                }
+            }
+            let part = mjpeg_part(&receiver.borrow_and_update());
+            Some((Ok(part), (receiver, Some(Instant::now()))))
+        },
+    )
+}
+
+#[cfg(all(test, feature = "http-api"))]
+mod tests {
+    use super::*;
+    use crate::web::auth::AuthConfig;
+    use futures::StreamExt;
+    use tokio::sync::RwLock as AsyncRwLock;
+
+    const JPEG: &[u8] = &[0xFF, 0xD8, 0x00, 0xFF, 0xD9];
+
+    fn state(auth: AuthConfig) -> AppState {
+        AppState {
+            auth: Arc::new(AsyncRwLock::new(auth)),
+            previews: PreviewHub::new(),
+            commands: tokio::sync::mpsc::unbounded_channel().0,
+        }
+    }
+
+    fn uri(path: &str) -> Uri {
+        path.parse().unwrap()
+    }
+
+    #[test]
+    fn test_hub_publish_and_remove() {
+        let hub = PreviewHub::new();
+        assert!(hub.latest(1).is_none());
+        assert!(hub.subscribe(1).is_none());
+
+        hub.publish(2, 64, 36, JPEG);
+        hub.publish(1, 64, 36, JPEG);
+        hub.publish(1, 32, 18, &[0xFF, 0xD8][..]);
+        hub.publish(PreviewTarget::Composition, 64, 36, JPEG);
+        assert_eq!(
+            hub.targets(),
+            vec![
+                PreviewTarget::Composition,
+                PreviewTarget::Output(1),
+                PreviewTarget::Output(2)
+            ]
+        );
+
+        let frame = hub.latest(1).unwrap();
+        assert_eq!(frame.sequence, 2);
+        assert_eq!((frame.width, frame.height), (32, 18));
+        assert_eq!(frame.jpeg.len(), 2);
+
+        assert!(hub.remove(2));
+        assert!(!hub.remove(2));
+        assert!(hub.remove(PreviewTarget::Composition));
+        assert_eq!(hub.targets(), vec![PreviewTarget::Output(1)]);
+    }
+
+    #[test]
+    fn test_target_parsing() {
+        assert_eq!(
+            "composition".parse::<PreviewTarget>(),
+            Ok(PreviewTarget::Composition)
+        );
+        assert_eq!("3".parse::<PreviewTarget>(), Ok(PreviewTarget::Output(3)));
+        assert!("main".parse::<PreviewTarget>().is_err());
+        assert_eq!(PreviewTarget::Output(3).to_string(), "3");
+
+        let json =
+            serde_json::to_string(&vec![PreviewTarget::Composition, PreviewTarget::Output(3)])
+                .unwrap();
+        assert_eq!(json, r#"["composition","3"]"#);
+        let parsed: Vec<PreviewTarget> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            parsed,
+            vec![PreviewTarget::Composition, PreviewTarget::Output(3)]
+        );
+    }
+
+    #[test]
+    fn test_sink_publishes_to_targets() {
+        use mapmap_io::{PixelFormat, VideoFormat, VideoFrame, VideoSink};
+
+        let hub = PreviewHub::new();
+        let mut sink = hub.sink(vec![PreviewTarget::Composition, PreviewTarget::Output(2)]);
+        let format = VideoFormat::new(1280, 720, PixelFormat::RGBA8, 60.0);
+        let frame = VideoFrame::new(vec![128; format.buffer_size()], format, Duration::ZERO);
+        sink.send_frame(&frame).unwrap();
+
+        let composition = hub.latest(PreviewTarget::Composition).unwrap();
+        assert_eq!((composition.width, composition.height), (640, 360));
+        assert!(composition.jpeg.starts_with(&[0xFF, 0xD8]));
+        assert_eq!(hub.latest(2).unwrap().jpeg, composition.jpeg);
+        assert!(hub.latest(1).is_none());
+    }
+
+    #[test]
+    fn test_query_interval() {
+        assert_eq!(PreviewQuery::default().min_interval(), Duration::ZERO);
+        let query = PreviewQuery { fps: Some(4.0) };
+        assert_eq!(query.min_interval(), Duration::from_millis(250));
+        assert_eq!(
+            PreviewQuery { fps: Some(0.0) }.min_interval(),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot() {
+        let state = state(AuthConfig::new());
+        let missing = preview_snapshot(
+            Path(PreviewTarget::Output(7)),
+            State(state.clone()),
+            HeaderMap::new(),
+            uri("/preview/7/snapshot"),
+        )
+        .await;
+        assert_eq!(missing.unwrap_err(), StatusCode::NOT_FOUND);
+
+        state.previews.publish(7, 64, 36, JPEG);
+        let response = preview_snapshot(
+            Path(PreviewTarget::Output(7)),
+            State(state),
+            HeaderMap::new(),
+            uri("/preview/7/snapshot"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/jpeg");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], JPEG);
+    }
+
+    #[tokio::test]
+    async fn test_auth_required() {
+        let state = state(AuthConfig::with_keys(vec!["secret".to_string()]));
+        state.previews.publish(1, 64, 36, JPEG);
+
+        let denied = preview_snapshot(
+            Path(PreviewTarget::Output(1)),
+            State(state.clone()),
+            HeaderMap::new(),
+            uri("/preview/1/snapshot"),
+        )
+        .await;
+        assert_eq!(denied.unwrap_err(), StatusCode::UNAUTHORIZED);
+
+        let allowed = preview_snapshot(
+            Path(PreviewTarget::Output(1)),
+            State(state.clone()),
+            HeaderMap::new(),
+            uri("/preview/1/snapshot?api_key=secret"),
+        )
+        .await;
+        assert!(allowed.is_ok());
+
+        let listed = list_previews(State(state), HeaderMap::new(), uri("/preview"))
+            .await
+            .map(|_| ());
+        assert_eq!(listed.unwrap_err(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_mjpeg_stream() {
+        let state = state(AuthConfig::new());
+        state.previews.publish(3, 64, 36, JPEG);
+
+        let response = preview_stream(
+            Path(PreviewTarget::Output(3)),
+            Query(PreviewQuery::default()),
+            State(state.clone()),
+            HeaderMap::new(),
+            uri("/preview/3"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "multipart/x-mixed-replace; boundary=mapflowframe"
+        );
+
+        let mut body = response.into_body().into_data_stream();
+        let first = body.next().await.unwrap().unwrap();
+        assert!(first.starts_with(b"--mapflowframe\r\nContent-Type: image/jpeg\r\n"));
+        assert!(first.ends_with(&[0xFF, 0xD9, b'\r', b'\n']));
+
+        state
+            .previews
+            .publish(3, 64, 36, &[0xFF, 0xD8, 0x01, 0xFF, 0xD9][..]);
+        let second = body.next().await.unwrap().unwrap();
+        assert!(second.windows(3).any(|window| window == [0xD8, 0x01, 0xFF]));
+
+        // Removing the output ends the stream
+        state.previews.remove(3);
+        assert!(body.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_unknown_output() {
+        let result = preview_stream(
+            Path(PreviewTarget::Output(9)),
+            Query(PreviewQuery::default()),
+            State(state(AuthConfig::new())),
+            HeaderMap::new(),
+            uri("/preview/9"),
+        )
+        .await;
+        assert_eq!(result.unwrap_err(), StatusCode::NOT_FOUND);
+    }
+}
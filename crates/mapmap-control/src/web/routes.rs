@@ -3,16 +3,22 @@
 #[cfg(feature = "http-api")]
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode, Uri},
     response::Json,
-    routing::{get, patch},
+    routing::{get, patch, post},
     Router,
 };
 
+#[cfg(feature = "http-api")]
+use super::auth::check_auth;
 #[cfg(feature = "http-api")]
 use super::handlers::{ApiResponse, LayerInfo, StatusResponse, UpdateLayerRequest};
 #[cfg(feature = "http-api")]
-use super::server::AppState;
+use super::preview::{list_previews, preview_snapshot, preview_stream};
+#[cfg(feature = "http-api")]
+use super::server::{AppState, WebCommand};
+#[cfg(feature = "http-api")]
+use crate::error::ControlError;
 
 /// Build the API router
 #[cfg(feature = "http-api")]
@@ -24,6 +30,10 @@ pub fn build_router() -> Router<AppState> {
         .route("/api/layers/:id", patch(update_layer))
         .route("/api/paints", get(get_paints))
         .route("/api/effects", get(get_effects))
+        .route("/api/macros/:name/run", post(run_macro))
+        .route("/preview", get(list_previews))
+        .route("/preview/:target", get(preview_stream))
+        .route("/preview/:target/snapshot", get(preview_snapshot))
 }
 
 #[cfg(not(feature = "http-api"))]
@@ -127,17 +137,60 @@ async fn get_effects(State(_state): State<AppState>) -> Json<ApiResponse<Vec<Str
     Json(ApiResponse::success(effects))
 }
 
+/// POST /api/macros/:name/run - Run a stored macro
+#[cfg(feature = "http-api")]
+async fn run_macro(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    uri: Uri,
+) -> Result<Json<ApiResponse<String>>, StatusCode> {
+    check_auth(&state, &headers, &uri).await?;
+    if name.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // The control manager answers on its next update
+    let (reply, response) = tokio::sync::oneshot::channel();
+    state
+        .commands
+        .send(WebCommand::RunMacro {
+            name: name.clone(),
+            reply,
+        })
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    match response.await {
+        Ok(Ok(())) => Ok(Json(ApiResponse::success(name))),
+        Ok(Err(ControlError::TargetNotFound(_))) => Err(StatusCode::NOT_FOUND),
+        Ok(Err(e)) => {
+            tracing::warn!("Running macro {} failed: {}", name, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        // No control manager is taking requests
+        Err(_) => Err(StatusCode::SERVICE_UNAVAILABLE),
+    }
+}
+
 #[cfg(all(test, feature = "http-api"))]
 mod tests {
     use super::*;
     use std::sync::Arc;
-    use tokio::sync::RwLock;
+    use tokio::sync::{mpsc, RwLock};
 
-    #[tokio::test]
-    async fn test_get_status() {
+    fn test_state() -> (AppState, mpsc::UnboundedReceiver<WebCommand>) {
+        let (commands, receiver) = mpsc::unbounded_channel();
         let state = AppState {
             auth: Arc::new(RwLock::new(super::super::auth::AuthConfig::new())),
+            previews: super::super::preview::PreviewHub::new(),
+            commands,
         };
+        (state, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_get_status() {
+        let (state, _commands) = test_state();
 
         let response = get_status(State(state)).await;
         assert!(response.0.success);
@@ -145,12 +198,84 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_layers() {
-        let state = AppState {
-            auth: Arc::new(RwLock::new(super::super::auth::AuthConfig::new())),
-        };
+        let (state, _commands) = test_state();
 
         let response = get_layers(State(state)).await;
         assert!(response.0.success);
         assert!(response.0.data.is_some());
     }
+
+    #[tokio::test]
+    async fn test_run_macro() {
+        use crate::shortcuts::Macro;
+        use crate::ControlManager;
+
+        let (state, commands) = test_state();
+        let mut manager = ControlManager::new();
+        manager.key_bindings.add_macro(Macro {
+            name: "Intro".to_string(),
+            description: String::new(),
+            actions: Vec::new(),
+            created_at: String::new(),
+            steps: Vec::new(),
+        });
+        manager.set_web_commands(commands);
+
+        let run = |name: &str| {
+            tokio::spawn(run_macro(
+                Path(name.to_string()),
+                State(state.clone()),
+                HeaderMap::new(),
+                Uri::from_static("/api/macros/run"),
+            ))
+        };
+        let (found, missing) = (run("Intro"), run("Missing"));
+        while !(found.is_finished() && missing.is_finished()) {
+            manager.update();
+            tokio::task::yield_now().await;
+        }
+
+        assert!(found.await.unwrap().is_ok());
+        assert_eq!(missing.await.unwrap().unwrap_err(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_run_macro_without_manager() {
+        let (state, commands) = test_state();
+        drop(commands);
+
+        let result = run_macro(
+            Path("Intro".to_string()),
+            State(state),
+            HeaderMap::new(),
+            Uri::from_static("/api/macros/Intro/run"),
+        )
+        .await;
+        assert_eq!(result.unwrap_err(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_run_macro_requires_api_key() {
+        let (state, mut commands) = test_state();
+        state.auth.write().await.add_key("secret".to_string());
+        let run = |query: &'static str| {
+            run_macro(
+                Path("Intro".to_string()),
+                State(state.clone()),
+                HeaderMap::new(),
+                Uri::from_static(query),
+            )
+        };
+
+        let result = run("/api/macros/Intro/run?api_key=wrong").await;
+        assert_eq!(result.unwrap_err(), StatusCode::UNAUTHORIZED);
+        assert!(commands.try_recv().is_err());
+
+        let authorized = tokio::spawn(run("/api/macros/Intro/run?api_key=secret"));
+        let Some(WebCommand::RunMacro { reply, .. }) = commands.recv().await else {
+            panic!("macro request not forwarded");
+        };
+        reply.send(Ok(())).unwrap();
+        assert!(authorized.await.unwrap().is_ok());
+    }
 }
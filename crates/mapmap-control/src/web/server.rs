@@ -6,28 +6,46 @@ use axum::http::{header, Method};
 #[cfg(feature = "http-api")]
 use tower_http::cors::{Any, CorsLayer};
 
-use std::net::SocketAddr;
 use std::sync::Arc;
 
 #[cfg(feature = "http-api")]
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, oneshot, RwLock};
 
 use crate::{error::ControlError, Result};
 
 use super::auth::AuthConfig;
 #[cfg(feature = "http-api")]
+use super::preview::PreviewHub;
+#[cfg(feature = "http-api")]
 use super::routes::build_router;
 #[cfg(feature = "http-api")]
 use super::websocket::ws_handler;
 
+/// Request the web API hands to the control manager
+#[derive(Debug)]
+#[cfg(feature = "http-api")]
+pub enum WebCommand {
+    /// Start a stored macro; the reply is an error if there is no such macro
+    RunMacro {
+        name: String,
+        reply: oneshot::Sender<Result<()>>,
+    },
+}
+
 /// Application state shared across all requests
 #[derive(Clone)]
 #[cfg(feature = "http-api")]
 pub struct AppState {
     pub auth: Arc<RwLock<AuthConfig>>,
+    pub previews: PreviewHub,
+    /// Requests answered by the control manager
+    pub commands: mpsc::UnboundedSender<WebCommand>,
 }
 
 /// Web server configuration
+///
+/// Binds to the loopback interface without CORS by default. Binding to any
+/// other address requires an API key.
 #[derive(Debug, Clone)]
 pub struct WebServerConfig {
     pub host: String,
@@ -39,9 +57,9 @@ pub struct WebServerConfig {
 impl Default for WebServerConfig {
     fn default() -> Self {
         Self {
-            host: "0.0.0.0".to_string(),
+            host: "127.0.0.1".to_string(),
             port: 8080,
-            enable_cors: true,
+            enable_cors: false,
             auth: AuthConfig::new(),
         }
     }
@@ -73,19 +91,81 @@ impl WebServerConfig {
         self.auth = auth;
         self
     }
+
+    /// Check if the server only accepts connections from this machine
+    pub fn is_loopback(&self) -> bool {
+        self.host == "localhost"
+            || self
+                .host
+                .parse::<std::net::IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    }
+
+    /// Reject configurations that expose the API to the network without
+    /// an API key
+    pub fn validate(&self) -> Result<()> {
+        if !self.is_loopback() && !self.auth.is_enabled() {
+            return Err(ControlError::HttpError(format!(
+                "An API key is required to serve the web API on {}",
+                self.host
+            )));
+        }
+        Ok(())
+    }
+
+    /// Bind the listener, looking up host names such as "localhost"
+    #[cfg(feature = "http-api")]
+    async fn bind(&self) -> Result<tokio::net::TcpListener> {
+        // IP addresses are used as given, so "::1" needs no brackets
+        tokio::net::TcpListener::bind((self.host.as_str(), self.port))
+            .await
+            .map_err(|e| {
+                ControlError::HttpError(format!(
+                    "Failed to bind {} port {}: {}",
+                    self.host, self.port, e
+                ))
+            })
+    }
 }
 
 /// Web server for REST API and WebSocket
 pub struct WebServer {
     #[cfg(feature = "http-api")]
     config: WebServerConfig,
+    #[cfg(feature = "http-api")]
+    previews: PreviewHub,
+    #[cfg(feature = "http-api")]
+    commands: mpsc::UnboundedSender<WebCommand>,
+    #[cfg(feature = "http-api")]
+    command_receiver: Option<mpsc::UnboundedReceiver<WebCommand>>,
 }
 
 impl WebServer {
     /// Create a new web server
     #[cfg(feature = "http-api")]
     pub fn new(config: WebServerConfig) -> Self {
-        Self { config }
+        let (commands, command_receiver) = mpsc::unbounded_channel();
+        Self {
+            config,
+            previews: PreviewHub::new(),
+            commands,
+            command_receiver: Some(command_receiver),
+        }
+    }
+
+    /// Get the hub that output previews are published to
+    #[cfg(feature = "http-api")]
+    pub fn previews(&self) -> PreviewHub {
+        self.previews.clone()
+    }
+
+    /// Take the requests for the control manager
+    ///
+    /// Hand them to [`ControlManager::set_web_commands`](crate::ControlManager::set_web_commands),
+    /// which answers them on every update. Returns `None` after the first call.
+    #[cfg(feature = "http-api")]
+    pub fn take_commands(&mut self) -> Option<mpsc::UnboundedReceiver<WebCommand>> {
+        self.command_receiver.take()
     }
 
     #[cfg(not(feature = "http-api"))]
@@ -96,12 +176,13 @@ impl WebServer {
     /// Run the web server (blocking)
     #[cfg(feature = "http-api")]
     pub async fn run(self) -> Result<()> {
-        let addr: SocketAddr = format!("{}:{}", self.config.host, self.config.port)
-            .parse()
-            .map_err(|e| ControlError::HttpError(format!("Invalid address: {}", e)))?;
+        self.config.validate()?;
+        let listener = self.config.bind().await?;
 
         let state = AppState {
             auth: Arc::new(RwLock::new(self.config.auth.clone())),
+            previews: self.previews,
+            commands: self.commands,
         };
 
         // Build router with state
@@ -121,12 +202,9 @@ impl WebServer {
             app
         };
 
-        tracing::info!("Web server listening on {}", addr);
-
-        // Bind to the TCP listener
-        let listener = tokio::net::TcpListener::bind(addr)
-            .await
-            .map_err(|e| ControlError::HttpError(format!("Failed to bind: {}", e)))?;
+        if let Ok(addr) = listener.local_addr() {
+            tracing::info!("Web server listening on {}", addr);
+        }
 
         // Serve the application - use into_make_service()
         let make_service = app.into_make_service();
@@ -173,6 +251,46 @@ mod tests {
         assert!(!config.enable_cors);
     }
 
+    #[test]
+    fn test_network_bind_requires_api_key() {
+        let config = WebServerConfig::new(8080);
+        assert!(config.is_loopback());
+        assert!(!config.enable_cors);
+        assert!(config.validate().is_ok());
+
+        let config = config.with_host("0.0.0.0".to_string());
+        assert!(config.validate().is_err());
+        assert!(config
+            .with_auth(AuthConfig::with_keys(vec!["secret".to_string()]))
+            .validate()
+            .is_ok());
+
+        assert!(WebServerConfig::new(8080)
+            .with_host("::1".to_string())
+            .validate()
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bind_loopback_hosts() {
+        for host in ["localhost", "::1", "127.0.0.1"] {
+            let config = WebServerConfig::new(0).with_host(host.to_string());
+            assert!(config.validate().is_ok());
+            let listener = config.bind().await.unwrap();
+            assert!(
+                listener.local_addr().unwrap().ip().is_loopback(),
+                "{}",
+                host
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_network_bind_without_key_does_not_start() {
+        let server = WebServer::new(WebServerConfig::new(0).with_host("0.0.0.0".to_string()));
+        assert!(server.run().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_web_server_creation() {
         let config = WebServerConfig::new(18080);
@@ -44,6 +44,8 @@ pub mod cpal_backend {
         command_sender: Sender<Command>,
         #[allow(dead_code)]
         stream: cpal::Stream,
+        sample_rate: u32,
+        channels: u16,
     }
 
     impl CpalBackend {
@@ -54,7 +56,7 @@ pub mod cpal_backend {
             let (command_tx, command_rx) = unbounded::<Command>();
 
             // Build stream directly in main thread (cpal::Stream is not Send)
-            let stream = Self::build_stream(device_name, sample_tx)?;
+            let (stream, sample_rate, channels) = Self::build_stream(device_name, sample_tx)?;
 
             // Spawn command processing thread
             std::thread::Builder::new()
@@ -69,14 +71,28 @@ pub mod cpal_backend {
                 sample_receiver: sample_rx,
                 command_sender: command_tx,
                 stream,
+                sample_rate,
+                channels,
             })
         }
 
+        /// Get the sample rate of the input stream in Hz
+        pub fn sample_rate(&self) -> u32 {
+            self.sample_rate
+        }
+
+        /// Get the number of interleaved channels in the samples
+        pub fn channels(&self) -> u16 {
+            self.channels
+        }
+
         /// Build the audio stream (must be called from main thread)
+        ///
+        /// Returns the stream with its sample rate and channel count.
         fn build_stream(
             device_name: Option<String>,
             sample_tx: Sender<Vec<f32>>,
-        ) -> Result<cpal::Stream, AudioError> {
+        ) -> Result<(cpal::Stream, u32, u16), AudioError> {
             let host = cpal::default_host();
 
             // Get device
@@ -117,6 +133,8 @@ pub mod cpal_backend {
                 }
             };
 
+            let sample_rate = config.sample_rate().0;
+            let channels = config.channels();
             let err_fn = |err| eprintln!("Audio stream error: {}", err);
 
             // Build stream
@@ -177,7 +195,7 @@ pub mod cpal_backend {
                             e
                         )));
                     }
-                    Ok(stream)
+                    Ok((stream, sample_rate, channels))
                 }
                 Err(e) => Err(AudioError::StreamBuildError(e.to_string())),
             }
@@ -8,8 +8,9 @@
 //! - Configurable latency compensation for audio-video sync
 //! - Audio-reactive parameter mapping
 //! - Ring buffer for historical analysis data
+//! - Sample feeds from other sources, such as the soundtrack of a clip
 
-use crate::audio::{AudioAnalysis, AudioAnalyzer, AudioConfig};
+use crate::audio::{AudioAnalysis, AudioAnalyzer, AudioConfig, AudioSource};
 use crate::audio_reactive::AudioReactiveController;
 use crossbeam_channel::{Receiver, Sender, TryRecvError};
 use parking_lot::RwLock;
@@ -82,6 +83,12 @@ pub struct AudioMediaPipeline {
     /// Channel to receive analyzed data
     analysis_receiver: Receiver<AudioAnalysis>,
 
+    /// Where the analyzed samples come from
+    source: AudioSource,
+
+    /// Samples pulled on every analysis request, see `set_feed`
+    feed: Option<Receiver<Vec<f32>>>,
+
     /// Pipeline configuration
     config: AudioPipelineConfig,
 
@@ -159,6 +166,8 @@ impl AudioMediaPipeline {
             reactive_controller,
             sample_sender: sample_tx,
             analysis_receiver: analysis_rx,
+            source: AudioSource::SystemInput,
+            feed: None,
             config: pipeline_config.clone(),
             analysis_buffer: VecDeque::with_capacity(pipeline_config.analysis_buffer_size),
             audio_position: 0.0,
@@ -189,8 +198,51 @@ impl AudioMediaPipeline {
         }
     }
 
+    /// Analyze the samples arriving on `feed`, tagged as coming from `source`
+    ///
+    /// The feed is drained on every [`get_analysis`](Self::get_analysis) and
+    /// its mono samples should be at the configured sample rate. The
+    /// soundtrack of a clip is connected by `AudioTrack::connect_pipeline`
+    /// in `mapmap-media` as `AudioSource::VideoAudio`.
+    pub fn set_feed(&mut self, source: AudioSource, feed: Receiver<Vec<f32>>) {
+        self.source = source;
+        self.feed = Some(feed);
+        self.seek(0.0);
+    }
+
+    /// Stop pulling from the feed and analyze pushed system input again
+    pub fn clear_feed(&mut self) {
+        self.source = AudioSource::SystemInput;
+        self.feed = None;
+    }
+
+    /// Get where the analyzed samples come from
+    pub fn source(&self) -> AudioSource {
+        self.source
+    }
+
+    /// Forward the samples waiting on the feed to the analyzer
+    fn poll_feed(&mut self) {
+        let Some(feed) = self.feed.clone() else {
+            return;
+        };
+        loop {
+            match feed.try_recv() {
+                Ok(samples) => self.process_samples(&samples),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    // The clip was closed
+                    self.clear_feed();
+                    break;
+                }
+            }
+        }
+    }
+
     /// Get the latest audio analysis (with latency compensation)
     pub fn get_analysis(&mut self) -> Option<AudioAnalysis> {
+        self.poll_feed();
+
         // Drain all available analyses into buffer
         loop {
             match self.analysis_receiver.try_recv() {
@@ -351,4 +403,23 @@ mod tests {
         pipeline.set_latency_compensation(50.0);
         assert_eq!(pipeline.latency_compensation(), 50.0);
     }
+
+    #[test]
+    fn test_feed_is_analyzed_as_its_source() {
+        let mut pipeline = AudioMediaPipeline::new(AudioConfig::default());
+        assert_eq!(pipeline.source(), AudioSource::SystemInput);
+
+        let (tx, rx) = crossbeam_channel::bounded(4);
+        pipeline.set_feed(AudioSource::VideoAudio, rx);
+        assert_eq!(pipeline.source(), AudioSource::VideoAudio);
+
+        tx.send(vec![0.5; 4410]).unwrap();
+        pipeline.get_analysis();
+        assert!((pipeline.audio_position() - 0.1).abs() < 1e-9);
+
+        // Closing the clip falls back to system input
+        drop(tx);
+        pipeline.get_analysis();
+        assert_eq!(pipeline.source(), AudioSource::SystemInput);
+    }
 }
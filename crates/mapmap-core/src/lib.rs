@@ -35,6 +35,7 @@ pub mod module;
 pub mod oscillator;
 pub mod shader_graph;
 pub mod state;
+pub mod timecode;
 
 pub use animation::{
     AnimValue, AnimationClip, AnimationPlayer, AnimationTrack, InterpolationMode, Keyframe,
@@ -69,6 +70,7 @@ pub use shader_graph::{
     ShaderNode,
 };
 pub use state::{AppSettings, AppState};
+pub use timecode::{Timecode, TimecodeError, TimecodeRate};
 
 /// Core error types
 #[derive(Error, Debug)]
@@ -661,6 +661,9 @@ pub struct ModuleManager {
     modules: HashMap<ModuleId, MapFlowModule>,
     next_module_id: ModuleId,
     next_part_id: ModulePartId,
+    /// Module currently driving the show (if any)
+    #[serde(default)]
+    active_module: Option<ModuleId>,
     #[serde(skip)]
     color_palette: Vec<[f32; 4]>,
     next_color_index: usize,
@@ -671,6 +674,7 @@ impl PartialEq for ModuleManager {
         self.modules == other.modules
             && self.next_module_id == other.next_module_id
             && self.next_part_id == other.next_part_id
+            && self.active_module == other.active_module
             && self.next_color_index == other.next_color_index
     }
 }
@@ -681,6 +685,7 @@ impl ModuleManager {
             modules: HashMap::new(),
             next_module_id: 1,
             next_part_id: 1,
+            active_module: None,
             color_palette: vec![
                 [1.0, 0.2, 0.2, 1.0],
                 [1.0, 0.5, 0.2, 1.0],
@@ -725,6 +730,19 @@ impl ModuleManager {
 
     pub fn delete_module(&mut self, id: ModuleId) {
         self.modules.remove(&id);
+        if self.active_module == Some(id) {
+            self.active_module = None;
+        }
+    }
+
+    /// Set the module currently driving the show
+    pub fn set_active_module(&mut self, id: Option<ModuleId>) {
+        self.active_module = id.filter(|id| self.modules.contains_key(id));
+    }
+
+    /// Get the module currently driving the show
+    pub fn active_module(&self) -> Option<ModuleId> {
+        self.active_module
     }
 
     pub fn list_modules(&self) -> Vec<&MapFlowModule> {
@@ -20,7 +20,7 @@ pub enum PaintType {
     TestPattern,
     /// Solid color
     Color,
-    /// Camera/capture device
+    /// Camera/capture device or live network stream
     Camera,
 }
 
@@ -31,7 +31,7 @@ pub struct Paint {
     pub name: String,
     pub paint_type: PaintType,
 
-    /// Source path (for Video/Image types)
+    /// Source path (for Video/Image types) or stream URL (for Camera type)
     pub source_path: Option<String>,
 
     /// Playback rate (for Video type)
@@ -88,6 +88,25 @@ impl Paint {
         paint
     }
 
+    /// Create a live network stream paint (RTSP, HLS, UDP/RTP MPEG-TS, SRT)
+    pub fn stream(id: PaintId, name: impl Into<String>, url: impl Into<String>) -> Self {
+        let mut paint = Self::new(id, name, PaintType::Camera);
+        paint.source_path = Some(url.into());
+        paint.loop_playback = false;
+        paint.is_playing = true;
+        paint
+    }
+
+    /// Create a paint for local capture device `device_id` (webcam, capture card)
+    pub fn capture_device(id: PaintId, name: impl Into<String>, device_id: u32) -> Self {
+        Self::stream(id, name, format!("device://{}", device_id))
+    }
+
+    /// Returns true if the paint shows a live input rather than a file
+    pub fn is_live(&self) -> bool {
+        self.paint_type == PaintType::Camera
+    }
+
     /// Create a test pattern paint
     pub fn test_pattern(id: PaintId, name: impl Into<String>) -> Self {
         Self::new(id, name, PaintType::TestPattern)
@@ -178,6 +197,22 @@ mod tests {
         assert_eq!(paint.paint_type, PaintType::TestPattern);
     }
 
+    #[test]
+    fn test_stream_paint() {
+        let paint = Paint::stream(1, "Stage Cam", "rtsp://10.0.0.20/stream1");
+        assert_eq!(paint.paint_type, PaintType::Camera);
+        assert_eq!(
+            paint.source_path.as_deref(),
+            Some("rtsp://10.0.0.20/stream1")
+        );
+        assert!(paint.is_live());
+        assert!(!Paint::video(2, "Clip", "clip.mp4").is_live());
+
+        let webcam = Paint::capture_device(3, "Webcam", 1);
+        assert!(webcam.is_live());
+        assert_eq!(webcam.source_path.as_deref(), Some("device://1"));
+    }
+
     #[test]
     fn test_paint_manager() {
         let mut manager = PaintManager::new();
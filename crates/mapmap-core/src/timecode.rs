@@ -0,0 +1,403 @@
+//! SMPTE timecode shared by timecode input and output
+//!
+//! A frame-accurate SMPTE position used both for chasing external MTC/LTC
+//! (mapmap-control) and for stamping output frames (mapmap-io), so a received
+//! position can drive the output clock without any conversion.
+//!
+//! Drop-frame counting is supported for the NTSC rates (29.97 and 59.94 fps).
+//! It skips frame numbers at the start of every minute except every tenth
+//! minute, which keeps the displayed time in step with the wall clock.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Timecode errors
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TimecodeError {
+    #[error("Invalid timecode rate: {0}")]
+    InvalidRate(String),
+
+    #[error("Invalid timecode: {0}")]
+    InvalidTimecode(String),
+}
+
+type Result<T> = std::result::Result<T, TimecodeError>;
+
+/// Frame rate of a timecode, as an exact fraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TimecodeRate {
+    numerator: u32,
+    denominator: u32,
+    drop_frame: bool,
+}
+
+impl TimecodeRate {
+    /// 23.976 fps (24000/1001).
+    pub const FPS_23_976: Self = Self::exact(24_000, 1001, false);
+    /// 24 fps.
+    pub const FPS_24: Self = Self::exact(24, 1, false);
+    /// 25 fps.
+    pub const FPS_25: Self = Self::exact(25, 1, false);
+    /// 29.97 fps non-drop-frame.
+    pub const FPS_29_97: Self = Self::exact(30_000, 1001, false);
+    /// 29.97 fps drop-frame.
+    pub const FPS_29_97_DF: Self = Self::exact(30_000, 1001, true);
+    /// 30 fps.
+    pub const FPS_30: Self = Self::exact(30, 1, false);
+    /// 50 fps.
+    pub const FPS_50: Self = Self::exact(50, 1, false);
+    /// 59.94 fps non-drop-frame.
+    pub const FPS_59_94: Self = Self::exact(60_000, 1001, false);
+    /// 59.94 fps drop-frame.
+    pub const FPS_59_94_DF: Self = Self::exact(60_000, 1001, true);
+    /// 60 fps.
+    pub const FPS_60: Self = Self::exact(60, 1, false);
+
+    const fn exact(numerator: u32, denominator: u32, drop_frame: bool) -> Self {
+        Self {
+            numerator,
+            denominator,
+            drop_frame,
+        }
+    }
+
+    /// Creates a rate of `numerator / denominator` frames per second.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TimecodeError::InvalidRate` if the rate is zero, or if drop-frame
+    /// is requested for a rate other than 29.97 or 59.94 fps.
+    pub fn new(numerator: u32, denominator: u32, drop_frame: bool) -> Result<Self> {
+        if numerator == 0 || denominator == 0 {
+            return Err(TimecodeError::InvalidRate(format!(
+                "{}/{}",
+                numerator, denominator
+            )));
+        }
+        let rate = Self::exact(numerator, denominator, false);
+        if drop_frame && !rate.supports_drop_frame() {
+            return Err(TimecodeError::InvalidRate(format!(
+                "drop-frame is not defined for {:.3} fps",
+                rate.fps()
+            )));
+        }
+        Ok(Self { drop_frame, ..rate })
+    }
+
+    /// Returns the standard rate closest to `fps`, or a whole-number rate for
+    /// non-standard values.
+    ///
+    /// NTSC rates are returned as non-drop-frame; use
+    /// [`with_drop_frame`](Self::with_drop_frame) to switch.
+    pub fn from_fps(fps: f32) -> Self {
+        const STANDARD: [TimecodeRate; 8] = [
+            TimecodeRate::FPS_23_976,
+            TimecodeRate::FPS_24,
+            TimecodeRate::FPS_25,
+            TimecodeRate::FPS_29_97,
+            TimecodeRate::FPS_30,
+            TimecodeRate::FPS_50,
+            TimecodeRate::FPS_59_94,
+            TimecodeRate::FPS_60,
+        ];
+        STANDARD
+            .into_iter()
+            .find(|rate| (rate.fps() - fps as f64).abs() < 0.005)
+            .unwrap_or_else(|| Self::exact((fps.round() as u32).max(1), 1, false))
+    }
+
+    /// Returns this rate with drop-frame counting switched on or off.
+    ///
+    /// Drop-frame is ignored for rates that do not support it.
+    pub fn with_drop_frame(mut self, drop_frame: bool) -> Self {
+        self.drop_frame = drop_frame && self.supports_drop_frame();
+        self
+    }
+
+    /// Returns true if drop-frame counting is defined for this rate.
+    pub fn supports_drop_frame(&self) -> bool {
+        self.denominator == 1001 && self.nominal_fps() % 30 == 0
+    }
+
+    /// Returns true if frame numbers are dropped.
+    pub fn is_drop_frame(&self) -> bool {
+        self.drop_frame
+    }
+
+    /// Returns the number of frames counted per timecode second.
+    pub fn nominal_fps(&self) -> u32 {
+        self.numerator.div_ceil(self.denominator)
+    }
+
+    /// Returns the number of frames played per real second.
+    pub fn fps(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// Returns the real duration of one frame.
+    pub fn frame_duration(&self) -> Duration {
+        Duration::from_secs_f64(self.denominator as f64 / self.numerator as f64)
+    }
+
+    /// Returns the frame number shown at `elapsed` since frame zero.
+    pub fn frames_in(&self, elapsed: Duration) -> u64 {
+        (elapsed.as_nanos() * self.numerator as u128 / (self.denominator as u128 * 1_000_000_000))
+            as u64
+    }
+
+    /// Returns the real time at which frame `frame` starts.
+    pub fn time_of_frame(&self, frame: u64) -> Duration {
+        let nanos =
+            frame as u128 * self.denominator as u128 * 1_000_000_000 / self.numerator as u128;
+        Duration::from_nanos(nanos as u64)
+    }
+
+    /// Frame numbers dropped at the start of each dropping minute.
+    fn dropped_per_minute(&self) -> u64 {
+        if self.drop_frame {
+            self.nominal_fps() as u64 / 15
+        } else {
+            0
+        }
+    }
+
+    /// Returns the number of frames in a 24 hour day.
+    pub fn frames_per_day(&self) -> u64 {
+        let fps = self.nominal_fps() as u64;
+        24 * 6 * (600 * fps - 9 * self.dropped_per_minute())
+    }
+}
+
+impl Default for TimecodeRate {
+    fn default() -> Self {
+        Self::FPS_25
+    }
+}
+
+impl fmt::Display for TimecodeRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)?;
+        } else {
+            write!(f, "{:.2}", self.fps())?;
+        }
+        if self.drop_frame {
+            write!(f, " DF")?;
+        }
+        Ok(())
+    }
+}
+
+/// A SMPTE timecode position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Timecode {
+    /// Hours (0-23)
+    pub hours: u8,
+    /// Minutes (0-59)
+    pub minutes: u8,
+    /// Seconds (0-59)
+    pub seconds: u8,
+    /// Frames within the second
+    pub frames: u8,
+    /// Frame rate the position is counted in
+    pub rate: TimecodeRate,
+}
+
+impl Timecode {
+    /// Creates a timecode, returning `None` if any field is out of range or
+    /// names a frame that drop-frame counting skips.
+    pub fn new(
+        hours: u8,
+        minutes: u8,
+        seconds: u8,
+        frames: u8,
+        rate: TimecodeRate,
+    ) -> Option<Self> {
+        let dropped = rate.dropped_per_minute();
+        let valid = hours < 24
+            && minutes < 60
+            && seconds < 60
+            && (frames as u32) < rate.nominal_fps()
+            && !(seconds == 0 && (frames as u64) < dropped && minutes % 10 != 0);
+        valid.then_some(Self {
+            hours,
+            minutes,
+            seconds,
+            frames,
+            rate,
+        })
+    }
+
+    /// Creates a timecode from a frame count since midnight, wrapping at 24 hours.
+    pub fn from_frames(frame: u64, rate: TimecodeRate) -> Self {
+        let fps = rate.nominal_fps() as u64;
+        let mut frame = frame % rate.frames_per_day();
+
+        let dropped = rate.dropped_per_minute();
+        if dropped > 0 {
+            // Re-insert the skipped frame numbers
+            let per_10_minutes = 600 * fps - 9 * dropped;
+            let per_minute = 60 * fps - dropped;
+            let tens = frame / per_10_minutes;
+            let rest = frame % per_10_minutes;
+            frame += 9 * dropped * tens;
+            if rest >= dropped {
+                frame += dropped * ((rest - dropped) / per_minute);
+            }
+        }
+
+        Self {
+            hours: (frame / (fps * 3600)) as u8,
+            minutes: (frame / (fps * 60) % 60) as u8,
+            seconds: (frame / fps % 60) as u8,
+            frames: (frame % fps) as u8,
+            rate,
+        }
+    }
+
+    /// Creates the timecode shown at `elapsed` since midnight.
+    pub fn from_duration(elapsed: Duration, rate: TimecodeRate) -> Self {
+        Self::from_frames(rate.frames_in(elapsed), rate)
+    }
+
+    /// Parses `HH:MM:SS:FF`, also accepting `;` or `.` before the frames.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TimecodeError::InvalidTimecode` if the string is malformed or
+    /// out of range for `rate`.
+    pub fn parse(text: &str, rate: TimecodeRate) -> Result<Self> {
+        let invalid = || TimecodeError::InvalidTimecode(text.to_string());
+        let fields: Vec<u8> = text
+            .trim()
+            .split([':', ';', '.'])
+            .map(|field| field.parse().map_err(|_| invalid()))
+            .collect::<Result<_>>()?;
+        match fields[..] {
+            [hours, minutes, seconds, frames] => {
+                Self::new(hours, minutes, seconds, frames, rate).ok_or_else(invalid)
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Returns the frame count since midnight.
+    pub fn to_frames(&self) -> u64 {
+        let fps = self.rate.nominal_fps() as u64;
+        let total_minutes = self.hours as u64 * 60 + self.minutes as u64;
+        let frames = (total_minutes * 60 + self.seconds as u64) * fps + self.frames as u64;
+        frames - self.rate.dropped_per_minute() * (total_minutes - total_minutes / 10)
+    }
+
+    /// Returns the real time since midnight.
+    pub fn to_duration(&self) -> Duration {
+        self.rate.time_of_frame(self.to_frames())
+    }
+
+    /// Returns the real time since midnight in seconds.
+    pub fn to_seconds(&self) -> f64 {
+        self.to_frames() as f64 / self.rate.fps()
+    }
+
+    /// Returns the frame count of this position at another rate.
+    pub fn frames_at(&self, rate: TimecodeRate) -> u64 {
+        if rate == self.rate {
+            self.to_frames()
+        } else {
+            (self.to_seconds() * rate.fps()).round() as u64
+        }
+    }
+
+    /// Moves the position by a number of frames, wrapping at midnight.
+    pub fn offset(&self, frames: i64) -> Self {
+        let day = self.rate.frames_per_day() as i64;
+        let frame = (self.to_frames() as i64 + frames).rem_euclid(day);
+        Self::from_frames(frame as u64, self.rate)
+    }
+}
+
+impl fmt::Display for Timecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = if self.rate.is_drop_frame() { ';' } else { ':' };
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours, self.minutes, self.seconds, separator, self.frames
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_round_trip() {
+        for rate in [
+            TimecodeRate::FPS_23_976,
+            TimecodeRate::FPS_25,
+            TimecodeRate::FPS_29_97_DF,
+            TimecodeRate::FPS_59_94_DF,
+            TimecodeRate::FPS_60,
+        ] {
+            for frame in (0..rate.frames_per_day()).step_by(997) {
+                let timecode = Timecode::from_frames(frame, rate);
+                assert_eq!(timecode.to_frames(), frame, "{} at {}", timecode, rate);
+                assert!(Timecode::new(
+                    timecode.hours,
+                    timecode.minutes,
+                    timecode.seconds,
+                    timecode.frames,
+                    rate
+                )
+                .is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_drop_frame_counting() {
+        let rate = TimecodeRate::FPS_29_97_DF;
+        // 00:00:59;29 is followed by 00:01:00;02
+        let before = Timecode::new(0, 0, 59, 29, rate).unwrap();
+        assert_eq!(before.offset(1).to_string(), "00:01:00;02");
+        assert!(Timecode::new(0, 1, 0, 0, rate).is_none());
+        assert!(Timecode::new(0, 10, 0, 0, rate).is_some());
+
+        // Every tenth minute keeps frames 0 and 1
+        let before = Timecode::new(0, 9, 59, 29, rate).unwrap();
+        assert_eq!(before.offset(1).to_string(), "00:10:00;00");
+
+        // 59.94 DF drops four frame numbers
+        let rate = TimecodeRate::FPS_59_94_DF;
+        let before = Timecode::new(0, 0, 59, 59, rate).unwrap();
+        assert_eq!(before.offset(1).to_string(), "00:01:00;04");
+
+        // One hour of drop-frame stays within a frame of the wall clock
+        let hour = Timecode::new(1, 0, 0, 0, TimecodeRate::FPS_29_97_DF).unwrap();
+        assert!((hour.to_duration().as_secs_f64() - 3600.0).abs() < 0.04);
+    }
+
+    #[test]
+    fn test_parse_and_rates() {
+        let rate = TimecodeRate::from_fps(29.97).with_drop_frame(true);
+        assert_eq!(rate, TimecodeRate::FPS_29_97_DF);
+        assert_eq!(TimecodeRate::from_fps(15.0).nominal_fps(), 15);
+        assert!(TimecodeRate::new(25, 1, true).is_err());
+        assert!(!TimecodeRate::FPS_25.with_drop_frame(true).is_drop_frame());
+
+        let timecode = Timecode::parse("01:02:03;04", rate).unwrap();
+        assert_eq!(timecode.to_string(), "01:02:03;04");
+        assert!(Timecode::parse("01:02:03", rate).is_err());
+        assert!(Timecode::parse("00:01:00:00", rate).is_err());
+
+        let timecode = Timecode::new(0, 0, 10, 0, TimecodeRate::FPS_25).unwrap();
+        assert_eq!(timecode.frames_at(TimecodeRate::FPS_24), 240);
+
+        let elapsed = TimecodeRate::FPS_25.time_of_frame(90_000);
+        assert_eq!(elapsed, Duration::from_secs(3600));
+        assert_eq!(TimecodeRate::FPS_25.frames_in(elapsed), 90_000);
+    }
+}
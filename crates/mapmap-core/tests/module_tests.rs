@@ -47,3 +47,21 @@ fn test_module_color_rotation() {
         .color;
     assert_ne!(modules1, modules2);
 }
+
+#[test]
+fn test_active_module() {
+    let mut manager = ModuleManager::new();
+    let id = manager.create_module("Test Module".to_string());
+    assert_eq!(manager.active_module(), None);
+
+    manager.set_active_module(Some(id));
+    assert_eq!(manager.active_module(), Some(id));
+
+    // Unknown modules cannot be activated
+    manager.set_active_module(Some(id + 1));
+    assert_eq!(manager.active_module(), None);
+
+    manager.set_active_module(Some(id));
+    manager.delete_module(id);
+    assert_eq!(manager.active_module(), None);
+}
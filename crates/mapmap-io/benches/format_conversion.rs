@@ -0,0 +1,92 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use mapmap_io::{FormatConverter, PixelFormat, Resampler, ScaleFilter, VideoFormat, VideoFrame};
+use std::time::Duration;
+
+fn test_frame(width: u32, height: u32, pixel_format: PixelFormat) -> VideoFrame {
+    let format = VideoFormat::new(width, height, pixel_format, 60.0);
+    let data = (0..format.buffer_size()).map(|i| (i % 251) as u8).collect();
+    VideoFrame::new(data, format, Duration::ZERO)
+}
+
+fn bench_pixel_conversion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pixel_conversion");
+    group.sample_size(20);
+
+    let cases = [
+        (
+            "yuv420p_to_rgba_2160p",
+            3840,
+            2160,
+            PixelFormat::YUV420P,
+            PixelFormat::RGBA8,
+        ),
+        (
+            "nv12_to_rgba_1080p",
+            1920,
+            1080,
+            PixelFormat::NV12,
+            PixelFormat::RGBA8,
+        ),
+        (
+            "rgba_to_nv12_1080p",
+            1920,
+            1080,
+            PixelFormat::RGBA8,
+            PixelFormat::NV12,
+        ),
+        (
+            "rgba_to_uyvy_1080p",
+            1920,
+            1080,
+            PixelFormat::RGBA8,
+            PixelFormat::UYVY,
+        ),
+        (
+            "bgra_to_rgba_1080p",
+            1920,
+            1080,
+            PixelFormat::BGRA8,
+            PixelFormat::RGBA8,
+        ),
+    ];
+
+    for (name, width, height, source, target) in cases {
+        let frame = test_frame(width, height, source);
+        let target = VideoFormat::new(width, height, target, 60.0);
+
+        for parallel in [false, true] {
+            let converter = FormatConverter::new().with_parallel(parallel);
+            let id = if parallel { "parallel" } else { "single" };
+            group.bench_function(BenchmarkId::new(name, id), |b| {
+                b.iter(|| black_box(converter.convert(&frame, &target).unwrap()));
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_scaling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scaling");
+    group.sample_size(20);
+
+    let frame = test_frame(1920, 1080, PixelFormat::RGBA8);
+    for filter in [
+        ScaleFilter::Bilinear,
+        ScaleFilter::Bicubic,
+        ScaleFilter::Lanczos3,
+    ] {
+        let resampler = Resampler::new(filter);
+        group.bench_function(
+            BenchmarkId::new("rgba_1080p_to_720p", format!("{:?}", filter)),
+            |b| {
+                b.iter(|| black_box(resampler.resize(&frame, 1280, 720).unwrap()));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_pixel_conversion, bench_scaling);
+criterion_main!(benches);
@@ -0,0 +1,390 @@
+//! Color space definitions for RGB/YUV conversion.
+//!
+//! YUV video is described by three properties that must match between the
+//! producer and consumer of a frame:
+//!
+//! - [`ColorMatrix`] - the luma/chroma coefficients (BT.601, BT.709, BT.2020)
+//! - [`ColorRange`] - whether code values use the full 0-255 range or the
+//!   limited "studio" range (16-235 luma, 16-240 chroma)
+//! - [`ChromaSiting`] - where subsampled chroma samples sit relative to luma
+
+use serde::{Deserialize, Serialize};
+
+/// YUV color matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ColorMatrix {
+    /// ITU-R BT.601, used for standard definition video
+    Bt601,
+    /// ITU-R BT.709, used for high definition video
+    #[default]
+    Bt709,
+    /// ITU-R BT.2020 non-constant luminance, used for UHD video
+    Bt2020,
+}
+
+impl ColorMatrix {
+    /// Returns the red and blue luma coefficients (Kr, Kb).
+    pub fn coefficients(&self) -> (f32, f32) {
+        match self {
+            ColorMatrix::Bt601 => (0.299, 0.114),
+            ColorMatrix::Bt709 => (0.2126, 0.0722),
+            ColorMatrix::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+
+    /// Returns the matrix conventionally used for a frame height.
+    pub fn for_height(height: u32) -> Self {
+        if height < 720 {
+            ColorMatrix::Bt601
+        } else {
+            ColorMatrix::Bt709
+        }
+    }
+}
+
+/// YUV code value range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ColorRange {
+    /// Luma 16-235, chroma 16-240 (broadcast and most encoders)
+    #[default]
+    Limited,
+    /// Luma and chroma 0-255 (JPEG and some capture devices)
+    Full,
+}
+
+/// Position of subsampled chroma samples relative to the luma grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ChromaSiting {
+    /// Horizontally co-sited with the left luma sample, vertically centered
+    /// (MPEG-2, H.264 and HEVC default)
+    #[default]
+    Left,
+    /// Centered between luma samples in both directions (MPEG-1, JPEG)
+    Center,
+    /// Co-sited with the top-left luma sample (BT.2020 UHD)
+    TopLeft,
+}
+
+impl ChromaSiting {
+    /// Returns true if chroma is co-sited with luma horizontally.
+    pub fn horizontal_cosited(&self) -> bool {
+        matches!(self, ChromaSiting::Left | ChromaSiting::TopLeft)
+    }
+
+    /// Returns true if chroma is co-sited with luma vertically.
+    pub fn vertical_cosited(&self) -> bool {
+        matches!(self, ChromaSiting::TopLeft)
+    }
+}
+
+/// Complete description of how RGB maps to YUV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct ColorSettings {
+    /// Color matrix
+    pub matrix: ColorMatrix,
+    /// Code value range
+    pub range: ColorRange,
+    /// Chroma sample position
+    pub chroma_siting: ChromaSiting,
+}
+
+impl ColorSettings {
+    /// Creates settings with the default chroma siting.
+    pub fn new(matrix: ColorMatrix, range: ColorRange) -> Self {
+        Self {
+            matrix,
+            range,
+            chroma_siting: ChromaSiting::default(),
+        }
+    }
+
+    /// Returns the settings conventionally used for a frame height, in
+    /// limited range.
+    pub fn for_height(height: u32) -> Self {
+        Self::new(ColorMatrix::for_height(height), ColorRange::Limited)
+    }
+
+    /// Sets the chroma siting.
+    pub fn with_chroma_siting(mut self, chroma_siting: ChromaSiting) -> Self {
+        self.chroma_siting = chroma_siting;
+        self
+    }
+
+    /// Converts one RGB pixel to YUV.
+    pub fn rgb_to_yuv(&self, rgb: [u8; 3]) -> [u8; 3] {
+        YuvTransform::new(self).rgb_to_yuv(rgb[0], rgb[1], rgb[2])
+    }
+
+    /// Converts one YUV pixel to RGB.
+    pub fn yuv_to_rgb(&self, yuv: [u8; 3]) -> [u8; 3] {
+        YuvTransform::new(self).yuv_to_rgb(yuv[0], yuv[1], yuv[2])
+    }
+}
+
+/// Fixed-point bits of the transform coefficients
+const SHIFT: u32 = 16;
+const HALF: i32 = 1 << (SHIFT - 1);
+
+/// Pixels per block in the row conversion loops
+const LANES: usize = 8;
+
+/// Precomputed fixed-point RGB/YUV transform.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct YuvTransform {
+    to_yuv: [[i32; 3]; 3],
+    to_rgb: [[i32; 3]; 3],
+    y_offset: i32,
+}
+
+impl YuvTransform {
+    pub(crate) fn new(settings: &ColorSettings) -> Self {
+        let (kr, kb) = settings.matrix.coefficients();
+        let kg = 1.0 - kr - kb;
+        let (y_offset, y_scale, c_scale) = match settings.range {
+            ColorRange::Limited => (16, 219.0, 224.0),
+            ColorRange::Full => (0, 255.0, 255.0),
+        };
+        let fixed = |v: f32| (v * (1 << SHIFT) as f32).round() as i32;
+
+        // Normalized Y' = Kr R + Kg G + Kb B, Pb = (B - Y') / 2(1 - Kb),
+        // Pr = (R - Y') / 2(1 - Kr), scaled from 0-255 RGB to code values
+        let ys = y_scale / 255.0;
+        let cs = c_scale / 255.0;
+        let pb = 2.0 * (1.0 - kb);
+        let pr = 2.0 * (1.0 - kr);
+        let to_yuv = [
+            [fixed(kr * ys), fixed(kg * ys), fixed(kb * ys)],
+            [
+                fixed(-kr / pb * cs),
+                fixed(-kg / pb * cs),
+                fixed((1.0 - kb) / pb * cs),
+            ],
+            [
+                fixed((1.0 - kr) / pr * cs),
+                fixed(-kg / pr * cs),
+                fixed(-kb / pr * cs),
+            ],
+        ];
+
+        // Inverse, from offset-removed code values back to 0-255 RGB
+        let yi = 255.0 / y_scale;
+        let ci = 255.0 / c_scale;
+        let to_rgb = [
+            [fixed(yi), 0, fixed(pr * ci)],
+            [
+                fixed(yi),
+                fixed(-kb * pb / kg * ci),
+                fixed(-kr * pr / kg * ci),
+            ],
+            [fixed(yi), fixed(pb * ci), 0],
+        ];
+
+        Self {
+            to_yuv,
+            to_rgb,
+            y_offset,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn rgb_to_yuv(&self, r: u8, g: u8, b: u8) -> [u8; 3] {
+        let (r, g, b) = (r as i32, g as i32, b as i32);
+        let m = &self.to_yuv;
+        let y = (m[0][0] * r + m[0][1] * g + m[0][2] * b + HALF) >> SHIFT;
+        let u = (m[1][0] * r + m[1][1] * g + m[1][2] * b + HALF) >> SHIFT;
+        let v = (m[2][0] * r + m[2][1] * g + m[2][2] * b + HALF) >> SHIFT;
+        [
+            clamp_u8(y + self.y_offset),
+            clamp_u8(u + 128),
+            clamp_u8(v + 128),
+        ]
+    }
+
+    /// Converts a row of RGBA pixels into luma and full resolution chroma.
+    ///
+    /// Pixels are processed in blocks of [`LANES`] so that the fixed-point
+    /// arithmetic is auto-vectorized.
+    pub(crate) fn rgba_row_to_yuv(&self, rgba: &[u8], y: &mut [u8], u: &mut [u8], v: &mut [u8]) {
+        let blocks = y.len() / LANES;
+        let m = &self.to_yuv;
+
+        for block in 0..blocks {
+            let base = block * LANES;
+            let pixels = &rgba[base * 4..(base + LANES) * 4];
+            let mut rgb = [[0i32; LANES]; 3];
+            for (i, pixel) in pixels.chunks_exact(4).enumerate() {
+                rgb[0][i] = pixel[0] as i32;
+                rgb[1][i] = pixel[1] as i32;
+                rgb[2][i] = pixel[2] as i32;
+            }
+
+            let offsets = [self.y_offset, 128, 128];
+            for (row, (out, offset)) in [&mut *y, &mut *u, &mut *v]
+                .into_iter()
+                .zip(offsets)
+                .enumerate()
+            {
+                let out = &mut out[base..base + LANES];
+                for i in 0..LANES {
+                    let value = (m[row][0] * rgb[0][i]
+                        + m[row][1] * rgb[1][i]
+                        + m[row][2] * rgb[2][i]
+                        + HALF)
+                        >> SHIFT;
+                    out[i] = clamp_u8(value + offset);
+                }
+            }
+        }
+
+        for i in blocks * LANES..y.len() {
+            let [py, pu, pv] = self.rgb_to_yuv(rgba[i * 4], rgba[i * 4 + 1], rgba[i * 4 + 2]);
+            y[i] = py;
+            u[i] = pu;
+            v[i] = pv;
+        }
+    }
+
+    /// Converts a row of luma and full resolution chroma into opaque RGBA
+    /// pixels, in blocks of [`LANES`].
+    pub(crate) fn yuv_row_to_rgba(&self, y: &[u8], u: &[u8], v: &[u8], rgba: &mut [u8]) {
+        let blocks = y.len() / LANES;
+        let m = &self.to_rgb;
+
+        for block in 0..blocks {
+            let base = block * LANES;
+            let mut yuv = [[0i32; LANES]; 3];
+            for i in 0..LANES {
+                yuv[0][i] = y[base + i] as i32 - self.y_offset;
+                yuv[1][i] = u[base + i] as i32 - 128;
+                yuv[2][i] = v[base + i] as i32 - 128;
+            }
+
+            let mut rgb = [[0u8; LANES]; 3];
+            for i in 0..LANES {
+                let luma = m[0][0] * yuv[0][i] + HALF;
+                rgb[0][i] = clamp_u8((luma + m[0][2] * yuv[2][i]) >> SHIFT);
+                rgb[1][i] = clamp_u8((luma + m[1][1] * yuv[1][i] + m[1][2] * yuv[2][i]) >> SHIFT);
+                rgb[2][i] = clamp_u8((luma + m[2][1] * yuv[1][i]) >> SHIFT);
+            }
+
+            let pixels = &mut rgba[base * 4..(base + LANES) * 4];
+            for (i, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+                pixel[0] = rgb[0][i];
+                pixel[1] = rgb[1][i];
+                pixel[2] = rgb[2][i];
+                pixel[3] = 255;
+            }
+        }
+
+        for i in blocks * LANES..y.len() {
+            let [r, g, b] = self.yuv_to_rgb(y[i], u[i], v[i]);
+            rgba[i * 4..i * 4 + 4].copy_from_slice(&[r, g, b, 255]);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn yuv_to_rgb(&self, y: u8, u: u8, v: u8) -> [u8; 3] {
+        let y = y as i32 - self.y_offset;
+        let u = u as i32 - 128;
+        let v = v as i32 - 128;
+        let m = &self.to_rgb;
+        [
+            clamp_u8((m[0][0] * y + m[0][2] * v + HALF) >> SHIFT),
+            clamp_u8((m[1][0] * y + m[1][1] * u + m[1][2] * v + HALF) >> SHIFT),
+            clamp_u8((m[2][0] * y + m[2][1] * u + HALF) >> SHIFT),
+        ]
+    }
+}
+
+#[inline]
+fn clamp_u8(value: i32) -> u8 {
+    value.clamp(0, 255) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(matrix: ColorMatrix, range: ColorRange) -> ColorSettings {
+        ColorSettings::new(matrix, range)
+    }
+
+    #[test]
+    fn test_reference_values() {
+        let limited = |m| settings(m, ColorRange::Limited);
+        let full = |m| settings(m, ColorRange::Full);
+
+        // Black and white
+        for matrix in [ColorMatrix::Bt601, ColorMatrix::Bt709, ColorMatrix::Bt2020] {
+            assert_eq!(limited(matrix).rgb_to_yuv([0, 0, 0]), [16, 128, 128]);
+            assert_eq!(limited(matrix).rgb_to_yuv([255, 255, 255]), [235, 128, 128]);
+            assert_eq!(full(matrix).rgb_to_yuv([255, 255, 255]), [255, 128, 128]);
+        }
+
+        // 100% red
+        let red = [255, 0, 0];
+        assert_eq!(limited(ColorMatrix::Bt601).rgb_to_yuv(red), [81, 90, 240]);
+        assert_eq!(limited(ColorMatrix::Bt709).rgb_to_yuv(red), [63, 102, 240]);
+        assert_eq!(limited(ColorMatrix::Bt2020).rgb_to_yuv(red), [74, 97, 240]);
+        assert_eq!(full(ColorMatrix::Bt601).rgb_to_yuv(red), [76, 85, 255]);
+
+        // 100% blue
+        let blue = [0, 0, 255];
+        assert_eq!(limited(ColorMatrix::Bt709).rgb_to_yuv(blue), [32, 240, 118]);
+
+        // Decoding is exact for neutral values
+        for matrix in [ColorMatrix::Bt601, ColorMatrix::Bt709, ColorMatrix::Bt2020] {
+            assert_eq!(limited(matrix).yuv_to_rgb([16, 128, 128]), [0, 0, 0]);
+            assert_eq!(limited(matrix).yuv_to_rgb([235, 128, 128]), [255, 255, 255]);
+            assert_eq!(full(matrix).yuv_to_rgb([128, 128, 128]), [128, 128, 128]);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for matrix in [ColorMatrix::Bt601, ColorMatrix::Bt709, ColorMatrix::Bt2020] {
+            for range in [ColorRange::Limited, ColorRange::Full] {
+                let settings = settings(matrix, range);
+                for r in (0..=255).step_by(17) {
+                    for g in (0..=255).step_by(17) {
+                        for b in (0..=255).step_by(17) {
+                            let rgb = [r as u8, g as u8, b as u8];
+                            let back = settings.yuv_to_rgb(settings.rgb_to_yuv(rgb));
+                            for c in 0..3 {
+                                assert!(
+                                    (back[c] as i32 - rgb[c] as i32).abs() <= 2,
+                                    "{:?} {:?}: {:?} -> {:?}",
+                                    matrix,
+                                    range,
+                                    rgb,
+                                    back
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_row_conversion_matches_pixels() {
+        let settings = settings(ColorMatrix::Bt709, ColorRange::Limited);
+        let transform = YuvTransform::new(&settings);
+        // 19 pixels: two full blocks and a tail
+        let rgba: Vec<u8> = (0..19 * 4).map(|i| (i * 37 % 256) as u8).collect();
+
+        let (mut y, mut u, mut v) = (vec![0; 19], vec![0; 19], vec![0; 19]);
+        transform.rgba_row_to_yuv(&rgba, &mut y, &mut u, &mut v);
+        let mut back = vec![0; 19 * 4];
+        transform.yuv_row_to_rgba(&y, &u, &v, &mut back);
+
+        for i in 0..19 {
+            let pixel = &rgba[i * 4..i * 4 + 3];
+            let yuv = settings.rgb_to_yuv([pixel[0], pixel[1], pixel[2]]);
+            assert_eq!([y[i], u[i], v[i]], yuv);
+            let [r, g, b] = settings.yuv_to_rgb(yuv);
+            assert_eq!(&back[i * 4..i * 4 + 4], &[r, g, b, 255]);
+        }
+    }
+}
@@ -1,24 +1,110 @@
 //! Format conversion utilities.
 //!
 //! This module provides utilities for converting between different pixel formats,
-//! particularly YUV to RGB conversions which are common in video I/O.
+//! in particular YUV/RGB conversions in both directions, which are needed by
+//! every decoder and encoder. See [`crate::color`] for the supported color
+//! matrices, ranges and chroma sitings.
+//!
+//! Conversions are split into rows that are processed in parallel with rayon.
+//! The per-pixel loops work on fixed-size blocks so they are auto-vectorized,
+//! and RGBA/BGRA swaps use SSSE3 shuffles where available. Frames whose size
+//! differs from the target format are resized with a [`Resampler`] first.
 
+use crate::color::{ChromaSiting, ColorMatrix, ColorRange, ColorSettings, YuvTransform};
 use crate::error::{IoError, Result};
 use crate::format::{PixelFormat, VideoFormat, VideoFrame};
+use crate::scale::{Resampler, ScaleFilter};
+use rayon::prelude::*;
+
+/// Outputs smaller than this are converted on the calling thread
+const PARALLEL_MIN_BYTES: usize = 64 * 1024;
 
 /// Format converter for pixel format conversion.
 ///
-/// Provides software-based conversion between various pixel formats.
+/// Provides software-based conversion between all [`PixelFormat`]s. RGB
+/// formats are converted by swizzling, YUV formats are repacked without
+/// touching the color values, and RGB/YUV conversions use the configured
+/// [`ColorSettings`].
+///
 /// For performance-critical applications, consider using GPU-accelerated
 /// conversion via compute shaders.
 pub struct FormatConverter {
+    color: ColorSettings,
+    resampler: Resampler,
+    parallel: bool,
     // Future: Add GPU context for hardware-accelerated conversion
 }
 
 impl FormatConverter {
-    /// Creates a new format converter.
+    /// Creates a new format converter using BT.601 full range and bilinear
+    /// scaling.
+    ///
+    /// This keeps the behavior of earlier versions; use
+    /// [`with_color_settings`](Self::with_color_settings) for BT.709 or
+    /// limited-range video.
     pub fn new() -> Self {
-        Self {}
+        Self {
+            color: ColorSettings::new(ColorMatrix::Bt601, ColorRange::Full),
+            resampler: Resampler::default(),
+            parallel: true,
+        }
+    }
+
+    /// Sets all color settings.
+    pub fn with_color_settings(mut self, color: ColorSettings) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets the YUV color matrix.
+    pub fn with_matrix(mut self, matrix: ColorMatrix) -> Self {
+        self.color.matrix = matrix;
+        self
+    }
+
+    /// Sets the YUV code value range.
+    pub fn with_range(mut self, range: ColorRange) -> Self {
+        self.color.range = range;
+        self
+    }
+
+    /// Sets the chroma siting used when subsampling and upsampling chroma.
+    pub fn with_chroma_siting(mut self, chroma_siting: ChromaSiting) -> Self {
+        self.color.chroma_siting = chroma_siting;
+        self
+    }
+
+    /// Sets the filter used when the target size differs from the frame.
+    pub fn with_scale_filter(mut self, filter: ScaleFilter) -> Self {
+        self.resampler = self.resampler.with_filter(filter);
+        self
+    }
+
+    /// Enables or disables multi-threaded conversion (enabled by default).
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self.resampler = self.resampler.with_parallel(parallel);
+        self
+    }
+
+    /// Returns the color settings.
+    pub fn color_settings(&self) -> &ColorSettings {
+        &self.color
+    }
+
+    /// Sets the color settings.
+    pub fn set_color_settings(&mut self, color: ColorSettings) {
+        self.color = color;
+    }
+
+    /// Returns the filter used for scaling.
+    pub fn scale_filter(&self) -> ScaleFilter {
+        self.resampler.filter()
+    }
+
+    /// Returns true if conversion is multi-threaded.
+    pub fn is_parallel(&self) -> bool {
+        self.parallel
     }
 
     /// Converts a video frame from one format to another.
@@ -30,11 +116,13 @@ impl FormatConverter {
     ///
     /// # Returns
     ///
-    /// A new frame with the converted pixel data.
+    /// A new frame with the converted pixel data, resized if the target
+    /// dimensions differ.
     ///
     /// # Errors
     ///
-    /// Returns an error if the conversion is not supported or fails.
+    /// Returns an error if the frame data does not match its format or if a
+    /// chroma subsampled format is requested for odd dimensions.
     pub fn convert(&self, frame: &VideoFrame, target_format: &VideoFormat) -> Result<VideoFrame> {
         // If formats match, just clone the frame
         if frame.format.pixel_format == target_format.pixel_format
@@ -44,43 +132,54 @@ impl FormatConverter {
             return Ok(frame.clone());
         }
 
-        // Dispatch to specific conversion functions
-        match (&frame.format.pixel_format, &target_format.pixel_format) {
-            // RGB conversions
-            (PixelFormat::RGBA8, PixelFormat::BGRA8) => self.rgba_to_bgra(frame, target_format),
-            (PixelFormat::BGRA8, PixelFormat::RGBA8) => self.bgra_to_rgba(frame, target_format),
-            (PixelFormat::RGB8, PixelFormat::RGBA8) => self.rgb_to_rgba(frame, target_format),
-            (PixelFormat::RGBA8, PixelFormat::RGB8) => self.rgba_to_rgb(frame, target_format),
-
-            // YUV to RGB conversions
-            (PixelFormat::YUV420P, PixelFormat::RGBA8) => {
-                self.yuv420p_to_rgba(frame, target_format)
-            }
-            (PixelFormat::YUV422P, PixelFormat::RGBA8) => {
-                self.yuv422p_to_rgba(frame, target_format)
-            }
-            (PixelFormat::UYVY, PixelFormat::RGBA8) => self.uyvy_to_rgba(frame, target_format),
-            (PixelFormat::NV12, PixelFormat::RGBA8) => self.nv12_to_rgba(frame, target_format),
-
-            // Unsupported conversions
-            _ => Err(IoError::UnsupportedPixelFormat(format!(
-                "Conversion from {} to {} not supported",
-                frame.format.pixel_format, target_format.pixel_format
-            ))),
-        }
-    }
-
-    /// Converts RGBA to BGRA by swapping R and B channels.
-    fn rgba_to_bgra(&self, frame: &VideoFrame, target_format: &VideoFormat) -> Result<VideoFrame> {
-        let mut output = Vec::with_capacity(frame.data.len());
+        frame.validate()?;
 
-        for pixel in frame.data.chunks_exact(4) {
-            output.push(pixel[2]); // B
-            output.push(pixel[1]); // G
-            output.push(pixel[0]); // R
-            output.push(pixel[3]); // A
+        // Resize in the source format, where the dimensions are known to be
+        // valid, then change the pixel format
+        if frame.format.width != target_format.width || frame.format.height != target_format.height
+        {
+            check_dimensions(
+                target_format.pixel_format,
+                target_format.width as usize,
+                target_format.height as usize,
+            )?;
+            let scaled = self
+                .resampler
+                .resize(frame, target_format.width, target_format.height)?;
+            return self.convert(&scaled, target_format);
         }
 
+        let width = frame.format.width as usize;
+        let height = frame.format.height as usize;
+        let source = frame.format.pixel_format;
+        let target = target_format.pixel_format;
+        let parallel = self.parallel;
+
+        let output = if source.is_rgb() && target.is_rgb() {
+            swizzle(&frame.data, width, source, target, parallel)
+        } else if source.is_rgb() {
+            let rgba = swizzle(&frame.data, width, source, PixelFormat::RGBA8, parallel);
+            let planes = YuvPlanes::from_rgba(&rgba, width, height, &self.color, parallel);
+            planes
+                .subsample(target, &self.color, parallel)?
+                .pack(target)
+        } else {
+            let planes = YuvPlanes::unpack(&frame.data, source, width, height)?;
+            if target.is_rgb() {
+                let rgba = planes
+                    .upsample(&self.color, parallel)
+                    .to_rgba(&self.color, parallel);
+                swizzle(&rgba, width, PixelFormat::RGBA8, target, parallel)
+            } else if chroma_subsampling(source) == chroma_subsampling(target) {
+                planes.pack(target)
+            } else {
+                planes
+                    .upsample(&self.color, parallel)
+                    .subsample(target, &self.color, parallel)?
+                    .pack(target)
+            }
+        };
+
         Ok(VideoFrame::with_metadata(
             output,
             target_format.clone(),
@@ -88,282 +187,509 @@ impl FormatConverter {
             frame.metadata.clone(),
         ))
     }
+}
 
-    /// Converts BGRA to RGBA by swapping R and B channels.
-    fn bgra_to_rgba(&self, frame: &VideoFrame, target_format: &VideoFormat) -> Result<VideoFrame> {
-        // Same as RGBA to BGRA
-        self.rgba_to_bgra(frame, target_format)
+impl Default for FormatConverter {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    /// Converts RGB to RGBA by adding an alpha channel.
-    fn rgb_to_rgba(&self, frame: &VideoFrame, target_format: &VideoFormat) -> Result<VideoFrame> {
-        let pixel_count = (frame.format.width * frame.format.height) as usize;
-        let mut output = Vec::with_capacity(pixel_count * 4);
-
-        for pixel in frame.data.chunks_exact(3) {
-            output.push(pixel[0]); // R
-            output.push(pixel[1]); // G
-            output.push(pixel[2]); // B
-            output.push(255); // A (fully opaque)
-        }
-
-        Ok(VideoFrame::with_metadata(
-            output,
-            target_format.clone(),
-            frame.timestamp,
-            frame.metadata.clone(),
-        ))
+/// Calls `f` with the index and contents of every `row_len` sized row of
+/// `output`, in parallel for large outputs.
+pub(crate) fn for_each_row<F>(output: &mut [u8], row_len: usize, parallel: bool, f: F)
+where
+    F: Fn(usize, &mut [u8]) + Send + Sync,
+{
+    if row_len == 0 {
+        return;
     }
-
-    /// Converts RGBA to RGB by dropping the alpha channel.
-    fn rgba_to_rgb(&self, frame: &VideoFrame, target_format: &VideoFormat) -> Result<VideoFrame> {
-        let pixel_count = (frame.format.width * frame.format.height) as usize;
-        let mut output = Vec::with_capacity(pixel_count * 3);
-
-        for pixel in frame.data.chunks_exact(4) {
-            output.push(pixel[0]); // R
-            output.push(pixel[1]); // G
-            output.push(pixel[2]); // B
-        }
-
-        Ok(VideoFrame::with_metadata(
-            output,
-            target_format.clone(),
-            frame.timestamp,
-            frame.metadata.clone(),
-        ))
+    if parallel && output.len() >= PARALLEL_MIN_BYTES {
+        output
+            .par_chunks_mut(row_len)
+            .enumerate()
+            .for_each(|(i, row)| f(i, row));
+    } else {
+        output
+            .chunks_mut(row_len)
+            .enumerate()
+            .for_each(|(i, row)| f(i, row));
     }
+}
 
-    /// Converts YUV420P to RGBA.
-    ///
-    /// YUV420P is a planar format with full resolution Y plane and
-    /// quarter resolution U and V planes.
-    fn yuv420p_to_rgba(
-        &self,
-        frame: &VideoFrame,
-        target_format: &VideoFormat,
-    ) -> Result<VideoFrame> {
-        let width = frame.format.width as usize;
-        let height = frame.format.height as usize;
-        let y_size = width * height;
-        let uv_size = y_size / 4;
-
-        if frame.data.len() < y_size + 2 * uv_size {
-            return Err(IoError::InvalidFrameData(
-                "Insufficient data for YUV420P frame".to_string(),
-            ));
-        }
-
-        let y_plane = &frame.data[0..y_size];
-        let u_plane = &frame.data[y_size..y_size + uv_size];
-        let v_plane = &frame.data[y_size + uv_size..y_size + 2 * uv_size];
-
-        let mut output = vec![0u8; width * height * 4];
+/// Like [`for_each_row`], for three planes of equal size.
+fn for_each_row3<F>(planes: [&mut [u8]; 3], row_len: usize, parallel: bool, f: F)
+where
+    F: Fn(usize, &mut [u8], &mut [u8], &mut [u8]) + Send + Sync,
+{
+    let [a, b, c] = planes;
+    if row_len == 0 {
+        return;
+    }
+    if parallel && a.len() * 3 >= PARALLEL_MIN_BYTES {
+        a.par_chunks_mut(row_len)
+            .zip(b.par_chunks_mut(row_len))
+            .zip(c.par_chunks_mut(row_len))
+            .enumerate()
+            .for_each(|(i, ((a, b), c))| f(i, a, b, c));
+    } else {
+        a.chunks_mut(row_len)
+            .zip(b.chunks_mut(row_len))
+            .zip(c.chunks_mut(row_len))
+            .enumerate()
+            .for_each(|(i, ((a, b), c))| f(i, a, b, c));
+    }
+}
 
-        for y in 0..height {
-            for x in 0..width {
-                let y_idx = y * width + x;
-                let uv_idx = (y / 2) * (width / 2) + (x / 2);
+/// Returns the horizontal and vertical chroma subsampling factors.
+pub(crate) fn chroma_subsampling(format: PixelFormat) -> (usize, usize) {
+    match format {
+        PixelFormat::YUV420P | PixelFormat::NV12 => (2, 2),
+        PixelFormat::YUV422P | PixelFormat::UYVY => (2, 1),
+        PixelFormat::RGBA8 | PixelFormat::BGRA8 | PixelFormat::RGB8 => (1, 1),
+    }
+}
 
-                let y_val = y_plane[y_idx] as i32;
-                let u_val = u_plane[uv_idx] as i32 - 128;
-                let v_val = v_plane[uv_idx] as i32 - 128;
+/// Returns the byte offsets of R, G, B and (if present) A in an RGB pixel.
+fn channel_layout(format: PixelFormat) -> ([usize; 3], Option<usize>) {
+    match format {
+        PixelFormat::BGRA8 => ([2, 1, 0], Some(3)),
+        PixelFormat::RGB8 => ([0, 1, 2], None),
+        _ => ([0, 1, 2], Some(3)),
+    }
+}
 
-                let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val);
+/// Reorders channels between RGB formats, adding opaque alpha if needed.
+fn swizzle(
+    data: &[u8],
+    width: usize,
+    source: PixelFormat,
+    target: PixelFormat,
+    parallel: bool,
+) -> Vec<u8> {
+    if source == target {
+        return data.to_vec();
+    }
 
-                let out_idx = y_idx * 4;
-                output[out_idx] = r;
-                output[out_idx + 1] = g;
-                output[out_idx + 2] = b;
-                output[out_idx + 3] = 255; // Alpha
-            }
-        }
+    let src_bpp = source.bytes_per_pixel();
+    let dst_bpp = target.bytes_per_pixel();
+    let src_row = width * src_bpp;
+    let mut output = vec![0u8; data.len() / src_bpp * dst_bpp];
 
-        Ok(VideoFrame::with_metadata(
-            output,
-            target_format.clone(),
-            frame.timestamp,
-            frame.metadata.clone(),
-        ))
+    if src_bpp == 4 && dst_bpp == 4 {
+        for_each_row(&mut output, width * 4, parallel, |y, row| {
+            swap_red_blue(&data[y * src_row..(y + 1) * src_row], row);
+        });
+        return output;
     }
 
-    /// Converts YUV422P to RGBA.
-    ///
-    /// YUV422P is a planar format with full resolution Y plane and
-    /// half resolution U and V planes (horizontally subsampled).
-    fn yuv422p_to_rgba(
-        &self,
-        frame: &VideoFrame,
-        target_format: &VideoFormat,
-    ) -> Result<VideoFrame> {
-        let width = frame.format.width as usize;
-        let height = frame.format.height as usize;
-        let y_size = width * height;
-        let uv_size = y_size / 2;
-
-        if frame.data.len() < y_size + 2 * uv_size {
-            return Err(IoError::InvalidFrameData(
-                "Insufficient data for YUV422P frame".to_string(),
-            ));
+    let (src_rgb, src_alpha) = channel_layout(source);
+    let (dst_rgb, dst_alpha) = channel_layout(target);
+    for_each_row(&mut output, width * dst_bpp, parallel, |y, row| {
+        let src = &data[y * src_row..(y + 1) * src_row];
+        for (src, dst) in src.chunks_exact(src_bpp).zip(row.chunks_exact_mut(dst_bpp)) {
+            for c in 0..3 {
+                dst[dst_rgb[c]] = src[src_rgb[c]];
+            }
+            if let Some(a) = dst_alpha {
+                dst[a] = src_alpha.map_or(255, |i| src[i]);
+            }
         }
+    });
 
-        let y_plane = &frame.data[0..y_size];
-        let u_plane = &frame.data[y_size..y_size + uv_size];
-        let v_plane = &frame.data[y_size + uv_size..y_size + 2 * uv_size];
-
-        let mut output = vec![0u8; width * height * 4];
-
-        for y in 0..height {
-            for x in 0..width {
-                let y_idx = y * width + x;
-                let uv_idx = y * (width / 2) + (x / 2);
+    output
+}
 
-                let y_val = y_plane[y_idx] as i32;
-                let u_val = u_plane[uv_idx] as i32 - 128;
-                let v_val = v_plane[uv_idx] as i32 - 128;
+/// Swaps the first and third byte of every 4-byte pixel (RGBA <-> BGRA).
+fn swap_red_blue(src: &[u8], dst: &mut [u8]) {
+    let mut done = 0;
 
-                let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val);
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("ssse3") {
+        // SAFETY: SSSE3 support was checked above
+        done = unsafe { swap_red_blue_ssse3(src, dst) };
+    }
 
-                let out_idx = y_idx * 4;
-                output[out_idx] = r;
-                output[out_idx + 1] = g;
-                output[out_idx + 2] = b;
-                output[out_idx + 3] = 255; // Alpha
-            }
-        }
+    for (src, dst) in src[done..]
+        .chunks_exact(4)
+        .zip(dst[done..].chunks_exact_mut(4))
+    {
+        let pixel = u32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+        let swapped = (pixel & 0xFF00_FF00) | ((pixel >> 16) & 0xFF) | ((pixel & 0xFF) << 16);
+        dst.copy_from_slice(&swapped.to_le_bytes());
+    }
+}
 
-        Ok(VideoFrame::with_metadata(
-            output,
-            target_format.clone(),
-            frame.timestamp,
-            frame.metadata.clone(),
-        ))
+/// Shuffles 16 bytes (4 pixels) at a time and returns the number of bytes
+/// processed.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "ssse3")]
+unsafe fn swap_red_blue_ssse3(src: &[u8], dst: &mut [u8]) -> usize {
+    use std::arch::x86_64::{
+        __m128i, _mm_loadu_si128, _mm_setr_epi8, _mm_shuffle_epi8, _mm_storeu_si128,
+    };
+
+    let mask = _mm_setr_epi8(2, 1, 0, 3, 6, 5, 4, 7, 10, 9, 8, 11, 14, 13, 12, 15);
+    let len = src.len().min(dst.len()) / 16 * 16;
+    let mut i = 0;
+    while i < len {
+        let pixels = _mm_loadu_si128(src.as_ptr().add(i) as *const __m128i);
+        _mm_storeu_si128(
+            dst.as_mut_ptr().add(i) as *mut __m128i,
+            _mm_shuffle_epi8(pixels, mask),
+        );
+        i += 16;
     }
+    len
+}
 
-    /// Converts UYVY to RGBA.
-    ///
-    /// UYVY is a packed YUV 4:2:2 format.
-    fn uyvy_to_rgba(&self, frame: &VideoFrame, target_format: &VideoFormat) -> Result<VideoFrame> {
-        let width = frame.format.width as usize;
-        let height = frame.format.height as usize;
-        let expected_size = width * height * 2;
+/// YUV image split into planes, with chroma at any subsampling.
+pub(crate) struct YuvPlanes {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) chroma_width: usize,
+    pub(crate) chroma_height: usize,
+    pub(crate) y: Vec<u8>,
+    pub(crate) u: Vec<u8>,
+    pub(crate) v: Vec<u8>,
+}
 
-        if frame.data.len() < expected_size {
-            return Err(IoError::InvalidFrameData(
-                "Insufficient data for UYVY frame".to_string(),
-            ));
+impl YuvPlanes {
+    /// Converts RGBA pixels to full resolution (4:4:4) planes.
+    fn from_rgba(
+        rgba: &[u8],
+        width: usize,
+        height: usize,
+        color: &ColorSettings,
+        parallel: bool,
+    ) -> Self {
+        let transform = YuvTransform::new(color);
+        let pixels = width * height;
+        let mut y = vec![0; pixels];
+        let mut u = vec![0; pixels];
+        let mut v = vec![0; pixels];
+
+        for_each_row3([&mut y, &mut u, &mut v], width, parallel, |row, y, u, v| {
+            let src = &rgba[row * width * 4..(row + 1) * width * 4];
+            transform.rgba_row_to_yuv(src, y, u, v);
+        });
+
+        Self {
+            width,
+            height,
+            chroma_width: width,
+            chroma_height: height,
+            y,
+            u,
+            v,
         }
+    }
 
-        let mut output = vec![0u8; width * height * 4];
-
-        for y in 0..height {
-            for x in 0..(width / 2) {
-                let in_idx = (y * width + x * 2) * 2;
-                let out_idx1 = (y * width + x * 2) * 4;
-                let out_idx2 = out_idx1 + 4;
-
-                let u = frame.data[in_idx] as i32 - 128;
-                let y1 = frame.data[in_idx + 1] as i32;
-                let v = frame.data[in_idx + 2] as i32 - 128;
-                let y2 = frame.data[in_idx + 3] as i32;
-
-                let (r1, g1, b1) = yuv_to_rgb(y1, u, v);
-                let (r2, g2, b2) = yuv_to_rgb(y2, u, v);
+    /// Converts full resolution planes to opaque RGBA pixels.
+    fn to_rgba(&self, color: &ColorSettings, parallel: bool) -> Vec<u8> {
+        let transform = YuvTransform::new(color);
+        let width = self.width;
+        let mut output = vec![0u8; self.y.len() * 4];
+
+        for_each_row(&mut output, width * 4, parallel, |row, rgba| {
+            let range = row * width..(row + 1) * width;
+            transform.yuv_row_to_rgba(
+                &self.y[range.clone()],
+                &self.u[range.clone()],
+                &self.v[range],
+                rgba,
+            );
+        });
+
+        output
+    }
 
-                output[out_idx1] = r1;
-                output[out_idx1 + 1] = g1;
-                output[out_idx1 + 2] = b1;
-                output[out_idx1 + 3] = 255;
+    /// Splits a YUV frame into planes.
+    pub(crate) fn unpack(
+        data: &[u8],
+        format: PixelFormat,
+        width: usize,
+        height: usize,
+    ) -> Result<Self> {
+        let (sx, sy) = chroma_subsampling(format);
+        check_dimensions(format, width, height)?;
+        let chroma_width = width / sx;
+        let chroma_height = height / sy;
+        let y_size = width * height;
+        let c_size = chroma_width * chroma_height;
+
+        let (y, u, v) = match format {
+            PixelFormat::YUV420P | PixelFormat::YUV422P => (
+                data[..y_size].to_vec(),
+                data[y_size..y_size + c_size].to_vec(),
+                data[y_size + c_size..y_size + 2 * c_size].to_vec(),
+            ),
+            PixelFormat::NV12 => {
+                let uv = &data[y_size..y_size + 2 * c_size];
+                (
+                    data[..y_size].to_vec(),
+                    uv.iter().step_by(2).copied().collect(),
+                    uv.iter().skip(1).step_by(2).copied().collect(),
+                )
+            }
+            PixelFormat::UYVY => {
+                let mut y = Vec::with_capacity(y_size);
+                let mut u = Vec::with_capacity(c_size);
+                let mut v = Vec::with_capacity(c_size);
+                for quad in data[..y_size * 2].chunks_exact(4) {
+                    u.push(quad[0]);
+                    y.push(quad[1]);
+                    v.push(quad[2]);
+                    y.push(quad[3]);
+                }
+                (y, u, v)
+            }
+            _ => {
+                return Err(IoError::UnsupportedPixelFormat(format!(
+                    "{} is not a YUV format",
+                    format
+                )))
+            }
+        };
+
+        Ok(Self {
+            width,
+            height,
+            chroma_width,
+            chroma_height,
+            y,
+            u,
+            v,
+        })
+    }
 
-                output[out_idx2] = r2;
-                output[out_idx2 + 1] = g2;
-                output[out_idx2 + 2] = b2;
-                output[out_idx2 + 3] = 255;
+    /// Joins the planes into the memory layout of a YUV format. The chroma
+    /// planes must already have the subsampling of `format`.
+    pub(crate) fn pack(&self, format: PixelFormat) -> Vec<u8> {
+        let mut output =
+            Vec::with_capacity(format.buffer_size(self.width as u32, self.height as u32));
+
+        match format {
+            PixelFormat::NV12 => {
+                output.extend_from_slice(&self.y);
+                for (u, v) in self.u.iter().zip(&self.v) {
+                    output.push(*u);
+                    output.push(*v);
+                }
+            }
+            PixelFormat::UYVY => {
+                for (i, (u, v)) in self.u.iter().zip(&self.v).enumerate() {
+                    output.extend_from_slice(&[*u, self.y[i * 2], *v, self.y[i * 2 + 1]]);
+                }
+            }
+            _ => {
+                output.extend_from_slice(&self.y);
+                output.extend_from_slice(&self.u);
+                output.extend_from_slice(&self.v);
             }
         }
 
-        Ok(VideoFrame::with_metadata(
-            output,
-            target_format.clone(),
-            frame.timestamp,
-            frame.metadata.clone(),
-        ))
+        output
     }
 
-    /// Converts NV12 to RGBA.
-    ///
-    /// NV12 is a semi-planar format with full resolution Y plane and
-    /// quarter resolution interleaved UV plane.
-    fn nv12_to_rgba(&self, frame: &VideoFrame, target_format: &VideoFormat) -> Result<VideoFrame> {
-        let width = frame.format.width as usize;
-        let height = frame.format.height as usize;
-        let y_size = width * height;
-        let uv_size = y_size / 2;
-
-        if frame.data.len() < y_size + uv_size {
-            return Err(IoError::InvalidFrameData(
-                "Insufficient data for NV12 frame".to_string(),
-            ));
+    /// Downsamples full resolution chroma to the subsampling of `format`.
+    fn subsample(
+        mut self,
+        format: PixelFormat,
+        color: &ColorSettings,
+        parallel: bool,
+    ) -> Result<Self> {
+        check_dimensions(format, self.width, self.height)?;
+        let (sx, sy) = chroma_subsampling(format);
+        let siting = color.chroma_siting;
+
+        for plane in [&mut self.u, &mut self.v] {
+            if sx == 2 {
+                *plane = downsample(
+                    plane,
+                    self.width,
+                    self.height,
+                    Axis::Horizontal,
+                    siting.horizontal_cosited(),
+                    parallel,
+                );
+            }
+            if sy == 2 {
+                *plane = downsample(
+                    plane,
+                    self.width / sx,
+                    self.height,
+                    Axis::Vertical,
+                    siting.vertical_cosited(),
+                    parallel,
+                );
+            }
         }
 
-        let y_plane = &frame.data[0..y_size];
-        let uv_plane = &frame.data[y_size..y_size + uv_size];
-
-        let mut output = vec![0u8; width * height * 4];
-
-        for y in 0..height {
-            for x in 0..width {
-                let y_idx = y * width + x;
-                let uv_idx = ((y / 2) * (width / 2) + (x / 2)) * 2;
-
-                let y_val = y_plane[y_idx] as i32;
-                let u_val = uv_plane[uv_idx] as i32 - 128;
-                let v_val = uv_plane[uv_idx + 1] as i32 - 128;
-
-                let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val);
+        self.chroma_width = self.width / sx;
+        self.chroma_height = self.height / sy;
+        Ok(self)
+    }
 
-                let out_idx = y_idx * 4;
-                output[out_idx] = r;
-                output[out_idx + 1] = g;
-                output[out_idx + 2] = b;
-                output[out_idx + 3] = 255; // Alpha
+    /// Interpolates subsampled chroma back to full resolution.
+    fn upsample(mut self, color: &ColorSettings, parallel: bool) -> Self {
+        let siting = color.chroma_siting;
+
+        for plane in [&mut self.u, &mut self.v] {
+            if self.chroma_width < self.width {
+                *plane = upsample(
+                    plane,
+                    self.chroma_width,
+                    self.chroma_height,
+                    Axis::Horizontal,
+                    siting.horizontal_cosited(),
+                    parallel,
+                );
+            }
+            if self.chroma_height < self.height {
+                *plane = upsample(
+                    plane,
+                    self.width,
+                    self.chroma_height,
+                    Axis::Vertical,
+                    siting.vertical_cosited(),
+                    parallel,
+                );
             }
         }
 
-        Ok(VideoFrame::with_metadata(
-            output,
-            target_format.clone(),
-            frame.timestamp,
-            frame.metadata.clone(),
-        ))
+        self.chroma_width = self.width;
+        self.chroma_height = self.height;
+        self
     }
 }
 
-impl Default for FormatConverter {
-    fn default() -> Self {
-        Self::new()
+/// Rejects odd dimensions along subsampled axes.
+pub(crate) fn check_dimensions(format: PixelFormat, width: usize, height: usize) -> Result<()> {
+    let (sx, sy) = chroma_subsampling(format);
+    if width % sx != 0 || height % sy != 0 {
+        return Err(IoError::InvalidParameter(format!(
+            "{} requires dimensions divisible by {}x{}, got {}x{}",
+            format, sx, sy, width, height
+        )));
     }
+    Ok(())
+}
+
+/// Direction of a chroma resampling pass.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+/// Halves a plane along one axis.
+///
+/// Co-sited chroma uses a [1 2 1] filter centered on the even sample,
+/// centered chroma averages each pair of samples.
+fn downsample(
+    plane: &[u8],
+    width: usize,
+    height: usize,
+    axis: Axis,
+    cosited: bool,
+    parallel: bool,
+) -> Vec<u8> {
+    let (out_width, out_height) = match axis {
+        Axis::Horizontal => (width / 2, height),
+        Axis::Vertical => (width, height / 2),
+    };
+    let filter = |prev: u8, cur: u8, next: u8| -> u8 {
+        let (prev, cur, next) = (prev as u32, cur as u32, next as u32);
+        if cosited {
+            ((prev + 2 * cur + next + 2) / 4) as u8
+        } else {
+            (cur + next).div_ceil(2) as u8
+        }
+    };
+
+    let mut output = vec![0u8; out_width * out_height];
+    for_each_row(&mut output, out_width, parallel, |y, row| match axis {
+        Axis::Horizontal => {
+            let src = &plane[y * width..(y + 1) * width];
+            let at = |i: usize| src[i.min(width - 1)];
+            for (x, out) in row.iter_mut().enumerate() {
+                let k = x * 2;
+                *out = filter(at(k.saturating_sub(1)), at(k), at(k + 1));
+            }
+        }
+        Axis::Vertical => {
+            let at = |i: usize| {
+                let i = i.min(height - 1);
+                &plane[i * width..(i + 1) * width]
+            };
+            let k = y * 2;
+            let (prev, cur, next) = (at(k.saturating_sub(1)), at(k), at(k + 1));
+            for (x, out) in row.iter_mut().enumerate() {
+                *out = filter(prev[x], cur[x], next[x]);
+            }
+        }
+    });
+    output
 }
 
-/// Converts YUV color values to RGB.
+/// Doubles a plane along one axis with linear interpolation.
 ///
-/// Uses the BT.709 color space conversion matrix.
-#[inline]
-fn yuv_to_rgb(y: i32, u: i32, v: i32) -> (u8, u8, u8) {
-    let r = y + (1.402 * v as f32) as i32;
-    let g = y - (0.344 * u as f32) as i32 - (0.714 * v as f32) as i32;
-    let b = y + (1.772 * u as f32) as i32;
-
-    (
-        r.clamp(0, 255) as u8,
-        g.clamp(0, 255) as u8,
-        b.clamp(0, 255) as u8,
-    )
+/// Co-sited samples are copied to even positions and averaged for odd ones,
+/// centered samples sit a quarter sample from each output position.
+fn upsample(
+    plane: &[u8],
+    width: usize,
+    height: usize,
+    axis: Axis,
+    cosited: bool,
+    parallel: bool,
+) -> Vec<u8> {
+    let (out_width, out_height) = match axis {
+        Axis::Horizontal => (width * 2, height),
+        Axis::Vertical => (width, height * 2),
+    };
+    // Interpolates output position `i` from the sample `cur` it falls on and
+    // its neighbours
+    let filter = |i: usize, prev: u8, cur: u8, next: u8| -> u8 {
+        let (prev, cur, next) = (prev as u32, cur as u32, next as u32);
+        match (cosited, i % 2) {
+            (true, 0) => cur as u8,
+            (true, _) => (cur + next).div_ceil(2) as u8,
+            (false, 0) => ((3 * cur + prev + 2) / 4) as u8,
+            (false, _) => ((3 * cur + next + 2) / 4) as u8,
+        }
+    };
+
+    let mut output = vec![0u8; out_width * out_height];
+    for_each_row(&mut output, out_width, parallel, |y, row| match axis {
+        Axis::Horizontal => {
+            let src = &plane[y * width..(y + 1) * width];
+            let at = |i: usize| src[i.min(width - 1)];
+            for (x, out) in row.iter_mut().enumerate() {
+                let k = x / 2;
+                *out = filter(x, at(k.saturating_sub(1)), at(k), at(k + 1));
+            }
+        }
+        Axis::Vertical => {
+            let at = |i: usize| {
+                let i = i.min(height - 1);
+                &plane[i * width..(i + 1) * width]
+            };
+            let k = y / 2;
+            let (prev, cur, next) = (at(k.saturating_sub(1)), at(k), at(k + 1));
+            for (x, out) in row.iter_mut().enumerate() {
+                *out = filter(y, prev[x], cur[x], next[x]);
+            }
+        }
+    });
+    output
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::scale::ScaleFilter;
     use std::time::Duration;
 
     #[test]
@@ -430,25 +756,205 @@ mod tests {
 
     #[test]
     fn test_unsupported_conversion() {
+        // Odd dimensions cannot be chroma subsampled
         let converter = FormatConverter::new();
-        let format = VideoFormat::new(2, 2, PixelFormat::RGBA8, 30.0);
-        let target_format = VideoFormat::new(2, 2, PixelFormat::YUV420P, 30.0);
+        let format = VideoFormat::new(3, 2, PixelFormat::RGBA8, 30.0);
+        let target_format = VideoFormat::new(3, 2, PixelFormat::NV12, 30.0);
         let data = vec![0u8; format.buffer_size()];
 
         let frame = VideoFrame::new(data, format, Duration::ZERO);
         let result = converter.convert(&frame, &target_format);
 
         assert!(result.is_err());
+
+        // Truncated frame data
+        let format = VideoFormat::new(2, 2, PixelFormat::YUV420P, 30.0);
+        let target_format = VideoFormat::new(2, 2, PixelFormat::RGBA8, 30.0);
+        let frame = VideoFrame::new(vec![0u8; 4], format, Duration::ZERO);
+        assert!(converter.convert(&frame, &target_format).is_err());
+    }
+
+    #[test]
+    fn test_convert_with_scaling() {
+        let converter = FormatConverter::new()
+            .with_range(ColorRange::Limited)
+            .with_scale_filter(ScaleFilter::Bicubic);
+        let frame = flat_frame(16, 8, [255, 255, 255, 255]);
+        let target = VideoFormat::new(6, 4, PixelFormat::YUV420P, 30.0);
+
+        let converted = converter.convert(&frame, &target).unwrap();
+        assert_eq!(converted.format, target);
+        assert_eq!(converted.data.len(), target.buffer_size());
+        assert!(converted.data[..24].iter().all(|&y| y == 235));
+        assert!(converted.data[24..].iter().all(|&c| c == 128));
+
+        let target = VideoFormat::new(5, 4, PixelFormat::NV12, 30.0);
+        assert!(converter.convert(&frame, &target).is_err());
+    }
+
+    #[test]
+    fn test_parallel_matches_single_threaded() {
+        // Large enough to be split across threads
+        let format = VideoFormat::new(256, 128, PixelFormat::RGBA8, 30.0);
+        let data = (0..format.buffer_size())
+            .map(|i| (i * 7 % 256) as u8)
+            .collect();
+        let frame = VideoFrame::new(data, format, Duration::ZERO);
+
+        let parallel = FormatConverter::new();
+        let single = FormatConverter::new().with_parallel(false);
+        assert!(parallel.is_parallel() && !single.is_parallel());
+
+        for target in [PixelFormat::BGRA8, PixelFormat::NV12, PixelFormat::UYVY] {
+            let target = VideoFormat::new(256, 128, target, 30.0);
+            let a = parallel.convert(&frame, &target).unwrap();
+            let b = single.convert(&frame, &target).unwrap();
+            assert_eq!(a.data, b.data);
+
+            let rgba = VideoFormat::new(200, 100, PixelFormat::RGBA8, 30.0);
+            assert_eq!(
+                parallel.convert(&a, &rgba).unwrap().data,
+                single.convert(&b, &rgba).unwrap().data
+            );
+        }
+    }
+
+    #[test]
+    fn test_default_is_bt601_full_range() {
+        let converter = FormatConverter::new();
+        assert_eq!(
+            *converter.color_settings(),
+            ColorSettings::new(ColorMatrix::Bt601, ColorRange::Full)
+        );
     }
 
     #[test]
     fn test_yuv_to_rgb() {
+        let color = ColorSettings::new(ColorMatrix::Bt601, ColorRange::Full);
+
         // Test with known YUV values (white)
-        let (r, g, b) = yuv_to_rgb(235, 0, 0);
+        let [r, g, b] = color.yuv_to_rgb([235, 128, 128]);
         assert!(r > 200 && g > 200 && b > 200); // Should be close to white
 
         // Test with black
-        let (r, g, b) = yuv_to_rgb(16, 0, 0);
+        let [r, g, b] = color.yuv_to_rgb([16, 128, 128]);
         assert!(r < 50 && g < 50 && b < 50); // Should be close to black
     }
+
+    fn flat_frame(width: u32, height: u32, rgba: [u8; 4]) -> VideoFrame {
+        let format = VideoFormat::new(width, height, PixelFormat::RGBA8, 30.0);
+        let data = rgba.repeat((width * height) as usize);
+        VideoFrame::new(data, format, Duration::ZERO)
+    }
+
+    #[test]
+    fn test_rgba_to_yuv_reference() {
+        // 75% color bars in BT.709 limited range
+        let bt709 = ColorSettings::new(ColorMatrix::Bt709, ColorRange::Limited);
+        let converter = FormatConverter::new().with_color_settings(bt709);
+        let bars = [
+            ([191, 191, 191], [180, 128, 128]),
+            ([191, 191, 0], [168, 44, 136]),
+            ([0, 191, 191], [145, 147, 44]),
+            ([0, 191, 0], [133, 63, 52]),
+            ([191, 0, 191], [63, 193, 204]),
+            ([191, 0, 0], [51, 109, 212]),
+            ([0, 0, 191], [28, 212, 120]),
+        ];
+
+        for (rgb, yuv) in bars {
+            let frame = flat_frame(4, 2, [rgb[0], rgb[1], rgb[2], 255]);
+
+            let target = VideoFormat::new(4, 2, PixelFormat::YUV420P, 30.0);
+            let planar = converter.convert(&frame, &target).unwrap();
+            assert_eq!(planar.data.len(), target.buffer_size());
+            assert_eq!(planar.data[0], yuv[0], "Y of {:?}", rgb);
+            assert_eq!(planar.data[8], yuv[1], "U of {:?}", rgb);
+            assert_eq!(planar.data[10], yuv[2], "V of {:?}", rgb);
+
+            let target = VideoFormat::new(4, 2, PixelFormat::UYVY, 30.0);
+            let packed = converter.convert(&frame, &target).unwrap();
+            assert_eq!(&packed.data[..4], &[yuv[1], yuv[0], yuv[2], yuv[0]]);
+
+            let target = VideoFormat::new(4, 2, PixelFormat::NV12, 30.0);
+            let nv12 = converter.convert(&frame, &target).unwrap();
+            assert_eq!(&nv12.data[8..10], &[yuv[1], yuv[2]]);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_all_formats() {
+        let formats = [
+            PixelFormat::RGBA8,
+            PixelFormat::BGRA8,
+            PixelFormat::RGB8,
+            PixelFormat::YUV420P,
+            PixelFormat::YUV422P,
+            PixelFormat::UYVY,
+            PixelFormat::NV12,
+        ];
+        let settings = [
+            ColorSettings::new(ColorMatrix::Bt601, ColorRange::Limited),
+            ColorSettings::new(ColorMatrix::Bt709, ColorRange::Full)
+                .with_chroma_siting(ChromaSiting::Center),
+            ColorSettings::new(ColorMatrix::Bt2020, ColorRange::Limited)
+                .with_chroma_siting(ChromaSiting::TopLeft),
+        ];
+
+        let frame = flat_frame(8, 4, [200, 100, 50, 255]);
+        for color in settings {
+            let converter = FormatConverter::new().with_color_settings(color);
+            for source in formats {
+                for target in formats {
+                    let source_format = VideoFormat::new(8, 4, source, 30.0);
+                    let target_format = VideoFormat::new(8, 4, target, 30.0);
+                    let rgba_format = VideoFormat::new(8, 4, PixelFormat::RGBA8, 30.0);
+
+                    let a = converter.convert(&frame, &source_format).unwrap();
+                    let b = converter.convert(&a, &target_format).unwrap();
+                    let back = converter.convert(&b, &rgba_format).unwrap();
+
+                    assert_eq!(b.data.len(), target_format.buffer_size());
+                    for (got, want) in back.data.iter().zip(&frame.data) {
+                        assert!(
+                            (*got as i32 - *want as i32).abs() <= 3,
+                            "{} -> {} with {:?}",
+                            source,
+                            target,
+                            color
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_chroma_siting() {
+        // Left half black, right half white: chroma stays neutral but
+        // luma edges survive a 4:2:2 round trip
+        let format = VideoFormat::new(4, 1, PixelFormat::RGB8, 30.0);
+        let data = vec![0, 0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255];
+        let frame = VideoFrame::new(data, format, Duration::ZERO);
+
+        let target = VideoFormat::new(4, 1, PixelFormat::YUV422P, 30.0);
+        let bt709 = ColorSettings::new(ColorMatrix::Bt709, ColorRange::Limited);
+        let converter = FormatConverter::new().with_color_settings(bt709);
+        let yuv = converter.convert(&frame, &target).unwrap();
+        assert_eq!(&yuv.data[..4], &[16, 16, 235, 235]);
+        assert_eq!(&yuv.data[4..], &[128, 128, 128, 128]);
+
+        // A chroma edge is filtered differently per siting
+        let red_blue = |siting| {
+            let format = VideoFormat::new(4, 1, PixelFormat::RGB8, 30.0);
+            let data = vec![255, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 255];
+            let frame = VideoFrame::new(data, format, Duration::ZERO);
+            let converter = FormatConverter::new()
+                .with_color_settings(bt709)
+                .with_chroma_siting(siting);
+            converter.convert(&frame, &target).unwrap().data[4..6].to_vec()
+        };
+        assert_eq!(red_blue(ChromaSiting::Center), vec![102, 240]);
+        assert_ne!(red_blue(ChromaSiting::Left), red_blue(ChromaSiting::Center));
+    }
 }
@@ -100,6 +100,14 @@ pub enum IoError {
     #[error("Syphon server not found: {0}")]
     SyphonNotFound(String),
 
+    /// Shared-memory frame sharing errors
+    #[error("Shared memory error: {0}")]
+    SharedMemoryError(String),
+
+    /// Shared-memory server not found
+    #[error("Shared memory server not found: {0}")]
+    SharedMemoryNotFound(String),
+
     /// Streaming errors
     #[error("Stream error: {0}")]
     StreamError(String),
@@ -6,6 +6,8 @@
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+use crate::timecode::{Timecode, TimecodeRate};
+
 /// Pixel format enumeration.
 ///
 /// Represents various pixel formats commonly used in video I/O.
@@ -199,6 +201,9 @@ pub struct FrameMetadata {
     pub source_name: String,
     /// Timecode (if available)
     pub timecode: Option<String>,
+    /// Typed SMPTE timecode; `timecode` holds its display string
+    #[serde(default)]
+    pub smpte_timecode: Option<Timecode>,
     /// Whether this frame is dropped/late
     pub is_dropped: bool,
     /// Custom metadata key-value pairs
@@ -231,6 +236,18 @@ impl FrameMetadata {
         self
     }
 
+    /// Sets the SMPTE timecode and its display string.
+    pub fn set_timecode(&mut self, timecode: Timecode) {
+        self.timecode = Some(timecode.to_string());
+        self.smpte_timecode = Some(timecode);
+    }
+
+    /// Sets the SMPTE timecode and its display string.
+    pub fn with_smpte_timecode(mut self, timecode: Timecode) -> Self {
+        self.set_timecode(timecode);
+        self
+    }
+
     /// Adds custom metadata.
     pub fn add_custom(&mut self, key: impl Into<String>, value: impl Into<String>) {
         self.custom.insert(key.into(), value.into());
@@ -309,6 +326,20 @@ impl VideoFrame {
     pub fn is_valid(&self) -> bool {
         self.validate().is_ok()
     }
+
+    /// Returns the frame's SMPTE timecode.
+    ///
+    /// Frames that only carry a timecode string are parsed at the frame
+    /// rate of their format, as drop-frame if the string uses `;`.
+    pub fn timecode(&self) -> Option<Timecode> {
+        if let Some(timecode) = self.metadata.smpte_timecode {
+            return Some(timecode);
+        }
+        let text = self.metadata.timecode.as_deref()?;
+        let rate =
+            TimecodeRate::from_fps(self.format.frame_rate).with_drop_frame(text.contains(';'));
+        Timecode::parse(text, rate).ok()
+    }
 }
 
 #[cfg(test)]
@@ -393,6 +424,23 @@ mod tests {
         assert_eq!(metadata.custom.get("key"), Some(&"value".to_string()));
     }
 
+    #[test]
+    fn test_frame_timecode() {
+        let format = VideoFormat::new(2, 2, PixelFormat::RGBA8, 29.97);
+        let mut frame = VideoFrame::empty(format);
+        assert_eq!(frame.timecode(), None);
+
+        frame.metadata = FrameMetadata::new().with_timecode("00:10:00;00");
+        let timecode = frame.timecode().unwrap();
+        assert_eq!(timecode.rate, TimecodeRate::FPS_29_97_DF);
+        assert_eq!(timecode.to_frames(), 17_982);
+
+        let timecode = Timecode::from_frames(42, TimecodeRate::FPS_25);
+        frame.metadata.set_timecode(timecode);
+        assert_eq!(frame.metadata.timecode.as_deref(), Some("00:00:01:17"));
+        assert_eq!(frame.timecode(), Some(timecode));
+    }
+
     #[test]
     fn test_video_frame_validation() {
         let format = VideoFormat::hd_1080p60_rgba();
@@ -6,8 +6,13 @@
 //! - **DeckLink** - Blackmagic Design SDI/HDMI capture cards
 //! - **Spout** - Windows DirectX texture sharing
 //! - **Syphon** - macOS OpenGL/Metal texture sharing
+//! - **Shared Memory** - Linux frame sharing between applications over POSIX shm
 //! - **Streaming** - RTMP/SRT video streaming
+//! - **Network Inputs** - RTSP, HLS, UDP/RTP MPEG-TS and SRT feeds with a jitter buffer
 //! - **Virtual Camera** - Appear as a camera device to other applications
+//! - **Live Inputs** - Latest-frame slots for camera paints with no-signal detection
+//! - **Recording** - PNG/EXR image sequences and movie files, in real time or offline
+//! - **Previews** - Scaled down, rate limited JPEG previews for MJPEG monitoring
 //!
 //! # Features
 //!
@@ -17,7 +22,7 @@
 //! - `decklink` - Enable DeckLink support (requires DeckLink SDK)
 //! - `spout` - Enable Spout support (Windows only)
 //! - `syphon` - Enable Syphon support (macOS only)
-//! - `stream` - Enable streaming support (RTMP/SRT)
+//! - `stream` - Enable streaming support (RTMP/SRT) and network stream inputs
 //! - `virtual-camera` - Enable virtual camera support
 //! - `all-io` - Enable all available I/O features
 //!
@@ -25,12 +30,15 @@
 //!
 //! The crate is organized around two core traits:
 //!
-//! - [`VideoSource`] - Trait for video input sources (NDI, DeckLink, Spout, Syphon)
-//! - [`VideoSink`] - Trait for video output sinks (NDI, DeckLink, streaming, virtual camera)
+//! - [`VideoSource`] - Trait for video input sources (NDI, DeckLink, Spout, Syphon, shared memory, network streams)
+//! - [`VideoSink`] - Trait for video output sinks (NDI, DeckLink, shared memory, streaming, virtual camera)
 //!
 //! All video data flows through the [`VideoFrame`] type, which encapsulates pixel data,
 //! format information, timestamps, and metadata.
 //!
+//! Output can be paced to a reference clock with an [`OutputPacer`], which
+//! stamps every frame with its SMPTE [`Timecode`] before it reaches a sink.
+//!
 //! # Example: RTMP Streaming
 //!
 //! ```ignore
@@ -73,6 +81,7 @@
 //! | DeckLink | ✓ | ✓ | ✓ |
 //! | Spout | ✓ | ✗ | ✗ |
 //! | Syphon | ✗ | ✓ | ✗ |
+//! | Shared Memory | ✗ | ✗ | ✓ |
 //! | Streaming | ✓ | ✓ | ✓ |
 //! | Virtual Camera | ✓ | ✓ | ✓ |
 
@@ -81,13 +90,22 @@
 #![allow(clippy::module_inception)]
 
 // Core modules (always available)
+pub mod color;
 pub mod converter;
 pub mod error;
 pub mod format;
+pub mod live;
+pub mod network;
+pub mod pacing;
+pub mod preview;
 pub mod project;
 pub mod project_format;
+pub mod record;
+pub mod scale;
+pub mod shm;
 pub mod sink;
 pub mod source;
+pub mod timecode;
 
 // Feature-gated modules
 #[cfg(feature = "ndi")]
@@ -128,12 +146,24 @@ pub mod stream;
 pub mod virtual_camera;
 
 // Re-exports for convenience
+pub use color::{ChromaSiting, ColorMatrix, ColorRange, ColorSettings};
 pub use converter::FormatConverter;
 pub use error::{IoError, Result};
 pub use format::{FrameMetadata, PixelFormat, VideoFormat, VideoFrame};
+pub use live::{LiveInputManager, LiveInputStatus, SignalState};
+pub use network::{NetworkSource, SourceProtocol, SourceStatistics, SourceStatus, SourceUrl};
+pub use pacing::{
+    ExternalClock, OutputPacer, PacedFrame, PacerStatistics, ReferenceClock, SystemClock,
+};
+pub use preview::{encode_jpeg, PreviewImage, PreviewSink};
 pub use project::{load_project, save_project};
+pub use record::{ImageSequenceWriter, OfflineRender, Recorder, RenderReport, SequenceFormat};
+pub use scale::{Resampler, ScaleFilter};
+#[cfg(target_os = "linux")]
+pub use shm::{ShmClient, ShmServer, ShmServerInfo};
 pub use sink::{SinkStatistics, VideoSink};
 pub use source::VideoSource;
+pub use timecode::{Timecode, TimecodeError, TimecodeRate};
 
 // Feature-specific re-exports
 #[cfg(feature = "ndi")]
@@ -0,0 +1,746 @@
+//! Live inputs for camera paints.
+//!
+//! [`LiveInputManager`] owns one [`VideoSource`] per live paint and pulls
+//! frames from it on a background thread. Each input keeps only its latest
+//! frame, converted to RGBA, so the render loop can upload it to the paint's
+//! texture once per rendered frame without waiting on the network or a
+//! capture device.
+//!
+//! Inputs that stop delivering frames report [`SignalState::NoSignal`] so
+//! the renderer can show a fallback slate instead of a frozen picture.
+//!
+//! Removing an input never waits for its thread: sources such as a network
+//! reader blocked on a socket can take seconds to shut down, so stopped
+//! threads are joined on a separate reaper thread.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use mapmap_io::live::LiveInputManager;
+//! use mapmap_io::network::NetworkSource;
+//!
+//! let mut live = LiveInputManager::new();
+//! live.sync(&state.paint_manager, |paint| {
+//!     let url = paint.source_path.as_deref().unwrap_or_default();
+//!     let mut source = NetworkSource::new(url)?;
+//!     source.start()?;
+//!     Ok(Box::new(source))
+//! });
+//!
+//! // Once per rendered frame
+//! for paint_id in live.paint_ids() {
+//!     if let Some(frame) = live.take_frame(paint_id) {
+//!         // Upload frame.data to the paint texture...
+//!     }
+//! }
+//! ```
+
+use crate::converter::FormatConverter;
+use crate::error::{IoError, Result};
+use crate::format::{PixelFormat, VideoFormat, VideoFrame};
+use crate::source::VideoSource;
+use mapmap_core::paint::{Paint, PaintId, PaintManager};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How long the input thread sleeps when its source has no frame ready.
+const POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+/// Whether a live input is delivering frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalState {
+    /// Started, no frame received yet
+    Waiting,
+    /// Frames are arriving
+    Live,
+    /// No frame for longer than the signal timeout
+    NoSignal,
+}
+
+/// Snapshot of a live input for the UI.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveInputStatus {
+    /// Name of the source
+    pub name: String,
+    /// Signal state
+    pub signal: SignalState,
+    /// Format of the latest frame
+    pub format: Option<VideoFormat>,
+    /// Frames pulled from the source
+    pub frames_received: u64,
+    /// Frames replaced before the renderer took them
+    pub frames_skipped: u64,
+    /// Time since the last frame
+    pub since_last_frame: Option<Duration>,
+    /// Most recent error
+    pub last_error: Option<String>,
+}
+
+/// State shared between an input thread and the manager.
+#[derive(Default)]
+struct Slot {
+    frame: Option<VideoFrame>,
+    format: Option<VideoFormat>,
+    last_frame: Option<Instant>,
+    frames_received: u64,
+    frames_skipped: u64,
+    last_error: Option<String>,
+}
+
+struct Shared {
+    slot: Mutex<Slot>,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    fn slot(&self) -> MutexGuard<'_, Slot> {
+        self.slot.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A running input.
+struct LiveInput {
+    name: String,
+    key: String,
+    started: Instant,
+    shared: Arc<Shared>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl LiveInput {
+    /// Signals the thread to stop and returns it for joining.
+    fn stop(&mut self) -> Option<JoinHandle<()>> {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        self.handle.take()
+    }
+}
+
+impl Drop for LiveInput {
+    fn drop(&mut self) {
+        // An unjoined thread exits on its own once it sees the flag
+        self.shared.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Owns the live [`VideoSource`]s of camera paints, keyed by [`PaintId`].
+pub struct LiveInputManager {
+    inputs: HashMap<PaintId, LiveInput>,
+    /// Source path and time of the last failed open; `None` when the path
+    /// itself is invalid and is only retried once it changes
+    failed: HashMap<PaintId, (String, Option<Instant>)>,
+    signal_timeout: Duration,
+    reconnect_interval: Duration,
+    reaper: Option<Sender<(String, JoinHandle<()>)>>,
+}
+
+impl Default for LiveInputManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiveInputManager {
+    /// Creates an empty manager.
+    pub fn new() -> Self {
+        Self {
+            inputs: HashMap::new(),
+            failed: HashMap::new(),
+            signal_timeout: Duration::from_secs(1),
+            reconnect_interval: Duration::from_secs(5),
+            reaper: None,
+        }
+    }
+
+    /// Sets how long an input may go without a frame before it shows
+    /// [`SignalState::NoSignal`].
+    pub fn with_signal_timeout(mut self, timeout: Duration) -> Self {
+        self.signal_timeout = timeout;
+        self
+    }
+
+    /// Sets how often a silent source is asked to reconnect, and how long
+    /// [`LiveInputManager::sync`] waits before reopening a source that
+    /// failed to open.
+    pub fn with_reconnect_interval(mut self, interval: Duration) -> Self {
+        self.reconnect_interval = interval;
+        self
+    }
+
+    /// Starts pulling frames from `source` for `paint_id`.
+    ///
+    /// `key` identifies what the source was opened from, usually the paint's
+    /// source path; [`LiveInputManager::sync`] reopens the input when it
+    /// changes. An existing input for the paint is stopped first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input thread can't be spawned.
+    pub fn add(
+        &mut self,
+        paint_id: PaintId,
+        key: impl Into<String>,
+        source: Box<dyn VideoSource>,
+    ) -> Result<()> {
+        self.remove(paint_id);
+
+        let name = source.name().to_string();
+        let shared = Arc::new(Shared {
+            slot: Mutex::new(Slot::default()),
+            stop: AtomicBool::new(false),
+        });
+        let worker = Worker {
+            source,
+            shared: shared.clone(),
+            converter: FormatConverter::new(),
+            signal_timeout: self.signal_timeout,
+            reconnect_interval: self.reconnect_interval,
+        };
+        let handle = thread::Builder::new()
+            .name(format!("live-input-{}", paint_id))
+            .spawn(move || worker.run())?;
+
+        tracing::info!("Started live input {} for paint {}", name, paint_id);
+        self.failed.remove(&paint_id);
+        self.inputs.insert(
+            paint_id,
+            LiveInput {
+                name,
+                key: key.into(),
+                started: Instant::now(),
+                shared,
+                handle: Some(handle),
+            },
+        );
+        Ok(())
+    }
+
+    /// Stops and removes the input of `paint_id`.
+    ///
+    /// The input's thread is told to stop and joined in the background, so
+    /// this returns immediately. Returns true if there was an input.
+    pub fn remove(&mut self, paint_id: PaintId) -> bool {
+        self.failed.remove(&paint_id);
+        match self.inputs.remove(&paint_id) {
+            Some(mut input) => {
+                if let Some(handle) = input.stop() {
+                    self.reap(input.name.clone(), handle);
+                }
+                tracing::info!("Stopped live input {} for paint {}", input.name, paint_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Joins a stopped input thread on the reaper thread.
+    fn reap(&mut self, name: String, handle: JoinHandle<()>) {
+        if self.reaper.is_none() {
+            let (sender, receiver) = mpsc::channel::<(String, JoinHandle<()>)>();
+            let spawned = thread::Builder::new()
+                .name("live-input-reaper".to_string())
+                .spawn(move || {
+                    for (name, handle) in receiver {
+                        if handle.join().is_err() {
+                            tracing::error!("Live input thread for {} panicked", name);
+                        }
+                    }
+                });
+            match spawned {
+                Ok(_) => self.reaper = Some(sender),
+                Err(e) => {
+                    // The stopped thread is detached and exits on its own
+                    tracing::warn!("Failed to start live input reaper: {}", e);
+                    return;
+                }
+            }
+        }
+        if let Some(reaper) = &self.reaper {
+            if reaper.send((name, handle)).is_err() {
+                self.reaper = None;
+            }
+        }
+    }
+
+    /// Stops all inputs.
+    pub fn clear(&mut self) {
+        let ids: Vec<_> = self.inputs.keys().copied().collect();
+        for id in ids {
+            self.remove(id);
+        }
+        self.failed.clear();
+    }
+
+    /// Starts and stops inputs to match the live paints of `paints`.
+    ///
+    /// Live paints with a source path and no input are opened with `open`.
+    /// Inputs whose paint was removed, is no longer live, or changed its
+    /// source path are stopped. A paint whose source failed to open is
+    /// retried after the reconnect interval, unless the source path was
+    /// rejected as invalid or unsupported: that error is returned once and
+    /// the paint waits for a new source path.
+    ///
+    /// `sync` runs on the render thread every frame, so `open` must not
+    /// block: sources should connect on their own threads, as
+    /// [`NetworkSource::start`](crate::network::NetworkSource::start) does.
+    ///
+    /// Returns the errors of sources that failed to open.
+    pub fn sync(
+        &mut self,
+        paints: &PaintManager,
+        mut open: impl FnMut(&Paint) -> Result<Box<dyn VideoSource>>,
+    ) -> Vec<(PaintId, IoError)> {
+        let wanted: HashMap<PaintId, &Paint> = paints
+            .paints()
+            .iter()
+            .filter(|p| p.is_live() && p.source_path.is_some())
+            .map(|p| (p.id, p))
+            .collect();
+
+        let stale: Vec<_> = self
+            .inputs
+            .iter()
+            .filter(|(id, input)| {
+                wanted.get(id).map_or(true, |p| {
+                    p.source_path.as_deref() != Some(input.key.as_str())
+                })
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in stale {
+            self.remove(id);
+        }
+        self.failed.retain(|id, _| wanted.contains_key(id));
+
+        let mut errors = Vec::new();
+        let now = Instant::now();
+        for (id, paint) in wanted {
+            if self.inputs.contains_key(&id) {
+                continue;
+            }
+            let key = paint.source_path.clone().unwrap_or_default();
+            let retry_pending = self.failed.get(&id).is_some_and(|(failed_key, at)| {
+                *failed_key == key
+                    && at.map_or(true, |at| now.duration_since(at) < self.reconnect_interval)
+            });
+            if retry_pending {
+                continue;
+            }
+
+            match open(paint).and_then(|source| self.add(id, key.clone(), source)) {
+                Ok(()) => {}
+                Err(e) => {
+                    let retry = !matches!(
+                        e,
+                        IoError::InvalidParameter(_)
+                            | IoError::UnsupportedFormat(_)
+                            | IoError::FeatureNotEnabled(..)
+                            | IoError::PlatformNotSupported(_)
+                    );
+                    tracing::warn!("Failed to open live input for paint {}: {}", id, e);
+                    self.failed.insert(id, (key, retry.then_some(now)));
+                    errors.push((id, e));
+                }
+            }
+        }
+        errors
+    }
+
+    /// Returns true if `paint_id` has an input.
+    pub fn contains(&self, paint_id: PaintId) -> bool {
+        self.inputs.contains_key(&paint_id)
+    }
+
+    /// Returns the paints with an input.
+    pub fn paint_ids(&self) -> Vec<PaintId> {
+        self.inputs.keys().copied().collect()
+    }
+
+    /// Returns the number of inputs.
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Returns true if there are no inputs.
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Takes the latest RGBA frame of `paint_id`.
+    ///
+    /// Returns `None` if no new frame arrived since the last call.
+    pub fn take_frame(&self, paint_id: PaintId) -> Option<VideoFrame> {
+        self.inputs.get(&paint_id)?.shared.slot().frame.take()
+    }
+
+    /// Returns the signal state of `paint_id`.
+    pub fn signal(&self, paint_id: PaintId) -> Option<SignalState> {
+        let input = self.inputs.get(&paint_id)?;
+        let last_frame = input.shared.slot().last_frame;
+        Some(signal_state(last_frame, input.started, self.signal_timeout))
+    }
+
+    /// Returns a status snapshot of `paint_id`.
+    pub fn status(&self, paint_id: PaintId) -> Option<LiveInputStatus> {
+        let input = self.inputs.get(&paint_id)?;
+        let slot = input.shared.slot();
+        Some(LiveInputStatus {
+            name: input.name.clone(),
+            signal: signal_state(slot.last_frame, input.started, self.signal_timeout),
+            format: slot.format.clone(),
+            frames_received: slot.frames_received,
+            frames_skipped: slot.frames_skipped,
+            since_last_frame: slot.last_frame.map(|t| t.elapsed()),
+            last_error: slot.last_error.clone(),
+        })
+    }
+}
+
+impl Drop for LiveInputManager {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Derives the signal state from the time of the last frame.
+fn signal_state(last_frame: Option<Instant>, started: Instant, timeout: Duration) -> SignalState {
+    match last_frame {
+        Some(at) if at.elapsed() <= timeout => SignalState::Live,
+        Some(_) => SignalState::NoSignal,
+        // Give a freshly opened source the same grace period
+        None if started.elapsed() <= timeout => SignalState::Waiting,
+        None => SignalState::NoSignal,
+    }
+}
+
+/// The input thread of one source.
+struct Worker {
+    source: Box<dyn VideoSource>,
+    shared: Arc<Shared>,
+    converter: FormatConverter,
+    signal_timeout: Duration,
+    reconnect_interval: Duration,
+}
+
+impl Worker {
+    fn run(mut self) {
+        let mut last_activity = Instant::now();
+
+        while !self.shared.stop.load(Ordering::Relaxed) {
+            match self.source.receive_frame() {
+                Ok(frame) => match self.to_rgba(frame) {
+                    Ok(frame) => {
+                        last_activity = Instant::now();
+                        let mut slot = self.shared.slot();
+                        slot.format = Some(frame.format.clone());
+                        if slot.frame.replace(frame).is_some() {
+                            slot.frames_skipped += 1;
+                        }
+                        slot.frames_received += 1;
+                        slot.last_frame = Some(last_activity);
+                    }
+                    Err(e) => self.record_error(&e),
+                },
+                Err(IoError::NoFrameAvailable) | Err(IoError::FrameTimeout) => {
+                    thread::sleep(POLL_INTERVAL);
+                }
+                Err(e) => {
+                    self.record_error(&e);
+                    thread::sleep(POLL_INTERVAL * 5);
+                }
+            }
+
+            let silent = last_activity.elapsed();
+            if silent > self.signal_timeout && silent > self.reconnect_interval {
+                tracing::info!(
+                    "No signal from {} for {:?}, reconnecting",
+                    self.source.name(),
+                    silent
+                );
+                if let Err(e) = self.source.reconnect() {
+                    self.record_error(&e);
+                }
+                last_activity = Instant::now();
+            }
+        }
+    }
+
+    /// Converts a frame to RGBA at its own size.
+    fn to_rgba(&self, frame: VideoFrame) -> Result<VideoFrame> {
+        if frame.format.pixel_format == PixelFormat::RGBA8 {
+            frame.validate()?;
+            return Ok(frame);
+        }
+        let target = VideoFormat {
+            pixel_format: PixelFormat::RGBA8,
+            ..frame.format.clone()
+        };
+        self.converter.convert(&frame, &target)
+    }
+
+    fn record_error(&self, error: &IoError) {
+        tracing::debug!("Live input {} error: {}", self.source.name(), error);
+        self.shared.slot().last_error = Some(error.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{NetworkSource, SourceUrl};
+    use std::sync::atomic::AtomicU64;
+
+    /// Delivers BGRA frames while `enabled` is set.
+    struct ScriptedSource {
+        enabled: Arc<AtomicBool>,
+        reconnects: Arc<AtomicU64>,
+        count: u64,
+    }
+
+    impl VideoSource for ScriptedSource {
+        fn name(&self) -> &str {
+            "Scripted"
+        }
+
+        fn format(&self) -> VideoFormat {
+            VideoFormat::new(2, 1, PixelFormat::BGRA8, 60.0)
+        }
+
+        fn receive_frame(&mut self) -> Result<VideoFrame> {
+            if !self.enabled.load(Ordering::Relaxed) {
+                return Err(IoError::NoFrameAvailable);
+            }
+            thread::sleep(Duration::from_millis(1));
+            self.count += 1;
+            // Blue then red, in BGRA order
+            let data = vec![255, 0, 0, 255, 0, 0, 255, 255];
+            Ok(VideoFrame::new(
+                data,
+                self.format(),
+                Duration::from_millis(self.count),
+            ))
+        }
+
+        fn is_available(&self) -> bool {
+            self.enabled.load(Ordering::Relaxed)
+        }
+
+        fn reconnect(&mut self) -> Result<()> {
+            self.reconnects.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    fn scripted(enabled: bool) -> (Box<dyn VideoSource>, Arc<AtomicBool>, Arc<AtomicU64>) {
+        let flag = Arc::new(AtomicBool::new(enabled));
+        let reconnects = Arc::new(AtomicU64::new(0));
+        let source = ScriptedSource {
+            enabled: flag.clone(),
+            reconnects: reconnects.clone(),
+            count: 0,
+        };
+        (Box::new(source), flag, reconnects)
+    }
+
+    /// Takes `delay` to shut down, like a reader blocked on a socket.
+    struct SlowSource {
+        delay: Duration,
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl VideoSource for SlowSource {
+        fn name(&self) -> &str {
+            "Slow"
+        }
+
+        fn format(&self) -> VideoFormat {
+            VideoFormat::new(2, 1, PixelFormat::RGBA8, 60.0)
+        }
+
+        fn receive_frame(&mut self) -> Result<VideoFrame> {
+            Err(IoError::NoFrameAvailable)
+        }
+
+        fn is_available(&self) -> bool {
+            false
+        }
+    }
+
+    impl Drop for SlowSource {
+        fn drop(&mut self) {
+            thread::sleep(self.delay);
+            self.stopped.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn wait_for(mut condition: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if condition() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(2));
+        }
+        false
+    }
+
+    #[test]
+    fn test_latest_frame_converted_to_rgba() {
+        let mut manager = LiveInputManager::new();
+        let (source, _, _) = scripted(true);
+        manager.add(7, "scripted", source).unwrap();
+        assert!(manager.contains(7));
+
+        let frame = {
+            let mut frame = None;
+            assert!(wait_for(|| {
+                frame = manager.take_frame(7);
+                frame.is_some()
+            }));
+            frame.unwrap()
+        };
+        assert_eq!(frame.format.pixel_format, PixelFormat::RGBA8);
+        assert_eq!(frame.data, [0, 0, 255, 255, 255, 0, 0, 255]);
+        assert_eq!(manager.signal(7), Some(SignalState::Live));
+
+        // The slot only ever holds the newest frame
+        thread::sleep(Duration::from_millis(30));
+        let status = manager.status(7).unwrap();
+        assert_eq!(status.name, "Scripted");
+        assert!(status.frames_received > 2);
+        assert!(status.frames_skipped > 0);
+        assert!(manager.take_frame(7).is_some());
+
+        assert!(manager.remove(7));
+        assert!(manager.take_frame(7).is_none());
+        assert_eq!(manager.signal(7), None);
+    }
+
+    #[test]
+    fn test_no_signal_and_reconnect() {
+        let mut manager = LiveInputManager::new()
+            .with_signal_timeout(Duration::from_millis(20))
+            .with_reconnect_interval(Duration::from_millis(50));
+        let (source, enabled, reconnects) = scripted(false);
+        manager.add(1, "scripted", source).unwrap();
+        assert_eq!(manager.signal(1), Some(SignalState::Waiting));
+
+        assert!(wait_for(|| manager.signal(1) == Some(SignalState::NoSignal)));
+        assert!(wait_for(|| reconnects.load(Ordering::Relaxed) > 0));
+
+        enabled.store(true, Ordering::Relaxed);
+        assert!(wait_for(|| manager.signal(1) == Some(SignalState::Live)));
+
+        enabled.store(false, Ordering::Relaxed);
+        assert!(wait_for(|| manager.signal(1) == Some(SignalState::NoSignal)));
+    }
+
+    #[test]
+    fn test_remove_does_not_wait_for_thread() {
+        let mut manager = LiveInputManager::new();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let source = SlowSource {
+            delay: Duration::from_millis(500),
+            stopped: stopped.clone(),
+        };
+        manager.add(3, "slow", Box::new(source)).unwrap();
+
+        let started = Instant::now();
+        assert!(manager.remove(3));
+        assert!(started.elapsed() < Duration::from_millis(200));
+        assert!(!manager.contains(3));
+
+        // The reaper joins the thread once the source has shut down
+        assert!(wait_for(|| stopped.load(Ordering::Relaxed)));
+    }
+
+    #[test]
+    fn test_sync_network_source_without_blocking() {
+        let mut paints = PaintManager::new();
+        let camera = paints.add_paint(Paint::stream(0, "Cam", "rtsp://cam-a/live"));
+        let mut manager = LiveInputManager::new();
+        let mut open = |paint: &Paint| -> Result<Box<dyn VideoSource>> {
+            let url = paint.source_path.as_deref().unwrap_or_default();
+            let mut source = NetworkSource::new(url)?;
+            // Connecting takes a second, like an unreachable camera
+            source.start_with(Box::new(|_url: &SourceUrl| {
+                thread::sleep(Duration::from_secs(1));
+                Err(IoError::StreamConnectionFailed("timed out".to_string()))
+            }))?;
+            Ok(Box::new(source))
+        };
+
+        let started = Instant::now();
+        assert!(manager.sync(&paints, &mut open).is_empty());
+        assert!(manager.contains(camera));
+        paints.remove_paint(camera);
+        manager.sync(&paints, &mut open);
+        assert!(manager.is_empty());
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_sync_with_paints() {
+        let mut paints = PaintManager::new();
+        let camera = paints.add_paint(Paint::stream(0, "Cam", "rtsp://cam-a/live"));
+        let clip = paints.add_paint(Paint::video(0, "Clip", "clip.mp4"));
+        let broken = paints.add_paint(Paint::stream(0, "Broken", "rtsp://cam-b/live"));
+
+        let mut manager = LiveInputManager::new();
+        let opened = std::cell::RefCell::new(Vec::new());
+        let mut open = |paint: &Paint| -> Result<Box<dyn VideoSource>> {
+            opened.borrow_mut().push(paint.id);
+            if paint.id == broken {
+                return Err(IoError::StreamConnectionFailed("refused".to_string()));
+            }
+            Ok(scripted(true).0)
+        };
+
+        let errors = manager.sync(&paints, &mut open);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, broken);
+        assert!(manager.contains(camera));
+        assert!(!manager.contains(clip));
+        assert_eq!(manager.len(), 1);
+
+        // Running inputs are kept, the failed one waits for the retry interval
+        assert!(manager.sync(&paints, &mut open).is_empty());
+        assert_eq!(opened.borrow().len(), 2);
+
+        // Changing the URL reopens, removing the paint stops the input
+        paints.get_paint_mut(camera).unwrap().source_path = Some("rtsp://cam-c/live".into());
+        manager.sync(&paints, &mut open);
+        assert_eq!(opened.borrow().len(), 3);
+        assert!(manager.contains(camera));
+
+        paints.remove_paint(camera);
+        manager.sync(&paints, &mut open);
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn test_sync_invalid_source_is_not_retried() {
+        let mut paints = PaintManager::new();
+        let camera = paints.add_paint(Paint::stream(0, "Cam", "/media/cam.mp4"));
+
+        let mut manager = LiveInputManager::new().with_reconnect_interval(Duration::ZERO);
+        let opened = std::cell::Cell::new(0);
+        let mut open = |paint: &Paint| -> Result<Box<dyn VideoSource>> {
+            opened.set(opened.get() + 1);
+            crate::network::SourceUrl::parse_live(paint.source_path.as_deref().unwrap())?;
+            Ok(scripted(true).0)
+        };
+
+        let errors = manager.sync(&paints, &mut open);
+        assert!(matches!(errors[..], [(id, IoError::InvalidParameter(_))] if id == camera));
+        assert!(manager.sync(&paints, &mut open).is_empty());
+        assert_eq!(opened.get(), 1);
+
+        paints.get_paint_mut(camera).unwrap().source_path = Some("rtsp://cam/live".into());
+        assert!(manager.sync(&paints, &mut open).is_empty());
+        assert!(manager.contains(camera));
+    }
+}
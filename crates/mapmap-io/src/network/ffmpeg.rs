@@ -0,0 +1,207 @@
+//! FFmpeg-backed frame reader for network, file and capture device inputs.
+
+use super::url::SourceUrl;
+use super::FrameReader;
+use crate::error::{IoError, Result};
+use crate::format::{FrameMetadata, PixelFormat, VideoFormat, VideoFrame};
+use ffmpeg_next as ffmpeg;
+use std::time::Duration;
+
+/// Demuxes and decodes the best video stream of an input to RGBA frames.
+///
+/// Frame timestamps are relative to the first decoded frame. The scaler is
+/// recreated whenever the decoded size or pixel format changes, which
+/// happens when an IP camera switches profiles mid-stream.
+pub struct FfmpegReader {
+    input: ffmpeg::format::context::Input,
+    decoder: ffmpeg::codec::decoder::Video,
+    scaler: Option<(
+        ffmpeg::format::Pixel,
+        u32,
+        u32,
+        ffmpeg::software::scaling::Context,
+    )>,
+    stream_index: usize,
+    time_base: ffmpeg::Rational,
+    frame_rate: f32,
+    first_pts: Option<i64>,
+    name: String,
+    frame_count: u64,
+    eof: bool,
+}
+
+/// Opens a capture device with the input format of its platform.
+fn open_device(
+    url: &SourceUrl,
+    format: &str,
+    options: ffmpeg::Dictionary,
+) -> Result<ffmpeg::format::context::Input> {
+    ffmpeg::device::register_all();
+    let name = std::ffi::CString::new(format)
+        .map_err(|e| IoError::StreamError(format!("{}: {}", url, e)))?;
+    // SAFETY: the name is NUL-terminated and the returned format is static
+    let input_format = unsafe { ffmpeg::ffi::av_find_input_format(name.as_ptr()) };
+    if input_format.is_null() {
+        return Err(IoError::StreamConnectionFailed(format!(
+            "{}: FFmpeg was built without the {} input",
+            url, format
+        )));
+    }
+    // SAFETY: checked for null above
+    let input_format = unsafe { ffmpeg::format::Input::wrap(input_format as *mut _) };
+    ffmpeg::format::open_with(&url.as_str(), &ffmpeg::Format::Input(input_format), options)
+        .map(|context| context.input())
+        .map_err(|e| IoError::StreamConnectionFailed(format!("{}: {}", url, e)))
+}
+
+impl FfmpegReader {
+    /// Opens `url` with its demuxer options and `timeout` for blocking
+    /// reads.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::StreamConnectionFailed` if the input cannot be
+    /// opened and `IoError::StreamError` if it has no decodable video
+    /// stream.
+    pub fn open(url: &SourceUrl, timeout: Duration) -> Result<Self> {
+        ffmpeg::init().map_err(|e| IoError::StreamError(e.to_string()))?;
+
+        let mut options = ffmpeg::Dictionary::new();
+        for (key, value) in url.demuxer_options(timeout) {
+            options.set(key, &value);
+        }
+
+        let input = match url.device_format() {
+            Some(format) => open_device(url, format, options)?,
+            None => ffmpeg::format::input_with_dictionary(url.as_str(), options)
+                .map_err(|e| IoError::StreamConnectionFailed(format!("{}: {}", url, e)))?,
+        };
+
+        let stream = input
+            .streams()
+            .best(ffmpeg::media::Type::Video)
+            .ok_or_else(|| IoError::StreamError(format!("No video stream in {}", url)))?;
+        let stream_index = stream.index();
+        let time_base = stream.time_base();
+        let rate = stream.avg_frame_rate();
+        let frame_rate = if rate.denominator() > 0 && rate.numerator() > 0 {
+            rate.numerator() as f32 / rate.denominator() as f32
+        } else {
+            // Live inputs often don't advertise a rate
+            30.0
+        };
+
+        let mut context = ffmpeg::codec::Context::from_parameters(stream.parameters())
+            .map_err(|e| IoError::StreamError(e.to_string()))?;
+        if url.protocol().is_live() {
+            context.set_flags(ffmpeg::codec::Flags::LOW_DELAY);
+        }
+        let decoder = context
+            .decoder()
+            .video()
+            .map_err(|e| IoError::StreamError(e.to_string()))?;
+
+        tracing::info!(
+            "Opened {} input {}: {}x{} @ {:.2} fps",
+            url.protocol(),
+            url,
+            decoder.width(),
+            decoder.height(),
+            frame_rate
+        );
+
+        Ok(Self {
+            input,
+            decoder,
+            scaler: None,
+            stream_index,
+            time_base,
+            frame_rate,
+            first_pts: None,
+            name: url.masked(),
+            frame_count: 0,
+            eof: false,
+        })
+    }
+
+    /// Converts a decoded frame to RGBA.
+    fn convert(&mut self, decoded: &ffmpeg::util::frame::Video) -> Result<VideoFrame> {
+        let (format, width, height) = (decoded.format(), decoded.width(), decoded.height());
+        let stale = self
+            .scaler
+            .as_ref()
+            .map_or(true, |(f, w, h, _)| (*f, *w, *h) != (format, width, height));
+        if stale {
+            let context = ffmpeg::software::scaling::Context::get(
+                format,
+                width,
+                height,
+                ffmpeg::format::Pixel::RGBA,
+                width,
+                height,
+                ffmpeg::software::scaling::Flags::BILINEAR,
+            )
+            .map_err(|e| IoError::ConversionError(e.to_string()))?;
+            self.scaler = Some((format, width, height, context));
+        }
+        let Some((_, _, _, scaler)) = self.scaler.as_mut() else {
+            return Err(IoError::ConversionError("Scaler unavailable".to_string()));
+        };
+
+        let mut rgba = ffmpeg::util::frame::Video::empty();
+        scaler
+            .run(decoded, &mut rgba)
+            .map_err(|e| IoError::ConversionError(e.to_string()))?;
+
+        // Copy row by row, the scaler pads lines to its alignment
+        let row = width as usize * 4;
+        let stride = rgba.stride(0);
+        let mut data = Vec::with_capacity(row * height as usize);
+        for line in rgba.data(0).chunks(stride).take(height as usize) {
+            data.extend_from_slice(&line[..row]);
+        }
+
+        let pts = decoded.timestamp().unwrap_or(0);
+        let first = *self.first_pts.get_or_insert(pts);
+        let seconds = (pts - first).max(0) as f64 * f64::from(self.time_base);
+
+        self.frame_count += 1;
+        let format = VideoFormat::new(width, height, PixelFormat::RGBA8, self.frame_rate);
+        Ok(VideoFrame::with_metadata(
+            data,
+            format,
+            Duration::from_secs_f64(seconds),
+            FrameMetadata::with_source(self.name.clone()).with_frame_number(self.frame_count),
+        ))
+    }
+}
+
+impl FrameReader for FfmpegReader {
+    fn read_frame(&mut self) -> Result<Option<VideoFrame>> {
+        let mut decoded = ffmpeg::util::frame::Video::empty();
+        loop {
+            if self.decoder.receive_frame(&mut decoded).is_ok() {
+                return self.convert(&decoded).map(Some);
+            }
+            if self.eof {
+                return Ok(None);
+            }
+
+            let mut packet = ffmpeg::Packet::empty();
+            match packet.read(&mut self.input) {
+                Ok(()) if packet.stream() == self.stream_index => {
+                    // Corrupt packets are common on lossy links, skip them
+                    if let Err(e) = self.decoder.send_packet(&packet) {
+                        tracing::debug!("Dropping undecodable packet: {}", e);
+                    }
+                }
+                Ok(()) => {}
+                Err(ffmpeg::Error::Eof) => {
+                    self.eof = true;
+                    let _ = self.decoder.send_eof();
+                }
+                Err(e) => return Err(IoError::StreamError(e.to_string())),
+            }
+        }
+    }
+}
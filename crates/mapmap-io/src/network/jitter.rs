@@ -0,0 +1,380 @@
+//! Jitter buffer that smooths out irregular frame arrival.
+
+use crate::format::VideoFrame;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Timestamp jumps larger than this restart the playout clock.
+const DISCONTINUITY: Duration = Duration::from_secs(2);
+
+/// A buffered frame and when it arrived.
+#[derive(Debug)]
+struct Buffered {
+    frame: VideoFrame,
+    arrival: Instant,
+}
+
+/// Holds decoded frames for a fixed delay and releases them at the pace of
+/// their timestamps.
+///
+/// Each frame is scheduled at `anchor + (timestamp - anchor timestamp) +
+/// delay`, where the anchor is the earliest arrival seen relative to the
+/// stream clock. Network jitter up to the delay is absorbed. A frame that
+/// arrives later than that moves the anchor, trading latency for smooth
+/// playback. Timestamp discontinuities, such as a camera restart, reset the
+/// anchor.
+///
+/// Interarrival jitter is estimated as in RFC 3550.
+#[derive(Debug)]
+pub struct JitterBuffer {
+    delay: Duration,
+    capacity: usize,
+    frames: VecDeque<Buffered>,
+    anchor: Option<(Instant, Duration)>,
+    last_pushed: Option<Duration>,
+    last_released: Option<Duration>,
+    last_transit: Option<f64>,
+    jitter: f64,
+    latency: Option<Duration>,
+    received: u64,
+    released: u64,
+    dropped: u64,
+    late: u64,
+    resyncs: u64,
+}
+
+impl JitterBuffer {
+    /// Creates a buffer that delays frames by `delay` and holds at most
+    /// `capacity` frames.
+    pub fn new(delay: Duration, capacity: usize) -> Self {
+        Self {
+            delay,
+            capacity: capacity.max(1),
+            frames: VecDeque::new(),
+            anchor: None,
+            last_pushed: None,
+            last_released: None,
+            last_transit: None,
+            jitter: 0.0,
+            latency: None,
+            received: 0,
+            released: 0,
+            dropped: 0,
+            late: 0,
+            resyncs: 0,
+        }
+    }
+
+    /// Returns the playout delay.
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Sets the playout delay.
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    /// Adds a frame that arrived at `arrival`.
+    ///
+    /// Frames older than the last released frame are dropped, as are the
+    /// oldest frames once the buffer is full.
+    pub fn push(&mut self, frame: VideoFrame, arrival: Instant) {
+        self.received += 1;
+        let timestamp = frame.timestamp;
+
+        let discontinuity = self.last_pushed.is_some_and(|last| {
+            timestamp + DISCONTINUITY < last || timestamp > last + DISCONTINUITY
+        });
+        if discontinuity {
+            tracing::debug!(
+                "Timestamp discontinuity ({:?} after {:?}), resynchronizing",
+                timestamp,
+                self.last_pushed
+            );
+            self.resyncs += 1;
+            self.dropped += self.frames.len() as u64;
+            self.frames.clear();
+            self.anchor = None;
+            self.last_released = None;
+            self.last_transit = None;
+        }
+        self.last_pushed = Some(timestamp);
+
+        if self.last_released.is_some_and(|last| timestamp <= last) {
+            self.late += 1;
+            self.dropped += 1;
+            return;
+        }
+
+        let (anchor_time, anchor_ts) = *self.anchor.get_or_insert((arrival, timestamp));
+        // Positive when the frame arrived later than the anchor predicts
+        let transit = arrival.saturating_duration_since(anchor_time).as_secs_f64()
+            - anchor_time.saturating_duration_since(arrival).as_secs_f64()
+            - signed_secs(timestamp, anchor_ts);
+
+        if let Some(last) = self.last_transit {
+            self.jitter += ((transit - last).abs() - self.jitter) / 16.0;
+        }
+        self.last_transit = Some(transit);
+
+        if transit < 0.0 {
+            // Earlier than any frame so far: the network was faster
+            self.anchor = Some((arrival, timestamp));
+            self.last_transit = Some(0.0);
+        } else if transit > self.delay.as_secs_f64() {
+            // Too late for its slot: play it now and shift the schedule
+            self.late += 1;
+            self.anchor = Some((
+                arrival.checked_sub(self.delay).unwrap_or(arrival),
+                timestamp,
+            ));
+            self.last_transit = Some(0.0);
+        }
+
+        let index = self
+            .frames
+            .iter()
+            .rposition(|b| b.frame.timestamp < timestamp)
+            .map_or(0, |i| i + 1);
+        if self
+            .frames
+            .get(index)
+            .is_some_and(|b| b.frame.timestamp == timestamp)
+        {
+            self.dropped += 1;
+            return;
+        }
+        self.frames.insert(index, Buffered { frame, arrival });
+
+        while self.frames.len() > self.capacity {
+            self.frames.pop_front();
+            self.dropped += 1;
+        }
+    }
+
+    /// Returns the time at which a frame with `timestamp` is due.
+    pub fn playout_time(&self, timestamp: Duration) -> Option<Instant> {
+        let (anchor_time, anchor_ts) = self.anchor?;
+        let offset = timestamp.checked_sub(anchor_ts);
+        let scheduled = match offset {
+            Some(offset) => anchor_time + offset,
+            None => anchor_time
+                .checked_sub(anchor_ts - timestamp)
+                .unwrap_or(anchor_time),
+        };
+        Some(scheduled + self.delay)
+    }
+
+    /// Returns the newest frame that is due at `now`.
+    ///
+    /// Older due frames are skipped, so a consumer that polls slower than
+    /// the stream frame rate always shows the most recent frame.
+    pub fn pop(&mut self, now: Instant) -> Option<VideoFrame> {
+        let due = self
+            .frames
+            .iter()
+            .take_while(|b| {
+                self.playout_time(b.frame.timestamp)
+                    .is_some_and(|t| t <= now)
+            })
+            .count();
+        if due == 0 {
+            return None;
+        }
+
+        self.dropped += (due - 1) as u64;
+        let buffered = self.frames.drain(..due).next_back()?;
+        self.released += 1;
+        self.last_released = Some(buffered.frame.timestamp);
+
+        let latency = now.saturating_duration_since(buffered.arrival);
+        self.latency = Some(match self.latency {
+            Some(average) => (average * 7 + latency) / 8,
+            None => latency,
+        });
+        Some(buffered.frame)
+    }
+
+    /// Returns the number of buffered frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns true if no frames are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Returns the media time spanned by the buffered frames.
+    pub fn depth(&self) -> Duration {
+        match (self.frames.front(), self.frames.back()) {
+            (Some(first), Some(last)) => last.frame.timestamp.saturating_sub(first.frame.timestamp),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Returns the estimated interarrival jitter.
+    pub fn jitter(&self) -> Duration {
+        Duration::from_secs_f64(self.jitter)
+    }
+
+    /// Returns the average time frames spend between arrival and release.
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// Returns the number of frames pushed.
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Returns the number of frames released by [`JitterBuffer::pop`].
+    pub fn released(&self) -> u64 {
+        self.released
+    }
+
+    /// Returns the number of frames dropped as late, duplicate, skipped or
+    /// overflowing.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Returns the number of frames that missed their playout slot.
+    pub fn late(&self) -> u64 {
+        self.late
+    }
+
+    /// Returns the number of timestamp discontinuities.
+    pub fn resyncs(&self) -> u64 {
+        self.resyncs
+    }
+
+    /// Drops all frames and restarts the playout clock, keeping statistics.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.anchor = None;
+        self.last_pushed = None;
+        self.last_released = None;
+        self.last_transit = None;
+    }
+}
+
+/// Returns `a - b` in seconds.
+fn signed_secs(a: Duration, b: Duration) -> f64 {
+    a.as_secs_f64() - b.as_secs_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{PixelFormat, VideoFormat};
+
+    fn frame(ms: u64) -> VideoFrame {
+        let format = VideoFormat::new(2, 2, PixelFormat::RGBA8, 25.0);
+        let mut frame = VideoFrame::empty(format);
+        frame.timestamp = Duration::from_millis(ms);
+        frame
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_frames_released_after_delay() {
+        let start = Instant::now();
+        let mut buffer = JitterBuffer::new(ms(100), 16);
+
+        buffer.push(frame(0), start);
+        buffer.push(frame(40), start + ms(40));
+        assert!(buffer.pop(start + ms(99)).is_none());
+
+        let first = buffer.pop(start + ms(100)).unwrap();
+        assert_eq!(first.timestamp, ms(0));
+        assert!(buffer.pop(start + ms(120)).is_none());
+        assert_eq!(buffer.pop(start + ms(140)).unwrap().timestamp, ms(40));
+        assert_eq!(buffer.released(), 2);
+        assert_eq!(buffer.latency(), Some(ms(100)));
+    }
+
+    #[test]
+    fn test_jitter_absorbed_and_reordered() {
+        let start = Instant::now();
+        let mut buffer = JitterBuffer::new(ms(100), 16);
+
+        // 40 ms frames arriving in bursts and out of order
+        buffer.push(frame(0), start);
+        buffer.push(frame(80), start + ms(90));
+        buffer.push(frame(40), start + ms(95));
+        buffer.push(frame(120), start + ms(125));
+        assert_eq!(buffer.late(), 0);
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.depth(), ms(120));
+        assert!(buffer.jitter() > Duration::ZERO);
+
+        let released: Vec<_> = [100, 140, 180, 220]
+            .iter()
+            .filter_map(|t| buffer.pop(start + ms(*t)))
+            .map(|f| f.timestamp)
+            .collect();
+        assert_eq!(released, [ms(0), ms(40), ms(80), ms(120)]);
+        assert_eq!(buffer.dropped(), 0);
+    }
+
+    #[test]
+    fn test_slow_consumer_gets_newest_frame() {
+        let start = Instant::now();
+        let mut buffer = JitterBuffer::new(ms(50), 16);
+        for i in 0..5 {
+            buffer.push(frame(i * 20), start + ms(i * 20));
+        }
+
+        let frame = buffer.pop(start + ms(150)).unwrap();
+        assert_eq!(frame.timestamp, ms(80));
+        assert_eq!(buffer.dropped(), 4);
+        assert!(buffer.is_empty());
+
+        // Stale frames are refused once a newer one was shown
+        buffer.push(self::frame(60), start + ms(151));
+        assert_eq!(buffer.late(), 1);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_late_frame_shifts_schedule() {
+        let start = Instant::now();
+        let mut buffer = JitterBuffer::new(ms(50), 16);
+        buffer.push(frame(0), start);
+        assert!(buffer.pop(start + ms(50)).is_some());
+
+        // 200 ms late: played immediately instead of being held back
+        buffer.push(frame(40), start + ms(240));
+        assert_eq!(buffer.late(), 1);
+        assert_eq!(buffer.pop(start + ms(240)).unwrap().timestamp, ms(40));
+        // Following frames keep the new pace
+        buffer.push(frame(80), start + ms(280));
+        assert!(buffer.pop(start + ms(279)).is_none());
+        assert!(buffer.pop(start + ms(280)).is_some());
+    }
+
+    #[test]
+    fn test_capacity_and_discontinuity() {
+        let start = Instant::now();
+        let mut buffer = JitterBuffer::new(ms(500), 3);
+        for i in 0..5 {
+            buffer.push(frame(i * 10), start);
+        }
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.dropped(), 2);
+
+        // Camera restarted its clock
+        buffer.push(frame(90_000), start + ms(10));
+        assert_eq!(buffer.resyncs(), 1);
+        assert_eq!(buffer.len(), 1);
+        assert_eq!(
+            buffer.playout_time(ms(90_000)),
+            Some(start + ms(10) + ms(500))
+        );
+    }
+}
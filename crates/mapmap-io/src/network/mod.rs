@@ -0,0 +1,703 @@
+//! Network and file stream inputs.
+//!
+//! [`NetworkSource`] is a [`VideoSource`] for IP cameras and remote feeds:
+//! RTSP, HTTP(S) including HLS, UDP/RTP MPEG transport streams and SRT
+//! listeners, as well as local capture devices. Demuxing and decoding run on a background thread. Frames pass
+//! through a [`JitterBuffer`] so they are released at the pace of their
+//! timestamps instead of their bursty network arrival.
+//!
+//! When a feed stalls or errors, the reader thread reopens it with
+//! exponential [`Backoff`]. [`NetworkSource::statistics`] reports the
+//! connection status, jitter, buffer depth and latency for the UI.
+//!
+//! Decoding uses FFmpeg and requires the `stream` feature. Other demuxers
+//! can be plugged in through [`FrameReader`] and [`NetworkSource::start_with`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! use mapmap_io::network::NetworkSource;
+//! use mapmap_io::VideoSource;
+//! use std::time::Duration;
+//!
+//! let mut camera = NetworkSource::new("rtsp://10.0.0.20:554/stream1")
+//!     .unwrap()
+//!     .with_latency(Duration::from_millis(150));
+//! camera.start().unwrap();
+//!
+//! // Once per rendered frame
+//! if let Ok(frame) = camera.receive_frame() {
+//!     // Upload frame...
+//! }
+//! ```
+
+#[cfg(feature = "stream")]
+pub mod ffmpeg;
+pub mod jitter;
+pub mod url;
+
+#[cfg(feature = "stream")]
+pub use ffmpeg::FfmpegReader;
+pub use jitter::JitterBuffer;
+pub use url::{SourceProtocol, SourceUrl};
+
+use crate::error::{IoError, Result};
+use crate::format::{VideoFormat, VideoFrame};
+use crate::source::VideoSource;
+use crate::stream::transport::Backoff;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Demuxes and decodes frames from an opened input.
+///
+/// Readers are created and used on the source's reader thread, so they
+/// don't need to be `Send`.
+pub trait FrameReader {
+    /// Blocks until the next frame is decoded.
+    ///
+    /// Returns `Ok(None)` at the end of the input.
+    ///
+    /// # Errors
+    ///
+    /// Any error makes the source reopen the input.
+    fn read_frame(&mut self) -> Result<Option<VideoFrame>>;
+}
+
+/// Opens a [`FrameReader`] for a URL, called again for every reconnect.
+pub type ReaderFactory = Box<dyn FnMut(&SourceUrl) -> Result<Box<dyn FrameReader>> + Send>;
+
+/// Connection state of a [`NetworkSource`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceStatus {
+    /// Not started
+    Idle,
+    /// Opening the input
+    Connecting,
+    /// Opened, waiting for the first frame
+    Buffering,
+    /// Receiving frames
+    Live,
+    /// No frame for longer than the stall timeout
+    Stalled,
+    /// Waiting to reopen after a failure
+    Reconnecting,
+    /// A file input reached its end
+    Ended,
+}
+
+/// Receive statistics of a [`NetworkSource`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceStatistics {
+    /// Connection state
+    pub status: SourceStatus,
+    /// Frames decoded
+    pub frames_received: u64,
+    /// Frames returned by `receive_frame`
+    pub frames_delivered: u64,
+    /// Frames dropped as late, duplicate, skipped or overflowing
+    pub frames_dropped: u64,
+    /// Frames that missed their playout slot
+    pub late_frames: u64,
+    /// Number of times the input was reopened
+    pub reconnects: u64,
+    /// Estimated interarrival jitter in milliseconds
+    pub jitter_ms: f32,
+    /// Media time held in the jitter buffer in milliseconds
+    pub buffer_ms: f32,
+    /// Average time from decode to delivery in milliseconds
+    pub latency_ms: Option<f32>,
+    /// Time since the last decoded frame
+    pub since_last_frame: Option<Duration>,
+    /// Most recent error
+    pub last_error: Option<String>,
+}
+
+/// State shared with the reader thread.
+struct State {
+    buffer: JitterBuffer,
+    status: SourceStatus,
+    last_frame: Option<Instant>,
+    reconnects: u64,
+    last_error: Option<String>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    stop: AtomicBool,
+    reconnect: AtomicBool,
+}
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_status(&self, status: SourceStatus) {
+        self.state().status = status;
+    }
+
+    fn fail(&self, error: &IoError) {
+        let mut state = self.state();
+        state.last_error = Some(error.to_string());
+        state.status = SourceStatus::Reconnecting;
+    }
+
+    /// Sleeps for `delay` unless stopped or asked to reconnect first.
+    fn wait(&self, delay: Duration) {
+        let deadline = Instant::now() + delay;
+        while Instant::now() < deadline {
+            if self.stop.load(Ordering::Relaxed) || self.reconnect.load(Ordering::Relaxed) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+/// Live video input from a network stream or file.
+///
+/// The source is configured with the `with_*` methods and started with
+/// [`NetworkSource::start`]. [`VideoSource::receive_frame`] never blocks;
+/// it returns `IoError::NoFrameAvailable` until a frame is due, so it can
+/// be polled once per rendered frame like a camera paint.
+pub struct NetworkSource {
+    name: String,
+    url: SourceUrl,
+    latency: Duration,
+    stall_timeout: Duration,
+    backoff: Backoff,
+    format: VideoFormat,
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+    frame_count: u64,
+}
+
+impl NetworkSource {
+    /// Creates a source for `url` without opening it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL can't be parsed, see
+    /// [`SourceUrl::parse`].
+    pub fn new(url: &str) -> Result<Self> {
+        Ok(Self::from_url(SourceUrl::parse(url)?))
+    }
+
+    /// Creates a source for a parsed `url` without opening it.
+    pub fn from_url(url: SourceUrl) -> Self {
+        let latency = Duration::from_millis(200);
+        Self {
+            name: url.masked(),
+            url,
+            latency,
+            stall_timeout: Duration::from_secs(3),
+            backoff: Backoff::new(Duration::from_millis(250), Duration::from_secs(10)),
+            format: VideoFormat::hd_1080p30_rgba(),
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    buffer: JitterBuffer::new(latency, 120),
+                    status: SourceStatus::Idle,
+                    last_frame: None,
+                    reconnects: 0,
+                    last_error: None,
+                }),
+                stop: AtomicBool::new(false),
+                reconnect: AtomicBool::new(false),
+            }),
+            worker: None,
+            frame_count: 0,
+        }
+    }
+
+    /// Sets the display name.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the jitter buffer delay.
+    ///
+    /// Larger values absorb more network jitter at the cost of latency.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.set_latency(latency);
+        self
+    }
+
+    /// Sets the most frames the jitter buffer holds.
+    pub fn with_buffer_capacity(self, frames: usize) -> Self {
+        self.shared.state().buffer = JitterBuffer::new(self.latency, frames);
+        self
+    }
+
+    /// Sets how long the input may go without a frame before it is reopened.
+    pub fn with_stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = timeout;
+        self
+    }
+
+    /// Sets the delays between reconnect attempts.
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Changes the jitter buffer delay, also while running.
+    pub fn set_latency(&mut self, latency: Duration) {
+        self.latency = latency;
+        self.shared.state().buffer.set_delay(latency);
+    }
+
+    /// Returns the input URL.
+    pub fn url(&self) -> &SourceUrl {
+        &self.url
+    }
+
+    /// Returns the jitter buffer delay.
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    /// Opens the input with FFmpeg on a background thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::FeatureNotEnabled` without the `stream` feature,
+    /// or an error if the thread can't be spawned. Connection failures are
+    /// retried in the background and show up in
+    /// [`NetworkSource::statistics`].
+    pub fn start(&mut self) -> Result<()> {
+        #[cfg(feature = "stream")]
+        {
+            let timeout = self.stall_timeout;
+            self.start_with(Box::new(move |url: &SourceUrl| {
+                Ok(Box::new(FfmpegReader::open(url, timeout)?) as Box<dyn FrameReader>)
+            }))
+        }
+        #[cfg(not(feature = "stream"))]
+        {
+            Err(IoError::feature_not_enabled(
+                "Network stream input",
+                "stream",
+            ))
+        }
+    }
+
+    /// Starts the reader thread with a custom reader factory.
+    ///
+    /// Does nothing if the source is already running.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the thread can't be spawned.
+    pub fn start_with(&mut self, open: ReaderFactory) -> Result<()> {
+        if self.worker.is_some() {
+            return Ok(());
+        }
+
+        self.shared.stop.store(false, Ordering::Relaxed);
+        self.shared.set_status(SourceStatus::Connecting);
+        let worker = Worker {
+            url: self.url.clone(),
+            open,
+            shared: self.shared.clone(),
+            stall_timeout: self.stall_timeout,
+            backoff: self.backoff.clone(),
+        };
+        let handle = thread::Builder::new()
+            .name(format!(
+                "{}-input",
+                self.url.protocol().name().to_lowercase()
+            ))
+            .spawn(move || worker.run())?;
+
+        tracing::info!("Started {} input {}", self.url.protocol(), self.url);
+        self.worker = Some(handle);
+        Ok(())
+    }
+
+    /// Stops the reader thread and clears buffered frames.
+    ///
+    /// A reader blocked in a read returns once its read timeout expires.
+    pub fn stop(&mut self) {
+        let Some(handle) = self.worker.take() else {
+            return;
+        };
+        self.shared.stop.store(true, Ordering::Relaxed);
+        if handle.join().is_err() {
+            tracing::error!("Input thread for {} panicked", self.url);
+        }
+        let mut state = self.shared.state();
+        state.buffer.clear();
+        state.status = SourceStatus::Idle;
+    }
+
+    /// Returns true if the reader thread is running.
+    pub fn is_running(&self) -> bool {
+        self.worker.is_some()
+    }
+
+    /// Returns the connection state.
+    pub fn status(&self) -> SourceStatus {
+        let state = self.shared.state();
+        let stalled = state.status == SourceStatus::Live
+            && state
+                .last_frame
+                .is_some_and(|t| t.elapsed() > self.stall_timeout);
+        if stalled {
+            SourceStatus::Stalled
+        } else {
+            state.status
+        }
+    }
+
+    /// Returns receive statistics.
+    pub fn statistics(&self) -> SourceStatistics {
+        let status = self.status();
+        let state = self.shared.state();
+        let buffer = &state.buffer;
+        SourceStatistics {
+            status,
+            frames_received: buffer.received(),
+            frames_delivered: buffer.released(),
+            frames_dropped: buffer.dropped(),
+            late_frames: buffer.late(),
+            reconnects: state.reconnects,
+            jitter_ms: buffer.jitter().as_secs_f32() * 1000.0,
+            buffer_ms: buffer.depth().as_secs_f32() * 1000.0,
+            latency_ms: buffer.latency().map(|l| l.as_secs_f32() * 1000.0),
+            since_last_frame: state.last_frame.map(|t| t.elapsed()),
+            last_error: state.last_error.clone(),
+        }
+    }
+}
+
+impl VideoSource for NetworkSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn format(&self) -> VideoFormat {
+        self.format.clone()
+    }
+
+    fn receive_frame(&mut self) -> Result<VideoFrame> {
+        let mut state = self.shared.state();
+        if let Some(frame) = state.buffer.pop(Instant::now()) {
+            drop(state);
+            self.format = frame.format.clone();
+            self.frame_count += 1;
+            return Ok(frame);
+        }
+        if state.status == SourceStatus::Ended && state.buffer.is_empty() {
+            return Err(IoError::StreamDisconnected);
+        }
+        Err(IoError::NoFrameAvailable)
+    }
+
+    fn is_available(&self) -> bool {
+        self.status() == SourceStatus::Live
+    }
+
+    fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    fn reconnect(&mut self) -> Result<()> {
+        if self.worker.is_none() {
+            return self.start();
+        }
+        self.shared.reconnect.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl Drop for NetworkSource {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// The reader thread.
+struct Worker {
+    url: SourceUrl,
+    open: ReaderFactory,
+    shared: Arc<Shared>,
+    stall_timeout: Duration,
+    backoff: Backoff,
+}
+
+impl Worker {
+    fn run(mut self) {
+        while !self.shared.stop.load(Ordering::Relaxed) {
+            self.shared.reconnect.store(false, Ordering::Relaxed);
+            self.shared.set_status(SourceStatus::Connecting);
+
+            match (self.open)(&self.url) {
+                Ok(reader) => {
+                    if self.read(reader) {
+                        self.shared.set_status(SourceStatus::Ended);
+                        tracing::info!("Input {} ended", self.url);
+                        return;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to open {}: {}", self.url, e);
+                    self.shared.fail(&e);
+                }
+            }
+            if self.shared.stop.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let delay = self.backoff.next_delay();
+            {
+                let mut state = self.shared.state();
+                state.reconnects += 1;
+                state.status = SourceStatus::Reconnecting;
+            }
+            tracing::info!("Reopening {} in {:?}", self.url, delay);
+            self.shared.wait(delay);
+        }
+    }
+
+    /// Reads frames until the input fails, stalls or ends.
+    ///
+    /// Returns true if a file input reached its end.
+    fn read(&mut self, mut reader: Box<dyn FrameReader>) -> bool {
+        self.shared.set_status(SourceStatus::Buffering);
+        let mut last_frame = Instant::now();
+
+        loop {
+            if self.shared.stop.load(Ordering::Relaxed)
+                || self.shared.reconnect.swap(false, Ordering::Relaxed)
+            {
+                return false;
+            }
+
+            match reader.read_frame() {
+                Ok(Some(frame)) => {
+                    let now = Instant::now();
+                    // After a long stall the demuxer is usually far behind the
+                    // live edge, reopening catches up faster than draining it
+                    if now.duration_since(last_frame) > self.stall_timeout {
+                        tracing::warn!(
+                            "Input {} stalled for {:?}",
+                            self.url,
+                            now.duration_since(last_frame)
+                        );
+                        self.shared.fail(&IoError::FrameTimeout);
+                        return false;
+                    }
+                    last_frame = now;
+                    self.backoff.reset();
+
+                    let mut state = self.shared.state();
+                    state.buffer.push(frame, now);
+                    state.last_frame = Some(now);
+                    state.status = SourceStatus::Live;
+                }
+                Ok(None) if !self.url.protocol().is_live() => return true,
+                Ok(None) => {
+                    tracing::warn!("Live input {} ended", self.url);
+                    self.shared.fail(&IoError::StreamDisconnected);
+                    return false;
+                }
+                Err(e) => {
+                    tracing::warn!("Input {} failed: {}", self.url, e);
+                    self.shared.fail(&e);
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::PixelFormat;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Produces `frames` frames 10 ms apart, then fails.
+    struct FakeReader {
+        next: u64,
+        frames: u64,
+        gap: Duration,
+    }
+
+    impl FrameReader for FakeReader {
+        fn read_frame(&mut self) -> Result<Option<VideoFrame>> {
+            if self.next == self.frames {
+                return Err(IoError::StreamError("connection reset".to_string()));
+            }
+            thread::sleep(self.gap);
+            let format = VideoFormat::new(4, 2, PixelFormat::RGBA8, 100.0);
+            let mut frame = VideoFrame::empty(format);
+            frame.timestamp = Duration::from_millis(self.next * 10);
+            self.next += 1;
+            Ok(Some(frame))
+        }
+    }
+
+    fn wait_for(mut condition: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if condition() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    #[test]
+    fn test_receive_and_reconnect() {
+        let opened = Arc::new(AtomicUsize::new(0));
+        let counter = opened.clone();
+        let mut source = NetworkSource::new("udp://0.0.0.0:5000")
+            .unwrap()
+            .with_latency(Duration::from_millis(20))
+            .with_backoff(Backoff::new(
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+            ));
+        assert_eq!(source.status(), SourceStatus::Idle);
+        assert!(matches!(
+            source.receive_frame(),
+            Err(IoError::NoFrameAvailable)
+        ));
+
+        source
+            .start_with(Box::new(move |_url: &SourceUrl| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(FakeReader {
+                    next: 0,
+                    frames: 5,
+                    gap: Duration::from_millis(10),
+                }) as Box<dyn FrameReader>)
+            }))
+            .unwrap();
+        assert!(source.is_running());
+
+        let mut received = 0;
+        assert!(wait_for(|| {
+            if source.receive_frame().is_ok() {
+                received += 1;
+            }
+            received >= 3
+        }));
+        assert_eq!(source.format().width, 4);
+        assert_eq!(source.frame_count(), received);
+
+        // Every reader fails after five frames and is reopened
+        assert!(wait_for(|| opened.load(Ordering::SeqCst) >= 3));
+        let stats = source.statistics();
+        assert!(stats.reconnects >= 2);
+        assert!(stats.frames_received >= 10);
+        assert!(stats.latency_ms.is_some());
+        assert_eq!(
+            stats.last_error.as_deref(),
+            Some("Stream error: connection reset")
+        );
+
+        source.stop();
+        assert_eq!(source.status(), SourceStatus::Idle);
+        assert!(!source.is_running());
+    }
+
+    #[test]
+    fn test_stall_reopens_input() {
+        let opened = Arc::new(AtomicUsize::new(0));
+        let counter = opened.clone();
+        let mut source = NetworkSource::new("rtsp://cam.local/live")
+            .unwrap()
+            .with_stall_timeout(Duration::from_millis(50))
+            .with_backoff(Backoff::new(Duration::ZERO, Duration::ZERO));
+
+        source
+            .start_with(Box::new(move |_url: &SourceUrl| {
+                // The first connection delivers frames 100 ms apart
+                let gap = if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Duration::from_millis(100)
+                } else {
+                    Duration::from_millis(5)
+                };
+                Ok(Box::new(FakeReader {
+                    next: 0,
+                    frames: u64::MAX,
+                    gap,
+                }) as Box<dyn FrameReader>)
+            }))
+            .unwrap();
+
+        assert!(wait_for(|| opened.load(Ordering::SeqCst) >= 2));
+        assert!(wait_for(|| source.status() == SourceStatus::Live));
+        assert!(source.is_available());
+        assert_eq!(
+            source.statistics().last_error.as_deref(),
+            Some("Timeout waiting for frame")
+        );
+    }
+
+    #[test]
+    fn test_file_input_ends() {
+        struct Finite(u64);
+        impl FrameReader for Finite {
+            fn read_frame(&mut self) -> Result<Option<VideoFrame>> {
+                if self.0 == 0 {
+                    return Ok(None);
+                }
+                self.0 -= 1;
+                Ok(Some(VideoFrame::empty(VideoFormat::new(
+                    2,
+                    2,
+                    PixelFormat::RGBA8,
+                    30.0,
+                ))))
+            }
+        }
+
+        let mut source = NetworkSource::new("/media/loop.mp4")
+            .unwrap()
+            .with_latency(Duration::ZERO);
+        source
+            .start_with(Box::new(|_url: &SourceUrl| {
+                Ok(Box::new(Finite(1)) as Box<dyn FrameReader>)
+            }))
+            .unwrap();
+
+        assert!(wait_for(|| source.status() == SourceStatus::Ended));
+        assert!(source.receive_frame().is_ok());
+        assert!(matches!(
+            source.receive_frame(),
+            Err(IoError::StreamDisconnected)
+        ));
+        assert_eq!(source.statistics().reconnects, 0);
+    }
+
+    #[test]
+    fn test_open_failures_back_off() {
+        let mut source = NetworkSource::new("srt://0.0.0.0:9000")
+            .unwrap()
+            .with_backoff(Backoff::new(
+                Duration::from_millis(5),
+                Duration::from_millis(20),
+            ));
+        source
+            .start_with(Box::new(|url: &SourceUrl| {
+                Err(IoError::StreamConnectionFailed(url.to_string()))
+            }))
+            .unwrap();
+
+        assert!(wait_for(|| source.statistics().reconnects >= 3));
+        let stats = source.statistics();
+        assert!(matches!(
+            stats.status,
+            SourceStatus::Reconnecting | SourceStatus::Connecting
+        ));
+        assert_eq!(stats.frames_received, 0);
+        assert!(!source.is_available());
+    }
+}
@@ -0,0 +1,494 @@
+//! Genlock-style output pacing.
+//!
+//! An [`OutputPacer`] releases frames on the frame boundaries of a reference
+//! clock instead of whenever rendering finishes. Each released frame is
+//! stamped with its frame number and SMPTE [`Timecode`], and the pacer keeps
+//! statistics on how far output drifted from the reference.
+//!
+//! Two reference clocks are provided:
+//!
+//! - [`SystemClock`] - the local monotonic clock, optionally at time of day
+//! - [`ExternalClock`] - positions fed from an MTC or LTC decoder
+//!
+//! If the reference loses its signal the pacer freewheels from the last
+//! known position until it comes back.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+use crate::format::VideoFrame;
+use crate::sink::VideoSink;
+use crate::timecode::{Timecode, TimecodeRate};
+
+/// A clock that output is paced against.
+pub trait ReferenceClock: Send {
+    /// Returns a human-readable name for this clock.
+    fn name(&self) -> &str;
+
+    /// Returns the current reference position, or `None` while the clock has
+    /// no signal.
+    fn now(&self) -> Option<Duration>;
+}
+
+/// Reference clock driven by the local monotonic clock.
+pub struct SystemClock {
+    start: Instant,
+    offset: Duration,
+}
+
+impl SystemClock {
+    /// Creates a clock that starts at zero.
+    pub fn new() -> Self {
+        Self::starting_at(Duration::ZERO)
+    }
+
+    /// Creates a clock that starts at `offset`.
+    pub fn starting_at(offset: Duration) -> Self {
+        Self {
+            start: Instant::now(),
+            offset,
+        }
+    }
+
+    /// Creates a clock that starts at the current local time of day, for
+    /// time-of-day timecode.
+    pub fn time_of_day() -> Self {
+        use chrono::Timelike;
+
+        let now = chrono::Local::now();
+        Self::starting_at(Duration::new(
+            now.num_seconds_from_midnight() as u64,
+            // Leap seconds are reported as nanoseconds above one second
+            now.nanosecond() % 1_000_000_000,
+        ))
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReferenceClock for SystemClock {
+    fn name(&self) -> &str {
+        "System"
+    }
+
+    fn now(&self) -> Option<Duration> {
+        Some(self.offset + self.start.elapsed())
+    }
+}
+
+/// Reference clock chasing an external timecode source such as MTC or LTC.
+///
+/// The clock is a cheap handle; clone it to feed positions from the thread
+/// that decodes the timecode while the pacer reads it on the output thread.
+#[derive(Clone)]
+pub struct ExternalClock {
+    anchor: Arc<Mutex<Option<(Duration, Instant)>>>,
+    timeout: Duration,
+}
+
+impl ExternalClock {
+    /// Creates a clock without a signal.
+    pub fn new() -> Self {
+        Self {
+            anchor: Arc::new(Mutex::new(None)),
+            timeout: Duration::from_millis(500),
+        }
+    }
+
+    /// Sets how long the clock interpolates after the last received position
+    /// before reporting that the signal is lost.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Feeds a decoded timecode.
+    pub fn receive(&self, timecode: Timecode) {
+        self.receive_position(timecode.to_duration());
+    }
+
+    /// Feeds a reference position.
+    pub fn receive_position(&self, position: Duration) {
+        if let Ok(mut anchor) = self.anchor.lock() {
+            *anchor = Some((position, Instant::now()));
+        }
+    }
+
+    /// Returns true if a position was received within the timeout.
+    pub fn has_signal(&self) -> bool {
+        self.now().is_some()
+    }
+}
+
+impl Default for ExternalClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReferenceClock for ExternalClock {
+    fn name(&self) -> &str {
+        "External"
+    }
+
+    fn now(&self) -> Option<Duration> {
+        let (position, received) = (*self.anchor.lock().ok()?)?;
+        let elapsed = received.elapsed();
+        (elapsed <= self.timeout).then_some(position + elapsed)
+    }
+}
+
+/// A frame slot released by the pacer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PacedFrame {
+    /// Frame number on the reference clock
+    pub frame_number: u64,
+    /// Timecode of the frame
+    pub timecode: Timecode,
+    /// How late the frame was released, in seconds (negative if early)
+    pub drift: f64,
+    /// Frame slots skipped since the previous frame
+    pub dropped: u64,
+    /// Whether the reference clock had a signal
+    pub locked: bool,
+}
+
+/// Statistics about output pacing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PacerStatistics {
+    /// Frames released
+    pub frames: u64,
+    /// Frame slots skipped because output fell behind
+    pub frames_dropped: u64,
+    /// Frames released while freewheeling without a reference
+    pub frames_unlocked: u64,
+    /// Times the pacer resynchronized after the reference jumped
+    pub resyncs: u64,
+    /// Drift of the last frame, in seconds
+    pub last_drift: f64,
+    /// Largest absolute drift, in seconds
+    pub max_drift: f64,
+    /// Mean absolute drift, in seconds
+    pub average_drift: f64,
+}
+
+/// Releases frames on the frame boundaries of a reference clock.
+pub struct OutputPacer {
+    rate: TimecodeRate,
+    clock: Box<dyn ReferenceClock>,
+    next_frame: Option<u64>,
+    /// Last reference reading, for freewheeling without a signal
+    last_reference: Option<(Duration, Instant)>,
+    /// Reference jumps larger than this resynchronize instead of dropping
+    resync_threshold: Duration,
+    stats: PacerStatistics,
+}
+
+impl OutputPacer {
+    /// Creates a pacer for `rate` against `clock`.
+    pub fn new(rate: TimecodeRate, clock: impl ReferenceClock + 'static) -> Self {
+        Self {
+            rate,
+            clock: Box::new(clock),
+            next_frame: None,
+            last_reference: None,
+            resync_threshold: Duration::from_secs(1),
+            stats: PacerStatistics::default(),
+        }
+    }
+
+    /// Creates a pacer against the local system clock.
+    pub fn system(rate: TimecodeRate) -> Self {
+        Self::new(rate, SystemClock::new())
+    }
+
+    /// Sets the largest reference jump that is treated as dropped frames
+    /// rather than a relocate.
+    pub fn with_resync_threshold(mut self, threshold: Duration) -> Self {
+        self.resync_threshold = threshold;
+        self
+    }
+
+    /// Returns the timecode rate.
+    pub fn rate(&self) -> TimecodeRate {
+        self.rate
+    }
+
+    /// Changes the timecode rate, restarting pacing on the next frame
+    /// boundary if it differs.
+    pub fn set_rate(&mut self, rate: TimecodeRate) {
+        if rate != self.rate {
+            self.rate = rate;
+            self.next_frame = None;
+        }
+    }
+
+    /// Returns the name of the reference clock.
+    pub fn clock_name(&self) -> &str {
+        self.clock.name()
+    }
+
+    /// Returns the pacing statistics.
+    pub fn statistics(&self) -> &PacerStatistics {
+        &self.stats
+    }
+
+    /// Restarts pacing on the next frame boundary, e.g. after a relocate.
+    pub fn reset(&mut self) {
+        self.next_frame = None;
+    }
+
+    /// Reads the reference, freewheeling from the last reading without a signal.
+    fn reference(&mut self) -> (Duration, bool) {
+        let now = Instant::now();
+        match self.clock.now() {
+            Some(position) => {
+                self.last_reference = Some((position, now));
+                (position, true)
+            }
+            None => {
+                let (position, at) = *self.last_reference.get_or_insert((Duration::ZERO, now));
+                (position + now.saturating_duration_since(at), false)
+            }
+        }
+    }
+
+    /// Picks the next frame slot for a reference position; returns the slot,
+    /// the time to wait for it and the number of skipped slots.
+    fn plan(&mut self, reference: Duration) -> (u64, Duration, u64) {
+        let current = self.rate.frames_in(reference);
+        let mut dropped = 0;
+        let frame = match self.next_frame {
+            Some(next) => {
+                let due = self.rate.time_of_frame(next);
+                let jump = if due > reference {
+                    due - reference
+                } else {
+                    reference - due
+                };
+                if jump > self.resync_threshold {
+                    self.stats.resyncs += 1;
+                    current + 1
+                } else if current > next {
+                    dropped = current - next;
+                    current
+                } else {
+                    next
+                }
+            }
+            None => current + 1,
+        };
+        let wait = self.rate.time_of_frame(frame).saturating_sub(reference);
+        (frame, wait, dropped)
+    }
+
+    /// Waits for the next frame boundary of the reference clock.
+    pub fn wait_for_next_frame(&mut self) -> PacedFrame {
+        let (reference, _) = self.reference();
+        let (frame, wait, dropped) = self.plan(reference);
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+        let (reference, locked) = self.reference();
+        self.release(frame, reference, dropped, locked)
+    }
+
+    /// Releases the next frame slot if its boundary has passed, without
+    /// waiting.
+    ///
+    /// This is for render loops that must not block: call it once per
+    /// rendered frame and only send the frame on if a slot is returned.
+    pub fn poll(&mut self) -> Option<PacedFrame> {
+        let (reference, locked) = self.reference();
+        let (frame, wait, dropped) = self.plan(reference);
+        if !wait.is_zero() {
+            self.next_frame = Some(frame);
+            return None;
+        }
+        Some(self.release(frame, reference, dropped, locked))
+    }
+
+    fn release(
+        &mut self,
+        frame: u64,
+        reference: Duration,
+        dropped: u64,
+        locked: bool,
+    ) -> PacedFrame {
+        self.next_frame = Some(frame + 1);
+
+        let due = self.rate.time_of_frame(frame).as_secs_f64();
+        let drift = reference.as_secs_f64() - due;
+        let stats = &mut self.stats;
+        stats.frames += 1;
+        stats.frames_dropped += dropped;
+        if !locked {
+            stats.frames_unlocked += 1;
+        }
+        stats.last_drift = drift;
+        stats.max_drift = stats.max_drift.max(drift.abs());
+        stats.average_drift += (drift.abs() - stats.average_drift) / stats.frames as f64;
+        if dropped > 0 {
+            tracing::warn!(
+                "Output fell behind the reference clock, skipped {} frames",
+                dropped
+            );
+        }
+
+        PacedFrame {
+            frame_number: frame,
+            timecode: Timecode::from_frames(frame, self.rate),
+            drift,
+            dropped,
+            locked,
+        }
+    }
+
+    /// Stamps a frame with the number, timestamp and timecode of a slot.
+    pub fn stamp(&self, frame: &mut VideoFrame, paced: &PacedFrame) {
+        frame.timestamp = self.rate.time_of_frame(paced.frame_number);
+        frame.metadata.frame_number = paced.frame_number;
+        frame.metadata.set_timecode(paced.timecode);
+    }
+
+    /// Waits for the next frame boundary, stamps `frame` and sends it to `sink`.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error reported by the sink.
+    pub fn present<S: VideoSink + ?Sized>(
+        &mut self,
+        sink: &mut S,
+        frame: &mut VideoFrame,
+    ) -> Result<PacedFrame> {
+        let paced = self.wait_for_next_frame();
+        self.stamp(frame, &paced);
+        sink.send_frame(frame)?;
+        Ok(paced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::VideoFormat;
+    use crate::sink::TestVideoSink;
+
+    /// Clock whose position is set by the test
+    #[derive(Clone)]
+    struct ManualClock(Arc<Mutex<Option<Duration>>>);
+
+    impl ManualClock {
+        fn set(&self, position: Option<Duration>) {
+            *self.0.lock().unwrap() = position;
+        }
+    }
+
+    impl ReferenceClock for ManualClock {
+        fn name(&self) -> &str {
+            "Manual"
+        }
+
+        fn now(&self) -> Option<Duration> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_plan_frame_slots() {
+        let clock = ManualClock(Arc::new(Mutex::new(None)));
+        let mut pacer =
+            OutputPacer::new(TimecodeRate::FPS_25, clock).with_resync_threshold(ms(100));
+
+        // First frame waits for the next boundary
+        assert_eq!(pacer.plan(ms(30)), (1, ms(10), 0));
+        pacer.release(1, ms(40), 0, true);
+
+        // Early: wait for the slot
+        assert_eq!(pacer.plan(ms(50)), (2, ms(30), 0));
+        pacer.release(2, ms(81), 0, true);
+
+        // Late: slot 5 is due, slots 3 and 4 are dropped
+        assert_eq!(pacer.plan(ms(210)), (5, Duration::ZERO, 2));
+        let paced = pacer.release(5, ms(210), 2, true);
+        assert_eq!(paced.timecode.to_string(), "00:00:00:05");
+        assert!((paced.drift - 0.01).abs() < 1e-9);
+
+        // Reference relocated backwards
+        assert_eq!(pacer.plan(ms(0)), (1, ms(40), 0));
+
+        let stats = pacer.statistics();
+        assert_eq!(stats.frames, 3);
+        assert_eq!(stats.frames_dropped, 2);
+        assert_eq!(stats.resyncs, 1);
+        assert!((stats.max_drift - 0.01).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_present_stamps_frames() {
+        let clock = ManualClock(Arc::new(Mutex::new(Some(ms(3_599_999)))));
+        let format = VideoFormat::new(4, 4, crate::format::PixelFormat::RGBA8, 25.0);
+        let mut sink = TestVideoSink::new("Test", format.clone());
+        let mut pacer = OutputPacer::new(TimecodeRate::FPS_25, clock.clone());
+
+        let mut frame = VideoFrame::empty(format);
+        let paced = pacer.present(&mut sink, &mut frame).unwrap();
+        assert!(paced.locked);
+        assert_eq!(paced.frame_number, 90_000);
+        assert_eq!(frame.metadata.timecode.as_deref(), Some("01:00:00:00"));
+        assert_eq!(frame.timecode(), Some(paced.timecode));
+        assert_eq!(frame.timestamp, Duration::from_secs(3600));
+        assert_eq!(sink.frame_count(), 1);
+
+        // Freewheel without a signal
+        clock.set(None);
+        let paced = pacer.wait_for_next_frame();
+        assert!(!paced.locked);
+        assert_eq!(pacer.statistics().frames_unlocked, 1);
+    }
+
+    #[test]
+    fn test_poll_releases_due_slots() {
+        let clock = ManualClock(Arc::new(Mutex::new(Some(ms(30)))));
+        let mut pacer = OutputPacer::new(TimecodeRate::FPS_25, clock.clone());
+
+        // Not due yet, but the slot is remembered
+        assert!(pacer.poll().is_none());
+        clock.set(Some(ms(39)));
+        assert!(pacer.poll().is_none());
+
+        clock.set(Some(ms(41)));
+        assert_eq!(pacer.poll().unwrap().frame_number, 1);
+        assert!(pacer.poll().is_none());
+
+        // A slow render skips slots instead of queueing them
+        clock.set(Some(ms(200)));
+        let paced = pacer.poll().unwrap();
+        assert_eq!((paced.frame_number, paced.dropped), (5, 3));
+
+        pacer.set_rate(TimecodeRate::FPS_30);
+        assert_eq!(pacer.rate(), TimecodeRate::FPS_30);
+        assert!(pacer.poll().is_none());
+    }
+
+    #[test]
+    fn test_external_clock() {
+        let clock = ExternalClock::new().with_timeout(ms(200));
+        assert!(!clock.has_signal());
+
+        let timecode = Timecode::new(10, 0, 0, 0, TimecodeRate::FPS_25).unwrap();
+        clock.clone().receive(timecode);
+        let position = clock.now().unwrap();
+        assert!(position >= Duration::from_secs(36_000));
+        assert!(position < Duration::from_secs(36_000) + ms(200));
+    }
+}
@@ -0,0 +1,374 @@
+//! Low-bandwidth JPEG previews of outputs.
+//!
+//! A [`PreviewSink`] scales frames down, limits their rate and encodes them
+//! as JPEG for monitors that only need to see what an output shows, such as
+//! an MJPEG stream in a browser at front of house. [`encode_jpeg`] produces a
+//! single snapshot of any frame.
+
+use crate::converter::FormatConverter;
+use crate::error::{IoError, Result};
+use crate::format::{PixelFormat, VideoFormat, VideoFrame};
+use crate::sink::{SinkStatistics, VideoSink};
+use image::codecs::jpeg::JpegEncoder;
+use image::ColorType;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A JPEG encoded preview frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewImage {
+    /// JPEG data
+    pub jpeg: Arc<Vec<u8>>,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Timestamp of the source frame
+    pub timestamp: Duration,
+    /// Number of the preview frame, starting at 1
+    pub sequence: u64,
+}
+
+/// Callback receiving every encoded preview frame.
+pub type PreviewCallback = Box<dyn FnMut(&PreviewImage) + Send>;
+
+/// Encodes a frame as a JPEG with `quality` from 1 to 100.
+///
+/// Frames in other formats than RGB8 are converted first.
+///
+/// # Errors
+///
+/// Returns an error if the frame data does not match its format or the
+/// encoder fails.
+pub fn encode_jpeg(frame: &VideoFrame, quality: u8) -> Result<Vec<u8>> {
+    encode_with(&FormatConverter::new(), frame, quality)
+}
+
+fn encode_with(converter: &FormatConverter, frame: &VideoFrame, quality: u8) -> Result<Vec<u8>> {
+    let format = VideoFormat::new(
+        frame.format.width,
+        frame.format.height,
+        PixelFormat::RGB8,
+        frame.format.frame_rate,
+    );
+    encode_rgb(&converter.convert(frame, &format)?, quality)
+}
+
+/// Encodes an RGB8 frame.
+fn encode_rgb(frame: &VideoFrame, quality: u8) -> Result<Vec<u8>> {
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, quality.clamp(1, 100))
+        .encode(
+            &frame.data,
+            frame.format.width,
+            frame.format.height,
+            ColorType::Rgb8,
+        )
+        .map_err(|e| IoError::EncodeFailed(e.to_string()))?;
+    Ok(jpeg)
+}
+
+/// Returns the largest size within `max_width` x `max_height` with the
+/// aspect ratio of `width` x `height`, rounded to even dimensions.
+///
+/// Frames are never scaled up.
+pub fn preview_size(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    if width <= max_width && height <= max_height {
+        return (width, height);
+    }
+    let scale = (max_width as f64 / width as f64).min(max_height as f64 / height as f64);
+    // Even sizes keep chroma subsampled sources resizable
+    let even = |v: f64| ((v.round() as u32) & !1).max(2);
+    (even(width as f64 * scale), even(height as f64 * scale))
+}
+
+/// Serves scaled down, rate limited JPEG previews of the frames it receives.
+///
+/// Frames arriving faster than the maximum rate are skipped before any
+/// work is done, so the sink can be fed every rendered frame. The newest
+/// preview is available from [`PreviewSink::snapshot`] and is passed to the
+/// callback set with [`PreviewSink::on_frame`], for example to publish it
+/// to an MJPEG stream.
+pub struct PreviewSink {
+    name: String,
+    format: VideoFormat,
+    max_width: u32,
+    max_height: u32,
+    max_fps: f32,
+    quality: u8,
+    converter: FormatConverter,
+    callback: Option<PreviewCallback>,
+    latest: Option<PreviewImage>,
+    last_encoded: Option<Instant>,
+    started: Option<Instant>,
+    bytes_encoded: u64,
+    frame_count: u64,
+    frames_skipped: u64,
+    frames_dropped: u64,
+}
+
+impl PreviewSink {
+    /// Creates a preview sink limited to 640x360 at 15 fps and JPEG quality
+    /// 70.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            format: VideoFormat::new(640, 360, PixelFormat::RGB8, 15.0),
+            max_width: 640,
+            max_height: 360,
+            max_fps: 15.0,
+            quality: 70,
+            converter: FormatConverter::new(),
+            callback: None,
+            latest: None,
+            last_encoded: None,
+            started: None,
+            bytes_encoded: 0,
+            frame_count: 0,
+            frames_skipped: 0,
+            frames_dropped: 0,
+        }
+    }
+
+    /// Sets the maximum preview size; frames are scaled down to fit.
+    pub fn with_max_size(mut self, width: u32, height: u32) -> Self {
+        self.set_max_size(width, height);
+        self
+    }
+
+    /// Sets the maximum preview frame rate.
+    pub fn with_max_fps(mut self, fps: f32) -> Self {
+        self.set_max_fps(fps);
+        self
+    }
+
+    /// Sets the JPEG quality from 1 to 100.
+    pub fn with_quality(mut self, quality: u8) -> Self {
+        self.set_quality(quality);
+        self
+    }
+
+    /// Sets the converter used to scale frames and convert them to RGB.
+    pub fn with_converter(mut self, converter: FormatConverter) -> Self {
+        self.converter = converter;
+        self
+    }
+
+    /// Calls `callback` with every encoded preview frame.
+    pub fn on_frame(mut self, callback: impl FnMut(&PreviewImage) + Send + 'static) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Returns the maximum preview size.
+    pub fn max_size(&self) -> (u32, u32) {
+        (self.max_width, self.max_height)
+    }
+
+    /// Sets the maximum preview size.
+    pub fn set_max_size(&mut self, width: u32, height: u32) {
+        self.max_width = width.max(2);
+        self.max_height = height.max(2);
+    }
+
+    /// Returns the maximum preview frame rate.
+    pub fn max_fps(&self) -> f32 {
+        self.max_fps
+    }
+
+    /// Sets the maximum preview frame rate.
+    pub fn set_max_fps(&mut self, fps: f32) {
+        self.max_fps = fps.max(0.1);
+    }
+
+    /// Returns the JPEG quality.
+    pub fn quality(&self) -> u8 {
+        self.quality
+    }
+
+    /// Sets the JPEG quality from 1 to 100.
+    pub fn set_quality(&mut self, quality: u8) {
+        self.quality = quality.clamp(1, 100);
+    }
+
+    /// Returns the newest preview frame.
+    pub fn snapshot(&self) -> Option<PreviewImage> {
+        self.latest.clone()
+    }
+
+    /// Returns the number of frames skipped by the frame rate limit.
+    pub fn frames_skipped(&self) -> u64 {
+        self.frames_skipped
+    }
+
+    /// Returns true if a frame arriving at `now` is within the rate limit.
+    fn is_due(&self, now: Instant) -> bool {
+        // Half a millisecond of slack keeps frames at exactly the rate
+        let interval =
+            Duration::from_secs_f32(1.0 / self.max_fps).saturating_sub(Duration::from_micros(500));
+        self.last_encoded
+            .map_or(true, |last| now.saturating_duration_since(last) >= interval)
+    }
+}
+
+impl VideoSink for PreviewSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn format(&self) -> VideoFormat {
+        self.format.clone()
+    }
+
+    fn send_frame(&mut self, frame: &VideoFrame) -> Result<()> {
+        let now = Instant::now();
+        if !self.is_due(now) {
+            self.frames_skipped += 1;
+            return Ok(());
+        }
+        frame.validate()?;
+
+        let (width, height) = preview_size(
+            frame.format.width,
+            frame.format.height,
+            self.max_width,
+            self.max_height,
+        );
+        let format = VideoFormat::new(width, height, PixelFormat::RGB8, self.max_fps);
+        let encoded = self
+            .converter
+            .convert(frame, &format)
+            .and_then(|scaled| encode_rgb(&scaled, self.quality));
+        let jpeg = match encoded {
+            Ok(jpeg) => jpeg,
+            Err(e) => {
+                self.frames_dropped += 1;
+                return Err(e);
+            }
+        };
+
+        self.last_encoded = Some(now);
+        self.started.get_or_insert(now);
+        self.bytes_encoded += jpeg.len() as u64;
+        self.frame_count += 1;
+        self.format = format;
+
+        let image = PreviewImage {
+            jpeg: Arc::new(jpeg),
+            width,
+            height,
+            timestamp: frame.timestamp,
+            sequence: self.frame_count,
+        };
+        if let Some(callback) = self.callback.as_mut() {
+            callback(&image);
+        }
+        self.latest = Some(image);
+        Ok(())
+    }
+
+    fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    fn statistics(&self) -> Option<SinkStatistics> {
+        let elapsed = self
+            .started
+            .map(|started| started.elapsed().as_secs_f64())
+            .filter(|secs| *secs > 0.0);
+        Some(SinkStatistics {
+            frames_sent: self.frame_count,
+            frames_dropped: self.frames_dropped,
+            bitrate: elapsed.map(|secs| (self.bytes_encoded as f64 * 8.0 / secs) as u64),
+            average_latency_ms: None,
+        })
+    }
+}
+
+impl std::fmt::Debug for PreviewSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreviewSink")
+            .field("name", &self.name)
+            .field("max_width", &self.max_width)
+            .field("max_height", &self.max_height)
+            .field("max_fps", &self.max_fps)
+            .field("quality", &self.quality)
+            .field("frame_count", &self.frame_count)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn gradient(width: u32, height: u32) -> VideoFrame {
+        let format = VideoFormat::new(width, height, PixelFormat::RGBA8, 60.0);
+        let mut data = Vec::with_capacity(format.buffer_size());
+        for y in 0..height {
+            for x in 0..width {
+                data.extend_from_slice(&[
+                    (x * 255 / width) as u8,
+                    (y * 255 / height) as u8,
+                    128,
+                    255,
+                ]);
+            }
+        }
+        VideoFrame::new(data, format, Duration::from_millis(500))
+    }
+
+    #[test]
+    fn test_preview_size() {
+        assert_eq!(preview_size(1920, 1080, 640, 360), (640, 360));
+        assert_eq!(preview_size(1080, 1920, 640, 360), (202, 360));
+        assert_eq!(preview_size(320, 240, 640, 360), (320, 240));
+        assert_eq!(preview_size(4096, 2160, 640, 640), (640, 338));
+    }
+
+    #[test]
+    fn test_encode_jpeg_snapshot() {
+        let jpeg = encode_jpeg(&gradient(64, 32), 80).unwrap();
+        assert_eq!(&jpeg[..2], &[0xFF, 0xD8]);
+        assert_eq!(&jpeg[jpeg.len() - 2..], &[0xFF, 0xD9]);
+
+        let decoded = image::load_from_memory(&jpeg).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (64, 32));
+    }
+
+    #[test]
+    fn test_sink_scales_and_publishes() {
+        let (tx, rx) = mpsc::channel();
+        let mut sink = PreviewSink::new("Main Output")
+            .with_max_size(160, 90)
+            .with_quality(60)
+            .on_frame(move |image| tx.send(image.clone()).unwrap());
+        assert!(sink.snapshot().is_none());
+
+        sink.send_frame(&gradient(320, 180)).unwrap();
+        let image = rx.try_recv().unwrap();
+        assert_eq!((image.width, image.height), (160, 90));
+        assert_eq!(image.sequence, 1);
+        assert_eq!(image.timestamp, Duration::from_millis(500));
+        assert_eq!(sink.snapshot(), Some(image.clone()));
+        assert_eq!(sink.format().width, 160);
+
+        let decoded = image::load_from_memory(&image.jpeg).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (160, 90));
+    }
+
+    #[test]
+    fn test_frame_rate_limit() {
+        let mut sink = PreviewSink::new("Main Output").with_max_fps(1.0);
+        let frame = gradient(32, 32);
+        for _ in 0..5 {
+            sink.send_frame(&frame).unwrap();
+        }
+        assert_eq!(sink.frame_count(), 1);
+        assert_eq!(sink.frames_skipped(), 4);
+
+        let statistics = sink.statistics().unwrap();
+        assert_eq!(statistics.frames_sent, 1);
+    }
+}
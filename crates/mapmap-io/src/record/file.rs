@@ -0,0 +1,576 @@
+//! Movie file recording.
+//!
+//! This module records frames into MOV/MP4/MKV files through the stream
+//! encoder and FFmpeg's muxers. It requires the `stream` feature.
+
+#[cfg(feature = "stream")]
+use super::Recorder;
+#[cfg(feature = "stream")]
+use crate::error::{IoError, Result};
+#[cfg(feature = "stream")]
+use crate::format::{VideoFormat, VideoFrame};
+#[cfg(feature = "stream")]
+use crate::sink::{SinkStatistics, VideoSink};
+use crate::stream::encoder::VideoCodec;
+#[cfg(feature = "stream")]
+use crate::stream::encoder::{EncodedPacket, EncoderPreset, VideoEncoder};
+#[cfg(feature = "stream")]
+use ffmpeg_next as ffmpeg;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+#[cfg(feature = "stream")]
+use std::path::PathBuf;
+
+/// Movie container format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Container {
+    /// QuickTime
+    #[default]
+    Mov,
+    /// MPEG-4 Part 14
+    Mp4,
+    /// Matroska
+    Mkv,
+}
+
+impl Container {
+    /// Returns the file extension.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Container::Mov => "mov",
+            Container::Mp4 => "mp4",
+            Container::Mkv => "mkv",
+        }
+    }
+
+    /// Returns the muxer name for FFmpeg.
+    pub fn ffmpeg_name(&self) -> &'static str {
+        match self {
+            Container::Mov => "mov",
+            Container::Mp4 => "mp4",
+            Container::Mkv => "matroska",
+        }
+    }
+
+    /// Detects the container from a file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "mov" => Some(Container::Mov),
+            "mp4" | "m4v" => Some(Container::Mp4),
+            "mkv" => Some(Container::Mkv),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the container can hold the codec.
+    pub fn supports(&self, codec: VideoCodec) -> bool {
+        match self {
+            Container::Mov => matches!(
+                codec,
+                VideoCodec::H264 | VideoCodec::H265 | VideoCodec::ProRes | VideoCodec::Ffv1
+            ),
+            Container::Mp4 => {
+                matches!(codec, VideoCodec::H264 | VideoCodec::H265 | VideoCodec::VP9)
+            }
+            Container::Mkv => true,
+        }
+    }
+}
+
+/// Records frames into movie files.
+///
+/// Frames are encoded with a [`VideoEncoder`] and muxed into `{stem}.{ext}`;
+/// later segments are named `{stem}_{segment:03}.{ext}`. Every segment starts
+/// with a fresh encoder and timestamps starting at zero, so that it begins
+/// on a keyframe and plays on its own.
+///
+/// # Example
+///
+/// ```ignore
+/// use mapmap_io::record::{FileRecorder, Recorder};
+/// use mapmap_io::stream::VideoCodec;
+/// use mapmap_io::format::VideoFormat;
+///
+/// let mut recorder = FileRecorder::new(
+///     "show.mov",
+///     VideoFormat::hd_1080p30_rgba(),
+///     VideoCodec::ProRes,
+///     0,
+/// )
+/// .unwrap()
+/// .with_split_size(4 * 1024 * 1024 * 1024);
+/// recorder.start().unwrap();
+/// ```
+#[cfg(feature = "stream")]
+pub struct FileRecorder {
+    path: PathBuf,
+    container: Container,
+    codec: VideoCodec,
+    bitrate: u64,
+    preset: EncoderPreset,
+    format: VideoFormat,
+    encoder: Option<VideoEncoder>,
+    output: Option<Segment>,
+    split_size: Option<u64>,
+    recording: bool,
+    segments: Vec<PathBuf>,
+    segment_bytes: u64,
+    bytes_written: u64,
+    frame_count: u64,
+    frames_dropped: u64,
+}
+
+#[cfg(feature = "stream")]
+impl FileRecorder {
+    /// Creates a recorder; recording begins with [`Recorder::start`].
+    ///
+    /// # Parameters
+    ///
+    /// - `path` - Output file; its extension selects the container
+    /// - `format` - Format of the incoming frames
+    /// - `codec` - Video codec
+    /// - `bitrate` - Target bitrate in bits per second (ignored by ProRes and FFV1)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the extension is not a supported container or the
+    /// container cannot hold the codec.
+    pub fn new(
+        path: impl Into<PathBuf>,
+        format: VideoFormat,
+        codec: VideoCodec,
+        bitrate: u64,
+    ) -> Result<Self> {
+        let path = path.into();
+        let container = Container::from_path(&path).ok_or_else(|| {
+            IoError::InvalidParameter(format!(
+                "{} is not a .mov, .mp4 or .mkv file",
+                path.display()
+            ))
+        })?;
+        if !container.supports(codec) {
+            return Err(IoError::UnsupportedFormat(format!(
+                "{:?} cannot be stored in {:?}",
+                codec, container
+            )));
+        }
+
+        Ok(Self {
+            path,
+            container,
+            codec,
+            bitrate,
+            preset: EncoderPreset::Medium,
+            format,
+            encoder: None,
+            output: None,
+            split_size: None,
+            recording: false,
+            segments: Vec::new(),
+            segment_bytes: 0,
+            bytes_written: 0,
+            frame_count: 0,
+            frames_dropped: 0,
+        })
+    }
+
+    /// Sets the encoder preset.
+    pub fn with_preset(mut self, preset: EncoderPreset) -> Self {
+        self.preset = preset;
+        self
+    }
+
+    /// Starts a new segment after this many bytes.
+    pub fn with_split_size(mut self, bytes: u64) -> Self {
+        self.split_size = Some(bytes);
+        self
+    }
+
+    /// Sets the size after which a new segment is started.
+    pub fn set_split_size(&mut self, bytes: Option<u64>) {
+        self.split_size = bytes;
+    }
+
+    /// Returns the container.
+    pub fn container(&self) -> Container {
+        self.container
+    }
+
+    /// Returns the codec.
+    pub fn codec(&self) -> VideoCodec {
+        self.codec
+    }
+
+    fn segment_path(&self, segment: usize) -> PathBuf {
+        if segment == 1 {
+            return self.path.clone();
+        }
+        let stem = self
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.path.with_file_name(format!(
+            "{}_{:03}.{}",
+            stem,
+            segment,
+            self.container.extension()
+        ))
+    }
+
+    fn open_segment(&mut self) -> Result<()> {
+        let path = self.segment_path(self.segments.len() + 1);
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut encoder =
+            VideoEncoder::new(self.codec, self.format.clone(), self.bitrate, self.preset)?;
+        let mut output = ffmpeg::format::output_as(&path, self.container.ffmpeg_name())
+            .map_err(|e| IoError::StreamError(format!("{}: {}", path.display(), e)))?;
+        if output
+            .format()
+            .flags()
+            .contains(ffmpeg::format::Flags::GLOBAL_HEADER)
+        {
+            encoder = encoder.with_global_header();
+        }
+
+        let parameters = encoder.codec_parameters()?;
+        {
+            let mut stream = output
+                .add_stream(ffmpeg::encoder::find_by_name(self.codec.ffmpeg_name()))
+                .map_err(|e| IoError::StreamError(e.to_string()))?;
+            stream.set_parameters(parameters);
+            stream.set_time_base(ffmpeg::Rational(1, 1000));
+        }
+        output
+            .write_header()
+            .map_err(|e| IoError::StreamError(format!("{}: {}", path.display(), e)))?;
+        // The muxer may pick its own time base in write_header
+        let time_base = output
+            .stream(0)
+            .map_or(ffmpeg::Rational(1, 1000), |stream| stream.time_base());
+
+        tracing::info!(
+            "Recording {:?} in {:?} to {}",
+            self.codec,
+            self.container,
+            path.display()
+        );
+        self.encoder = Some(encoder);
+        self.output = Some(Segment {
+            output,
+            time_base,
+            origin: None,
+        });
+        self.segments.push(path);
+        self.segment_bytes = 0;
+        Ok(())
+    }
+
+    fn close_segment(&mut self) -> Result<()> {
+        if let Some(mut encoder) = self.encoder.take() {
+            let packets = encoder.flush()?;
+            for packet in &packets {
+                self.write_packet(packet)?;
+            }
+        }
+        if let Some(mut segment) = self.output.take() {
+            segment
+                .output
+                .write_trailer()
+                .map_err(|e| IoError::StreamError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn write_packet(&mut self, packet: &EncodedPacket) -> Result<()> {
+        if packet.data.is_empty() {
+            return Ok(());
+        }
+        let segment = self.output.as_mut().ok_or(IoError::StreamDisconnected)?;
+        let origin = *segment.origin.get_or_insert(packet.dts);
+
+        let mut muxed = ffmpeg::Packet::copy(&packet.data);
+        muxed.set_stream(0);
+        muxed.set_pts(Some(packet.pts - origin));
+        muxed.set_dts(Some(packet.dts - origin));
+        if packet.is_keyframe {
+            muxed.set_flags(ffmpeg::packet::Flags::KEY);
+        }
+        muxed.rescale_ts(ffmpeg::Rational(1, 1000), segment.time_base);
+        muxed
+            .write_interleaved(&mut segment.output)
+            .map_err(|e| IoError::StreamError(e.to_string()))?;
+
+        self.segment_bytes += packet.size() as u64;
+        self.bytes_written += packet.size() as u64;
+        Ok(())
+    }
+}
+
+/// An open output file.
+#[cfg(feature = "stream")]
+struct Segment {
+    output: ffmpeg::format::context::Output,
+    /// Stream time base chosen by the muxer
+    time_base: ffmpeg::Rational,
+    /// Encoder timestamp (ms) of the first packet, written as zero
+    origin: Option<i64>,
+}
+
+#[cfg(feature = "stream")]
+impl Recorder for FileRecorder {
+    fn start(&mut self) -> Result<()> {
+        if !self.recording {
+            self.open_segment()?;
+            self.recording = true;
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        if !self.recording {
+            return Ok(());
+        }
+        self.recording = false;
+        self.close_segment()?;
+        tracing::info!(
+            "Stopped recording after {} frames ({} bytes)",
+            self.frame_count,
+            self.bytes_written
+        );
+        Ok(())
+    }
+
+    fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    fn split(&mut self) -> Result<()> {
+        if !self.recording {
+            return Err(IoError::InvalidParameter(
+                "Cannot split a stopped recording".to_string(),
+            ));
+        }
+        self.close_segment()?;
+        self.open_segment()
+    }
+
+    fn segments(&self) -> &[PathBuf] {
+        &self.segments
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+}
+
+#[cfg(feature = "stream")]
+impl VideoSink for FileRecorder {
+    fn name(&self) -> &str {
+        "File Recorder"
+    }
+
+    fn format(&self) -> VideoFormat {
+        self.format.clone()
+    }
+
+    fn send_frame(&mut self, frame: &VideoFrame) -> Result<()> {
+        if !self.recording {
+            return Ok(());
+        }
+        frame.validate()?;
+
+        if self
+            .split_size
+            .is_some_and(|limit| self.segment_bytes >= limit)
+        {
+            self.split()?;
+        }
+
+        let encoder = self.encoder.as_mut().ok_or(IoError::StreamDisconnected)?;
+        let packets = match encoder.encode(frame) {
+            Ok(packets) => packets,
+            Err(e) => {
+                self.frames_dropped += 1;
+                return Err(e);
+            }
+        };
+        for packet in &packets {
+            self.write_packet(packet)?;
+        }
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    fn is_available(&self) -> bool {
+        self.recording
+    }
+
+    fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        // The muxer interleaves and writes packets itself; buffered frames
+        // are only drained when a segment is finalized
+        Ok(())
+    }
+
+    fn statistics(&self) -> Option<SinkStatistics> {
+        Some(SinkStatistics {
+            frames_sent: self.frame_count,
+            frames_dropped: self.frames_dropped,
+            bitrate: Some(self.bitrate),
+            average_latency_ms: None,
+        })
+    }
+}
+
+#[cfg(feature = "stream")]
+impl Drop for FileRecorder {
+    fn drop(&mut self) {
+        if self.recording {
+            let _ = self.stop();
+        }
+    }
+}
+
+// Stub implementation when stream feature is disabled
+/// File recorder (stub implementation when feature is disabled)
+#[cfg(not(feature = "stream"))]
+pub struct FileRecorder;
+
+#[cfg(not(feature = "stream"))]
+impl FileRecorder {
+    /// Create a new file recorder (returns error when feature is disabled)
+    pub fn new(
+        _path: impl Into<std::path::PathBuf>,
+        _format: crate::format::VideoFormat,
+        _codec: VideoCodec,
+        _bitrate: u64,
+    ) -> crate::error::Result<Self> {
+        Err(crate::error::IoError::feature_not_enabled(
+            "File recording",
+            "stream",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_container_from_path() {
+        assert_eq!(
+            Container::from_path(Path::new("out/show.MOV")),
+            Some(Container::Mov)
+        );
+        assert_eq!(
+            Container::from_path(Path::new("show.mkv")),
+            Some(Container::Mkv)
+        );
+        assert_eq!(Container::from_path(Path::new("show.avi")), None);
+        assert_eq!(Container::Mkv.ffmpeg_name(), "matroska");
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn test_file_recorder_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let format = VideoFormat::new(64, 36, crate::format::PixelFormat::RGBA8, 30.0);
+        let mut recorder = FileRecorder::new(
+            dir.path().join("take.mov"),
+            format.clone(),
+            VideoCodec::ProRes,
+            0,
+        )
+        .unwrap();
+        assert!(FileRecorder::new("take.mp4", format.clone(), VideoCodec::ProRes, 0).is_err());
+        assert!(FileRecorder::new("take.avi", format.clone(), VideoCodec::H264, 0).is_err());
+
+        let frame = VideoFrame::empty(format);
+        recorder.send_frame(&frame).unwrap();
+        assert_eq!(recorder.frame_count(), 0);
+
+        recorder.start().unwrap();
+        recorder.send_frame(&frame).unwrap();
+        recorder.split().unwrap();
+        recorder.send_frame(&frame).unwrap();
+        recorder.stop().unwrap();
+
+        assert_eq!(recorder.frame_count(), 2);
+        assert_eq!(recorder.segments().len(), 2);
+        assert!(dir.path().join("take_002.mov").exists());
+        assert!(recorder.bytes_written() > 0);
+    }
+
+    /// Counts the non-empty video packets FFmpeg demuxes from a file.
+    #[cfg(feature = "stream")]
+    fn video_packets(path: &Path) -> usize {
+        let mut input = ffmpeg::format::input(&path).unwrap();
+        input
+            .packets()
+            .filter(|(stream, packet)| {
+                stream.parameters().medium() == ffmpeg::media::Type::Video && packet.size() > 0
+            })
+            .count()
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn test_file_recorder_writes_playable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let format = VideoFormat::new(64, 36, crate::format::PixelFormat::RGBA8, 30.0);
+
+        for (name, codec) in [
+            ("take.mov", VideoCodec::ProRes),
+            ("take.mp4", VideoCodec::H264),
+        ] {
+            let mut recorder =
+                FileRecorder::new(dir.path().join(name), format.clone(), codec, 500_000).unwrap();
+            recorder.start().unwrap();
+            for index in 0..12 {
+                let mut frame = VideoFrame::empty(format.clone());
+                frame.timestamp = format.frame_duration() * index;
+                recorder.send_frame(&frame).unwrap();
+            }
+            recorder.stop().unwrap();
+
+            assert_eq!(video_packets(&dir.path().join(name)), 12, "{}", name);
+        }
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn test_file_recorder_split_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let format = VideoFormat::new(64, 36, crate::format::PixelFormat::RGBA8, 30.0);
+        let mut recorder = FileRecorder::new(
+            dir.path().join("take.mkv"),
+            format.clone(),
+            VideoCodec::Ffv1,
+            0,
+        )
+        .unwrap()
+        .with_split_size(1);
+
+        recorder.start().unwrap();
+        for index in 0..3 {
+            let mut frame = VideoFrame::empty(format.clone());
+            frame.timestamp = format.frame_duration() * index;
+            recorder.send_frame(&frame).unwrap();
+        }
+        recorder.stop().unwrap();
+
+        // Every intra-coded frame fills a segment
+        assert_eq!(recorder.segments().len(), 3);
+        for segment in recorder.segments() {
+            assert_eq!(video_packets(segment), 1);
+        }
+    }
+}
@@ -0,0 +1,267 @@
+//! Recording output to disk.
+//!
+//! This module provides [`VideoSink`]s that write frames to files:
+//!
+//! - [`ImageSequenceWriter`] - lossless PNG or EXR image sequences, no FFmpeg required
+//! - [`FileRecorder`] - H.264/HEVC/ProRes/FFV1 movie files through the
+//!   [`VideoEncoder`](crate::stream::VideoEncoder) (requires the `stream` feature)
+//!
+//! Both implement [`Recorder`] for start/stop/split control, and both can be
+//! driven by an [`OfflineRender`], which produces frames as fast as they can
+//! be rendered instead of in real time.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use mapmap_io::record::{ImageSequenceWriter, OfflineRender, SequenceFormat};
+//! use mapmap_io::{TimecodeRate, VideoFormat, VideoFrame};
+//! use std::time::Duration;
+//!
+//! let format = VideoFormat::hd_1080p30_rgba();
+//! let mut writer = ImageSequenceWriter::new("renders", "shot", format.clone(), SequenceFormat::Png);
+//!
+//! let report = OfflineRender::new(TimecodeRate::FPS_30, Duration::from_secs(10))
+//!     .render(&mut writer, |_frame, _time| Ok(VideoFrame::empty(format.clone())))
+//!     .unwrap();
+//! println!("Rendered {} frames at {:.1}x real time", report.frames, report.speed());
+//! ```
+
+pub mod file;
+pub mod sequence;
+
+pub use file::{Container, FileRecorder};
+pub use sequence::{ImageSequenceWriter, SequenceFormat};
+
+use crate::error::{IoError, Result};
+use crate::format::VideoFrame;
+use crate::sink::VideoSink;
+use crate::timecode::{Timecode, TimecodeRate};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Control interface shared by recording sinks.
+///
+/// Frames sent while a recorder is stopped are accepted and discarded, so a
+/// recorder can stay attached to an output and be armed on demand.
+pub trait Recorder: VideoSink {
+    /// Starts recording into a new segment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the output file or directory cannot be created.
+    fn start(&mut self) -> Result<()>;
+
+    /// Stops recording and finalizes the current segment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if buffered data cannot be written.
+    fn stop(&mut self) -> Result<()>;
+
+    /// Returns true while frames are being written.
+    fn is_recording(&self) -> bool;
+
+    /// Finalizes the current segment and continues in a new one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the recorder is not recording or the next segment
+    /// cannot be created.
+    fn split(&mut self) -> Result<()>;
+
+    /// Returns the paths of all segments written so far.
+    fn segments(&self) -> &[PathBuf];
+
+    /// Returns the total number of bytes written.
+    fn bytes_written(&self) -> u64;
+}
+
+/// Summary of a finished offline render.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderReport {
+    /// Number of frames written
+    pub frames: u64,
+    /// Duration of the rendered media
+    pub media_duration: Duration,
+    /// Wall clock time the render took
+    pub elapsed: Duration,
+    /// True if the render was cancelled before the end
+    pub cancelled: bool,
+}
+
+impl RenderReport {
+    /// Returns how many times faster than real time the render ran.
+    pub fn speed(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            f64::INFINITY
+        } else {
+            self.media_duration.as_secs_f64() / self.elapsed.as_secs_f64()
+        }
+    }
+}
+
+/// Renders a span of a timeline into a sink without real-time pacing.
+///
+/// Each frame is requested from a render callback with its frame index and
+/// timeline position, stamped with a timestamp and SMPTE timecode, and sent
+/// to the sink as soon as it is ready.
+#[derive(Debug, Clone)]
+pub struct OfflineRender {
+    rate: TimecodeRate,
+    start: Duration,
+    duration: Duration,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl OfflineRender {
+    /// Creates a render of `duration` at `rate`, starting at zero.
+    pub fn new(rate: TimecodeRate, duration: Duration) -> Self {
+        Self {
+            rate,
+            start: Duration::ZERO,
+            duration,
+            cancel: None,
+        }
+    }
+
+    /// Sets the timeline position of the first frame.
+    pub fn with_start(mut self, start: Duration) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Sets a flag that stops the render when set to true.
+    pub fn with_cancel_flag(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Returns the frame rate.
+    pub fn rate(&self) -> TimecodeRate {
+        self.rate
+    }
+
+    /// Returns the number of frames that will be rendered.
+    pub fn frame_count(&self) -> u64 {
+        self.rate.frames_in(self.duration)
+    }
+
+    /// Runs the render.
+    ///
+    /// `render` is called with the frame index (starting at 0) and the
+    /// timeline position of each frame. If `sink` is a [`Recorder`], it
+    /// should be started first.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from the render callback or the sink. The
+    /// sink is flushed before returning successfully.
+    pub fn render<S, F>(&self, sink: &mut S, mut render: F) -> Result<RenderReport>
+    where
+        S: VideoSink + ?Sized,
+        F: FnMut(u64, Duration) -> Result<VideoFrame>,
+    {
+        let started = Instant::now();
+        let first_frame = self.rate.frames_in(self.start);
+        let total = self.frame_count();
+        let mut frames = 0;
+        let mut cancelled = false;
+
+        for index in 0..total {
+            if self
+                .cancel
+                .as_ref()
+                .is_some_and(|flag| flag.load(Ordering::Relaxed))
+            {
+                cancelled = true;
+                break;
+            }
+
+            let position = self.rate.time_of_frame(first_frame + index);
+            let mut frame = render(index, position)?;
+            if frame.format.width == 0 || frame.format.height == 0 {
+                return Err(IoError::InvalidFrameData(format!(
+                    "Render produced an empty frame at {:?}",
+                    position
+                )));
+            }
+            frame.timestamp = position;
+            frame.metadata.frame_number = index;
+            frame
+                .metadata
+                .set_timecode(Timecode::from_frames(first_frame + index, self.rate));
+
+            sink.send_frame(&frame)?;
+            frames += 1;
+        }
+
+        sink.flush()?;
+
+        Ok(RenderReport {
+            frames,
+            media_duration: self.rate.time_of_frame(frames),
+            elapsed: started.elapsed(),
+            cancelled,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{PixelFormat, VideoFormat};
+
+    #[test]
+    fn test_offline_render() {
+        let format = VideoFormat::new(8, 8, PixelFormat::RGBA8, 30.0);
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer =
+            ImageSequenceWriter::new(dir.path(), "render", format.clone(), SequenceFormat::Png);
+        writer.start().unwrap();
+
+        let render = OfflineRender::new(TimecodeRate::FPS_30, Duration::from_secs(1))
+            .with_start(Duration::from_secs(3600));
+        assert_eq!(render.frame_count(), 30);
+
+        let mut positions = Vec::new();
+        let report = render
+            .render(&mut writer, |_, position| {
+                positions.push(position);
+                Ok(VideoFrame::empty(format.clone()))
+            })
+            .unwrap();
+
+        assert_eq!(report.frames, 30);
+        assert!(!report.cancelled);
+        assert_eq!(report.media_duration, Duration::from_secs(1));
+        assert!(report.speed() > 1.0);
+        assert_eq!(positions[0], Duration::from_secs(3600));
+        assert_eq!(writer.frame_count(), 30);
+    }
+
+    #[test]
+    fn test_offline_render_cancel() {
+        let format = VideoFormat::new(4, 4, PixelFormat::RGBA8, 30.0);
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer =
+            ImageSequenceWriter::new(dir.path(), "render", format.clone(), SequenceFormat::Png);
+        writer.start().unwrap();
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        let report = OfflineRender::new(TimecodeRate::FPS_30, Duration::from_secs(10))
+            .with_cancel_flag(cancel)
+            .render(&mut writer, |index, _| {
+                if index == 4 {
+                    flag.store(true, Ordering::Relaxed);
+                }
+                Ok(VideoFrame::empty(format.clone()))
+            })
+            .unwrap();
+
+        assert!(report.cancelled);
+        assert_eq!(report.frames, 5);
+    }
+}
@@ -0,0 +1,404 @@
+//! Image sequence recording.
+//!
+//! Writes every frame as a numbered PNG or OpenEXR file. Image sequences are
+//! lossless, need no FFmpeg, and can be imported by every compositing tool.
+
+use super::Recorder;
+use crate::converter::FormatConverter;
+use crate::error::{IoError, Result};
+use crate::format::{PixelFormat, VideoFormat, VideoFrame};
+use crate::sink::{SinkStatistics, VideoSink};
+use image::{DynamicImage, ImageFormat, Rgba32FImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Image file format of a sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SequenceFormat {
+    /// 8-bit RGBA PNG
+    #[default]
+    Png,
+    /// 32-bit float RGBA OpenEXR, with linear color in 0.0-1.0
+    Exr,
+}
+
+impl SequenceFormat {
+    /// Returns the file extension.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SequenceFormat::Png => "png",
+            SequenceFormat::Exr => "exr",
+        }
+    }
+}
+
+/// Records frames as a numbered image sequence.
+///
+/// Files are named `{prefix}_{frame:06}.{ext}` and frame numbers continue
+/// across segments. Each segment is written to its own directory,
+/// `{directory}/{prefix}_{segment:03}`, so that a split sequence can be
+/// imported in parts.
+pub struct ImageSequenceWriter {
+    directory: PathBuf,
+    prefix: String,
+    format: VideoFormat,
+    image_format: SequenceFormat,
+    converter: FormatConverter,
+    split_size: Option<u64>,
+    recording: bool,
+    segments: Vec<PathBuf>,
+    segment_bytes: u64,
+    bytes_written: u64,
+    next_frame: u64,
+    frame_count: u64,
+    frames_dropped: u64,
+}
+
+impl ImageSequenceWriter {
+    /// Creates a writer; recording begins with [`Recorder::start`].
+    ///
+    /// # Parameters
+    ///
+    /// - `directory` - Directory that receives the segment directories
+    /// - `prefix` - File name prefix
+    /// - `format` - Format of the incoming frames; other formats are converted
+    /// - `image_format` - PNG or EXR
+    pub fn new(
+        directory: impl Into<PathBuf>,
+        prefix: impl Into<String>,
+        format: VideoFormat,
+        image_format: SequenceFormat,
+    ) -> Self {
+        Self {
+            directory: directory.into(),
+            prefix: prefix.into(),
+            format,
+            image_format,
+            converter: FormatConverter::new(),
+            split_size: None,
+            recording: false,
+            segments: Vec::new(),
+            segment_bytes: 0,
+            bytes_written: 0,
+            next_frame: 0,
+            frame_count: 0,
+            frames_dropped: 0,
+        }
+    }
+
+    /// Starts a new segment after this many bytes.
+    pub fn with_split_size(mut self, bytes: u64) -> Self {
+        self.split_size = Some(bytes);
+        self
+    }
+
+    /// Sets the number of the first frame.
+    pub fn with_start_number(mut self, frame: u64) -> Self {
+        self.next_frame = frame;
+        self
+    }
+
+    /// Sets the converter used for non-RGBA frames.
+    pub fn with_converter(mut self, converter: FormatConverter) -> Self {
+        self.converter = converter;
+        self
+    }
+
+    /// Returns the image file format.
+    pub fn image_format(&self) -> SequenceFormat {
+        self.image_format
+    }
+
+    /// Returns the size after which a new segment is started.
+    pub fn split_size(&self) -> Option<u64> {
+        self.split_size
+    }
+
+    /// Sets the size after which a new segment is started.
+    pub fn set_split_size(&mut self, bytes: Option<u64>) {
+        self.split_size = bytes;
+    }
+
+    /// Returns the path the next frame will be written to.
+    pub fn next_path(&self) -> Option<PathBuf> {
+        self.segments.last().map(|dir| {
+            dir.join(format!(
+                "{}_{:06}.{}",
+                self.prefix,
+                self.next_frame,
+                self.image_format.extension()
+            ))
+        })
+    }
+
+    fn open_segment(&mut self) -> Result<()> {
+        let dir = self
+            .directory
+            .join(format!("{}_{:03}", self.prefix, self.segments.len() + 1));
+        std::fs::create_dir_all(&dir)?;
+        tracing::info!("Recording image sequence to {}", dir.display());
+        self.segments.push(dir);
+        self.segment_bytes = 0;
+        Ok(())
+    }
+
+    fn write_image(&self, frame: &VideoFrame, path: &Path) -> Result<()> {
+        let rgba_format = VideoFormat {
+            pixel_format: PixelFormat::RGBA8,
+            ..frame.format.clone()
+        };
+        let rgba = self.converter.convert(frame, &rgba_format)?;
+        let (width, height) = (rgba.format.width, rgba.format.height);
+        let invalid = || IoError::InvalidFrameData("Frame does not fit its format".to_string());
+
+        let image = match self.image_format {
+            SequenceFormat::Png => DynamicImage::ImageRgba8(
+                RgbaImage::from_raw(width, height, rgba.data).ok_or_else(invalid)?,
+            ),
+            SequenceFormat::Exr => {
+                // EXR holds linear light, alpha is already linear
+                let samples = rgba
+                    .data
+                    .chunks_exact(4)
+                    .flat_map(|p| {
+                        [
+                            srgb_to_linear(p[0]),
+                            srgb_to_linear(p[1]),
+                            srgb_to_linear(p[2]),
+                            p[3] as f32 / 255.0,
+                        ]
+                    })
+                    .collect();
+                DynamicImage::ImageRgba32F(
+                    Rgba32FImage::from_raw(width, height, samples).ok_or_else(invalid)?,
+                )
+            }
+        };
+
+        let image_format = match self.image_format {
+            SequenceFormat::Png => ImageFormat::Png,
+            SequenceFormat::Exr => ImageFormat::OpenExr,
+        };
+        image
+            .save_with_format(path, image_format)
+            .map_err(|e| IoError::UnsupportedFormat(format!("{}: {}", path.display(), e)))
+    }
+}
+
+/// Decodes an 8-bit sRGB value to linear light.
+fn srgb_to_linear(value: u8) -> f32 {
+    let c = value as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl Recorder for ImageSequenceWriter {
+    fn start(&mut self) -> Result<()> {
+        if !self.recording {
+            self.open_segment()?;
+            self.recording = true;
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        if self.recording {
+            tracing::info!(
+                "Stopped image sequence after {} frames ({} bytes)",
+                self.frame_count,
+                self.bytes_written
+            );
+        }
+        self.recording = false;
+        Ok(())
+    }
+
+    fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    fn split(&mut self) -> Result<()> {
+        if !self.recording {
+            return Err(IoError::InvalidParameter(
+                "Cannot split a stopped recording".to_string(),
+            ));
+        }
+        self.open_segment()
+    }
+
+    fn segments(&self) -> &[PathBuf] {
+        &self.segments
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+}
+
+impl VideoSink for ImageSequenceWriter {
+    fn name(&self) -> &str {
+        "Image Sequence"
+    }
+
+    fn format(&self) -> VideoFormat {
+        self.format.clone()
+    }
+
+    fn send_frame(&mut self, frame: &VideoFrame) -> Result<()> {
+        if !self.recording {
+            return Ok(());
+        }
+        frame.validate()?;
+
+        if self
+            .split_size
+            .is_some_and(|limit| self.segment_bytes >= limit)
+        {
+            self.split()?;
+        }
+
+        let path = self.next_path().ok_or(IoError::StreamDisconnected)?;
+        if let Err(e) = self.write_image(frame, &path) {
+            self.frames_dropped += 1;
+            return Err(e);
+        }
+
+        let size = std::fs::metadata(&path)?.len();
+        self.segment_bytes += size;
+        self.bytes_written += size;
+        self.next_frame += 1;
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    fn is_available(&self) -> bool {
+        self.recording
+    }
+
+    fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    fn statistics(&self) -> Option<SinkStatistics> {
+        Some(SinkStatistics {
+            frames_sent: self.frame_count,
+            frames_dropped: self.frames_dropped,
+            bitrate: None,
+            average_latency_ms: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::ColorRange;
+    use std::time::Duration;
+
+    fn gradient(format: &VideoFormat) -> VideoFrame {
+        let data = (0..format.buffer_size()).map(|i| (i % 256) as u8).collect();
+        VideoFrame::new(data, format.clone(), Duration::ZERO)
+    }
+
+    #[test]
+    fn test_png_sequence() {
+        let dir = tempfile::tempdir().unwrap();
+        let format = VideoFormat::new(16, 8, PixelFormat::RGBA8, 30.0);
+        let mut writer =
+            ImageSequenceWriter::new(dir.path(), "shot", format.clone(), SequenceFormat::Png)
+                .with_start_number(100);
+        let frame = gradient(&format);
+
+        // Frames are ignored until recording starts
+        writer.send_frame(&frame).unwrap();
+        assert_eq!(writer.frame_count(), 0);
+
+        writer.start().unwrap();
+        writer.send_frame(&frame).unwrap();
+        writer.send_frame(&frame).unwrap();
+        writer.stop().unwrap();
+        writer.send_frame(&frame).unwrap();
+
+        assert_eq!(writer.frame_count(), 2);
+        assert!(writer.bytes_written() > 0);
+        let path = dir.path().join("shot_001").join("shot_000101.png");
+        let image = image::open(&path).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (16, 8));
+        assert_eq!(image.into_raw(), frame.data);
+    }
+
+    #[test]
+    fn test_exr_sequence_from_yuv() {
+        let dir = tempfile::tempdir().unwrap();
+        let format = VideoFormat::new(8, 4, PixelFormat::NV12, 30.0);
+        let mut writer =
+            ImageSequenceWriter::new(dir.path(), "yuv", format.clone(), SequenceFormat::Exr)
+                .with_converter(FormatConverter::new().with_range(ColorRange::Limited));
+        writer.start().unwrap();
+
+        let mut data = vec![235u8; 32];
+        data.extend(vec![128u8; 16]);
+        writer
+            .send_frame(&VideoFrame::new(data, format, Duration::ZERO))
+            .unwrap();
+
+        let path = dir.path().join("yuv_001").join("yuv_000000.exr");
+        let image = image::open(&path).unwrap().to_rgba32f();
+        assert!(image.pixels().all(|p| p.0 == [1.0, 1.0, 1.0, 1.0]));
+    }
+
+    #[test]
+    fn test_exr_is_linear() {
+        let dir = tempfile::tempdir().unwrap();
+        let format = VideoFormat::new(4, 4, PixelFormat::RGBA8, 30.0);
+        let mut writer =
+            ImageSequenceWriter::new(dir.path(), "grey", format.clone(), SequenceFormat::Exr);
+        writer.start().unwrap();
+
+        let data = [128u8, 128, 128, 255].repeat(16);
+        writer
+            .send_frame(&VideoFrame::new(data, format, Duration::ZERO))
+            .unwrap();
+
+        let path = dir.path().join("grey_001").join("grey_000000.exr");
+        let image = image::open(&path).unwrap().to_rgba32f();
+        for pixel in image.pixels() {
+            for &c in &pixel.0[..3] {
+                assert!((c - 0.2159).abs() < 1e-3, "{}", c);
+            }
+            assert_eq!(pixel.0[3], 1.0);
+        }
+    }
+
+    #[test]
+    fn test_split_on_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let format = VideoFormat::new(16, 16, PixelFormat::RGBA8, 30.0);
+        let mut writer =
+            ImageSequenceWriter::new(dir.path(), "split", format.clone(), SequenceFormat::Png)
+                .with_split_size(1);
+        assert!(writer.split().is_err());
+
+        writer.start().unwrap();
+        for _ in 0..3 {
+            writer.send_frame(&gradient(&format)).unwrap();
+        }
+
+        assert_eq!(writer.segments().len(), 3);
+        assert!(dir
+            .path()
+            .join("split_003")
+            .join("split_000002.png")
+            .exists());
+
+        writer.set_split_size(None);
+        writer.split().unwrap();
+        writer.send_frame(&gradient(&format)).unwrap();
+        writer.send_frame(&gradient(&format)).unwrap();
+        assert_eq!(writer.segments().len(), 4);
+        assert_eq!(writer.statistics().unwrap().frames_sent, 5);
+    }
+}
@@ -0,0 +1,390 @@
+//! Frame resampling.
+//!
+//! The [`Resampler`] resizes [`VideoFrame`]s with separable convolution
+//! filters, so that a frame can be fitted to a sink's [`VideoFormat`](crate::format::VideoFormat).
+//! RGB formats are filtered per channel, YUV formats per plane, with chroma
+//! planes scaled to the subsampled target size. Rows are processed in
+//! parallel.
+
+use crate::converter::{check_dimensions, chroma_subsampling, for_each_row, YuvPlanes};
+use crate::error::{IoError, Result};
+use crate::format::{VideoFormat, VideoFrame};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+/// Fixed-point bits of the filter weights
+const WEIGHT_BITS: u32 = 14;
+const WEIGHT_ONE: i32 = 1 << WEIGHT_BITS;
+
+/// Resampling filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ScaleFilter {
+    /// Linear interpolation between the two nearest samples
+    #[default]
+    Bilinear,
+    /// Catmull-Rom cubic interpolation over four samples
+    Bicubic,
+    /// Windowed sinc over six samples, sharpest but slowest
+    Lanczos3,
+}
+
+impl ScaleFilter {
+    /// Returns the filter radius in source samples, when not downscaling.
+    pub fn support(&self) -> f32 {
+        match self {
+            ScaleFilter::Bilinear => 1.0,
+            ScaleFilter::Bicubic => 2.0,
+            ScaleFilter::Lanczos3 => 3.0,
+        }
+    }
+
+    /// Evaluates the filter kernel at distance `x`.
+    fn kernel(&self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            ScaleFilter::Bilinear => (1.0 - x).max(0.0),
+            ScaleFilter::Bicubic => {
+                // Catmull-Rom (a = -0.5)
+                if x < 1.0 {
+                    1.5 * x * x * x - 2.5 * x * x + 1.0
+                } else if x < 2.0 {
+                    -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0
+                } else {
+                    0.0
+                }
+            }
+            ScaleFilter::Lanczos3 => {
+                if x < 1e-6 {
+                    1.0
+                } else if x < 3.0 {
+                    let px = PI * x;
+                    3.0 * px.sin() * (px / 3.0).sin() / (px * px)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Frame resizer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resampler {
+    filter: ScaleFilter,
+    parallel: bool,
+}
+
+impl Resampler {
+    /// Creates a resampler using `filter`.
+    pub fn new(filter: ScaleFilter) -> Self {
+        Self {
+            filter,
+            parallel: true,
+        }
+    }
+
+    /// Sets the filter.
+    pub fn with_filter(mut self, filter: ScaleFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Enables or disables multi-threaded resampling (enabled by default).
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Returns the filter.
+    pub fn filter(&self) -> ScaleFilter {
+        self.filter
+    }
+
+    /// Returns true if resampling is multi-threaded.
+    pub fn is_parallel(&self) -> bool {
+        self.parallel
+    }
+
+    /// Resizes a frame, keeping its pixel format, timestamp and metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the target size is empty, if the frame data does
+    /// not match its format, or if the target size is odd along a chroma
+    /// subsampled axis.
+    pub fn resize(&self, frame: &VideoFrame, width: u32, height: u32) -> Result<VideoFrame> {
+        if width == 0 || height == 0 {
+            return Err(IoError::InvalidParameter(format!(
+                "Cannot resize to {}x{}",
+                width, height
+            )));
+        }
+        if frame.format.width == width && frame.format.height == height {
+            return Ok(frame.clone());
+        }
+        frame.validate()?;
+
+        let format = frame.format.pixel_format;
+        let (src_width, src_height) = (frame.format.width as usize, frame.format.height as usize);
+        let (dst_width, dst_height) = (width as usize, height as usize);
+
+        let data = if format.is_rgb() {
+            self.resample_plane(
+                &frame.data,
+                (src_width, src_height),
+                (dst_width, dst_height),
+                format.bytes_per_pixel(),
+            )
+        } else {
+            check_dimensions(format, dst_width, dst_height)?;
+            let (sx, sy) = chroma_subsampling(format);
+            let planes = YuvPlanes::unpack(&frame.data, format, src_width, src_height)?;
+            let src_chroma = (planes.chroma_width, planes.chroma_height);
+            let dst_chroma = (dst_width / sx, dst_height / sy);
+
+            YuvPlanes {
+                width: dst_width,
+                height: dst_height,
+                chroma_width: dst_chroma.0,
+                chroma_height: dst_chroma.1,
+                y: self.resample_plane(
+                    &planes.y,
+                    (src_width, src_height),
+                    (dst_width, dst_height),
+                    1,
+                ),
+                u: self.resample_plane(&planes.u, src_chroma, dst_chroma, 1),
+                v: self.resample_plane(&planes.v, src_chroma, dst_chroma, 1),
+            }
+            .pack(format)
+        };
+
+        let target = VideoFormat {
+            width,
+            height,
+            ..frame.format.clone()
+        };
+        Ok(VideoFrame::with_metadata(
+            data,
+            target,
+            frame.timestamp,
+            frame.metadata.clone(),
+        ))
+    }
+
+    /// Resizes an interleaved plane with `channels` bytes per sample, first
+    /// horizontally, then vertically.
+    fn resample_plane(
+        &self,
+        src: &[u8],
+        (src_width, src_height): (usize, usize),
+        (dst_width, dst_height): (usize, usize),
+        channels: usize,
+    ) -> Vec<u8> {
+        let horizontal = if src_width == dst_width {
+            src.to_vec()
+        } else {
+            let taps = Taps::new(src_width, dst_width, self.filter);
+            let src_row = src_width * channels;
+            let mut output = vec![0u8; dst_width * channels * src_height];
+            for_each_row(
+                &mut output,
+                dst_width * channels,
+                self.parallel,
+                |y, row| {
+                    let src = &src[y * src_row..(y + 1) * src_row];
+                    for (x, pixel) in row.chunks_exact_mut(channels).enumerate() {
+                        let (start, weights) = taps.get(x);
+                        for (c, out) in pixel.iter_mut().enumerate() {
+                            let sum: i32 = weights
+                                .iter()
+                                .enumerate()
+                                .map(|(k, w)| w * src[(start + k) * channels + c] as i32)
+                                .sum();
+                            *out = round_weighted(sum);
+                        }
+                    }
+                },
+            );
+            output
+        };
+
+        if src_height == dst_height {
+            return horizontal;
+        }
+
+        let taps = Taps::new(src_height, dst_height, self.filter);
+        let row_len = dst_width * channels;
+        let mut output = vec![0u8; row_len * dst_height];
+        for_each_row(&mut output, row_len, self.parallel, |y, row| {
+            let (start, weights) = taps.get(y);
+            let mut sums = vec![0i32; row_len];
+            for (k, w) in weights.iter().enumerate() {
+                let src = &horizontal[(start + k) * row_len..(start + k + 1) * row_len];
+                for (sum, value) in sums.iter_mut().zip(src) {
+                    *sum += w * *value as i32;
+                }
+            }
+            for (out, sum) in row.iter_mut().zip(sums) {
+                *out = round_weighted(sum);
+            }
+        });
+        output
+    }
+}
+
+impl Default for Resampler {
+    fn default() -> Self {
+        Self::new(ScaleFilter::default())
+    }
+}
+
+#[inline]
+fn round_weighted(sum: i32) -> u8 {
+    ((sum + (WEIGHT_ONE >> 1)) >> WEIGHT_BITS).clamp(0, 255) as u8
+}
+
+/// Precomputed fixed-point filter weights for one axis.
+struct Taps {
+    starts: Vec<usize>,
+    weights: Vec<Vec<i32>>,
+}
+
+impl Taps {
+    fn new(src_len: usize, dst_len: usize, filter: ScaleFilter) -> Self {
+        let scale = src_len as f32 / dst_len as f32;
+        // Widen the kernel when downscaling so every source sample contributes
+        let filter_scale = scale.max(1.0);
+        let support = filter.support() * filter_scale;
+
+        let mut starts = Vec::with_capacity(dst_len);
+        let mut weights = Vec::with_capacity(dst_len);
+        for i in 0..dst_len {
+            let center = (i as f32 + 0.5) * scale;
+            let left = (center - support).floor().max(0.0) as usize;
+            let right = ((center + support).ceil() as usize)
+                .min(src_len)
+                .max(left + 1);
+
+            let mut values: Vec<f32> = (left..right)
+                .map(|j| filter.kernel((j as f32 + 0.5 - center) / filter_scale))
+                .collect();
+            let total: f32 = values.iter().sum();
+            if total.abs() < 1e-6 {
+                values = vec![0.0; right - left];
+                let nearest = (center as usize).clamp(left, right - 1);
+                values[nearest - left] = 1.0;
+            } else {
+                values.iter_mut().for_each(|v| *v /= total);
+            }
+
+            // Put the rounding error on the largest weight so each row sums to
+            // exactly one
+            let mut fixed: Vec<i32> = values
+                .iter()
+                .map(|v| (v * WEIGHT_ONE as f32).round() as i32)
+                .collect();
+            let error = WEIGHT_ONE - fixed.iter().sum::<i32>();
+            if let Some(max) = fixed.iter_mut().max() {
+                *max += error;
+            }
+
+            starts.push(left);
+            weights.push(fixed);
+        }
+
+        Self { starts, weights }
+    }
+
+    #[inline]
+    fn get(&self, i: usize) -> (usize, &[i32]) {
+        (self.starts[i], &self.weights[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::PixelFormat;
+    use std::time::Duration;
+
+    const FILTERS: [ScaleFilter; 3] = [
+        ScaleFilter::Bilinear,
+        ScaleFilter::Bicubic,
+        ScaleFilter::Lanczos3,
+    ];
+
+    fn frame(width: u32, height: u32, format: PixelFormat, data: Vec<u8>) -> VideoFrame {
+        VideoFrame::new(
+            data,
+            VideoFormat::new(width, height, format, 30.0),
+            Duration::ZERO,
+        )
+    }
+
+    #[test]
+    fn test_flat_color_is_preserved() {
+        let source = frame(6, 4, PixelFormat::RGBA8, [10, 120, 240, 255].repeat(24));
+
+        for filter in FILTERS {
+            let resampler = Resampler::new(filter);
+            for (width, height) in [(12, 8), (3, 2), (7, 5)] {
+                let scaled = resampler.resize(&source, width, height).unwrap();
+                assert_eq!(scaled.format.width, width);
+                assert_eq!(scaled.data.len(), scaled.format.buffer_size());
+                assert!(scaled
+                    .data
+                    .chunks_exact(4)
+                    .all(|p| p == [10, 120, 240, 255]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_gradient_and_edges() {
+        // Horizontal ramp stays monotonic when upscaled
+        let ramp: Vec<u8> = (0..8u8).flat_map(|x| [x * 32; 3]).collect();
+        let source = frame(8, 1, PixelFormat::RGB8, ramp);
+        let scaled = Resampler::new(ScaleFilter::Bilinear)
+            .resize(&source, 16, 1)
+            .unwrap();
+        let reds: Vec<u8> = scaled.data.iter().step_by(3).copied().collect();
+        assert!(reds.windows(2).all(|w| w[0] <= w[1]), "{:?}", reds);
+
+        // Downscaling by two averages neighbours
+        let source = frame(
+            4,
+            1,
+            PixelFormat::RGB8,
+            [0, 0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255].to_vec(),
+        );
+        let scaled = Resampler::new(ScaleFilter::Bilinear)
+            .resize(&source, 2, 1)
+            .unwrap();
+        assert!(scaled.data[0] < 64 && scaled.data[3] > 191);
+
+        // Sharp edges ring with Lanczos but stay within range
+        let scaled = Resampler::new(ScaleFilter::Lanczos3)
+            .resize(&source, 9, 3)
+            .unwrap();
+        assert_eq!(scaled.data.len(), 9 * 3 * 3);
+    }
+
+    #[test]
+    fn test_resize_yuv() {
+        let format = VideoFormat::new(8, 4, PixelFormat::NV12, 30.0);
+        let mut data = vec![180u8; 32];
+        data.extend([60u8, 200].repeat(8));
+        let source = VideoFrame::new(data, format, Duration::ZERO);
+
+        let scaled = Resampler::default().resize(&source, 4, 2).unwrap();
+        assert_eq!(scaled.format.pixel_format, PixelFormat::NV12);
+        assert_eq!(
+            scaled.data,
+            [vec![180u8; 8], vec![60, 200, 60, 200]].concat()
+        );
+
+        assert!(Resampler::default().resize(&source, 5, 2).is_err());
+        assert!(Resampler::default().resize(&source, 0, 2).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Shared-memory frame client.
+
+use super::object_name;
+use super::segment::Segment;
+use crate::error::{IoError, Result};
+use crate::format::{VideoFormat, VideoFrame};
+use crate::source::VideoSource;
+use std::ffi::CString;
+
+/// Attempts at reading a consistent frame before giving up until the next
+/// call.
+const READ_ATTEMPTS: usize = 4;
+
+/// Receives frames published by a [`ShmServer`](super::ShmServer) or any
+/// other application implementing the protocol.
+///
+/// [`VideoSource::receive_frame`] never blocks: it returns the newest frame
+/// once and `IoError::NoFrameAvailable` until the server publishes another.
+/// When the server replaces its segment the client reopens it by name, and
+/// [`VideoSource::reconnect`] picks up a server that was restarted.
+pub struct ShmClient {
+    name: String,
+    object: CString,
+    format: VideoFormat,
+    segment: Option<Segment>,
+    last_frame: u64,
+    frame_count: u64,
+}
+
+impl ShmClient {
+    /// Connects to the server named `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::SharedMemoryNotFound` if no running server uses the
+    /// name and `IoError::SharedMemoryError` if the segment is not a
+    /// compatible frame sharing segment.
+    pub fn connect(name: impl Into<String>) -> Result<Self> {
+        let name = name.into();
+        let object = object_name(&name)?;
+        let segment = open_live(&object, &name)?;
+        let format = segment
+            .format()
+            .ok_or_else(|| IoError::UnsupportedPixelFormat(format!("server '{}'", name)))?;
+        Ok(Self {
+            name,
+            object,
+            format,
+            segment: Some(segment),
+            last_frame: 0,
+            frame_count: 0,
+        })
+    }
+
+    /// Returns the process id of the connected server.
+    pub fn server_pid(&self) -> Option<u32> {
+        self.segment.as_ref().map(Segment::pid)
+    }
+
+    /// Returns the segment to read from, reopening it if the server
+    /// replaced it.
+    fn segment(&mut self) -> Result<&Segment> {
+        if self.segment.as_ref().is_some_and(Segment::is_closed) {
+            self.segment = None;
+            match open_live(&self.object, &self.name) {
+                Ok(segment) => {
+                    tracing::debug!("Reopened shared memory server '{}'", self.name);
+                    self.segment = Some(segment);
+                    self.last_frame = 0;
+                }
+                Err(IoError::SharedMemoryNotFound(_)) => return Err(IoError::StreamDisconnected),
+                Err(e) => return Err(e),
+            }
+        }
+        self.segment.as_ref().ok_or(IoError::StreamDisconnected)
+    }
+}
+
+impl VideoSource for ShmClient {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn format(&self) -> VideoFormat {
+        self.format.clone()
+    }
+
+    fn receive_frame(&mut self) -> Result<VideoFrame> {
+        let last_frame = self.last_frame;
+        let (number, frame) = read_newer(self.segment()?, last_frame)?;
+        self.format = frame.format.clone();
+        self.last_frame = number;
+        self.frame_count += 1;
+        Ok(frame)
+    }
+
+    fn is_available(&self) -> bool {
+        self.segment
+            .as_ref()
+            .is_some_and(|segment| !segment.is_closed() && segment.owner_alive())
+    }
+
+    fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    fn reconnect(&mut self) -> Result<()> {
+        // A restarted server counts frames from 1 again
+        self.segment = Some(open_live(&self.object, &self.name)?);
+        self.last_frame = 0;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for ShmClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShmClient")
+            .field("name", &self.name)
+            .field("object", &self.object)
+            .field("format", &self.format)
+            .field("connected", &self.segment.is_some())
+            .field("frame_count", &self.frame_count)
+            .finish()
+    }
+}
+
+/// Opens `object` if its server is still running.
+fn open_live(object: &CString, name: &str) -> Result<Segment> {
+    let segment = Segment::open(object)?;
+    if segment.is_closed() || !segment.owner_alive() {
+        return Err(IoError::SharedMemoryNotFound(name.to_string()));
+    }
+    Ok(segment)
+}
+
+/// Reads the newest frame if it is newer than `last_frame`.
+fn read_newer(segment: &Segment, last_frame: u64) -> Result<(u64, VideoFrame)> {
+    for _ in 0..READ_ATTEMPTS {
+        let Some(latest) = segment.latest() else {
+            std::hint::spin_loop();
+            continue;
+        };
+        if latest == 0 || latest == last_frame {
+            return Err(IoError::NoFrameAvailable);
+        }
+        if let Some(frame) = segment.read_frame(latest)? {
+            return Ok((latest, frame));
+        }
+        std::hint::spin_loop();
+    }
+    // The server kept overwriting the slot, the next call will catch up
+    Err(IoError::NoFrameAvailable)
+}
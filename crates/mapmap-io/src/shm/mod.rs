@@ -0,0 +1,464 @@
+//! Shared-memory frame sharing between applications on the same machine
+//! (Linux only).
+//!
+//! This is the Linux counterpart of Spout and Syphon. A [`ShmServer`]
+//! publishes frames into a POSIX shared-memory object named
+//! `/mapflow.<name>` (visible as `/dev/shm/mapflow.<name>`), and any number
+//! of [`ShmClient`]s map it read-only and copy out the newest frame. Servers
+//! never wait for clients: frames are written round-robin into three slots,
+//! each guarded by a sequence number, so readers detect and retry torn
+//! reads without locks. Running servers are found with [`list_servers`].
+//!
+//! # Wire Layout
+//!
+//! Other applications can interoperate by implementing the layout below.
+//! All integers use native byte order (little-endian on every supported
+//! target) and fields marked atomic must be accessed with 32-bit atomic
+//! loads and stores. Clients map the segment read-only, so 64-bit values are
+//! split into two 32-bit halves and guarded by a sequence number.
+//!
+//! The object name is `/mapflow.` followed by the server name with every
+//! character outside `[A-Za-z0-9._-]` replaced by `_`. The segment starts
+//! with a header:
+//!
+//! | Offset | Size | Field |
+//! |--------|------|-------|
+//! | 0 | 4 | Magic `0x4853464D` (`"MFSH"`), atomic, stored last on creation |
+//! | 4 | 4 | Protocol version, currently 1 |
+//! | 8 | 4 | Data offset: start of slot 0 pixel data, 4096 |
+//! | 12 | 4 | Slot count, 3 |
+//! | 16 | 8 | Slot size: bytes reserved for each frame's pixels |
+//! | 24 | 4 | Server process id |
+//! | 28 | 4 | Flags, atomic. Bit 0 is set once the segment is closed |
+//! | 32 | 4 | Latest: low half of the number of the newest complete frame, atomic, 0 before the first |
+//! | 36 | 4 | Latest: high half, atomic |
+//! | 40 | 4 | Width, atomic |
+//! | 44 | 4 | Height, atomic |
+//! | 48 | 4 | Pixel format code, atomic |
+//! | 52 | 4 | Frame rate as `f32` bits, atomic |
+//! | 56 | 64 | Server name, UTF-8, NUL padded |
+//! | 120 | 4 | Latest sequence, atomic, odd while Latest is being written |
+//! | 128 | 48 × slot count | Slot descriptors |
+//!
+//! Each slot descriptor is:
+//!
+//! | Offset | Size | Field |
+//! |--------|------|-------|
+//! | 0 | 4 | Sequence, atomic, odd while the slot is being written |
+//! | 4 | 4 | Reserved |
+//! | 8 | 8 | Frame number |
+//! | 16 | 8 | Timestamp in nanoseconds |
+//! | 24 | 4 | Width |
+//! | 28 | 4 | Height |
+//! | 32 | 4 | Pixel format code |
+//! | 36 | 4 | Reserved |
+//! | 40 | 8 | Length of the pixel data in bytes |
+//!
+//! Frame `n` lives in slot `n % slot count`, with its pixels at
+//! `data offset + slot × slot size`, tightly packed in the layout of
+//! [`PixelFormat::buffer_size`]. Pixel format codes are 1 RGBA8, 2 BGRA8,
+//! 3 RGB8, 4 YUV420P, 5 YUV422P, 6 UYVY and 7 NV12.
+//!
+//! A writer increments the slot sequence to odd, writes the descriptor and
+//! pixels, increments the sequence to even with release ordering and then
+//! stores the frame number into Latest the same way: Latest sequence to
+//! odd, both halves, Latest sequence to even. A reader loads Latest and
+//! retries if its sequence was odd or changed meanwhile, then loads the
+//! slot sequence with acquire ordering, skips the slot if it is odd or the
+//! frame number differs, copies the descriptor and pixels and accepts them
+//! only if the sequence is unchanged afterwards.
+//!
+//! When frames outgrow the slots the server sets the closed flag, unlinks
+//! the object and creates a larger one under the same name. Clients that
+//! see the closed flag reopen the name. A segment whose server process no
+//! longer exists is stale and is replaced by the next server with that
+//! name.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use mapmap_io::shm::{list_servers, ShmClient, ShmServer};
+//! use mapmap_io::{VideoFormat, VideoFrame, VideoSink, VideoSource};
+//!
+//! let mut server = ShmServer::new("Main Output", VideoFormat::hd_1080p60_rgba())?;
+//! server.send_frame(&VideoFrame::empty(VideoFormat::hd_1080p60_rgba()))?;
+//!
+//! for info in list_servers()? {
+//!     println!("{} ({}x{})", info.name, info.format.width, info.format.height);
+//! }
+//! let mut client = ShmClient::connect("Main Output")?;
+//! let frame = client.receive_frame()?;
+//! ```
+
+use crate::error::{IoError, Result};
+use crate::format::{PixelFormat, VideoFormat};
+
+#[cfg(target_os = "linux")]
+mod client;
+#[cfg(target_os = "linux")]
+mod segment;
+#[cfg(target_os = "linux")]
+mod server;
+
+#[cfg(target_os = "linux")]
+pub use client::ShmClient;
+#[cfg(target_os = "linux")]
+pub use server::ShmServer;
+
+/// Segment magic number, `"MFSH"` in little-endian byte order.
+pub const MAGIC: u32 = 0x4853_464D;
+
+/// Protocol version written to and expected in the header.
+pub const VERSION: u32 = 1;
+
+/// Prefix of every shared-memory object name.
+pub const OBJECT_PREFIX: &str = "/mapflow.";
+
+/// Header flag set once a segment is closed or replaced.
+pub const FLAG_CLOSED: u32 = 1;
+
+/// Longest accepted server name in bytes.
+const MAX_NAME_LEN: usize = 200;
+
+/// Information about a running shared-memory server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShmServerInfo {
+    /// Server name
+    pub name: String,
+    /// Shared-memory object name
+    pub object_name: String,
+    /// Advertised video format
+    pub format: VideoFormat,
+    /// Server process id
+    pub pid: u32,
+    /// Number of the newest published frame
+    pub frame_number: u64,
+}
+
+/// Returns the protocol code for `format`.
+pub fn pixel_format_code(format: PixelFormat) -> u32 {
+    match format {
+        PixelFormat::RGBA8 => 1,
+        PixelFormat::BGRA8 => 2,
+        PixelFormat::RGB8 => 3,
+        PixelFormat::YUV420P => 4,
+        PixelFormat::YUV422P => 5,
+        PixelFormat::UYVY => 6,
+        PixelFormat::NV12 => 7,
+    }
+}
+
+/// Returns the pixel format for a protocol code.
+pub fn pixel_format_from_code(code: u32) -> Option<PixelFormat> {
+    Some(match code {
+        1 => PixelFormat::RGBA8,
+        2 => PixelFormat::BGRA8,
+        3 => PixelFormat::RGB8,
+        4 => PixelFormat::YUV420P,
+        5 => PixelFormat::YUV422P,
+        6 => PixelFormat::UYVY,
+        7 => PixelFormat::NV12,
+        _ => return None,
+    })
+}
+
+/// Returns the shared-memory object name for a server name.
+///
+/// # Errors
+///
+/// Returns `IoError::InvalidParameter` for an empty name or one longer
+/// than 200 bytes.
+pub fn object_name(name: &str) -> Result<std::ffi::CString> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(IoError::InvalidParameter(format!(
+            "Shared memory server names must be 1 to {} bytes: '{}'",
+            MAX_NAME_LEN, name
+        )));
+    }
+    let sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    std::ffi::CString::new(format!("{}{}", OBJECT_PREFIX, sanitized))
+        .map_err(|e| IoError::InvalidParameter(e.to_string()))
+}
+
+/// Lists the running shared-memory servers on this machine.
+///
+/// Segments left behind by servers that exited are skipped.
+///
+/// # Errors
+///
+/// Returns `IoError::Io` if `/dev/shm` cannot be read, or
+/// `IoError::PlatformNotSupported` on other platforms than Linux.
+#[cfg(target_os = "linux")]
+pub fn list_servers() -> Result<Vec<ShmServerInfo>> {
+    let prefix = OBJECT_PREFIX.trim_start_matches('/');
+    let mut servers = Vec::new();
+    for entry in std::fs::read_dir("/dev/shm")? {
+        let file_name = entry?.file_name();
+        let Some(file_name) = file_name.to_str().filter(|n| n.starts_with(prefix)) else {
+            continue;
+        };
+        let Ok(object) = std::ffi::CString::new(format!("/{}", file_name)) else {
+            continue;
+        };
+        let segment = match segment::Segment::open(&object) {
+            Ok(segment) => segment,
+            Err(e) => {
+                tracing::debug!("Skipping shared memory segment {}: {}", file_name, e);
+                continue;
+            }
+        };
+        if segment.is_closed() || !segment.owner_alive() {
+            continue;
+        }
+        let Some(format) = segment.format() else {
+            continue;
+        };
+        servers.push(ShmServerInfo {
+            name: segment.server_name(),
+            object_name: object.to_string_lossy().into_owned(),
+            format,
+            pid: segment.pid(),
+            frame_number: segment.latest().unwrap_or(0),
+        });
+    }
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(servers)
+}
+
+/// Lists the running shared-memory servers (not supported on this
+/// platform).
+#[cfg(not(target_os = "linux"))]
+pub fn list_servers() -> Result<Vec<ShmServerInfo>> {
+    Err(IoError::platform_not_supported(
+        "Shared memory frame sharing is only available on Linux",
+    ))
+}
+
+// Stub types for non-Linux platforms
+/// Shared-memory server (stub implementation on non-Linux platforms)
+#[cfg(not(target_os = "linux"))]
+pub struct ShmServer;
+
+#[cfg(not(target_os = "linux"))]
+impl ShmServer {
+    /// Create a new shared-memory server (returns error on non-Linux platforms)
+    pub fn new(_name: impl Into<String>, _format: VideoFormat) -> Result<Self> {
+        Err(IoError::platform_not_supported(
+            "Shared memory frame sharing is only available on Linux",
+        ))
+    }
+}
+
+/// Shared-memory client (stub implementation on non-Linux platforms)
+#[cfg(not(target_os = "linux"))]
+pub struct ShmClient;
+
+#[cfg(not(target_os = "linux"))]
+impl ShmClient {
+    /// Connect to a shared-memory server (returns error on non-Linux platforms)
+    pub fn connect(_name: impl Into<String>) -> Result<Self> {
+        Err(IoError::platform_not_supported(
+            "Shared memory frame sharing is only available on Linux",
+        ))
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::format::VideoFrame;
+    use crate::sink::VideoSink;
+    use crate::source::VideoSource;
+    use std::thread;
+    use std::time::Duration;
+
+    /// Returns a server name unique to this test process.
+    fn unique(name: &str) -> String {
+        format!("test {} {}", name, std::process::id())
+    }
+
+    fn frame(width: u32, height: u32, value: u8, ms: u64) -> VideoFrame {
+        let format = VideoFormat::new(width, height, PixelFormat::RGBA8, 30.0);
+        let data = vec![value; format.buffer_size()];
+        VideoFrame::new(data, format, Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn test_object_name_and_codes() {
+        let object = object_name("Main Output/1").unwrap();
+        assert_eq!(object.to_str().unwrap(), "/mapflow.Main_Output_1");
+        assert!(object_name("").is_err());
+        assert!(object_name(&"x".repeat(201)).is_err());
+
+        for format in [
+            PixelFormat::RGBA8,
+            PixelFormat::BGRA8,
+            PixelFormat::RGB8,
+            PixelFormat::YUV420P,
+            PixelFormat::YUV422P,
+            PixelFormat::UYVY,
+            PixelFormat::NV12,
+        ] {
+            assert_eq!(
+                pixel_format_from_code(pixel_format_code(format)),
+                Some(format)
+            );
+        }
+        assert_eq!(pixel_format_from_code(0), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let name = unique("round trip");
+        let mut server = ShmServer::new(&name, frame(4, 2, 0, 0).format).unwrap();
+        let mut client = ShmClient::connect(&name).unwrap();
+        assert!(client.is_available());
+        assert_eq!(client.format().width, 4);
+        assert!(matches!(
+            client.receive_frame(),
+            Err(IoError::NoFrameAvailable)
+        ));
+
+        server.send_frame(&frame(4, 2, 7, 40)).unwrap();
+        let received = client.receive_frame().unwrap();
+        assert_eq!(received.data, vec![7; 32]);
+        assert_eq!(received.timestamp, Duration::from_millis(40));
+        assert_eq!(received.metadata.frame_number, 1);
+        assert_eq!(received.metadata.source_name, name);
+        assert!(matches!(
+            client.receive_frame(),
+            Err(IoError::NoFrameAvailable)
+        ));
+
+        // A client that falls behind skips to the newest frame
+        for i in 2..=5 {
+            server.send_frame(&frame(4, 2, i, 40 * i as u64)).unwrap();
+        }
+        let received = client.receive_frame().unwrap();
+        assert_eq!(received.data[0], 5);
+        assert_eq!(client.frame_count(), 2);
+        assert_eq!(server.frame_count(), 5);
+    }
+
+    #[test]
+    fn test_frame_numbers_beyond_32_bits() {
+        let object = object_name(&unique("wide")).unwrap();
+        let format = frame(2, 2, 0, 0).format;
+        let server = segment::Segment::create(&object, "wide", &format, 16).unwrap();
+        // Clients map read-only and must still read every field
+        let client = segment::Segment::open(&object).unwrap();
+        assert_eq!(client.latest(), Some(0));
+
+        let number = (1u64 << 32) + 3;
+        server.write_frame(number, &frame(2, 2, 9, 0));
+        assert_eq!(client.latest(), Some(number));
+        let received = client.read_frame(number).unwrap().unwrap();
+        assert_eq!(received.metadata.frame_number, number);
+        assert_eq!(received.data, vec![9; 16]);
+    }
+
+    #[test]
+    fn test_discovery_and_shutdown() {
+        let name = unique("discovery");
+        let server = ShmServer::new(&name, VideoFormat::hd_720p60_rgba()).unwrap();
+        assert!(matches!(
+            ShmServer::new(&name, VideoFormat::hd_720p60_rgba()),
+            Err(IoError::SharedMemoryError(_))
+        ));
+
+        let info = list_servers()
+            .unwrap()
+            .into_iter()
+            .find(|info| info.name == name)
+            .unwrap();
+        assert_eq!(info.object_name, server.object_name());
+        assert_eq!(info.format, VideoFormat::hd_720p60_rgba());
+        assert_eq!(info.pid, std::process::id());
+        assert_eq!(info.frame_number, 0);
+
+        let mut client = ShmClient::connect(&name).unwrap();
+        drop(server);
+        assert!(!client.is_available());
+        assert!(matches!(
+            client.receive_frame(),
+            Err(IoError::StreamDisconnected)
+        ));
+        assert!(!list_servers().unwrap().iter().any(|info| info.name == name));
+        assert!(matches!(
+            ShmClient::connect(&name),
+            Err(IoError::SharedMemoryNotFound(_))
+        ));
+
+        // A restarted server is picked up by reconnecting
+        let mut server = ShmServer::new(&name, VideoFormat::hd_720p60_rgba()).unwrap();
+        client.reconnect().unwrap();
+        server.send_frame(&frame(8, 8, 1, 0)).unwrap();
+        assert_eq!(client.receive_frame().unwrap().format.width, 8);
+    }
+
+    #[test]
+    fn test_client_follows_resize() {
+        let name = unique("resize");
+        let mut server = ShmServer::new(&name, frame(2, 2, 0, 0).format).unwrap();
+        let mut client = ShmClient::connect(&name).unwrap();
+
+        server.send_frame(&frame(2, 2, 1, 0)).unwrap();
+        assert_eq!(client.receive_frame().unwrap().data.len(), 16);
+
+        // Outgrows the slots, so the server replaces its segment
+        server.send_frame(&frame(16, 8, 2, 40)).unwrap();
+        let received = client.receive_frame().unwrap();
+        assert_eq!(received.format.width, 16);
+        assert_eq!(received.data, vec![2; 16 * 8 * 4]);
+        assert_eq!(client.format().height, 8);
+
+        // Smaller frames reuse the larger slots
+        server.send_frame(&frame(2, 2, 3, 80)).unwrap();
+        assert_eq!(client.receive_frame().unwrap().data, vec![3; 16]);
+    }
+
+    #[test]
+    fn test_concurrent_reads_are_consistent() {
+        let name = unique("concurrent");
+        let format = VideoFormat::new(64, 64, PixelFormat::RGBA8, 60.0);
+        let mut server = ShmServer::new(&name, format).unwrap();
+        let mut client = ShmClient::connect(&name).unwrap();
+
+        let producer = thread::spawn(move || {
+            for i in 1..=300u64 {
+                server.send_frame(&frame(64, 64, i as u8, i)).unwrap();
+            }
+            server
+        });
+
+        let mut last = 0;
+        let mut received = 0;
+        while !producer.is_finished() || received == 0 {
+            match client.receive_frame() {
+                Ok(frame) => {
+                    let number = frame.metadata.frame_number;
+                    assert!(number > last);
+                    // Every byte comes from the same frame, no torn reads
+                    assert!(frame.data.iter().all(|b| *b == number as u8));
+                    assert_eq!(frame.timestamp, Duration::from_millis(number));
+                    last = number;
+                    received += 1;
+                }
+                Err(IoError::NoFrameAvailable) => thread::yield_now(),
+                Err(e) => panic!("{}", e),
+            }
+        }
+        let server = producer.join().unwrap();
+        assert!(received > 0);
+        assert_eq!(server.frame_count(), 300);
+    }
+}
@@ -0,0 +1,457 @@
+//! Mapping of a shared-memory segment and access to its header and slots.
+//!
+//! Offsets follow the layout documented in the [module docs](super).
+
+use super::{pixel_format_code, pixel_format_from_code, FLAG_CLOSED, MAGIC, VERSION};
+use crate::error::{IoError, Result};
+use crate::format::{FrameMetadata, VideoFormat, VideoFrame};
+use std::ffi::CString;
+use std::io;
+use std::ptr;
+use std::sync::atomic::{fence, AtomicU32, Ordering};
+use std::time::Duration;
+
+/// Byte offset of slot 0 pixel data, one page after the start.
+pub(super) const DATA_OFFSET: usize = 4096;
+/// Number of frame slots written round-robin.
+pub(super) const SLOT_COUNT: usize = 3;
+/// Maximum length of the server name in bytes.
+pub(super) const NAME_LEN: usize = 64;
+
+const MAGIC_OFFSET: usize = 0;
+const VERSION_OFFSET: usize = 4;
+const DATA_OFFSET_OFFSET: usize = 8;
+const SLOT_COUNT_OFFSET: usize = 12;
+const SLOT_SIZE_OFFSET: usize = 16;
+const PID_OFFSET: usize = 24;
+const FLAGS_OFFSET: usize = 28;
+const LATEST_OFFSET: usize = 32;
+const LATEST_HIGH_OFFSET: usize = 36;
+const WIDTH_OFFSET: usize = 40;
+const HEIGHT_OFFSET: usize = 44;
+const PIXEL_FORMAT_OFFSET: usize = 48;
+const FRAME_RATE_OFFSET: usize = 52;
+const NAME_OFFSET: usize = 56;
+const LATEST_SEQUENCE_OFFSET: usize = 120;
+const SLOTS_OFFSET: usize = 128;
+
+const SLOT_DESCRIPTOR_SIZE: usize = 48;
+const SLOT_SEQUENCE: usize = 0;
+const SLOT_FRAME_NUMBER: usize = 8;
+const SLOT_TIMESTAMP: usize = 16;
+const SLOT_WIDTH: usize = 24;
+const SLOT_HEIGHT: usize = 28;
+const SLOT_PIXEL_FORMAT: usize = 32;
+const SLOT_LENGTH: usize = 40;
+
+/// A mapped shared-memory segment.
+///
+/// The server maps it read-write and unlinks it on drop, clients map it
+/// read-only. All fields that change after creation are accessed through
+/// 32-bit atomics, which are plain loads on every target and so never write
+/// to the read-only mapping. 64-bit values are split into halves guarded by
+/// a sequence number, as is the pixel data of each slot.
+pub(super) struct Segment {
+    ptr: *mut u8,
+    len: usize,
+    object: CString,
+    owner: bool,
+}
+
+// SAFETY: the pointer refers to a process-wide mapping that stays valid until
+// drop and is not tied to the creating thread. Concurrent access, even from
+// other processes, only goes through atomics and the sequence protocol, and
+// only the owning server ever writes.
+unsafe impl Send for Segment {}
+
+impl Segment {
+    /// Creates and initializes the segment `object` for a server.
+    ///
+    /// A segment left behind by a server that crashed or closed is replaced,
+    /// one owned by a running server is an error.
+    pub(super) fn create(
+        object: &CString,
+        name: &str,
+        format: &VideoFormat,
+        slot_size: usize,
+    ) -> Result<Self> {
+        let len = DATA_OFFSET + SLOT_COUNT * slot_size;
+        let fd = match shm_open(object, libc::O_CREAT | libc::O_EXCL | libc::O_RDWR) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                if let Ok(existing) = Segment::open(object) {
+                    if !existing.is_closed() && existing.owner_alive() {
+                        return Err(IoError::SharedMemoryError(format!(
+                            "A server named '{}' is already running (pid {})",
+                            existing.server_name(),
+                            existing.pid()
+                        )));
+                    }
+                }
+                tracing::debug!("Replacing stale shared memory segment {:?}", object);
+                unsafe { libc::shm_unlink(object.as_ptr()) };
+                shm_open(object, libc::O_CREAT | libc::O_EXCL | libc::O_RDWR)?
+            }
+            result => result?,
+        };
+
+        let mapped = unsafe {
+            if libc::ftruncate(fd, len as libc::off_t) != 0 {
+                let e = io::Error::last_os_error();
+                libc::close(fd);
+                libc::shm_unlink(object.as_ptr());
+                return Err(e.into());
+            }
+            map(fd, len, libc::PROT_READ | libc::PROT_WRITE)
+        };
+        let ptr = match mapped {
+            Ok(ptr) => ptr,
+            Err(e) => {
+                unsafe { libc::shm_unlink(object.as_ptr()) };
+                return Err(e);
+            }
+        };
+
+        let segment = Self {
+            ptr,
+            len,
+            object: object.clone(),
+            owner: true,
+        };
+        // Fresh segments are zero-filled, so only the non-zero fields are set
+        unsafe {
+            segment.write_u32(VERSION_OFFSET, VERSION);
+            segment.write_u32(DATA_OFFSET_OFFSET, DATA_OFFSET as u32);
+            segment.write_u32(SLOT_COUNT_OFFSET, SLOT_COUNT as u32);
+            segment.write_u64(SLOT_SIZE_OFFSET, slot_size as u64);
+            segment.write_u32(PID_OFFSET, std::process::id());
+            let name = truncate(name, NAME_LEN);
+            ptr::copy_nonoverlapping(name.as_ptr(), ptr.add(NAME_OFFSET), name.len());
+        }
+        segment.set_format(format);
+        // Published last, readers ignore segments without it
+        segment
+            .atomic_u32(MAGIC_OFFSET)
+            .store(MAGIC, Ordering::Release);
+        Ok(segment)
+    }
+
+    /// Maps the existing segment `object` read-only.
+    pub(super) fn open(object: &CString) -> Result<Self> {
+        let fd = shm_open(object, libc::O_RDONLY).map_err(|e| {
+            if e.kind() == io::ErrorKind::NotFound {
+                IoError::SharedMemoryNotFound(display_name(object))
+            } else {
+                e.into()
+            }
+        })?;
+
+        let len = unsafe {
+            let mut stat: libc::stat = std::mem::zeroed();
+            if libc::fstat(fd, &mut stat) != 0 {
+                let e = io::Error::last_os_error();
+                libc::close(fd);
+                return Err(e.into());
+            }
+            stat.st_size as usize
+        };
+        if len < DATA_OFFSET {
+            unsafe { libc::close(fd) };
+            return Err(IoError::SharedMemoryError(format!(
+                "Segment {} is too small ({} bytes)",
+                display_name(object),
+                len
+            )));
+        }
+
+        let ptr = unsafe { map(fd, len, libc::PROT_READ)? };
+        let segment = Self {
+            ptr,
+            len,
+            object: object.clone(),
+            owner: false,
+        };
+
+        let magic = segment.atomic_u32(MAGIC_OFFSET).load(Ordering::Acquire);
+        if magic != MAGIC {
+            return Err(IoError::SharedMemoryError(format!(
+                "Segment {} is not a frame sharing segment",
+                display_name(object)
+            )));
+        }
+        let version = unsafe { segment.read_u32(VERSION_OFFSET) };
+        if version != VERSION {
+            return Err(IoError::SharedMemoryError(format!(
+                "Segment {} uses protocol version {}, expected {}",
+                display_name(object),
+                version,
+                VERSION
+            )));
+        }
+        let data_offset = unsafe { segment.read_u32(DATA_OFFSET_OFFSET) } as usize;
+        let slot_count = unsafe { segment.read_u32(SLOT_COUNT_OFFSET) } as usize;
+        let required = (segment.slot_size() as u64)
+            .checked_mul(slot_count as u64)
+            .and_then(|slots| slots.checked_add(data_offset as u64));
+        if data_offset != DATA_OFFSET
+            || slot_count != SLOT_COUNT
+            || required.map_or(true, |required| required > len as u64)
+        {
+            return Err(IoError::SharedMemoryError(format!(
+                "Segment {} has an invalid layout",
+                display_name(object)
+            )));
+        }
+        Ok(segment)
+    }
+
+    /// Returns the name the server registered with.
+    pub(super) fn server_name(&self) -> String {
+        let bytes = unsafe { std::slice::from_raw_parts(self.ptr.add(NAME_OFFSET), NAME_LEN) };
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(NAME_LEN);
+        String::from_utf8_lossy(&bytes[..end]).into_owned()
+    }
+
+    /// Returns the process id of the server.
+    pub(super) fn pid(&self) -> u32 {
+        unsafe { self.read_u32(PID_OFFSET) }
+    }
+
+    /// Returns true if the server process still exists.
+    pub(super) fn owner_alive(&self) -> bool {
+        let Ok(pid) = libc::pid_t::try_from(self.pid()) else {
+            return false;
+        };
+        // Signal 0 only checks for existence, EPERM means it belongs to another user
+        pid > 0
+            && (unsafe { libc::kill(pid, 0) } == 0
+                || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM))
+    }
+
+    /// Returns the bytes reserved for each frame.
+    pub(super) fn slot_size(&self) -> usize {
+        unsafe { self.read_u64(SLOT_SIZE_OFFSET) as usize }
+    }
+
+    /// Returns true once the server closed or replaced the segment.
+    pub(super) fn is_closed(&self) -> bool {
+        self.atomic_u32(FLAGS_OFFSET).load(Ordering::Acquire) & FLAG_CLOSED != 0
+    }
+
+    /// Returns the number of the newest complete frame, 0 before the first.
+    ///
+    /// Returns `None` while the server is updating it.
+    pub(super) fn latest(&self) -> Option<u64> {
+        let sequence = self.atomic_u32(LATEST_SEQUENCE_OFFSET);
+        let before = sequence.load(Ordering::Acquire);
+        if before & 1 != 0 {
+            return None;
+        }
+        let low = self.atomic_u32(LATEST_OFFSET).load(Ordering::Relaxed);
+        let high = self.atomic_u32(LATEST_HIGH_OFFSET).load(Ordering::Relaxed);
+        fence(Ordering::Acquire);
+        (sequence.load(Ordering::Relaxed) == before)
+            .then_some((u64::from(high) << 32) | u64::from(low))
+    }
+
+    /// Publishes `frame_number` as the newest complete frame.
+    fn set_latest(&self, frame_number: u64) {
+        let sequence = self.atomic_u32(LATEST_SEQUENCE_OFFSET);
+        let start = sequence.load(Ordering::Relaxed);
+        sequence.store(start.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.atomic_u32(LATEST_OFFSET)
+            .store(frame_number as u32, Ordering::Relaxed);
+        self.atomic_u32(LATEST_HIGH_OFFSET)
+            .store((frame_number >> 32) as u32, Ordering::Relaxed);
+        sequence.store(start.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Returns the format advertised in the header.
+    ///
+    /// Returns `None` if the pixel format code is unknown.
+    pub(super) fn format(&self) -> Option<VideoFormat> {
+        let pixel_format =
+            pixel_format_from_code(self.atomic_u32(PIXEL_FORMAT_OFFSET).load(Ordering::Relaxed))?;
+        Some(VideoFormat::new(
+            self.atomic_u32(WIDTH_OFFSET).load(Ordering::Relaxed),
+            self.atomic_u32(HEIGHT_OFFSET).load(Ordering::Relaxed),
+            pixel_format,
+            f32::from_bits(self.atomic_u32(FRAME_RATE_OFFSET).load(Ordering::Relaxed)),
+        ))
+    }
+
+    /// Advertises `format` in the header.
+    pub(super) fn set_format(&self, format: &VideoFormat) {
+        self.atomic_u32(WIDTH_OFFSET)
+            .store(format.width, Ordering::Relaxed);
+        self.atomic_u32(HEIGHT_OFFSET)
+            .store(format.height, Ordering::Relaxed);
+        self.atomic_u32(PIXEL_FORMAT_OFFSET)
+            .store(pixel_format_code(format.pixel_format), Ordering::Relaxed);
+        self.atomic_u32(FRAME_RATE_OFFSET)
+            .store(format.frame_rate.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Marks the segment closed so clients stop reading and reconnect.
+    pub(super) fn close(&self) {
+        self.atomic_u32(FLAGS_OFFSET)
+            .fetch_or(FLAG_CLOSED, Ordering::Release);
+    }
+
+    /// Writes `frame` as frame number `frame_number` and publishes it.
+    ///
+    /// The frame must fit into a slot.
+    pub(super) fn write_frame(&self, frame_number: u64, frame: &VideoFrame) {
+        debug_assert!(self.owner && frame.data.len() <= self.slot_size());
+        let (descriptor, data) = self.slot(frame_number);
+        let sequence = self.atomic_u32(descriptor + SLOT_SEQUENCE);
+        let start = sequence.load(Ordering::Relaxed);
+
+        // Odd while the slot is being rewritten
+        sequence.store(start.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+        unsafe {
+            self.write_u64(descriptor + SLOT_FRAME_NUMBER, frame_number);
+            self.write_u64(
+                descriptor + SLOT_TIMESTAMP,
+                frame.timestamp.as_nanos() as u64,
+            );
+            self.write_u32(descriptor + SLOT_WIDTH, frame.format.width);
+            self.write_u32(descriptor + SLOT_HEIGHT, frame.format.height);
+            self.write_u32(
+                descriptor + SLOT_PIXEL_FORMAT,
+                pixel_format_code(frame.format.pixel_format),
+            );
+            self.write_u64(descriptor + SLOT_LENGTH, frame.data.len() as u64);
+            ptr::copy_nonoverlapping(frame.data.as_ptr(), self.ptr.add(data), frame.data.len());
+        }
+        sequence.store(start.wrapping_add(2), Ordering::Release);
+        self.set_latest(frame_number);
+    }
+
+    /// Copies frame number `frame_number` out of its slot.
+    ///
+    /// Returns `Ok(None)` if the slot is being rewritten or already holds a
+    /// newer frame, in which case the caller should retry with the latest
+    /// frame number.
+    pub(super) fn read_frame(&self, frame_number: u64) -> Result<Option<VideoFrame>> {
+        let (descriptor, data) = self.slot(frame_number);
+        let sequence = self.atomic_u32(descriptor + SLOT_SEQUENCE);
+
+        let before = sequence.load(Ordering::Acquire);
+        if before & 1 != 0 {
+            return Ok(None);
+        }
+        let (number, timestamp, width, height, code, length) = unsafe {
+            (
+                self.read_u64(descriptor + SLOT_FRAME_NUMBER),
+                self.read_u64(descriptor + SLOT_TIMESTAMP),
+                self.read_u32(descriptor + SLOT_WIDTH),
+                self.read_u32(descriptor + SLOT_HEIGHT),
+                self.read_u32(descriptor + SLOT_PIXEL_FORMAT),
+                self.read_u64(descriptor + SLOT_LENGTH) as usize,
+            )
+        };
+        if number != frame_number || length > self.slot_size() {
+            return Ok(None);
+        }
+        let mut pixels = vec![0; length];
+        unsafe { ptr::copy_nonoverlapping(self.ptr.add(data), pixels.as_mut_ptr(), length) };
+        fence(Ordering::Acquire);
+        if sequence.load(Ordering::Relaxed) != before {
+            return Ok(None);
+        }
+
+        let pixel_format = pixel_format_from_code(code).ok_or_else(|| {
+            IoError::UnsupportedPixelFormat(format!("shared memory pixel format code {}", code))
+        })?;
+        let frame_rate = f32::from_bits(self.atomic_u32(FRAME_RATE_OFFSET).load(Ordering::Relaxed));
+        let format = VideoFormat::new(width, height, pixel_format, frame_rate);
+        let frame = VideoFrame::with_metadata(
+            pixels,
+            format,
+            Duration::from_nanos(timestamp),
+            FrameMetadata::with_source(self.server_name()).with_frame_number(number),
+        );
+        frame.validate()?;
+        Ok(Some(frame))
+    }
+
+    /// Returns the descriptor and pixel data offsets for `frame_number`.
+    fn slot(&self, frame_number: u64) -> (usize, usize) {
+        let index = (frame_number % SLOT_COUNT as u64) as usize;
+        (
+            SLOTS_OFFSET + index * SLOT_DESCRIPTOR_SIZE,
+            DATA_OFFSET + index * self.slot_size(),
+        )
+    }
+
+    fn atomic_u32(&self, offset: usize) -> &AtomicU32 {
+        debug_assert!(offset % 4 == 0 && offset + 4 <= self.len);
+        unsafe { &*(self.ptr.add(offset) as *const AtomicU32) }
+    }
+
+    unsafe fn read_u32(&self, offset: usize) -> u32 {
+        ptr::read_volatile(self.ptr.add(offset) as *const u32)
+    }
+
+    unsafe fn read_u64(&self, offset: usize) -> u64 {
+        ptr::read_volatile(self.ptr.add(offset) as *const u64)
+    }
+
+    unsafe fn write_u32(&self, offset: usize, value: u32) {
+        ptr::write_volatile(self.ptr.add(offset) as *mut u32, value);
+    }
+
+    unsafe fn write_u64(&self, offset: usize, value: u64) {
+        ptr::write_volatile(self.ptr.add(offset) as *mut u64, value);
+    }
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        if self.owner {
+            self.close();
+            unsafe { libc::shm_unlink(self.object.as_ptr()) };
+        }
+        unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
+    }
+}
+
+/// Opens a shared-memory object with mode 0644.
+fn shm_open(object: &CString, flags: libc::c_int) -> io::Result<libc::c_int> {
+    let fd = unsafe { libc::shm_open(object.as_ptr(), flags, 0o644 as libc::mode_t) };
+    if fd < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(fd)
+    }
+}
+
+/// Maps `len` bytes of `fd` shared and closes the descriptor.
+unsafe fn map(fd: libc::c_int, len: usize, protection: libc::c_int) -> Result<*mut u8> {
+    let ptr = libc::mmap(ptr::null_mut(), len, protection, libc::MAP_SHARED, fd, 0);
+    let error = io::Error::last_os_error();
+    libc::close(fd);
+    if ptr == libc::MAP_FAILED {
+        return Err(IoError::SharedMemoryError(format!(
+            "mmap failed: {}",
+            error
+        )));
+    }
+    Ok(ptr as *mut u8)
+}
+
+/// Returns `object` without its leading slash, for error messages.
+fn display_name(object: &CString) -> String {
+    object.to_string_lossy().trim_start_matches('/').to_string()
+}
+
+/// Truncates `name` to at most `max` bytes on a character boundary.
+fn truncate(name: &str, max: usize) -> &str {
+    let mut end = name.len().min(max);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    &name[..end]
+}
@@ -0,0 +1,140 @@
+//! Shared-memory frame server.
+
+use super::object_name;
+use super::segment::Segment;
+use crate::error::{IoError, Result};
+use crate::format::{VideoFormat, VideoFrame};
+use crate::sink::{SinkStatistics, VideoSink};
+use std::ffi::CString;
+
+/// Publishes frames to shared memory under a name that clients on the same
+/// machine can discover with [`list_servers`](super::list_servers).
+///
+/// Frames are copied into the next of three slots and published without
+/// waiting for clients, so a slow or crashed client never stalls output.
+/// A frame larger than the current slots replaces the segment with a bigger
+/// one, connected clients follow automatically.
+pub struct ShmServer {
+    name: String,
+    object: CString,
+    format: VideoFormat,
+    segment: Option<Segment>,
+    frame_count: u64,
+}
+
+impl ShmServer {
+    /// Creates a server named `name` sized for frames of `format`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::InvalidParameter` for an empty name and
+    /// `IoError::SharedMemoryError` if another running server already uses
+    /// the name or the segment cannot be created.
+    pub fn new(name: impl Into<String>, format: VideoFormat) -> Result<Self> {
+        let name = name.into();
+        let object = object_name(&name)?;
+        let segment = Segment::create(&object, &name, &format, format.buffer_size())?;
+        tracing::info!(
+            "Shared memory server '{}' started ({}x{} {})",
+            name,
+            format.width,
+            format.height,
+            format.pixel_format
+        );
+        Ok(Self {
+            name,
+            object,
+            format,
+            segment: Some(segment),
+            frame_count: 0,
+        })
+    }
+
+    /// Returns the shared-memory object name, e.g. `/mapflow.Main_Output`.
+    pub fn object_name(&self) -> &str {
+        self.object.to_str().unwrap_or_default()
+    }
+
+    /// Replaces the segment with one whose slots hold `slot_size` bytes.
+    fn resize(&mut self, slot_size: usize) -> Result<()> {
+        tracing::debug!(
+            "Growing shared memory server '{}' to {} bytes per frame",
+            self.name,
+            slot_size
+        );
+        // Dropping marks the old segment closed and unlinks it first
+        self.segment = None;
+        let segment = Segment::create(&self.object, &self.name, &self.format, slot_size)?;
+        self.segment = Some(segment);
+        Ok(())
+    }
+}
+
+impl VideoSink for ShmServer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn format(&self) -> VideoFormat {
+        self.format.clone()
+    }
+
+    fn send_frame(&mut self, frame: &VideoFrame) -> Result<()> {
+        frame.validate()?;
+        if frame.format != self.format {
+            self.format = frame.format.clone();
+        }
+
+        let size = frame.data.len();
+        let fits = self
+            .segment
+            .as_ref()
+            .is_some_and(|segment| segment.slot_size() >= size);
+        if !fits {
+            self.resize(size)?;
+        }
+        let Some(segment) = self.segment.as_ref() else {
+            return Err(IoError::SharedMemoryError("Server is closed".to_string()));
+        };
+        segment.set_format(&frame.format);
+
+        let frame_number = self.frame_count + 1;
+        segment.write_frame(frame_number, frame);
+        self.frame_count = frame_number;
+        Ok(())
+    }
+
+    fn is_available(&self) -> bool {
+        self.segment.is_some()
+    }
+
+    fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    fn reconnect(&mut self) -> Result<()> {
+        if self.segment.is_none() {
+            let slot_size = self.format.buffer_size();
+            self.resize(slot_size)?;
+        }
+        Ok(())
+    }
+
+    fn statistics(&self) -> Option<SinkStatistics> {
+        Some(SinkStatistics {
+            frames_sent: self.frame_count,
+            ..SinkStatistics::default()
+        })
+    }
+}
+
+impl std::fmt::Debug for ShmServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShmServer")
+            .field("name", &self.name)
+            .field("object", &self.object)
+            .field("format", &self.format)
+            .field("frame_count", &self.frame_count)
+            .finish()
+    }
+}
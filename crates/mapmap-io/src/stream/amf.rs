@@ -0,0 +1,286 @@
+//! AMF0 encoding for RTMP commands and FLV metadata.
+//!
+//! Only the value types used by RTMP publishing are supported: numbers,
+//! booleans, strings, objects, ECMA arrays, null and undefined.
+
+use crate::error::{IoError, Result};
+
+const NUMBER: u8 = 0x00;
+const BOOLEAN: u8 = 0x01;
+const STRING: u8 = 0x02;
+const OBJECT: u8 = 0x03;
+const NULL: u8 = 0x05;
+const UNDEFINED: u8 = 0x06;
+const ECMA_ARRAY: u8 = 0x08;
+const OBJECT_END: u8 = 0x09;
+const LONG_STRING: u8 = 0x0C;
+
+/// An AMF0 value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Amf0Value {
+    /// 64-bit float
+    Number(f64),
+    /// Boolean
+    Boolean(bool),
+    /// UTF-8 string
+    String(String),
+    /// Anonymous object with ordered properties
+    Object(Vec<(String, Amf0Value)>),
+    /// Associative array with ordered properties
+    EcmaArray(Vec<(String, Amf0Value)>),
+    /// Null
+    Null,
+    /// Undefined
+    Undefined,
+}
+
+impl Amf0Value {
+    /// Creates an object from `(key, value)` pairs.
+    pub fn object<K: Into<String>>(properties: impl IntoIterator<Item = (K, Amf0Value)>) -> Self {
+        Amf0Value::Object(properties.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    /// Creates a string value.
+    pub fn string(value: impl Into<String>) -> Self {
+        Amf0Value::String(value.into())
+    }
+
+    /// Returns the number, if this is a number.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Amf0Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the string, if this is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Amf0Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Looks up a property of an object or ECMA array.
+    pub fn get(&self, key: &str) -> Option<&Amf0Value> {
+        match self {
+            Amf0Value::Object(props) | Amf0Value::EcmaArray(props) => {
+                props.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+            }
+            _ => None,
+        }
+    }
+
+    /// Appends the encoded value to `out`.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Amf0Value::Number(n) => {
+                out.push(NUMBER);
+                out.extend_from_slice(&n.to_be_bytes());
+            }
+            Amf0Value::Boolean(b) => {
+                out.push(BOOLEAN);
+                out.push(*b as u8);
+            }
+            Amf0Value::String(s) => {
+                if s.len() > u16::MAX as usize {
+                    out.push(LONG_STRING);
+                    out.extend_from_slice(&(s.len() as u32).to_be_bytes());
+                    out.extend_from_slice(s.as_bytes());
+                } else {
+                    out.push(STRING);
+                    encode_key(s, out);
+                }
+            }
+            Amf0Value::Object(props) => {
+                out.push(OBJECT);
+                encode_properties(props, out);
+            }
+            Amf0Value::EcmaArray(props) => {
+                out.push(ECMA_ARRAY);
+                out.extend_from_slice(&(props.len() as u32).to_be_bytes());
+                encode_properties(props, out);
+            }
+            Amf0Value::Null => out.push(NULL),
+            Amf0Value::Undefined => out.push(UNDEFINED),
+        }
+    }
+
+    /// Decodes one value from the start of `data`.
+    ///
+    /// Returns the value and the number of bytes consumed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::RtmpError` if the data is truncated or uses an
+    /// unsupported type.
+    pub fn decode(data: &[u8]) -> Result<(Self, usize)> {
+        let mut reader = Reader { data, pos: 0 };
+        let value = reader.value()?;
+        Ok((value, reader.pos))
+    }
+}
+
+/// Encodes a sequence of values, as used by RTMP command messages.
+pub fn encode_all(values: &[Amf0Value]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for value in values {
+        value.encode(&mut out);
+    }
+    out
+}
+
+/// Decodes values until `data` is exhausted.
+///
+/// # Errors
+///
+/// Returns `IoError::RtmpError` if any value is malformed.
+pub fn decode_all(data: &[u8]) -> Result<Vec<Amf0Value>> {
+    let mut values = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let (value, used) = Amf0Value::decode(&data[pos..])?;
+        values.push(value);
+        pos += used;
+    }
+    Ok(values)
+}
+
+fn encode_key(key: &str, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(key.len() as u16).to_be_bytes());
+    out.extend_from_slice(key.as_bytes());
+}
+
+fn encode_properties(props: &[(String, Amf0Value)], out: &mut Vec<u8>) {
+    for (key, value) in props {
+        encode_key(key, out);
+        value.encode(out);
+    }
+    out.extend_from_slice(&[0, 0, OBJECT_END]);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8]> {
+        let end = self.pos + len;
+        let bytes = self
+            .data
+            .get(self.pos..end)
+            .ok_or_else(|| IoError::RtmpError("Truncated AMF0 value".to_string()))?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn string(&mut self, len: usize) -> Result<String> {
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| IoError::RtmpError("Invalid UTF-8 in AMF0 string".to_string()))
+    }
+
+    fn u16(&mut self) -> Result<usize> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]) as usize)
+    }
+
+    fn u32(&mut self) -> Result<usize> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    }
+
+    fn properties(&mut self) -> Result<Vec<(String, Amf0Value)>> {
+        let mut props = Vec::new();
+        loop {
+            let len = self.u16()?;
+            if len == 0 && self.data.get(self.pos) == Some(&OBJECT_END) {
+                self.pos += 1;
+                return Ok(props);
+            }
+            let key = self.string(len)?;
+            props.push((key, self.value()?));
+        }
+    }
+
+    fn value(&mut self) -> Result<Amf0Value> {
+        let marker = self.take(1)?[0];
+        Ok(match marker {
+            NUMBER => {
+                let b = self.take(8)?;
+                Amf0Value::Number(f64::from_be_bytes(b.try_into().expect("8 bytes")))
+            }
+            BOOLEAN => Amf0Value::Boolean(self.take(1)?[0] != 0),
+            STRING => {
+                let len = self.u16()?;
+                Amf0Value::String(self.string(len)?)
+            }
+            LONG_STRING => {
+                let len = self.u32()?;
+                Amf0Value::String(self.string(len)?)
+            }
+            OBJECT => Amf0Value::Object(self.properties()?),
+            ECMA_ARRAY => {
+                self.u32()?;
+                Amf0Value::EcmaArray(self.properties()?)
+            }
+            NULL => Amf0Value::Null,
+            UNDEFINED => Amf0Value::Undefined,
+            other => {
+                return Err(IoError::RtmpError(format!(
+                    "Unsupported AMF0 type 0x{:02x}",
+                    other
+                )))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_connect_command() {
+        let bytes = encode_all(&[
+            Amf0Value::string("connect"),
+            Amf0Value::Number(1.0),
+            Amf0Value::object([("app", Amf0Value::string("live"))]),
+        ]);
+
+        let expected: &[u8] = &[
+            0x02, 0x00, 0x07, b'c', b'o', b'n', b'n', b'e', b'c', b't', // "connect"
+            0x00, 0x3F, 0xF0, 0, 0, 0, 0, 0, 0, // 1.0
+            0x03, 0x00, 0x03, b'a', b'p', b'p', 0x02, 0x00, 0x04, b'l', b'i', b'v', b'e', 0x00,
+            0x00, 0x09,
+        ];
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let values = vec![
+            Amf0Value::string("_result"),
+            Amf0Value::Number(2.0),
+            Amf0Value::Null,
+            Amf0Value::EcmaArray(vec![
+                ("width".to_string(), Amf0Value::Number(1920.0)),
+                ("stereo".to_string(), Amf0Value::Boolean(true)),
+                (
+                    "nested".to_string(),
+                    Amf0Value::object([("a", Amf0Value::Undefined)]),
+                ),
+            ]),
+        ];
+        let decoded = decode_all(&encode_all(&values)).unwrap();
+        assert_eq!(decoded, values);
+        assert_eq!(
+            decoded[3].get("width").and_then(Amf0Value::as_number),
+            Some(1920.0)
+        );
+
+        assert!(decode_all(&[0x02, 0x00, 0x05, b'a']).is_err());
+        assert!(decode_all(&[0x11]).is_err());
+    }
+}
@@ -0,0 +1,549 @@
+//! Audio track encoding for streams.
+//!
+//! Samples from the active audio input are remixed and resampled to the
+//! stream's layout, cut into fixed-size frames and encoded as AAC (requires
+//! the `stream` feature) or 16-bit PCM.
+
+use crate::error::{IoError, Result};
+#[cfg(feature = "stream")]
+use ffmpeg_next as ffmpeg;
+use std::time::Duration;
+
+/// Samples per channel in one encoded audio frame.
+pub const SAMPLES_PER_FRAME: usize = 1024;
+
+/// AAC sampling frequency table, indexed by the MPEG-4 frequency index.
+const AAC_SAMPLE_RATES: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+/// Audio codec of a stream's audio track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AudioCodec {
+    /// AAC-LC (requires the `stream` feature)
+    #[default]
+    Aac,
+    /// Uncompressed 16-bit little-endian PCM (RTMP only)
+    Pcm,
+}
+
+/// A block of interleaved audio samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioFrame {
+    /// Interleaved samples in -1.0..=1.0
+    pub samples: Vec<f32>,
+    /// Sample rate in Hz
+    pub sample_rate: u32,
+    /// Number of interleaved channels
+    pub channels: u16,
+    /// Stream time of the first sample
+    pub timestamp: Duration,
+}
+
+impl AudioFrame {
+    /// Creates an audio frame.
+    pub fn new(samples: Vec<f32>, sample_rate: u32, channels: u16, timestamp: Duration) -> Self {
+        Self {
+            samples,
+            sample_rate,
+            channels,
+            timestamp,
+        }
+    }
+
+    /// Returns the number of samples per channel.
+    pub fn sample_count(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    /// Returns the duration of the frame.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.sample_count() as f64 / self.sample_rate.max(1) as f64)
+    }
+}
+
+/// One encoded audio frame.
+#[derive(Debug, Clone)]
+pub struct EncodedAudio {
+    /// Encoded data (raw AAC access unit or PCM samples)
+    pub data: Vec<u8>,
+    /// Stream time of the first sample
+    pub pts: Duration,
+}
+
+/// Encodes audio from the active input into a stream's audio track.
+pub struct AudioEncoder {
+    codec: AudioCodec,
+    sample_rate: u32,
+    channels: u16,
+    bitrate: u64,
+    pending: Vec<f32>,
+    samples_encoded: u64,
+    start: Option<Duration>,
+    // Resampler state: last input sample per channel and the read position
+    history: Vec<f32>,
+    phase: f64,
+    #[cfg(feature = "stream")]
+    aac: Option<AacEncoder>,
+}
+
+impl AudioEncoder {
+    /// Creates an audio encoder.
+    ///
+    /// # Parameters
+    ///
+    /// - `codec` - Output codec
+    /// - `sample_rate` - Output sample rate in Hz
+    /// - `channels` - Output channel count (1 or 2)
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::InvalidParameter` if the codec does not support the
+    /// sample rate or channel count, and a feature error for AAC without the
+    /// `stream` feature.
+    pub fn new(codec: AudioCodec, sample_rate: u32, channels: u16) -> Result<Self> {
+        if !(1..=2).contains(&channels) {
+            return Err(IoError::InvalidParameter(format!(
+                "Audio tracks support 1 or 2 channels, got {}",
+                channels
+            )));
+        }
+        match codec {
+            AudioCodec::Aac => {
+                if aac_frequency_index(sample_rate).is_none() {
+                    return Err(IoError::InvalidParameter(format!(
+                        "AAC does not support {} Hz",
+                        sample_rate
+                    )));
+                }
+                if !cfg!(feature = "stream") {
+                    return Err(IoError::feature_not_enabled("AAC encoding", "stream"));
+                }
+            }
+            AudioCodec::Pcm => {
+                if !matches!(sample_rate, 5512 | 11025 | 22050 | 44100) {
+                    return Err(IoError::InvalidParameter(format!(
+                        "PCM audio in FLV does not support {} Hz",
+                        sample_rate
+                    )));
+                }
+            }
+        }
+
+        tracing::info!(
+            "Creating audio encoder: codec={:?}, {} Hz, {} channels",
+            codec,
+            sample_rate,
+            channels
+        );
+
+        Ok(Self {
+            codec,
+            sample_rate,
+            channels,
+            bitrate: 128_000,
+            pending: Vec::new(),
+            samples_encoded: 0,
+            start: None,
+            history: Vec::new(),
+            phase: 0.0,
+            #[cfg(feature = "stream")]
+            aac: None,
+        })
+    }
+
+    /// Sets the target bitrate for AAC.
+    pub fn with_bitrate(mut self, bitrate: u64) -> Self {
+        self.bitrate = bitrate;
+        self
+    }
+
+    /// Returns the codec.
+    pub fn codec(&self) -> AudioCodec {
+        self.codec
+    }
+
+    /// Returns the output sample rate.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Returns the output channel count.
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Returns the target bitrate.
+    pub fn bitrate(&self) -> u64 {
+        match self.codec {
+            AudioCodec::Aac => self.bitrate,
+            AudioCodec::Pcm => self.sample_rate as u64 * self.channels as u64 * 16,
+        }
+    }
+
+    /// Returns the MPEG-4 AudioSpecificConfig for AAC.
+    pub fn audio_specific_config(&self) -> Option<[u8; 2]> {
+        match self.codec {
+            AudioCodec::Aac => aac_audio_specific_config(self.sample_rate, self.channels),
+            AudioCodec::Pcm => None,
+        }
+    }
+
+    /// Encodes input samples, returning every complete frame.
+    ///
+    /// Input in any layout is remixed to the output channel count and
+    /// linearly resampled to the output sample rate. Samples that do not
+    /// fill a frame are kept for the next call. The AAC encoder delays its
+    /// output by a frame, and its first access unit starts slightly before
+    /// the first input sample.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::InvalidParameter` for an empty channel layout, and
+    /// an encoder error if FFmpeg's AAC encoder fails.
+    pub fn encode(&mut self, frame: &AudioFrame) -> Result<Vec<EncodedAudio>> {
+        if frame.channels == 0 || frame.sample_rate == 0 {
+            return Err(IoError::InvalidParameter(
+                "Audio frame has no channels or sample rate".to_string(),
+            ));
+        }
+        if self.start.is_none() {
+            self.start = Some(frame.timestamp);
+        }
+
+        let remixed = remix(&frame.samples, frame.channels, self.channels);
+        let resampled = self.resample(&remixed, frame.sample_rate);
+        self.pending.extend(resampled);
+
+        let frame_len = SAMPLES_PER_FRAME * self.channels as usize;
+        let mut packets = Vec::new();
+        while self.pending.len() >= frame_len {
+            let samples: Vec<f32> = self.pending.drain(..frame_len).collect();
+            let position = self.samples_encoded as i64;
+            self.samples_encoded += SAMPLES_PER_FRAME as u64;
+            match self.codec {
+                AudioCodec::Pcm => packets.push(EncodedAudio {
+                    data: samples
+                        .iter()
+                        .flat_map(|&s| {
+                            ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes()
+                        })
+                        .collect(),
+                    pts: self.time_of(position),
+                }),
+                AudioCodec::Aac => {
+                    for (data, pts) in self.encode_aac(&samples, position)? {
+                        packets.push(EncodedAudio {
+                            data,
+                            pts: self.time_of(pts),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(packets)
+    }
+
+    /// Returns the stream time of a sample position, which may be negative
+    /// for encoder priming.
+    fn time_of(&self, position: i64) -> Duration {
+        let start = self.start.unwrap_or_default();
+        let offset =
+            Duration::from_secs_f64(position.unsigned_abs() as f64 / self.sample_rate as f64);
+        if position < 0 {
+            start.saturating_sub(offset)
+        } else {
+            start + offset
+        }
+    }
+
+    /// Encodes one frame of interleaved samples, returning the access units
+    /// the encoder produced with their sample positions.
+    #[cfg(feature = "stream")]
+    fn encode_aac(&mut self, samples: &[f32], position: i64) -> Result<Vec<(Vec<u8>, i64)>> {
+        if self.aac.is_none() {
+            self.aac = Some(AacEncoder::new(
+                self.sample_rate,
+                self.channels,
+                self.bitrate,
+            )?);
+        }
+        let (sample_rate, channels) = (self.sample_rate, self.channels);
+        match self.aac.as_mut() {
+            Some(aac) => aac.encode(samples, sample_rate, channels, position),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    #[cfg(not(feature = "stream"))]
+    fn encode_aac(&mut self, _samples: &[f32], _position: i64) -> Result<Vec<(Vec<u8>, i64)>> {
+        Err(IoError::feature_not_enabled("AAC encoding", "stream"))
+    }
+
+    /// Linear resampling of interleaved samples in the output layout.
+    fn resample(&mut self, input: &[f32], input_rate: u32) -> Vec<f32> {
+        if input_rate == self.sample_rate {
+            return input.to_vec();
+        }
+        let channels = self.channels as usize;
+        let step = input_rate as f64 / self.sample_rate as f64;
+
+        // Continue from the last sample of the previous call
+        let mut ext = std::mem::take(&mut self.history);
+        ext.extend_from_slice(input);
+        let frames = ext.len() / channels;
+        if frames < 2 {
+            self.history = ext;
+            return Vec::new();
+        }
+
+        let mut out = Vec::new();
+        let mut pos = self.phase;
+        while pos + 1.0 < frames as f64 {
+            let i = pos as usize;
+            let t = (pos - i as f64) as f32;
+            for c in 0..channels {
+                let a = ext[i * channels + c];
+                let b = ext[(i + 1) * channels + c];
+                out.push(a + (b - a) * t);
+            }
+            pos += step;
+        }
+
+        self.phase = pos - (frames - 1) as f64;
+        self.history = ext[(frames - 1) * channels..].to_vec();
+        out
+    }
+}
+
+/// FFmpeg's native AAC-LC encoder.
+///
+/// Produces raw access units without ADTS headers; the muxers add the
+/// AudioSpecificConfig or ADTS header themselves.
+#[cfg(feature = "stream")]
+struct AacEncoder {
+    encoder: ffmpeg::encoder::Audio,
+    layout: ffmpeg::ChannelLayout,
+}
+
+#[cfg(feature = "stream")]
+impl AacEncoder {
+    fn new(sample_rate: u32, channels: u16, bitrate: u64) -> Result<Self> {
+        ffmpeg::init().map_err(|e| IoError::EncoderInitFailed(e.to_string()))?;
+        let codec = ffmpeg::encoder::find_by_name("aac").ok_or_else(|| {
+            IoError::EncoderInitFailed("FFmpeg was built without the aac encoder".to_string())
+        })?;
+
+        let layout = if channels == 1 {
+            ffmpeg::ChannelLayout::MONO
+        } else {
+            ffmpeg::ChannelLayout::STEREO
+        };
+        let mut encoder = ffmpeg::codec::Context::new()
+            .encoder()
+            .audio()
+            .map_err(|e| IoError::EncoderInitFailed(e.to_string()))?;
+        encoder.set_rate(sample_rate as i32);
+        encoder.set_channel_layout(layout);
+        encoder.set_channels(i32::from(channels));
+        encoder.set_format(ffmpeg::format::Sample::F32(
+            ffmpeg::format::sample::Type::Planar,
+        ));
+        encoder.set_bit_rate(bitrate as usize);
+        encoder.set_time_base(ffmpeg::Rational(1, sample_rate as i32));
+        let encoder = encoder
+            .open_as(codec)
+            .map_err(|e| IoError::EncoderInitFailed(format!("aac: {}", e)))?;
+
+        Ok(Self { encoder, layout })
+    }
+
+    fn encode(
+        &mut self,
+        samples: &[f32],
+        sample_rate: u32,
+        channels: u16,
+        position: i64,
+    ) -> Result<Vec<(Vec<u8>, i64)>> {
+        let channels = channels as usize;
+        let mut frame = ffmpeg::frame::Audio::new(
+            ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Planar),
+            samples.len() / channels,
+            self.layout,
+        );
+        for channel in 0..channels {
+            let plane = frame.plane_mut::<f32>(channel);
+            for (out, &sample) in plane
+                .iter_mut()
+                .zip(samples.iter().skip(channel).step_by(channels))
+            {
+                *out = sample;
+            }
+        }
+        frame.set_rate(sample_rate);
+        frame.set_pts(Some(position));
+        self.encoder
+            .send_frame(&frame)
+            .map_err(|e| IoError::EncodeFailed(e.to_string()))?;
+
+        let mut units = Vec::new();
+        let mut packet = ffmpeg::Packet::empty();
+        loop {
+            match self.encoder.receive_packet(&mut packet) {
+                Ok(()) => units.push((
+                    packet.data().unwrap_or_default().to_vec(),
+                    packet.pts().unwrap_or(position),
+                )),
+                Err(ffmpeg::Error::Other {
+                    errno: ffmpeg::error::EAGAIN,
+                })
+                | Err(ffmpeg::Error::Eof) => return Ok(units),
+                Err(e) => return Err(IoError::EncodeFailed(e.to_string())),
+            }
+        }
+    }
+}
+
+/// Converts interleaved samples between channel counts.
+fn remix(samples: &[f32], from: u16, to: u16) -> Vec<f32> {
+    if from == to {
+        return samples.to_vec();
+    }
+    let from = from as usize;
+    samples
+        .chunks_exact(from)
+        .flat_map(|frame| {
+            let mixed = if to == 1 {
+                frame.iter().sum::<f32>() / from as f32
+            } else {
+                frame[0]
+            };
+            let right = if to == 2 && from >= 2 {
+                frame[1]
+            } else {
+                mixed
+            };
+            let pair = [mixed, right];
+            pair.into_iter().take(to as usize)
+        })
+        .collect()
+}
+
+/// Returns the MPEG-4 sampling frequency index of a sample rate.
+pub fn aac_frequency_index(sample_rate: u32) -> Option<u8> {
+    AAC_SAMPLE_RATES
+        .iter()
+        .position(|&rate| rate == sample_rate)
+        .map(|i| i as u8)
+}
+
+/// Returns the two-byte AudioSpecificConfig for AAC-LC.
+pub fn aac_audio_specific_config(sample_rate: u32, channels: u16) -> Option<[u8; 2]> {
+    let index = aac_frequency_index(sample_rate)? as u16;
+    let config = (2 << 11) | (index << 7) | ((channels & 0x0F) << 3);
+    Some(config.to_be_bytes())
+}
+
+/// Returns the ADTS header for a raw AAC-LC access unit of `payload_len` bytes.
+pub fn adts_header(sample_rate: u32, channels: u16, payload_len: usize) -> Option<[u8; 7]> {
+    let index = aac_frequency_index(sample_rate)?;
+    let channels = channels as u8;
+    let len = payload_len + 7;
+    Some([
+        0xFF,
+        0xF1,
+        (1 << 6) | (index << 2) | (channels >> 2),
+        ((channels & 3) << 6) | (len >> 11) as u8,
+        (len >> 3) as u8,
+        (((len & 7) as u8) << 5) | 0x1F,
+        0xFC,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aac_headers() {
+        // 44.1 kHz stereo AAC-LC
+        assert_eq!(aac_audio_specific_config(44100, 2), Some([0x12, 0x10]));
+        assert_eq!(aac_audio_specific_config(48000, 1), Some([0x11, 0x88]));
+        assert_eq!(aac_audio_specific_config(44000, 2), None);
+
+        let header = adts_header(44100, 2, 200).unwrap();
+        assert_eq!(header[..3], [0xFF, 0xF1, 0x50]);
+        let len = ((header[3] as usize & 3) << 11)
+            | ((header[4] as usize) << 3)
+            | (header[5] as usize >> 5);
+        assert_eq!(len, 207);
+    }
+
+    #[test]
+    fn test_pcm_frames_from_mono_input() {
+        let mut encoder = AudioEncoder::new(AudioCodec::Pcm, 44100, 2).unwrap();
+        assert_eq!(encoder.bitrate(), 1_411_200);
+
+        // Mono input as delivered by the audio backend
+        let input = AudioFrame::new(vec![0.5; 1500], 44100, 1, Duration::from_secs(2));
+        let packets = encoder.encode(&input).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].pts, Duration::from_secs(2));
+        assert_eq!(packets[0].data.len(), SAMPLES_PER_FRAME * 2 * 2);
+        assert_eq!(packets[0].data[..4], [0xFF, 0x3F, 0xFF, 0x3F]);
+
+        let packets = encoder.encode(&input).unwrap();
+        assert_eq!(packets.len(), 1);
+        let expected = Duration::from_secs(2) + Duration::from_secs_f64(1024.0 / 44100.0);
+        assert_eq!(packets[0].pts, expected);
+    }
+
+    #[test]
+    fn test_resampling() {
+        let mut encoder = AudioEncoder::new(AudioCodec::Pcm, 22050, 1).unwrap();
+        // 4096 samples at 44.1 kHz give 2048 at 22.05 kHz, in pieces
+        let mut total = 0;
+        for i in 0..4 {
+            let samples = (0..1024).map(|s| ((i * 1024 + s) % 2) as f32).collect();
+            let input = AudioFrame::new(samples, 44100, 1, Duration::ZERO);
+            total += encoder.encode(&input).unwrap().len() * SAMPLES_PER_FRAME;
+        }
+        assert_eq!(total + encoder.pending.len(), 2048);
+        // Every output sample lands on an even input sample
+        assert!(encoder.pending.iter().all(|&s| s == 0.0));
+
+        assert!(AudioEncoder::new(AudioCodec::Pcm, 48000, 2).is_err());
+        assert!(AudioEncoder::new(AudioCodec::Pcm, 44100, 6).is_err());
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn test_aac_frames() {
+        let mut encoder = AudioEncoder::new(AudioCodec::Aac, 48000, 2)
+            .unwrap()
+            .with_bitrate(96_000);
+
+        let mut packets = Vec::new();
+        for i in 0..8u32 {
+            let samples = (0..SAMPLES_PER_FRAME * 2)
+                .map(|s| ((s as f32) * 0.05).sin() * 0.5)
+                .collect();
+            let timestamp = Duration::from_secs(1) + Duration::from_millis(u64::from(i) * 21);
+            let input = AudioFrame::new(samples, 48000, 2, timestamp);
+            packets.extend(encoder.encode(&input).unwrap());
+        }
+
+        // One frame of encoder delay at most
+        assert!(packets.len() >= 6);
+        assert!(packets.iter().all(|p| !p.data.is_empty()));
+        assert!(packets.windows(2).all(|w| w[0].pts < w[1].pts));
+        // Priming starts the first access unit one frame early
+        assert!(packets[0].pts <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_remix() {
+        assert_eq!(remix(&[1.0, 0.0, 0.5, 0.5], 2, 1), [0.5, 0.5]);
+        assert_eq!(remix(&[0.25, 0.75], 1, 2), [0.25, 0.25, 0.75, 0.75]);
+    }
+}
@@ -2,13 +2,21 @@
 //!
 //! This module provides video encoding capabilities for streaming using FFmpeg.
 
+#[cfg(feature = "stream")]
+use crate::color::{ColorMatrix, ColorRange, ColorSettings};
+#[cfg(feature = "stream")]
+use crate::converter::FormatConverter;
 #[cfg(feature = "stream")]
 use crate::error::{IoError, Result};
+use crate::format::PixelFormat;
 #[cfg(feature = "stream")]
 use crate::format::{VideoFormat, VideoFrame};
+#[cfg(feature = "stream")]
+use ffmpeg_next as ffmpeg;
+#[cfg(feature = "stream")]
+use std::time::Duration;
 
 /// Video codec enumeration.
-#[cfg(feature = "stream")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VideoCodec {
     /// H.264 (AVC)
@@ -19,9 +27,12 @@ pub enum VideoCodec {
     VP8,
     /// VP9
     VP9,
+    /// Apple ProRes 422 (intra-frame, for editing)
+    ProRes,
+    /// FFV1 (lossless, for archiving)
+    Ffv1,
 }
 
-#[cfg(feature = "stream")]
 impl VideoCodec {
     /// Returns the codec name for FFmpeg.
     pub fn ffmpeg_name(&self) -> &'static str {
@@ -30,12 +41,27 @@ impl VideoCodec {
             VideoCodec::H265 => "libx265",
             VideoCodec::VP8 => "libvpx",
             VideoCodec::VP9 => "libvpx-vp9",
+            VideoCodec::ProRes => "prores_ks",
+            VideoCodec::Ffv1 => "ffv1",
         }
     }
+
+    /// Returns the pixel format frames are converted to before encoding.
+    pub fn input_pixel_format(&self) -> PixelFormat {
+        match self {
+            VideoCodec::ProRes => PixelFormat::YUV422P,
+            VideoCodec::Ffv1 => PixelFormat::BGRA8,
+            _ => PixelFormat::YUV420P,
+        }
+    }
+
+    /// Returns true if every frame is a keyframe.
+    pub fn is_intra_only(&self) -> bool {
+        matches!(self, VideoCodec::ProRes | VideoCodec::Ffv1)
+    }
 }
 
 /// Encoder preset for quality/speed tradeoff.
-#[cfg(feature = "stream")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EncoderPreset {
     /// Ultra fast encoding, lower quality
@@ -56,7 +82,6 @@ pub enum EncoderPreset {
     LowLatency,
 }
 
-#[cfg(feature = "stream")]
 impl EncoderPreset {
     /// Returns the preset name for FFmpeg.
     pub fn ffmpeg_name(&self) -> &'static str {
@@ -73,15 +98,36 @@ impl EncoderPreset {
     }
 }
 
+/// Frames between keyframes when no keyframe is requested
+#[cfg(feature = "stream")]
+const KEYFRAME_INTERVAL: u32 = 60;
+
 /// Video encoder using FFmpeg.
 ///
-/// Encodes raw video frames into compressed video packets suitable for streaming.
+/// Encodes raw video frames into compressed video packets suitable for
+/// streaming and recording. Frames are converted to the codec's input pixel
+/// format (see [`VideoCodec::input_pixel_format`]) with the conventional color
+/// matrix for their height in limited range, and the stream is tagged to
+/// match. Packet timestamps are in milliseconds on the clock of the frame
+/// timestamps.
+///
+/// The FFmpeg encoder is opened with the first frame and closed by
+/// [`VideoEncoder::flush`]; the next frame reopens it, so an encoder can be
+/// reused across reconnects and recording segments.
 #[cfg(feature = "stream")]
 pub struct VideoEncoder {
     codec: VideoCodec,
     format: VideoFormat,
+    input_format: VideoFormat,
     bitrate: u64,
+    preset: EncoderPreset,
+    global_header: bool,
+    color: ColorSettings,
+    converter: FormatConverter,
+    encoder: Option<ffmpeg::encoder::Video>,
     frame_count: u64,
+    last_pts: Option<i64>,
+    force_keyframe: bool,
 }
 
 #[cfg(feature = "stream")]
@@ -91,29 +137,68 @@ impl VideoEncoder {
     /// # Parameters
     ///
     /// - `codec` - The video codec to use
-    /// - `format` - The input video format
-    /// - `bitrate` - Target bitrate in bits per second
-    /// - `_preset` - Encoder preset for quality/speed tradeoff (currently unused)
+    /// - `format` - The format of the frames passed to [`VideoEncoder::encode`]
+    /// - `bitrate` - Target bitrate in bits per second (ignored by ProRes and FFV1)
+    /// - `preset` - Encoder preset for quality/speed tradeoff
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::EncoderInitFailed` if FFmpeg was built without the
+    /// codec, or `IoError::UnsupportedVideoFormat` if the frame size cannot
+    /// be encoded.
     pub fn new(
         codec: VideoCodec,
         format: VideoFormat,
         bitrate: u64,
-        _preset: EncoderPreset,
+        preset: EncoderPreset,
     ) -> Result<Self> {
-        // In a full implementation, this would initialize FFmpeg encoder
+        ffmpeg::init().map_err(|e| IoError::EncoderInitFailed(e.to_string()))?;
+        if ffmpeg::encoder::find_by_name(codec.ffmpeg_name()).is_none() {
+            return Err(IoError::EncoderInitFailed(format!(
+                "FFmpeg was built without the {} encoder",
+                codec.ffmpeg_name()
+            )));
+        }
+
+        // Subsampled chroma needs even dimensions
+        let subsampled = codec.input_pixel_format().is_yuv();
+        if format.width == 0
+            || format.height == 0
+            || (subsampled && (format.width % 2 != 0 || format.height % 2 != 0))
+        {
+            return Err(IoError::UnsupportedVideoFormat {
+                width: format.width,
+                height: format.height,
+                fps: format.frame_rate,
+            });
+        }
+
         tracing::info!(
             "Creating video encoder: codec={:?}, format={}, bitrate={}, preset={:?}",
             codec,
             format,
             bitrate,
-            _preset
+            preset
         );
 
+        let color = ColorSettings::for_height(format.height);
+        let input_format = VideoFormat {
+            pixel_format: codec.input_pixel_format(),
+            ..format.clone()
+        };
         Ok(Self {
             codec,
             format,
+            input_format,
             bitrate,
+            preset,
+            global_header: false,
+            color,
+            converter: FormatConverter::new().with_color_settings(color),
+            encoder: None,
             frame_count: 0,
+            last_pts: None,
+            force_keyframe: false,
         })
     }
 
@@ -137,7 +222,16 @@ impl VideoEncoder {
         )
     }
 
-    /// Encodes a video frame into a compressed packet.
+    /// Stores the codec configuration (e.g. H.264 SPS/PPS) once in the codec
+    /// parameters instead of in-band with every keyframe, as MOV and MP4
+    /// muxers require.
+    pub fn with_global_header(mut self) -> Self {
+        self.global_header = true;
+        self.encoder = None;
+        self
+    }
+
+    /// Encodes a video frame.
     ///
     /// # Parameters
     ///
@@ -145,17 +239,56 @@ impl VideoEncoder {
     ///
     /// # Returns
     ///
-    /// An encoded video packet ready for streaming.
-    pub fn encode(&mut self, frame: &VideoFrame) -> Result<EncodedPacket> {
+    /// The packets the encoder produced. Encoders with lookahead or B-frames
+    /// return nothing for the first frames and catch up on
+    /// [`VideoEncoder::flush`].
+    pub fn encode(&mut self, frame: &VideoFrame) -> Result<Vec<EncodedPacket>> {
         // Validate frame format matches encoder format
-        if frame.format.pixel_format != self.format.pixel_format {
+        if frame.format.pixel_format != self.format.pixel_format
+            || frame.format.width != self.format.width
+            || frame.format.height != self.format.height
+        {
             return Err(IoError::ConversionError(format!(
                 "Frame format {} doesn't match encoder format {}",
-                frame.format.pixel_format, self.format.pixel_format
+                frame.format, self.format
             )));
         }
+        frame.validate()?;
+
+        let converted;
+        let data = if self.format.pixel_format == self.input_format.pixel_format {
+            &frame.data
+        } else {
+            converted = self.converter.convert(frame, &self.input_format)?;
+            &converted.data
+        };
+
+        let mut picture = ffmpeg::frame::Video::new(
+            ffmpeg_pixel_format(self.codec),
+            self.input_format.width,
+            self.input_format.height,
+        );
+        copy_planes(
+            data,
+            &self.input_format,
+            self.codec == VideoCodec::ProRes,
+            &mut picture,
+        );
+        let pts = self.next_pts(frame.timestamp);
+        picture.set_pts(Some(pts));
+        if std::mem::take(&mut self.force_keyframe) {
+            picture.set_kind(ffmpeg::picture::Type::I);
+        }
 
-        // In a full implementation, this would use FFmpeg to encode the frame
+        if self.encoder.is_none() {
+            self.encoder = Some(self.open()?);
+        }
+        let Some(encoder) = self.encoder.as_mut() else {
+            return Err(IoError::EncodeFailed("Encoder unavailable".to_string()));
+        };
+        encoder
+            .send_frame(&picture)
+            .map_err(|e| IoError::EncodeFailed(e.to_string()))?;
         self.frame_count += 1;
 
         tracing::trace!(
@@ -164,21 +297,39 @@ impl VideoEncoder {
             frame.timestamp
         );
 
-        // Return a stub packet
-        Ok(EncodedPacket {
-            data: Vec::new(), // Would contain actual encoded data
-            pts: self.frame_count as i64,
-            dts: self.frame_count as i64,
-            is_keyframe: self.frame_count == 1 || self.frame_count % 60 == 0, // Keyframe on first frame and every 60 frames
-        })
+        let mut packets = Vec::new();
+        receive_packets(encoder, &mut packets)?;
+        Ok(packets)
     }
 
-    /// Flushes any buffered frames from the encoder.
+    /// Flushes any buffered frames from the encoder and closes it.
     ///
-    /// Should be called before closing the encoder to ensure all frames are encoded.
+    /// Should be called before closing the output to ensure all frames are
+    /// encoded. The next frame reopens the encoder, starting with a keyframe.
     pub fn flush(&mut self) -> Result<Vec<EncodedPacket>> {
+        let mut packets = Vec::new();
+        if let Some(mut encoder) = self.encoder.take() {
+            encoder
+                .send_eof()
+                .map_err(|e| IoError::EncodeFailed(e.to_string()))?;
+            receive_packets(&mut encoder, &mut packets)?;
+        }
         tracing::debug!("Flushing encoder, {} frames encoded", self.frame_count);
-        Ok(Vec::new())
+        Ok(packets)
+    }
+
+    /// Returns the codec parameters for a muxer stream, opening the encoder
+    /// if needed.
+    pub(crate) fn codec_parameters(&mut self) -> Result<ffmpeg::codec::Parameters> {
+        if self.encoder.is_none() {
+            self.encoder = Some(self.open()?);
+        }
+        let Some(encoder) = self.encoder.as_ref() else {
+            return Err(IoError::EncoderInitFailed(
+                "Encoder unavailable".to_string(),
+            ));
+        };
+        Ok(ffmpeg::codec::Parameters::from(encoder))
     }
 
     /// Returns the number of frames encoded.
@@ -191,7 +342,7 @@ impl VideoEncoder {
         self.codec
     }
 
-    /// Returns the encoder format.
+    /// Returns the format of the frames the encoder accepts.
     pub fn format(&self) -> &VideoFormat {
         &self.format
     }
@@ -200,6 +351,186 @@ impl VideoEncoder {
     pub fn bitrate(&self) -> u64 {
         self.bitrate
     }
+
+    /// Changes the target bitrate.
+    ///
+    /// libx264 reconfigures its rate control with the next frame; other
+    /// codecs pick the bitrate up when the encoder is next opened.
+    pub fn set_bitrate(&mut self, bitrate: u64) {
+        tracing::debug!("Encoder bitrate {} -> {}", self.bitrate, bitrate);
+        self.bitrate = bitrate;
+        if self.codec.is_intra_only() {
+            return;
+        }
+        if let Some(encoder) = self.encoder.as_mut() {
+            // SAFETY: the context is open and owned by this encoder, and
+            // nothing else reads it between frames. libx264 compares
+            // bit_rate against its rate control before every frame.
+            unsafe {
+                (*encoder.as_mut_ptr()).bit_rate = bitrate as i64;
+            }
+        }
+    }
+
+    /// Makes the next encoded frame a keyframe, e.g. after a reconnect.
+    pub fn request_keyframe(&mut self) {
+        self.force_keyframe = true;
+    }
+
+    /// Returns the pts of a frame in milliseconds.
+    ///
+    /// Encoders reject repeated or decreasing timestamps, so frames that
+    /// arrive with a stale timestamp are placed one frame after the last.
+    fn next_pts(&mut self, timestamp: Duration) -> i64 {
+        let by_clock = timestamp.as_millis() as i64;
+        let pts = match self.last_pts {
+            Some(last) if by_clock <= last => {
+                last + (self.format.frame_duration().as_millis() as i64).max(1)
+            }
+            _ => by_clock,
+        };
+        self.last_pts = Some(pts);
+        pts
+    }
+
+    fn open(&self) -> Result<ffmpeg::encoder::Video> {
+        let codec = ffmpeg::encoder::find_by_name(self.codec.ffmpeg_name()).ok_or_else(|| {
+            IoError::EncoderInitFailed(format!(
+                "FFmpeg was built without the {} encoder",
+                self.codec.ffmpeg_name()
+            ))
+        })?;
+
+        let mut encoder = ffmpeg::codec::Context::new()
+            .encoder()
+            .video()
+            .map_err(|e| IoError::EncoderInitFailed(e.to_string()))?;
+        encoder.set_width(self.input_format.width);
+        encoder.set_height(self.input_format.height);
+        encoder.set_format(ffmpeg_pixel_format(self.codec));
+        encoder.set_time_base(ffmpeg::Rational(1, 1000));
+        encoder.set_frame_rate(Some(ffmpeg::Rational(
+            (self.format.frame_rate * 1000.0).round() as i32,
+            1000,
+        )));
+        encoder.set_gop(KEYFRAME_INTERVAL);
+        if !self.codec.is_intra_only() && self.bitrate > 0 {
+            encoder.set_bit_rate(self.bitrate as usize);
+        }
+        if self.preset == EncoderPreset::LowLatency {
+            encoder.set_max_b_frames(0);
+        }
+        if self.input_format.pixel_format.is_yuv() {
+            encoder.set_colorspace(match self.color.matrix {
+                ColorMatrix::Bt601 => ffmpeg::color::Space::SMPTE170M,
+                ColorMatrix::Bt709 => ffmpeg::color::Space::BT709,
+                ColorMatrix::Bt2020 => ffmpeg::color::Space::BT2020NCL,
+            });
+            encoder.set_color_range(match self.color.range {
+                ColorRange::Limited => ffmpeg::color::Range::MPEG,
+                ColorRange::Full => ffmpeg::color::Range::JPEG,
+            });
+        }
+        if self.global_header {
+            encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+        }
+
+        let mut options = ffmpeg::Dictionary::new();
+        match self.codec {
+            VideoCodec::H264 | VideoCodec::H265 => {
+                options.set("preset", self.preset.ffmpeg_name());
+                if self.preset == EncoderPreset::LowLatency {
+                    options.set("tune", "zerolatency");
+                }
+                // Requested keyframes must be IDR frames so viewers can join
+                options.set("forced-idr", "1");
+            }
+            VideoCodec::VP8 | VideoCodec::VP9 if self.preset == EncoderPreset::LowLatency => {
+                options.set("deadline", "realtime");
+            }
+            _ => {}
+        }
+
+        encoder
+            .open_as_with(codec, options)
+            .map_err(|e| IoError::EncoderInitFailed(format!("{}: {}", self.codec.ffmpeg_name(), e)))
+    }
+}
+
+/// Returns the FFmpeg pixel format the codec is fed with.
+#[cfg(feature = "stream")]
+fn ffmpeg_pixel_format(codec: VideoCodec) -> ffmpeg::format::Pixel {
+    match codec {
+        // prores_ks only takes 10-bit input
+        VideoCodec::ProRes => ffmpeg::format::Pixel::YUV422P10LE,
+        VideoCodec::Ffv1 => ffmpeg::format::Pixel::BGRA,
+        _ => ffmpeg::format::Pixel::YUV420P,
+    }
+}
+
+/// Copies tightly packed planes into an FFmpeg frame with padded lines.
+///
+/// With `ten_bit`, 8-bit code values are scaled to 10-bit little endian
+/// samples.
+#[cfg(feature = "stream")]
+fn copy_planes(
+    data: &[u8],
+    format: &VideoFormat,
+    ten_bit: bool,
+    picture: &mut ffmpeg::frame::Video,
+) {
+    let (width, height) = (format.width as usize, format.height as usize);
+    let planes = match format.pixel_format {
+        PixelFormat::YUV420P => vec![
+            (width, height),
+            (width / 2, height / 2),
+            (width / 2, height / 2),
+        ],
+        PixelFormat::YUV422P => vec![(width, height), (width / 2, height), (width / 2, height)],
+        pixel_format => vec![(width * pixel_format.bytes_per_pixel(), height)],
+    };
+
+    let mut offset = 0;
+    for (plane, (row_bytes, rows)) in planes.into_iter().enumerate() {
+        let stride = picture.stride(plane);
+        let dst = picture.data_mut(plane);
+        let src = &data[offset..offset + row_bytes * rows];
+        for (y, line) in src.chunks_exact(row_bytes).enumerate() {
+            let out = &mut dst[y * stride..];
+            if ten_bit {
+                for (sample, &value) in out.chunks_exact_mut(2).zip(line) {
+                    sample.copy_from_slice(&(u16::from(value) << 2).to_le_bytes());
+                }
+            } else {
+                out[..row_bytes].copy_from_slice(line);
+            }
+        }
+        offset += row_bytes * rows;
+    }
+}
+
+/// Drains every packet the encoder has ready.
+#[cfg(feature = "stream")]
+fn receive_packets(
+    encoder: &mut ffmpeg::encoder::Video,
+    packets: &mut Vec<EncodedPacket>,
+) -> Result<()> {
+    let mut packet = ffmpeg::Packet::empty();
+    loop {
+        match encoder.receive_packet(&mut packet) {
+            Ok(()) => packets.push(EncodedPacket {
+                data: packet.data().unwrap_or_default().to_vec(),
+                pts: packet.pts().or(packet.dts()).unwrap_or(0),
+                dts: packet.dts().or(packet.pts()).unwrap_or(0),
+                is_keyframe: packet.is_key(),
+            }),
+            Err(ffmpeg::Error::Other {
+                errno: ffmpeg::error::EAGAIN,
+            })
+            | Err(ffmpeg::Error::Eof) => return Ok(()),
+            Err(e) => return Err(IoError::EncodeFailed(e.to_string())),
+        }
+    }
 }
 
 /// An encoded video packet.
@@ -208,9 +539,9 @@ impl VideoEncoder {
 pub struct EncodedPacket {
     /// Compressed packet data
     pub data: Vec<u8>,
-    /// Presentation timestamp
+    /// Presentation timestamp in milliseconds
     pub pts: i64,
-    /// Decode timestamp
+    /// Decode timestamp in milliseconds
     pub dts: i64,
     /// Whether this is a keyframe
     pub is_keyframe: bool,
@@ -222,6 +553,16 @@ impl EncodedPacket {
     pub fn size(&self) -> usize {
         self.data.len()
     }
+
+    /// Returns the presentation time on the frame clock.
+    pub fn presentation_time(&self) -> Duration {
+        Duration::from_millis(self.pts.max(0) as u64)
+    }
+
+    /// Returns the decode time on the frame clock.
+    pub fn decode_time(&self) -> Duration {
+        Duration::from_millis(self.dts.max(0) as u64)
+    }
 }
 
 // Stub implementations when stream feature is disabled
@@ -250,6 +591,11 @@ mod tests {
     fn test_video_codec_names() {
         assert_eq!(VideoCodec::H264.ffmpeg_name(), "libx264");
         assert_eq!(VideoCodec::H265.ffmpeg_name(), "libx265");
+        assert_eq!(VideoCodec::ProRes.ffmpeg_name(), "prores_ks");
+        assert_eq!(
+            VideoCodec::ProRes.input_pixel_format(),
+            PixelFormat::YUV422P
+        );
     }
 
     #[test]
@@ -269,37 +615,107 @@ mod tests {
         assert_eq!(encoder.frame_count(), 0);
     }
 
+    fn small_format() -> VideoFormat {
+        VideoFormat::new(64, 36, PixelFormat::RGBA8, 30.0)
+    }
+
+    fn frame_at(index: u64) -> VideoFrame {
+        let mut frame = VideoFrame::empty(small_format());
+        frame.timestamp = small_format().frame_duration() * index as u32;
+        frame
+    }
+
     #[test]
     fn test_video_encoder_encode() {
-        let mut encoder = VideoEncoder::default_h264_1080p60().unwrap();
-        let format = VideoFormat::hd_1080p60_rgba();
-        let frame = VideoFrame::empty(format);
+        let mut encoder = VideoEncoder::new(
+            VideoCodec::H264,
+            small_format(),
+            500_000,
+            EncoderPreset::Medium,
+        )
+        .unwrap();
 
-        let packet = encoder.encode(&frame);
-        assert!(packet.is_ok());
-        assert_eq!(encoder.frame_count(), 1);
+        let mut packets = Vec::new();
+        for index in 0..10 {
+            packets.extend(encoder.encode(&frame_at(index)).unwrap());
+        }
+        packets.extend(encoder.flush().unwrap());
+
+        assert_eq!(encoder.frame_count(), 10);
+        assert_eq!(packets.len(), 10);
+        assert!(packets.iter().all(|p| !p.data.is_empty()));
+        assert!(packets.windows(2).all(|w| w[0].dts < w[1].dts));
+        // In-band SPS/PPS start the stream without a global header
+        assert!(packets[0].is_keyframe);
+        assert_eq!(&packets[0].data[..4], &[0, 0, 0, 1]);
     }
 
     #[test]
     fn test_video_encoder_keyframe() {
-        let mut encoder = VideoEncoder::default_h264_1080p60().unwrap();
-        let format = VideoFormat::hd_1080p60_rgba();
+        let mut encoder = VideoEncoder::new(
+            VideoCodec::H264,
+            small_format(),
+            500_000,
+            EncoderPreset::LowLatency,
+        )
+        .unwrap();
 
-        // First frame should be keyframe
-        let frame = VideoFrame::empty(format.clone());
-        let packet = encoder.encode(&frame).unwrap();
-        assert!(packet.is_keyframe); // Corrected: First frame IS keyframe
+        // Without lookahead every frame comes straight back
+        let first = encoder.encode(&frame_at(0)).unwrap();
+        assert_eq!(first.len(), 1);
+        assert!(first[0].is_keyframe);
 
-        // Encode 58 more frames (total 59)
-        for _ in 0..58 {
-            let frame = VideoFrame::empty(format.clone());
-            encoder.encode(&frame).unwrap();
-        }
+        let second = encoder.encode(&frame_at(1)).unwrap();
+        assert!(!second[0].is_keyframe);
+
+        encoder.request_keyframe();
+        let forced = encoder.encode(&frame_at(2)).unwrap();
+        assert!(forced[0].is_keyframe);
 
-        // Frame 60 should be keyframe
-        let frame = VideoFrame::empty(format.clone());
-        let packet = encoder.encode(&frame).unwrap();
-        assert!(packet.is_keyframe);
+        // A stale timestamp still yields increasing pts
+        let repeated = encoder.encode(&frame_at(2)).unwrap();
+        assert!(repeated[0].pts > forced[0].pts);
+    }
+
+    #[test]
+    fn test_video_encoder_set_bitrate() {
+        let mut encoder = VideoEncoder::new(
+            VideoCodec::H264,
+            small_format(),
+            2_000_000,
+            EncoderPreset::LowLatency,
+        )
+        .unwrap();
+        encoder.encode(&frame_at(0)).unwrap();
+
+        encoder.set_bitrate(250_000);
+        assert_eq!(encoder.bitrate(), 250_000);
+        let context = encoder.encoder.as_ref().unwrap();
+        assert_eq!(unsafe { (*context.as_ptr()).bit_rate }, 250_000);
+        let packets = encoder.encode(&frame_at(1)).unwrap();
+        assert_eq!(packets.len(), 1);
+    }
+
+    #[test]
+    fn test_video_encoder_prores() {
+        let mut encoder =
+            VideoEncoder::new(VideoCodec::ProRes, small_format(), 0, EncoderPreset::Medium)
+                .unwrap();
+
+        let mut packets = encoder.encode(&frame_at(0)).unwrap();
+        packets.extend(encoder.flush().unwrap());
+        assert_eq!(packets.len(), 1);
+        assert!(packets[0].is_keyframe);
+        assert!(!packets[0].data.is_empty());
+
+        // Reopens after a flush
+        assert_eq!(encoder.encode(&frame_at(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_video_encoder_odd_size() {
+        let format = VideoFormat::new(63, 36, PixelFormat::RGBA8, 30.0);
+        assert!(VideoEncoder::new(VideoCodec::H264, format, 0, EncoderPreset::Medium).is_err());
     }
 
     #[test]
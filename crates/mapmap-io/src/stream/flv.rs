@@ -0,0 +1,443 @@
+//! FLV muxing for RTMP.
+//!
+//! RTMP carries FLV tag bodies as audio (type 8), video (type 9) and data
+//! (type 18) messages. [`FlvMuxer`] turns H.264 access units in Annex B
+//! format and encoded audio frames into those bodies, inserting the AVC and
+//! AAC sequence headers the receiver needs before the first frame.
+//! [`FlvTag::to_bytes`] produces the tag as it appears in an `.flv` file.
+
+use super::amf::Amf0Value;
+use super::audio::{AudioCodec, AudioEncoder};
+use crate::error::{IoError, Result};
+use crate::format::VideoFormat;
+
+/// Size of the FLV file header including the first PreviousTagSize.
+pub const FILE_HEADER_SIZE: usize = 13;
+
+const CODEC_AVC: u8 = 7;
+const SOUND_FORMAT_PCM_LE: u8 = 3;
+const SOUND_FORMAT_AAC: u8 = 10;
+
+/// Type of an FLV tag, equal to its RTMP message type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlvTagType {
+    /// Audio data
+    Audio = 8,
+    /// Video data
+    Video = 9,
+    /// AMF0 script data
+    Script = 18,
+}
+
+/// One FLV tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlvTag {
+    /// Tag type
+    pub kind: FlvTagType,
+    /// Decode timestamp in milliseconds
+    pub timestamp: u32,
+    /// Tag body
+    pub body: Vec<u8>,
+    /// True for video keyframes and sequence headers
+    pub keyframe: bool,
+}
+
+impl FlvTag {
+    /// Serializes the tag with its header and trailing PreviousTagSize.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.body.len() + 15);
+        out.push(self.kind as u8);
+        out.extend_from_slice(&(self.body.len() as u32).to_be_bytes()[1..]);
+        out.extend_from_slice(&(self.timestamp & 0xFF_FFFF).to_be_bytes()[1..]);
+        out.push((self.timestamp >> 24) as u8);
+        out.extend_from_slice(&[0, 0, 0]);
+        out.extend_from_slice(&self.body);
+        out.extend_from_slice(&(self.body.len() as u32 + 11).to_be_bytes());
+        out
+    }
+}
+
+/// Returns the FLV file header followed by PreviousTagSize0.
+pub fn file_header(has_video: bool, has_audio: bool) -> [u8; FILE_HEADER_SIZE] {
+    let flags = (has_audio as u8) << 2 | has_video as u8;
+    [b'F', b'L', b'V', 1, flags, 0, 0, 0, 9, 0, 0, 0, 0]
+}
+
+/// Audio track parameters of an FLV stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlvAudioTrack {
+    /// Audio codec
+    pub codec: AudioCodec,
+    /// Sample rate in Hz
+    pub sample_rate: u32,
+    /// Channel count
+    pub channels: u16,
+}
+
+impl FlvAudioTrack {
+    /// Returns the track parameters of an encoder.
+    pub fn from_encoder(encoder: &AudioEncoder) -> Self {
+        Self {
+            codec: encoder.codec(),
+            sample_rate: encoder.sample_rate(),
+            channels: encoder.channels(),
+        }
+    }
+
+    /// First byte of every audio tag body.
+    fn sound_header(&self) -> u8 {
+        let stereo = (self.channels > 1) as u8;
+        match self.codec {
+            // AAC always signals 44 kHz stereo; the real layout is in the
+            // AudioSpecificConfig
+            AudioCodec::Aac => SOUND_FORMAT_AAC << 4 | 3 << 2 | 1 << 1 | 1,
+            AudioCodec::Pcm => {
+                let rate = match self.sample_rate {
+                    5512 => 0,
+                    11025 => 1,
+                    22050 => 2,
+                    _ => 3,
+                };
+                SOUND_FORMAT_PCM_LE << 4 | rate << 2 | 1 << 1 | stereo
+            }
+        }
+    }
+}
+
+/// Builds FLV tags from encoded H.264 video and audio.
+#[derive(Debug, Clone)]
+pub struct FlvMuxer {
+    audio: Option<FlvAudioTrack>,
+    avc_config: Option<Vec<u8>>,
+    audio_header_sent: bool,
+}
+
+impl FlvMuxer {
+    /// Creates a muxer with an optional audio track.
+    pub fn new(audio: Option<FlvAudioTrack>) -> Self {
+        Self {
+            audio,
+            avc_config: None,
+            audio_header_sent: false,
+        }
+    }
+
+    /// Returns true if the stream has an audio track.
+    pub fn has_audio(&self) -> bool {
+        self.audio.is_some()
+    }
+
+    /// Forgets the sent sequence headers, e.g. after a reconnect.
+    pub fn reset(&mut self) {
+        self.avc_config = None;
+        self.audio_header_sent = false;
+    }
+
+    /// Builds the `onMetaData` script tag.
+    pub fn metadata(&self, format: &VideoFormat, video_bitrate: u64) -> FlvTag {
+        let mut props = vec![
+            ("width".to_string(), Amf0Value::Number(format.width as f64)),
+            (
+                "height".to_string(),
+                Amf0Value::Number(format.height as f64),
+            ),
+            (
+                "framerate".to_string(),
+                Amf0Value::Number(format.frame_rate as f64),
+            ),
+            (
+                "videocodecid".to_string(),
+                Amf0Value::Number(CODEC_AVC as f64),
+            ),
+            (
+                "videodatarate".to_string(),
+                Amf0Value::Number(video_bitrate as f64 / 1000.0),
+            ),
+        ];
+        if let Some(audio) = &self.audio {
+            let codec_id = match audio.codec {
+                AudioCodec::Aac => SOUND_FORMAT_AAC,
+                AudioCodec::Pcm => SOUND_FORMAT_PCM_LE,
+            };
+            props.extend([
+                (
+                    "audiocodecid".to_string(),
+                    Amf0Value::Number(codec_id as f64),
+                ),
+                (
+                    "audiosamplerate".to_string(),
+                    Amf0Value::Number(audio.sample_rate as f64),
+                ),
+                ("audiosamplesize".to_string(), Amf0Value::Number(16.0)),
+                ("stereo".to_string(), Amf0Value::Boolean(audio.channels > 1)),
+            ]);
+        }
+        props.push((
+            "encoder".to_string(),
+            Amf0Value::string(concat!("MapFlow ", env!("CARGO_PKG_VERSION"))),
+        ));
+
+        let mut body = Vec::new();
+        Amf0Value::string("onMetaData").encode(&mut body);
+        Amf0Value::EcmaArray(props).encode(&mut body);
+        FlvTag {
+            kind: FlvTagType::Script,
+            timestamp: 0,
+            body,
+            keyframe: false,
+        }
+    }
+
+    /// Muxes one H.264 access unit in Annex B format.
+    ///
+    /// A sequence header tag is emitted before the first keyframe and
+    /// whenever the SPS or PPS change. Frames before the first keyframe
+    /// cannot be decoded and are skipped.
+    ///
+    /// # Parameters
+    ///
+    /// - `dts_ms` - Decode timestamp in milliseconds
+    /// - `composition_ms` - Presentation minus decode time in milliseconds
+    /// - `data` - The access unit
+    /// - `keyframe` - Whether the access unit is an IDR frame
+    pub fn video(
+        &mut self,
+        dts_ms: u32,
+        composition_ms: i32,
+        data: &[u8],
+        keyframe: bool,
+    ) -> Vec<FlvTag> {
+        let mut tags = Vec::new();
+        let mut sps = None;
+        let mut pps = None;
+        let mut avcc = Vec::with_capacity(data.len() + 16);
+        for nal in nal_units(data) {
+            match nal.first().map(|b| b & 0x1F) {
+                Some(7) => sps = Some(nal),
+                Some(8) => pps = Some(nal),
+                // Access unit delimiters are implied by the tag
+                Some(9) | None => continue,
+                _ => {}
+            }
+            avcc.extend_from_slice(&(nal.len() as u32).to_be_bytes());
+            avcc.extend_from_slice(nal);
+        }
+
+        if let (Some(sps), Some(pps)) = (sps, pps) {
+            let config = avc_decoder_configuration(sps, pps);
+            if self.avc_config.as_ref() != Some(&config) {
+                let mut body = vec![1 << 4 | CODEC_AVC, 0, 0, 0, 0];
+                body.extend_from_slice(&config);
+                tags.push(FlvTag {
+                    kind: FlvTagType::Video,
+                    timestamp: dts_ms,
+                    body,
+                    keyframe: true,
+                });
+                self.avc_config = Some(config);
+            }
+        }
+
+        if self.avc_config.is_none() && !data.is_empty() {
+            return tags;
+        }
+
+        let frame_type = if keyframe { 1 } else { 2 };
+        let mut body = Vec::with_capacity(avcc.len() + 5);
+        body.push(frame_type << 4 | CODEC_AVC);
+        body.push(1);
+        body.extend_from_slice(&composition_ms.to_be_bytes()[1..]);
+        body.extend_from_slice(&avcc);
+        tags.push(FlvTag {
+            kind: FlvTagType::Video,
+            timestamp: dts_ms,
+            body,
+            keyframe,
+        });
+        tags
+    }
+
+    /// Muxes one encoded audio frame.
+    ///
+    /// For AAC, the AudioSpecificConfig is sent before the first frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::InvalidParameter` if the muxer has no audio track.
+    pub fn audio(&mut self, timestamp_ms: u32, data: &[u8]) -> Result<Vec<FlvTag>> {
+        let audio = self
+            .audio
+            .ok_or_else(|| IoError::InvalidParameter("Stream has no audio track".to_string()))?;
+        let header = audio.sound_header();
+        let mut tags = Vec::new();
+        let tag = |body| FlvTag {
+            kind: FlvTagType::Audio,
+            timestamp: timestamp_ms,
+            body,
+            keyframe: false,
+        };
+
+        match audio.codec {
+            AudioCodec::Aac => {
+                if !self.audio_header_sent {
+                    let config =
+                        super::audio::aac_audio_specific_config(audio.sample_rate, audio.channels)
+                            .ok_or_else(|| {
+                                IoError::InvalidParameter(format!(
+                                    "AAC does not support {} Hz",
+                                    audio.sample_rate
+                                ))
+                            })?;
+                    tags.push(tag(vec![header, 0, config[0], config[1]]));
+                    self.audio_header_sent = true;
+                }
+                let mut body = vec![header, 1];
+                body.extend_from_slice(data);
+                tags.push(tag(body));
+            }
+            AudioCodec::Pcm => {
+                let mut body = vec![header];
+                body.extend_from_slice(data);
+                tags.push(tag(body));
+            }
+        }
+        Ok(tags)
+    }
+}
+
+/// Splits an Annex B byte stream into NAL units without start codes.
+pub fn nal_units(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut starts = Vec::new();
+    let mut i = 0;
+    while i + 3 <= data.len() {
+        if data[i] == 0 && data[i + 1] == 0 && data[i + 2] == 1 {
+            starts.push(i + 3);
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+
+    let mut units = Vec::with_capacity(starts.len());
+    for (n, &start) in starts.iter().enumerate() {
+        let mut end = starts.get(n + 1).map_or(data.len(), |&next| next - 3);
+        // Trailing zeros belong to the next four-byte start code
+        while end > start && data[end - 1] == 0 && n + 1 < starts.len() {
+            end -= 1;
+        }
+        units.push(&data[start..end]);
+    }
+    units.into_iter()
+}
+
+/// Builds an AVCDecoderConfigurationRecord from one SPS and PPS.
+pub fn avc_decoder_configuration(sps: &[u8], pps: &[u8]) -> Vec<u8> {
+    let profile = |i| sps.get(i).copied().unwrap_or(0);
+    let mut out = vec![1, profile(1), profile(2), profile(3), 0xFF, 0xE1];
+    out.extend_from_slice(&(sps.len() as u16).to_be_bytes());
+    out.extend_from_slice(sps);
+    out.push(1);
+    out.extend_from_slice(&(pps.len() as u16).to_be_bytes());
+    out.extend_from_slice(pps);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::PixelFormat;
+
+    const SPS: &[u8] = &[0x67, 0x64, 0x00, 0x1F, 0xAC, 0xD9];
+    const PPS: &[u8] = &[0x68, 0xEB, 0xE3, 0xCB];
+
+    fn idr_access_unit() -> Vec<u8> {
+        let mut au = vec![0, 0, 0, 1];
+        au.extend_from_slice(SPS);
+        au.extend_from_slice(&[0, 0, 0, 1]);
+        au.extend_from_slice(PPS);
+        au.extend_from_slice(&[0, 0, 1, 0x65, 0x88, 0x84, 0x00]);
+        au
+    }
+
+    #[test]
+    fn test_nal_units() {
+        let au = idr_access_unit();
+        let units: Vec<_> = nal_units(&au).collect();
+        assert_eq!(units, [SPS, PPS, &[0x65, 0x88, 0x84, 0x00][..]]);
+    }
+
+    #[test]
+    fn test_video_tags() {
+        let mut muxer = FlvMuxer::new(None);
+
+        // Inter frames before the first keyframe are skipped
+        assert!(muxer.video(0, 0, &[0, 0, 1, 0x41, 0x9A], false).is_empty());
+
+        let tags = muxer.video(33, 66, &idr_access_unit(), true);
+        assert_eq!(tags.len(), 2);
+        let header = &tags[0].body;
+        assert_eq!(header[..5], [0x17, 0, 0, 0, 0]);
+        assert_eq!(header[5..11], [1, 0x64, 0x00, 0x1F, 0xFF, 0xE1]);
+
+        let frame = &tags[1];
+        assert_eq!(frame.timestamp, 33);
+        assert_eq!(frame.body[..5], [0x17, 1, 0, 0, 66]);
+        // Length-prefixed NAL units
+        assert_eq!(frame.body[5..9], [0, 0, 0, SPS.len() as u8]);
+
+        // Same parameter sets: no new sequence header
+        assert_eq!(muxer.video(66, 0, &idr_access_unit(), true).len(), 1);
+        let inter = muxer.video(100, 0, &[0, 0, 1, 0x41, 0x9A], false);
+        assert_eq!(inter[0].body[..2], [0x27, 1]);
+        assert!(!inter[0].keyframe);
+    }
+
+    #[test]
+    fn test_audio_tags() {
+        let track = FlvAudioTrack {
+            codec: AudioCodec::Aac,
+            sample_rate: 44100,
+            channels: 2,
+        };
+        let mut muxer = FlvMuxer::new(Some(track));
+        let tags = muxer.audio(10, &[0xDE, 0xAD]).unwrap();
+        assert_eq!(tags[0].body, [0xAF, 0, 0x12, 0x10]);
+        assert_eq!(tags[1].body, [0xAF, 1, 0xDE, 0xAD]);
+        assert_eq!(muxer.audio(20, &[1]).unwrap().len(), 1);
+
+        let mut pcm = FlvMuxer::new(Some(FlvAudioTrack {
+            codec: AudioCodec::Pcm,
+            sample_rate: 22050,
+            channels: 1,
+        }));
+        assert_eq!(pcm.audio(0, &[1, 2]).unwrap()[0].body, [0x3A, 1, 2]);
+
+        assert!(FlvMuxer::new(None).audio(0, &[]).is_err());
+    }
+
+    #[test]
+    fn test_tag_serialization() {
+        let muxer = FlvMuxer::new(None);
+        let format = VideoFormat::new(1280, 720, PixelFormat::RGBA8, 30.0);
+        let tag = muxer.metadata(&format, 2_500_000);
+        let values = super::super::amf::decode_all(&tag.body).unwrap();
+        assert_eq!(values[0].as_str(), Some("onMetaData"));
+        assert_eq!(
+            values[1].get("height").and_then(Amf0Value::as_number),
+            Some(720.0)
+        );
+
+        let tag = FlvTag {
+            kind: FlvTagType::Video,
+            timestamp: 0x0123_4567,
+            body: vec![0xAA; 3],
+            keyframe: false,
+        };
+        let bytes = tag.to_bytes();
+        assert_eq!(
+            bytes,
+            [9, 0, 0, 3, 0x23, 0x45, 0x67, 0x01, 0, 0, 0, 0xAA, 0xAA, 0xAA, 0, 0, 0, 14]
+        );
+        assert_eq!(file_header(true, true)[..5], *b"FLV\x01\x05");
+    }
+}
@@ -1,14 +1,17 @@
 //! Video streaming output.
 //!
 //! This module provides video streaming capabilities including RTMP and SRT streaming.
-//! All streaming functionality requires the `stream` feature flag.
+//! The streamers require the `stream` feature flag; the muxers and protocol
+//! implementations they build on are always available.
 //!
 //! # Features
 //!
 //! - RTMP streaming to platforms like Twitch, YouTube, Facebook Live
-//! - SRT streaming for low-latency applications (stub)
+//! - SRT streaming for low-latency applications
+//! - FLV and MPEG-TS muxing with optional AAC audio
 //! - H.264/H.265 encoding
-//! - Automatic reconnection on network failure
+//! - Automatic reconnection with exponential backoff
+//! - Adaptive bitrate when the send queue backs up
 //!
 //! # Example
 //!
@@ -29,17 +32,27 @@
 //! streamer.send_frame(&frame).unwrap();
 //! ```
 
+pub mod amf;
+pub mod audio;
 pub mod encoder;
+pub mod flv;
+pub mod mpegts;
 pub mod rtmp;
 pub mod srt;
+pub mod transport;
 
 // Re-exports
+pub use audio::{AudioCodec, AudioEncoder, AudioFrame};
 #[cfg(feature = "stream")]
-pub use encoder::{EncodedPacket, EncoderPreset, VideoCodec, VideoEncoder};
+pub use encoder::{EncodedPacket, VideoEncoder};
+pub use encoder::{EncoderPreset, VideoCodec};
 #[cfg(feature = "stream")]
 pub use rtmp::RtmpStreamer;
+pub use rtmp::{RtmpConnection, RtmpUrl};
 #[cfg(feature = "stream")]
 pub use srt::SrtStreamer;
+pub use srt::{SrtConnection, SrtUrl};
+pub use transport::{AdaptiveBitrate, Backoff};
 
 // Stub exports when stream feature is disabled
 #[cfg(not(feature = "stream"))]
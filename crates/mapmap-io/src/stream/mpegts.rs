@@ -0,0 +1,395 @@
+//! MPEG transport stream muxing for SRT.
+//!
+//! [`TsMuxer`] packs H.264 access units and ADTS AAC frames into PES packets
+//! and splits them into 188-byte transport packets. The PAT and PMT are
+//! repeated before every keyframe so that receivers can join at any
+//! keyframe, and the PCR is carried on the video PID.
+
+use std::time::Duration;
+
+/// Size of a transport stream packet.
+pub const TS_PACKET_SIZE: usize = 188;
+
+const SYNC_BYTE: u8 = 0x47;
+const PAT_PID: u16 = 0x0000;
+const PMT_PID: u16 = 0x1000;
+const VIDEO_PID: u16 = 0x0100;
+const AUDIO_PID: u16 = 0x0101;
+const PROGRAM_NUMBER: u16 = 1;
+const STREAM_TYPE_H264: u8 = 0x1B;
+const STREAM_TYPE_AAC_ADTS: u8 = 0x0F;
+const STREAM_ID_VIDEO: u8 = 0xE0;
+const STREAM_ID_AUDIO: u8 = 0xC0;
+
+/// H.264 access unit delimiter, required before every access unit in TS.
+const ACCESS_UNIT_DELIMITER: [u8; 6] = [0, 0, 0, 1, 0x09, 0xF0];
+
+/// Muxes H.264 video and AAC audio into an MPEG transport stream.
+#[derive(Debug, Clone)]
+pub struct TsMuxer {
+    has_audio: bool,
+    continuity: [u8; 4],
+    tables_sent: bool,
+}
+
+impl TsMuxer {
+    /// Creates a muxer with a video and an optional AAC audio stream.
+    pub fn new(has_audio: bool) -> Self {
+        Self {
+            has_audio,
+            continuity: [0; 4],
+            tables_sent: false,
+        }
+    }
+
+    /// Returns true if the stream has an audio track.
+    pub fn has_audio(&self) -> bool {
+        self.has_audio
+    }
+
+    /// Restarts the stream, e.g. after a reconnect.
+    pub fn reset(&mut self) {
+        self.continuity = [0; 4];
+        self.tables_sent = false;
+    }
+
+    /// Returns the PAT and PMT packets.
+    pub fn tables(&mut self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(2 * TS_PACKET_SIZE);
+
+        let mut pat = Vec::with_capacity(8);
+        pat.extend_from_slice(&PROGRAM_NUMBER.to_be_bytes());
+        pat.extend_from_slice(&(0xE000 | PMT_PID).to_be_bytes());
+        let section = psi_section(0x00, 0x0001, &pat);
+        self.write_section(PAT_PID, &section, &mut out);
+
+        let mut pmt = Vec::with_capacity(14);
+        pmt.extend_from_slice(&(0xE000 | VIDEO_PID).to_be_bytes());
+        pmt.extend_from_slice(&0xF000u16.to_be_bytes());
+        for (stream_type, pid) in self.streams() {
+            pmt.push(stream_type);
+            pmt.extend_from_slice(&(0xE000 | pid).to_be_bytes());
+            pmt.extend_from_slice(&0xF000u16.to_be_bytes());
+        }
+        let section = psi_section(0x02, PROGRAM_NUMBER, &pmt);
+        self.write_section(PMT_PID, &section, &mut out);
+
+        self.tables_sent = true;
+        out
+    }
+
+    fn streams(&self) -> Vec<(u8, u16)> {
+        let mut streams = vec![(STREAM_TYPE_H264, VIDEO_PID)];
+        if self.has_audio {
+            streams.push((STREAM_TYPE_AAC_ADTS, AUDIO_PID));
+        }
+        streams
+    }
+
+    fn write_section(&mut self, pid: u16, section: &[u8], out: &mut Vec<u8>) {
+        let start = out.len();
+        self.write_header(pid, true, false, out);
+        out.push(0); // pointer field
+        out.extend_from_slice(section);
+        out.resize(start + TS_PACKET_SIZE, 0xFF);
+    }
+
+    /// Muxes one H.264 access unit in Annex B format.
+    ///
+    /// The tables are inserted before keyframes and before the first frame.
+    pub fn video(&mut self, pts: Duration, dts: Duration, data: &[u8], keyframe: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        if keyframe || !self.tables_sent {
+            out.extend(self.tables());
+        }
+
+        let mut payload = Vec::with_capacity(data.len() + ACCESS_UNIT_DELIMITER.len());
+        let has_delimiter = super::flv::nal_units(data)
+            .next()
+            .is_some_and(|nal| nal.first().map(|b| b & 0x1F) == Some(9));
+        if !has_delimiter {
+            payload.extend_from_slice(&ACCESS_UNIT_DELIMITER);
+        }
+        payload.extend_from_slice(data);
+
+        let pes = pes_packet(
+            STREAM_ID_VIDEO,
+            to_90khz(pts),
+            Some(to_90khz(dts)),
+            &payload,
+        );
+        self.packetize(VIDEO_PID, &pes, Some(to_90khz(dts)), keyframe, &mut out);
+        out
+    }
+
+    /// Muxes one AAC frame with its ADTS header.
+    pub fn audio(&mut self, pts: Duration, adts_frame: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        if !self.tables_sent {
+            out.extend(self.tables());
+        }
+        let pes = pes_packet(STREAM_ID_AUDIO, to_90khz(pts), None, adts_frame);
+        self.packetize(AUDIO_PID, &pes, None, false, &mut out);
+        out
+    }
+
+    fn continuity_counter(&mut self, pid: u16) -> u8 {
+        let index = match pid {
+            PAT_PID => 0,
+            PMT_PID => 1,
+            VIDEO_PID => 2,
+            _ => 3,
+        };
+        let cc = self.continuity[index];
+        self.continuity[index] = (cc + 1) & 0x0F;
+        cc
+    }
+
+    fn write_header(&mut self, pid: u16, start: bool, adaptation: bool, out: &mut Vec<u8>) {
+        let cc = self.continuity_counter(pid);
+        out.push(SYNC_BYTE);
+        out.push((start as u8) << 6 | (pid >> 8) as u8 & 0x1F);
+        out.push(pid as u8);
+        let control = if adaptation { 0b11 } else { 0b01 };
+        out.push(control << 4 | cc);
+    }
+
+    fn packetize(
+        &mut self,
+        pid: u16,
+        pes: &[u8],
+        pcr: Option<u64>,
+        random_access: bool,
+        out: &mut Vec<u8>,
+    ) {
+        let mut remaining = pes;
+        let mut first = true;
+        while !remaining.is_empty() {
+            let pcr = if first { pcr } else { None };
+            let flags = if first && random_access { 0x40 } else { 0 } | pcr.map_or(0, |_| 0x10);
+
+            // Adaptation field size including its length byte
+            let mut field_size = if flags != 0 {
+                2 + pcr.map_or(0, |_| 6)
+            } else {
+                0
+            };
+            let space = TS_PACKET_SIZE - 4 - field_size;
+            if remaining.len() < space {
+                field_size += space - remaining.len();
+            }
+            let payload_len = (TS_PACKET_SIZE - 4 - field_size).min(remaining.len());
+
+            let start = out.len();
+            self.write_header(pid, first, field_size > 0, out);
+            if field_size > 0 {
+                out.push((field_size - 1) as u8);
+                if field_size > 1 {
+                    out.push(flags);
+                    if let Some(pcr) = pcr {
+                        out.extend_from_slice(&encode_pcr(pcr));
+                    }
+                    out.resize(start + 4 + field_size, 0xFF);
+                }
+            }
+            out.extend_from_slice(&remaining[..payload_len]);
+            debug_assert_eq!(out.len() - start, TS_PACKET_SIZE);
+
+            remaining = &remaining[payload_len..];
+            first = false;
+        }
+    }
+}
+
+/// Converts a stream time to 90 kHz clock ticks.
+fn to_90khz(time: Duration) -> u64 {
+    (time.as_nanos() * 9 / 100_000) as u64 & 0x1_FFFF_FFFF
+}
+
+fn encode_timestamp(marker: u8, ts: u64) -> [u8; 5] {
+    [
+        marker << 4 | ((ts >> 29) as u8 & 0x0E) | 1,
+        (ts >> 22) as u8,
+        (ts >> 14) as u8 | 1,
+        (ts >> 7) as u8,
+        (ts << 1) as u8 | 1,
+    ]
+}
+
+fn encode_pcr(base: u64) -> [u8; 6] {
+    [
+        (base >> 25) as u8,
+        (base >> 17) as u8,
+        (base >> 9) as u8,
+        (base >> 1) as u8,
+        ((base & 1) as u8) << 7 | 0x7E,
+        0,
+    ]
+}
+
+fn pes_packet(stream_id: u8, pts: u64, dts: Option<u64>, payload: &[u8]) -> Vec<u8> {
+    let header_len = if dts.is_some() { 10 } else { 5 };
+    let mut pes = Vec::with_capacity(payload.len() + 9 + header_len);
+    pes.extend_from_slice(&[0, 0, 1, stream_id]);
+
+    // Video PES packets may exceed the 16-bit length and use 0 (unbounded)
+    let length = payload.len() + 3 + header_len;
+    let length = if length > u16::MAX as usize || stream_id == STREAM_ID_VIDEO {
+        0
+    } else {
+        length as u16
+    };
+    pes.extend_from_slice(&length.to_be_bytes());
+
+    pes.push(0x80);
+    match dts {
+        Some(dts) => {
+            pes.push(0xC0);
+            pes.push(header_len as u8);
+            pes.extend_from_slice(&encode_timestamp(0b0011, pts));
+            pes.extend_from_slice(&encode_timestamp(0b0001, dts));
+        }
+        None => {
+            pes.push(0x80);
+            pes.push(header_len as u8);
+            pes.extend_from_slice(&encode_timestamp(0b0010, pts));
+        }
+    }
+    pes.extend_from_slice(payload);
+    pes
+}
+
+/// Builds a PSI section with its CRC.
+fn psi_section(table_id: u8, id: u16, body: &[u8]) -> Vec<u8> {
+    let length = 5 + body.len() + 4;
+    let mut section = Vec::with_capacity(length + 3);
+    section.push(table_id);
+    section.extend_from_slice(&(0xB000 | length as u16).to_be_bytes());
+    section.extend_from_slice(&id.to_be_bytes());
+    section.extend_from_slice(&[0xC1, 0, 0]);
+    section.extend_from_slice(body);
+    let crc = crc32_mpeg2(&section);
+    section.extend_from_slice(&crc.to_be_bytes());
+    section
+}
+
+/// CRC-32/MPEG-2 as used by PSI sections.
+pub fn crc32_mpeg2(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= (byte as u32) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split_packets(data: &[u8]) -> Vec<&[u8]> {
+        assert_eq!(data.len() % TS_PACKET_SIZE, 0);
+        data.chunks(TS_PACKET_SIZE).collect()
+    }
+
+    fn pid(packet: &[u8]) -> u16 {
+        u16::from_be_bytes([packet[1] & 0x1F, packet[2]])
+    }
+
+    #[test]
+    fn test_pat() {
+        let mut muxer = TsMuxer::new(true);
+        let tables = muxer.tables();
+        let packets = split_packets(&tables);
+        assert_eq!(packets.len(), 2);
+
+        // Same PAT as FFmpeg's mpegts muxer writes by default
+        let pat: &[u8] = &[
+            0x47, 0x40, 0x00, 0x10, 0x00, 0x00, 0xB0, 0x0D, 0x00, 0x01, 0xC1, 0x00, 0x00, 0x00,
+            0x01, 0xF0, 0x00, 0x2A, 0xB1, 0x04, 0xB2,
+        ];
+        assert_eq!(&packets[0][..pat.len()], pat);
+        assert!(packets[0][pat.len()..].iter().all(|&b| b == 0xFF));
+
+        let pmt = packets[1];
+        assert_eq!(pid(pmt), PMT_PID);
+        let section_len = (u16::from_be_bytes([pmt[6], pmt[7]]) & 0x0FFF) as usize;
+        let section = &pmt[5..8 + section_len];
+        // A section including its CRC checks to zero
+        assert_eq!(crc32_mpeg2(section), 0);
+        assert_eq!(section[12], STREAM_TYPE_H264);
+        assert_eq!(section[17], STREAM_TYPE_AAC_ADTS);
+    }
+
+    #[test]
+    fn test_video_packetization() {
+        let mut muxer = TsMuxer::new(false);
+        let frame: Vec<u8> = [0, 0, 0, 1, 0x65]
+            .into_iter()
+            .chain((0..1000).map(|i| i as u8 | 1))
+            .collect();
+        let pts = Duration::from_millis(100);
+        let out = muxer.video(pts, pts, &frame, true);
+        let packets = split_packets(&out);
+        assert_eq!(pid(packets[0]), PAT_PID);
+        assert_eq!(pid(packets[1]), PMT_PID);
+
+        let first = packets[2];
+        assert_eq!(pid(first), VIDEO_PID);
+        assert_eq!(first[1] & 0x40, 0x40, "payload unit start");
+        assert_eq!(first[3] & 0x30, 0x30, "adaptation field");
+        assert_eq!(first[5] & 0x50, 0x50, "random access and PCR");
+        // PCR base of 100 ms
+        let pcr = (first[6] as u64) << 25
+            | (first[7] as u64) << 17
+            | (first[8] as u64) << 9
+            | (first[9] as u64) << 1
+            | (first[10] as u64) >> 7;
+        assert_eq!(pcr, 9000);
+
+        // PES payload with the access unit delimiter reassembles exactly
+        let mut payload = Vec::new();
+        for (i, packet) in packets[2..].iter().enumerate() {
+            assert_eq!(packet[3] & 0x0F, i as u8, "continuity counter");
+            let start = if packet[3] & 0x20 != 0 {
+                5 + packet[4] as usize
+            } else {
+                4
+            };
+            payload.extend_from_slice(&packet[start..]);
+        }
+        assert_eq!(payload[..4], [0, 0, 1, STREAM_ID_VIDEO]);
+        let header_len = payload[8] as usize;
+        assert_eq!(
+            payload[9 + header_len..15 + header_len],
+            ACCESS_UNIT_DELIMITER
+        );
+        assert_eq!(&payload[15 + header_len..], &frame[..]);
+
+        // Inter frames carry no tables
+        let out = muxer.video(pts, pts, &[0, 0, 1, 0x41, 1], false);
+        assert_eq!(split_packets(&out).len(), 1);
+    }
+
+    #[test]
+    fn test_audio_pes() {
+        let mut muxer = TsMuxer::new(true);
+        muxer.tables();
+        let out = muxer.audio(Duration::from_secs(1), &[0xFF, 0xF1, 1, 2, 3]);
+        let packet = split_packets(&out)[0];
+        assert_eq!(pid(packet), AUDIO_PID);
+        let start = 5 + packet[4] as usize;
+        let pes = &packet[start..];
+        assert_eq!(pes[..4], [0, 0, 1, STREAM_ID_AUDIO]);
+        assert_eq!(u16::from_be_bytes([pes[4], pes[5]]), 3 + 5 + 5);
+        assert_eq!(pes[7], 0x80);
+        assert_eq!(&pes[14..], &[0xFF, 0xF1, 1, 2, 3]);
+        assert_eq!(to_90khz(Duration::from_secs(1)), 90_000);
+    }
+}
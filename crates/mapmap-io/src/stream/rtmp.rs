@@ -1,19 +1,767 @@
 //! RTMP streaming output.
 //!
-//! This module provides RTMP streaming capabilities using FFmpeg.
+//! [`RtmpConnection`] implements the publishing side of RTMP over TCP: the
+//! handshake, chunking, and the `connect`/`createStream`/`publish` command
+//! sequence. [`RtmpStreamer`] encodes frames with the [`VideoEncoder`], muxes
+//! them into FLV tags and delivers them over the connection, reconnecting
+//! with exponential backoff and lowering the bitrate when the send queue
+//! backs up.
+//!
+//! [`VideoEncoder`]: crate::stream::encoder::VideoEncoder
 
-#[cfg(feature = "stream")]
+use super::amf::{self, Amf0Value};
+use super::flv::{FlvTag, FlvTagType};
+use super::transport::Outbox;
 use crate::error::{IoError, Result};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "stream")]
+use super::audio::{AudioEncoder, AudioFrame};
+#[cfg(feature = "stream")]
+use super::flv::{FlvAudioTrack, FlvMuxer};
+#[cfg(feature = "stream")]
+use super::transport::{AdaptiveBitrate, Backoff, BitrateMeter};
 #[cfg(feature = "stream")]
 use crate::format::{VideoFormat, VideoFrame};
 #[cfg(feature = "stream")]
 use crate::sink::{SinkStatistics, VideoSink};
 #[cfg(feature = "stream")]
-use crate::stream::encoder::{EncoderPreset, VideoCodec, VideoEncoder};
+use crate::stream::encoder::{EncodedPacket, EncoderPreset, VideoCodec, VideoEncoder};
+#[cfg(feature = "stream")]
+use std::time::Instant;
+
+/// Default RTMP port.
+pub const DEFAULT_PORT: u16 = 1935;
+
+const HANDSHAKE_SIZE: usize = 1536;
+const DEFAULT_CHUNK_SIZE: usize = 128;
+const OUTGOING_CHUNK_SIZE: usize = 4096;
+
+const MSG_SET_CHUNK_SIZE: u8 = 1;
+const MSG_ACKNOWLEDGEMENT: u8 = 3;
+const MSG_USER_CONTROL: u8 = 4;
+const MSG_WINDOW_ACK_SIZE: u8 = 5;
+const MSG_COMMAND: u8 = 20;
+
+const CSID_PROTOCOL: u32 = 2;
+const CSID_COMMAND: u32 = 3;
+const CSID_AUDIO: u32 = 4;
+const CSID_DATA: u32 = 5;
+const CSID_VIDEO: u32 = 6;
+
+const USER_CONTROL_PING_REQUEST: u16 = 6;
+const USER_CONTROL_PING_RESPONSE: u16 = 7;
+
+/// Parts of an `rtmp://host[:port]/app/stream_key` URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtmpUrl {
+    /// Server host name or address
+    pub host: String,
+    /// Server port
+    pub port: u16,
+    /// Application name
+    pub app: String,
+    /// Stream key
+    pub stream_key: String,
+    /// True for `rtmps://`
+    pub secure: bool,
+}
+
+impl RtmpUrl {
+    /// Parses an RTMP URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::InvalidParameter` if the scheme is not `rtmp://` or
+    /// `rtmps://`, or the application or stream key is missing.
+    pub fn parse(url: &str) -> Result<Self> {
+        let (secure, rest) = if let Some(rest) = url.strip_prefix("rtmp://") {
+            (false, rest)
+        } else if let Some(rest) = url.strip_prefix("rtmps://") {
+            (true, rest)
+        } else {
+            return Err(IoError::InvalidParameter(
+                "RTMP URL must start with rtmp:// or rtmps://".to_string(),
+            ));
+        };
+
+        let invalid = || {
+            IoError::InvalidParameter(
+                "RTMP URL must have the form rtmp://host[:port]/app/stream_key".to_string(),
+            )
+        };
+        let (authority, path) = rest.split_once('/').ok_or_else(invalid)?;
+        let (app, stream_key) = path.split_once('/').ok_or_else(invalid)?;
+        if authority.is_empty() || app.is_empty() || stream_key.is_empty() {
+            return Err(invalid());
+        }
+
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid())?),
+            None => (authority, if secure { 443 } else { DEFAULT_PORT }),
+        };
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            app: app.to_string(),
+            stream_key: stream_key.to_string(),
+            secure,
+        })
+    }
+
+    /// Returns the `tcUrl` sent in the connect command.
+    pub fn tc_url(&self) -> String {
+        let scheme = if self.secure { "rtmps" } else { "rtmp" };
+        format!("{}://{}:{}/{}", scheme, self.host, self.port, self.app)
+    }
+
+    /// Returns the URL with the stream key hidden, for logging.
+    pub fn masked(&self) -> String {
+        format!("{}/****", self.tc_url())
+    }
+}
+
+/// A complete RTMP message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RtmpMessage {
+    /// Message type id
+    pub kind: u8,
+    /// Message stream id
+    pub stream_id: u32,
+    /// Timestamp in milliseconds
+    pub timestamp: u32,
+    /// Message payload
+    pub payload: Vec<u8>,
+}
+
+impl RtmpMessage {
+    /// Decodes the AMF0 values of a command or data message.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::RtmpError` if the payload is not valid AMF0.
+    pub fn amf_values(&self) -> Result<Vec<Amf0Value>> {
+        amf::decode_all(&self.payload)
+    }
+}
+
+/// Splits messages into chunks.
+#[derive(Debug, Clone)]
+pub struct ChunkWriter {
+    chunk_size: usize,
+}
+
+impl ChunkWriter {
+    /// Creates a writer using the default chunk size of 128 bytes.
+    pub fn new() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Returns the outgoing chunk size.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Builds a Set Chunk Size message and applies it to this writer.
+    pub fn set_chunk_size(&mut self, size: usize) -> Vec<u8> {
+        let bytes = self.write(
+            CSID_PROTOCOL,
+            &RtmpMessage {
+                kind: MSG_SET_CHUNK_SIZE,
+                stream_id: 0,
+                timestamp: 0,
+                payload: (size as u32).to_be_bytes().to_vec(),
+            },
+        );
+        self.chunk_size = size;
+        bytes
+    }
+
+    /// Serializes a message on a chunk stream.
+    ///
+    /// Every message starts with a full (type 0) header; continuation chunks
+    /// use type 3 headers.
+    pub fn write(&self, csid: u32, message: &RtmpMessage) -> Vec<u8> {
+        let extended = message.timestamp >= 0xFF_FFFF;
+        let chunks = message.payload.len().div_ceil(self.chunk_size).max(1);
+        let mut out = Vec::with_capacity(message.payload.len() + 16 + chunks * 5);
+
+        write_basic_header(&mut out, 0, csid);
+        let timestamp = if extended {
+            0xFF_FFFF
+        } else {
+            message.timestamp
+        };
+        out.extend_from_slice(&timestamp.to_be_bytes()[1..]);
+        out.extend_from_slice(&(message.payload.len() as u32).to_be_bytes()[1..]);
+        out.push(message.kind);
+        out.extend_from_slice(&message.stream_id.to_le_bytes());
+        if extended {
+            out.extend_from_slice(&message.timestamp.to_be_bytes());
+        }
+
+        for (i, chunk) in message.payload.chunks(self.chunk_size).enumerate() {
+            if i > 0 {
+                write_basic_header(&mut out, 3, csid);
+                if extended {
+                    out.extend_from_slice(&message.timestamp.to_be_bytes());
+                }
+            }
+            out.extend_from_slice(chunk);
+        }
+        out
+    }
+}
+
+impl Default for ChunkWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn write_basic_header(out: &mut Vec<u8>, fmt: u8, csid: u32) {
+    match csid {
+        2..=63 => out.push(fmt << 6 | csid as u8),
+        64..=319 => out.extend_from_slice(&[fmt << 6, (csid - 64) as u8]),
+        _ => {
+            let id = (csid - 64) as u16;
+            out.push(fmt << 6 | 1);
+            out.extend_from_slice(&id.to_le_bytes());
+        }
+    }
+}
+
+/// Per chunk stream state of the reader.
+#[derive(Debug, Clone, Default)]
+struct ChunkStream {
+    timestamp: u32,
+    delta: u32,
+    length: usize,
+    kind: u8,
+    stream_id: u32,
+    extended: bool,
+    payload: Vec<u8>,
+}
+
+/// Reassembles messages from incoming chunks.
+#[derive(Debug, Clone)]
+pub struct ChunkReader {
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    streams: HashMap<u32, ChunkStream>,
+}
+
+impl ChunkReader {
+    /// Creates a reader using the default chunk size of 128 bytes.
+    pub fn new() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            buffer: Vec::new(),
+            streams: HashMap::new(),
+        }
+    }
+
+    /// Appends received bytes.
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns the next complete message, if one is buffered.
+    ///
+    /// Set Chunk Size messages are applied to the reader before they are
+    /// returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::RtmpError` for a chunk on an unknown chunk stream
+    /// that does not start with a full header.
+    pub fn next_message(&mut self) -> Result<Option<RtmpMessage>> {
+        loop {
+            let Some((consumed, message)) = self.parse_chunk()? else {
+                return Ok(None);
+            };
+            self.buffer.drain(..consumed);
+            if let Some(message) = message {
+                if message.kind == MSG_SET_CHUNK_SIZE && message.payload.len() >= 4 {
+                    let size = u32::from_be_bytes(message.payload[..4].try_into().expect("4"));
+                    self.chunk_size = (size & 0x7FFF_FFFF).max(1) as usize;
+                }
+                return Ok(Some(message));
+            }
+        }
+    }
+
+    /// Parses one chunk from the buffer without consuming it.
+    fn parse_chunk(&mut self) -> Result<Option<(usize, Option<RtmpMessage>)>> {
+        let buf = &self.buffer;
+        let Some(&first) = buf.first() else {
+            return Ok(None);
+        };
+        let fmt = first >> 6;
+        let (csid, mut pos) = match first & 0x3F {
+            0 if buf.len() >= 2 => (buf[1] as u32 + 64, 2),
+            1 if buf.len() >= 3 => (u16::from_le_bytes([buf[1], buf[2]]) as u32 + 64, 3),
+            0 | 1 => return Ok(None),
+            id => (id as u32, 1),
+        };
+
+        let header_len = [11, 7, 3, 0][fmt as usize];
+        if buf.len() < pos + header_len {
+            return Ok(None);
+        }
+        let u24 = |b: &[u8]| u32::from_be_bytes([0, b[0], b[1], b[2]]);
+
+        let mut state = match self.streams.get(&csid) {
+            Some(state) => state.clone(),
+            None if fmt == 0 => ChunkStream::default(),
+            None => {
+                return Err(IoError::RtmpError(format!(
+                    "Chunk stream {} starts without a full header",
+                    csid
+                )))
+            }
+        };
+        let starting = state.payload.is_empty();
+
+        let header = &buf[pos..pos + header_len];
+        let mut raw_timestamp = None;
+        if fmt <= 2 {
+            raw_timestamp = Some(u24(&header[0..3]));
+        }
+        if fmt <= 1 {
+            state.length = u24(&header[3..6]) as usize;
+            state.kind = header[6];
+        }
+        if fmt == 0 {
+            state.stream_id = u32::from_le_bytes(header[7..11].try_into().expect("4"));
+        }
+        pos += header_len;
+
+        if let Some(raw) = raw_timestamp {
+            state.extended = raw == 0xFF_FFFF;
+        }
+        let mut timestamp_field = raw_timestamp.unwrap_or(state.delta);
+        if state.extended {
+            if buf.len() < pos + 4 {
+                return Ok(None);
+            }
+            timestamp_field = u32::from_be_bytes(buf[pos..pos + 4].try_into().expect("4"));
+            pos += 4;
+        }
+
+        if starting {
+            match fmt {
+                0 => state.timestamp = timestamp_field,
+                1 | 2 => {
+                    state.delta = timestamp_field;
+                    state.timestamp = state.timestamp.wrapping_add(timestamp_field);
+                }
+                _ => state.timestamp = state.timestamp.wrapping_add(state.delta),
+            }
+        }
+
+        let take = (state.length - state.payload.len()).min(self.chunk_size);
+        if buf.len() < pos + take {
+            return Ok(None);
+        }
+        state.payload.extend_from_slice(&buf[pos..pos + take]);
+        pos += take;
+
+        let message = if state.payload.len() >= state.length {
+            Some(RtmpMessage {
+                kind: state.kind,
+                stream_id: state.stream_id,
+                timestamp: state.timestamp,
+                payload: std::mem::take(&mut state.payload),
+            })
+        } else {
+            None
+        };
+        self.streams.insert(csid, state);
+        Ok(Some((pos, message)))
+    }
+}
+
+impl Default for ChunkReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds the C1 handshake packet: time, zero, and pseudo-random bytes.
+fn handshake_c1() -> Vec<u8> {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0x2545_F491, |d| d.as_nanos() as u64)
+        | 1;
+    let mut state = seed;
+    let mut c1 = Vec::with_capacity(HANDSHAKE_SIZE);
+    c1.extend_from_slice(&[0; 8]);
+    while c1.len() < HANDSHAKE_SIZE {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        c1.extend_from_slice(&state.to_le_bytes());
+    }
+    c1.truncate(HANDSHAKE_SIZE);
+    c1
+}
+
+/// A publishing RTMP connection.
+///
+/// The connection is established with blocking I/O and then switched to
+/// non-blocking mode; media messages are queued in an [`Outbox`] and written
+/// by [`RtmpConnection::pump`].
+pub struct RtmpConnection {
+    socket: TcpStream,
+    reader: ChunkReader,
+    writer: ChunkWriter,
+    stream_id: u32,
+    outbox: Outbox,
+    transaction: f64,
+    bytes_received: u64,
+    ack_window: u64,
+    last_ack: u64,
+}
+
+impl RtmpConnection {
+    /// Connects to the server and starts publishing `url.stream_key`.
+    ///
+    /// # Parameters
+    ///
+    /// - `url` - Server and stream to publish to
+    /// - `timeout` - Limit for the TCP connect and each server response
+    /// - `max_latency` - Media that may be queued before frames are dropped
+    ///
+    /// # Errors
+    ///
+    /// - `IoError::StreamConnectionFailed` - The server is unreachable or the
+    ///   handshake fails
+    /// - `IoError::RtmpError` - The server rejects the connect or publish
+    ///   command, or the URL needs TLS
+    pub fn connect(url: &RtmpUrl, timeout: Duration, max_latency: Duration) -> Result<Self> {
+        if url.secure {
+            return Err(IoError::RtmpError(
+                "RTMPS requires TLS, which is not supported; use rtmp://".to_string(),
+            ));
+        }
+
+        let failed =
+            |e: io::Error| IoError::StreamConnectionFailed(format!("{}: {}", url.masked(), e));
+        let addr = (url.host.as_str(), url.port)
+            .to_socket_addrs()
+            .map_err(failed)?
+            .next()
+            .ok_or_else(|| failed(io::ErrorKind::NotFound.into()))?;
+        let mut socket = TcpStream::connect_timeout(&addr, timeout).map_err(failed)?;
+        socket.set_nodelay(true).map_err(failed)?;
+        socket.set_read_timeout(Some(timeout)).map_err(failed)?;
+        socket.set_write_timeout(Some(timeout)).map_err(failed)?;
+
+        // C0 + C1, S0 + S1 + S2, C2
+        let mut c0c1 = vec![3u8];
+        c0c1.extend(handshake_c1());
+        socket.write_all(&c0c1).map_err(failed)?;
+        let mut s0s1 = vec![0u8; 1 + HANDSHAKE_SIZE];
+        socket.read_exact(&mut s0s1).map_err(failed)?;
+        if s0s1[0] != 3 {
+            return Err(IoError::StreamConnectionFailed(format!(
+                "Unsupported RTMP version {}",
+                s0s1[0]
+            )));
+        }
+        socket.write_all(&s0s1[1..]).map_err(failed)?;
+        let mut s2 = vec![0u8; HANDSHAKE_SIZE];
+        socket.read_exact(&mut s2).map_err(failed)?;
+
+        let mut connection = Self {
+            socket,
+            reader: ChunkReader::new(),
+            writer: ChunkWriter::new(),
+            stream_id: 0,
+            outbox: Outbox::new(max_latency),
+            transaction: 0.0,
+            bytes_received: 0,
+            ack_window: 2_500_000,
+            last_ack: 0,
+        };
+        connection.publish(url)?;
+        connection.socket.set_nonblocking(true)?;
+        tracing::info!("Publishing to {}", url.masked());
+        Ok(connection)
+    }
+
+    fn publish(&mut self, url: &RtmpUrl) -> Result<()> {
+        let chunk_size = self.writer.set_chunk_size(OUTGOING_CHUNK_SIZE);
+        self.socket.write_all(&chunk_size)?;
+
+        let connect = self.command(
+            "connect",
+            vec![Amf0Value::object([
+                ("app", Amf0Value::string(url.app.as_str())),
+                ("type", Amf0Value::string("nonprivate")),
+                (
+                    "flashVer",
+                    Amf0Value::string("FMLE/3.0 (compatible; MapFlow)"),
+                ),
+                ("tcUrl", Amf0Value::string(url.tc_url())),
+            ])],
+            0,
+        )?;
+        self.expect_result(connect)?;
+
+        let key = Amf0Value::string(url.stream_key.as_str());
+        self.command("releaseStream", vec![Amf0Value::Null, key.clone()], 0)?;
+        self.command("FCPublish", vec![Amf0Value::Null, key.clone()], 0)?;
+        let create = self.command("createStream", vec![Amf0Value::Null], 0)?;
+        let result = self.expect_result(create)?;
+        self.stream_id = result
+            .get(3)
+            .and_then(Amf0Value::as_number)
+            .ok_or_else(|| IoError::RtmpError("createStream returned no stream id".to_string()))?
+            as u32;
+
+        let stream_id = self.stream_id;
+        self.command(
+            "publish",
+            vec![Amf0Value::Null, key, Amf0Value::string("live")],
+            stream_id,
+        )?;
+        loop {
+            let values = self.read_command()?;
+            if values.first().and_then(Amf0Value::as_str) != Some("onStatus") {
+                continue;
+            }
+            let info = values.get(3);
+            let field = |name| info.and_then(|i| i.get(name)).and_then(Amf0Value::as_str);
+            if field("level") == Some("error") {
+                return Err(IoError::RtmpError(format!(
+                    "Publish rejected: {}",
+                    field("description")
+                        .or(field("code"))
+                        .unwrap_or("no reason given")
+                )));
+            }
+            if field("code") == Some("NetStream.Publish.Start") {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Sends a command message and returns its transaction id.
+    fn command(&mut self, name: &str, args: Vec<Amf0Value>, stream_id: u32) -> Result<f64> {
+        self.transaction += 1.0;
+        let mut values = vec![Amf0Value::string(name), Amf0Value::Number(self.transaction)];
+        values.extend(args);
+        let bytes = self.writer.write(
+            CSID_COMMAND,
+            &RtmpMessage {
+                kind: MSG_COMMAND,
+                stream_id,
+                timestamp: 0,
+                payload: amf::encode_all(&values),
+            },
+        );
+        self.socket.write_all(&bytes)?;
+        Ok(self.transaction)
+    }
+
+    /// Waits for the `_result` or `_error` of a transaction.
+    fn expect_result(&mut self, transaction: f64) -> Result<Vec<Amf0Value>> {
+        loop {
+            let values = self.read_command()?;
+            let name = values.first().and_then(Amf0Value::as_str);
+            let id = values.get(1).and_then(Amf0Value::as_number);
+            if id != Some(transaction) {
+                continue;
+            }
+            match name {
+                Some("_result") => return Ok(values),
+                Some("_error") => {
+                    let description = values
+                        .get(3)
+                        .and_then(|info| info.get("description"))
+                        .and_then(Amf0Value::as_str)
+                        .unwrap_or("no reason given");
+                    return Err(IoError::RtmpError(format!(
+                        "Command rejected: {}",
+                        description
+                    )));
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    /// Blocks until the next command message arrives.
+    fn read_command(&mut self) -> Result<Vec<Amf0Value>> {
+        loop {
+            if let Some(message) = self.reader.next_message()? {
+                self.handle_control(&message)?;
+                if message.kind == MSG_COMMAND {
+                    return message.amf_values();
+                }
+                continue;
+            }
+            let mut buf = [0u8; 4096];
+            let n = self.socket.read(&mut buf).map_err(|e| {
+                IoError::StreamConnectionFailed(format!("No response from server: {}", e))
+            })?;
+            if n == 0 {
+                return Err(IoError::StreamDisconnected);
+            }
+            self.received(&buf[..n]);
+        }
+    }
+
+    fn received(&mut self, data: &[u8]) {
+        self.bytes_received += data.len() as u64;
+        self.reader.push(data);
+    }
+
+    /// Answers pings and acknowledgement windows.
+    fn handle_control(&mut self, message: &RtmpMessage) -> Result<()> {
+        let payload = &message.payload;
+        match message.kind {
+            MSG_WINDOW_ACK_SIZE if payload.len() >= 4 => {
+                self.ack_window = u32::from_be_bytes(payload[..4].try_into().expect("4")) as u64;
+            }
+            MSG_USER_CONTROL if payload.len() >= 6 => {
+                let event = u16::from_be_bytes([payload[0], payload[1]]);
+                if event == USER_CONTROL_PING_REQUEST {
+                    let mut response = USER_CONTROL_PING_RESPONSE.to_be_bytes().to_vec();
+                    response.extend_from_slice(&payload[2..6]);
+                    self.send_control(MSG_USER_CONTROL, response);
+                }
+            }
+            _ => {}
+        }
+
+        if self.ack_window > 0 && self.bytes_received - self.last_ack >= self.ack_window {
+            self.last_ack = self.bytes_received;
+            let sequence = (self.bytes_received as u32).to_be_bytes().to_vec();
+            self.send_control(MSG_ACKNOWLEDGEMENT, sequence);
+        }
+        Ok(())
+    }
+
+    fn send_control(&mut self, kind: u8, payload: Vec<u8>) {
+        let bytes = self.writer.write(
+            CSID_PROTOCOL,
+            &RtmpMessage {
+                kind,
+                stream_id: 0,
+                timestamp: 0,
+                payload,
+            },
+        );
+        self.outbox.push_control(bytes);
+    }
+
+    /// Queues an FLV tag as a media message.
+    ///
+    /// Video tags are queued as droppable frames; audio and script tags are
+    /// always delivered. Returns the number of frames dropped to stay within
+    /// the latency limit.
+    pub fn send_tag(&mut self, tag: &FlvTag, frame: u64, media_time: Duration) -> u64 {
+        let (csid, payload) = match tag.kind {
+            FlvTagType::Audio => (CSID_AUDIO, tag.body.clone()),
+            FlvTagType::Video => (CSID_VIDEO, tag.body.clone()),
+            FlvTagType::Script => {
+                // Metadata is set on the stream with @setDataFrame
+                let mut payload = Vec::with_capacity(tag.body.len() + 16);
+                Amf0Value::string("@setDataFrame").encode(&mut payload);
+                payload.extend_from_slice(&tag.body);
+                (CSID_DATA, payload)
+            }
+        };
+        let bytes = self.writer.write(
+            csid,
+            &RtmpMessage {
+                kind: tag.kind as u8,
+                stream_id: self.stream_id,
+                timestamp: tag.timestamp,
+                payload,
+            },
+        );
+        match tag.kind {
+            FlvTagType::Video => self
+                .outbox
+                .push_frame(bytes, frame, tag.keyframe, media_time),
+            _ => {
+                self.outbox.push_control(bytes);
+                0
+            }
+        }
+    }
+
+    /// Handles incoming messages and writes queued data.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::StreamDisconnected` if the server closed the
+    /// connection, and other I/O errors from the socket.
+    pub fn pump(&mut self) -> Result<usize> {
+        let mut buf = [0u8; 4096];
+        loop {
+            match self.socket.read(&mut buf) {
+                Ok(0) => return Err(IoError::StreamDisconnected),
+                Ok(n) => self.received(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        while let Some(message) = self.reader.next_message()? {
+            self.handle_control(&message)?;
+        }
+        Ok(self.outbox.pump(&mut self.socket)?)
+    }
+
+    /// Returns the send queue.
+    pub fn outbox(&self) -> &Outbox {
+        &self.outbox
+    }
+
+    /// Returns the message stream id assigned by the server.
+    pub fn stream_id(&self) -> u32 {
+        self.stream_id
+    }
+
+    /// Stops publishing and closes the connection.
+    pub fn close(mut self) {
+        let _ = self.socket.set_nonblocking(false);
+        let _ = self.outbox.pump(&mut self.socket);
+        let stream_id = self.stream_id;
+        let _ = self.command(
+            "deleteStream",
+            vec![Amf0Value::Null, Amf0Value::Number(stream_id as f64)],
+            0,
+        );
+        let _ = self.socket.shutdown(Shutdown::Both);
+    }
+}
 
 /// RTMP streamer for sending video to RTMP servers.
 ///
 /// Supports streaming to platforms like Twitch, YouTube, Facebook Live, etc.
+/// Video is encoded as H.264 and, if an audio encoder is attached with
+/// [`RtmpStreamer::with_audio`], samples passed to
+/// [`RtmpStreamer::send_audio`] are muxed as a second track.
+///
+/// When the connection fails, frames are dropped and reconnects are
+/// attempted with exponential backoff. When the send queue holds more than
+/// the high water mark, the encoder bitrate is lowered step by step down to
+/// the minimum bitrate, and raised again once the queue has drained.
 ///
 /// # Example
 ///
@@ -33,16 +781,30 @@ use crate::stream::encoder::{EncoderPreset, VideoCodec, VideoEncoder};
 #[cfg(feature = "stream")]
 pub struct RtmpStreamer {
     url: String,
+    target: RtmpUrl,
     encoder: VideoEncoder,
+    audio: Option<AudioEncoder>,
     format: VideoFormat,
+    muxer: FlvMuxer,
+    connection: Option<RtmpConnection>,
+    timeout: Duration,
+    max_latency: Duration,
+    backoff: Backoff,
+    abr: AdaptiveBitrate,
+    meter: BitrateMeter,
+    measured_bitrate: u64,
+    stream_start: Option<Duration>,
     frame_count: u64,
-    connected: bool,
+    frames_dropped: u64,
 }
 
 #[cfg(feature = "stream")]
 impl RtmpStreamer {
     /// Creates a new RTMP streamer.
     ///
+    /// The connection is opened by [`RtmpStreamer::connect`] or the first
+    /// frame.
+    ///
     /// # Parameters
     ///
     /// - `url` - RTMP URL (e.g., "rtmp://live.twitch.tv/app/stream_key")
@@ -50,15 +812,9 @@ impl RtmpStreamer {
     /// - `bitrate` - Target bitrate in bits per second
     pub fn new(url: impl Into<String>, format: VideoFormat, bitrate: u64) -> Result<Self> {
         let url = url.into();
+        let target = RtmpUrl::parse(&url)?;
 
-        // Validate RTMP URL
-        if !url.starts_with("rtmp://") && !url.starts_with("rtmps://") {
-            return Err(IoError::InvalidParameter(
-                "RTMP URL must start with rtmp:// or rtmps://".to_string(),
-            ));
-        }
-
-        tracing::info!("Creating RTMP streamer to {}", url);
+        tracing::info!("Creating RTMP streamer to {}", target.masked());
 
         let encoder = VideoEncoder::new(
             VideoCodec::H264,
@@ -69,10 +825,21 @@ impl RtmpStreamer {
 
         Ok(Self {
             url,
+            target,
             encoder,
+            audio: None,
             format,
+            muxer: FlvMuxer::new(None),
+            connection: None,
+            timeout: Duration::from_secs(5),
+            max_latency: Duration::from_secs(2),
+            backoff: Backoff::default(),
+            abr: AdaptiveBitrate::new(bitrate / 4, bitrate),
+            meter: BitrateMeter::default(),
+            measured_bitrate: 0,
+            stream_start: None,
             frame_count: 0,
-            connected: false,
+            frames_dropped: 0,
         })
     }
 
@@ -86,57 +853,192 @@ impl RtmpStreamer {
         Self::new(url, VideoFormat::hd_720p60_rgba(), 3_500_000)
     }
 
+    /// Adds an audio track encoded by `encoder`.
+    pub fn with_audio(mut self, encoder: AudioEncoder) -> Self {
+        self.muxer = FlvMuxer::new(Some(FlvAudioTrack::from_encoder(&encoder)));
+        self.audio = Some(encoder);
+        self
+    }
+
+    /// Sets the reconnect backoff.
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the lowest bitrate adaptive bitrate may choose.
+    pub fn with_min_bitrate(mut self, bitrate: u64) -> Self {
+        self.abr = AdaptiveBitrate::new(bitrate, self.abr_max());
+        self
+    }
+
+    /// Sets the connect and response timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how much media may be queued before frames are dropped.
+    pub fn with_max_latency(mut self, latency: Duration) -> Self {
+        self.max_latency = latency;
+        self
+    }
+
+    fn abr_max(&self) -> u64 {
+        self.abr.bitrate().max(self.encoder.bitrate())
+    }
+
     /// Connects to the RTMP server.
+    ///
+    /// # Errors
+    ///
+    /// Returns the connection error; the next attempt is scheduled with
+    /// exponential backoff.
     pub fn connect(&mut self) -> Result<()> {
-        if self.connected {
+        if self.connection.is_some() {
             return Ok(());
         }
 
-        tracing::info!("Connecting to RTMP server: {}", self.url);
-
-        // In a full implementation, this would:
-        // 1. Initialize FFmpeg's RTMP muxer
-        // 2. Perform RTMP handshake
-        // 3. Send stream metadata
-        // 4. Start encoding thread
-
-        self.connected = true;
-        Ok(())
+        tracing::info!("Connecting to RTMP server: {}", self.target.masked());
+        match RtmpConnection::connect(&self.target, self.timeout, self.max_latency) {
+            Ok(mut connection) => {
+                self.muxer.reset();
+                let metadata = self.muxer.metadata(&self.format, self.encoder.bitrate());
+                connection.send_tag(&metadata, 0, Duration::ZERO);
+                self.encoder.request_keyframe();
+                self.connection = Some(connection);
+                self.backoff.reset();
+                Ok(())
+            }
+            Err(e) => {
+                let delay = self.backoff.fail(Instant::now());
+                tracing::warn!("RTMP connection failed ({}), retrying in {:?}", e, delay);
+                Err(e)
+            }
+        }
     }
 
     /// Disconnects from the RTMP server.
     pub fn disconnect(&mut self) -> Result<()> {
-        if !self.connected {
+        let Some(connection) = self.connection.take() else {
             return Ok(());
-        }
+        };
 
         tracing::info!("Disconnecting from RTMP server");
 
         // Flush encoder
         self.encoder.flush()?;
-
-        self.connected = false;
+        connection.close();
         Ok(())
     }
 
     /// Returns true if connected to the server.
     pub fn is_connected(&self) -> bool {
-        self.connected
+        self.connection.is_some()
+    }
+
+    /// Returns the RTMP URL with the stream key hidden.
+    pub fn url(&self) -> String {
+        self.target.masked()
     }
 
-    /// Returns the RTMP URL (without stream key exposed).
-    pub fn url(&self) -> &str {
-        // In production, we'd mask the stream key
+    /// Returns the full RTMP URL including the stream key.
+    pub fn full_url(&self) -> &str {
         &self.url
     }
 
+    /// Returns the current target bitrate of the encoder.
+    pub fn target_bitrate(&self) -> u64 {
+        self.encoder.bitrate()
+    }
+
     /// Returns statistics about the stream.
+    ///
+    /// The bitrate is the measured output rate over the last two seconds,
+    /// and the latency is the media time waiting in the send queue.
     pub fn statistics(&self) -> SinkStatistics {
         SinkStatistics {
             frames_sent: self.frame_count,
-            frames_dropped: 0, // Would track actual drops in full implementation
-            bitrate: Some(self.encoder.bitrate()),
-            average_latency_ms: None,
+            frames_dropped: self.frames_dropped,
+            bitrate: Some(self.measured_bitrate),
+            average_latency_ms: self
+                .connection
+                .as_ref()
+                .map(|c| c.outbox().latency().as_secs_f32() * 1000.0),
+        }
+    }
+
+    /// Encodes and sends audio from the active audio input.
+    ///
+    /// `frame.timestamp` must use the same clock as the video frames. Audio
+    /// sent while disconnected is discarded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::InvalidParameter` if no audio track was added.
+    pub fn send_audio(&mut self, frame: &AudioFrame) -> Result<()> {
+        let encoder = self.audio.as_mut().ok_or_else(|| {
+            IoError::InvalidParameter("RTMP streamer has no audio track".to_string())
+        })?;
+        let packets = encoder.encode(frame)?;
+        let Some(connection) = self.connection.as_mut() else {
+            return Ok(());
+        };
+
+        let start = *self.stream_start.get_or_insert(frame.timestamp);
+        for packet in packets {
+            let time = packet.pts.saturating_sub(start);
+            for tag in self.muxer.audio(time.as_millis() as u32, &packet.data)? {
+                connection.send_tag(&tag, self.frame_count, time);
+            }
+        }
+        self.pump()
+    }
+
+    /// Muxes encoded video into FLV tags and queues them.
+    ///
+    /// The muxer sends the AVC sequence header from the SPS/PPS of the
+    /// first keyframe.
+    fn send_packets(&mut self, packets: &[EncodedPacket]) {
+        let start = self.stream_start.unwrap_or_default();
+        for packet in packets {
+            let time = packet.decode_time().saturating_sub(start);
+            let tags = self.muxer.video(
+                time.as_millis() as u32,
+                (packet.pts - packet.dts) as i32,
+                &packet.data,
+                packet.is_keyframe,
+            );
+            if let Some(connection) = self.connection.as_mut() {
+                for tag in &tags {
+                    self.frames_dropped += connection.send_tag(tag, self.frame_count, time);
+                }
+            }
+        }
+    }
+
+    /// Writes queued data and adapts the bitrate to the queue.
+    fn pump(&mut self) -> Result<()> {
+        let Some(connection) = self.connection.as_mut() else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        match connection.pump() {
+            Ok(written) => {
+                self.meter.record(written, now);
+                self.measured_bitrate = self.meter.bitrate(now);
+                if let Some(bitrate) = self.abr.update(connection.outbox().latency(), now) {
+                    tracing::info!("Adapting RTMP bitrate to {} bps", bitrate);
+                    self.encoder.set_bitrate(bitrate);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                tracing::warn!("RTMP connection lost: {}", e);
+                self.connection = None;
+                self.backoff.fail(now);
+                Err(IoError::StreamDisconnected)
+            }
         }
     }
 }
@@ -152,26 +1054,33 @@ impl VideoSink for RtmpStreamer {
     }
 
     fn send_frame(&mut self, frame: &VideoFrame) -> Result<()> {
-        if !self.connected {
-            self.connect()?;
-        }
-
         // Validate frame
         frame.validate()?;
 
-        // Encode frame
-        let _packet = self.encoder.encode(frame)?;
+        if self.connection.is_none() {
+            if !self.backoff.is_ready(Instant::now()) {
+                self.frames_dropped += 1;
+                return Err(IoError::StreamDisconnected);
+            }
+            if let Err(e) = self.connect() {
+                self.frames_dropped += 1;
+                return Err(e);
+            }
+        }
 
-        // In a full implementation, this would send the packet via RTMP
+        // Encode frame
+        let packets = self.encoder.encode(frame)?;
+        self.stream_start.get_or_insert(frame.timestamp);
+        self.send_packets(&packets);
         self.frame_count += 1;
 
         tracing::trace!("Sent frame {} to RTMP stream", self.frame_count);
 
-        Ok(())
+        self.pump()
     }
 
     fn is_available(&self) -> bool {
-        self.connected
+        self.connection.is_some()
     }
 
     fn frame_count(&self) -> u64 {
@@ -179,12 +1088,14 @@ impl VideoSink for RtmpStreamer {
     }
 
     fn flush(&mut self) -> Result<()> {
-        self.encoder.flush()?;
-        Ok(())
+        let packets = self.encoder.flush()?;
+        self.send_packets(&packets);
+        self.pump()
     }
 
     fn reconnect(&mut self) -> Result<()> {
         self.disconnect()?;
+        self.backoff.reset();
         self.connect()
     }
 
@@ -196,7 +1107,7 @@ impl VideoSink for RtmpStreamer {
 #[cfg(feature = "stream")]
 impl Drop for RtmpStreamer {
     fn drop(&mut self) {
-        if self.connected {
+        if self.connection.is_some() {
             let _ = self.disconnect();
         }
     }
@@ -223,67 +1134,429 @@ impl RtmpStreamer {
 }
 
 #[cfg(test)]
-#[cfg(feature = "stream")]
+pub(crate) mod test_server {
+    //! Minimal RTMP server that accepts one publisher.
+
+    use super::*;
+    use std::net::TcpListener;
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread::{self, JoinHandle};
+
+    /// Starts a server on a free port, returning its port, the received
+    /// media messages, and the server thread.
+    pub(crate) fn spawn(
+        reject_publish: bool,
+    ) -> (u16, Receiver<RtmpMessage>, JoinHandle<io::Result<()>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            let (mut socket, _) = listener.accept()?;
+            socket.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+            let mut c0c1 = vec![0u8; 1 + HANDSHAKE_SIZE];
+            socket.read_exact(&mut c0c1)?;
+            let mut response = vec![3u8];
+            response.extend(vec![7u8; HANDSHAKE_SIZE]);
+            response.extend_from_slice(&c0c1[1..]);
+            socket.write_all(&response)?;
+            let mut c2 = vec![0u8; HANDSHAKE_SIZE];
+            socket.read_exact(&mut c2)?;
+            assert!(c2.iter().all(|&b| b == 7), "C2 must echo S1");
+
+            let mut reader = ChunkReader::new();
+            let writer = ChunkWriter::new();
+            let reply = |socket: &mut TcpStream, stream_id, values: Vec<Amf0Value>| {
+                let bytes = writer.write(
+                    CSID_COMMAND,
+                    &RtmpMessage {
+                        kind: MSG_COMMAND,
+                        stream_id,
+                        timestamp: 0,
+                        payload: amf::encode_all(&values),
+                    },
+                );
+                socket.write_all(&bytes)
+            };
+
+            // Ping the client to check it answers
+            let ping = writer.write(
+                CSID_PROTOCOL,
+                &RtmpMessage {
+                    kind: MSG_USER_CONTROL,
+                    stream_id: 0,
+                    timestamp: 0,
+                    payload: vec![0, 6, 0, 0, 0, 42],
+                },
+            );
+            socket.write_all(&ping)?;
+
+            let mut buf = [0u8; 65536];
+            loop {
+                let n = socket.read(&mut buf)?;
+                if n == 0 {
+                    return Ok(());
+                }
+                reader.push(&buf[..n]);
+                while let Some(message) = reader.next_message().unwrap() {
+                    if message.kind == MSG_SET_CHUNK_SIZE {
+                        continue;
+                    }
+                    if message.kind != MSG_COMMAND {
+                        let _ = sender.send(message);
+                        continue;
+                    }
+                    let values = message.amf_values().unwrap();
+                    let transaction = values[1].clone();
+                    match values[0].as_str() {
+                        Some("connect") => {
+                            assert_eq!(
+                                values[2].get("app").and_then(Amf0Value::as_str),
+                                Some("live")
+                            );
+                            let status = Amf0Value::object([(
+                                "code",
+                                Amf0Value::string("NetConnection.Connect.Success"),
+                            )]);
+                            reply(
+                                &mut socket,
+                                0,
+                                vec![
+                                    Amf0Value::string("_result"),
+                                    transaction,
+                                    Amf0Value::Null,
+                                    status,
+                                ],
+                            )?;
+                        }
+                        Some("createStream") => reply(
+                            &mut socket,
+                            0,
+                            vec![
+                                Amf0Value::string("_result"),
+                                transaction,
+                                Amf0Value::Null,
+                                Amf0Value::Number(1.0),
+                            ],
+                        )?,
+                        Some("publish") => {
+                            assert_eq!(message.stream_id, 1);
+                            let (level, code) = if reject_publish {
+                                ("error", "NetStream.Publish.BadName")
+                            } else {
+                                ("status", "NetStream.Publish.Start")
+                            };
+                            let info = Amf0Value::object([
+                                ("level", Amf0Value::string(level)),
+                                ("code", Amf0Value::string(code)),
+                            ]);
+                            reply(
+                                &mut socket,
+                                1,
+                                vec![
+                                    Amf0Value::string("onStatus"),
+                                    Amf0Value::Number(0.0),
+                                    Amf0Value::Null,
+                                    info,
+                                ],
+                            )?;
+                        }
+                        Some("deleteStream") => return Ok(()),
+                        _ => {}
+                    }
+                }
+            }
+        });
+
+        (port, receiver, handle)
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Instant;
 
     #[test]
-    fn test_rtmp_streamer_creation() {
-        let format = VideoFormat::hd_1080p60_rgba();
-        let streamer = RtmpStreamer::new("rtmp://localhost/live/stream", format, 6_000_000);
-        assert!(streamer.is_ok());
+    fn test_parse_url() {
+        let url = RtmpUrl::parse("rtmp://live.twitch.tv/app/live_123_abc").unwrap();
+        assert_eq!(url.host, "live.twitch.tv");
+        assert_eq!(url.port, DEFAULT_PORT);
+        assert_eq!(url.app, "app");
+        assert_eq!(url.stream_key, "live_123_abc");
+        assert_eq!(url.masked(), "rtmp://live.twitch.tv:1935/app/****");
+
+        let url = RtmpUrl::parse("rtmps://example.com:8443/live/a/b").unwrap();
+        assert!(url.secure);
+        assert_eq!(url.port, 8443);
+        assert_eq!(url.stream_key, "a/b");
+
+        assert!(RtmpUrl::parse("http://localhost/live/key").is_err());
+        assert!(RtmpUrl::parse("rtmp://localhost/live").is_err());
+        assert!(RtmpUrl::parse("rtmp://localhost:port/live/key").is_err());
     }
 
     #[test]
-    fn test_rtmp_streamer_invalid_url() {
-        let format = VideoFormat::hd_1080p60_rgba();
-        let streamer = RtmpStreamer::new("http://localhost/stream", format, 6_000_000);
-        assert!(streamer.is_err());
+    fn test_chunk_round_trip() {
+        let mut writer = ChunkWriter::new();
+        let mut reader = ChunkReader::new();
+        let big = RtmpMessage {
+            kind: FlvTagType::Video as u8,
+            stream_id: 1,
+            timestamp: 0x0100_0000,
+            payload: (0..1000).map(|i| i as u8).collect(),
+        };
+        let small = RtmpMessage {
+            kind: MSG_COMMAND,
+            stream_id: 0,
+            timestamp: 5,
+            payload: vec![1, 2, 3],
+        };
+
+        let mut bytes = writer.write(CSID_VIDEO, &big);
+        // 1000 bytes in 128-byte chunks: 7 continuation headers with
+        // extended timestamps
+        assert_eq!(bytes.len(), 1 + 11 + 4 + 1000 + 7 * (1 + 4));
+        bytes.extend(writer.set_chunk_size(4096));
+        bytes.extend(writer.write(CSID_VIDEO, &big));
+        bytes.extend(writer.write(CSID_COMMAND, &small));
+
+        // Deliver in small pieces
+        let mut messages = Vec::new();
+        for piece in bytes.chunks(100) {
+            reader.push(piece);
+            while let Some(message) = reader.next_message().unwrap() {
+                messages.push(message);
+            }
+        }
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0], big);
+        assert_eq!(messages[1].kind, MSG_SET_CHUNK_SIZE);
+        assert_eq!(messages[2], big);
+        assert_eq!(messages[3], small);
     }
 
     #[test]
-    fn test_rtmp_streamer_default_presets() {
-        let streamer = RtmpStreamer::default_1080p60("rtmp://localhost/live/stream");
-        assert!(streamer.is_ok());
+    fn test_reader_compressed_headers() {
+        // Type 0, then type 2 (delta 10), then type 3 (repeat delta)
+        let mut bytes = vec![0x04, 0, 0, 100, 0, 0, 2, 8, 1, 0, 0, 0, 0xAA, 0xBB];
+        bytes.extend_from_slice(&[0x84, 0, 0, 10, 0xCC, 0xDD]);
+        bytes.extend_from_slice(&[0xC4, 0xEE, 0xFF]);
+
+        let mut reader = ChunkReader::new();
+        reader.push(&bytes);
+        let timestamps: Vec<_> = std::iter::from_fn(|| reader.next_message().unwrap())
+            .map(|m| (m.timestamp, m.payload))
+            .collect();
+        assert_eq!(
+            timestamps,
+            [
+                (100, vec![0xAA, 0xBB]),
+                (110, vec![0xCC, 0xDD]),
+                (120, vec![0xEE, 0xFF])
+            ]
+        );
 
-        let streamer = RtmpStreamer::default_720p60("rtmp://localhost/live/stream");
-        assert!(streamer.is_ok());
+        let mut reader = ChunkReader::new();
+        reader.push(&[0xC9]);
+        assert!(reader.next_message().is_err());
     }
 
     #[test]
-    fn test_rtmp_streamer_connect() {
-        let format = VideoFormat::hd_1080p60_rgba();
-        let mut streamer =
-            RtmpStreamer::new("rtmp://localhost/live/stream", format, 6_000_000).unwrap();
+    fn test_publish_to_local_server() {
+        let (port, media, server) = test_server::spawn(false);
+        let url = RtmpUrl::parse(&format!("rtmp://127.0.0.1:{}/live/key", port)).unwrap();
+        let mut connection =
+            RtmpConnection::connect(&url, Duration::from_secs(5), Duration::from_secs(1)).unwrap();
+        assert_eq!(connection.stream_id(), 1);
 
-        assert!(!streamer.is_connected());
-        assert!(streamer.connect().is_ok());
-        assert!(streamer.is_connected());
+        let tag = FlvTag {
+            kind: FlvTagType::Video,
+            timestamp: 40,
+            body: vec![0x17, 1, 0, 0, 0, 0, 0, 0, 1, 0x65],
+            keyframe: true,
+        };
+        connection.send_tag(&tag, 0, Duration::ZERO);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !connection.outbox().is_empty() && Instant::now() < deadline {
+            connection.pump().unwrap();
+        }
+
+        // The ping response arrives before the video message
+        let pong = media.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(pong.kind, MSG_USER_CONTROL);
+        assert_eq!(pong.payload, [0, 7, 0, 0, 0, 42]);
+        let video = media.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(video.kind, FlvTagType::Video as u8);
+        assert_eq!(video.stream_id, 1);
+        assert_eq!(video.timestamp, 40);
+        assert_eq!(video.payload, tag.body);
+
+        connection.close();
+        server.join().unwrap().unwrap();
     }
 
     #[test]
-    fn test_rtmp_streamer_send_frame() {
-        let format = VideoFormat::hd_1080p60_rgba();
-        let mut streamer =
-            RtmpStreamer::new("rtmp://localhost/live/stream", format.clone(), 6_000_000).unwrap();
-
-        let frame = VideoFrame::empty(format);
-        assert!(streamer.send_frame(&frame).is_ok());
-        assert_eq!(streamer.frame_count(), 1);
-        assert!(streamer.is_connected());
+    fn test_publish_rejected() {
+        let (port, _media, _server) = test_server::spawn(true);
+        let url = RtmpUrl::parse(&format!("rtmp://127.0.0.1:{}/live/key", port)).unwrap();
+        let result = RtmpConnection::connect(&url, Duration::from_secs(5), Duration::from_secs(1));
+        assert!(matches!(result, Err(IoError::RtmpError(_))));
     }
 
     #[test]
-    fn test_rtmp_streamer_statistics() {
-        let format = VideoFormat::hd_1080p60_rgba();
-        let mut streamer =
-            RtmpStreamer::new("rtmp://localhost/live/stream", format.clone(), 6_000_000).unwrap();
+    fn test_connect_refused() {
+        // Bind and drop a listener to find a closed port
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = RtmpUrl::parse(&format!("rtmp://127.0.0.1:{}/live/key", port)).unwrap();
+        let result = RtmpConnection::connect(&url, Duration::from_secs(1), Duration::from_secs(1));
+        assert!(matches!(result, Err(IoError::StreamConnectionFailed(_))));
+    }
+
+    #[cfg(feature = "stream")]
+    mod streamer {
+        use super::super::*;
+        use crate::format::VideoFormat;
+        use crate::stream::audio::AudioCodec;
+
+        #[test]
+        fn test_rtmp_streamer_creation() {
+            let format = VideoFormat::hd_1080p60_rgba();
+            let streamer = RtmpStreamer::new("rtmp://localhost/live/stream", format, 6_000_000);
+            assert!(streamer.is_ok());
+        }
 
-        let frame = VideoFrame::empty(format);
-        streamer.send_frame(&frame).unwrap();
+        #[test]
+        fn test_rtmp_streamer_invalid_url() {
+            let format = VideoFormat::hd_1080p60_rgba();
+            let streamer = RtmpStreamer::new("http://localhost/stream", format, 6_000_000);
+            assert!(streamer.is_err());
+        }
+
+        #[test]
+        fn test_rtmp_streamer_default_presets() {
+            let streamer = RtmpStreamer::default_1080p60("rtmp://localhost/live/stream");
+            assert!(streamer.is_ok());
+
+            let streamer = RtmpStreamer::default_720p60("rtmp://localhost/live/stream");
+            assert!(streamer.is_ok());
+        }
 
-        let stats = streamer.statistics();
-        assert_eq!(stats.frames_sent, 1);
-        assert!(stats.bitrate.is_some());
+        #[test]
+        fn test_rtmp_streamer_connect() {
+            let (port, _media, _server) = test_server::spawn(false);
+            let format = VideoFormat::hd_1080p60_rgba();
+            let mut streamer = RtmpStreamer::new(
+                format!("rtmp://127.0.0.1:{}/live/stream", port),
+                format,
+                6_000_000,
+            )
+            .unwrap();
+
+            assert!(!streamer.is_connected());
+            assert!(streamer.connect().is_ok());
+            assert!(streamer.is_connected());
+            assert_eq!(
+                streamer.url(),
+                format!("rtmp://127.0.0.1:{}/live/****", port)
+            );
+        }
+
+        #[test]
+        fn test_rtmp_streamer_send_frame() {
+            let (port, media, _server) = test_server::spawn(false);
+            let format = VideoFormat::new(64, 64, crate::format::PixelFormat::RGBA8, 30.0);
+            let mut streamer = RtmpStreamer::new(
+                format!("rtmp://127.0.0.1:{}/live/stream", port),
+                format.clone(),
+                1_000_000,
+            )
+            .unwrap()
+            .with_audio(AudioEncoder::new(AudioCodec::Aac, 44100, 2).unwrap());
+
+            for index in 0..5 {
+                let mut frame = VideoFrame::empty(format.clone());
+                frame.timestamp = format.frame_duration() * index;
+                assert!(streamer.send_frame(&frame).is_ok());
+                let audio = AudioFrame::new(vec![0.25; 1470 * 2], 44100, 2, frame.timestamp);
+                streamer.send_audio(&audio).unwrap();
+            }
+            assert_eq!(streamer.frame_count(), 5);
+            assert!(streamer.is_connected());
+
+            // Metadata first
+            let metadata = loop {
+                let message = media.recv_timeout(Duration::from_secs(5)).unwrap();
+                if message.kind == FlvTagType::Script as u8 {
+                    break message;
+                }
+            };
+            let values = metadata.amf_values().unwrap();
+            assert_eq!(values[0].as_str(), Some("@setDataFrame"));
+            assert_eq!(values[1].as_str(), Some("onMetaData"));
+
+            let (mut video, mut audio) = (Vec::new(), Vec::new());
+            while video.len() < 6 || audio.len() < 3 {
+                let message = media.recv_timeout(Duration::from_secs(5)).unwrap();
+                if message.kind == FlvTagType::Video as u8 {
+                    video.push(message.payload);
+                } else if message.kind == FlvTagType::Audio as u8 {
+                    audio.push(message.payload);
+                }
+            }
+
+            // AVC sequence header, the keyframe, then inter frames
+            assert_eq!(video[0][..2], [0x17, 0x00]);
+            assert_eq!(video[1][..2], [0x17, 0x01]);
+            assert!(video[2..]
+                .iter()
+                .all(|body| body[..2] == [0x27, 0x01] && body.len() > 5));
+
+            // AudioSpecificConfig, then raw AAC frames
+            assert_eq!(audio[0], [0xAF, 0x00, 0x12, 0x10]);
+            assert!(audio[1..]
+                .iter()
+                .all(|body| body[..2] == [0xAF, 0x01] && body.len() > 2));
+        }
+
+        #[test]
+        fn test_rtmp_streamer_backoff() {
+            let port = std::net::TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port();
+            let format = VideoFormat::new(64, 64, crate::format::PixelFormat::RGBA8, 30.0);
+            let mut streamer = RtmpStreamer::new(
+                format!("rtmp://127.0.0.1:{}/live/stream", port),
+                format.clone(),
+                1_000_000,
+            )
+            .unwrap()
+            .with_backoff(Backoff::new(
+                Duration::from_secs(60),
+                Duration::from_secs(60),
+            ));
+
+            let frame = VideoFrame::empty(format);
+            assert!(matches!(
+                streamer.send_frame(&frame),
+                Err(IoError::StreamConnectionFailed(_))
+            ));
+            // Waiting for the backoff: dropped without another attempt
+            assert!(matches!(
+                streamer.send_frame(&frame),
+                Err(IoError::StreamDisconnected)
+            ));
+
+            let stats = streamer.statistics();
+            assert_eq!(stats.frames_sent, 0);
+            assert_eq!(stats.frames_dropped, 2);
+        }
     }
 }
@@ -1,24 +1,740 @@
-//! SRT streaming output (stub implementation).
+//! SRT streaming output.
 //!
-//! Secure Reliable Transport (SRT) is a protocol for low-latency video streaming.
-//! This is currently a stub implementation.
+//! Secure Reliable Transport (SRT) is a protocol for low-latency video
+//! streaming over UDP. [`SrtConnection`] implements an SRT caller in live
+//! mode: the version 5 handshake, data packets carrying seven MPEG-TS
+//! packets each, retransmission of packets the receiver reports lost, and
+//! keepalives. Encryption is not supported.
+//!
+//! [`SrtStreamer`] encodes frames, muxes them into an MPEG transport stream
+//! and delivers them over the connection, with the same reconnect and
+//! adaptive bitrate behavior as the RTMP streamer.
 
-#[cfg(feature = "stream")]
+use super::mpegts::TS_PACKET_SIZE;
+use super::transport::Outbox;
 use crate::error::{IoError, Result};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "stream")]
+use super::audio::{adts_header, AudioCodec, AudioEncoder, AudioFrame};
+#[cfg(feature = "stream")]
+use super::mpegts::TsMuxer;
+#[cfg(feature = "stream")]
+use super::transport::{AdaptiveBitrate, Backoff, BitrateMeter};
 #[cfg(feature = "stream")]
 use crate::format::{VideoFormat, VideoFrame};
 #[cfg(feature = "stream")]
 use crate::sink::{SinkStatistics, VideoSink};
+#[cfg(feature = "stream")]
+use crate::stream::encoder::{EncodedPacket, EncoderPreset, VideoCodec, VideoEncoder};
+
+/// Payload of one SRT data packet: seven transport stream packets.
+pub const PAYLOAD_SIZE: usize = 7 * TS_PACKET_SIZE;
+
+const HEADER_SIZE: usize = 16;
+const HANDSHAKE_CIF_SIZE: usize = 48;
+const SRT_MAGIC: u16 = 0x4A17;
+const SRT_VERSION: u32 = 0x0001_0500;
+
+const CONTROL_HANDSHAKE: u16 = 0;
+const CONTROL_KEEPALIVE: u16 = 1;
+const CONTROL_ACK: u16 = 2;
+const CONTROL_NAK: u16 = 3;
+const CONTROL_SHUTDOWN: u16 = 5;
+const CONTROL_ACKACK: u16 = 6;
+
+const HS_INDUCTION: u32 = 1;
+const HS_CONCLUSION: u32 = 0xFFFF_FFFF;
+const HS_EXT_HSREQ: u16 = 1;
+const HS_EXT_HSRSP: u16 = 2;
+const HS_EXT_SID: u16 = 5;
+const HS_FLAG_HSREQ: u16 = 0x1;
+const HS_FLAG_CONFIG: u16 = 0x4;
+
+/// TSBPD send and receive, too-late packet drop, periodic NAK, rexmit flag.
+const SRT_FLAGS: u32 = 0x01 | 0x02 | 0x08 | 0x10 | 0x20;
+
+/// The peer is considered gone after this long without any packet.
+const PEER_IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Parts of an `srt://host:port[?latency=ms&streamid=id]` URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrtUrl {
+    /// Listener host name or address
+    pub host: String,
+    /// Listener port
+    pub port: u16,
+    /// Receiver latency
+    pub latency: Duration,
+    /// Stream id sent to the listener
+    pub stream_id: Option<String>,
+}
+
+impl SrtUrl {
+    /// Parses an SRT URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::InvalidParameter` if the scheme is not `srt://`,
+    /// the port is missing, or a query parameter is malformed.
+    pub fn parse(url: &str) -> Result<Self> {
+        let rest = url.strip_prefix("srt://").ok_or_else(|| {
+            IoError::InvalidParameter("SRT URL must start with srt://".to_string())
+        })?;
+        let invalid =
+            |what: &str| IoError::InvalidParameter(format!("Invalid SRT URL {}: {}", url, what));
+
+        let (authority, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let authority = authority.trim_end_matches('/');
+        let (host, port) = authority
+            .rsplit_once(':')
+            .ok_or_else(|| invalid("missing port"))?;
+        let port = port.parse().map_err(|_| invalid("bad port"))?;
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+
+        let mut parsed = Self {
+            host: host.to_string(),
+            port,
+            latency: Duration::from_millis(120),
+            stream_id: None,
+        };
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            match key {
+                "latency" => {
+                    let ms = value.parse().map_err(|_| invalid("bad latency"))?;
+                    parsed.latency = Duration::from_millis(ms);
+                }
+                "streamid" => parsed.stream_id = Some(value.to_string()),
+                "mode" if value != "caller" => {
+                    return Err(invalid("only caller mode is supported"))
+                }
+                _ => tracing::debug!("Ignoring SRT option {}", key),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Handshake control information field.
+#[derive(Debug, Clone, PartialEq)]
+struct Handshake {
+    version: u32,
+    encryption: u16,
+    extension: u16,
+    initial_sequence: u32,
+    mtu: u32,
+    flow_window: u32,
+    kind: u32,
+    socket_id: u32,
+    cookie: u32,
+    extensions: Vec<(u16, Vec<u32>)>,
+}
+
+impl Handshake {
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HANDSHAKE_CIF_SIZE + 32);
+        out.extend_from_slice(&self.version.to_be_bytes());
+        out.extend_from_slice(&self.encryption.to_be_bytes());
+        out.extend_from_slice(&self.extension.to_be_bytes());
+        for word in [
+            self.initial_sequence,
+            self.mtu,
+            self.flow_window,
+            self.kind,
+            self.socket_id,
+            self.cookie,
+        ] {
+            out.extend_from_slice(&word.to_be_bytes());
+        }
+        // Peer IP, unused by the listener for callers
+        out.extend_from_slice(&[0; 16]);
+        for (kind, words) in &self.extensions {
+            out.extend_from_slice(&kind.to_be_bytes());
+            out.extend_from_slice(&(words.len() as u16).to_be_bytes());
+            for word in words {
+                out.extend_from_slice(&word.to_be_bytes());
+            }
+        }
+        out
+    }
+
+    fn decode(cif: &[u8]) -> Option<Self> {
+        if cif.len() < HANDSHAKE_CIF_SIZE {
+            return None;
+        }
+        let word = |i: usize| u32::from_be_bytes(cif[i..i + 4].try_into().expect("4"));
+        let mut extensions = Vec::new();
+        let mut pos = HANDSHAKE_CIF_SIZE;
+        while pos + 4 <= cif.len() {
+            let kind = u16::from_be_bytes([cif[pos], cif[pos + 1]]);
+            let len = u16::from_be_bytes([cif[pos + 2], cif[pos + 3]]) as usize;
+            pos += 4;
+            if pos + len * 4 > cif.len() {
+                break;
+            }
+            extensions.push((kind, (0..len).map(|i| word(pos + i * 4)).collect()));
+            pos += len * 4;
+        }
+        Some(Self {
+            version: word(0),
+            encryption: u16::from_be_bytes([cif[4], cif[5]]),
+            extension: u16::from_be_bytes([cif[6], cif[7]]),
+            initial_sequence: word(8),
+            mtu: word(12),
+            flow_window: word(16),
+            kind: word(20),
+            socket_id: word(24),
+            cookie: word(28),
+            extensions,
+        })
+    }
+}
+
+/// Encodes a stream id as SRT extension words.
+///
+/// The string is padded to whole words and each word is sent byte-reversed.
+fn stream_id_words(id: &str) -> Vec<u32> {
+    id.as_bytes()
+        .chunks(4)
+        .map(|chunk| {
+            let mut word = [0u8; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u32::from_le_bytes(word)
+        })
+        .collect()
+}
+
+/// Builds a control packet.
+fn control_packet(kind: u16, info: u32, timestamp: u32, dest: u32, cif: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_SIZE + cif.len());
+    out.extend_from_slice(&(0x8000_0000 | (kind as u32) << 16).to_be_bytes());
+    out.extend_from_slice(&info.to_be_bytes());
+    out.extend_from_slice(&timestamp.to_be_bytes());
+    out.extend_from_slice(&dest.to_be_bytes());
+    out.extend_from_slice(cif);
+    out
+}
+
+/// A parsed incoming packet header.
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    control: Option<u16>,
+    info: u32,
+}
+
+fn parse_header(packet: &[u8]) -> Option<Header> {
+    if packet.len() < HEADER_SIZE {
+        return None;
+    }
+    let word0 = u32::from_be_bytes(packet[0..4].try_into().expect("4"));
+    let control = (word0 & 0x8000_0000 != 0).then_some(((word0 >> 16) & 0x7FFF) as u16);
+    Some(Header {
+        control,
+        info: u32::from_be_bytes(packet[4..8].try_into().expect("4")),
+    })
+}
+
+/// A sent data packet kept for retransmission.
+#[derive(Debug)]
+struct SentPacket {
+    sequence: u32,
+    packet: Vec<u8>,
+    sent_at: Instant,
+}
+
+/// Pseudo-random 31-bit value for socket ids and sequence numbers.
+fn random_u31(salt: u32) -> u32 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos() as u64 ^ d.as_secs());
+    let mut x = nanos ^ (salt as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    (x as u32) & 0x7FFF_FFFF
+}
+
+/// Socket state of a connection, separate from its send queue.
+struct Link {
+    socket: UdpSocket,
+    peer_socket_id: u32,
+    started: Instant,
+    next_sequence: u32,
+    next_message: u32,
+    latency: Duration,
+    sent: VecDeque<SentPacket>,
+    max_bandwidth: u64,
+    tokens: f64,
+    last_refill: Instant,
+    last_sent: Instant,
+    last_received: Instant,
+    retransmitted: u64,
+    rtt: Option<Duration>,
+}
+
+impl Link {
+    fn timestamp(&self) -> u32 {
+        self.started.elapsed().as_micros() as u32
+    }
+
+    /// Adds pacing credit for the time since the last refill.
+    fn refill(&mut self, now: Instant) {
+        // Hold at most 20 ms of data so bursts stay short
+        let rate = self.max_bandwidth as f64 / 8.0;
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        let limit = rate * 0.02 + (PAYLOAD_SIZE + HEADER_SIZE) as f64;
+        self.tokens = (self.tokens + elapsed * rate).min(limit);
+        self.last_refill = now;
+    }
+
+    /// Sends one data packet, or returns `WouldBlock` when over the pacing rate.
+    fn send_data(&mut self, payload: &[u8]) -> io::Result<usize> {
+        let size = payload.len() + HEADER_SIZE;
+        if self.tokens < size as f64 {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+
+        let sequence = self.next_sequence;
+        // Solo packet (PP = 11), in order flag clear, no encryption
+        let message = 0xC000_0000 | (self.next_message & 0x03FF_FFFF);
+        let mut packet = Vec::with_capacity(size);
+        packet.extend_from_slice(&sequence.to_be_bytes());
+        packet.extend_from_slice(&message.to_be_bytes());
+        packet.extend_from_slice(&self.timestamp().to_be_bytes());
+        packet.extend_from_slice(&self.peer_socket_id.to_be_bytes());
+        packet.extend_from_slice(payload);
+
+        self.socket.send(&packet)?;
+        self.tokens -= size as f64;
+        self.next_sequence = (sequence + 1) & 0x7FFF_FFFF;
+        self.next_message = (self.next_message + 1) & 0x03FF_FFFF;
+        let now = Instant::now();
+        self.last_sent = now;
+        self.sent.push_back(SentPacket {
+            sequence,
+            packet,
+            sent_at: now,
+        });
+        Ok(payload.len())
+    }
+
+    fn send_control(&mut self, kind: u16, info: u32, cif: &[u8]) -> io::Result<()> {
+        let packet = control_packet(kind, info, self.timestamp(), self.peer_socket_id, cif);
+        match self.socket.send(&packet) {
+            Ok(_) => {
+                self.last_sent = Instant::now();
+                Ok(())
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Handles all pending control packets.
+    fn receive(&mut self) -> Result<()> {
+        let mut buf = [0u8; 1500];
+        loop {
+            let n = match self.socket.recv(&mut buf) {
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                // A closed port shows up as a receive error on connected UDP sockets
+                Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+                    return Err(IoError::StreamDisconnected)
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let packet = &buf[..n];
+            let Some(header) = parse_header(packet) else {
+                continue;
+            };
+            self.last_received = Instant::now();
+            match header.control {
+                Some(CONTROL_ACK) => {
+                    // Full ACKs carry an ACK number that is confirmed with ACKACK
+                    if header.info != 0 {
+                        self.send_control(CONTROL_ACKACK, header.info, &[])?;
+                    }
+                    let word = |i: usize| {
+                        packet
+                            .get(i..i + 4)
+                            .map(|w| u32::from_be_bytes(w.try_into().expect("4")))
+                    };
+                    if let Some(next_expected) = word(HEADER_SIZE) {
+                        self.acknowledge(next_expected);
+                    }
+                    if let Some(rtt) = word(HEADER_SIZE + 4) {
+                        self.rtt = Some(Duration::from_micros(rtt as u64));
+                    }
+                }
+                Some(CONTROL_NAK) => {
+                    let losses = parse_loss_list(&packet[HEADER_SIZE..]);
+                    self.retransmit(&losses)?;
+                }
+                Some(CONTROL_SHUTDOWN) => return Err(IoError::StreamDisconnected),
+                _ => {}
+            }
+        }
+    }
+
+    /// Forgets packets the receiver has confirmed.
+    fn acknowledge(&mut self, next_expected: u32) {
+        while self
+            .sent
+            .front()
+            .is_some_and(|p| sequence_before(p.sequence, next_expected))
+        {
+            self.sent.pop_front();
+        }
+    }
+
+    /// Forgets packets too old to be useful to the receiver.
+    fn expire_sent(&mut self, now: Instant) {
+        let keep = self.latency * 2 + Duration::from_millis(100);
+        while self
+            .sent
+            .front()
+            .is_some_and(|p| now.duration_since(p.sent_at) > keep)
+        {
+            self.sent.pop_front();
+        }
+    }
+
+    fn retransmit(&mut self, losses: &[(u32, u32)]) -> io::Result<()> {
+        for packet in self.sent.iter_mut() {
+            let lost = losses.iter().any(|&(first, last)| {
+                !sequence_before(packet.sequence, first) && !sequence_before(last, packet.sequence)
+            });
+            if lost {
+                // Set the retransmitted flag
+                packet.packet[4] |= 0x04;
+                self.socket.send(&packet.packet)?;
+                self.retransmitted += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for Link {
+    /// Sends one data packet per write.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.send_data(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// An SRT caller connection in live mode.
+///
+/// Data is queued in an [`Outbox`] and sent by [`SrtConnection::pump`],
+/// paced to the maximum bandwidth. Packets are kept for retransmission
+/// until the receiver acknowledges them or they are older than twice the
+/// latency.
+pub struct SrtConnection {
+    link: Link,
+    outbox: Outbox,
+}
+
+impl SrtConnection {
+    /// Connects to an SRT listener.
+    ///
+    /// # Parameters
+    ///
+    /// - `url` - Listener address, latency and stream id
+    /// - `timeout` - Limit for the whole handshake
+    /// - `max_bandwidth` - Pacing rate in bits per second
+    /// - `max_latency` - Media that may be queued before frames are dropped
+    ///
+    /// # Errors
+    ///
+    /// - `IoError::StreamConnectionFailed` - The listener does not answer
+    /// - `IoError::SrtError` - The listener rejects the connection
+    pub fn connect(
+        url: &SrtUrl,
+        timeout: Duration,
+        max_bandwidth: u64,
+        max_latency: Duration,
+    ) -> Result<Self> {
+        let target = format!("{}:{}", url.host, url.port);
+        let failed = |e: io::Error| IoError::StreamConnectionFailed(format!("{}: {}", target, e));
+        let addr: SocketAddr = (url.host.as_str(), url.port)
+            .to_socket_addrs()
+            .map_err(failed)?
+            .next()
+            .ok_or_else(|| failed(io::ErrorKind::NotFound.into()))?;
+        let bind = if addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind).map_err(failed)?;
+        socket.connect(addr).map_err(failed)?;
+
+        let socket_id = random_u31(1);
+        let initial_sequence = random_u31(2);
+        let started = Instant::now();
+        let deadline = started + timeout;
+
+        // Induction: learn the listener's cookie
+        let induction = Handshake {
+            version: 4,
+            encryption: 0,
+            extension: 2,
+            initial_sequence,
+            mtu: 1500,
+            flow_window: 8192,
+            kind: HS_INDUCTION,
+            socket_id,
+            cookie: 0,
+            extensions: Vec::new(),
+        };
+        let response = handshake_exchange(&socket, &induction, deadline)?;
+        if response.version != 5 || response.extension != SRT_MAGIC {
+            return Err(IoError::SrtError(
+                "Listener does not support SRT version 5 handshakes".to_string(),
+            ));
+        }
+
+        // Conclusion with the SRT options
+        let latency_ms = url.latency.as_millis().min(u16::MAX as u128) as u32;
+        let mut extensions = vec![(
+            HS_EXT_HSREQ,
+            vec![SRT_VERSION, SRT_FLAGS, latency_ms << 16 | latency_ms],
+        )];
+        let mut flags = HS_FLAG_HSREQ;
+        if let Some(id) = &url.stream_id {
+            extensions.push((HS_EXT_SID, stream_id_words(id)));
+            flags |= HS_FLAG_CONFIG;
+        }
+        let conclusion = Handshake {
+            version: 5,
+            extension: flags,
+            kind: HS_CONCLUSION,
+            cookie: response.cookie,
+            extensions,
+            ..induction
+        };
+        let response = handshake_exchange(&socket, &conclusion, deadline)?;
+        if response.kind != HS_CONCLUSION {
+            return Err(IoError::SrtError(format!(
+                "Listener rejected the connection (reason {})",
+                response.kind
+            )));
+        }
+        // The receiver's latency wins if it asks for more
+        let peer_latency = response
+            .extensions
+            .iter()
+            .find(|(kind, _)| *kind == HS_EXT_HSRSP)
+            .and_then(|(_, words)| words.get(2))
+            .map(|&word| Duration::from_millis((word >> 16) as u64))
+            .unwrap_or_default();
+
+        socket.set_nonblocking(true).map_err(failed)?;
+        tracing::info!("SRT connected to {}", target);
+
+        let now = Instant::now();
+        Ok(Self {
+            link: Link {
+                socket,
+                peer_socket_id: response.socket_id,
+                started,
+                next_sequence: initial_sequence,
+                next_message: 1,
+                latency: peer_latency.max(url.latency),
+                sent: VecDeque::new(),
+                max_bandwidth,
+                tokens: 0.0,
+                last_refill: now,
+                last_sent: now,
+                last_received: now,
+                retransmitted: 0,
+                rtt: None,
+            },
+            outbox: Outbox::new(max_latency),
+        })
+    }
+
+    /// Queues transport stream data for one video frame.
+    ///
+    /// The data is split into packets of [`PAYLOAD_SIZE`] bytes. Returns the
+    /// number of frames dropped to stay within the latency limit.
+    pub fn send_video(
+        &mut self,
+        data: &[u8],
+        frame: u64,
+        keyframe: bool,
+        media_time: Duration,
+    ) -> u64 {
+        data.chunks(PAYLOAD_SIZE)
+            .enumerate()
+            .map(|(i, chunk)| {
+                self.outbox
+                    .push_frame(chunk.to_vec(), frame, keyframe && i == 0, media_time)
+            })
+            .sum()
+    }
+
+    /// Queues transport stream data that is never dropped, such as audio.
+    pub fn send_audio(&mut self, data: &[u8]) {
+        for chunk in data.chunks(PAYLOAD_SIZE) {
+            self.outbox.push_control(chunk.to_vec());
+        }
+    }
+
+    /// Handles control packets and sends queued data at the paced rate.
+    ///
+    /// Returns the number of payload bytes sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::StreamDisconnected` if the listener shut down the
+    /// connection or stopped responding.
+    pub fn pump(&mut self) -> Result<usize> {
+        self.link.receive()?;
+
+        let now = Instant::now();
+        if now.duration_since(self.link.last_received) > PEER_IDLE_TIMEOUT {
+            return Err(IoError::StreamDisconnected);
+        }
+
+        self.link.refill(now);
+        let written = self.outbox.pump(&mut self.link)?;
+        if written == 0 && now.duration_since(self.link.last_sent) > KEEPALIVE_INTERVAL {
+            self.link.send_control(CONTROL_KEEPALIVE, 0, &[])?;
+        }
+        self.link.expire_sent(now);
+        Ok(written)
+    }
+
+    /// Returns the send queue.
+    pub fn outbox(&self) -> &Outbox {
+        &self.outbox
+    }
+
+    /// Returns the number of retransmitted packets.
+    pub fn retransmitted(&self) -> u64 {
+        self.link.retransmitted
+    }
+
+    /// Returns the round trip time reported by the receiver.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.link.rtt
+    }
+
+    /// Returns the negotiated latency.
+    pub fn latency(&self) -> Duration {
+        self.link.latency
+    }
+
+    /// Changes the pacing rate.
+    pub fn set_max_bandwidth(&mut self, bits_per_second: u64) {
+        self.link.max_bandwidth = bits_per_second;
+    }
+
+    /// Sends queued data, then tells the listener the connection is closing.
+    pub fn close(mut self) {
+        let deadline = Instant::now() + Duration::from_millis(200);
+        while !self.outbox.is_empty() && Instant::now() < deadline {
+            if self.pump().is_err() {
+                break;
+            }
+        }
+        let _ = self.link.send_control(CONTROL_SHUTDOWN, 0, &[]);
+    }
+}
+
+/// Returns true if sequence number `a` comes before `b`, with wraparound.
+fn sequence_before(a: u32, b: u32) -> bool {
+    let diff = b.wrapping_sub(a) & 0x7FFF_FFFF;
+    diff != 0 && diff < 0x4000_0000
+}
+
+/// Parses a NAK loss list into inclusive sequence ranges.
+fn parse_loss_list(cif: &[u8]) -> Vec<(u32, u32)> {
+    let words: Vec<u32> = cif
+        .chunks_exact(4)
+        .map(|w| u32::from_be_bytes(w.try_into().expect("4")))
+        .collect();
+    let mut losses = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        let first = words[i] & 0x7FFF_FFFF;
+        if words[i] & 0x8000_0000 != 0 && i + 1 < words.len() {
+            losses.push((first, words[i + 1] & 0x7FFF_FFFF));
+            i += 2;
+        } else {
+            losses.push((first, first));
+            i += 1;
+        }
+    }
+    losses
+}
+
+/// Sends one handshake and waits for the matching response, retrying every
+/// 250 ms until the deadline.
+fn handshake_exchange(
+    socket: &UdpSocket,
+    handshake: &Handshake,
+    deadline: Instant,
+) -> Result<Handshake> {
+    let packet = control_packet(CONTROL_HANDSHAKE, 0, 0, 0, &handshake.encode());
+    let mut buf = [0u8; 1500];
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(IoError::StreamConnectionFailed(
+                "SRT listener did not answer the handshake".to_string(),
+            ));
+        }
+        socket.send(&packet).map_err(|e| {
+            IoError::StreamConnectionFailed(format!("Cannot reach SRT listener: {}", e))
+        })?;
+        socket.set_read_timeout(Some((deadline - now).min(Duration::from_millis(250))))?;
+
+        match socket.recv(&mut buf) {
+            Ok(n) => {
+                let header = parse_header(&buf[..n]);
+                if header.and_then(|h| h.control) == Some(CONTROL_HANDSHAKE) {
+                    if let Some(response) = Handshake::decode(&buf[HEADER_SIZE..n]) {
+                        return Ok(response);
+                    }
+                }
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(e) => {
+                return Err(IoError::StreamConnectionFailed(format!(
+                    "SRT handshake failed: {}",
+                    e
+                )))
+            }
+        }
+    }
+}
 
 /// SRT streamer for sending video via SRT protocol.
 ///
 /// SRT (Secure Reliable Transport) provides low-latency streaming with
-/// error correction and encryption.
+/// error correction. Video is encoded as H.264 and sent as an MPEG
+/// transport stream; an AAC audio track can be added with
+/// [`SrtStreamer::with_audio`] and fed with [`SrtStreamer::send_audio`].
 ///
-/// # Note
-///
-/// This is currently a stub implementation. Full SRT support requires
-/// the libsrt library and additional FFmpeg configuration.
+/// Connection failures are retried with exponential backoff, and the
+/// encoder bitrate is lowered while the send queue backs up.
 ///
 /// # Example
 ///
@@ -28,7 +744,7 @@ use crate::sink::{SinkStatistics, VideoSink};
 ///
 /// let format = VideoFormat::hd_1080p60_rgba();
 /// let mut streamer = SrtStreamer::new(
-///     "srt://localhost:9000",
+///     "srt://localhost:9000?latency=200",
 ///     format,
 ///     6_000_000, // 6 Mbps
 /// ).unwrap();
@@ -36,39 +752,68 @@ use crate::sink::{SinkStatistics, VideoSink};
 #[cfg(feature = "stream")]
 pub struct SrtStreamer {
     url: String,
+    target: SrtUrl,
+    encoder: VideoEncoder,
+    audio: Option<AudioEncoder>,
     format: VideoFormat,
-    bitrate: u64,
-    connected: bool,
+    muxer: TsMuxer,
+    connection: Option<SrtConnection>,
+    timeout: Duration,
+    max_latency: Duration,
+    max_bandwidth: u64,
+    backoff: Backoff,
+    abr: AdaptiveBitrate,
+    meter: BitrateMeter,
+    measured_bitrate: u64,
+    stream_start: Option<Duration>,
     frame_count: u64,
+    frames_dropped: u64,
 }
 
 #[cfg(feature = "stream")]
 impl SrtStreamer {
     /// Creates a new SRT streamer.
     ///
+    /// The connection is opened by [`SrtStreamer::connect`] or the first
+    /// frame.
+    ///
     /// # Parameters
     ///
-    /// - `url` - SRT URL (e.g., "srt://host:port")
+    /// - `url` - SRT URL (e.g., "srt://host:port?latency=120&streamid=name")
     /// - `format` - Video format to stream
     /// - `bitrate` - Target bitrate in bits per second
     pub fn new(url: impl Into<String>, format: VideoFormat, bitrate: u64) -> Result<Self> {
         let url = url.into();
+        let target = SrtUrl::parse(&url)?;
 
-        // Validate SRT URL
-        if !url.starts_with("srt://") {
-            return Err(IoError::InvalidParameter(
-                "SRT URL must start with srt://".to_string(),
-            ));
-        }
+        tracing::info!("Creating SRT streamer to {}:{}", target.host, target.port);
 
-        tracing::warn!("SRT streaming is not fully implemented yet");
+        let encoder = VideoEncoder::new(
+            VideoCodec::H264,
+            format.clone(),
+            bitrate,
+            EncoderPreset::LowLatency,
+        )?;
 
         Ok(Self {
             url,
+            target,
+            encoder,
+            audio: None,
             format,
-            bitrate,
-            connected: false,
+            muxer: TsMuxer::new(false),
+            connection: None,
+            timeout: Duration::from_secs(3),
+            max_latency: Duration::from_secs(1),
+            // Headroom for muxing overhead, audio and retransmissions
+            max_bandwidth: bitrate * 3 / 2,
+            backoff: Backoff::default(),
+            abr: AdaptiveBitrate::new(bitrate / 4, bitrate),
+            meter: BitrateMeter::default(),
+            measured_bitrate: 0,
+            stream_start: None,
             frame_count: 0,
+            frames_dropped: 0,
         })
     }
 
@@ -77,46 +822,179 @@ impl SrtStreamer {
         Self::new(url, VideoFormat::hd_1080p60_rgba(), 6_000_000)
     }
 
-    /// Connects to the SRT server.
+    /// Adds an audio track encoded by `encoder`, which must use AAC.
+    pub fn with_audio(mut self, encoder: AudioEncoder) -> Self {
+        self.max_bandwidth += encoder.bitrate() * 3 / 2;
+        self.muxer = TsMuxer::new(true);
+        self.audio = Some(encoder);
+        self
+    }
+
+    /// Sets the reconnect backoff.
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the lowest bitrate adaptive bitrate may choose.
+    pub fn with_min_bitrate(mut self, bitrate: u64) -> Self {
+        self.abr = AdaptiveBitrate::new(bitrate, self.encoder.bitrate());
+        self
+    }
+
+    /// Sets the handshake timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how much media may be queued before frames are dropped.
+    pub fn with_max_latency(mut self, latency: Duration) -> Self {
+        self.max_latency = latency;
+        self
+    }
+
+    /// Connects to the SRT listener.
+    ///
+    /// # Errors
+    ///
+    /// Returns the connection error; the next attempt is scheduled with
+    /// exponential backoff.
     pub fn connect(&mut self) -> Result<()> {
-        if self.connected {
+        if self.connection.is_some() {
             return Ok(());
         }
 
         tracing::info!("Connecting to SRT server: {}", self.url);
-
-        // TODO: Implement actual SRT connection
-        // This would require:
-        // 1. libsrt integration
-        // 2. SRT handshake
-        // 3. Stream setup
-        // 4. Error correction configuration
-
-        Err(IoError::SrtError(
-            "SRT streaming not yet implemented. This requires libsrt integration.".to_string(),
-        ))
+        match SrtConnection::connect(
+            &self.target,
+            self.timeout,
+            self.max_bandwidth,
+            self.max_latency,
+        ) {
+            Ok(connection) => {
+                self.muxer.reset();
+                self.encoder.request_keyframe();
+                self.connection = Some(connection);
+                self.backoff.reset();
+                Ok(())
+            }
+            Err(e) => {
+                let delay = self.backoff.fail(Instant::now());
+                tracing::warn!("SRT connection failed ({}), retrying in {:?}", e, delay);
+                Err(e)
+            }
+        }
     }
 
     /// Disconnects from the SRT server.
     pub fn disconnect(&mut self) -> Result<()> {
-        if !self.connected {
+        let Some(connection) = self.connection.take() else {
             return Ok(());
-        }
+        };
 
         tracing::info!("Disconnecting from SRT server");
-        self.connected = false;
+        self.encoder.flush()?;
+        connection.close();
         Ok(())
     }
 
     /// Returns true if connected to the server.
     pub fn is_connected(&self) -> bool {
-        self.connected
+        self.connection.is_some()
     }
 
     /// Returns the SRT URL.
     pub fn url(&self) -> &str {
         &self.url
     }
+
+    /// Returns the current target bitrate of the encoder.
+    pub fn target_bitrate(&self) -> u64 {
+        self.encoder.bitrate()
+    }
+
+    /// Returns the number of packets retransmitted on the current connection.
+    pub fn retransmitted(&self) -> u64 {
+        self.connection
+            .as_ref()
+            .map_or(0, SrtConnection::retransmitted)
+    }
+
+    /// Encodes and sends audio from the active audio input.
+    ///
+    /// `frame.timestamp` must use the same clock as the video frames. Audio
+    /// sent while disconnected is discarded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IoError::InvalidParameter` if no audio track was added or
+    /// the track is not AAC.
+    pub fn send_audio(&mut self, frame: &AudioFrame) -> Result<()> {
+        let encoder = self.audio.as_mut().ok_or_else(|| {
+            IoError::InvalidParameter("SRT streamer has no audio track".to_string())
+        })?;
+        if encoder.codec() != AudioCodec::Aac {
+            return Err(IoError::InvalidParameter(
+                "SRT streams carry AAC audio only".to_string(),
+            ));
+        }
+        let (sample_rate, channels) = (encoder.sample_rate(), encoder.channels());
+        let packets = encoder.encode(frame)?;
+        let Some(connection) = self.connection.as_mut() else {
+            return Ok(());
+        };
+
+        let start = *self.stream_start.get_or_insert(frame.timestamp);
+        for packet in packets {
+            let mut adts = adts_header(sample_rate, channels, packet.data.len())
+                .map(Vec::from)
+                .unwrap_or_default();
+            adts.extend_from_slice(&packet.data);
+            let data = self.muxer.audio(packet.pts.saturating_sub(start), &adts);
+            connection.send_audio(&data);
+        }
+        self.pump()
+    }
+
+    /// Muxes encoded video into transport stream packets and queues them.
+    fn send_packets(&mut self, packets: &[EncodedPacket]) {
+        let start = self.stream_start.unwrap_or_default();
+        for packet in packets {
+            let pts = packet.presentation_time().saturating_sub(start);
+            let dts = packet.decode_time().saturating_sub(start);
+            let data = self.muxer.video(pts, dts, &packet.data, packet.is_keyframe);
+            if let Some(connection) = self.connection.as_mut() {
+                self.frames_dropped +=
+                    connection.send_video(&data, self.frame_count, packet.is_keyframe, dts);
+            }
+        }
+    }
+
+    /// Sends queued data and adapts the bitrate to the queue.
+    fn pump(&mut self) -> Result<()> {
+        let Some(connection) = self.connection.as_mut() else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        match connection.pump() {
+            Ok(written) => {
+                self.meter.record(written, now);
+                self.measured_bitrate = self.meter.bitrate(now);
+                if let Some(bitrate) = self.abr.update(connection.outbox().latency(), now) {
+                    tracing::info!("Adapting SRT bitrate to {} bps", bitrate);
+                    self.encoder.set_bitrate(bitrate);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                tracing::warn!("SRT connection lost: {}", e);
+                self.connection = None;
+                self.backoff.fail(now);
+                Err(IoError::StreamDisconnected)
+            }
+        }
+    }
 }
 
 #[cfg(feature = "stream")]
@@ -129,19 +1007,32 @@ impl VideoSink for SrtStreamer {
         self.format.clone()
     }
 
-    fn send_frame(&mut self, _frame: &VideoFrame) -> Result<()> {
-        if !self.connected {
-            self.connect()?;
+    fn send_frame(&mut self, frame: &VideoFrame) -> Result<()> {
+        frame.validate()?;
+
+        if self.connection.is_none() {
+            if !self.backoff.is_ready(Instant::now()) {
+                self.frames_dropped += 1;
+                return Err(IoError::StreamDisconnected);
+            }
+            if let Err(e) = self.connect() {
+                self.frames_dropped += 1;
+                return Err(e);
+            }
         }
 
-        // TODO: Implement frame sending
-        Err(IoError::SrtError(
-            "SRT streaming not yet implemented".to_string(),
-        ))
+        let packets = self.encoder.encode(frame)?;
+        self.stream_start.get_or_insert(frame.timestamp);
+        self.send_packets(&packets);
+        self.frame_count += 1;
+
+        tracing::trace!("Sent frame {} to SRT stream", self.frame_count);
+
+        self.pump()
     }
 
     fn is_available(&self) -> bool {
-        false // Not yet implemented
+        self.connection.is_some()
     }
 
     fn frame_count(&self) -> u64 {
@@ -149,24 +1040,39 @@ impl VideoSink for SrtStreamer {
     }
 
     fn flush(&mut self) -> Result<()> {
-        Ok(())
+        let packets = self.encoder.flush()?;
+        self.send_packets(&packets);
+        self.pump()
     }
 
     fn reconnect(&mut self) -> Result<()> {
         self.disconnect()?;
+        self.backoff.reset();
         self.connect()
     }
 
     fn statistics(&self) -> Option<SinkStatistics> {
         Some(SinkStatistics {
             frames_sent: self.frame_count,
-            frames_dropped: 0,
-            bitrate: Some(self.bitrate),
-            average_latency_ms: None,
+            frames_dropped: self.frames_dropped,
+            bitrate: Some(self.measured_bitrate),
+            average_latency_ms: self
+                .connection
+                .as_ref()
+                .map(|c| c.outbox().latency().as_secs_f32() * 1000.0),
         })
     }
 }
 
+#[cfg(feature = "stream")]
+impl Drop for SrtStreamer {
+    fn drop(&mut self) {
+        if self.connection.is_some() {
+            let _ = self.disconnect();
+        }
+    }
+}
+
 // Stub implementation when stream feature is disabled
 /// SRT streamer (stub implementation when feature is disabled)
 #[cfg(not(feature = "stream"))]
@@ -188,31 +1094,330 @@ impl SrtStreamer {
 }
 
 #[cfg(test)]
-#[cfg(feature = "stream")]
+pub(crate) mod test_listener {
+    //! Minimal SRT listener that accepts one caller.
+
+    use super::*;
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread::{self, JoinHandle};
+
+    const LISTENER_SOCKET_ID: u32 = 777;
+    const COOKIE: u32 = 0x00C0_FFEE;
+
+    /// What the listener saw from the caller.
+    #[derive(Debug, Default)]
+    pub(crate) struct Session {
+        pub(crate) stream_id: Option<String>,
+        pub(crate) payloads: Vec<Vec<u8>>,
+        pub(crate) retransmitted: Vec<u32>,
+        pub(crate) ackack: bool,
+        pub(crate) shutdown: bool,
+    }
+
+    /// Starts a listener on a free port. It NAKs the first data packet and
+    /// acknowledges the third, and reports the session once the caller
+    /// shuts down.
+    pub(crate) fn spawn() -> (u16, Receiver<Session>, JoinHandle<io::Result<()>>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let (sender, receiver) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            socket.set_read_timeout(Some(Duration::from_secs(5)))?;
+            let mut session = Session::default();
+            let mut buf = [0u8; 1500];
+            let mut data_packets = 0;
+
+            loop {
+                let (n, peer) = socket.recv_from(&mut buf)?;
+                let packet = &buf[..n];
+                let header = parse_header(packet).unwrap();
+                match header.control {
+                    Some(CONTROL_HANDSHAKE) => {
+                        let request = Handshake::decode(&packet[HEADER_SIZE..]).unwrap();
+                        let response = if request.kind == HS_INDUCTION {
+                            assert_eq!(request.version, 4);
+                            Handshake {
+                                version: 5,
+                                extension: SRT_MAGIC,
+                                cookie: COOKIE,
+                                socket_id: LISTENER_SOCKET_ID,
+                                ..request
+                            }
+                        } else {
+                            assert_eq!(request.cookie, COOKIE);
+                            for (kind, words) in &request.extensions {
+                                if *kind == HS_EXT_SID {
+                                    let bytes: Vec<u8> =
+                                        words.iter().flat_map(|w| w.to_le_bytes()).collect();
+                                    let id = String::from_utf8(bytes).unwrap();
+                                    session.stream_id = Some(id.trim_end_matches('\0').into());
+                                }
+                            }
+                            Handshake {
+                                socket_id: LISTENER_SOCKET_ID,
+                                extensions: vec![(
+                                    HS_EXT_HSRSP,
+                                    vec![SRT_VERSION, SRT_FLAGS, 200 << 16 | 200],
+                                )],
+                                ..request
+                            }
+                        };
+                        let reply = control_packet(CONTROL_HANDSHAKE, 0, 0, 0, &response.encode());
+                        socket.send_to(&reply, peer)?;
+                    }
+                    Some(CONTROL_ACKACK) => session.ackack = true,
+                    Some(CONTROL_SHUTDOWN) => {
+                        session.shutdown = true;
+                        let _ = sender.send(session);
+                        return Ok(());
+                    }
+                    Some(_) => {}
+                    None => {
+                        let dest = u32::from_be_bytes(packet[12..16].try_into().unwrap());
+                        assert_eq!(dest, LISTENER_SOCKET_ID);
+                        let sequence = u32::from_be_bytes(packet[0..4].try_into().unwrap());
+                        if packet[4] & 0x04 != 0 {
+                            session.retransmitted.push(sequence);
+                            continue;
+                        }
+                        session.payloads.push(packet[HEADER_SIZE..].to_vec());
+                        data_packets += 1;
+                        if data_packets == 1 {
+                            let nak = control_packet(CONTROL_NAK, 0, 0, 0, &sequence.to_be_bytes());
+                            socket.send_to(&nak, peer)?;
+                        }
+                        if data_packets == 3 {
+                            let mut cif = (sequence + 1).to_be_bytes().to_vec();
+                            cif.extend_from_slice(&1500u32.to_be_bytes());
+                            let ack = control_packet(CONTROL_ACK, 1, 0, 0, &cif);
+                            socket.send_to(&ack, peer)?;
+                        }
+                    }
+                }
+            }
+        });
+
+        (port, receiver, handle)
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_srt_streamer_creation() {
-        let format = VideoFormat::hd_1080p60_rgba();
-        let streamer = SrtStreamer::new("srt://localhost:9000", format, 6_000_000);
-        assert!(streamer.is_ok());
+    fn test_parse_url() {
+        let url = SrtUrl::parse("srt://example.com:9000?latency=250&streamid=live/cam1").unwrap();
+        assert_eq!(url.host, "example.com");
+        assert_eq!(url.port, 9000);
+        assert_eq!(url.latency, Duration::from_millis(250));
+        assert_eq!(url.stream_id.as_deref(), Some("live/cam1"));
+
+        let url = SrtUrl::parse("srt://10.0.0.1:4200").unwrap();
+        assert_eq!(url.latency, Duration::from_millis(120));
+
+        assert!(SrtUrl::parse("http://localhost/stream").is_err());
+        assert!(SrtUrl::parse("srt://localhost").is_err());
+        assert!(SrtUrl::parse("srt://localhost:9000?latency=soon").is_err());
+        assert!(SrtUrl::parse("srt://localhost:9000?mode=listener").is_err());
+    }
+
+    #[test]
+    fn test_handshake_round_trip() {
+        let handshake = Handshake {
+            version: 5,
+            encryption: 0,
+            extension: HS_FLAG_HSREQ | HS_FLAG_CONFIG,
+            initial_sequence: 1234,
+            mtu: 1500,
+            flow_window: 8192,
+            kind: HS_CONCLUSION,
+            socket_id: 42,
+            cookie: 99,
+            extensions: vec![
+                (HS_EXT_HSREQ, vec![SRT_VERSION, SRT_FLAGS, 120 << 16 | 120]),
+                (HS_EXT_SID, stream_id_words("abcde")),
+            ],
+        };
+        let bytes = handshake.encode();
+        assert_eq!(bytes.len(), HANDSHAKE_CIF_SIZE + 4 + 12 + 4 + 8);
+        assert_eq!(Handshake::decode(&bytes), Some(handshake));
+
+        // Each word of the stream id is byte-reversed on the wire
+        assert_eq!(stream_id_words("abcde"), [0x6463_6261, 0x0000_0065]);
+    }
+
+    #[test]
+    fn test_loss_list_and_sequences() {
+        let mut cif = Vec::new();
+        for word in [5u32, 0x8000_0000 | 10, 12, 0x7FFF_FFFF] {
+            cif.extend_from_slice(&word.to_be_bytes());
+        }
+        assert_eq!(
+            parse_loss_list(&cif),
+            [(5, 5), (10, 12), (0x7FFF_FFFF, 0x7FFF_FFFF)]
+        );
+
+        assert!(sequence_before(1, 2));
+        assert!(!sequence_before(2, 2));
+        assert!(!sequence_before(3, 2));
+        // Wraparound
+        assert!(sequence_before(0x7FFF_FFFF, 0));
+        assert!(!sequence_before(0, 0x7FFF_FFFF));
     }
 
     #[test]
-    fn test_srt_streamer_invalid_url() {
-        let format = VideoFormat::hd_1080p60_rgba();
-        let streamer = SrtStreamer::new("http://localhost/stream", format, 6_000_000);
-        assert!(streamer.is_err());
+    fn test_send_to_local_listener() {
+        let (port, sessions, server) = test_listener::spawn();
+        let url = SrtUrl::parse(&format!(
+            "srt://127.0.0.1:{}?latency=100&streamid=live/test",
+            port
+        ))
+        .unwrap();
+        let mut connection = SrtConnection::connect(
+            &url,
+            Duration::from_secs(5),
+            100_000_000,
+            Duration::from_secs(1),
+        )
+        .unwrap();
+        // The listener asked for more latency
+        assert_eq!(connection.latency(), Duration::from_millis(200));
+
+        let data: Vec<u8> = (0..16 * TS_PACKET_SIZE).map(|i| i as u8).collect();
+        connection.send_video(&data, 0, true, Duration::ZERO);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while (!connection.outbox().is_empty() || connection.retransmitted() == 0)
+            && Instant::now() < deadline
+        {
+            connection.pump().unwrap();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(connection.retransmitted(), 1);
+        // Let the ACK arrive
+        std::thread::sleep(Duration::from_millis(50));
+        connection.pump().unwrap();
+        assert_eq!(connection.rtt(), Some(Duration::from_micros(1500)));
+        connection.close();
+
+        let session = sessions.recv_timeout(Duration::from_secs(5)).unwrap();
+        server.join().unwrap().unwrap();
+        assert_eq!(session.stream_id.as_deref(), Some("live/test"));
+        assert_eq!(session.payloads.len(), 3);
+        assert_eq!(session.payloads[0].len(), PAYLOAD_SIZE);
+        assert_eq!(session.payloads.concat(), data);
+        assert_eq!(session.retransmitted.len(), 1);
+        assert!(session.ackack);
+        assert!(session.shutdown);
     }
 
     #[test]
-    fn test_srt_streamer_not_implemented() {
-        let format = VideoFormat::hd_1080p60_rgba();
-        let mut streamer = SrtStreamer::new("srt://localhost:9000", format, 6_000_000).unwrap();
+    fn test_connect_timeout() {
+        // A socket that never answers
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = silent.local_addr().unwrap().port();
+        let url = SrtUrl::parse(&format!("srt://127.0.0.1:{}", port)).unwrap();
+        let result = SrtConnection::connect(
+            &url,
+            Duration::from_millis(300),
+            1_000_000,
+            Duration::from_secs(1),
+        );
+        assert!(matches!(result, Err(IoError::StreamConnectionFailed(_))));
+    }
 
-        // Connect should fail with not implemented error
-        assert!(streamer.connect().is_err());
-        assert!(!streamer.is_connected());
+    #[cfg(feature = "stream")]
+    mod streamer {
+        use super::super::*;
+        use crate::format::{PixelFormat, VideoFormat};
+        use std::collections::HashMap;
+
+        #[test]
+        fn test_srt_streamer_creation() {
+            let format = VideoFormat::hd_1080p60_rgba();
+            let streamer = SrtStreamer::new("srt://localhost:9000", format, 6_000_000);
+            assert!(streamer.is_ok());
+        }
+
+        #[test]
+        fn test_srt_streamer_invalid_url() {
+            let format = VideoFormat::hd_1080p60_rgba();
+            let streamer = SrtStreamer::new("http://localhost/stream", format, 6_000_000);
+            assert!(streamer.is_err());
+        }
+
+        /// Reassembles the payloads of the TS packets on each PID.
+        fn ts_payloads(ts: &[u8]) -> HashMap<u16, Vec<u8>> {
+            let mut payloads: HashMap<u16, Vec<u8>> = HashMap::new();
+            for packet in ts.chunks(TS_PACKET_SIZE) {
+                let pid = (u16::from(packet[1] & 0x1F) << 8) | u16::from(packet[2]);
+                let offset = if packet[3] & 0x20 != 0 {
+                    5 + packet[4] as usize
+                } else {
+                    4
+                };
+                payloads
+                    .entry(pid)
+                    .or_default()
+                    .extend_from_slice(&packet[offset..]);
+            }
+            payloads
+        }
+
+        #[test]
+        fn test_srt_streamer_send_frame() {
+            let (port, sessions, _server) = test_listener::spawn();
+            let format = VideoFormat::new(64, 64, PixelFormat::RGBA8, 30.0);
+            let mut streamer = SrtStreamer::new(
+                format!("srt://127.0.0.1:{}", port),
+                format.clone(),
+                1_000_000,
+            )
+            .unwrap()
+            .with_audio(AudioEncoder::new(AudioCodec::Aac, 48000, 2).unwrap());
+
+            assert!(!streamer.is_connected());
+            for index in 0..5 {
+                let mut frame = VideoFrame::empty(format.clone());
+                frame.timestamp = format.frame_duration() * index;
+                streamer.send_frame(&frame).unwrap();
+                let audio = AudioFrame::new(vec![0.25; 1600 * 2], 48000, 2, frame.timestamp);
+                streamer.send_audio(&audio).unwrap();
+                assert!(streamer.is_connected());
+            }
+            assert_eq!(streamer.frame_count(), 5);
+            streamer.disconnect().unwrap();
+
+            let session = sessions.recv_timeout(Duration::from_secs(5)).unwrap();
+            let ts = session.payloads.concat();
+            assert_eq!(ts.len() % TS_PACKET_SIZE, 0);
+            assert!(ts.chunks(TS_PACKET_SIZE).all(|p| p[0] == 0x47));
+
+            // Video PES carrying the in-band SPS of the keyframe
+            let payloads = ts_payloads(&ts);
+            let video = &payloads[&0x0100];
+            assert_eq!(video[..4], [0x00, 0x00, 0x01, 0xE0]);
+            assert!(video.windows(4).any(|w| w == [0x00, 0x00, 0x01, 0x67]));
+
+            // Audio PES carrying ADTS frames
+            let audio = &payloads[&0x0101];
+            assert_eq!(audio[..4], [0x00, 0x00, 0x01, 0xC0]);
+            assert!(audio.windows(2).any(|w| w == [0xFF, 0xF1]));
+        }
+
+        #[test]
+        fn test_srt_streamer_connect_fails() {
+            let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let port = silent.local_addr().unwrap().port();
+            let format = VideoFormat::hd_1080p60_rgba();
+            let mut streamer =
+                SrtStreamer::new(format!("srt://127.0.0.1:{}", port), format, 6_000_000)
+                    .unwrap()
+                    .with_timeout(Duration::from_millis(200));
+
+            assert!(streamer.connect().is_err());
+            assert!(!streamer.is_connected());
+        }
     }
 }
@@ -0,0 +1,503 @@
+//! Delivery helpers shared by the network streamers.
+//!
+//! - [`Backoff`] - exponential reconnect delays
+//! - [`Outbox`] - non-blocking send queue with drop accounting
+//! - [`BitrateMeter`] - measured output bitrate
+//! - [`AdaptiveBitrate`] - lowers the encoder bitrate while the queue backs up
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Exponential backoff between reconnect attempts.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    attempts: u32,
+    retry_at: Option<Instant>,
+}
+
+impl Backoff {
+    /// Creates a backoff starting at `initial` and doubling up to `max`.
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            attempts: 0,
+            retry_at: None,
+        }
+    }
+
+    /// Returns the delay before the next attempt and counts a failure.
+    pub fn next_delay(&mut self) -> Duration {
+        let factor = 1u32.checked_shl(self.attempts.min(16)).unwrap_or(u32::MAX);
+        self.attempts += 1;
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+
+    /// Counts a failure and schedules the next attempt relative to `now`.
+    pub fn fail(&mut self, now: Instant) -> Duration {
+        let delay = self.next_delay();
+        self.retry_at = Some(now + delay);
+        delay
+    }
+
+    /// Returns true if an attempt may be made at `now`.
+    pub fn is_ready(&self, now: Instant) -> bool {
+        self.retry_at.map_or(true, |at| now >= at)
+    }
+
+    /// Returns the number of failed attempts since the last success.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Resets after a successful connection.
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+        self.retry_at = None;
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(500), Duration::from_secs(30))
+    }
+}
+
+/// A message waiting to be sent.
+#[derive(Debug)]
+struct Queued {
+    data: Vec<u8>,
+    written: usize,
+    /// Media frame this message belongs to, if droppable
+    frame: Option<u64>,
+    keyframe: bool,
+    media_time: Duration,
+}
+
+/// Send queue for a non-blocking socket.
+///
+/// Messages are written in order by [`Outbox::pump`] until the socket would
+/// block. When the queued media exceeds the latency limit, whole video frames
+/// are dropped from the front of the queue up to the next keyframe, so the
+/// stream stays decodable and catches up to live.
+#[derive(Debug)]
+pub struct Outbox {
+    queue: VecDeque<Queued>,
+    queued_bytes: usize,
+    max_latency: Duration,
+    frames_dropped: u64,
+    bytes_sent: u64,
+}
+
+impl Outbox {
+    /// Creates an outbox that holds at most `max_latency` of media.
+    pub fn new(max_latency: Duration) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            queued_bytes: 0,
+            max_latency,
+            frames_dropped: 0,
+            bytes_sent: 0,
+        }
+    }
+
+    /// Queues control data that must never be dropped.
+    pub fn push_control(&mut self, data: Vec<u8>) {
+        let media_time = self.queue.back().map_or(Duration::ZERO, |q| q.media_time);
+        self.push(Queued {
+            data,
+            written: 0,
+            frame: None,
+            keyframe: false,
+            media_time,
+        });
+    }
+
+    /// Queues the data of one media frame.
+    ///
+    /// Returns the number of frames dropped to stay within the latency limit.
+    pub fn push_frame(
+        &mut self,
+        data: Vec<u8>,
+        frame: u64,
+        keyframe: bool,
+        media_time: Duration,
+    ) -> u64 {
+        self.push(Queued {
+            data,
+            written: 0,
+            frame: Some(frame),
+            keyframe,
+            media_time,
+        });
+        self.trim()
+    }
+
+    fn push(&mut self, queued: Queued) {
+        self.queued_bytes += queued.data.len();
+        self.queue.push_back(queued);
+    }
+
+    /// Drops frames from the front until the queue fits the latency limit.
+    fn trim(&mut self) -> u64 {
+        let mut dropped = 0;
+        while self.latency() > self.max_latency {
+            // The oldest frame that has not started sending, and everything
+            // of it up to the next keyframe
+            let Some(start) = self
+                .queue
+                .iter()
+                .position(|q| q.written == 0 && q.frame.is_some())
+            else {
+                break;
+            };
+            let end = self
+                .queue
+                .iter()
+                .enumerate()
+                .skip(start + 1)
+                .find(|(_, q)| q.keyframe)
+                .map(|(i, _)| i);
+            let Some(end) = end else {
+                break;
+            };
+
+            let mut last_frame = None;
+            let mut i = start;
+            let mut remaining = end - start;
+            while remaining > 0 {
+                let droppable = self.queue[i].frame.is_some() && self.queue[i].written == 0;
+                if droppable {
+                    let q = self.queue.remove(i).expect("index in range");
+                    self.queued_bytes -= q.data.len();
+                    if last_frame != q.frame {
+                        dropped += 1;
+                        last_frame = q.frame;
+                    }
+                } else {
+                    i += 1;
+                }
+                remaining -= 1;
+            }
+        }
+        self.frames_dropped += dropped;
+        dropped
+    }
+
+    /// Writes queued data until the writer would block.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error other than `WouldBlock` and `Interrupted`.
+    pub fn pump<W: Write>(&mut self, writer: &mut W) -> io::Result<usize> {
+        let mut total = 0;
+        while let Some(front) = self.queue.front_mut() {
+            match writer.write(&front.data[front.written..]) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    front.written += n;
+                    total += n;
+                    self.queued_bytes -= n;
+                    if front.written == front.data.len() {
+                        self.queue.pop_front();
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        self.bytes_sent += total as u64;
+        Ok(total)
+    }
+
+    /// Returns the media time spanned by the queue.
+    pub fn latency(&self) -> Duration {
+        match (self.queue.front(), self.queue.back()) {
+            (Some(front), Some(back)) => back.media_time.saturating_sub(front.media_time),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Returns the number of bytes waiting to be sent.
+    pub fn queued_bytes(&self) -> usize {
+        self.queued_bytes
+    }
+
+    /// Returns true if nothing is waiting.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Returns the total number of frames dropped.
+    pub fn frames_dropped(&self) -> u64 {
+        self.frames_dropped
+    }
+
+    /// Returns the total number of bytes written.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    /// Discards everything queued, e.g. after a disconnect.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.queued_bytes = 0;
+    }
+}
+
+/// Measures throughput over a sliding window.
+#[derive(Debug, Clone)]
+pub struct BitrateMeter {
+    window: Duration,
+    samples: VecDeque<(Instant, usize)>,
+    bytes: usize,
+}
+
+impl BitrateMeter {
+    /// Creates a meter averaging over `window`.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Records bytes sent at `now`.
+    pub fn record(&mut self, bytes: usize, now: Instant) {
+        self.samples.push_back((now, bytes));
+        self.bytes += bytes;
+        self.expire(now);
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some(&(at, bytes)) = self.samples.front() {
+            if now.duration_since(at) <= self.window {
+                break;
+            }
+            self.samples.pop_front();
+            self.bytes -= bytes;
+        }
+    }
+
+    /// Returns the bitrate in bits per second.
+    pub fn bitrate(&mut self, now: Instant) -> u64 {
+        self.expire(now);
+        let span = self
+            .samples
+            .front()
+            .map_or(Duration::ZERO, |&(at, _)| now.duration_since(at))
+            .max(Duration::from_millis(100))
+            .min(self.window);
+        (self.bytes as f64 * 8.0 / span.as_secs_f64()) as u64
+    }
+}
+
+impl Default for BitrateMeter {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(2))
+    }
+}
+
+/// Adjusts the target bitrate from the send queue latency.
+///
+/// The bitrate is lowered by a quarter whenever the queue holds more than
+/// the high water mark, and raised by a tenth towards the configured maximum
+/// after the queue has stayed below the low water mark for the recovery
+/// interval.
+#[derive(Debug, Clone)]
+pub struct AdaptiveBitrate {
+    min: u64,
+    max: u64,
+    current: u64,
+    high_water: Duration,
+    low_water: Duration,
+    recovery: Duration,
+    last_change: Option<Instant>,
+}
+
+impl AdaptiveBitrate {
+    /// Creates a controller between `min` and `max`, starting at `max`.
+    pub fn new(min: u64, max: u64) -> Self {
+        Self {
+            min: min.min(max),
+            max,
+            current: max,
+            high_water: Duration::from_millis(500),
+            low_water: Duration::from_millis(100),
+            recovery: Duration::from_secs(5),
+            last_change: None,
+        }
+    }
+
+    /// Sets the queue latencies that trigger a decrease and allow an increase.
+    pub fn with_water_marks(mut self, low: Duration, high: Duration) -> Self {
+        self.low_water = low;
+        self.high_water = high;
+        self
+    }
+
+    /// Sets how long the queue must stay short before increasing.
+    pub fn with_recovery(mut self, recovery: Duration) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    /// Returns the current target bitrate.
+    pub fn bitrate(&self) -> u64 {
+        self.current
+    }
+
+    /// Feeds the current queue latency and returns a new target bitrate if
+    /// it changed.
+    pub fn update(&mut self, queue_latency: Duration, now: Instant) -> Option<u64> {
+        let settled = self
+            .last_change
+            .map_or(true, |at| now.duration_since(at) >= self.recovery);
+
+        let next = if queue_latency > self.high_water {
+            // Back off at most once per high water interval
+            let recent = self
+                .last_change
+                .is_some_and(|at| now.duration_since(at) < self.high_water);
+            if recent {
+                return None;
+            }
+            (self.current * 3 / 4).max(self.min)
+        } else if queue_latency < self.low_water && settled {
+            (self.current + self.current / 10).min(self.max)
+        } else {
+            return None;
+        };
+
+        if next == self.current {
+            return None;
+        }
+        self.current = next;
+        self.last_change = Some(now);
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writer that accepts a limited number of bytes per call
+    struct Throttled {
+        budget: usize,
+        written: Vec<u8>,
+    }
+
+    impl Write for Throttled {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.budget == 0 {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let n = buf.len().min(self.budget);
+            self.budget -= n;
+            self.written.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_backoff() {
+        let mut backoff = Backoff::new(ms(100), ms(1000));
+        let delays: Vec<_> = (0..6).map(|_| backoff.next_delay()).collect();
+        assert_eq!(
+            delays,
+            [ms(100), ms(200), ms(400), ms(800), ms(1000), ms(1000)]
+        );
+
+        let now = Instant::now();
+        backoff.reset();
+        assert!(backoff.is_ready(now));
+        backoff.fail(now);
+        assert!(!backoff.is_ready(now));
+        assert!(backoff.is_ready(now + ms(100)));
+        assert_eq!(backoff.attempts(), 1);
+    }
+
+    #[test]
+    fn test_outbox_partial_writes() {
+        let mut outbox = Outbox::new(Duration::from_secs(1));
+        outbox.push_control(vec![1, 2, 3]);
+        outbox.push_frame(vec![4, 5, 6, 7], 0, true, ms(0));
+
+        let mut writer = Throttled {
+            budget: 5,
+            written: Vec::new(),
+        };
+        assert_eq!(outbox.pump(&mut writer).unwrap(), 5);
+        assert_eq!(outbox.queued_bytes(), 2);
+
+        writer.budget = 100;
+        outbox.pump(&mut writer).unwrap();
+        assert!(outbox.is_empty());
+        assert_eq!(writer.written, [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(outbox.bytes_sent(), 7);
+    }
+
+    #[test]
+    fn test_outbox_drops_to_keyframe() {
+        let mut outbox = Outbox::new(ms(100));
+        // Keyframe every 4 frames at 30 fps
+        let mut dropped = 0;
+        for frame in 0..12u64 {
+            dropped += outbox.push_frame(vec![0; 10], frame, frame % 4 == 0, ms(frame * 33));
+        }
+
+        assert!(dropped > 0);
+        assert_eq!(dropped % 4, 0);
+        assert_eq!(outbox.frames_dropped(), dropped);
+        assert!(outbox.latency() <= ms(100));
+        assert_eq!(outbox.queued_bytes(), (12 - dropped as usize) * 10);
+    }
+
+    #[test]
+    fn test_bitrate_meter() {
+        let mut meter = BitrateMeter::new(Duration::from_secs(1));
+        let start = Instant::now();
+        for i in 0..10 {
+            meter.record(12_500, start + ms(i * 100));
+        }
+        // 125 kB over ~0.9 s
+        let bitrate = meter.bitrate(start + ms(900));
+        assert!((1_000_000..1_200_000).contains(&bitrate), "{}", bitrate);
+        assert_eq!(meter.bitrate(start + ms(5000)), 0);
+    }
+
+    #[test]
+    fn test_adaptive_bitrate() {
+        let mut abr = AdaptiveBitrate::new(1_000_000, 4_000_000).with_recovery(ms(1000));
+        let start = Instant::now();
+
+        assert_eq!(abr.update(ms(800), start), Some(3_000_000));
+        // Not again within the high water interval
+        assert_eq!(abr.update(ms(800), start + ms(100)), None);
+        assert_eq!(abr.update(ms(800), start + ms(600)), Some(2_250_000));
+        assert_eq!(abr.update(ms(300), start + ms(5000)), None);
+        assert_eq!(abr.update(ms(10), start + ms(5000)), Some(2_475_000));
+        assert_eq!(abr.update(ms(10), start + ms(5500)), None);
+
+        for i in 0..20 {
+            abr.update(ms(800), start + Duration::from_secs(10 + i));
+        }
+        assert_eq!(abr.bitrate(), 1_000_000);
+    }
+}
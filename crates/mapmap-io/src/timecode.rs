@@ -0,0 +1,8 @@
+//! SMPTE timecode for video frames.
+//!
+//! Output frames are stamped with the same [`Timecode`] type that
+//! `mapmap-control` decodes from MTC and LTC, so a chased position can drive
+//! an [`ExternalClock`](crate::ExternalClock) directly. The types live in
+//! `mapmap-core` and are re-exported here.
+
+pub use mapmap_core::timecode::{Timecode, TimecodeError, TimecodeRate};
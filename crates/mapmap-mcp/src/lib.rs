@@ -53,7 +53,7 @@ pub enum McpAction {
     LayerSetMediaTime(u64, f64),
     /// Set playback speed (layer_id, speed)
     LayerSetPlaybackSpeed(u64, f32),
-    /// Set loop mode (layer_id, loop_mode: "none", "loop", "ping-pong")
+    /// Set loop mode (layer_id, loop_mode: "none", "loop", "ping-pong", "reverse")
     LayerSetLoopMode(u64, String),
     /// List media library (optional folder filter)
     MediaLibraryList(Option<String>),
@@ -268,7 +268,7 @@ impl McpServer {
                             "type": "object",
                             "properties": {
                                 "layer_id": { "type": "integer" },
-                                "loop_mode": { "type": "string", "enum": ["none", "loop", "ping-pong"] }
+                                "loop_mode": { "type": "string", "enum": ["none", "loop", "ping-pong", "reverse"] }
                             },
                             "required": ["layer_id", "loop_mode"]
                         }),
@@ -925,6 +925,29 @@ impl McpServer {
                         }
                         Some(success_response(id, serde_json::json!({"status":"queued"})))
                     }
+                    "layer_set_loop_mode" => {
+                        if let Some(args) = params.arguments {
+                            if let (Some(layer_id), Some(loop_mode)) = (
+                                args.get("layer_id").and_then(|v| v.as_u64()),
+                                args.get("loop_mode").and_then(|v| v.as_str()),
+                            ) {
+                                if !["none", "loop", "ping-pong", "reverse"].contains(&loop_mode) {
+                                    return Some(error_response(id, -32602, "Invalid loop_mode"));
+                                }
+                                if let Some(sender) = &self.action_sender {
+                                    let _ = sender.send(crate::McpAction::LayerSetLoopMode(
+                                        layer_id,
+                                        loop_mode.to_string(),
+                                    ));
+                                }
+                                return Some(success_response(
+                                    id,
+                                    serde_json::json!({"status": "queued"}),
+                                ));
+                            }
+                        }
+                        Some(error_response(id, -32602, "Missing arguments"))
+                    }
                     "layer_list" => {
                         // Mock empty list for now
                         let layers: Vec<String> = vec![];
@@ -1110,6 +1133,38 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_handle_layer_set_loop_mode() {
+        let (tx, rx) = unbounded();
+        let server = McpServer::new(Some(tx));
+
+        let request = |mode: &str| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": 4,
+                "method": "tools/call",
+                "params": {
+                    "name": "layer_set_loop_mode",
+                    "arguments": { "layer_id": 2, "loop_mode": mode }
+                }
+            })
+            .to_string()
+        };
+
+        server.handle_request(&request("ping-pong")).await;
+        match rx.try_recv().unwrap() {
+            McpAction::LayerSetLoopMode(layer_id, mode) => {
+                assert_eq!(layer_id, 2);
+                assert_eq!(mode, "ping-pong");
+            }
+            other => panic!("Expected LayerSetLoopMode action, got {:?}", other),
+        }
+
+        let response = server.handle_request(&request("bounce")).await.unwrap();
+        assert!(response.error.is_some());
+        assert!(rx.try_recv().is_err());
+    }
+
     #[tokio::test]
     async fn test_handle_cue_navigation() {
         let (tx, rx) = unbounded();
@@ -0,0 +1,1123 @@
+//! Audio track decoding and playback for media files
+//!
+//! An [`AudioTrack`] decodes the soundtrack of a clip into an [`AudioQueue`],
+//! which an audio output drains in its device callback. The number of
+//! samples the device has consumed is the playback position of the track,
+//! and [`VideoPlayer`](crate::VideoPlayer) follows it as the master clock.
+
+use crate::{MediaError, Result};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use mapmap_core::{AudioMediaPipeline, AudioSource};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default amount of audio decoded ahead of the playback position
+const DEFAULT_BUFFER_AHEAD: Duration = Duration::from_millis(200);
+
+/// Number of mono chunks the analysis tap holds before dropping
+const ANALYSIS_QUEUE_SIZE: usize = 64;
+
+/// Decoded audio samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedAudio {
+    /// Interleaved samples in the range -1.0 to 1.0
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Position of the first sample in the media
+    pub pts: Duration,
+}
+
+impl DecodedAudio {
+    /// Get the number of sample frames (samples per channel)
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    /// Get the playback duration of the samples
+    pub fn duration(&self) -> Duration {
+        frames_to_duration(self.frames() as u64, self.sample_rate)
+    }
+
+    /// Mix all channels down to mono
+    pub fn to_mono(&self) -> Vec<f32> {
+        downmix(&self.samples, self.channels)
+    }
+}
+
+/// Audio decoder trait
+///
+/// Like [`VideoDecoder`](crate::VideoDecoder), audio decoders do not need to
+/// be `Send`; they are driven from the thread that owns the player.
+pub trait AudioDecoder {
+    /// Decode the next chunk of interleaved samples
+    fn next_samples(&mut self) -> Result<DecodedAudio>;
+    fn seek(&mut self, timestamp: Duration) -> Result<()>;
+    fn duration(&self) -> Duration;
+    /// Sample rate of the decoded samples
+    fn sample_rate(&self) -> u32;
+    /// Channel count of the decoded samples
+    fn channels(&self) -> u16;
+}
+
+fn frames_to_duration(frames: u64, sample_rate: u32) -> Duration {
+    let nanos = frames as u128 * 1_000_000_000 / sample_rate.max(1) as u128;
+    Duration::from_nanos(nanos as u64)
+}
+
+fn duration_to_frames(duration: Duration, sample_rate: u32) -> u64 {
+    (duration.as_secs_f64() * sample_rate as f64).round() as u64
+}
+
+fn downmix(samples: &[f32], channels: u16) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+// ============================================================================
+// Audio Queue (shared with the output device)
+// ============================================================================
+
+struct QueueState {
+    samples: VecDeque<f32>,
+    /// Media position of the first sample pushed after the last reset
+    start: Duration,
+    frames_played: u64,
+    playing: bool,
+    connected: bool,
+    finished: bool,
+    volume: f32,
+    underruns: u64,
+    tap: Option<Sender<Vec<f32>>>,
+}
+
+/// Buffer of decoded samples shared between a track and an audio output
+///
+/// The output calls [`AudioQueue::pull`] from its device callback. Samples
+/// only count as played once they were pulled, so [`AudioQueue::position`]
+/// is the audio clock the video follows. Cloning the queue returns another
+/// handle to the same buffer.
+#[derive(Clone)]
+pub struct AudioQueue {
+    sample_rate: u32,
+    channels: u16,
+    state: Arc<Mutex<QueueState>>,
+}
+
+impl AudioQueue {
+    /// Create an empty, paused queue
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1),
+            channels: channels.max(1),
+            state: Arc::new(Mutex::new(QueueState {
+                samples: VecDeque::new(),
+                start: Duration::ZERO,
+                frames_played: 0,
+                playing: false,
+                connected: false,
+                finished: false,
+                volume: 1.0,
+                underruns: 0,
+                tap: None,
+            })),
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Append interleaved samples
+    pub fn push(&self, samples: &[f32]) {
+        self.state.lock().samples.extend(samples);
+    }
+
+    /// Fill `out` with the next interleaved samples (called by the output)
+    ///
+    /// Plays silence while the queue is paused or has run dry. Returns the
+    /// number of samples taken from the queue.
+    pub fn pull(&self, out: &mut [f32]) -> usize {
+        let channels = self.channels as usize;
+        let mut state = self.state.lock();
+        state.connected = true;
+        if !state.playing {
+            out.fill(0.0);
+            return 0;
+        }
+
+        // Only hand out whole frames so the channels stay aligned
+        let count = out.len().min(state.samples.len()) / channels * channels;
+        let volume = state.volume;
+        for (dst, src) in out.iter_mut().zip(state.samples.drain(..count)) {
+            *dst = src * volume;
+        }
+        out[count..].fill(0.0);
+
+        if count < out.len() && !state.finished {
+            state.underruns += 1;
+        }
+        state.frames_played += (count / channels) as u64;
+        if count > 0 {
+            if let Some(tap) = &state.tap {
+                let _ = tap.try_send(downmix(&out[..count], self.channels));
+            }
+        }
+        count
+    }
+
+    /// Get the media position of the next sample the output plays
+    pub fn position(&self) -> Duration {
+        let state = self.state.lock();
+        state.start + frames_to_duration(state.frames_played, self.sample_rate)
+    }
+
+    /// Get the duration of the samples waiting to be played
+    pub fn buffered(&self) -> Duration {
+        let len = self.state.lock().samples.len();
+        frames_to_duration((len / self.channels as usize) as u64, self.sample_rate)
+    }
+
+    /// Discard all samples and continue at `position`
+    pub fn reset(&self, position: Duration) {
+        let mut state = self.state.lock();
+        state.samples.clear();
+        state.start = position;
+        state.frames_played = 0;
+        state.finished = false;
+    }
+
+    pub fn set_playing(&self, playing: bool) {
+        self.state.lock().playing = playing;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.state.lock().playing
+    }
+
+    /// Check if an output has pulled from the queue and is still attached
+    pub fn is_connected(&self) -> bool {
+        self.state.lock().connected
+    }
+
+    /// Mark the queue as no longer drained by an output
+    pub fn disconnect(&self) {
+        self.state.lock().connected = false;
+    }
+
+    /// Mark the end of the track, so running dry is not an underrun
+    pub fn set_finished(&self) {
+        self.state.lock().finished = true;
+    }
+
+    /// Check if the track has ended and every sample was played
+    pub fn is_drained(&self) -> bool {
+        let state = self.state.lock();
+        state.finished && state.samples.is_empty()
+    }
+
+    /// Set the output volume (0.0 - 1.0)
+    pub fn set_volume(&self, volume: f32) {
+        self.state.lock().volume = volume.clamp(0.0, 1.0);
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.state.lock().volume
+    }
+
+    /// Get the number of device callbacks that ran out of samples
+    pub fn underruns(&self) -> u64 {
+        self.state.lock().underruns
+    }
+
+    fn set_tap(&self, tap: Sender<Vec<f32>>) {
+        self.state.lock().tap = Some(tap);
+    }
+}
+
+impl std::fmt::Debug for AudioQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AudioQueue")
+            .field("sample_rate", &self.sample_rate)
+            .field("channels", &self.channels)
+            .field("position", &self.position())
+            .field("buffered", &self.buffered())
+            .finish()
+    }
+}
+
+// ============================================================================
+// Audio Track
+// ============================================================================
+
+/// The soundtrack of a clip, decoded ahead into an [`AudioQueue`]
+///
+/// Attach it to a player with
+/// [`VideoPlayer::set_audio_track`](crate::VideoPlayer::set_audio_track),
+/// which keeps the queue filled and starts, pauses and seeks it together
+/// with the video. Hand [`AudioTrack::queue`] to an audio output to hear it.
+pub struct AudioTrack {
+    decoder: Box<dyn AudioDecoder>,
+    queue: AudioQueue,
+    buffer_ahead: Duration,
+    /// Drop decoded samples before this position (set by seeking)
+    trim_to: Option<Duration>,
+    ended: bool,
+}
+
+impl AudioTrack {
+    /// Create a track from an audio decoder
+    pub fn new(decoder: impl AudioDecoder + 'static) -> Self {
+        Self::new_with_box(Box::new(decoder))
+    }
+
+    /// Create a track from a boxed audio decoder
+    pub fn new_with_box(decoder: Box<dyn AudioDecoder>) -> Self {
+        let queue = AudioQueue::new(decoder.sample_rate(), decoder.channels());
+        Self {
+            decoder,
+            queue,
+            buffer_ahead: DEFAULT_BUFFER_AHEAD,
+            trim_to: None,
+            ended: false,
+        }
+    }
+
+    /// Open the audio stream of a media file, resampled to the given format
+    ///
+    /// The format should match the audio output, see
+    /// `CpalAudioOutput::sample_rate` and `CpalAudioOutput::channels`.
+    /// Returns `MediaError::NoAudioStream` if the file has no audio or FFmpeg
+    /// support is not enabled.
+    pub fn open<P: AsRef<Path>>(path: P, sample_rate: u32, channels: u16) -> Result<Self> {
+        #[cfg(feature = "ffmpeg")]
+        {
+            let decoder = ffmpeg_impl::FFmpegAudioDecoder::open(path, sample_rate, channels)?;
+            Ok(Self::new(decoder))
+        }
+
+        #[cfg(not(feature = "ffmpeg"))]
+        {
+            let _ = (path, sample_rate, channels);
+            Err(MediaError::NoAudioStream)
+        }
+    }
+
+    /// Set how much audio is decoded ahead of the playback position
+    pub fn with_buffer_ahead(mut self, buffer_ahead: Duration) -> Self {
+        self.buffer_ahead = buffer_ahead;
+        self
+    }
+
+    /// Get a handle to the queue the output plays from
+    pub fn queue(&self) -> AudioQueue {
+        self.queue.clone()
+    }
+
+    /// Receive the played samples mixed down to mono
+    ///
+    /// Chunks arrive as the output consumes them, at the sample rate of the
+    /// track, and are dropped if the receiver falls behind. Calling this
+    /// again replaces the previous receiver.
+    pub fn analysis_receiver(&self) -> Receiver<Vec<f32>> {
+        let (tx, rx) = bounded(ANALYSIS_QUEUE_SIZE);
+        self.queue.set_tap(tx);
+        rx
+    }
+
+    /// Feed the played samples to `pipeline` as [`AudioSource::VideoAudio`]
+    ///
+    /// Effects driven by the pipeline then react to the clip's own
+    /// soundtrack. Replaces the receiver from [`AudioTrack::analysis_receiver`].
+    pub fn connect_pipeline(&self, pipeline: &mut AudioMediaPipeline) {
+        pipeline.set_feed(AudioSource::VideoAudio, self.analysis_receiver());
+    }
+
+    /// Decode until the queue holds the configured amount of audio
+    pub fn fill(&mut self) -> Result<()> {
+        while !self.ended && self.queue.buffered() < self.buffer_ahead {
+            match self.decoder.next_samples() {
+                Ok(audio) => self.push(audio),
+                Err(MediaError::EndOfStream) => {
+                    self.ended = true;
+                    self.queue.set_finished();
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, audio: DecodedAudio) {
+        let Some(target) = self.trim_to else {
+            self.queue.push(&audio.samples);
+            return;
+        };
+
+        // Seeking lands on the packet before the target, skip up to it
+        if audio.pts + audio.duration() <= target {
+            return;
+        }
+        let skip = duration_to_frames(target.saturating_sub(audio.pts), audio.sample_rate);
+        let skip = (skip as usize * audio.channels as usize).min(audio.samples.len());
+        self.queue.push(&audio.samples[skip..]);
+        self.trim_to = None;
+    }
+
+    /// Seek to `timestamp`, discarding the buffered samples
+    pub fn seek(&mut self, timestamp: Duration) -> Result<()> {
+        self.decoder.seek(timestamp)?;
+        self.queue.reset(timestamp);
+        self.trim_to = Some(timestamp);
+        self.ended = false;
+        Ok(())
+    }
+
+    /// Get the playback position (the audio clock)
+    pub fn position(&self) -> Duration {
+        self.queue.position()
+    }
+
+    /// Get the playback position if it can drive the video
+    ///
+    /// This is `None` until an output drains the queue and after the track
+    /// has played to its end.
+    pub fn clock(&self) -> Option<Duration> {
+        (self.queue.is_connected() && !self.queue.is_drained()).then(|| self.queue.position())
+    }
+
+    pub fn is_ended(&self) -> bool {
+        self.ended
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.decoder.duration()
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.queue.sample_rate()
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.queue.channels()
+    }
+}
+
+impl std::fmt::Debug for AudioTrack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AudioTrack")
+            .field("queue", &self.queue)
+            .field("buffer_ahead", &self.buffer_ahead)
+            .field("ended", &self.ended)
+            .finish()
+    }
+}
+
+// ============================================================================
+// FFmpeg Implementation (when feature is enabled)
+// ============================================================================
+
+#[cfg(feature = "ffmpeg")]
+mod ffmpeg_impl {
+    use super::*;
+    use ffmpeg_next as ffmpeg;
+    use tracing::info;
+
+    /// Decodes the best audio stream of a file to packed f32 samples
+    pub struct FFmpegAudioDecoder {
+        input_ctx: ffmpeg::format::context::Input,
+        decoder: ffmpeg::codec::decoder::Audio,
+        resampler: ffmpeg::software::resampling::Context,
+        audio_stream_idx: usize,
+        time_base: ffmpeg::Rational,
+        duration: Duration,
+        sample_rate: u32,
+        channels: u16,
+        /// Position after the last decoded chunk, used for frames without pts
+        position: Duration,
+        /// The demuxer reached the end and the decoder is being drained
+        draining: bool,
+        /// The decoder and resampler are drained
+        finished: bool,
+    }
+
+    impl FFmpegAudioDecoder {
+        pub fn open<P: AsRef<Path>>(path: P, sample_rate: u32, channels: u16) -> Result<Self> {
+            let path = path.as_ref();
+
+            if !path.exists() {
+                return Err(MediaError::FileOpen(format!(
+                    "File not found: {}",
+                    path.display()
+                )));
+            }
+
+            ffmpeg::init().map_err(|e| MediaError::DecoderError(e.to_string()))?;
+
+            let input_ctx =
+                ffmpeg::format::input(&path).map_err(|e| MediaError::FileOpen(e.to_string()))?;
+
+            let audio_stream = input_ctx
+                .streams()
+                .best(ffmpeg::media::Type::Audio)
+                .ok_or(MediaError::NoAudioStream)?;
+
+            let audio_stream_idx = audio_stream.index();
+            let time_base = audio_stream.time_base();
+            let duration_secs = audio_stream.duration().max(0) as f64 * f64::from(time_base);
+            let duration = Duration::from_secs_f64(duration_secs);
+
+            let decoder = ffmpeg::codec::Context::from_parameters(audio_stream.parameters())
+                .map_err(|e| MediaError::DecoderError(e.to_string()))?
+                .decoder()
+                .audio()
+                .map_err(|e| MediaError::DecoderError(e.to_string()))?;
+
+            let resampler = resampler(&decoder, sample_rate, channels)?;
+
+            info!(
+                "Audio decoder initialized: {} Hz, {} channels -> {} Hz, {} channels, duration: {:.2}s",
+                decoder.rate(),
+                decoder.channels(),
+                sample_rate,
+                channels,
+                duration_secs
+            );
+
+            Ok(Self {
+                input_ctx,
+                decoder,
+                resampler,
+                audio_stream_idx,
+                time_base,
+                duration,
+                sample_rate,
+                channels,
+                position: Duration::ZERO,
+                draining: false,
+                finished: false,
+            })
+        }
+
+        /// Read the next packet of the audio stream, `None` at the end
+        fn next_packet(&mut self) -> Option<ffmpeg::Packet> {
+            let index = self.audio_stream_idx;
+            self.input_ctx
+                .packets()
+                .find(|(stream, _)| stream.index() == index)
+                .map(|(_, packet)| packet)
+        }
+
+        /// Resample a decoded frame, or flush the resampler if `decoded` is `None`
+        fn resample(&mut self, decoded: Option<&ffmpeg::frame::Audio>) -> Result<DecodedAudio> {
+            // Room for everything the resampler buffered plus the new frame
+            let input_samples = decoded.map_or(0, |frame| frame.samples()) as u64;
+            let buffered = self
+                .resampler
+                .delay()
+                .map_or(0, |delay| delay.output.max(0) as u64);
+            let capacity = (input_samples * u64::from(self.sample_rate))
+                .div_ceil(u64::from(self.decoder.rate().max(1)))
+                + buffered
+                + 32;
+            let mut resampled = ffmpeg::frame::Audio::new(
+                ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+                capacity as usize,
+                ffmpeg::ChannelLayout::default(self.channels as i32),
+            );
+            let result = match decoded {
+                Some(frame) => self.resampler.run(frame, &mut resampled),
+                None => self.resampler.flush(&mut resampled),
+            };
+            result.map_err(|e| MediaError::DecoderError(e.to_string()))?;
+
+            // Packed audio keeps all channels in the first plane
+            let len = resampled.samples() * self.channels as usize;
+            let samples: Vec<f32> = resampled
+                .data(0)
+                .chunks_exact(4)
+                .take(len)
+                .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+
+            let pts = decoded
+                .and_then(|frame| frame.timestamp())
+                .map(|ts| Duration::from_secs_f64(ts.max(0) as f64 * f64::from(self.time_base)))
+                .unwrap_or(self.position);
+
+            let audio = DecodedAudio {
+                samples,
+                sample_rate: self.sample_rate,
+                channels: self.channels,
+                pts,
+            };
+            self.position = pts + audio.duration();
+            Ok(audio)
+        }
+    }
+
+    /// Create a resampler from the decoder's format to packed f32
+    fn resampler(
+        decoder: &ffmpeg::codec::decoder::Audio,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Result<ffmpeg::software::resampling::Context> {
+        // Some containers leave the layout unset, derive it from the channel count
+        let mut source_layout = decoder.channel_layout();
+        if source_layout.is_empty() {
+            source_layout = ffmpeg::ChannelLayout::default(decoder.channels() as i32);
+        }
+
+        ffmpeg::software::resampling::Context::get(
+            decoder.format(),
+            source_layout,
+            decoder.rate(),
+            ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+            ffmpeg::ChannelLayout::default(channels as i32),
+            sample_rate,
+        )
+        .map_err(|e| MediaError::DecoderError(e.to_string()))
+    }
+
+    impl AudioDecoder for FFmpegAudioDecoder {
+        fn next_samples(&mut self) -> Result<DecodedAudio> {
+            if self.finished {
+                return Err(MediaError::EndOfStream);
+            }
+
+            loop {
+                // A packet can hold several frames, return them all before reading on
+                let mut decoded = ffmpeg::frame::Audio::empty();
+                match self.decoder.receive_frame(&mut decoded) {
+                    Ok(()) => return self.resample(Some(&decoded)),
+                    Err(ffmpeg::Error::Other {
+                        errno: ffmpeg::error::EAGAIN,
+                    }) => {}
+                    Err(ffmpeg::Error::Eof) => {
+                        // The decoder is drained, hand out what the resampler still holds
+                        self.finished = true;
+                        let rest = self.resample(None)?;
+                        if rest.samples.is_empty() {
+                            return Err(MediaError::EndOfStream);
+                        }
+                        return Ok(rest);
+                    }
+                    Err(e) => return Err(MediaError::DecoderError(e.to_string())),
+                }
+
+                match self.next_packet() {
+                    Some(packet) => self
+                        .decoder
+                        .send_packet(&packet)
+                        .map_err(|e| MediaError::DecoderError(e.to_string()))?,
+                    None if !self.draining => {
+                        self.draining = true;
+                        self.decoder
+                            .send_eof()
+                            .map_err(|e| MediaError::DecoderError(e.to_string()))?;
+                    }
+                    None => {
+                        // Drained decoders report Eof, guard against ones that don't
+                        self.finished = true;
+                        return Err(MediaError::EndOfStream);
+                    }
+                }
+            }
+        }
+
+        fn seek(&mut self, timestamp: Duration) -> Result<()> {
+            let timestamp_ts = (timestamp.as_secs_f64() / f64::from(self.time_base)) as i64;
+
+            self.input_ctx
+                .seek(timestamp_ts, ..timestamp_ts)
+                .map_err(|e| MediaError::SeekError(e.to_string()))?;
+
+            // Samples buffered before the seek belong to the old position
+            self.decoder.flush();
+            self.resampler = resampler(&self.decoder, self.sample_rate, self.channels)?;
+            self.position = timestamp;
+            self.draining = false;
+            self.finished = false;
+
+            Ok(())
+        }
+
+        fn duration(&self) -> Duration {
+            self.duration
+        }
+
+        fn sample_rate(&self) -> u32 {
+            self.sample_rate
+        }
+
+        fn channels(&self) -> u16 {
+            self.channels
+        }
+    }
+}
+
+#[cfg(feature = "ffmpeg")]
+pub use ffmpeg_impl::FFmpegAudioDecoder;
+
+// ============================================================================
+// Test Tone Fallback (always available)
+// ============================================================================
+
+/// Test tone decoder producing a sine wave
+#[derive(Debug, Clone)]
+pub struct TestToneDecoder {
+    frequency: f32,
+    sample_rate: u32,
+    channels: u16,
+    duration: Duration,
+    frames_decoded: u64,
+    chunk_frames: usize,
+}
+
+impl TestToneDecoder {
+    /// Create a new test tone decoder
+    pub fn new(frequency: f32, sample_rate: u32, channels: u16, duration: Duration) -> Self {
+        Self {
+            frequency,
+            sample_rate: sample_rate.max(1),
+            channels: channels.max(1),
+            duration,
+            frames_decoded: 0,
+            chunk_frames: 1024,
+        }
+    }
+}
+
+impl AudioDecoder for TestToneDecoder {
+    fn next_samples(&mut self) -> Result<DecodedAudio> {
+        let total = duration_to_frames(self.duration, self.sample_rate);
+        if self.frames_decoded >= total {
+            return Err(MediaError::EndOfStream);
+        }
+
+        let frames = (self.chunk_frames as u64).min(total - self.frames_decoded);
+        let channels = self.channels as usize;
+        let mut samples = Vec::with_capacity(frames as usize * channels);
+        for i in 0..frames {
+            let t = (self.frames_decoded + i) as f32 / self.sample_rate as f32;
+            let value = (t * self.frequency * std::f32::consts::TAU).sin() * 0.25;
+            samples.extend(std::iter::repeat(value).take(channels));
+        }
+
+        let pts = frames_to_duration(self.frames_decoded, self.sample_rate);
+        self.frames_decoded += frames;
+
+        Ok(DecodedAudio {
+            samples,
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            pts,
+        })
+    }
+
+    fn seek(&mut self, timestamp: Duration) -> Result<()> {
+        if timestamp > self.duration {
+            return Err(MediaError::SeekError(
+                "Timestamp beyond duration".to_string(),
+            ));
+        }
+
+        self.frames_decoded = duration_to_frames(timestamp, self.sample_rate);
+        Ok(())
+    }
+
+    fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+}
+
+// ============================================================================
+// CPAL Output (when feature is enabled)
+// ============================================================================
+
+/// Plays an [`AudioQueue`] on a CPAL output device
+#[cfg(feature = "audio")]
+pub mod cpal_output {
+    use super::AudioQueue;
+    use crate::{MediaError, Result};
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use tracing::{error, info};
+
+    /// CPAL audio output
+    ///
+    /// `cpal::Stream` is not `Send`, so the output stays on the thread that
+    /// created it, like the player.
+    pub struct CpalAudioOutput {
+        device: cpal::Device,
+        config: cpal::SupportedStreamConfig,
+        stream: Option<cpal::Stream>,
+        queue: Option<AudioQueue>,
+    }
+
+    impl CpalAudioOutput {
+        /// Open the output device with the given name, or the default device
+        pub fn new(device_name: Option<String>) -> Result<Self> {
+            let host = cpal::default_host();
+
+            let device = match device_name {
+                Some(name) => host
+                    .output_devices()
+                    .map_err(|e| MediaError::AudioOutput(e.to_string()))?
+                    .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+                    .ok_or_else(|| {
+                        MediaError::AudioOutput(format!("Device '{}' not found", name))
+                    })?,
+                None => host.default_output_device().ok_or_else(|| {
+                    MediaError::AudioOutput("No default output device".to_string())
+                })?,
+            };
+
+            let config = device
+                .default_output_config()
+                .map_err(|e| MediaError::AudioOutput(e.to_string()))?;
+
+            Ok(Self {
+                device,
+                config,
+                stream: None,
+                queue: None,
+            })
+        }
+
+        /// List the names of the available output devices
+        pub fn list_devices() -> Result<Vec<String>> {
+            let host = cpal::default_host();
+            let devices = host
+                .output_devices()
+                .map_err(|e| MediaError::AudioOutput(e.to_string()))?;
+            Ok(devices.filter_map(|d| d.name().ok()).collect())
+        }
+
+        /// Sample rate tracks must be decoded at for this device
+        pub fn sample_rate(&self) -> u32 {
+            self.config.sample_rate().0
+        }
+
+        /// Channel count tracks must be decoded with for this device
+        pub fn channels(&self) -> u16 {
+            self.config.channels()
+        }
+
+        /// Start playing `queue`, replacing the queue played before
+        pub fn play(&mut self, queue: AudioQueue) -> Result<()> {
+            if queue.sample_rate() != self.sample_rate() || queue.channels() != self.channels() {
+                return Err(MediaError::AudioOutput(format!(
+                    "Queue format {} Hz/{} ch does not match device format {} Hz/{} ch",
+                    queue.sample_rate(),
+                    queue.channels(),
+                    self.sample_rate(),
+                    self.channels()
+                )));
+            }
+            self.stop();
+
+            let stream = match self.config.sample_format() {
+                cpal::SampleFormat::F32 => self.build_stream::<f32>(queue.clone()),
+                cpal::SampleFormat::I16 => self.build_stream::<i16>(queue.clone()),
+                cpal::SampleFormat::U16 => self.build_stream::<u16>(queue.clone()),
+                format => {
+                    return Err(MediaError::AudioOutput(format!(
+                        "Unsupported sample format: {:?}",
+                        format
+                    )));
+                }
+            }?;
+            stream
+                .play()
+                .map_err(|e| MediaError::AudioOutput(format!("Failed to start stream: {}", e)))?;
+
+            info!(
+                "Audio output started: {} Hz, {} channels",
+                self.sample_rate(),
+                self.channels()
+            );
+            self.stream = Some(stream);
+            self.queue = Some(queue);
+            Ok(())
+        }
+
+        fn build_stream<T>(&self, queue: AudioQueue) -> Result<cpal::Stream>
+        where
+            T: cpal::SizedSample + cpal::FromSample<f32>,
+        {
+            let mut scratch = Vec::new();
+            self.device
+                .build_output_stream(
+                    &self.config.config(),
+                    move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                        scratch.resize(data.len(), 0.0);
+                        queue.pull(&mut scratch);
+                        for (dst, src) in data.iter_mut().zip(&scratch) {
+                            *dst = T::from_sample(*src);
+                        }
+                    },
+                    |err| error!("Audio output stream error: {}", err),
+                    None,
+                )
+                .map_err(|e| MediaError::AudioOutput(e.to_string()))
+        }
+
+        /// Stop playback and release the queue
+        pub fn stop(&mut self) {
+            self.stream = None;
+            if let Some(queue) = self.queue.take() {
+                queue.disconnect();
+            }
+        }
+
+        pub fn is_playing(&self) -> bool {
+            self.stream.is_some()
+        }
+    }
+
+    impl Drop for CpalAudioOutput {
+        fn drop(&mut self) {
+            self.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone_track() -> AudioTrack {
+        AudioTrack::new(TestToneDecoder::new(
+            440.0,
+            48000,
+            2,
+            Duration::from_secs(2),
+        ))
+    }
+
+    #[test]
+    fn test_test_tone_decoder() {
+        let mut decoder = TestToneDecoder::new(440.0, 48000, 2, Duration::from_millis(30));
+
+        let audio = decoder.next_samples().unwrap();
+        assert_eq!(audio.frames(), 1024);
+        assert_eq!(audio.samples.len(), 2048);
+        assert_eq!(audio.pts, Duration::ZERO);
+        assert!(audio.samples.iter().all(|s| s.abs() <= 0.25));
+        assert_eq!(audio.to_mono().len(), 1024);
+
+        // 30 ms at 48 kHz are 1440 frames
+        let rest = decoder.next_samples().unwrap();
+        assert_eq!(rest.frames(), 416);
+        assert_eq!(rest.pts, audio.duration());
+        assert!(matches!(
+            decoder.next_samples(),
+            Err(MediaError::EndOfStream)
+        ));
+
+        decoder.seek(Duration::from_millis(10)).unwrap();
+        assert_eq!(
+            decoder.next_samples().unwrap().pts,
+            Duration::from_millis(10)
+        );
+        assert!(decoder.seek(Duration::from_secs(1)).is_err());
+    }
+
+    #[test]
+    fn test_queue_clock_counts_played_samples() {
+        let queue = AudioQueue::new(1000, 2);
+        queue.push(&[0.5; 200]);
+        assert_eq!(queue.buffered(), Duration::from_millis(100));
+
+        // Paused queues play silence and keep the clock still
+        let mut out = [1.0; 40];
+        assert_eq!(queue.pull(&mut out), 0);
+        assert!(out.iter().all(|s| *s == 0.0));
+        assert_eq!(queue.position(), Duration::ZERO);
+        assert!(queue.is_connected());
+
+        queue.set_playing(true);
+        queue.set_volume(0.5);
+        assert_eq!(queue.pull(&mut out), 40);
+        assert!(out.iter().all(|s| *s == 0.25));
+        assert_eq!(queue.position(), Duration::from_millis(20));
+
+        // Running dry pads with silence and counts an underrun
+        let mut out = [1.0; 400];
+        assert_eq!(queue.pull(&mut out), 160);
+        assert!(out[160..].iter().all(|s| *s == 0.0));
+        assert_eq!(queue.position(), Duration::from_millis(100));
+        assert_eq!(queue.underruns(), 1);
+
+        queue.set_finished();
+        queue.pull(&mut out);
+        assert_eq!(queue.underruns(), 1);
+        assert!(queue.is_drained());
+
+        queue.reset(Duration::from_secs(5));
+        assert_eq!(queue.position(), Duration::from_secs(5));
+        assert!(!queue.is_drained());
+    }
+
+    #[test]
+    fn test_track_fills_ahead_and_seeks() {
+        let mut track = tone_track().with_buffer_ahead(Duration::from_millis(100));
+        let queue = track.queue();
+        assert_eq!(track.clock(), None);
+
+        track.fill().unwrap();
+        assert!(queue.buffered() >= Duration::from_millis(100));
+        assert!(queue.buffered() < Duration::from_millis(130));
+
+        queue.set_playing(true);
+        let mut out = vec![0.0; 960];
+        queue.pull(&mut out);
+        assert_eq!(track.clock(), Some(Duration::from_millis(10)));
+
+        // Seeking trims the decoded chunk to the exact target
+        track.seek(Duration::from_millis(1010)).unwrap();
+        track.fill().unwrap();
+        assert_eq!(track.position(), Duration::from_millis(1010));
+        let mut first = vec![0.0; 2];
+        queue.pull(&mut first);
+        let expected = (1.01f32 * 440.0 * std::f32::consts::TAU).sin() * 0.25;
+        assert!((first[0] - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_track_drains_at_end() {
+        let mut track = AudioTrack::new(TestToneDecoder::new(
+            440.0,
+            1000,
+            1,
+            Duration::from_millis(50),
+        ));
+        let queue = track.queue();
+        queue.set_playing(true);
+
+        track.fill().unwrap();
+        assert!(track.is_ended());
+        let mut out = vec![0.0; 100];
+        queue.pull(&mut out);
+        assert!(queue.is_drained());
+        assert_eq!(track.clock(), None);
+        assert_eq!(queue.underruns(), 0);
+    }
+
+    #[test]
+    fn test_analysis_receiver_gets_played_mono_samples() {
+        let mut track = tone_track();
+        let rx = track.analysis_receiver();
+        let queue = track.queue();
+        track.fill().unwrap();
+
+        let mut out = vec![0.0; 512];
+        queue.pull(&mut out);
+        assert!(rx.try_recv().is_err());
+
+        queue.set_playing(true);
+        queue.pull(&mut out);
+        let mono = rx.try_recv().unwrap();
+        assert_eq!(mono.len(), 256);
+        assert_eq!(mono[10], out[20]);
+    }
+
+    /// Writes `frames` of 16-bit mono PCM at `rate` as a WAV file
+    #[cfg(feature = "ffmpeg")]
+    fn write_wav(path: &Path, rate: u32, frames: u32) {
+        let data_len = frames * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&rate.to_le_bytes());
+        wav.extend_from_slice(&(rate * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for i in 0..frames {
+            let sample = ((i as f32 * 0.05).sin() * 8000.0) as i16;
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+        std::fs::write(path, wav).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "ffmpeg")]
+    fn test_ffmpeg_decoder_keeps_the_end() {
+        let path = std::env::temp_dir().join(format!("mapflow-audio-{}.wav", std::process::id()));
+        write_wav(&path, 44100, 44100);
+
+        // Resampling to 48 kHz stereo, one second must stay one second
+        let mut decoder = FFmpegAudioDecoder::open(&path, 48000, 2).unwrap();
+        let mut frames = 0;
+        loop {
+            match decoder.next_samples() {
+                Ok(audio) => frames += audio.frames(),
+                Err(MediaError::EndOfStream) => break,
+                Err(e) => panic!("{}", e),
+            }
+        }
+        assert!(matches!(
+            decoder.next_samples(),
+            Err(MediaError::EndOfStream)
+        ));
+        assert!(
+            (frames as i64 - 48000).abs() <= 2,
+            "decoded {} frames",
+            frames
+        );
+
+        // Seeking back decodes again
+        decoder.seek(Duration::ZERO).unwrap();
+        assert!(decoder.next_samples().is_ok());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_track_feeds_pipeline() {
+        use mapmap_core::audio_media_pipeline::AudioPipelineConfig;
+
+        let mut pipeline = AudioMediaPipeline::with_config(
+            mapmap_core::AudioConfig::default(),
+            AudioPipelineConfig {
+                sample_rate: 48000,
+                ..Default::default()
+            },
+        );
+        let mut track = tone_track();
+        track.connect_pipeline(&mut pipeline);
+        assert_eq!(pipeline.source(), AudioSource::VideoAudio);
+
+        let queue = track.queue();
+        track.fill().unwrap();
+        queue.set_playing(true);
+        let mut out = vec![0.0; 960];
+        queue.pull(&mut out);
+
+        pipeline.get_analysis();
+        assert!((pipeline.audio_position() - 0.01).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,146 @@
+//! Beat-synced playback
+//!
+//! A [`BeatSync`] locks a clip to musical time. The clip's in/out range is
+//! given a length in beats, and the player derives its speed from that length
+//! and the current tempo, so the clip always spans the same number of beats.
+//!
+//! The tempo and beat count come from outside the media crate, e.g. from
+//! `AudioAnalysis::tempo_bpm` and a count of detected beats, or from
+//! `MidiClock::get_tempo_bpm` and `MidiClock::get_beat_position`. They are
+//! passed to [`VideoPlayer::set_tempo`](crate::VideoPlayer::set_tempo) and
+//! [`VideoPlayer::on_beat`](crate::VideoPlayer::on_beat).
+
+use std::time::Duration;
+
+/// Beat grid that a queued cue jump waits for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quantize {
+    #[default]
+    Beat, // Next beat
+    Bar, // Next downbeat
+}
+
+/// Settings locking a clip to the tempo
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeatSync {
+    /// Length of the in/out range in beats
+    pub clip_beats: f32,
+    /// Beats per bar, the first beat of a bar is the downbeat
+    pub beats_per_bar: u32,
+    /// Positions in the clip that can be jumped to on a beat
+    pub cue_points: Vec<Duration>,
+    /// Beat grid for cue jumps
+    pub quantize: Quantize,
+    /// Jump back in phase with the beat on every downbeat
+    pub resync_on_downbeat: bool,
+}
+
+impl BeatSync {
+    /// Create settings for a clip spanning `clip_beats` beats in 4/4
+    pub fn new(clip_beats: f32) -> Self {
+        Self {
+            clip_beats: clip_beats.max(0.25),
+            beats_per_bar: 4,
+            cue_points: Vec::new(),
+            quantize: Quantize::default(),
+            resync_on_downbeat: true,
+        }
+    }
+
+    pub fn with_beats_per_bar(mut self, beats_per_bar: u32) -> Self {
+        self.beats_per_bar = beats_per_bar.max(1);
+        self
+    }
+
+    pub fn with_cue_points(mut self, cue_points: Vec<Duration>) -> Self {
+        self.cue_points = cue_points;
+        self
+    }
+
+    pub fn with_quantize(mut self, quantize: Quantize) -> Self {
+        self.quantize = quantize;
+        self
+    }
+
+    pub fn with_resync_on_downbeat(mut self, resync: bool) -> Self {
+        self.resync_on_downbeat = resync;
+        self
+    }
+
+    /// Get the playback speed that makes `clip_length` last `clip_beats` at `bpm`
+    pub fn speed(&self, clip_length: Duration, bpm: f32) -> f32 {
+        let beats_duration = self.clip_beats as f64 * 60.0 / bpm.max(1.0) as f64;
+        (clip_length.as_secs_f64() / beats_duration) as f32
+    }
+
+    /// Get how much clip time one beat covers
+    pub fn beat_length(&self, clip_length: Duration) -> Duration {
+        clip_length.div_f64(self.clip_beats as f64)
+    }
+
+    /// Check if `beat` starts a bar
+    pub fn is_downbeat(&self, beat: u64) -> bool {
+        beat % self.beats_per_bar.max(1) as u64 == 0
+    }
+
+    /// Check if a cue jump queued for the quantize grid happens on `beat`
+    pub fn is_cue_beat(&self, beat: u64) -> bool {
+        match self.quantize {
+            Quantize::Beat => true,
+            Quantize::Bar => self.is_downbeat(beat),
+        }
+    }
+
+    /// Get the offset into the clip `beats` after an anchor `offset`
+    ///
+    /// The result wraps around the clip length.
+    pub fn offset_after(&self, offset: Duration, beats: u64, clip_length: Duration) -> Duration {
+        if clip_length.is_zero() {
+            return Duration::ZERO;
+        }
+        let length = clip_length.as_secs_f64();
+        let advanced = offset.as_secs_f64() + beats as f64 * length / self.clip_beats as f64;
+        Duration::from_secs_f64(advanced.rem_euclid(length))
+    }
+}
+
+impl Default for BeatSync {
+    fn default() -> Self {
+        Self::new(4.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speed_from_tempo() {
+        // Two seconds over 4 beats at 120 BPM is exactly real time
+        let sync = BeatSync::new(4.0);
+        let clip = Duration::from_secs(2);
+        assert!((sync.speed(clip, 120.0) - 1.0).abs() < 1e-6);
+        assert!((sync.speed(clip, 60.0) - 0.5).abs() < 1e-6);
+        assert_eq!(sync.beat_length(clip), Duration::from_millis(500));
+
+        let sync = BeatSync::new(8.0);
+        assert!((sync.speed(clip, 120.0) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_beat_grid() {
+        let sync = BeatSync::new(4.0).with_beats_per_bar(3);
+        assert!(sync.is_downbeat(0));
+        assert!(!sync.is_downbeat(2));
+        assert!(sync.is_downbeat(6));
+        assert!(sync.is_cue_beat(2));
+
+        let sync = sync.with_quantize(Quantize::Bar);
+        assert!(!sync.is_cue_beat(2));
+        assert!(sync.is_cue_beat(3));
+
+        let clip = Duration::from_secs(2);
+        let offset = sync.offset_after(Duration::from_millis(1500), 3, clip);
+        assert!(offset > Duration::from_millis(999) && offset < Duration::from_millis(1001));
+    }
+}
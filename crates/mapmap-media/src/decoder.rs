@@ -13,6 +13,43 @@ pub enum PixelFormat {
     RGBA8,
     BGRA8,
     YUV420P,
+    /// DXT1 blocks (Hap)
+    BC1,
+    /// DXT5 blocks (Hap Alpha)
+    BC3,
+    /// DXT5 blocks of scaled YCoCg with luma in alpha (Hap Q)
+    BC3YCoCg,
+}
+
+impl PixelFormat {
+    /// Check if frames hold GPU texture blocks instead of pixels
+    pub fn is_compressed(self) -> bool {
+        self.block_bytes().is_some()
+    }
+
+    /// Get the bytes per 4x4 block of compressed formats
+    pub fn block_bytes(self) -> Option<usize> {
+        match self {
+            PixelFormat::BC1 => Some(8),
+            PixelFormat::BC3 | PixelFormat::BC3YCoCg => Some(16),
+            PixelFormat::RGBA8 | PixelFormat::BGRA8 | PixelFormat::YUV420P => None,
+        }
+    }
+
+    /// Get the size of a `width` x `height` frame in bytes
+    ///
+    /// Compressed frames cover the size rounded up to whole blocks.
+    pub fn frame_size(self, width: u32, height: u32) -> usize {
+        let (width, height) = (width as usize, height as usize);
+        match self {
+            PixelFormat::RGBA8 | PixelFormat::BGRA8 => width * height * 4,
+            PixelFormat::YUV420P => width * height * 3 / 2,
+            _ => {
+                let blocks = width.div_ceil(4) * height.div_ceil(4);
+                blocks * self.block_bytes().unwrap_or(0)
+            }
+        }
+    }
 }
 
 /// A decoded video frame
@@ -32,6 +69,9 @@ impl DecodedFrame {
     }
 
     /// Convert to RGBA8 format (with YUV420P conversion)
+    ///
+    /// Compressed frames are decoded on the CPU; upload them to BC textures
+    /// instead where possible.
     pub fn to_rgba(&self) -> Vec<u8> {
         match self.format {
             PixelFormat::RGBA8 => self.data.clone(),
@@ -46,6 +86,9 @@ impl DecodedFrame {
                 // Simple YUV420P to RGBA conversion (BT.601)
                 yuv420p_to_rgba(&self.data, self.width, self.height)
             }
+            PixelFormat::BC1 | PixelFormat::BC3 | PixelFormat::BC3YCoCg => {
+                crate::hap::bc_to_rgba(&self.data, self.format, self.width, self.height)
+            }
         }
     }
 }
@@ -87,11 +130,12 @@ fn yuv420p_to_rgba(yuv_data: &[u8], width: u32, height: u32) -> Vec<u8> {
 
 /// Video decoder trait
 ///
-/// Note: VideoDecoder does not require Send because FFmpeg's scaler context
-/// is not thread-safe. Decoders should be used on a single thread or wrapped
-/// in appropriate synchronization primitives.
-pub trait VideoDecoder {
+/// Decoders are `Send` so a [`FramePipeline`](crate::FramePipeline) can move
+/// them to a decode thread. FFmpeg's scaler context is not thread-safe and is
+/// therefore cached per thread instead of stored in the decoder.
+pub trait VideoDecoder: Send {
     fn next_frame(&mut self) -> Result<DecodedFrame>;
+    /// Seek so the next frame is the one shown at `timestamp`
     fn seek(&mut self, timestamp: Duration) -> Result<()>;
     fn duration(&self) -> Duration;
     fn resolution(&self) -> (u32, u32);
@@ -121,14 +165,59 @@ pub enum HwAccelType {
 mod ffmpeg_impl {
     use super::*;
     use ffmpeg_next as ffmpeg;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
     use std::path::PathBuf;
 
+    /// Source pixel format, width and height of a scaler
+    type ScalerKey = (i32, u32, u32);
+
+    thread_local! {
+        // SwsContext is not Send, so every decoding thread keeps its own
+        // RGBA scalers, created on first use
+        static SCALERS: RefCell<HashMap<ScalerKey, ffmpeg::software::scaling::Context>> =
+            RefCell::new(HashMap::new());
+    }
+
+    /// Convert a decoded frame to RGBA with this thread's scaler
+    fn scale_to_rgba(decoded: &ffmpeg::util::frame::Video) -> Result<ffmpeg::util::frame::Video> {
+        let format = decoded.format();
+        let (width, height) = (decoded.width(), decoded.height());
+        let key = (
+            ffmpeg::ffi::AVPixelFormat::from(format) as i32,
+            width,
+            height,
+        );
+
+        SCALERS.with(|scalers| {
+            let mut scalers = scalers.borrow_mut();
+            let scaler = match scalers.entry(key) {
+                std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                std::collections::hash_map::Entry::Vacant(entry) => entry.insert(
+                    ffmpeg::software::scaling::Context::get(
+                        format,
+                        width,
+                        height,
+                        ffmpeg::format::Pixel::RGBA,
+                        width,
+                        height,
+                        ffmpeg::software::scaling::Flags::BILINEAR,
+                    )
+                    .map_err(|e| MediaError::DecoderError(e.to_string()))?,
+                ),
+            };
+
+            let mut rgb_frame = ffmpeg::util::frame::Video::empty();
+            scaler
+                .run(decoded, &mut rgb_frame)
+                .map_err(|e| MediaError::DecoderError(e.to_string()))?;
+            Ok(rgb_frame)
+        })
+    }
+
     pub struct RealFFmpegDecoder {
         input_ctx: ffmpeg::format::context::Input,
         decoder: ffmpeg::codec::decoder::Video,
-        // NOTE: Scaler is not thread-safe (SwsContext is not Send)
-        // For multi-threading: Move scaler creation to next_frame() or use thread_local!
-        scaler: ffmpeg::software::scaling::Context,
         video_stream_idx: usize,
         time_base: ffmpeg::Rational,
         duration: Duration,
@@ -137,6 +226,10 @@ mod ffmpeg_impl {
         height: u32,
         hw_accel: HwAccelType,
         path: PathBuf,
+        /// Frames ending before this are decoded but not returned after a seek
+        seek_target: Option<Duration>,
+        /// Hap packets are passed on as texture blocks, bypassing FFmpeg's decoder
+        hap: bool,
     }
 
     impl RealFFmpegDecoder {
@@ -175,6 +268,7 @@ mod ffmpeg_impl {
 
             // Get stream parameters
             let codec_params = video_stream.parameters();
+            let hap = codec_params.id() == ffmpeg::codec::Id::HAP;
 
             // Calculate FPS
             let fps = video_stream.avg_frame_rate();
@@ -198,18 +292,6 @@ mod ffmpeg_impl {
             let width = decoder.width();
             let height = decoder.height();
 
-            // Create scaler to convert to RGBA
-            let scaler = ffmpeg::software::scaling::Context::get(
-                decoder.format(),
-                width,
-                height,
-                ffmpeg::format::Pixel::RGBA,
-                width,
-                height,
-                ffmpeg::software::scaling::Flags::BILINEAR,
-            )
-            .map_err(|e| MediaError::DecoderError(e.to_string()))?;
-
             info!(
                 "Decoder initialized successfully: {}x{} @ {:.2} fps, duration: {:.2}s, hw_accel: {:?}",
                 width,
@@ -222,7 +304,6 @@ mod ffmpeg_impl {
             Ok(Self {
                 input_ctx,
                 decoder,
-                scaler,
                 video_stream_idx,
                 time_base,
                 duration,
@@ -231,6 +312,8 @@ mod ffmpeg_impl {
                 height,
                 hw_accel: actual_hw_accel,
                 path: path.to_path_buf(),
+                seek_target: None,
+                hap,
             })
         }
 
@@ -258,11 +341,29 @@ mod ffmpeg_impl {
 
     impl super::VideoDecoder for RealFFmpegDecoder {
         fn next_frame(&mut self) -> Result<DecodedFrame> {
+            let frame_duration = Duration::from_secs_f64(1.0 / self.fps.max(1.0));
             for (stream, packet) in self.input_ctx.packets() {
                 if stream.index() != self.video_stream_idx {
                     continue;
                 }
 
+                if self.hap {
+                    let pts = Duration::from_secs_f64(
+                        (packet.pts().unwrap_or(0) as f64 * f64::from(self.time_base)).max(0.0),
+                    );
+                    // Hap frames are all keyframes, nothing to decode up to the target
+                    if let Some(target) = self.seek_target {
+                        if pts + frame_duration <= target {
+                            continue;
+                        }
+                        self.seek_target = None;
+                    }
+                    let data = packet
+                        .data()
+                        .ok_or_else(|| MediaError::DecoderError("Empty Hap packet".to_string()))?;
+                    return crate::hap::decode_hap_frame(data, self.width, self.height, pts);
+                }
+
                 self.decoder
                     .send_packet(&packet)
                     .map_err(|e| MediaError::DecoderError(e.to_string()))?;
@@ -270,16 +371,22 @@ mod ffmpeg_impl {
                 let mut decoded = ffmpeg::util::frame::Video::empty();
 
                 if self.decoder.receive_frame(&mut decoded).is_ok() {
-                    // Scale to RGBA
-                    let mut rgb_frame = ffmpeg::util::frame::Video::empty();
-                    self.scaler
-                        .run(&decoded, &mut rgb_frame)
-                        .map_err(|e| MediaError::DecoderError(e.to_string()))?;
-
                     let pts = Duration::from_secs_f64(
-                        decoded.timestamp().unwrap_or(0) as f64 * f64::from(self.time_base),
+                        (decoded.timestamp().unwrap_or(0) as f64 * f64::from(self.time_base))
+                            .max(0.0),
                     );
 
+                    // Skip frames between the keyframe and the seek target
+                    if let Some(target) = self.seek_target {
+                        if pts + frame_duration <= target {
+                            continue;
+                        }
+                        self.seek_target = None;
+                    }
+
+                    // Scale to RGBA
+                    let rgb_frame = scale_to_rgba(&decoded)?;
+
                     return Ok(DecodedFrame {
                         data: rgb_frame.data(0).to_vec(),
                         format: PixelFormat::RGBA8,
@@ -294,14 +401,17 @@ mod ffmpeg_impl {
         }
 
         fn seek(&mut self, timestamp: Duration) -> Result<()> {
-            let timestamp_ts = (timestamp.as_secs_f64() / f64::from(self.time_base)) as i64;
+            // Container seeks are in AV_TIME_BASE (microsecond) units
+            let timestamp_ts = timestamp.as_micros().min(i64::MAX as u128) as i64;
 
+            // Land on the keyframe at or before the target, then decode up to it
             self.input_ctx
-                .seek(timestamp_ts, ..)
+                .seek(timestamp_ts, ..=timestamp_ts)
                 .map_err(|e| MediaError::SeekError(e.to_string()))?;
 
             // Flush decoder buffers
             self.decoder.flush();
+            self.seek_target = Some(timestamp);
 
             Ok(())
         }
@@ -401,8 +511,9 @@ impl VideoDecoder for TestPatternDecoder {
             ));
         }
 
-        self.current_time = timestamp;
+        // Land on the frame shown at the timestamp
         self.frame_count = (timestamp.as_secs_f64() * self.fps) as u64;
+        self.current_time = Duration::from_secs_f64(self.frame_count as f64 / self.fps);
 
         Ok(())
     }
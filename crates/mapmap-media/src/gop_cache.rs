@@ -0,0 +1,144 @@
+//! Cache of decoded frames for reverse playback
+//!
+//! Decoders only run forward. To play backward, the player seeks to the
+//! start of a window before the playhead, decodes the window forward into a
+//! [`GopCache`] and then walks back through the cached frames. When the
+//! playhead leaves the window, the previous window is decoded.
+
+use crate::DecodedFrame;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Decoded frames of one time window, ordered by timestamp
+pub struct GopCache {
+    frames: VecDeque<DecodedFrame>,
+    capacity: usize,
+    start: Duration,
+    end: Duration,
+}
+
+impl GopCache {
+    /// Create an empty cache holding at most `capacity` frames
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(2),
+            start: Duration::ZERO,
+            end: Duration::ZERO,
+        }
+    }
+
+    /// Discard all frames and start caching the window `start..=end`
+    pub fn begin(&mut self, start: Duration, end: Duration) {
+        self.frames.clear();
+        self.start = start;
+        self.end = end;
+    }
+
+    /// Discard all frames
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Add the next decoded frame of the window
+    ///
+    /// When the cache is full the earliest frame is evicted and the window
+    /// shrinks to start at the next one.
+    pub fn insert(&mut self, frame: DecodedFrame) {
+        if self.frames.len() >= self.capacity {
+            self.frames.pop_front();
+            if let Some(first) = self.frames.front() {
+                self.start = first.pts;
+            }
+        }
+        self.frames.push_back(frame);
+    }
+
+    /// Check if the frame shown at `time` is cached
+    pub fn covers(&self, time: Duration) -> bool {
+        !self.frames.is_empty() && self.start <= time && time <= self.end
+    }
+
+    /// Get the frame shown at `time`: the last frame starting at or before it
+    pub fn frame_at(&self, time: Duration) -> Option<&DecodedFrame> {
+        if !self.covers(time) {
+            return None;
+        }
+        self.frames
+            .iter()
+            .rev()
+            .find(|frame| frame.pts <= time)
+            .or_else(|| self.frames.front())
+    }
+
+    /// Get the cached window
+    pub fn range(&self) -> (Duration, Duration) {
+        (self.start, self.end)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the capacity, discarding the cached frames
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(2);
+        self.frames.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PixelFormat;
+
+    fn frame(ms: u64) -> DecodedFrame {
+        DecodedFrame {
+            data: vec![],
+            format: PixelFormat::RGBA8,
+            width: 0,
+            height: 0,
+            pts: Duration::from_millis(ms),
+        }
+    }
+
+    #[test]
+    fn test_frame_at() {
+        let mut cache = GopCache::new(10);
+        cache.begin(Duration::from_millis(100), Duration::from_millis(200));
+        assert!(!cache.covers(Duration::from_millis(150)));
+
+        for ms in [100, 140, 180] {
+            cache.insert(frame(ms));
+        }
+        let at = |ms| cache.frame_at(Duration::from_millis(ms)).map(|f| f.pts);
+        assert_eq!(at(100), Some(Duration::from_millis(100)));
+        assert_eq!(at(179), Some(Duration::from_millis(140)));
+        assert_eq!(at(200), Some(Duration::from_millis(180)));
+        assert_eq!(at(99), None);
+        assert_eq!(at(201), None);
+    }
+
+    #[test]
+    fn test_eviction_shrinks_window() {
+        let mut cache = GopCache::new(3);
+        cache.begin(Duration::ZERO, Duration::from_millis(300));
+        for ms in [0, 100, 200, 300] {
+            cache.insert(frame(ms));
+        }
+        assert_eq!(cache.len(), 3);
+        assert_eq!(
+            cache.range(),
+            (Duration::from_millis(100), Duration::from_millis(300))
+        );
+        assert!(!cache.covers(Duration::from_millis(50)));
+    }
+}
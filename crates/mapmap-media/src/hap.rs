@@ -0,0 +1,487 @@
+//! Hap video frames
+//!
+//! Hap, Hap Alpha and Hap Q store every frame as DXT/BC compressed texture
+//! blocks, usually with a Snappy pass on top. Decoding a frame only undoes
+//! the Snappy pass, and the blocks go to the GPU as they are. This keeps
+//! CPU load low enough to play many 4K layers at once.
+//!
+//! [`bc_to_rgba`] decodes blocks on the CPU for consumers without a GPU,
+//! such as previews.
+
+use crate::{DecodedFrame, MediaError, PixelFormat, Result};
+use std::time::Duration;
+
+// ============================================================================
+// Section parsing
+// ============================================================================
+
+/// Second-stage compressors (high nibble of the section type)
+const COMPRESSOR_NONE: u8 = 0xA;
+const COMPRESSOR_SNAPPY: u8 = 0xB;
+const COMPRESSOR_COMPLEX: u8 = 0xC;
+
+/// Texture formats (low nibble of the section type)
+const TEXTURE_DXT1: u8 = 0xB;
+const TEXTURE_DXT5: u8 = 0xE;
+const TEXTURE_YCOCG_DXT5: u8 = 0xF;
+const TEXTURE_BPTC: u8 = 0xC;
+
+/// Section types inside the decode instructions of a chunked frame
+const SECTION_MULTIPLE_IMAGES: u8 = 0x0D;
+const SECTION_DECODE_INSTRUCTIONS: u8 = 0x01;
+const SECTION_CHUNK_COMPRESSORS: u8 = 0x02;
+const SECTION_CHUNK_SIZES: u8 = 0x03;
+const SECTION_CHUNK_OFFSETS: u8 = 0x04;
+
+fn invalid(message: &str) -> MediaError {
+    MediaError::DecoderError(format!("Invalid Hap frame: {}", message))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Result<u32> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid("truncated table"))
+}
+
+/// Split the section at the start of `data` into its type, body and the rest
+fn parse_section(data: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+    if data.len() < 4 {
+        return Err(invalid("truncated section header"));
+    }
+    let mut size = u32::from_le_bytes([data[0], data[1], data[2], 0]) as usize;
+    let section_type = data[3];
+    let mut header: usize = 4;
+    // A zero size means the size follows in 4 bytes
+    if size == 0 {
+        size = read_u32(data, 4)? as usize;
+        header = 8;
+    }
+    let end = header
+        .checked_add(size)
+        .filter(|end| *end <= data.len())
+        .ok_or_else(|| invalid("section larger than the frame"))?;
+    Ok((section_type, &data[header..end], &data[end..]))
+}
+
+/// Decode one Hap packet into a frame of compressed texture blocks
+///
+/// `width` and `height` are the video size; the blocks cover it rounded up
+/// to multiples of 4.
+pub fn decode_hap_frame(
+    packet: &[u8],
+    width: u32,
+    height: u32,
+    pts: Duration,
+) -> Result<DecodedFrame> {
+    let (section_type, body, _) = parse_section(packet)?;
+    if section_type == SECTION_MULTIPLE_IMAGES {
+        return Err(MediaError::UnsupportedFormat(
+            "Hap Q Alpha is not supported".to_string(),
+        ));
+    }
+
+    let format = match section_type & 0x0F {
+        TEXTURE_DXT1 => PixelFormat::BC1,
+        TEXTURE_DXT5 => PixelFormat::BC3,
+        TEXTURE_YCOCG_DXT5 => PixelFormat::BC3YCoCg,
+        TEXTURE_BPTC => {
+            return Err(MediaError::UnsupportedFormat(
+                "Hap R (BC7) is not supported".to_string(),
+            ))
+        }
+        other => {
+            return Err(MediaError::UnsupportedFormat(format!(
+                "Hap texture format {:#x}",
+                other
+            )))
+        }
+    };
+
+    let data = match section_type >> 4 {
+        COMPRESSOR_NONE => body.to_vec(),
+        COMPRESSOR_SNAPPY => snappy_decompress(body)?,
+        COMPRESSOR_COMPLEX => decode_chunks(body)?,
+        other => return Err(invalid(&format!("unknown compressor {:#x}", other))),
+    };
+
+    let expected = format.frame_size(width, height);
+    if data.len() < expected {
+        return Err(invalid(&format!(
+            "{} bytes of blocks for {}x{}, expected {}",
+            data.len(),
+            width,
+            height,
+            expected
+        )));
+    }
+
+    let mut data = data;
+    data.truncate(expected);
+    Ok(DecodedFrame {
+        data,
+        format,
+        width,
+        height,
+        pts,
+    })
+}
+
+/// Decode a chunked frame: decode instructions followed by the chunk data
+fn decode_chunks(body: &[u8]) -> Result<Vec<u8>> {
+    let (section_type, instructions, frame_data) = parse_section(body)?;
+    if section_type != SECTION_DECODE_INSTRUCTIONS {
+        return Err(invalid("missing decode instructions"));
+    }
+
+    let mut compressors: &[u8] = &[];
+    let mut sizes: &[u8] = &[];
+    let mut offsets: Option<&[u8]> = None;
+    let mut rest = instructions;
+    while !rest.is_empty() {
+        let (section_type, table, next) = parse_section(rest)?;
+        match section_type {
+            SECTION_CHUNK_COMPRESSORS => compressors = table,
+            SECTION_CHUNK_SIZES => sizes = table,
+            SECTION_CHUNK_OFFSETS => offsets = Some(table),
+            _ => {} // Unknown instructions are skipped
+        }
+        rest = next;
+    }
+    if sizes.len() != compressors.len() * 4 {
+        return Err(invalid("chunk tables do not match"));
+    }
+
+    let mut data = Vec::new();
+    let mut position = 0;
+    for (i, compressor) in compressors.iter().enumerate() {
+        let size = read_u32(sizes, i * 4)? as usize;
+        let start = match offsets {
+            Some(offsets) => read_u32(offsets, i * 4)? as usize,
+            None => position,
+        };
+        let chunk = start
+            .checked_add(size)
+            .and_then(|end| frame_data.get(start..end))
+            .ok_or_else(|| invalid("chunk outside the frame"))?;
+        match *compressor {
+            COMPRESSOR_NONE => data.extend_from_slice(chunk),
+            COMPRESSOR_SNAPPY => data.extend_from_slice(&snappy_decompress(chunk)?),
+            other => return Err(invalid(&format!("unknown chunk compressor {:#x}", other))),
+        }
+        position = start + size;
+    }
+    Ok(data)
+}
+
+// ============================================================================
+// Snappy
+// ============================================================================
+
+/// Decompress a raw (unframed) Snappy block
+fn snappy_decompress(src: &[u8]) -> Result<Vec<u8>> {
+    let truncated = || invalid("truncated Snappy data");
+
+    // Uncompressed length as a little-endian varint
+    let mut length = 0usize;
+    let mut pos = 0;
+    loop {
+        let byte = *src.get(pos).ok_or_else(truncated)?;
+        length |= ((byte & 0x7F) as usize) << (7 * pos);
+        pos += 1;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        if pos == 5 {
+            return Err(invalid("Snappy length overflows"));
+        }
+    }
+
+    // The length is untrusted, only reserve a sane amount up front
+    let mut out = Vec::with_capacity(length.min(1 << 26));
+    let read_le = |pos: usize, bytes: usize| -> Result<usize> {
+        let slice = src.get(pos..pos + bytes).ok_or_else(truncated)?;
+        Ok(slice
+            .iter()
+            .rev()
+            .fold(0usize, |value, byte| (value << 8) | *byte as usize))
+    };
+
+    while pos < src.len() {
+        let tag = src[pos];
+        pos += 1;
+        let (len, offset) = match tag & 0x03 {
+            // Literal, long lengths follow in 1-4 bytes
+            0 => {
+                let mut len = (tag >> 2) as usize;
+                if len >= 60 {
+                    let bytes = len - 59;
+                    len = read_le(pos, bytes)?;
+                    pos += bytes;
+                }
+                let literal = src.get(pos..pos + len + 1).ok_or_else(truncated)?;
+                out.extend_from_slice(literal);
+                pos += len + 1;
+                continue;
+            }
+            1 => {
+                let offset = ((tag as usize >> 5) << 8) | read_le(pos, 1)?;
+                pos += 1;
+                (4 + ((tag >> 2) & 0x07) as usize, offset)
+            }
+            2 => {
+                let offset = read_le(pos, 2)?;
+                pos += 2;
+                ((tag >> 2) as usize + 1, offset)
+            }
+            _ => {
+                let offset = read_le(pos, 4)?;
+                pos += 4;
+                ((tag >> 2) as usize + 1, offset)
+            }
+        };
+
+        if offset == 0 || offset > out.len() {
+            return Err(invalid("Snappy copy before the start"));
+        }
+        // Copies may overlap their own output
+        let start = out.len() - offset;
+        for i in 0..len {
+            out.push(out[start + i]);
+        }
+    }
+
+    if out.len() != length {
+        return Err(invalid("Snappy length mismatch"));
+    }
+    Ok(out)
+}
+
+// ============================================================================
+// CPU block decoding
+// ============================================================================
+
+fn rgb565(color: u16) -> [u8; 3] {
+    let r = ((color >> 11) & 0x1F) as u32;
+    let g = ((color >> 5) & 0x3F) as u32;
+    let b = (color & 0x1F) as u32;
+    [
+        ((r << 3) | (r >> 2)) as u8,
+        ((g << 2) | (g >> 4)) as u8,
+        ((b << 3) | (b >> 2)) as u8,
+    ]
+}
+
+/// Decode a DXT1 color block into 16 RGBA pixels
+///
+/// `opaque_only` forces the four color mode used inside DXT5 blocks.
+fn decode_color_block(block: &[u8], opaque_only: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    let (a, b) = (rgb565(c0), rgb565(c1));
+    let mix = |wa: u32, wb: u32, div: u32| -> [u8; 4] {
+        let channel = |i: usize| ((a[i] as u32 * wa + b[i] as u32 * wb) / div) as u8;
+        [channel(0), channel(1), channel(2), 255]
+    };
+
+    let palette = if c0 > c1 || opaque_only {
+        [
+            [a[0], a[1], a[2], 255],
+            [b[0], b[1], b[2], 255],
+            mix(2, 1, 3),
+            mix(1, 2, 3),
+        ]
+    } else {
+        [
+            [a[0], a[1], a[2], 255],
+            [b[0], b[1], b[2], 255],
+            mix(1, 1, 2),
+            [0, 0, 0, 0],
+        ]
+    };
+
+    let mut pixels = [[0u8; 4]; 16];
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = palette[((indices >> (2 * i)) & 0x03) as usize];
+    }
+    pixels
+}
+
+/// Decode a DXT5 alpha block into 16 alpha values
+fn decode_alpha_block(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+    let mut palette = [0u8; 8];
+    palette[0] = a0 as u8;
+    palette[1] = a1 as u8;
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = (((7 - i as u32) * a0 + i as u32 * a1) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (((5 - i as u32) * a0 + i as u32 * a1) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+
+    let bits = block[2..8]
+        .iter()
+        .rev()
+        .fold(0u64, |value, byte| (value << 8) | *byte as u64);
+    let mut alphas = [0u8; 16];
+    for (i, alpha) in alphas.iter_mut().enumerate() {
+        *alpha = palette[((bits >> (3 * i)) & 0x07) as usize];
+    }
+    alphas
+}
+
+/// Convert scaled YCoCg with luma in alpha (Hap Q) to RGBA
+fn ycocg_to_rgba(pixel: [u8; 4]) -> [u8; 4] {
+    let offset = 128.0 / 255.0;
+    let scale = pixel[2] as f32 / 8.0 + 1.0;
+    let co = (pixel[0] as f32 / 255.0 - offset) / scale;
+    let cg = (pixel[1] as f32 / 255.0 - offset) / scale;
+    let y = pixel[3] as f32 / 255.0;
+    let to_u8 = |v: f32| (v * 255.0).round().clamp(0.0, 255.0) as u8;
+    [to_u8(y + co - cg), to_u8(y + cg), to_u8(y - co - cg), 255]
+}
+
+/// Decode BC compressed blocks to tightly packed RGBA8 on the CPU
+///
+/// Much slower than sampling the blocks on the GPU; meant for previews and
+/// other CPU consumers. Other formats are returned unchanged.
+pub fn bc_to_rgba(data: &[u8], format: PixelFormat, width: u32, height: u32) -> Vec<u8> {
+    let Some(block_bytes) = format.block_bytes() else {
+        return data.to_vec();
+    };
+    let (width, height) = (width as usize, height as usize);
+    let blocks_wide = width.div_ceil(4);
+    let mut rgba = vec![0u8; width * height * 4];
+
+    for (index, block) in data.chunks_exact(block_bytes).enumerate() {
+        let (bx, by) = (index % blocks_wide * 4, index / blocks_wide * 4);
+        if by >= height {
+            break;
+        }
+        let pixels = match format {
+            PixelFormat::BC1 => decode_color_block(block, false),
+            _ => {
+                let mut pixels = decode_color_block(&block[8..], true);
+                for (pixel, alpha) in pixels.iter_mut().zip(decode_alpha_block(block)) {
+                    pixel[3] = alpha;
+                }
+                if format == PixelFormat::BC3YCoCg {
+                    for pixel in pixels.iter_mut() {
+                        *pixel = ycocg_to_rgba(*pixel);
+                    }
+                }
+                pixels
+            }
+        };
+
+        for (i, pixel) in pixels.iter().enumerate() {
+            let (x, y) = (bx + i % 4, by + i / 4);
+            if x < width && y < height {
+                let at = (y * width + x) * 4;
+                rgba[at..at + 4].copy_from_slice(pixel);
+            }
+        }
+    }
+    rgba
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A DXT1 block of one RGB565 color
+    fn solid_dxt1(color: u16) -> [u8; 8] {
+        let [lo, hi] = color.to_le_bytes();
+        [lo, hi, lo, hi, 0, 0, 0, 0]
+    }
+
+    fn section(section_type: u8, body: &[u8]) -> Vec<u8> {
+        let mut data = (body.len() as u32).to_le_bytes()[..3].to_vec();
+        data.push(section_type);
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[test]
+    fn test_snappy() {
+        // Literal "abcd", then an overlapping copy of 8 bytes at offset 4
+        let compressed = [12, 0x0C, b'a', b'b', b'c', b'd', 0x11, 4];
+        assert_eq!(snappy_decompress(&compressed).unwrap(), b"abcdabcdabcd");
+        assert!(snappy_decompress(&[12, 0x0C, b'a', b'b', b'c', b'd']).is_err());
+        assert!(snappy_decompress(&[4, 0x11, 4]).is_err());
+    }
+
+    #[test]
+    fn test_decode_hap_frame() {
+        // 8x4: two red blocks, stored uncompressed
+        let blocks = [solid_dxt1(0xF800), solid_dxt1(0xF800)].concat();
+        let packet = section(0xAB, &blocks);
+        let frame = decode_hap_frame(&packet, 8, 4, Duration::ZERO).unwrap();
+        assert_eq!(frame.format, PixelFormat::BC1);
+        assert_eq!(frame.data, blocks);
+        assert_eq!(&frame.to_rgba()[..8], &[255, 0, 0, 255, 255, 0, 0, 255]);
+
+        // Snappy literal of the same blocks
+        let mut snappy = vec![16, 15 << 2];
+        snappy.extend_from_slice(&blocks);
+        let packet = section(0xBB, &snappy);
+        let frame = decode_hap_frame(&packet, 8, 4, Duration::ZERO).unwrap();
+        assert_eq!(frame.data, blocks);
+
+        // Too few blocks for the size
+        assert!(decode_hap_frame(&section(0xAB, &blocks), 16, 4, Duration::ZERO).is_err());
+        assert!(matches!(
+            decode_hap_frame(&section(0x0D, &[0; 4]), 8, 4, Duration::ZERO),
+            Err(MediaError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_chunked_frame() {
+        let red = solid_dxt1(0xF800);
+        let blue = solid_dxt1(0x001F);
+        let mut snappy_blue = vec![8, 7 << 2];
+        snappy_blue.extend_from_slice(&blue);
+
+        let sizes = [8u32.to_le_bytes(), (snappy_blue.len() as u32).to_le_bytes()].concat();
+        let instructions = [
+            section(
+                SECTION_CHUNK_COMPRESSORS,
+                &[COMPRESSOR_NONE, COMPRESSOR_SNAPPY],
+            ),
+            section(SECTION_CHUNK_SIZES, &sizes),
+        ]
+        .concat();
+        let mut body = section(SECTION_DECODE_INSTRUCTIONS, &instructions);
+        body.extend_from_slice(&red);
+        body.extend_from_slice(&snappy_blue);
+
+        let frame = decode_hap_frame(&section(0xCB, &body), 8, 4, Duration::ZERO).unwrap();
+        assert_eq!(frame.data, [red, blue].concat());
+        let rgba = frame.to_rgba();
+        assert_eq!(&rgba[..4], &[255, 0, 0, 255]);
+        assert_eq!(&rgba[16..20], &[0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_bc3_ycocg() {
+        // Gray: luma 128 in alpha, chroma near 0.5 scaled down the most
+        let mut block = [128u8, 128, 0, 0, 0, 0, 0, 0].to_vec();
+        let chroma: u16 = (16 << 11) | (32 << 5) | 31;
+        block.extend_from_slice(&[chroma.to_le_bytes(), chroma.to_le_bytes()].concat());
+        block.extend_from_slice(&[0; 4]);
+
+        let rgba = bc_to_rgba(&block, PixelFormat::BC3YCoCg, 2, 2);
+        assert_eq!(rgba.len(), 16);
+        for pixel in rgba.chunks_exact(4) {
+            assert!(pixel[..3].iter().all(|c| (127..=129).contains(c)));
+            assert_eq!(pixel[3], 255);
+        }
+    }
+}
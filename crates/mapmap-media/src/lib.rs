@@ -2,32 +2,42 @@
 //!
 //! This crate provides video decoding capabilities via FFmpeg, including:
 //! - Video decoder abstraction
-//! - Playback control (seek, speed, loop)
-//!
-//! Multi-threaded decoding pipeline is planned for a future phase.
+//! - Playback control (frame-accurate seek, speed, loop, reverse, in/out points)
+//! - Audio track playback with the audio clock driving A/V sync
+//! - Multi-threaded decode-ahead pipeline
+//! - Beat-synced playback locked to a tempo
+//! - Hap frames passed through as GPU texture blocks
 
 use std::path::Path;
 use thiserror::Error;
 
+pub mod audio;
+pub mod beat_sync;
 pub mod decoder;
+pub mod gop_cache;
+pub mod hap;
 pub mod image_decoder;
+pub mod pipeline;
 pub mod player;
 pub mod sequence;
-// TODO: Enable pipeline with thread-local scaler approach
-// The pipeline module requires VideoDecoder to be Send, but FFmpeg's scaler (SwsContext) is not thread-safe.
-// Solution: Use thread-local scaler - create scaler once in decode thread, avoiding Send requirement.
-// This provides zero overhead and clean separation. See pipeline.rs for implementation details.
-// pub mod pipeline;
 
+#[cfg(feature = "audio")]
+pub use audio::cpal_output::CpalAudioOutput;
+#[cfg(feature = "ffmpeg")]
+pub use audio::FFmpegAudioDecoder;
+pub use audio::{AudioDecoder, AudioQueue, AudioTrack, DecodedAudio, TestToneDecoder};
+pub use beat_sync::{BeatSync, Quantize};
 pub use decoder::{
     DecodedFrame, FFmpegDecoder, HwAccelType, PixelFormat, TestPatternDecoder, VideoDecoder,
 };
+pub use gop_cache::GopCache;
+pub use hap::decode_hap_frame;
 pub use image_decoder::{GifDecoder, StillImageDecoder};
+pub use pipeline::{FramePipeline, FrameScheduler, PipelineConfig, PipelineStats, Priority};
 pub use player::{
     LoopMode, PlaybackCommand, PlaybackState, PlaybackStatus, PlayerError, VideoPlayer,
 };
 pub use sequence::ImageSequenceDecoder;
-// pub use pipeline::{FramePipeline, PipelineConfig, PipelineStats, Priority, FrameScheduler};
 
 /// Media errors
 #[derive(Error, Debug)]
@@ -38,14 +48,26 @@ pub enum MediaError {
     #[error("No video stream found")]
     NoVideoStream,
 
+    #[error("No audio stream found")]
+    NoAudioStream,
+
     #[error("Decoder error: {0}")]
     DecoderError(String),
 
     #[error("End of stream")]
     EndOfStream,
 
+    #[error("Frame not ready")]
+    FrameNotReady,
+
     #[error("Seek error: {0}")]
     SeekError(String),
+
+    #[error("Audio output error: {0}")]
+    AudioOutput(String),
+
+    #[error("Unsupported format: {0}")]
+    UnsupportedFormat(String),
 }
 
 /// Result type for media operations
@@ -58,33 +80,36 @@ pub type Result<T> = std::result::Result<T, MediaError>;
 /// - If path has a GIF extension, `GifDecoder` is used.
 /// - If path has a still image extension, `StillImageDecoder` is used.
 /// - Otherwise, it's assumed to be a video file and opened with `FFmpegDecoder`.
+///
+/// Image sequences and video files are decoded ahead on a separate thread
+/// (see [`FramePipeline`]); GIFs and still images are already decoded.
 pub fn open_path<P: AsRef<Path>>(path: P) -> Result<VideoPlayer> {
     let path = path.as_ref();
 
     // Check if it's an image sequence (directory)
-    if path.is_dir() {
-        let decoder = ImageSequenceDecoder::open(path, 30.0)?; // Default to 30 fps
-        return Ok(VideoPlayer::new(decoder));
-    }
-
-    // Check file extension for still images and GIFs
-    let ext = path
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_lowercase();
-
-    let decoder: Box<dyn VideoDecoder> = match ext.as_str() {
-        "gif" => Box::new(GifDecoder::open(path)?),
-        "png" | "jpg" | "jpeg" | "tif" | "tiff" | "bmp" | "webp" => {
-            Box::new(StillImageDecoder::open(path)?)
-        }
-        _ => {
-            // Default to FFmpeg for video files
-            let ffmpeg_decoder = FFmpegDecoder::open(path)?;
-            Box::new(ffmpeg_decoder)
+    let decoder: Box<dyn VideoDecoder> = if path.is_dir() {
+        Box::new(ImageSequenceDecoder::open(path, 30.0)?) // Default to 30 fps
+    } else {
+        // Check file extension for still images and GIFs
+        let ext = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        match ext.as_str() {
+            "gif" => return Ok(VideoPlayer::new(GifDecoder::open(path)?)),
+            "png" | "jpg" | "jpeg" | "tif" | "tiff" | "bmp" | "webp" => {
+                return Ok(VideoPlayer::new(StillImageDecoder::open(path)?));
+            }
+            _ => {
+                // Default to FFmpeg for video files
+                let ffmpeg_decoder = FFmpegDecoder::open(path)?;
+                Box::new(ffmpeg_decoder)
+            }
         }
     };
 
-    Ok(VideoPlayer::new_with_box(decoder))
+    let pipeline = FramePipeline::with_config(decoder, PipelineConfig::default())?;
+    Ok(VideoPlayer::with_pipeline(pipeline))
 }
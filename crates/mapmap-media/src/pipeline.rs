@@ -1,46 +1,50 @@
-//! Multi-threaded video decode and upload pipeline
+//! Multi-threaded decode-ahead pipeline
 //!
-//! This module implements a lock-free pipeline for video decoding and texture upload:
-//! - Decode thread: Reads video frames from disk and decodes them
-//! - Upload thread: Uploads decoded frames to GPU textures
-//! - Render thread: Renders the uploaded textures (runs in main thread)
+//! A [`FramePipeline`] moves a decoder to its own thread, which decodes
+//! frames ahead into a bounded queue while the render thread shows the
+//! current one. The pipeline implements [`VideoDecoder`] itself, so a
+//! [`VideoPlayer`](crate::VideoPlayer) uses it like any other decoder:
+//! `next_frame` takes a queued frame and never waits for the decoder, and
+//! `seek` hands the new position to the decode thread.
 //!
-//! ## Thread-Local Scaler Implementation Plan
+//! ## Thread-Local Scaler
 //!
-//! Currently disabled due to FFmpeg's SwsContext not being thread-safe.
-//! To re-enable multi-threading:
-//!
-//! 1. Modify FFmpegDecoder to create scaler in the thread where decoding happens
-//! 2. Remove scaler from struct, create as local variable in next_frame()
-//! 3. Or: Use thread_local! macro to cache scaler per thread
-//! 4. This makes VideoDecoder Send-safe without performance overhead
-//!
-//! Benefits:
-//! - Zero overhead compared to single-threaded
-//! - Clean separation of concerns
-//! - Pre-buffering prevents frame stutters
-//! - Overlapped decode + GPU upload
-
-use crate::{DecodedFrame, MediaError, Result, VideoDecoder, VideoPlayer};
-use crossbeam_channel::{bounded, Receiver, Sender};
-use std::sync::atomic::{AtomicBool, Ordering};
+//! FFmpeg's SwsContext is not thread-safe. The FFmpeg decoder keeps its
+//! scalers in a thread-local cache instead of in the decoder, so the decoder
+//! itself is `Send` and the scaler is created once on the thread that
+//! decodes.
+
+use crate::{DecodedFrame, MediaError, Result, VideoDecoder};
+use parking_lot::{Condvar, Mutex};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
-use tracing::{debug, error, info, warn};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// How long the decode thread sleeps before checking for a shutdown
+const IDLE_WAIT: Duration = Duration::from_millis(100);
+
+/// Smoothing factor for the average decode time
+const DECODE_TIME_SMOOTHING: f64 = 0.1;
 
 /// Frame pipeline statistics
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct PipelineStats {
+    /// Frames decoded by the decode thread
     pub decoded_frames: u64,
-    pub uploaded_frames: u64,
+    /// Frames taken from the queue for rendering
     pub rendered_frames: u64,
+    /// Frames evicted from a full queue or skipped as late
     pub dropped_frames: u64,
+    /// Frames waiting in the queue
+    pub queued_frames: usize,
+    /// Capacity of the queue
+    pub queue_depth: usize,
+    /// Average time to decode one frame
     pub decode_time_ms: f64,
-    pub upload_time_ms: f64,
 }
 
-/// Priority level for pipeline stages
+/// Priority of a queued frame
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Priority {
     Low = 0,
@@ -52,23 +56,21 @@ pub enum Priority {
 /// Pipeline configuration
 #[derive(Debug, Clone)]
 pub struct PipelineConfig {
-    /// Queue depth (number of frames to buffer)
+    /// Queue depth (number of frames to decode ahead)
     pub queue_depth: usize,
-    /// Enable frame dropping when queues are full
+    /// Keep decoding when the queue is full, evicting the oldest frame
+    /// instead of waiting for the render thread
     pub enable_frame_drop: bool,
-    /// Priority for decode thread
+    /// Priority of frames decoded ahead
     pub decode_priority: Priority,
-    /// Priority for upload thread
-    pub upload_priority: Priority,
 }
 
 impl Default for PipelineConfig {
     fn default() -> Self {
         Self {
             queue_depth: 3, // Triple buffering
-            enable_frame_drop: true,
+            enable_frame_drop: false,
             decode_priority: Priority::Normal,
-            upload_priority: Priority::High,
         }
     }
 }
@@ -81,188 +83,187 @@ pub struct PipelineFrame {
     pub priority: Priority,
 }
 
-/// Multi-threaded frame pipeline
-pub struct FramePipeline {
-    // Decode → Upload channel
-    decode_tx: Sender<PipelineFrame>,
-    decode_rx: Receiver<PipelineFrame>,
+/// Queue and decoder state shared with the decode thread
+struct PipelineState {
+    scheduler: FrameScheduler,
+    /// Incremented by every seek; frames decoded before it are discarded
+    generation: u64,
+    seek_to: Option<Duration>,
+    ended: bool,
+    error: Option<MediaError>,
+    running: bool,
+    stats: PipelineStats,
+}
 
-    // Upload → Render channel
-    upload_tx: Sender<PipelineFrame>,
-    pub upload_rx: Receiver<PipelineFrame>,
+struct Shared {
+    state: Mutex<PipelineState>,
+    /// Signals the decode thread that a frame was taken or a seek requested
+    wake: Condvar,
+}
 
-    // Control
-    running: Arc<AtomicBool>,
-    stats: Arc<parking_lot::RwLock<PipelineStats>>,
+/// Read-only view of the statistics of a running pipeline
+#[derive(Clone)]
+pub(crate) struct PipelineMonitor(Arc<Shared>);
 
-    // Threads
-    decode_thread: Option<JoinHandle<()>>,
+impl PipelineMonitor {
+    pub(crate) fn stats(&self) -> PipelineStats {
+        self.0.state.lock().stats
+    }
+}
+
+/// Decode-ahead pipeline running a decoder on its own thread
+pub struct FramePipeline {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+    duration: Duration,
+    resolution: (u32, u32),
+    fps: f64,
     config: PipelineConfig,
 }
 
 impl FramePipeline {
-    /// Create a new frame pipeline with default configuration
-    pub fn new() -> Self {
-        Self::with_config(PipelineConfig::default())
+    /// Start decoding ahead with the default configuration
+    pub fn new(decoder: impl VideoDecoder + 'static) -> Result<Self> {
+        Self::with_config(Box::new(decoder), PipelineConfig::default())
     }
 
-    /// Create a new frame pipeline with custom configuration
-    pub fn with_config(config: PipelineConfig) -> Self {
-        let (decode_tx, decode_rx) = bounded(config.queue_depth);
-        let (upload_tx, upload_rx) = bounded(config.queue_depth);
-
-        Self {
-            decode_tx,
-            decode_rx,
-            upload_tx,
-            upload_rx,
-            running: Arc::new(AtomicBool::new(false)),
-            stats: Arc::new(parking_lot::RwLock::new(PipelineStats::default())),
-            decode_thread: None,
+    /// Start decoding ahead with a custom configuration
+    pub fn with_config(decoder: Box<dyn VideoDecoder>, config: PipelineConfig) -> Result<Self> {
+        let queue_depth = config.queue_depth.max(1);
+        let shared = Arc::new(Shared {
+            state: Mutex::new(PipelineState {
+                scheduler: FrameScheduler::new(queue_depth),
+                generation: 0,
+                seek_to: None,
+                ended: false,
+                error: None,
+                running: true,
+                stats: PipelineStats {
+                    queue_depth,
+                    ..Default::default()
+                },
+            }),
+            wake: Condvar::new(),
+        });
+
+        let duration = decoder.duration();
+        let resolution = decoder.resolution();
+        let fps = decoder.fps();
+
+        let thread_shared = shared.clone();
+        let thread_config = config.clone();
+        let thread = thread::Builder::new()
+            .name("decode-ahead".to_string())
+            .spawn(move || decode_loop(decoder, &thread_shared, &thread_config))
+            .map_err(|e| MediaError::DecoderError(format!("Failed to spawn decoder: {}", e)))?;
+
+        info!(
+            "Decode-ahead pipeline started: {}x{} @ {:.2} fps, queue depth {}",
+            resolution.0, resolution.1, fps, queue_depth
+        );
+
+        Ok(Self {
+            shared,
+            thread: Some(thread),
+            duration,
+            resolution,
+            fps,
             config,
-        }
+        })
     }
 
-    /// Start the decode thread
-    pub fn start_decode_thread<D: VideoDecoder + 'static>(
-        &mut self,
-        mut decoder: D,
-        mut player: VideoPlayer,
-    ) {
-        if self.running.load(Ordering::Relaxed) {
-            warn!("Decode thread already running");
-            return;
-        }
-
-        self.running.store(true, Ordering::Relaxed);
-
-        let decode_tx = self.decode_tx.clone();
-        let running = self.running.clone();
-        let stats = self.stats.clone();
-        let enable_drop = self.config.enable_frame_drop;
+    pub fn config(&self) -> &PipelineConfig {
+        &self.config
+    }
 
-        let thread = thread::Builder::new()
-            .name("decode-thread".to_string())
-            .spawn(move || {
-                info!("Decode thread started");
-                let mut sequence = 0u64;
-
-                while running.load(Ordering::Relaxed) {
-                    let start = std::time::Instant::now();
-
-                    // Update player and get next frame
-                    if let Some(frame) = player.update(Duration::from_secs_f64(1.0 / decoder.fps()))
-                    {
-                        let pipeline_frame = PipelineFrame {
-                            frame,
-                            sequence,
-                            priority: Priority::Normal,
-                        };
-
-                        // Try to send frame to upload thread
-                        let result = if enable_drop {
-                            decode_tx.try_send(pipeline_frame)
-                        } else {
-                            decode_tx.send(pipeline_frame).map_err(|e| e.into())
-                        };
-
-                        match result {
-                            Ok(_) => {
-                                sequence += 1;
-                                let mut stats = stats.write();
-                                stats.decoded_frames += 1;
-                                stats.decode_time_ms = start.elapsed().as_secs_f64() * 1000.0;
-                            }
-                            Err(_) => {
-                                if enable_drop {
-                                    stats.write().dropped_frames += 1;
-                                    debug!("Dropped frame {} (queue full)", sequence);
-                                }
-                            }
-                        }
-                    }
-
-                    // Throttle to approximately match video FPS
-                    let elapsed = start.elapsed();
-                    let frame_duration = Duration::from_secs_f64(1.0 / decoder.fps());
-                    if elapsed < frame_duration {
-                        std::thread::sleep(frame_duration - elapsed);
-                    }
-                }
+    /// Get pipeline statistics
+    pub fn stats(&self) -> PipelineStats {
+        self.shared.state.lock().stats
+    }
 
-                info!("Decode thread stopped");
-            })
-            .expect("Failed to spawn decode thread");
-
-        self.decode_thread = Some(thread);
-    }
-
-    /// Start the upload thread (requires render backend)
-    /// Note: In Phase 1, this is a placeholder. Full implementation requires wgpu integration.
-    pub fn start_upload_thread(&mut self) {
-        let decode_rx = self.decode_rx.clone();
-        let upload_tx = self.upload_tx.clone();
-        let running = self.running.clone();
-        let stats = self.stats.clone();
-
-        thread::Builder::new()
-            .name("upload-thread".to_string())
-            .spawn(move || {
-                info!("Upload thread started");
-
-                while running.load(Ordering::Relaxed) {
-                    match decode_rx.recv_timeout(Duration::from_millis(100)) {
-                        Ok(pipeline_frame) => {
-                            let start = std::time::Instant::now();
-
-                            // TODO: Upload to GPU here (Phase 1 placeholder)
-                            // In real implementation:
-                            // 1. Get staging buffer from pool
-                            // 2. Copy frame data to staging buffer
-                            // 3. Record copy command
-                            // 4. Submit to GPU queue
-                            // 5. Send texture handle to render thread
-
-                            // For now, just forward the frame
-                            if upload_tx.send(pipeline_frame).is_ok() {
-                                let mut stats = stats.write();
-                                stats.uploaded_frames += 1;
-                                stats.upload_time_ms = start.elapsed().as_secs_f64() * 1000.0;
-                            }
-                        }
-                        Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
-                            // No frame available, continue
-                        }
-                        Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
-                            info!("Decode channel disconnected");
-                            break;
-                        }
-                    }
-                }
+    /// Reset statistics
+    pub fn reset_stats(&self) {
+        let mut state = self.shared.state.lock();
+        state.stats = PipelineStats {
+            queued_frames: state.scheduler.len(),
+            queue_depth: state.stats.queue_depth,
+            ..Default::default()
+        };
+    }
 
-                info!("Upload thread stopped");
-            })
-            .expect("Failed to spawn upload thread");
+    pub(crate) fn monitor(&self) -> PipelineMonitor {
+        PipelineMonitor(self.shared.clone())
     }
 
-    /// Stop the pipeline
+    /// Stop the decode thread
     pub fn stop(&mut self) {
-        info!("Stopping pipeline");
-        self.running.store(false, Ordering::Relaxed);
+        self.shared.state.lock().running = false;
+        self.shared.wake.notify_all();
 
-        if let Some(thread) = self.decode_thread.take() {
-            thread.join().expect("Failed to join decode thread");
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("Decode thread panicked");
+            }
         }
     }
+}
 
-    /// Get pipeline statistics
-    pub fn stats(&self) -> PipelineStats {
-        *self.stats.read()
+impl VideoDecoder for FramePipeline {
+    /// Take the next decoded frame
+    ///
+    /// Returns `MediaError::FrameNotReady` instead of waiting when the
+    /// decode thread has not caught up yet.
+    fn next_frame(&mut self) -> Result<DecodedFrame> {
+        let mut state = self.shared.state.lock();
+        match state.scheduler.pop() {
+            Some(pipeline_frame) => {
+                state.stats.rendered_frames += 1;
+                state.stats.queued_frames = state.scheduler.len();
+                drop(state);
+                self.shared.wake.notify_one();
+                Ok(pipeline_frame.frame)
+            }
+            None => match state.error.take() {
+                Some(e) => Err(e),
+                None if state.ended || self.thread.is_none() => Err(MediaError::EndOfStream),
+                None => Err(MediaError::FrameNotReady),
+            },
+        }
     }
 
-    /// Reset statistics
-    pub fn reset_stats(&self) {
-        *self.stats.write() = PipelineStats::default();
+    /// Discard the queued frames and continue decoding at `timestamp`
+    ///
+    /// The decode thread performs the seek; errors are returned by the
+    /// following `next_frame` calls.
+    fn seek(&mut self, timestamp: Duration) -> Result<()> {
+        let mut state = self.shared.state.lock();
+        state.scheduler.clear();
+        state.generation += 1;
+        state.seek_to = Some(timestamp);
+        state.ended = false;
+        state.error = None;
+        state.stats.queued_frames = 0;
+        drop(state);
+        self.shared.wake.notify_one();
+        Ok(())
+    }
+
+    fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    fn resolution(&self) -> (u32, u32) {
+        self.resolution
+    }
+
+    fn fps(&self) -> f64 {
+        self.fps
+    }
+
+    fn clone_decoder(&self) -> Result<Box<dyn VideoDecoder>> {
+        Err(MediaError::DecoderError(
+            "Decode-ahead pipelines cannot be cloned".to_string(),
+        ))
     }
 }
 
@@ -272,7 +273,84 @@ impl Drop for FramePipeline {
     }
 }
 
-/// Frame scheduler for prioritizing frames
+/// Body of the decode thread
+///
+/// The state lock is never held while decoding, so the render thread only
+/// waits for queue operations.
+fn decode_loop(mut decoder: Box<dyn VideoDecoder>, shared: &Shared, config: &PipelineConfig) {
+    debug!("Decode thread started");
+    let mut sequence = 0u64;
+    // The first frame after a seek is the one the player waits for
+    let mut priority = Priority::Critical;
+
+    let mut state = shared.state.lock();
+    while state.running {
+        if let Some(timestamp) = state.seek_to.take() {
+            let generation = state.generation;
+            drop(state);
+            let result = decoder.seek(timestamp);
+            state = shared.state.lock();
+            if let Err(e) = result {
+                if state.generation == generation {
+                    state.error = Some(e);
+                }
+            }
+            priority = Priority::Critical;
+            continue;
+        }
+
+        let full = state.scheduler.len() >= state.stats.queue_depth;
+        if state.ended || state.error.is_some() || (full && !config.enable_frame_drop) {
+            shared.wake.wait_for(&mut state, IDLE_WAIT);
+            continue;
+        }
+
+        let generation = state.generation;
+        drop(state);
+        let start = Instant::now();
+        let result = decoder.next_frame();
+        let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+        state = shared.state.lock();
+
+        // A seek arrived while decoding, this frame is from the old position
+        if state.generation != generation {
+            continue;
+        }
+
+        match result {
+            Ok(frame) => {
+                if state.scheduler.len() >= state.stats.queue_depth {
+                    state.stats.dropped_frames += 1;
+                }
+                state.scheduler.push(PipelineFrame {
+                    frame,
+                    sequence,
+                    priority,
+                });
+                sequence += 1;
+                priority = config.decode_priority;
+
+                let stats = &mut state.stats;
+                stats.decoded_frames += 1;
+                stats.decode_time_ms = if stats.decoded_frames == 1 {
+                    elapsed_ms
+                } else {
+                    stats.decode_time_ms
+                        + (elapsed_ms - stats.decode_time_ms) * DECODE_TIME_SMOOTHING
+                };
+                state.stats.queued_frames = state.scheduler.len();
+            }
+            Err(MediaError::EndOfStream) => state.ended = true,
+            Err(e) => {
+                warn!("Decode-ahead error: {}", e);
+                state.error = Some(e);
+            }
+        }
+    }
+    debug!("Decode thread stopped");
+}
+
+/// Bounded frame queue ordered by priority, then by decode order
 pub struct FrameScheduler {
     frames: Vec<PipelineFrame>,
     max_frames: usize,
@@ -286,7 +364,8 @@ impl FrameScheduler {
         }
     }
 
-    /// Add a frame to the scheduler
+    /// Add a frame to the scheduler, evicting the oldest lowest priority
+    /// frame when full
     pub fn push(&mut self, frame: PipelineFrame) {
         if self.frames.len() >= self.max_frames {
             // Remove lowest priority frame
@@ -314,6 +393,11 @@ impl FrameScheduler {
         }
     }
 
+    /// Remove all queued frames
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
     /// Get number of queued frames
     pub fn len(&self) -> usize {
         self.frames.len()
@@ -328,21 +412,19 @@ impl FrameScheduler {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::decoder::{TestPatternDecoder, VideoDecoder};
+    use crate::decoder::TestPatternDecoder;
     use crate::player::VideoPlayer;
 
-    #[test]
-    fn test_pipeline_config_default() {
-        let config = PipelineConfig::default();
-        assert_eq!(config.queue_depth, 3);
-        assert!(config.enable_frame_drop);
+    fn wait_for_frames(pipeline: &FramePipeline, count: usize) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while pipeline.stats().queued_frames < count {
+            assert!(Instant::now() < deadline, "Decode thread did not catch up");
+            thread::sleep(Duration::from_millis(1));
+        }
     }
 
-    #[test]
-    fn test_frame_scheduler() {
-        let mut scheduler = FrameScheduler::new(3);
-
-        let frame1 = PipelineFrame {
+    fn pipeline_frame(sequence: u64, priority: Priority) -> PipelineFrame {
+        PipelineFrame {
             frame: DecodedFrame {
                 data: vec![],
                 format: crate::decoder::PixelFormat::RGBA8,
@@ -350,24 +432,24 @@ mod tests {
                 height: 100,
                 pts: Duration::ZERO,
             },
-            sequence: 1,
-            priority: Priority::Low,
-        };
+            sequence,
+            priority,
+        }
+    }
 
-        let frame2 = PipelineFrame {
-            frame: DecodedFrame {
-                data: vec![],
-                format: crate::decoder::PixelFormat::RGBA8,
-                width: 100,
-                height: 100,
-                pts: Duration::ZERO,
-            },
-            sequence: 2,
-            priority: Priority::High,
-        };
+    #[test]
+    fn test_pipeline_config_default() {
+        let config = PipelineConfig::default();
+        assert_eq!(config.queue_depth, 3);
+        assert!(!config.enable_frame_drop);
+    }
+
+    #[test]
+    fn test_frame_scheduler() {
+        let mut scheduler = FrameScheduler::new(3);
 
-        scheduler.push(frame1);
-        scheduler.push(frame2);
+        scheduler.push(pipeline_frame(1, Priority::Low));
+        scheduler.push(pipeline_frame(2, Priority::High));
 
         assert_eq!(scheduler.len(), 2);
 
@@ -377,36 +459,106 @@ mod tests {
     }
 
     #[test]
-    fn test_pipeline_creation() {
-        let pipeline = FramePipeline::new();
+    fn test_frame_scheduler_evicts_oldest_lowest_priority() {
+        let mut scheduler = FrameScheduler::new(3);
+        scheduler.push(pipeline_frame(1, Priority::Critical));
+        scheduler.push(pipeline_frame(2, Priority::Normal));
+        scheduler.push(pipeline_frame(3, Priority::Normal));
+        scheduler.push(pipeline_frame(4, Priority::Normal));
+
+        let order: Vec<u64> = std::iter::from_fn(|| scheduler.pop())
+            .map(|f| f.sequence)
+            .collect();
+        assert_eq!(order, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_pipeline_decodes_ahead() {
+        let decoder = TestPatternDecoder::new(64, 36, Duration::from_secs(1), 30.0);
+        let mut pipeline = FramePipeline::new(decoder).unwrap();
+        assert_eq!(pipeline.resolution(), (64, 36));
+
+        // The queue fills up to its depth and the thread waits
+        wait_for_frames(&pipeline, 3);
+        thread::sleep(Duration::from_millis(20));
         let stats = pipeline.stats();
+        assert_eq!(stats.decoded_frames, 3);
+        assert_eq!(stats.queued_frames, 3);
+        assert_eq!(stats.dropped_frames, 0);
+
+        // Frames come out in decode order
+        let first = pipeline.next_frame().unwrap();
+        let second = pipeline.next_frame().unwrap();
+        assert_eq!(first.pts, Duration::ZERO);
+        assert!(second.pts > first.pts);
+        assert_eq!(pipeline.stats().rendered_frames, 2);
+    }
 
-        assert_eq!(stats.decoded_frames, 0);
-        assert_eq!(stats.uploaded_frames, 0);
-        assert_eq!(stats.rendered_frames, 0);
+    #[test]
+    fn test_pipeline_seek_discards_queue() {
+        let decoder = TestPatternDecoder::new(64, 36, Duration::from_secs(10), 30.0);
+        let mut pipeline = FramePipeline::new(decoder).unwrap();
+        wait_for_frames(&pipeline, 3);
+
+        pipeline.seek(Duration::from_secs(5)).unwrap();
+        let frame = loop {
+            match pipeline.next_frame() {
+                Ok(frame) => break frame,
+                Err(MediaError::FrameNotReady) => thread::sleep(Duration::from_millis(1)),
+                Err(e) => panic!("Unexpected error: {}", e),
+            }
+        };
+        assert_eq!(frame.pts, Duration::from_secs(5));
     }
 
     #[test]
-    fn test_pipeline_with_test_pattern() {
-        let mut pipeline = FramePipeline::new();
-        let decoder = TestPatternDecoder::new(640, 480, Duration::from_secs(1), 30.0);
-        let mut player = VideoPlayer::new(decoder);
-        player.set_looping(true);
-        player.play();
-
-        // Start decode thread
-        let test_decoder = TestPatternDecoder::new(640, 480, Duration::from_secs(1), 30.0);
-        pipeline.start_decode_thread(test_decoder, player);
-        pipeline.start_upload_thread();
-
-        // Let it run for a bit
-        std::thread::sleep(Duration::from_millis(200));
-
-        // Check stats
-        let stats = pipeline.stats();
-        assert!(stats.decoded_frames > 0, "Should have decoded frames");
+    fn test_pipeline_end_of_stream() {
+        // Two frames at 30 fps
+        let decoder = TestPatternDecoder::new(16, 16, Duration::from_millis(50), 30.0);
+        let mut pipeline = FramePipeline::new(decoder).unwrap();
+        wait_for_frames(&pipeline, 2);
+
+        assert!(pipeline.next_frame().is_ok());
+        assert!(pipeline.next_frame().is_ok());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            match pipeline.next_frame() {
+                Err(MediaError::EndOfStream) => break,
+                Err(MediaError::FrameNotReady) => {
+                    assert!(Instant::now() < deadline);
+                    thread::sleep(Duration::from_millis(1));
+                }
+                other => panic!("Unexpected result: {:?}", other.map(|f| f.pts)),
+            }
+        }
 
-        // Stop pipeline
         pipeline.stop();
+        assert!(matches!(
+            pipeline.next_frame(),
+            Err(MediaError::EndOfStream)
+        ));
+    }
+
+    #[test]
+    fn test_player_with_decode_ahead() {
+        let decoder = TestPatternDecoder::new(64, 36, Duration::from_secs(10), 30.0);
+        let mut player =
+            VideoPlayer::with_decode_ahead(decoder, PipelineConfig::default()).unwrap();
+        player.play().unwrap();
+
+        // The first update may come before the decoder, it must not wait
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let frame = loop {
+            if let Some(frame) = player.update(Duration::ZERO) {
+                break frame;
+            }
+            assert!(Instant::now() < deadline);
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(frame.pts, Duration::ZERO);
+
+        let stats = player.pipeline_stats().unwrap();
+        assert_eq!(stats.rendered_frames, 1);
+        assert_eq!(stats.queue_depth, 3);
     }
 }
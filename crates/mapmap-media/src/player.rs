@@ -3,7 +3,13 @@
 //! This module implements a fault-tolerant state machine for video/audio playback.
 //! It replaces legacy implementations with a clean, command-driven architecture.
 
-use crate::{DecodedFrame, VideoDecoder};
+use crate::beat_sync::BeatSync;
+use crate::gop_cache::GopCache;
+use crate::pipeline::PipelineMonitor;
+use crate::{
+    AudioTrack, DecodedFrame, FramePipeline, MediaError, PipelineConfig, PipelineStats,
+    VideoDecoder,
+};
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use std::time::Duration;
 use thiserror::Error;
@@ -20,8 +26,18 @@ pub enum PlayerError {
     InvalidStateTransition { from: String, to: String },
     #[error("Invalid command for current state {state:?}: {command:?}")]
     InvalidCommand { state: String, command: String },
+    #[error("Invalid in/out range: {0}")]
+    InvalidRange(String),
+    #[error("No cue point {0}")]
+    InvalidCue(usize),
 }
 
+/// Most frames decoded in one update to catch up with the clock
+const MAX_CATCH_UP_FRAMES: usize = 8;
+
+/// Default number of frames decoded per window for reverse playback
+const DEFAULT_GOP_CACHE_FRAMES: usize = 30;
+
 /// Playback state
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PlaybackState {
@@ -39,6 +55,22 @@ pub enum LoopMode {
     #[default]
     Loop, // Repeat indefinitely
     PlayOnce, // Stop at end
+    PingPong, // Alternate forward and backward
+    Reverse,  // Play backward, repeating
+}
+
+impl LoopMode {
+    /// Parse the names used by control APIs: "none", "loop", "ping-pong"
+    /// and "reverse"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(LoopMode::PlayOnce),
+            "loop" => Some(LoopMode::Loop),
+            "ping-pong" => Some(LoopMode::PingPong),
+            "reverse" => Some(LoopMode::Reverse),
+            _ => None,
+        }
+    }
 }
 
 /// Commands to control video playback
@@ -50,6 +82,12 @@ pub enum PlaybackCommand {
     Seek(Duration),
     SetSpeed(f32),
     SetLoopMode(LoopMode),
+    SetInPoint(Option<Duration>),
+    SetOutPoint(Option<Duration>),
+    SetBeatSync(Option<BeatSync>),
+    SetTempo(f32),
+    Beat(u64),
+    QueueCue(usize),
 }
 
 /// Status notifications from the player
@@ -59,16 +97,52 @@ pub enum PlaybackStatus {
     Error(PlayerError),
     ReachedEnd,
     Looped,
+    CueTriggered(usize),
 }
 
 /// Video player with robust state machine
+///
+/// Frames are presented by timestamp: each update shows the frame covering
+/// the playback clock, dropping frames that are already late and repeating
+/// the last one until the next is due. With an [`AudioTrack`] attached and
+/// played by an output, the audio position is the clock; otherwise the
+/// clock advances by the update delta.
+///
+/// Created with [`VideoPlayer::with_decode_ahead`], the decoder runs on its
+/// own thread and `update` only takes frames that are already decoded.
+///
+/// Playback loops between the in and out points. Backward playback (the
+/// `Reverse` and `PingPong` loop modes) decodes windows of frames forward
+/// into a [`GopCache`] and shows them in reverse.
+///
+/// With a [`BeatSync`] set, the speed follows the tempo so the in/out range
+/// spans a fixed number of beats. Beats passed to [`VideoPlayer::on_beat`]
+/// trigger queued cue jumps and pull playback back in phase on downbeats.
 pub struct VideoPlayer {
     decoder: Box<dyn VideoDecoder>,
     state: PlaybackState,
     current_time: Duration,
     playback_speed: f32,
     loop_mode: LoopMode,
+    /// Playing backward (`Reverse`, or the second half of `PingPong`)
+    reverse: bool,
+    in_point: Option<Duration>,
+    out_point: Option<Duration>,
+    gop: GopCache,
+    /// End of the reverse window still being decoded into `gop`
+    gop_fill: Option<Duration>,
+    beat_sync: Option<BeatSync>,
+    tempo_bpm: Option<f32>,
+    /// Beat and offset into the range that playback was last aligned to
+    beat_anchor: Option<(u64, Duration)>,
+    pending_cue: Option<usize>,
     last_frame: Option<DecodedFrame>,
+    /// End of the display interval of `last_frame`, `None` after a seek
+    frame_end: Option<Duration>,
+    audio: Option<AudioTrack>,
+    pipeline: Option<PipelineMonitor>,
+    frames_dropped: u64,
+    frames_repeated: u64,
 
     // Command and Status channels
     command_sender: Sender<PlaybackCommand>,
@@ -94,7 +168,21 @@ impl VideoPlayer {
             current_time: Duration::ZERO,
             playback_speed: 1.0,
             loop_mode: LoopMode::default(),
+            reverse: false,
+            in_point: None,
+            out_point: None,
+            gop: GopCache::new(DEFAULT_GOP_CACHE_FRAMES),
+            gop_fill: None,
+            beat_sync: None,
+            tempo_bpm: None,
+            beat_anchor: None,
+            pending_cue: None,
             last_frame: None,
+            frame_end: None,
+            audio: None,
+            pipeline: None,
+            frames_dropped: 0,
+            frames_repeated: 0,
             command_sender,
             command_receiver,
             status_sender,
@@ -102,6 +190,23 @@ impl VideoPlayer {
         }
     }
 
+    /// Create a video player that decodes ahead on a separate thread
+    pub fn with_decode_ahead(
+        decoder: impl VideoDecoder + 'static,
+        config: PipelineConfig,
+    ) -> crate::Result<Self> {
+        let pipeline = FramePipeline::with_config(Box::new(decoder), config)?;
+        Ok(Self::with_pipeline(pipeline))
+    }
+
+    /// Create a video player taking its frames from a running pipeline
+    pub fn with_pipeline(pipeline: FramePipeline) -> Self {
+        let monitor = pipeline.monitor();
+        let mut player = Self::new(pipeline);
+        player.pipeline = Some(monitor);
+        player
+    }
+
     /// Get a sender to send commands to the player
     pub fn command_sender(&self) -> Sender<PlaybackCommand> {
         self.command_sender.clone()
@@ -116,50 +221,202 @@ impl VideoPlayer {
     pub fn update(&mut self, dt: Duration) -> Option<DecodedFrame> {
         self.process_commands();
 
+        // Keep the soundtrack decoded ahead, also while paused
+        if let Some(audio) = self.audio.as_mut() {
+            if let Err(e) = audio.fill() {
+                warn!("Audio decoder error during playback: {}", e);
+            }
+        }
+
         // If not playing, return last frame or None
         if self.state != PlaybackState::Playing {
             return self.last_frame.clone();
         }
 
-        let duration = self.decoder.duration();
+        let (start, end) = self.play_range();
+        let step = dt.mul_f32(self.playback_speed);
 
-        // Advance time
-        self.current_time += dt.mul_f32(self.playback_speed);
+        if self.reverse {
+            if self.current_time >= start + step {
+                self.current_time -= step;
+            } else {
+                // Passed the in point, the overshoot continues after the turn
+                let overshoot = step - self.current_time.saturating_sub(start);
+                let target = match self.loop_mode {
+                    LoopMode::PingPong => {
+                        self.reverse = false;
+                        (start + overshoot).min(end)
+                    }
+                    _ => end.saturating_sub(overshoot).max(start),
+                };
+                if let Err(e) = self.seek_internal(target) {
+                    self.transition_to_error(e);
+                    return self.last_frame.clone();
+                }
+                self.sync_audio();
+                let _ = self.status_sender.send(PlaybackStatus::Looped);
+            }
+            return self.present_reverse_frame();
+        }
+
+        // Advance time, following the audio clock while it runs
+        self.current_time += step;
+        if let Some(position) = self.audio_clock() {
+            self.current_time = position;
+        }
 
         // Check for end of stream
-        if self.current_time >= duration {
+        if self.current_time >= end {
             match self.loop_mode {
-                LoopMode::Loop => {
-                    self.current_time = Duration::ZERO;
-                    if let Err(e) = self.seek_internal(Duration::ZERO) {
+                LoopMode::Loop | LoopMode::Reverse => {
+                    if let Err(e) = self.seek_internal(start) {
                         self.transition_to_error(e);
                         return self.last_frame.clone();
                     }
                     let _ = self.status_sender.send(PlaybackStatus::Looped);
                 }
                 LoopMode::PlayOnce => {
-                    self.current_time = duration; // Clamp to end
+                    self.current_time = end; // Clamp to end
                     let _ = self.transition_state(PlaybackState::Stopped); // Auto-stop
                     let _ = self.status_sender.send(PlaybackStatus::ReachedEnd);
                     return self.last_frame.clone();
                 }
+                LoopMode::PingPong => {
+                    let overshoot = self.current_time - end;
+                    self.reverse = true;
+                    self.current_time = end.saturating_sub(overshoot).max(start);
+                    self.reset_frames();
+                    self.sync_audio();
+                    let _ = self.status_sender.send(PlaybackStatus::Looped);
+                    return self.present_reverse_frame();
+                }
+            }
+        }
+
+        self.present_frame()
+    }
+
+    /// Get the frame covering `current_time` while playing backward
+    ///
+    /// Decodes the window of frames up to the playhead into the GOP cache
+    /// when the playhead leaves the cached window. With a decode-ahead
+    /// pipeline the window fills over several updates.
+    fn present_reverse_frame(&mut self) -> Option<DecodedFrame> {
+        let time = self.current_time;
+        if self.gop_fill.is_none() && !self.gop.covers(time) {
+            let frames = self.gop.capacity().saturating_sub(1) as u32;
+            let window = self.frame_duration() * frames;
+            let start = time.saturating_sub(window).max(self.play_range().0);
+            if let Err(e) = self.decoder.seek(start) {
+                warn!("Reverse playback seek failed: {}", e);
+                return self.last_frame.clone();
+            }
+            self.gop.begin(start, time);
+            self.gop_fill = Some(time);
+        }
+
+        if let Some(window_end) = self.gop_fill {
+            loop {
+                match self.decoder.next_frame() {
+                    Ok(frame) if frame.pts > window_end => break,
+                    Ok(frame) => self.gop.insert(frame),
+                    // Continue filling the window on the next update
+                    Err(MediaError::FrameNotReady) => return self.last_frame.clone(),
+                    Err(MediaError::EndOfStream) => break,
+                    Err(e) => {
+                        warn!("Decoder error during reverse playback: {}", e);
+                        break;
+                    }
+                }
             }
+            self.gop_fill = None;
         }
 
-        match self.decoder.next_frame() {
-            Ok(frame) => {
+        if let Some(frame) = self.gop.frame_at(time) {
+            if self
+                .last_frame
+                .as_ref()
+                .is_some_and(|last| last.pts == frame.pts)
+            {
+                self.frames_repeated += 1;
+            } else {
                 self.last_frame = Some(frame.clone());
-                Some(frame)
             }
-            Err(e) => {
-                warn!("Decoder error during playback: {}", e);
-                // We don't immediately stop on one bad frame, maybe?
-                // But for robustness, maybe we should just log and keep last frame.
-                // If it persists, we transition to Error.
-                // For now, let's just log and return last frame.
-                self.last_frame.clone()
+        }
+        self.last_frame.clone()
+    }
+
+    /// Get the frame covering `current_time`
+    ///
+    /// Repeats the last frame while it is still current and decodes past
+    /// late frames, at most `MAX_CATCH_UP_FRAMES` per update.
+    fn present_frame(&mut self) -> Option<DecodedFrame> {
+        if self.frame_end.is_some_and(|end| self.current_time < end) {
+            self.frames_repeated += 1;
+            return self.last_frame.clone();
+        }
+
+        let frame_duration = self.frame_duration();
+        for decoded in 0..MAX_CATCH_UP_FRAMES {
+            match self.decoder.next_frame() {
+                Ok(frame) => {
+                    // The frame decoded before this one was never shown
+                    if decoded > 0 {
+                        self.frames_dropped += 1;
+                    }
+                    let end = frame.pts + frame_duration;
+                    self.last_frame = Some(frame);
+                    self.frame_end = Some(end);
+                    if self.current_time < end {
+                        break;
+                    }
+                }
+                // The decode-ahead thread has not caught up, show the last frame
+                Err(MediaError::FrameNotReady) => break,
+                Err(e) => {
+                    warn!("Decoder error during playback: {}", e);
+                    // Keep showing the last frame rather than stopping on one bad frame
+                    break;
+                }
             }
         }
+        self.last_frame.clone()
+    }
+
+    fn frame_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.decoder.fps().max(1.0))
+    }
+
+    /// Forget the shown frame and the reverse window, e.g. after a seek
+    fn reset_frames(&mut self) {
+        self.frame_end = None;
+        self.gop.clear();
+        self.gop_fill = None;
+    }
+
+    /// Check if the soundtrack plays along
+    ///
+    /// Audio is neither resampled for speed changes nor played backward, so
+    /// it only runs forward at 1x.
+    fn audio_follows(&self) -> bool {
+        self.playback_speed == 1.0 && !self.reverse
+    }
+
+    /// Get the audio position if it drives playback
+    fn audio_clock(&self) -> Option<Duration> {
+        if !self.audio_follows() {
+            return None;
+        }
+        self.audio.as_ref().and_then(AudioTrack::clock)
+    }
+
+    /// Start or pause the soundtrack to match the playback state
+    fn sync_audio(&self) {
+        if let Some(audio) = &self.audio {
+            audio
+                .queue()
+                .set_playing(self.state == PlaybackState::Playing && self.audio_follows());
+        }
     }
 
     fn process_commands(&mut self) {
@@ -171,6 +428,12 @@ impl VideoPlayer {
                 PlaybackCommand::Seek(time) => self.seek(time),
                 PlaybackCommand::SetSpeed(speed) => self.set_speed(speed),
                 PlaybackCommand::SetLoopMode(mode) => self.set_loop_mode(mode),
+                PlaybackCommand::SetInPoint(time) => self.set_in_point(time),
+                PlaybackCommand::SetOutPoint(time) => self.set_out_point(time),
+                PlaybackCommand::SetBeatSync(sync) => self.set_beat_sync(sync),
+                PlaybackCommand::SetTempo(bpm) => self.set_tempo(bpm),
+                PlaybackCommand::Beat(beat) => self.on_beat(beat),
+                PlaybackCommand::QueueCue(index) => self.queue_cue(index),
             };
 
             if let Err(e) = result {
@@ -225,16 +488,23 @@ impl VideoPlayer {
     // --- Command Implementations ---
 
     pub fn play(&mut self) -> Result<(), PlayerError> {
-        self.transition_state(PlaybackState::Playing)
+        self.transition_state(PlaybackState::Playing)?;
+        self.sync_audio();
+        Ok(())
     }
 
     pub fn pause(&mut self) -> Result<(), PlayerError> {
-        self.transition_state(PlaybackState::Paused)
+        self.transition_state(PlaybackState::Paused)?;
+        self.sync_audio();
+        Ok(())
     }
 
+    /// Stop and return to the in point (the out point when in reverse)
     pub fn stop(&mut self) -> Result<(), PlayerError> {
         self.transition_state(PlaybackState::Stopped)?;
-        self.seek_internal(Duration::ZERO)
+        self.sync_audio();
+        let (start, end) = self.play_range();
+        self.seek_internal(if self.reverse { end } else { start })
     }
 
     pub fn seek(&mut self, time: Duration) -> Result<(), PlayerError> {
@@ -248,19 +518,258 @@ impl VideoPlayer {
         match self.decoder.seek(target_time) {
             Ok(_) => {
                 self.current_time = target_time;
+                self.reset_frames();
+                self.seek_audio();
                 Ok(())
             }
             Err(e) => Err(PlayerError::Seek(e.to_string())),
         }
     }
 
+    /// Move the soundtrack to `current_time`; audio errors never stop the video
+    fn seek_audio(&mut self) {
+        if let Some(audio) = self.audio.as_mut() {
+            if let Err(e) = audio.seek(self.current_time) {
+                warn!("Audio seek failed: {}", e);
+            }
+        }
+    }
+
     pub fn set_speed(&mut self, speed: f32) -> Result<(), PlayerError> {
-        self.playback_speed = speed.max(0.0); // No negative speed for now
+        let audio_followed = self.audio_follows();
+        self.playback_speed = speed.max(0.0); // No negative speed, use LoopMode::Reverse
+
+        // The soundtrack stood still while the video ran at another speed
+        if !audio_followed && self.audio_follows() {
+            self.seek_audio();
+        }
+        self.sync_audio();
         Ok(())
     }
 
+    /// Attach the soundtrack of the clip, replacing the previous one
+    ///
+    /// The track is moved to the current position and played, paused and
+    /// seeked with the video. Hand [`AudioTrack::queue`] to an audio output
+    /// before or after attaching it.
+    pub fn set_audio_track(&mut self, track: AudioTrack) {
+        self.audio = Some(track);
+        self.seek_audio();
+        self.sync_audio();
+    }
+
+    /// Detach the soundtrack, returning it
+    pub fn take_audio_track(&mut self) -> Option<AudioTrack> {
+        let track = self.audio.take();
+        if let Some(track) = &track {
+            track.queue().set_playing(false);
+        }
+        track
+    }
+
+    pub fn audio_track(&self) -> Option<&AudioTrack> {
+        self.audio.as_ref()
+    }
+
     pub fn set_loop_mode(&mut self, mode: LoopMode) -> Result<(), PlayerError> {
         self.loop_mode = mode;
+        let reverse = match mode {
+            LoopMode::Reverse => true,
+            // Ping-pong continues in the current direction
+            LoopMode::PingPong => self.reverse,
+            LoopMode::Loop | LoopMode::PlayOnce => false,
+        };
+        if reverse != self.reverse {
+            self.reverse = reverse;
+            // The decoder position and soundtrack only fit one direction
+            self.seek_internal(self.current_time)?;
+            self.sync_audio();
+        }
+        Ok(())
+    }
+
+    /// Set where playback starts and loops back to (`None` for the start)
+    pub fn set_in_point(&mut self, time: Option<Duration>) -> Result<(), PlayerError> {
+        let time = time.map(|t| t.min(self.decoder.duration()));
+        if let (Some(in_point), Some(out_point)) = (time, self.out_point) {
+            if in_point >= out_point {
+                return Err(PlayerError::InvalidRange(format!(
+                    "in point {:?} is not before out point {:?}",
+                    in_point, out_point
+                )));
+            }
+        }
+        self.in_point = time;
+        self.clamp_to_range()?;
+        self.apply_tempo()
+    }
+
+    /// Set where playback ends or loops (`None` for the end)
+    pub fn set_out_point(&mut self, time: Option<Duration>) -> Result<(), PlayerError> {
+        let time = time.map(|t| t.min(self.decoder.duration()));
+        if let (Some(in_point), Some(out_point)) = (self.in_point, time) {
+            if in_point >= out_point {
+                return Err(PlayerError::InvalidRange(format!(
+                    "out point {:?} is not after in point {:?}",
+                    out_point, in_point
+                )));
+            }
+        }
+        self.out_point = time;
+        self.clamp_to_range()?;
+        self.apply_tempo()
+    }
+
+    /// Move the playhead into the in/out range if it left it
+    fn clamp_to_range(&mut self) -> Result<(), PlayerError> {
+        let (start, end) = self.play_range();
+        if self.current_time < start || self.current_time > end {
+            self.seek_internal(start)?;
+        }
+        Ok(())
+    }
+
+    /// Get the range playback loops over, from the in to the out point
+    pub fn play_range(&self) -> (Duration, Duration) {
+        let duration = self.decoder.duration();
+        (
+            self.in_point.unwrap_or(Duration::ZERO),
+            self.out_point.unwrap_or(duration),
+        )
+    }
+
+    pub fn in_point(&self) -> Option<Duration> {
+        self.in_point
+    }
+
+    pub fn out_point(&self) -> Option<Duration> {
+        self.out_point
+    }
+
+    /// Check if playback currently runs backward
+    pub fn is_reversing(&self) -> bool {
+        self.reverse
+    }
+
+    /// Set how many frames reverse playback decodes per window
+    ///
+    /// Larger windows seek less often but hold more decoded frames in memory.
+    pub fn set_gop_cache_size(&mut self, frames: usize) {
+        self.gop.set_capacity(frames);
+        self.gop_fill = None;
+    }
+
+    /// Lock the speed to the tempo, or unlock it with `None`
+    ///
+    /// Unlocking keeps the current speed.
+    pub fn set_beat_sync(&mut self, sync: Option<BeatSync>) -> Result<(), PlayerError> {
+        self.beat_sync = sync;
+        self.beat_anchor = None;
+        self.pending_cue = None;
+        self.apply_tempo()
+    }
+
+    pub fn beat_sync(&self) -> Option<&BeatSync> {
+        self.beat_sync.as_ref()
+    }
+
+    /// Set the current tempo, e.g. from audio analysis or MIDI clock
+    pub fn set_tempo(&mut self, bpm: f32) -> Result<(), PlayerError> {
+        if !bpm.is_finite() || bpm <= 0.0 {
+            return Err(PlayerError::InvalidCommand {
+                state: format!("{:?}", self.state),
+                command: format!("SetTempo({})", bpm),
+            });
+        }
+        self.tempo_bpm = Some(bpm);
+        self.apply_tempo()
+    }
+
+    pub fn tempo(&self) -> Option<f32> {
+        self.tempo_bpm
+    }
+
+    /// Derive the speed from the tempo and the clip length in beats
+    fn apply_tempo(&mut self) -> Result<(), PlayerError> {
+        let speed = match (&self.beat_sync, self.tempo_bpm) {
+            (Some(sync), Some(bpm)) => {
+                let (start, end) = self.play_range();
+                sync.speed(end.saturating_sub(start), bpm)
+            }
+            _ => return Ok(()),
+        };
+        self.set_speed(speed)
+    }
+
+    /// Jump to cue point `index` on the next beat of the quantize grid
+    pub fn queue_cue(&mut self, index: usize) -> Result<(), PlayerError> {
+        match &self.beat_sync {
+            Some(sync) if index < sync.cue_points.len() => {
+                self.pending_cue = Some(index);
+                Ok(())
+            }
+            _ => Err(PlayerError::InvalidCue(index)),
+        }
+    }
+
+    /// Get the cue point waiting for its beat
+    pub fn pending_cue(&self) -> Option<usize> {
+        self.pending_cue
+    }
+
+    /// Handle beat number `beat` of the tempo source
+    ///
+    /// Beats count up from the first beat of the song or clock start, so
+    /// `beat % beats_per_bar == 0` is a downbeat. A queued cue is jumped to
+    /// on its quantize beat. The first downbeat launches the clip from the
+    /// in point, later downbeats pull forward playback back in phase when
+    /// it drifted by more than a frame.
+    pub fn on_beat(&mut self, beat: u64) -> Result<(), PlayerError> {
+        let Some(sync) = &self.beat_sync else {
+            return Ok(());
+        };
+        if self.state != PlaybackState::Playing {
+            return Ok(());
+        }
+        let (start, end) = self.play_range();
+
+        let cue = self
+            .pending_cue
+            .filter(|_| sync.is_cue_beat(beat))
+            .map(|index| (index, sync.cue_points[index].clamp(start, end)));
+        if let Some((index, cue)) = cue {
+            self.pending_cue = None;
+            self.beat_anchor = Some((beat, cue - start));
+            self.seek_internal(cue)?;
+            self.sync_audio();
+            let _ = self.status_sender.send(PlaybackStatus::CueTriggered(index));
+            return Ok(());
+        }
+
+        if !sync.resync_on_downbeat || !sync.is_downbeat(beat) || self.reverse {
+            return Ok(());
+        }
+        let length = end.saturating_sub(start);
+        let expected = match self.beat_anchor {
+            // Beats counting down mean the tempo source restarted
+            Some((anchor, offset)) if anchor <= beat => {
+                start + sync.offset_after(offset, beat - anchor, length)
+            }
+            _ => {
+                self.beat_anchor = Some((beat, Duration::ZERO));
+                self.seek_internal(start)?;
+                self.sync_audio();
+                return Ok(());
+            }
+        };
+
+        // Compare around the loop: the end of the range is close to its start
+        let drift = (self.current_time.as_secs_f64() - expected.as_secs_f64()).abs();
+        let drift = drift.min(length.as_secs_f64() - drift);
+        if drift > self.frame_duration().as_secs_f64() {
+            self.seek_internal(expected)?;
+            self.sync_audio();
+        }
         Ok(())
     }
 
@@ -293,13 +802,38 @@ impl VideoPlayer {
     pub fn fps(&self) -> f64 {
         self.decoder.fps()
     }
+
+    /// Get the number of late frames skipped to keep up with the clock
+    pub fn frames_dropped(&self) -> u64 {
+        self.frames_dropped
+    }
+
+    /// Get the number of updates that showed the previous frame again
+    pub fn frames_repeated(&self) -> u64 {
+        self.frames_repeated
+    }
+
+    /// Get the decode-ahead statistics, including late frames skipped by
+    /// the player
+    pub fn pipeline_stats(&self) -> Option<PipelineStats> {
+        self.pipeline.as_ref().map(|pipeline| {
+            let mut stats = pipeline.stats();
+            stats.dropped_frames += self.frames_dropped;
+            stats
+        })
+    }
+
+    /// Check if frames are decoded ahead on a separate thread
+    pub fn is_decoding_ahead(&self) -> bool {
+        self.pipeline.is_some()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::decoder::TestPatternDecoder;
-    use crate::{MediaError, PixelFormat};
+    use crate::{MediaError, PixelFormat, Quantize, TestToneDecoder};
 
     // A mock decoder that can be configured to fail.
     #[derive(Clone)]
@@ -405,6 +939,11 @@ mod tests {
 
         assert!(player.set_loop_mode(LoopMode::PlayOnce).is_ok());
         assert_eq!(player.loop_mode(), LoopMode::PlayOnce);
+
+        assert_eq!(LoopMode::from_name("none"), Some(LoopMode::PlayOnce));
+        assert_eq!(LoopMode::from_name("ping-pong"), Some(LoopMode::PingPong));
+        assert_eq!(LoopMode::from_name("reverse"), Some(LoopMode::Reverse));
+        assert_eq!(LoopMode::from_name("bounce"), None);
     }
 
     #[test]
@@ -539,4 +1078,238 @@ mod tests {
         // Should return None or last frame, but not panic
         assert!(frame.is_none());
     }
+
+    #[test]
+    fn test_frames_follow_clock() {
+        let decoder = TestPatternDecoder::new(64, 36, Duration::from_secs(10), 30.0);
+        let mut player = VideoPlayer::new(decoder);
+        player.play().unwrap();
+
+        let first = player.update(Duration::ZERO).unwrap();
+        assert_eq!(first.pts, Duration::ZERO);
+
+        // Still within the first frame: shown again
+        let frame = player.update(Duration::from_millis(10)).unwrap();
+        assert_eq!(frame.pts, Duration::ZERO);
+        assert_eq!(player.frames_repeated(), 1);
+
+        // 110 ms: frames at 33 and 67 ms are late and skipped
+        let frame = player.update(Duration::from_millis(100)).unwrap();
+        assert!(frame.pts > Duration::from_millis(90));
+        assert!(frame.pts <= Duration::from_millis(110));
+        assert_eq!(player.frames_dropped(), 2);
+    }
+
+    #[test]
+    fn test_audio_clock_drives_video() {
+        let decoder = TestPatternDecoder::new(64, 36, Duration::from_secs(10), 30.0);
+        let mut player = VideoPlayer::new(decoder);
+        let track = AudioTrack::new(TestToneDecoder::new(
+            440.0,
+            1000,
+            1,
+            Duration::from_secs(10),
+        ))
+        .with_buffer_ahead(Duration::from_secs(1));
+        let queue = track.queue();
+        player.set_audio_track(track);
+        assert!(!queue.is_playing());
+
+        // Without an output draining the queue the video runs on its own
+        player.play().unwrap();
+        assert!(queue.is_playing());
+        player.update(Duration::from_millis(20));
+        assert_eq!(player.current_time(), Duration::from_millis(20));
+
+        // The output consumed 200 ms of audio: the video jumps to match
+        let mut out = vec![0.0; 200];
+        queue.pull(&mut out);
+        let frame = player.update(Duration::from_millis(20)).unwrap();
+        assert_eq!(player.current_time(), Duration::from_millis(200));
+        assert!(frame.pts <= Duration::from_millis(200));
+        assert!(frame.pts + Duration::from_millis(34) > Duration::from_millis(200));
+
+        // An update without consumed audio repeats the frame
+        let again = player.update(Duration::from_millis(20)).unwrap();
+        assert_eq!(again.pts, frame.pts);
+
+        player.pause().unwrap();
+        assert!(!queue.is_playing());
+
+        player.seek(Duration::from_secs(2)).unwrap();
+        assert_eq!(queue.position(), Duration::from_secs(2));
+
+        // Other speeds mute the soundtrack and run on the update delta
+        player.play().unwrap();
+        player.set_speed(2.0).unwrap();
+        assert!(!queue.is_playing());
+        player.update(Duration::from_millis(100));
+        let time = player.current_time();
+        assert!(time > Duration::from_millis(2199) && time < Duration::from_millis(2201));
+
+        player.set_speed(1.0).unwrap();
+        assert!(queue.is_playing());
+        assert_eq!(queue.position(), time);
+
+        let track = player.take_audio_track().unwrap();
+        assert!(!track.queue().is_playing());
+    }
+
+    #[test]
+    fn test_reverse_playback() {
+        let decoder = TestPatternDecoder::new(64, 36, Duration::from_secs(2), 30.0);
+        let mut player = VideoPlayer::new(decoder);
+        player.set_gop_cache_size(10);
+        player.seek(Duration::from_secs(1)).unwrap();
+        player.set_loop_mode(LoopMode::Reverse).unwrap();
+        assert!(player.is_reversing());
+        player.play().unwrap();
+
+        // Frames come out of the GOP cache with decreasing timestamps
+        let mut last = player.update(Duration::ZERO).unwrap().pts;
+        assert!(last <= Duration::from_secs(1));
+        for _ in 0..20 {
+            let frame = player.update(Duration::from_millis(34)).unwrap();
+            assert!(frame.pts < last);
+            assert!(frame.pts <= player.current_time());
+            last = frame.pts;
+        }
+        assert!(player.current_time() < Duration::from_millis(400));
+
+        // Passing the start wraps to the end
+        let rx = player.status_receiver();
+        while rx.try_recv().is_ok() {}
+        player.update(Duration::from_millis(400));
+        assert!(player.current_time() > Duration::from_millis(1900));
+        assert_eq!(rx.try_recv(), Ok(PlaybackStatus::Looped));
+    }
+
+    #[test]
+    fn test_ping_pong() {
+        let decoder = TestPatternDecoder::new(64, 36, Duration::from_secs(1), 30.0);
+        let mut player = VideoPlayer::new(decoder);
+        player.set_loop_mode(LoopMode::PingPong).unwrap();
+        assert!(!player.is_reversing());
+        player.play().unwrap();
+
+        // The overshoot past the end is played backward
+        player.update(Duration::from_millis(1100));
+        assert!(player.is_reversing());
+        let time = player.current_time();
+        assert!(time > Duration::from_millis(899) && time < Duration::from_millis(901));
+        let frame = player.update(Duration::ZERO).unwrap();
+        assert!(frame.pts <= time);
+        assert!(frame.pts + Duration::from_millis(34) > time);
+
+        // And the overshoot past the start forward again
+        player.update(Duration::from_millis(1000));
+        assert!(!player.is_reversing());
+        let time = player.current_time();
+        assert!(time > Duration::from_millis(99) && time < Duration::from_millis(101));
+    }
+
+    #[test]
+    fn test_in_out_points() {
+        let decoder = TestPatternDecoder::new(64, 36, Duration::from_secs(10), 30.0);
+        let mut player = VideoPlayer::new(decoder);
+        player.set_in_point(Some(Duration::from_secs(2))).unwrap();
+        assert_eq!(player.current_time(), Duration::from_secs(2));
+        player.set_out_point(Some(Duration::from_secs(3))).unwrap();
+        assert_eq!(
+            player.play_range(),
+            (Duration::from_secs(2), Duration::from_secs(3))
+        );
+        assert!(matches!(
+            player.set_out_point(Some(Duration::from_secs(1))),
+            Err(PlayerError::InvalidRange(_))
+        ));
+
+        // Loops back to the in point
+        player.play().unwrap();
+        let frame = player.update(Duration::ZERO).unwrap();
+        assert_eq!(frame.pts, Duration::from_secs(2));
+        player.update(Duration::from_millis(1100));
+        assert_eq!(player.current_time(), Duration::from_secs(2));
+
+        // Play once stops at the out point, stop returns to the in point
+        player.set_loop_mode(LoopMode::PlayOnce).unwrap();
+        player.update(Duration::from_millis(1100));
+        assert_eq!(*player.state(), PlaybackState::Stopped);
+        assert_eq!(player.current_time(), Duration::from_secs(3));
+        player.play().unwrap();
+        player.stop().unwrap();
+        assert_eq!(player.current_time(), Duration::from_secs(2));
+
+        player.set_in_point(None).unwrap();
+        assert_eq!(player.play_range().0, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_beat_sync_speed() {
+        let decoder = TestPatternDecoder::new(64, 36, Duration::from_secs(4), 30.0);
+        let mut player = VideoPlayer::new(decoder);
+        player.set_beat_sync(Some(BeatSync::new(8.0))).unwrap();
+        assert_eq!(player.speed(), 1.0);
+
+        // 4 s over 8 beats is real time at 120 BPM
+        player.set_tempo(120.0).unwrap();
+        assert!((player.speed() - 1.0).abs() < 1e-6);
+        player.set_tempo(150.0).unwrap();
+        assert!((player.speed() - 1.25).abs() < 1e-6);
+
+        // Half the clip over the same 8 beats
+        player.set_out_point(Some(Duration::from_secs(2))).unwrap();
+        assert!((player.speed() - 0.625).abs() < 1e-6);
+
+        assert!(player.set_tempo(0.0).is_err());
+        player.set_beat_sync(None).unwrap();
+        player.set_tempo(60.0).unwrap();
+        assert!((player.speed() - 0.625).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_beat_sync_cues_and_resync() {
+        let decoder = TestPatternDecoder::new(64, 36, Duration::from_secs(2), 30.0);
+        let mut player = VideoPlayer::new(decoder);
+        let rx = player.status_receiver();
+        let sync = BeatSync::new(4.0)
+            .with_cue_points(vec![
+                Duration::from_millis(500),
+                Duration::from_millis(1500),
+            ])
+            .with_quantize(Quantize::Bar);
+        player.set_beat_sync(Some(sync)).unwrap();
+        player.set_tempo(120.0).unwrap();
+        assert_eq!(player.queue_cue(2), Err(PlayerError::InvalidCue(2)));
+
+        // The first downbeat launches the clip from the in point
+        player.play().unwrap();
+        player.update(Duration::from_millis(300));
+        player.on_beat(3).unwrap();
+        assert!(player.current_time() > Duration::from_millis(299));
+        player.on_beat(4).unwrap();
+        assert_eq!(player.current_time(), Duration::ZERO);
+
+        // A late clip is pulled back in phase on the next downbeat
+        player.update(Duration::from_millis(1800));
+        player.on_beat(8).unwrap();
+        assert_eq!(player.current_time(), Duration::ZERO);
+
+        // Cues wait for the next bar
+        player.queue_cue(1).unwrap();
+        player.update(Duration::from_millis(500));
+        player.on_beat(9).unwrap();
+        assert_eq!(player.pending_cue(), Some(1));
+        while rx.try_recv().is_ok() {}
+        player.on_beat(12).unwrap();
+        assert_eq!(player.pending_cue(), None);
+        assert_eq!(player.current_time(), Duration::from_millis(1500));
+        assert_eq!(rx.try_recv(), Ok(PlaybackStatus::CueTriggered(1)));
+
+        // The phase continues from the cue: one bar later is the cue again
+        player.update(Duration::from_millis(2000));
+        player.on_beat(16).unwrap();
+        let time = player.current_time();
+        assert!(time > Duration::from_millis(1499) && time < Duration::from_millis(1501));
+    }
 }
@@ -0,0 +1,29 @@
+// Hap Q: scaled YCoCg with luma in alpha to RGBA
+//
+// Reads the decompressed BC3 texels 1:1 into a texture of the video size.
+
+@group(0) @binding(0) var blocks: texture_2d<f32>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // Fullscreen triangle
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureLoad(blocks, vec2<i32>(in.position.xy), 0);
+    let offset = 128.0 / 255.0;
+    let scale = texel.b * (255.0 / 8.0) + 1.0;
+    let co = (texel.r - offset) / scale;
+    let cg = (texel.g - offset) / scale;
+    let y = texel.a;
+    return vec4<f32>(y + co - cg, y + cg, y - co - cg, 1.0);
+}
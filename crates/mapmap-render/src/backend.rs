@@ -66,8 +66,10 @@ impl WgpuBackend {
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: Some("MapFlow Device"),
+                    // BC textures are optional, Hap frames fall back to CPU decoding
                     required_features: wgpu::Features::TIMESTAMP_QUERY
-                        | wgpu::Features::PUSH_CONSTANTS,
+                        | wgpu::Features::PUSH_CONSTANTS
+                        | (adapter.features() & wgpu::Features::TEXTURE_COMPRESSION_BC),
                     required_limits: wgpu::Limits {
                         max_push_constant_size: 128,
                         ..Default::default()
@@ -111,6 +113,11 @@ impl WgpuBackend {
         self.device.limits()
     }
 
+    /// Check if block-compressed (BC) textures can be sampled
+    pub fn supports_bc_textures(&self) -> bool {
+        crate::compressed_texture::is_supported(&self.device)
+    }
+
     /// Get adapter info
     pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
         &self.adapter_info
@@ -176,9 +183,20 @@ impl RenderBackend for WgpuBackend {
     }
 
     fn upload_texture(&mut self, handle: TextureHandle, data: &[u8]) -> Result<()> {
-        let bytes_per_pixel = match handle.format {
-            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => 4,
-            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => 4,
+        // Block-compressed rows hold 4x4 blocks instead of pixels
+        let (bytes_per_row, rows) = match handle.format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {
+                (handle.width * 4, handle.height)
+            }
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+                (handle.width * 4, handle.height)
+            }
+            wgpu::TextureFormat::Bc1RgbaUnorm | wgpu::TextureFormat::Bc1RgbaUnormSrgb => {
+                crate::BlockFormat::Bc1.data_layout(handle.width, handle.height)
+            }
+            wgpu::TextureFormat::Bc3RgbaUnorm | wgpu::TextureFormat::Bc3RgbaUnormSrgb => {
+                crate::BlockFormat::Bc3.data_layout(handle.width, handle.height)
+            }
             _ => {
                 return Err(RenderError::TextureCreation(
                     "Unsupported texture format for upload".to_string(),
@@ -186,7 +204,7 @@ impl RenderBackend for WgpuBackend {
             }
         };
 
-        let expected_size = (handle.width * handle.height * bytes_per_pixel) as usize;
+        let expected_size = (bytes_per_row * rows) as usize;
         if data.len() != expected_size {
             return Err(RenderError::TextureCreation(format!(
                 "Data size mismatch: expected {}, got {}",
@@ -195,9 +213,6 @@ impl RenderBackend for WgpuBackend {
             )));
         }
 
-        // Calculate row stride
-        let bytes_per_row = handle.width * bytes_per_pixel;
-
         // Use direct write for all textures (queue.write_texture is efficient)
         self.queue.write_texture(
             wgpu::ImageCopyTexture {
@@ -210,7 +225,7 @@ impl RenderBackend for WgpuBackend {
             wgpu::ImageDataLayout {
                 offset: 0,
                 bytes_per_row: Some(bytes_per_row),
-                rows_per_image: Some(handle.height),
+                rows_per_image: Some(rows),
             },
             wgpu::Extent3d {
                 width: handle.width,
@@ -0,0 +1,335 @@
+//! Upload of block-compressed video frames
+//!
+//! GPU codecs such as Hap store frames as BC texture blocks. They are
+//! written to BC textures as they are and decompressed by the texture units
+//! when sampled, so a frame costs a quarter to an eighth of the upload
+//! bandwidth of RGBA and no CPU conversion. Hap Q frames hold scaled YCoCg
+//! and get one extra render pass to RGBA.
+//!
+//! BC textures need `wgpu::Features::TEXTURE_COMPRESSION_BC`, which desktop
+//! GPUs have. Without it, decode frames to RGBA on the CPU instead.
+
+use crate::{RenderError, Result};
+use std::sync::Arc;
+
+/// Layout of the texture blocks in a compressed frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockFormat {
+    /// BC1/DXT1 (Hap)
+    Bc1,
+    /// BC3/DXT5 (Hap Alpha)
+    Bc3,
+    /// BC3/DXT5 holding scaled YCoCg with luma in alpha (Hap Q)
+    Bc3YCoCg,
+}
+
+impl BlockFormat {
+    /// Get the bytes per 4x4 block
+    pub fn block_bytes(self) -> u32 {
+        match self {
+            BlockFormat::Bc1 => 8,
+            BlockFormat::Bc3 | BlockFormat::Bc3YCoCg => 16,
+        }
+    }
+
+    /// Get the texture format the blocks are uploaded to
+    ///
+    /// Video is sRGB encoded. YCoCg must be converted before decoding sRGB,
+    /// so its blocks are read as linear values.
+    pub fn texture_format(self) -> wgpu::TextureFormat {
+        match self {
+            BlockFormat::Bc1 => wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+            BlockFormat::Bc3 => wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+            BlockFormat::Bc3YCoCg => wgpu::TextureFormat::Bc3RgbaUnorm,
+        }
+    }
+
+    /// Get the size of a frame rounded up to whole blocks
+    pub fn padded_size(width: u32, height: u32) -> (u32, u32) {
+        (width.div_ceil(4) * 4, height.div_ceil(4) * 4)
+    }
+
+    /// Get the bytes per row of blocks and the number of block rows
+    pub fn data_layout(self, width: u32, height: u32) -> (u32, u32) {
+        (width.div_ceil(4) * self.block_bytes(), height.div_ceil(4))
+    }
+
+    /// Get the size of a `width` x `height` frame in bytes
+    pub fn frame_size(self, width: u32, height: u32) -> usize {
+        let (bytes_per_row, rows) = self.data_layout(width, height);
+        bytes_per_row as usize * rows as usize
+    }
+}
+
+/// Check if the device can sample BC textures
+pub fn is_supported(device: &wgpu::Device) -> bool {
+    device
+        .features()
+        .contains(wgpu::Features::TEXTURE_COMPRESSION_BC)
+}
+
+/// A texture receiving compressed frames of one size and format
+pub struct CompressedTexture {
+    format: BlockFormat,
+    width: u32,
+    height: u32,
+    blocks: wgpu::Texture,
+    /// RGBA result of the YCoCg conversion
+    converted: Option<(wgpu::Texture, wgpu::BindGroup)>,
+}
+
+impl CompressedTexture {
+    pub fn format(&self) -> BlockFormat {
+        self.format
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Create a view for sampling the frame as RGBA
+    pub fn create_view(&self) -> wgpu::TextureView {
+        let texture = match &self.converted {
+            Some((texture, _)) => texture,
+            None => &self.blocks,
+        };
+        texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    /// Get the UV extent of the video inside the view
+    ///
+    /// BC textures are padded to whole blocks, so sizes that are not
+    /// multiples of 4 have a few extra texels at the right and bottom.
+    pub fn uv_scale(&self) -> [f32; 2] {
+        if self.converted.is_some() {
+            return [1.0, 1.0];
+        }
+        let (width, height) = BlockFormat::padded_size(self.width, self.height);
+        [
+            self.width as f32 / width as f32,
+            self.height as f32 / height as f32,
+        ]
+    }
+}
+
+/// Uploads compressed frames to BC textures
+pub struct CompressedTextureUploader {
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+    ycocg_pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl CompressedTextureUploader {
+    /// Create an uploader, failing if the device has no BC texture support
+    pub fn new(device: Arc<wgpu::Device>, queue: Arc<wgpu::Queue>) -> Result<Self> {
+        if !is_supported(&device) {
+            return Err(RenderError::DeviceError(
+                "BC texture compression is not supported".to_string(),
+            ));
+        }
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Hap YCoCg Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Hap YCoCg Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/hap_ycocg.wgsl").into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Hap YCoCg Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let ycocg_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Hap YCoCg Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                // Written as stored values, the result is already sRGB encoded
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            ycocg_pipeline,
+            bind_group_layout,
+        })
+    }
+
+    /// Create a texture for `width` x `height` frames in `format`
+    pub fn create_texture(
+        &self,
+        format: BlockFormat,
+        width: u32,
+        height: u32,
+    ) -> CompressedTexture {
+        let (width, height) = (width.max(1), height.max(1));
+        let (padded_width, padded_height) = BlockFormat::padded_size(width, height);
+        let blocks = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Compressed Frame"),
+            size: wgpu::Extent3d {
+                width: padded_width,
+                height: padded_height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: format.texture_format(),
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let converted = (format == BlockFormat::Bc3YCoCg).then(|| {
+            let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Hap Q Frame"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[wgpu::TextureFormat::Rgba8Unorm],
+            });
+            let blocks_view = blocks.create_view(&wgpu::TextureViewDescriptor::default());
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Hap YCoCg Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&blocks_view),
+                }],
+            });
+            (texture, bind_group)
+        });
+
+        CompressedTexture {
+            format,
+            width,
+            height,
+            blocks,
+            converted,
+        }
+    }
+
+    /// Upload a frame of texture blocks
+    ///
+    /// Hap Q frames are converted to RGBA right away.
+    pub fn upload(&self, texture: &CompressedTexture, data: &[u8]) -> Result<()> {
+        let format = texture.format;
+        let expected = format.frame_size(texture.width, texture.height);
+        if data.len() != expected {
+            return Err(RenderError::TextureCreation(format!(
+                "Data size mismatch: expected {}, got {}",
+                expected,
+                data.len()
+            )));
+        }
+
+        let (bytes_per_row, rows) = format.data_layout(texture.width, texture.height);
+        let (width, height) = BlockFormat::padded_size(texture.width, texture.height);
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture.blocks,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(rows),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        if let Some((output, bind_group)) = &texture.converted {
+            let target = output.create_view(&wgpu::TextureViewDescriptor {
+                format: Some(wgpu::TextureFormat::Rgba8Unorm),
+                ..Default::default()
+            });
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Hap YCoCg Encoder"),
+                });
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Hap YCoCg Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+                render_pass.set_pipeline(&self.ycocg_pipeline);
+                render_pass.set_bind_group(0, bind_group, &[]);
+                render_pass.draw(0..3, 0..1);
+            }
+            self.queue.submit(Some(encoder.finish()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_layout() {
+        assert_eq!(BlockFormat::padded_size(1920, 1080), (1920, 1080));
+        assert_eq!(BlockFormat::padded_size(1918, 1081), (1920, 1084));
+
+        assert_eq!(BlockFormat::Bc1.data_layout(1920, 1080), (3840, 270));
+        assert_eq!(BlockFormat::Bc1.frame_size(1920, 1080), 1920 * 1080 / 2);
+        assert_eq!(BlockFormat::Bc3.frame_size(1920, 1080), 1920 * 1080);
+        assert_eq!(BlockFormat::Bc3YCoCg.frame_size(6, 6), 4 * 16);
+    }
+}
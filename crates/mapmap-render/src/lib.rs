@@ -7,12 +7,15 @@
 //! - GPU profiling
 //! - Effect chain post-processing
 //! - Preset system for effect chains
+//! - Asynchronous frame readback for CPU consumers
+//! - Block-compressed (Hap) frame upload
 
 use thiserror::Error;
 
 pub mod backend;
 pub mod color_calibration_renderer;
 pub mod compositor;
+pub mod compressed_texture;
 pub mod edge_blend_renderer;
 pub mod effect_chain_renderer;
 pub mod hot_reload;
@@ -21,12 +24,14 @@ pub mod oscillator_renderer;
 pub mod paint_texture_cache;
 pub mod preset;
 pub mod quad;
+pub mod readback;
 pub mod shader;
 pub mod texture;
 
 pub use backend::{RenderBackend, WgpuBackend};
 pub use color_calibration_renderer::ColorCalibrationRenderer;
 pub use compositor::Compositor;
+pub use compressed_texture::{BlockFormat, CompressedTexture, CompressedTextureUploader};
 pub use edge_blend_renderer::EdgeBlendRenderer;
 pub use effect_chain_renderer::{EffectChainRenderer, EffectParams};
 pub use hot_reload::{HotReloadIntegration, ShaderChangeEvent, ShaderHotReload, ShaderStatus};
@@ -34,6 +39,7 @@ pub use mesh_renderer::MeshRenderer;
 pub use oscillator_renderer::OscillatorRenderer;
 pub use preset::{EffectPreset, PresetLibrary, PresetMetadata};
 pub use quad::QuadRenderer;
+pub use readback::{FrameReadback, ReadbackFrame};
 pub use shader::{ShaderHandle, ShaderSource};
 pub use texture::{TextureDescriptor, TextureHandle, TexturePool};
 
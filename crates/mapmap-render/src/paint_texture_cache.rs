@@ -1,5 +1,6 @@
 //! Paint texture cache - manages GPU textures for paints
 
+use crate::compressed_texture::{self, BlockFormat, CompressedTexture, CompressedTextureUploader};
 use mapmap_core::paint::{Paint, PaintId, PaintType};
 use parking_lot::RwLock;
 use std::collections::HashMap;
@@ -12,25 +13,59 @@ pub struct PaintTextureCache {
     queue: Arc<wgpu::Queue>,
     /// Map of PaintId -> (TextureView, last_updated_version)
     cache: RwLock<HashMap<PaintId, CachedTexture>>,
+    /// Set if the device can sample BC textures
+    compressed: Option<CompressedTextureUploader>,
+}
+
+/// GPU storage of a paint's pixels
+enum PaintTexture {
+    Rgba(wgpu::Texture),
+    /// Block-compressed video frames
+    Compressed(CompressedTexture),
+}
+
+impl PaintTexture {
+    fn create_view(&self) -> wgpu::TextureView {
+        match self {
+            PaintTexture::Rgba(texture) => {
+                texture.create_view(&wgpu::TextureViewDescriptor::default())
+            }
+            PaintTexture::Compressed(texture) => texture.create_view(),
+        }
+    }
 }
 
 #[allow(dead_code)]
 struct CachedTexture {
-    texture: wgpu::Texture,
+    texture: PaintTexture,
     view: wgpu::TextureView,
     width: u32,
     height: u32,
+    /// Showing the "no signal" slate instead of live frames
+    no_signal: bool,
 }
 
 impl PaintTextureCache {
     pub fn new(device: Arc<wgpu::Device>, queue: Arc<wgpu::Queue>) -> Self {
+        let compressed = compressed_texture::is_supported(&device)
+            .then(|| CompressedTextureUploader::new(device.clone(), queue.clone()).ok())
+            .flatten();
         Self {
             device,
             queue,
             cache: RwLock::new(HashMap::new()),
+            compressed,
         }
     }
 
+    /// Check if compressed frames can be uploaded as they are
+    ///
+    /// Without BC texture support, decode them to RGBA and use
+    /// [`Self::upload_frame`] instead.
+    pub fn supports_compressed(&self) -> bool {
+        self.compressed.is_some()
+    }
+
     /// Get or create texture view for a paint
     pub fn get_texture_view(&self, paint: &Paint) -> wgpu::TextureView {
         let mut cache = self.cache.write();
@@ -38,52 +73,161 @@ impl PaintTextureCache {
         // Check if we already have this paint cached
         if let Some(cached) = cache.get(&paint.id) {
             // Return a new view of the cached texture
-            return cached
-                .texture
-                .create_view(&wgpu::TextureViewDescriptor::default());
+            return cached.texture.create_view();
         }
 
         // Create new texture for this paint
         let (width, height) = (paint.dimensions.x as u32, paint.dimensions.y as u32);
         let texture = self.create_texture_for_paint(paint, width, height);
-        let _view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-
         let cached = CachedTexture {
-            texture,
-            view: self
-                .device
-                .create_texture(&wgpu::TextureDescriptor {
-                    label: Some("dummy"),
-                    size: wgpu::Extent3d {
-                        width: 1,
-                        height: 1,
-                        depth_or_array_layers: 1,
-                    },
-                    mip_level_count: 1,
-                    sample_count: 1,
-                    dimension: wgpu::TextureDimension::D2,
-                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                    usage: wgpu::TextureUsages::TEXTURE_BINDING,
-                    view_formats: &[],
-                })
-                .create_view(&wgpu::TextureViewDescriptor::default()), // Placeholder, not used
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            texture: PaintTexture::Rgba(texture),
             width,
             height,
+            // Live paints show the slate until their first frame arrives
+            no_signal: paint.paint_type == PaintType::Camera,
         };
 
-        // Store the actual texture, get a view from it
-        let result_view = cached
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let result_view = cached.texture.create_view();
         cache.insert(paint.id, cached);
 
         result_view
     }
 
-    /// Create a GPU texture for a paint based on its type
-    fn create_texture_for_paint(&self, paint: &Paint, width: u32, height: u32) -> wgpu::Texture {
-        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
-            label: Some(&format!("paint_{}", paint.id)),
+    /// Upload a live frame to a paint's texture
+    ///
+    /// `rgba` holds tightly packed RGBA8 rows. The texture is recreated
+    /// when the frame size changes, e.g. when a camera switches resolution.
+    pub fn upload_frame(&self, paint_id: PaintId, width: u32, height: u32, rgba: &[u8]) {
+        if width == 0 || height == 0 || rgba.len() < width as usize * height as usize * 4 {
+            tracing::warn!(
+                "Ignoring live frame for paint {}: {} bytes for {}x{}",
+                paint_id,
+                rgba.len(),
+                width,
+                height
+            );
+            return;
+        }
+
+        let mut cache = self.cache.write();
+        let cached = cache.entry(paint_id).or_insert_with(|| {
+            self.create_rgba_entry(&format!("paint_{}_live", paint_id), width, height)
+        });
+        let reusable = matches!(cached.texture, PaintTexture::Rgba(_))
+            && cached.width == width
+            && cached.height == height;
+        if !reusable {
+            *cached = self.create_rgba_entry(&format!("paint_{}_live", paint_id), width, height);
+        }
+
+        cached.no_signal = false;
+        if let PaintTexture::Rgba(texture) = &cached.texture {
+            self.write_pixels(texture, width, height, rgba);
+        }
+    }
+
+    /// Upload a block-compressed video frame to a paint's texture
+    ///
+    /// The blocks are written to a BC texture without decoding them on the
+    /// CPU. Fails if the device has no BC texture support, see
+    /// [`Self::supports_compressed`].
+    pub fn upload_compressed_frame(
+        &self,
+        paint_id: PaintId,
+        format: BlockFormat,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> crate::Result<()> {
+        let uploader = self.compressed.as_ref().ok_or_else(|| {
+            crate::RenderError::DeviceError("BC texture compression is not supported".to_string())
+        })?;
+
+        let mut cache = self.cache.write();
+        let cached = cache
+            .entry(paint_id)
+            .or_insert_with(|| Self::create_compressed_entry(uploader, format, width, height));
+        let reusable = matches!(&cached.texture, PaintTexture::Compressed(texture)
+            if texture.format() == format && texture.size() == (width, height));
+        if !reusable {
+            *cached = Self::create_compressed_entry(uploader, format, width, height);
+        }
+
+        cached.no_signal = false;
+        if let PaintTexture::Compressed(texture) = &cached.texture {
+            uploader.upload(texture, data)?;
+        }
+        Ok(())
+    }
+
+    /// Replace a live paint's texture with the "no signal" slate
+    ///
+    /// The slate keeps the size of the last frame so mappings don't jump.
+    /// Does nothing if the slate is already shown.
+    pub fn show_no_signal(&self, paint: &Paint) {
+        let mut cache = self.cache.write();
+        let (width, height) = match cache.get(&paint.id) {
+            Some(cached) if cached.no_signal => return,
+            Some(cached) => (cached.width, cached.height),
+            None => (paint.dimensions.x as u32, paint.dimensions.y as u32),
+        };
+
+        let cached = cache.entry(paint.id).or_insert_with(|| {
+            self.create_rgba_entry(&format!("paint_{}", paint.id), width, height)
+        });
+        // The slate is drawn in RGBA, compressed textures are replaced
+        if !matches!(cached.texture, PaintTexture::Rgba(_)) {
+            *cached = self.create_rgba_entry(&format!("paint_{}", paint.id), width, height);
+        }
+        cached.no_signal = true;
+        let data = self.generate_no_signal_slate(width, height);
+        if let PaintTexture::Rgba(texture) = &cached.texture {
+            self.write_pixels(texture, width, height, &data);
+        }
+    }
+
+    /// Returns true if a live paint currently shows the "no signal" slate
+    pub fn is_no_signal(&self, paint_id: PaintId) -> bool {
+        self.cache
+            .read()
+            .get(&paint_id)
+            .is_some_and(|c| c.no_signal)
+    }
+
+    /// Create a cache entry with an RGBA texture of the given size
+    fn create_rgba_entry(&self, label: &str, width: u32, height: u32) -> CachedTexture {
+        let texture = self.create_texture(label, width, height);
+        CachedTexture {
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            texture: PaintTexture::Rgba(texture),
+            width,
+            height,
+            no_signal: false,
+        }
+    }
+
+    /// Create a cache entry with a BC texture of the given size
+    fn create_compressed_entry(
+        uploader: &CompressedTextureUploader,
+        format: BlockFormat,
+        width: u32,
+        height: u32,
+    ) -> CachedTexture {
+        let texture = uploader.create_texture(format, width, height);
+        CachedTexture {
+            view: texture.create_view(),
+            texture: PaintTexture::Compressed(texture),
+            width,
+            height,
+            no_signal: false,
+        }
+    }
+
+    /// Create a sampled RGBA texture that can be written from the CPU
+    fn create_texture(&self, label: &str, width: u32, height: u32) -> wgpu::Texture {
+        self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
             size: wgpu::Extent3d {
                 width,
                 height,
@@ -95,35 +239,19 @@ impl PaintTextureCache {
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
-        });
-
-        // Generate texture data based on paint type
-        let data = match paint.paint_type {
-            PaintType::Color => self.generate_solid_color(width, height, paint.color),
-            PaintType::TestPattern => self.generate_test_pattern(width, height),
-            PaintType::Image => {
-                // TODO: Load from source_path
-                self.generate_test_pattern(width, height)
-            }
-            PaintType::Video => {
-                // TODO: Load from video decoder
-                self.generate_test_pattern(width, height)
-            }
-            PaintType::Camera => {
-                // TODO: Load from camera capture
-                self.generate_solid_color(width, height, [0.2, 0.2, 0.2, 1.0])
-            }
-        };
+        })
+    }
 
-        // Upload to GPU
+    /// Write tightly packed RGBA rows to a whole texture
+    fn write_pixels(&self, texture: &wgpu::Texture, width: u32, height: u32, data: &[u8]) {
         self.queue.write_texture(
             wgpu::ImageCopyTexture {
-                texture: &texture,
+                texture,
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &data,
+            data,
             wgpu::ImageDataLayout {
                 offset: 0,
                 bytes_per_row: Some(width * 4),
@@ -135,6 +263,30 @@ impl PaintTextureCache {
                 depth_or_array_layers: 1,
             },
         );
+    }
+
+    /// Create a GPU texture for a paint based on its type
+    fn create_texture_for_paint(&self, paint: &Paint, width: u32, height: u32) -> wgpu::Texture {
+        let texture = self.create_texture(&format!("paint_{}", paint.id), width, height);
+
+        // Generate texture data based on paint type
+        let data = match paint.paint_type {
+            PaintType::Color => self.generate_solid_color(width, height, paint.color),
+            PaintType::TestPattern => self.generate_test_pattern(width, height),
+            PaintType::Image => {
+                // TODO: Load from source_path
+                self.generate_test_pattern(width, height)
+            }
+            PaintType::Video => {
+                // TODO: Load from video decoder
+                self.generate_test_pattern(width, height)
+            }
+            // Replaced by `upload_frame` once the live input delivers
+            PaintType::Camera => self.generate_no_signal_slate(width, height),
+        };
+
+        // Upload to GPU
+        self.write_pixels(&texture, width, height, &data);
 
         texture
     }
@@ -190,6 +342,42 @@ impl PaintTextureCache {
         data
     }
 
+    /// Generate the "no signal" slate for live paints without a picture
+    ///
+    /// Dark grey with diagonal hatching and dimmed color bars along the
+    /// bottom, so a dead feed is obvious on stage but not glaring.
+    fn generate_no_signal_slate(&self, width: u32, height: u32) -> Vec<u8> {
+        let pixel_count = (width * height) as usize;
+        let mut data = Vec::with_capacity(pixel_count * 4);
+
+        let bars: [[u8; 3]; 7] = [
+            [96, 96, 96],
+            [96, 96, 0],
+            [0, 96, 96],
+            [0, 96, 0],
+            [96, 0, 96],
+            [96, 0, 0],
+            [0, 0, 96],
+        ];
+        let bar_top = height - height / 8;
+        let bar_width = (width / 7).max(1);
+
+        for y in 0..height {
+            for x in 0..width {
+                let rgb = if y >= bar_top {
+                    bars[((x / bar_width) as usize).min(6)]
+                } else if (x + y) % 48 < 6 {
+                    [44, 44, 44]
+                } else {
+                    [28, 28, 28]
+                };
+                data.extend_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+            }
+        }
+
+        data
+    }
+
     /// Invalidate cache for a specific paint (call when paint changes)
     pub fn invalidate(&self, paint_id: PaintId) {
         self.cache.write().remove(&paint_id);
@@ -0,0 +1,226 @@
+//! Asynchronous GPU to CPU frame readback
+//!
+//! Copies a rendered texture into a small ring of staging buffers and maps
+//! them without stalling the render loop. Consumers such as pixel mapping
+//! get the most recent finished frame, typically one or two frames late.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+const FREE: u8 = 0;
+const COPIED: u8 = 1;
+const MAPPING: u8 = 2;
+const READY: u8 = 3;
+
+/// A frame read back from the GPU as tightly packed RGBA8
+#[derive(Debug, Clone)]
+pub struct ReadbackFrame {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+struct ReadbackSlot {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    bgra: bool,
+    state: Arc<AtomicU8>,
+    /// Order in which the copy was recorded
+    sequence: u64,
+}
+
+/// Reads rendered frames back to the CPU without blocking
+pub struct FrameReadback {
+    device: Arc<wgpu::Device>,
+    slots: Vec<ReadbackSlot>,
+    max_slots: usize,
+    /// Sequence number of the next copy
+    next_sequence: u64,
+    /// Sequence number of the last frame returned by `poll`
+    delivered: Option<u64>,
+}
+
+impl FrameReadback {
+    /// Create a readback ring with up to three frames in flight
+    pub fn new(device: Arc<wgpu::Device>) -> Self {
+        Self {
+            device,
+            slots: Vec::new(),
+            max_slots: 3,
+            next_sequence: 0,
+            delivered: None,
+        }
+    }
+
+    /// Record a copy of `texture` into a free staging buffer
+    ///
+    /// The texture must have `COPY_SRC` usage and a 4-byte RGBA or BGRA
+    /// format. If every buffer is still in flight the frame is skipped.
+    pub fn copy(&mut self, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) {
+        let width = texture.width();
+        let height = texture.height();
+        let bgra = matches!(
+            texture.format(),
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        );
+
+        let index = match self
+            .slots
+            .iter()
+            .position(|s| s.state.load(Ordering::Acquire) == FREE)
+        {
+            Some(index) => index,
+            None if self.slots.len() < self.max_slots => {
+                self.slots.push(self.create_slot(width, height));
+                self.slots.len() - 1
+            }
+            None => return,
+        };
+
+        if self.slots[index].width != width || self.slots[index].height != height {
+            self.slots[index] = self.create_slot(width, height);
+        }
+
+        let slot = &mut self.slots[index];
+        slot.bgra = bgra;
+        slot.sequence = self.next_sequence;
+        self.next_sequence += 1;
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &slot.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(slot.padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        slot.state.store(COPIED, Ordering::Release);
+    }
+
+    /// Start mapping the buffers copied this frame (call after `queue.submit`)
+    pub fn submitted(&mut self) {
+        for slot in &self.slots {
+            if slot.state.load(Ordering::Acquire) != COPIED {
+                continue;
+            }
+            slot.state.store(MAPPING, Ordering::Release);
+            let state = slot.state.clone();
+            slot.buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let next = if result.is_ok() { READY } else { FREE };
+                    state.store(next, Ordering::Release);
+                });
+        }
+    }
+
+    /// Collect the newest finished frame, if any completed since the last call
+    ///
+    /// Older finished frames are dropped without being copied, as are frames
+    /// that finish after a newer one was returned.
+    pub fn poll(&mut self) -> Option<ReadbackFrame> {
+        self.device.poll(wgpu::Maintain::Poll);
+
+        let ready: Vec<usize> = (0..self.slots.len())
+            .filter(|&i| self.slots[i].state.load(Ordering::Acquire) == READY)
+            .collect();
+        let newest = newest_unseen(
+            ready.iter().map(|&i| (i, self.slots[i].sequence)),
+            self.delivered,
+        );
+
+        let latest = newest.map(|index| {
+            let slot = &self.slots[index];
+            self.delivered = Some(slot.sequence);
+            Self::read_slot(slot)
+        });
+        for index in ready {
+            let slot = &self.slots[index];
+            slot.buffer.unmap();
+            slot.state.store(FREE, Ordering::Release);
+        }
+        latest
+    }
+
+    fn read_slot(slot: &ReadbackSlot) -> ReadbackFrame {
+        let row_size = slot.width as usize * 4;
+        let mut data = Vec::with_capacity(row_size * slot.height as usize);
+        {
+            let mapped = slot.buffer.slice(..).get_mapped_range();
+            for row in mapped.chunks(slot.padded_bytes_per_row as usize) {
+                data.extend_from_slice(&row[..row_size]);
+            }
+        }
+
+        if slot.bgra {
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        ReadbackFrame {
+            width: slot.width,
+            height: slot.height,
+            data,
+        }
+    }
+
+    fn create_slot(&self, width: u32, height: u32) -> ReadbackSlot {
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = (width * 4).div_ceil(alignment) * alignment;
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame Readback Buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        ReadbackSlot {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            bgra: false,
+            state: Arc::new(AtomicU8::new(FREE)),
+            sequence: 0,
+        }
+    }
+}
+
+/// Pick the slot with the highest sequence number that is newer than the
+/// last delivered frame
+fn newest_unseen(
+    ready: impl Iterator<Item = (usize, u64)>,
+    delivered: Option<u64>,
+) -> Option<usize> {
+    ready
+        .filter(|&(_, sequence)| delivered.map_or(true, |d| sequence > d))
+        .max_by_key(|&(_, sequence)| sequence)
+        .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_newest_unseen() {
+        // Slot order does not follow submission order
+        let ready = [(0, 7), (1, 5), (2, 6)];
+        assert_eq!(newest_unseen(ready.into_iter(), None), Some(0));
+        assert_eq!(newest_unseen(ready.into_iter(), Some(6)), Some(0));
+
+        // A frame finishing after a newer one was returned is stale
+        assert_eq!(newest_unseen([(1, 5)].into_iter(), Some(7)), None);
+        assert_eq!(newest_unseen(std::iter::empty(), None), None);
+    }
+}
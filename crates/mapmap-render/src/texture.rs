@@ -23,12 +23,15 @@ impl TextureHandle {
 
     /// Get texture size in bytes
     pub fn size_bytes(&self) -> u64 {
-        let bytes_per_pixel = match self.format {
-            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => 4,
-            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => 4,
-            _ => 4, // Default to 4 bytes
-        };
-        (self.width * self.height * bytes_per_pixel) as u64
+        let pixels = self.width as u64 * self.height as u64;
+        match self.format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => pixels * 4,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => pixels * 4,
+            // Blocks of 4x4 pixels in 8 or 16 bytes
+            wgpu::TextureFormat::Bc1RgbaUnorm | wgpu::TextureFormat::Bc1RgbaUnormSrgb => pixels / 2,
+            wgpu::TextureFormat::Bc3RgbaUnorm | wgpu::TextureFormat::Bc3RgbaUnormSrgb => pixels,
+            _ => pixels * 4, // Default to 4 bytes
+        }
     }
 }
 
@@ -123,6 +126,11 @@ impl TexturePool {
             .create_view()
     }
 
+    /// Get a texture by name.
+    pub fn get_texture(&self, name: &str) -> Option<Arc<wgpu::Texture>> {
+        self.textures.read().get(name).map(|h| h.texture.clone())
+    }
+
     /// Resize a texture if its dimensions have changed.
     pub fn resize_if_needed(&self, name: &str, new_width: u32, new_height: u32) {
         let mut textures = self.textures.write();
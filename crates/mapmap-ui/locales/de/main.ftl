@@ -31,6 +31,8 @@ dashboard-add-widget-tooltip = Neues Steuerelement hinzufügen
 dashboard-state = Status:
 dashboard-speed = Tempo:
 dashboard-loop = Wiederholen
+dashboard-beat-sync = Beat-Sync
+dashboard-clip-beats = Beats:
 dashboard-audio-analysis = Audio-Steuerung
 dashboard-device = Eingabegerät
 dashboard-no-device = Kein Gerät
@@ -41,6 +43,10 @@ dashboard-remove-widget = Entfernen
 dashboard-trigger = Trigger
 dashboard-rms = Pegel (RMS)
 dashboard-peak = Spitze
+dashboard-decode-section = Vorausdekodierung
+dashboard-decode-queue = Puffer
+dashboard-decode-time = Dekodierzeit
+dashboard-dropped-frames = Verworfene Frames
 dashboard-toggle-audio = Audio-Panel umschalten
 dashboard-open-audio-panel = 🔊 Audio-Einstellungen öffnen
 dashboard-audio-section = Audio-Input
@@ -187,6 +193,8 @@ label-speed = Geschwindigkeit
 label-mode = Modus:
 mode-loop = Schleife
 mode-play-once = Einmal abspielen
+mode-ping-pong = Ping-Pong
+mode-reverse = Rückwärts
 
 # Performance
 panel-performance = Leistung
@@ -377,6 +385,9 @@ audio-panel-title = Audio-Einstellungen
 audio-panel-settings = Signalverarbeitung
 audio-panel-device = Eingabegerät
 audio-panel-no-device = Kein Gerät
+audio-panel-source = Analysequelle
+audio-panel-source-input = Audio-Eingang
+audio-panel-source-video = Clip-Tonspur
 audio-panel-no-data = Kein Audiosignal
 audio-panel-rms = Pegel
 audio-panel-beat = Beat
@@ -418,3 +429,32 @@ settings-frame-rate = Bildrate
 settings-app = App-Einstellungen
 settings-language = Sprache
 settings-logging = Protokollierung
+settings-timecode = Timecode
+settings-timecode-source = Quelle
+settings-ltc-rate = LTC-Bildrate
+
+# Keyboard Shortcuts
+panel-shortcuts = Tastenkürzel
+hint-search-shortcuts = Tastenkürzel suchen...
+label-context = Kontext:
+filter-all = Alle
+btn-reset-all = Alle zurücksetzen
+btn-cancel = Abbrechen
+msg-recording = Neues Tastenkürzel drücken für
+col-action = Aktion
+col-shortcut = Tastenkürzel
+col-context = Kontext
+col-actions = Aktionen
+label-inherited = geerbt
+tip-edit-shortcut = Neues Tastenkürzel aufnehmen
+tip-reset-shortcut = Auf Standard zurücksetzen
+context-global = Global
+context-main-window = Hauptfenster
+context-output-window = Ausgabefenster
+context-editor = Editor
+context-timeline = Zeitleiste
+context-layer-panel = Ebenen-Panel
+shortcut-also-bound = Hier auch belegt mit { $others }
+shortcut-never-fires = Wird nie ausgelöst: { $others } ist zuerst belegt
+shortcut-overrides = Überschreibt { $others } in { $context }
+shortcut-overridden = Überschrieben von { $others } in { $context }
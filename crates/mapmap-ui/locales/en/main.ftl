@@ -31,6 +31,8 @@ dashboard-add-widget-tooltip = Add a new widget to the dashboard
 dashboard-state = State:
 dashboard-speed = Speed:
 dashboard-loop = Loop
+dashboard-beat-sync = Beat Sync
+dashboard-clip-beats = Beats:
 dashboard-audio-analysis = Audio Analysis
 dashboard-device = Device
 dashboard-no-device = No device selected
@@ -41,6 +43,10 @@ dashboard-remove-widget = Remove widget
 dashboard-trigger = Trigger
 dashboard-rms = RMS
 dashboard-peak = Peak
+dashboard-decode-section = Decode-Ahead
+dashboard-decode-queue = Queue
+dashboard-decode-time = Decode time
+dashboard-dropped-frames = Dropped frames
 
 media-browser-title = Media Browser
 media-browser-back = Back
@@ -171,6 +177,8 @@ label-speed = Speed
 label-mode = Mode:
 mode-loop = Loop
 mode-play-once = Play Once
+mode-ping-pong = Ping-Pong
+mode-reverse = Reverse
 
 # Performance
 panel-performance = Performance
@@ -361,6 +369,9 @@ audio-spectrum = Frequency Spectrum
 audio-panel-title = Audio Analysis
 audio-panel-device = Input Device
 audio-panel-no-device = No device
+audio-panel-source = Analyzed Source
+audio-panel-source-input = Audio Input
+audio-panel-source-video = Clip Soundtrack
 audio-panel-no-data = Waiting for audio data...
 audio-panel-rms = RMS Volume
 audio-panel-beat = Beat
@@ -402,3 +413,32 @@ settings-frame-rate = Frame Rate
 settings-app = App Settings
 settings-language = Language
 settings-logging = Logging
+settings-timecode = Timecode
+settings-timecode-source = Source
+settings-ltc-rate = LTC Frame Rate
+
+# Keyboard Shortcuts
+panel-shortcuts = Keyboard Shortcuts
+hint-search-shortcuts = Search shortcuts...
+label-context = Context:
+filter-all = All
+btn-reset-all = Reset All
+btn-cancel = Cancel
+msg-recording = Press the new shortcut for
+col-action = Action
+col-shortcut = Shortcut
+col-context = Context
+col-actions = Actions
+label-inherited = inherited
+tip-edit-shortcut = Record a new shortcut
+tip-reset-shortcut = Reset to default
+context-global = Global
+context-main-window = Main Window
+context-output-window = Output Window
+context-editor = Editor
+context-timeline = Timeline
+context-layer-panel = Layer Panel
+shortcut-also-bound = Also bound to { $others } here
+shortcut-never-fires = Never fires: { $others } is bound first
+shortcut-overrides = Overrides { $others } in { $context }
+shortcut-overridden = Overridden by { $others } in { $context }
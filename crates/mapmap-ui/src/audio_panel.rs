@@ -5,7 +5,7 @@
 
 use crate::i18n::LocaleManager;
 use egui::{Color32, Pos2, Rect, Sense, Stroke, Ui, Vec2};
-use mapmap_core::audio::{AudioAnalysis, AudioConfig};
+use mapmap_core::audio::{AudioAnalysis, AudioConfig, AudioSource};
 use std::time::Instant;
 
 const PEAK_DECAY_RATE: f32 = 0.5; // units per second
@@ -24,6 +24,8 @@ enum ViewMode {
 pub enum AudioPanelAction {
     DeviceChanged(String),
     ConfigChanged(AudioConfig),
+    /// Analyze a different source, e.g. the soundtrack of the playing clip
+    SourceChanged(AudioSource),
 }
 
 /// Audio visualization panel widget
@@ -38,6 +40,8 @@ pub struct AudioPanel {
     view_mode: ViewMode,
     /// Local configuration state for sliders (to avoid jumpiness)
     local_config: Option<AudioConfig>,
+    /// Source of the analyzed audio
+    source: AudioSource,
 }
 
 impl Default for AudioPanel {
@@ -48,6 +52,7 @@ impl Default for AudioPanel {
             last_beat_time: Instant::now(),
             view_mode: ViewMode::Spectrum,
             local_config: None,
+            source: AudioSource::SystemInput,
         }
     }
 }
@@ -97,6 +102,24 @@ impl AudioPanel {
                 }
             });
 
+        // --- Analyzed Source ---
+        let source_text = |source| match source {
+            AudioSource::VideoAudio => locale.t("audio-panel-source-video"),
+            _ => locale.t("audio-panel-source-input"),
+        };
+        egui::ComboBox::from_label(locale.t("audio-panel-source"))
+            .selected_text(source_text(self.source))
+            .show_ui(ui, |ui| {
+                for source in [AudioSource::SystemInput, AudioSource::VideoAudio] {
+                    if ui
+                        .selectable_value(&mut self.source, source, source_text(source))
+                        .changed()
+                    {
+                        action = Some(AudioPanelAction::SourceChanged(source));
+                    }
+                }
+            });
+
         ui.separator();
 
         // --- Settings (Gain, Gate, Smoothing) ---
@@ -3,6 +3,7 @@
 //! Handles saving and loading user preferences including language settings.
 
 use crate::theme::ThemeConfig;
+use mapmap_core::timecode::TimecodeRate;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fs;
@@ -25,6 +26,25 @@ impl fmt::Display for AudioMeterStyle {
     }
 }
 
+/// Where chased timecode comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum TimecodeInput {
+    /// MIDI timecode from the MIDI input
+    #[default]
+    Mtc,
+    /// Linear timecode decoded from the audio input
+    Ltc,
+}
+
+impl fmt::Display for TimecodeInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mtc => write!(f, "MTC (MIDI)"),
+            Self::Ltc => write!(f, "LTC (Audio)"),
+        }
+    }
+}
+
 /// User configuration settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserConfig {
@@ -45,6 +65,45 @@ pub struct UserConfig {
     /// Audio meter style
     #[serde(default)]
     pub meter_style: AudioMeterStyle,
+    /// Port of the web API and output previews, disabled if unset
+    #[serde(default)]
+    pub web_port: Option<u16>,
+    /// Address the web API binds to; any but loopback requires an API key
+    #[serde(default = "default_web_host")]
+    pub web_host: String,
+    /// API key required by the web API, open to local clients if unset
+    #[serde(default)]
+    pub web_api_key: Option<String>,
+    /// Previews served by the web API, `"composition"` or output ids
+    #[serde(default = "default_preview_targets")]
+    pub preview_targets: Vec<String>,
+    /// Source of the timecode cues chase
+    #[serde(default)]
+    pub timecode_input: TimecodeInput,
+    /// Frame rate of LTC on the audio input
+    #[serde(default = "default_ltc_rate")]
+    pub ltc_rate: TimecodeRate,
+    /// Art-Net destination of the DMX output, disabled if unset
+    #[serde(default)]
+    pub artnet_target: Option<String>,
+    /// sACN source name of the DMX output, disabled if unset
+    #[serde(default)]
+    pub sacn_source_name: Option<String>,
+    /// DMX universes sent besides the pixel-mapped ones
+    #[serde(default)]
+    pub dmx_universes: Vec<u16>,
+}
+
+fn default_web_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_ltc_rate() -> TimecodeRate {
+    TimecodeRate::FPS_30
+}
+
+fn default_preview_targets() -> Vec<String> {
+    vec!["composition".to_string()]
 }
 
 impl Default for UserConfig {
@@ -56,6 +115,15 @@ impl Default for UserConfig {
             theme: ThemeConfig::default(),
             target_fps: Some(60.0),
             meter_style: AudioMeterStyle::default(),
+            web_port: None,
+            web_host: default_web_host(),
+            web_api_key: None,
+            preview_targets: default_preview_targets(),
+            timecode_input: TimecodeInput::default(),
+            ltc_rate: default_ltc_rate(),
+            artnet_target: None,
+            sacn_source_name: None,
+            dmx_universes: Vec::new(),
         }
     }
 }
@@ -139,6 +207,15 @@ mod tests {
             theme: ThemeConfig::default(),
             target_fps: Some(60.0),
             meter_style: AudioMeterStyle::Digital,
+            web_port: Some(8080),
+            web_host: "0.0.0.0".to_string(),
+            web_api_key: Some("secret".to_string()),
+            preview_targets: vec!["composition".to_string(), "2".to_string()],
+            timecode_input: TimecodeInput::Ltc,
+            ltc_rate: TimecodeRate::FPS_25,
+            artnet_target: Some("2.0.0.10:6454".to_string()),
+            sacn_source_name: None,
+            dmx_universes: vec![0, 1],
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -147,5 +224,23 @@ mod tests {
         assert_eq!(loaded.language, "de");
         assert_eq!(loaded.recent_files.len(), 2);
         assert_eq!(loaded.meter_style, AudioMeterStyle::Digital);
+        assert_eq!(loaded.web_port, Some(8080));
+        assert_eq!(loaded.web_host, "0.0.0.0");
+        assert_eq!(loaded.web_api_key.as_deref(), Some("secret"));
+        assert_eq!(loaded.preview_targets, config.preview_targets);
+        assert_eq!(loaded.artnet_target, config.artnet_target);
+        assert_eq!(loaded.dmx_universes, vec![0, 1]);
+        assert_eq!(loaded.timecode_input, TimecodeInput::Ltc);
+        assert_eq!(loaded.ltc_rate, TimecodeRate::FPS_25);
+    }
+
+    #[test]
+    fn test_older_config_previews_composition() {
+        let loaded: UserConfig = serde_json::from_str(r#"{"language":"en"}"#).unwrap();
+        assert_eq!(loaded.web_port, None);
+        assert_eq!(loaded.web_host, "127.0.0.1");
+        assert_eq!(loaded.web_api_key, None);
+        assert_eq!(loaded.preview_targets, vec!["composition"]);
+        assert_eq!(loaded.timecode_input, TimecodeInput::Mtc);
     }
 }
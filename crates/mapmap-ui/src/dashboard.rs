@@ -5,7 +5,7 @@
 use crate::i18n::LocaleManager;
 use egui::Ui;
 use mapmap_core::AudioAnalysis;
-use mapmap_media::{LoopMode, PlaybackCommand, PlaybackState};
+use mapmap_media::{BeatSync, LoopMode, PipelineStats, PlaybackCommand, PlaybackState};
 use std::time::Duration;
 
 /// Dashboard control panel
@@ -22,8 +22,14 @@ pub struct Dashboard {
     speed: f32,
     /// Loop mode
     loop_mode: LoopMode,
+    /// Is the speed locked to the tempo?
+    beat_sync: bool,
+    /// Clip length in beats while beat-synced
+    clip_beats: f32,
     /// Latest audio analysis
     audio_analysis: Option<AudioAnalysis>,
+    /// Decode-ahead statistics of the media player
+    pipeline_stats: Option<PipelineStats>,
     /// Available audio devices
     audio_devices: Vec<String>,
     /// Selected audio device
@@ -45,7 +51,10 @@ impl Dashboard {
             duration: Duration::ZERO,
             speed: 1.0,
             loop_mode: LoopMode::Loop,
+            beat_sync: false,
+            clip_beats: 4.0,
             audio_analysis: None,
+            pipeline_stats: None,
             audio_devices: Vec::new(),
             selected_audio_device: None,
         }
@@ -67,6 +76,11 @@ impl Dashboard {
         self.audio_analysis = Some(analysis);
     }
 
+    /// Update the decode-ahead statistics (`None` hides them)
+    pub fn set_pipeline_stats(&mut self, stats: Option<PipelineStats>) {
+        self.pipeline_stats = stats;
+    }
+
     /// Update the list of available audio devices
     pub fn set_audio_devices(&mut self, devices: Vec<String>) {
         self.audio_devices = devices;
@@ -172,8 +186,10 @@ impl Dashboard {
             // Speed and loop controls
             ui.horizontal(|ui| {
                 ui.label(locale.t("dashboard-speed"));
+                // The tempo sets the speed while beat-synced
                 if ui
-                    .add(
+                    .add_enabled(
+                        !self.beat_sync,
                         egui::Slider::new(&mut self.speed, 0.1..=4.0)
                             .logarithmic(true)
                             .show_value(true),
@@ -187,7 +203,8 @@ impl Dashboard {
                 }
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    let mut looping = self.loop_mode == LoopMode::Loop;
+                    // Ping-pong and reverse also repeat
+                    let mut looping = self.loop_mode != LoopMode::PlayOnce;
                     if ui
                         .checkbox(&mut looping, locale.t("dashboard-loop"))
                         .changed()
@@ -204,8 +221,69 @@ impl Dashboard {
                     }
                 });
             });
+
+            // Beat sync
+            ui.horizontal(|ui| {
+                let mut changed = ui
+                    .checkbox(&mut self.beat_sync, locale.t("dashboard-beat-sync"))
+                    .changed();
+                ui.label(locale.t("dashboard-clip-beats"));
+                changed |= ui
+                    .add_enabled(
+                        self.beat_sync,
+                        egui::DragValue::new(&mut self.clip_beats)
+                            .speed(0.25)
+                            .clamp_range(0.25..=256.0),
+                    )
+                    .changed();
+                if changed {
+                    let sync = self.beat_sync.then(|| BeatSync::new(self.clip_beats));
+                    action = Some(DashboardAction::SendCommand(PlaybackCommand::SetBeatSync(
+                        sync,
+                    )));
+                }
+
+                let tempo = self
+                    .audio_analysis
+                    .as_ref()
+                    .and_then(|analysis| analysis.tempo_bpm);
+                if let Some(bpm) = tempo {
+                    ui.label(format!("{:.1} BPM", bpm));
+                }
+            });
         });
 
+        // Decode-ahead statistics
+        if let Some(stats) = self.pipeline_stats {
+            ui.add_space(8.0);
+            ui.group(|ui| {
+                ui.label(locale.t("dashboard-decode-section"));
+                egui::Grid::new("dashboard_pipeline_stats")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label(locale.t("dashboard-decode-queue"));
+                        ui.add(
+                            egui::ProgressBar::new(
+                                stats.queued_frames as f32 / stats.queue_depth.max(1) as f32,
+                            )
+                            .text(format!("{}/{}", stats.queued_frames, stats.queue_depth)),
+                        );
+                        ui.end_row();
+
+                        ui.label(locale.t("dashboard-decode-time"));
+                        ui.label(format!("{:.1} ms", stats.decode_time_ms));
+                        ui.end_row();
+
+                        ui.label(locale.t("dashboard-dropped-frames"));
+                        ui.label(format!(
+                            "{} / {}",
+                            stats.dropped_frames, stats.decoded_frames
+                        ));
+                        ui.end_row();
+                    });
+            });
+        }
+
         ui.add_space(8.0);
 
         // Audio controls
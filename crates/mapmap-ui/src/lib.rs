@@ -409,29 +409,22 @@ impl AppUI {
                     .selected_text(match self.loop_mode {
                         mapmap_media::LoopMode::Loop => self.i18n.t("mode-loop"),
                         mapmap_media::LoopMode::PlayOnce => self.i18n.t("mode-play-once"),
+                        mapmap_media::LoopMode::PingPong => self.i18n.t("mode-ping-pong"),
+                        mapmap_media::LoopMode::Reverse => self.i18n.t("mode-reverse"),
                     })
                     .show_ui(ui, |ui| {
-                        if ui
-                            .selectable_value(
-                                &mut self.loop_mode,
-                                mapmap_media::LoopMode::Loop,
-                                self.i18n.t("mode-loop"),
-                            )
-                            .clicked()
-                        {
-                            self.actions
-                                .push(UIAction::SetLoopMode(mapmap_media::LoopMode::Loop));
-                        }
-                        if ui
-                            .selectable_value(
-                                &mut self.loop_mode,
-                                mapmap_media::LoopMode::PlayOnce,
-                                self.i18n.t("mode-play-once"),
-                            )
-                            .clicked()
-                        {
-                            self.actions
-                                .push(UIAction::SetLoopMode(mapmap_media::LoopMode::PlayOnce));
+                        for (mode, key) in [
+                            (mapmap_media::LoopMode::Loop, "mode-loop"),
+                            (mapmap_media::LoopMode::PlayOnce, "mode-play-once"),
+                            (mapmap_media::LoopMode::PingPong, "mode-ping-pong"),
+                            (mapmap_media::LoopMode::Reverse, "mode-reverse"),
+                        ] {
+                            if ui
+                                .selectable_value(&mut self.loop_mode, mode, self.i18n.t(key))
+                                .clicked()
+                            {
+                                self.actions.push(UIAction::SetLoopMode(mode));
+                            }
                         }
                     });
             });
@@ -16,10 +16,12 @@ struct SocketInfo {
     position: Pos2,
 }
 
+/// Node editor for modules
+///
+/// The edited module is the [`ModuleManager`]'s active module, so recalling
+/// a cue switches the canvas too.
 #[allow(dead_code)]
 pub struct ModuleCanvas {
-    /// The ID of the currently active/edited module
-    active_module_id: Option<u64>,
     /// Canvas pan offset
     pan_offset: Vec2,
     /// Canvas zoom level
@@ -107,7 +109,6 @@ pub enum CanvasAction {
 impl Default for ModuleCanvas {
     fn default() -> Self {
         Self {
-            active_module_id: None,
             pan_offset: Vec2::ZERO,
             zoom: 1.0,
             dragging_part: None,
@@ -197,19 +198,9 @@ impl ModuleCanvas {
         ))
     }
 
-    /// Set the active module ID
-    pub fn set_active_module(&mut self, module_id: Option<u64>) {
-        self.active_module_id = module_id;
-    }
-
-    /// Get the active module ID
-    pub fn active_module_id(&self) -> Option<u64> {
-        self.active_module_id
-    }
-
     /// Add a Trigger node with specified type
     fn add_trigger_node(&mut self, manager: &mut ModuleManager, trigger_type: TriggerType) {
-        if let Some(id) = self.active_module_id {
+        if let Some(id) = manager.active_module() {
             if let Some(module) = manager.get_module_mut(id) {
                 let pos = Self::find_free_position(&module.parts, (100.0, 100.0));
                 module.add_part_with_type(
@@ -222,7 +213,7 @@ impl ModuleCanvas {
 
     /// Add a Source node with specified type
     fn add_source_node(&mut self, manager: &mut ModuleManager, source_type: SourceType) {
-        if let Some(id) = self.active_module_id {
+        if let Some(id) = manager.active_module() {
             if let Some(module) = manager.get_module_mut(id) {
                 let pos = Self::find_free_position(&module.parts, (200.0, 100.0));
                 module.add_part_with_type(
@@ -235,7 +226,7 @@ impl ModuleCanvas {
 
     /// Add a Mask node with specified type
     fn add_mask_node(&mut self, manager: &mut ModuleManager, mask_type: MaskType) {
-        if let Some(id) = self.active_module_id {
+        if let Some(id) = manager.active_module() {
             if let Some(module) = manager.get_module_mut(id) {
                 let pos = Self::find_free_position(&module.parts, (300.0, 100.0));
                 module.add_part_with_type(
@@ -248,7 +239,7 @@ impl ModuleCanvas {
 
     /// Add a Modulator node with specified type
     fn add_modulator_node(&mut self, manager: &mut ModuleManager, mod_type: ModulizerType) {
-        if let Some(id) = self.active_module_id {
+        if let Some(id) = manager.active_module() {
             if let Some(module) = manager.get_module_mut(id) {
                 let pos = Self::find_free_position(&module.parts, (400.0, 100.0));
                 module.add_part_with_type(
@@ -261,7 +252,7 @@ impl ModuleCanvas {
 
     /// Add a Layer node with specified type
     fn add_layer_node(&mut self, manager: &mut ModuleManager, layer_type: LayerAssignmentType) {
-        if let Some(id) = self.active_module_id {
+        if let Some(id) = manager.active_module() {
             if let Some(module) = manager.get_module_mut(id) {
                 let pos = Self::find_free_position(&module.parts, (500.0, 100.0));
                 module.add_part_with_type(
@@ -274,7 +265,7 @@ impl ModuleCanvas {
 
     /// Add a Mesh node with specified type
     fn add_mesh_node(&mut self, manager: &mut ModuleManager, mesh_type: MeshType) {
-        if let Some(id) = self.active_module_id {
+        if let Some(id) = manager.active_module() {
             if let Some(module) = manager.get_module_mut(id) {
                 let pos = Self::find_free_position(&module.parts, (450.0, 100.0));
                 module.add_part_with_type(
@@ -287,7 +278,7 @@ impl ModuleCanvas {
 
     /// Add an Output node with specified type
     fn add_output_node(&mut self, manager: &mut ModuleManager, output_type: OutputType) {
-        if let Some(id) = self.active_module_id {
+        if let Some(id) = manager.active_module() {
             if let Some(module) = manager.get_module_mut(id) {
                 let pos = Self::find_free_position(&module.parts, (600.0, 100.0));
                 module.add_part_with_type(
@@ -310,13 +301,13 @@ impl ModuleCanvas {
                 .clicked()
             {
                 let new_module_id = manager.create_module("New Module".to_string());
-                self.active_module_id = Some(new_module_id);
+                manager.set_active_module(Some(new_module_id));
             }
 
             ui.separator();
 
             // Part creation tools (only enabled when module is active)
-            let has_module = self.active_module_id.is_some();
+            let has_module = manager.active_module().is_some();
 
             ui.add_enabled_ui(has_module, |ui| {
                 // === SIGNAL FLOW ORDER: Trigger → Source → Mask → Modulator → Layer → Output ===
@@ -532,8 +523,8 @@ impl ModuleCanvas {
                 .iter()
                 .map(|m| (m.id, m.name.clone()))
                 .collect();
-            let current_name = self
-                .active_module_id
+            let mut active_module_id = manager.active_module();
+            let current_name = active_module_id
                 .and_then(|id| manager.get_module_mut(id))
                 .map(|m| m.name.clone())
                 .unwrap_or_else(|| "None".to_string());
@@ -541,11 +532,12 @@ impl ModuleCanvas {
             egui::ComboBox::from_id_source("module_selector")
                 .selected_text(current_name)
                 .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut self.active_module_id, None, "None");
+                    ui.selectable_value(&mut active_module_id, None, "None");
                     for (id, name) in module_names {
-                        ui.selectable_value(&mut self.active_module_id, Some(id), name);
+                        ui.selectable_value(&mut active_module_id, Some(id), name);
                     }
                 });
+            manager.set_active_module(active_module_id);
 
             ui.separator();
 
@@ -583,7 +575,7 @@ impl ModuleCanvas {
             }
 
             // === MODULE MANAGEMENT (only when module selected) ===
-            if let Some(module_id) = self.active_module_id {
+            if let Some(module_id) = manager.active_module() {
                 ui.separator();
 
                 // Get module for editing
@@ -635,7 +627,6 @@ impl ModuleCanvas {
                 // Delete module button
                 if ui.button("🗑").on_hover_text("Delete this module").clicked() {
                     manager.delete_module(module_id);
-                    self.active_module_id = None;
                 }
 
                 ui.separator();
@@ -672,8 +663,8 @@ impl ModuleCanvas {
         ui.separator();
 
         // Find the active module
-        let active_module = self
-            .active_module_id
+        let active_module = manager
+            .active_module()
             .and_then(|id| manager.get_module_mut(id));
 
         if let Some(module) = active_module {
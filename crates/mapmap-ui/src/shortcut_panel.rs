@@ -3,7 +3,10 @@
 //! UI Panel for viewing and customizing keyboard shortcuts.
 
 use egui::{self, Key as EguiKey, RichText, ScrollArea};
-use mapmap_control::shortcuts::{DefaultShortcuts, Key, Modifiers, Shortcut, ShortcutContext};
+use mapmap_control::shortcuts::{
+    conflicts, ConflictKind, DefaultShortcuts, Key, Modifiers, Shortcut, ShortcutConflict,
+    ShortcutContext,
+};
 
 use crate::i18n::LocaleManager;
 
@@ -29,7 +32,7 @@ pub struct ShortcutPanel {
     search_filter: String,
     /// Currently recording shortcut for this index
     recording_index: Option<usize>,
-    /// Filter by context
+    /// Filter by context; shows the bindings resolved in that context
     context_filter: Option<ShortcutContext>,
     /// Initialized flag
     initialized: bool,
@@ -106,7 +109,7 @@ impl ShortcutPanel {
             egui::ComboBox::from_id_source("context_filter")
                 .selected_text(match &self.context_filter {
                     None => i18n.t("filter-all"),
-                    Some(ctx) => context_display_name(ctx).to_string(),
+                    Some(ctx) => context_display_name(i18n, ctx),
                 })
                 .show_ui(ui, |ui| {
                     if ui
@@ -115,16 +118,11 @@ impl ShortcutPanel {
                     {
                         self.context_filter = None;
                     }
-                    for ctx in [
-                        ShortcutContext::Global,
-                        ShortcutContext::MainWindow,
-                        ShortcutContext::Editor,
-                        ShortcutContext::Timeline,
-                    ] {
+                    for ctx in ShortcutContext::ALL {
                         if ui
                             .selectable_label(
                                 self.context_filter == Some(ctx),
-                                context_display_name(&ctx),
+                                context_display_name(i18n, &ctx),
                             )
                             .clicked()
                         {
@@ -194,7 +192,15 @@ impl ShortcutPanel {
                     let mut pending_record: Option<usize> = None;
                     let mut pending_reset: Option<usize> = None;
 
-                    for (idx, shortcut) in self.shortcuts.iter().enumerate() {
+                    // With a context selected, show what each key does there
+                    let visible: Vec<usize> = match self.context_filter {
+                        Some(ctx) => conflicts::resolved_bindings(&self.shortcuts, ctx),
+                        None => (0..self.shortcuts.len()).collect(),
+                    };
+                    let all_conflicts = conflicts::find_conflicts(&self.shortcuts);
+
+                    for idx in visible {
+                        let shortcut = &self.shortcuts[idx];
                         // Apply search filter
                         if !self.search_filter.is_empty()
                             && !shortcut.description.to_lowercase().contains(&search_lower)
@@ -202,15 +208,22 @@ impl ShortcutPanel {
                             continue;
                         }
 
-                        if let Some(ref ctx_filter) = self.context_filter {
-                            if shortcut.context != *ctx_filter {
-                                continue;
-                            }
+                        // Action description, flagged if the binding collides
+                        let problems: Vec<&ShortcutConflict> = all_conflicts
+                            .iter()
+                            .filter(|c| c.kind != ConflictKind::Redundant)
+                            .filter(|c| c.shortcuts.contains(&idx))
+                            .collect();
+                        if problems.is_empty() {
+                            ui.label(&shortcut.description);
+                        } else {
+                            ui.label(
+                                RichText::new(format!("⚠ {}", shortcut.description))
+                                    .color(egui::Color32::from_rgb(255, 170, 0)),
+                            )
+                            .on_hover_text(conflict_summary(i18n, &self.shortcuts, idx, &problems));
                         }
 
-                        // Action description
-                        ui.label(&shortcut.description);
-
                         // Shortcut key combination
                         let shortcut_str = shortcut.to_shortcut_string();
                         let is_recording = self.recording_index == Some(idx);
@@ -222,7 +235,21 @@ impl ShortcutPanel {
                         }
 
                         // Context
-                        ui.label(context_display_name(&shortcut.context));
+                        match self.context_filter {
+                            Some(ctx) if ctx != shortcut.context => {
+                                ui.label(
+                                    RichText::new(format!(
+                                        "{} ({})",
+                                        context_display_name(i18n, &shortcut.context),
+                                        i18n.t("label-inherited")
+                                    ))
+                                    .weak(),
+                                );
+                            }
+                            _ => {
+                                ui.label(context_display_name(i18n, &shortcut.context));
+                            }
+                        }
 
                         // Actions
                         ui.horizontal(|ui| {
@@ -362,16 +389,47 @@ fn egui_key_to_key(egui_key: EguiKey) -> Option<Key> {
     })
 }
 
+/// Describe the conflicts a shortcut is involved in
+fn conflict_summary(
+    i18n: &LocaleManager,
+    shortcuts: &[Shortcut],
+    idx: usize,
+    conflicts: &[&ShortcutConflict],
+) -> String {
+    conflicts
+        .iter()
+        .map(|conflict| {
+            let others: Vec<&str> = conflict
+                .shortcuts
+                .iter()
+                .filter(|&&i| i != idx)
+                .map(|&i| shortcuts[i].description.as_str())
+                .collect();
+            let others = others.join(", ");
+            let context = context_display_name(i18n, &conflict.context);
+            let wins = conflict.shortcuts.first() == Some(&idx);
+            let key = match (conflict.kind, wins) {
+                (ConflictKind::Ambiguous, true) => "shortcut-also-bound",
+                (ConflictKind::Ambiguous, false) => "shortcut-never-fires",
+                (_, true) => "shortcut-overrides",
+                (_, false) => "shortcut-overridden",
+            };
+            i18n.t_args(key, &[("others", &others), ("context", &context)])
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// Get display name for a shortcut context
-fn context_display_name(ctx: &ShortcutContext) -> &'static str {
-    match ctx {
-        ShortcutContext::Global => "Global",
-        ShortcutContext::MainWindow => "Main Window",
-        ShortcutContext::OutputWindow => "Output Window",
-        ShortcutContext::Editor => "Editor",
-        ShortcutContext::Timeline => "Timeline",
-        ShortcutContext::LayerPanel => "Layer Panel",
-    }
+fn context_display_name(i18n: &LocaleManager, ctx: &ShortcutContext) -> String {
+    i18n.t(match ctx {
+        ShortcutContext::Global => "context-global",
+        ShortcutContext::MainWindow => "context-main-window",
+        ShortcutContext::OutputWindow => "context-output-window",
+        ShortcutContext::Editor => "context-editor",
+        ShortcutContext::Timeline => "context-timeline",
+        ShortcutContext::LayerPanel => "context-layer-panel",
+    })
 }
 
 #[cfg(test)]
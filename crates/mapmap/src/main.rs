@@ -14,20 +14,39 @@ use mapmap_control::midi::MidiInputHandler;
 use mapmap_control::{shortcuts::Action, ControlManager};
 use mapmap_core::{
     audio::{backend::cpal_backend::CpalBackend, backend::AudioBackend, AudioAnalyzer},
-    AppState, OutputId,
+    audio_media_pipeline::AudioPipelineConfig,
+    AppState, AudioAnalysis, AudioConfig, AudioMediaPipeline, AudioSource, OutputId, PaintId,
+    PaintType,
+};
+use mapmap_media::{
+    AudioTrack, CpalAudioOutput, LoopMode, MediaError, PlaybackCommand, VideoPlayer,
 };
 
 use mapmap_mcp::{McpAction, McpServer};
 // Define McpAction locally or import if we move it to core later -> Removed local definition
 
 use crossbeam_channel::{unbounded, Receiver};
-use mapmap_io::{load_project, save_project};
+use mapmap_control::dmx::PixelFrame;
+#[cfg(feature = "http-api")]
+use mapmap_control::{
+    web::{AuthConfig, PreviewTarget},
+    WebServer, WebServerConfig,
+};
+use mapmap_io::{
+    load_project, save_project, ExternalClock, LiveInputManager, NetworkSource, OutputPacer,
+    PacedFrame, PixelFormat, SignalState, SourceUrl, TimecodeRate, VideoFormat, VideoFrame,
+    VideoSink,
+};
+use mapmap_render::paint_texture_cache::PaintTextureCache;
 use mapmap_render::{
-    Compositor, EffectChainRenderer, MeshRenderer, OscillatorRenderer, QuadRenderer, TexturePool,
-    WgpuBackend,
+    BlockFormat, Compositor, EffectChainRenderer, FrameReadback, MeshRenderer, OscillatorRenderer,
+    QuadRenderer, ReadbackFrame, TexturePool, WgpuBackend,
+};
+use mapmap_ui::{
+    config::TimecodeInput, menu_bar, stereo_audio_meter::StereoAudioMeter, AppUI, EdgeBlendAction,
 };
-use mapmap_ui::{menu_bar, stereo_audio_meter::StereoAudioMeter, AppUI, EdgeBlendAction};
 use rfd::FileDialog;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::thread;
 use tracing::{error, info};
@@ -37,6 +56,36 @@ use winit::{
     event_loop::EventLoop,
 };
 
+/// Player of a video paint.
+struct PaintPlayer {
+    /// Source path the player was opened from.
+    source: String,
+    /// `None` when the source failed to open.
+    player: Option<VideoPlayer>,
+    /// Timestamp of the last uploaded frame.
+    last_pts: Option<std::time::Duration>,
+}
+
+/// Get the texture block layout of a compressed video frame format.
+fn block_format(format: mapmap_media::PixelFormat) -> Option<BlockFormat> {
+    match format {
+        mapmap_media::PixelFormat::BC1 => Some(BlockFormat::Bc1),
+        mapmap_media::PixelFormat::BC3 => Some(BlockFormat::Bc3),
+        mapmap_media::PixelFormat::BC3YCoCg => Some(BlockFormat::Bc3YCoCg),
+        _ => None,
+    }
+}
+
+/// Analysis settings for clip soundtracks played on `output`.
+///
+/// Soundtracks are decoded at the rate of the audio output.
+fn clip_audio_config(output: Option<&CpalAudioOutput>, config: &AudioConfig) -> AudioConfig {
+    AudioConfig {
+        sample_rate: output.map_or(config.sample_rate, CpalAudioOutput::sample_rate),
+        ..config.clone()
+    }
+}
+
 /// The main application state.
 struct App {
     /// Manages all application windows.
@@ -60,6 +109,36 @@ struct App {
     composite_texture: String,
     /// Ping-pong textures for layer composition.
     layer_ping_pong: [String; 2],
+    /// CPU readback of the composition for pixel mapping and output sinks.
+    frame_readback: FrameReadback,
+    /// Latest composition read back from the GPU.
+    output_frame: Option<ReadbackFrame>,
+    /// Reference clock chasing the MTC/LTC timecode input.
+    timecode_clock: ExternalClock,
+    /// Paces pixel mapping and output sinks against the timecode clock.
+    output_pacer: OutputPacer,
+    /// Sinks fed with the composition on every paced frame.
+    output_sinks: Vec<Box<dyn VideoSink>>,
+    /// GPU textures of paints.
+    paint_textures: PaintTextureCache,
+    /// Live inputs feeding camera paints.
+    live_inputs: LiveInputManager,
+    /// Players feeding video paints.
+    media_players: HashMap<PaintId, PaintPlayer>,
+    /// Last media player update, for the playback delta time.
+    last_media_update: std::time::Instant,
+    /// Number of beats detected in the audio input.
+    audio_beats: u64,
+    /// Output playing the soundtrack of the active clip.
+    media_audio: Option<CpalAudioOutput>,
+    /// Paint whose soundtrack `media_audio` plays.
+    media_audio_paint: Option<PaintId>,
+    /// Source analyzed for beats and the audio panel.
+    audio_source: AudioSource,
+    /// Analysis of the clip soundtrack while it is the audio source.
+    audio_pipeline: Option<AudioMediaPipeline>,
+    /// Timestamp of the last analysis taken from `audio_pipeline`.
+    last_pipeline_analysis: Option<f64>,
     /// The application state (project data).
     state: AppState,
     /// The audio backend.
@@ -153,14 +232,18 @@ impl App {
                 width,
                 height,
                 backend.surface_format(),
-                wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC,
             ),
             texture_pool.create(
                 "layer_pong_1",
                 width,
                 height,
                 backend.surface_format(),
-                wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC,
             ),
         ];
 
@@ -196,6 +279,14 @@ impl App {
         // Initialize Audio Analyzer
         let audio_analyzer = AudioAnalyzer::new(state.audio_config.clone());
 
+        let media_audio = match CpalAudioOutput::new(None) {
+            Ok(output) => Some(output),
+            Err(e) => {
+                error!("Failed to open audio output, clips play silently: {}", e);
+                None
+            }
+        };
+
         // Start MCP Server in a separate thread
         let (mcp_sender, mcp_receiver) = unbounded();
 
@@ -259,6 +350,11 @@ impl App {
 
         ui_state.initialize_icons(&egui_context, &assets_path);
 
+        let frame_readback = FrameReadback::new(backend.device.clone());
+        let timecode_clock = ExternalClock::new();
+        let output_pacer = OutputPacer::new(TimecodeRate::FPS_30, timecode_clock.clone());
+        let paint_textures = PaintTextureCache::new(backend.device.clone(), backend.queue.clone());
+
         let mut app = Self {
             window_manager,
             ui_state,
@@ -270,6 +366,21 @@ impl App {
             quad_renderer,
             composite_texture,
             layer_ping_pong,
+            frame_readback,
+            output_frame: None,
+            timecode_clock,
+            output_pacer,
+            output_sinks: Vec::new(),
+            paint_textures,
+            live_inputs: LiveInputManager::new(),
+            media_players: HashMap::new(),
+            last_media_update: std::time::Instant::now(),
+            audio_beats: 0,
+            media_audio,
+            media_audio_paint: None,
+            audio_source: AudioSource::SystemInput,
+            audio_pipeline: None,
+            last_pipeline_analysis: None,
             state,
             audio_backend,
             audio_analyzer,
@@ -314,9 +425,124 @@ impl App {
         // Create initial dummy texture
         app.create_dummy_texture(width, height, format);
 
+        app.start_dmx_output();
+
+        #[cfg(feature = "http-api")]
+        app.start_web_server();
+
         Ok(app)
     }
 
+    /// Decodes LTC from the audio input while it is the selected timecode
+    /// source, following changes of the setting and the input device.
+    fn update_ltc_decoder(&mut self) {
+        let config = &self.ui_state.user_config;
+        let wanted = match (&self.audio_backend, config.timecode_input) {
+            (Some(backend), TimecodeInput::Ltc) => Some((backend.sample_rate(), config.ltc_rate)),
+            _ => None,
+        };
+        let timecode = &mut self.control_manager.timecode;
+        let current = timecode
+            .ltc_decoder()
+            .map(|ltc| (ltc.sample_rate(), ltc.frame_rate()));
+        if wanted == current {
+            return;
+        }
+        match wanted {
+            Some((sample_rate, rate)) => {
+                info!("Decoding {} LTC from audio at {} Hz", rate, sample_rate);
+                timecode.enable_ltc(sample_rate, rate);
+            }
+            None => timecode.disable_ltc(),
+        }
+    }
+
+    /// Starts the DMX engine on the configured Art-Net and sACN outputs.
+    ///
+    /// Art-Net nodes are discovered by polling the Art-Net target, so
+    /// universes they output are unicast to them.
+    fn start_dmx_output(&mut self) {
+        let config = &self.ui_state.user_config;
+        let universes = config.dmx_universes.clone();
+        let artnet_target = config.artnet_target.clone();
+        let sacn_source_name = config.sacn_source_name.clone();
+
+        if let Some(target) = artnet_target {
+            match self.control_manager.init_artnet(&universes, &target) {
+                Ok(()) => {
+                    if let Err(e) = self.control_manager.init_artnet_discovery(&target) {
+                        tracing::warn!("Art-Net discovery unavailable: {}", e);
+                    }
+                }
+                Err(e) => error!("Failed to start Art-Net output: {}", e),
+            }
+        }
+        if let Some(source_name) = sacn_source_name {
+            if let Err(e) = self.control_manager.init_sacn(&universes, &source_name) {
+                error!("Failed to start sACN output: {}", e);
+            }
+        }
+    }
+
+    /// Starts the web API if a port is configured and publishes previews of
+    /// the selected targets through an output sink.
+    ///
+    /// The API is served on loopback unless another host and an API key are
+    /// configured.
+    ///
+    /// Outputs show the composition unchanged, so output previews are fed
+    /// the same frames as the composition preview.
+    #[cfg(feature = "http-api")]
+    fn start_web_server(&mut self) {
+        let Some(port) = self.ui_state.user_config.web_port else {
+            return;
+        };
+        let user_config = &self.ui_state.user_config;
+        let mut config = WebServerConfig::new(port).with_host(user_config.web_host.clone());
+        if let Some(key) = &user_config.web_api_key {
+            config = config.with_auth(AuthConfig::with_keys(vec![key.clone()]));
+        }
+        if let Err(e) = config.validate() {
+            error!("Web API disabled: {}", e);
+            return;
+        }
+        let mut server = WebServer::new(config);
+        if let Some(commands) = server.take_commands() {
+            self.control_manager.set_web_commands(commands);
+        }
+        let targets: Vec<PreviewTarget> = self
+            .ui_state
+            .user_config
+            .preview_targets
+            .iter()
+            .filter_map(|target| match target.parse() {
+                Ok(target) => Some(target),
+                Err(e) => {
+                    error!("Skipping web preview: {}", e);
+                    None
+                }
+            })
+            .collect();
+        if !targets.is_empty() {
+            info!("Publishing web previews of {:?}", targets);
+            self.output_sinks
+                .push(Box::new(server.previews().sink(targets)));
+        }
+
+        thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+
+            rt.block_on(async {
+                if let Err(e) = server.run().await {
+                    error!("Web server error: {}", e);
+                }
+            });
+        });
+    }
+
     /// Creates or recreates the dummy texture for effect input.
     fn create_dummy_texture(&mut self, width: u32, height: u32, format: wgpu::TextureFormat) {
         let texture = self
@@ -341,6 +567,250 @@ impl App {
         self.dummy_texture = Some(texture);
     }
 
+    /// Opens and closes live inputs to match the camera paints, and uploads
+    /// their latest frames or the "no signal" slate.
+    fn update_live_inputs(&mut self) {
+        // Connection failures are retried later, invalid sources only once
+        // their path changes
+        let errors = self.live_inputs.sync(&self.state.paint_manager, |paint| {
+            // Camera paints name a capture device (device://N) or a stream URL
+            let url = SourceUrl::parse_live(paint.source_path.as_deref().unwrap_or_default())?;
+            let mut source = NetworkSource::from_url(url).with_name(paint.name.clone());
+            source.start()?;
+            Ok(Box::new(source))
+        });
+        for (paint_id, e) in errors {
+            error!("Camera paint {} has no input: {}", paint_id, e);
+        }
+
+        for paint_id in self.live_inputs.paint_ids() {
+            if let Some(frame) = self.live_inputs.take_frame(paint_id) {
+                self.paint_textures.upload_frame(
+                    paint_id,
+                    frame.format.width,
+                    frame.format.height,
+                    &frame.data,
+                );
+            } else if self.live_inputs.signal(paint_id) == Some(SignalState::NoSignal) {
+                if let Some(paint) = self.state.paint_manager.get_paint(paint_id) {
+                    self.paint_textures.show_no_signal(paint);
+                }
+            }
+        }
+    }
+
+    /// Opens and closes players to match the video paints, advances them
+    /// and uploads their new frames.
+    fn update_media_players(&mut self) {
+        let now = std::time::Instant::now();
+        let dt = now.duration_since(self.last_media_update);
+        self.last_media_update = now;
+
+        let paints = self.state.paint_manager.paints();
+        self.media_players.retain(|id, slot| {
+            paints.iter().any(|paint| {
+                paint.id == *id
+                    && paint.paint_type == PaintType::Video
+                    && paint.source_path.as_deref() == Some(slot.source.as_str())
+            })
+        });
+        for paint in paints {
+            let Some(source) = paint.source_path.as_ref() else {
+                continue;
+            };
+            if paint.paint_type != PaintType::Video || self.media_players.contains_key(&paint.id) {
+                continue;
+            }
+            // A failed source is kept so it is not reopened every frame
+            let player = match mapmap_media::open_path(source) {
+                Ok(mut player) => {
+                    let mode = if paint.loop_playback {
+                        LoopMode::Loop
+                    } else {
+                        LoopMode::PlayOnce
+                    };
+                    let _ = player.set_loop_mode(mode);
+                    if let Some(output) = &self.media_audio {
+                        match AudioTrack::open(source, output.sample_rate(), output.channels()) {
+                            Ok(track) => player.set_audio_track(track),
+                            Err(MediaError::NoAudioStream) => {}
+                            Err(e) => error!("Failed to open audio of '{}': {}", source, e),
+                        }
+                    }
+                    if paint.is_playing {
+                        let _ = player.play();
+                    }
+                    Some(player)
+                }
+                Err(e) => {
+                    error!("Failed to open media '{}': {}", source, e);
+                    None
+                }
+            };
+            if self.media_audio_paint == Some(paint.id) {
+                self.media_audio_paint = None;
+            }
+            self.media_players.insert(
+                paint.id,
+                PaintPlayer {
+                    source: source.clone(),
+                    player,
+                    last_pts: None,
+                },
+            );
+        }
+
+        for (paint_id, slot) in &mut self.media_players {
+            let Some(frame) = slot.player.as_mut().and_then(|player| player.update(dt)) else {
+                continue;
+            };
+            if slot.last_pts != Some(frame.pts) {
+                slot.last_pts = Some(frame.pts);
+                // Hap frames go to BC textures as they are if the GPU can
+                // sample them, everything else is converted to RGBA
+                let compressed = block_format(frame.format)
+                    .filter(|_| self.paint_textures.supports_compressed());
+                if let Some(format) = compressed {
+                    if let Err(e) = self.paint_textures.upload_compressed_frame(
+                        *paint_id,
+                        format,
+                        frame.width,
+                        frame.height,
+                        &frame.data,
+                    ) {
+                        error!("Failed to upload frame for paint {}: {}", paint_id, e);
+                    }
+                } else {
+                    self.paint_textures.upload_frame(
+                        *paint_id,
+                        frame.width,
+                        frame.height,
+                        &frame.to_rgba(),
+                    );
+                }
+            }
+        }
+
+        let playback = self.active_player().map(|player| {
+            let time = (player.current_time(), player.duration());
+            (player.state().clone(), time, player.pipeline_stats())
+        });
+        match playback {
+            Some((state, (time, duration), stats)) => {
+                self.ui_state.dashboard.set_playback_state(state);
+                self.ui_state.dashboard.set_playback_time(time, duration);
+                self.ui_state.dashboard.set_pipeline_stats(stats);
+            }
+            None => self.ui_state.dashboard.set_pipeline_stats(None),
+        }
+
+        self.route_media_audio();
+    }
+
+    /// Plays the soundtrack of the active clip on the audio output.
+    ///
+    /// While the clip soundtrack is the audio source, it also feeds the
+    /// audio pipeline.
+    fn route_media_audio(&mut self) {
+        let paint_id = self.active_paint_id();
+        if paint_id == self.media_audio_paint {
+            return;
+        }
+        self.media_audio_paint = paint_id;
+
+        let Some(output) = &mut self.media_audio else {
+            return;
+        };
+        let track = paint_id
+            .and_then(|id| self.media_players.get(&id)?.player.as_ref())
+            .and_then(VideoPlayer::audio_track);
+        match track {
+            Some(track) => {
+                if let Err(e) = output.play(track.queue()) {
+                    error!("Failed to play clip audio: {}", e);
+                }
+                if let Some(pipeline) = &mut self.audio_pipeline {
+                    track.connect_pipeline(pipeline);
+                }
+            }
+            None => output.stop(),
+        }
+    }
+
+    /// Selects the audio analyzed for beats and the audio panel.
+    fn set_audio_source(&mut self, source: AudioSource) {
+        info!("Audio source changed to {:?}", source);
+        self.audio_source = source;
+        self.last_pipeline_analysis = None;
+        self.audio_pipeline = (source == AudioSource::VideoAudio).then(|| {
+            let audio_config =
+                clip_audio_config(self.media_audio.as_ref(), &self.state.audio_config);
+            AudioMediaPipeline::with_config(
+                audio_config.clone(),
+                AudioPipelineConfig {
+                    sample_rate: audio_config.sample_rate,
+                    ..Default::default()
+                },
+            )
+        });
+        // Connects the playing soundtrack on the next update
+        self.media_audio_paint = None;
+    }
+
+    /// Takes the latest analysis of the selected audio source.
+    ///
+    /// `input` is the analysis of the audio input, if new samples arrived.
+    fn take_audio_analysis(&mut self, input: Option<AudioAnalysis>) -> Option<AudioAnalysis> {
+        let Some(pipeline) = &mut self.audio_pipeline else {
+            return input;
+        };
+        // The pipeline keeps returning its latest analysis, skip repeats
+        let last = self.last_pipeline_analysis;
+        let analysis = pipeline
+            .get_analysis()
+            .filter(|analysis| last.map_or(true, |t| analysis.timestamp > t))?;
+        self.last_pipeline_analysis = Some(analysis.timestamp);
+        Some(analysis)
+    }
+
+    /// Paint of the selected layer if it has a player, or the first played paint.
+    fn active_paint_id(&self) -> Option<PaintId> {
+        let has_player = |id: &PaintId| {
+            self.media_players
+                .get(id)
+                .is_some_and(|slot| slot.player.is_some())
+        };
+        self.ui_state
+            .selected_layer_id
+            .and_then(|id| self.state.layer_manager.get_layer(id)?.paint_id)
+            .filter(has_player)
+            .or_else(|| self.media_players.keys().copied().find(has_player))
+    }
+
+    /// Player of the selected layer's paint, or the first player.
+    fn active_player(&self) -> Option<&VideoPlayer> {
+        self.media_players
+            .get(&self.active_paint_id()?)?
+            .player
+            .as_ref()
+    }
+
+    /// Player of the paint shown by layer `layer_id`.
+    fn layer_player(&self, layer_id: u64) -> Option<&VideoPlayer> {
+        let paint_id = self.state.layer_manager.get_layer(layer_id)?.paint_id?;
+        self.media_players.get(&paint_id)?.player.as_ref()
+    }
+
+    /// Sends `command` to the active player.
+    fn send_to_active_player(&self, command: PlaybackCommand) {
+        match self.active_player() {
+            Some(player) => {
+                let _ = player.command_sender().send(command);
+            }
+            None => info!("No media player for {:?}", command),
+        }
+    }
+
     /// Runs the application loop.
     pub fn run(mut self, event_loop: EventLoop<()>) {
         info!("Entering event loop");
@@ -457,9 +927,17 @@ impl App {
                 }
 
                 // Process audio
+                self.update_ltc_decoder();
+                let mut input_analysis = None;
                 if let Some(backend) = &mut self.audio_backend {
                     let samples = backend.get_samples();
                     if !samples.is_empty() {
+                        if self.control_manager.timecode.is_ltc_enabled() {
+                            // LTC is read from the first input channel
+                            let channels = backend.channels().max(1) as usize;
+                            let ltc: Vec<f32> = samples.iter().step_by(channels).copied().collect();
+                            self.control_manager.process_ltc_audio(&ltc);
+                        }
                         let timestamp = self.start_time.elapsed().as_secs_f64();
                         let analysis = self.audio_analyzer.process_samples(&samples, timestamp);
                         // Log periodically (every ~5 seconds based on timestamp)
@@ -471,10 +949,43 @@ impl App {
                                 analysis.peak_volume
                             );
                         }
-                        self.ui_state.dashboard.set_audio_analysis(analysis);
+                        input_analysis = Some(analysis);
+                    }
+                }
+                if let Some(analysis) = self.take_audio_analysis(input_analysis) {
+                    // Detected beats drive macro beat waits and beat-synced clips
+                    if analysis.beat_detected {
+                        self.control_manager.register_beat();
+                        for player in self
+                            .media_players
+                            .values()
+                            .filter_map(|slot| slot.player.as_ref())
+                        {
+                            let sender = player.command_sender();
+                            if let Some(bpm) = analysis.tempo_bpm {
+                                let _ = sender.send(PlaybackCommand::SetTempo(bpm));
+                            }
+                            let _ = sender.send(PlaybackCommand::Beat(self.audio_beats));
+                        }
+                        self.audio_beats += 1;
                     }
+                    self.ui_state.dashboard.set_audio_analysis(analysis);
+                }
+
+                // Update control systems and blend cues into the project
+                self.control_manager.update();
+                self.control_manager.apply_cues(&mut self.state);
+
+                // Chase external timecode on the output clock
+                if let Some(timecode) = self.control_manager.timecode.current() {
+                    self.timecode_clock.receive(timecode);
+                    self.output_pacer.set_rate(timecode.rate);
                 }
 
+                // Pull the latest frames of live and video paints
+                self.update_live_inputs();
+                self.update_media_players();
+
                 // Redraw all windows
                 for output_id in self
                     .window_manager
@@ -618,15 +1129,27 @@ impl App {
                 }
                 McpAction::MediaPlay => {
                     info!("MCP: Media Play");
-                    // TODO: Integrate with media player when available
+                    self.send_to_active_player(PlaybackCommand::Play);
                 }
                 McpAction::MediaPause => {
                     info!("MCP: Media Pause");
-                    // TODO: Integrate with media player when available
+                    self.send_to_active_player(PlaybackCommand::Pause);
                 }
                 McpAction::MediaStop => {
                     info!("MCP: Media Stop");
-                    // TODO: Integrate with media player when available
+                    self.send_to_active_player(PlaybackCommand::Stop);
+                }
+                McpAction::LayerSetLoopMode(id, name) => {
+                    info!("MCP: Set layer {} loop mode to {}", id, name);
+                    match (LoopMode::from_name(&name), self.layer_player(id)) {
+                        (Some(mode), Some(player)) => {
+                            let _ = player
+                                .command_sender()
+                                .send(PlaybackCommand::SetLoopMode(mode));
+                        }
+                        (None, _) => error!("MCP: Unknown loop mode {}", name),
+                        (_, None) => error!("MCP: Layer {} has no media player", id),
+                    }
                 }
                 McpAction::SetLayerOpacity(id, opacity) => {
                     info!("MCP: Set layer {} opacity to {}", id, opacity);
@@ -791,7 +1314,8 @@ impl App {
             // --------- ImGui removed (Phase 6 Complete) ----------
 
             // --------- egui: UI separat zeichnen ---------
-            let dashboard_action = None;
+            let mut dashboard_action = None;
+            let mut audio_source = None;
             let (tris, screen_descriptor) = {
                 let raw_input = self.egui_state.take_egui_input(&window_context.window);
                 let full_output = self.egui_context.run(raw_input, |ctx| {
@@ -836,6 +1360,12 @@ impl App {
                         self.ui_state.controller_overlay.show(ctx);
                     }
 
+                    dashboard_action = self.ui_state.dashboard.ui(
+                        ctx,
+                        &self.ui_state.i18n,
+                        self.ui_state.icon_manager.as_ref(),
+                    );
+
                     // === 1. TOP PANEL: Menu Bar + Toolbar ===
                     let menu_actions = menu_bar::show(ctx, &mut self.ui_state);
                     self.ui_state.actions.extend(menu_actions);
@@ -945,7 +1475,10 @@ impl App {
                                     egui::CollapsingHeader::new("🔊 Audio")
                                         .default_open(false)
                                         .show(ui, |ui| {
-                                            let analysis = self.audio_analyzer.get_latest_analysis();
+                                            let analysis = match &mut self.audio_pipeline {
+                                                Some(pipeline) => pipeline.get_analysis().unwrap_or_default(),
+                                                None => self.audio_analyzer.get_latest_analysis(),
+                                            };
                                             
                                             // Stereo Audio Level Meter
                                             let db_left = if analysis.rms_volume > 0.0 {
@@ -995,6 +1528,15 @@ impl App {
                                                     mapmap_ui::audio_panel::AudioPanelAction::ConfigChanged(cfg) => {
                                                         self.audio_analyzer.update_config(cfg.clone());
                                                         self.state.audio_config = cfg;
+                                                        if let Some(pipeline) = &self.audio_pipeline {
+                                                            pipeline.update_audio_config(clip_audio_config(
+                                                                self.media_audio.as_ref(),
+                                                                &self.state.audio_config,
+                                                            ));
+                                                        }
+                                                    }
+                                                    mapmap_ui::audio_panel::AudioPanelAction::SourceChanged(source) => {
+                                                        audio_source = Some(source);
                                                     }
                                                 }
                                             }
@@ -1135,6 +1677,49 @@ impl App {
 
                                 ui.separator();
 
+                                // Timecode Settings
+                                egui::CollapsingHeader::new(format!("⏱ {}", self.ui_state.i18n.t("settings-timecode")))
+                                    .default_open(false)
+                                    .show(ui, |ui| {
+                                        ui.horizontal(|ui| {
+                                            ui.label(format!("{}:", self.ui_state.i18n.t("settings-timecode-source")));
+                                            let current = self.ui_state.user_config.timecode_input;
+                                            egui::ComboBox::from_id_source("timecode_input_select")
+                                                .selected_text(format!("{}", current))
+                                                .show_ui(ui, |ui| {
+                                                    for input in [TimecodeInput::Mtc, TimecodeInput::Ltc] {
+                                                        if ui.selectable_value(&mut self.ui_state.user_config.timecode_input, input, format!("{}", input)).clicked() {
+                                                            let _ = self.ui_state.user_config.save();
+                                                        }
+                                                    }
+                                                });
+                                        });
+
+                                        if self.ui_state.user_config.timecode_input == TimecodeInput::Ltc {
+                                            ui.horizontal(|ui| {
+                                                ui.label(format!("{}:", self.ui_state.i18n.t("settings-ltc-rate")));
+                                                let current = self.ui_state.user_config.ltc_rate;
+                                                egui::ComboBox::from_id_source("ltc_rate_select")
+                                                    .selected_text(format!("{}", current))
+                                                    .show_ui(ui, |ui| {
+                                                        let rates = [
+                                                            TimecodeRate::FPS_24,
+                                                            TimecodeRate::FPS_25,
+                                                            TimecodeRate::FPS_29_97_DF,
+                                                            TimecodeRate::FPS_30,
+                                                        ];
+                                                        for rate in rates {
+                                                            if ui.selectable_value(&mut self.ui_state.user_config.ltc_rate, rate, format!("{}", rate)).clicked() {
+                                                                let _ = self.ui_state.user_config.save();
+                                                            }
+                                                        }
+                                                    });
+                                            });
+                                        }
+                                    });
+
+                                ui.separator();
+
                                 // Logging Settings
                                 egui::CollapsingHeader::new(format!("📝 {}", self.ui_state.i18n.t("settings-logging")))
                                     .default_open(false)
@@ -1245,16 +1830,16 @@ impl App {
                         self.ui_state.show_audio = !self.ui_state.show_audio;
                     }
                     mapmap_ui::DashboardAction::AudioDeviceChanged(_device) => {}
-                    mapmap_ui::DashboardAction::SendCommand(_cmd) => {
-                        // TODO: Implement playback commands if not handled elsewhere
-                        // Currently PlaybackCommand handling seems missing in main.rs or handled via Mcp?
-                        // "McpAction::MediaPlay" has TODO.
-                        // This suggests buttons in Dashboard might do nothing currently!
-                        // But fixing playback is not my task.
+                    mapmap_ui::DashboardAction::SendCommand(cmd) => {
+                        self.send_to_active_player(cmd);
                     }
                 }
             }
 
+            if let Some(source) = audio_source {
+                self.set_audio_source(source);
+            }
+
             // Handle TransformPanel actions
             if let Some(action) = self.ui_state.transform_panel.take_action() {
                 if let Some(selected_id) = self.ui_state.selected_layer_id {
@@ -1410,8 +1995,15 @@ impl App {
                             .mesh_renderer
                             .create_uniform_bind_group(&uniform_buffer);
 
-                        // Use dummy texture for now (TODO: Get paint's actual texture)
-                        let texture_view = self.dummy_view.as_ref().unwrap();
+                        // Fall back to the dummy texture if the paint no longer exists
+                        let paint_view = self
+                            .state
+                            .paint_manager
+                            .get_paint(paint_id)
+                            .map(|paint| self.paint_textures.get_texture_view(paint));
+                        let texture_view = paint_view
+                            .as_ref()
+                            .unwrap_or_else(|| self.dummy_view.as_ref().unwrap());
                         let texture_bind_group =
                             self.mesh_renderer.create_texture_bind_group(texture_view);
 
@@ -1501,6 +2093,18 @@ impl App {
 
             // TODO: Apply output transforms (edge blend, color calibration) here
 
+            // Read the composition back for pixel mapping and output sinks
+            if !self.control_manager.pixel_mapper.fixtures().is_empty()
+                || !self.output_sinks.is_empty()
+            {
+                if let Some(texture) = self
+                    .texture_pool
+                    .get_texture(&self.layer_ping_pong[current_target_idx])
+                {
+                    self.frame_readback.copy(&mut encoder, &texture);
+                }
+            }
+
             // Final copy to the screen
             let final_texture_view = &layer_pp_views[current_target_idx];
             let bind_group = self
@@ -1525,10 +2129,50 @@ impl App {
         }
 
         self.backend.queue.submit(Some(encoder.finish()));
+        self.frame_readback.submitted();
         surface_texture.present();
 
+        // Drive pixel-mapped fixtures and output sinks on the output clock
+        if let Some(frame) = self.frame_readback.poll() {
+            self.output_frame = Some(frame);
+        }
+        if let Some(paced) = self.output_pacer.poll() {
+            self.send_output_frame(&paced);
+        }
+
         Ok(())
     }
+
+    /// Sends the latest composition readback to pixel mapping and output sinks.
+    fn send_output_frame(&mut self, paced: &PacedFrame) {
+        let Some(frame) = &self.output_frame else {
+            return;
+        };
+
+        if !self.control_manager.pixel_mapper.fixtures().is_empty() {
+            let pixels = PixelFrame::new(frame.width, frame.height, &frame.data);
+            if let Err(e) = self.control_manager.send_pixel_map(&pixels) {
+                error!("Pixel map output failed: {}", e);
+            }
+        }
+
+        if self.output_sinks.is_empty() {
+            return;
+        }
+        let format = VideoFormat::new(
+            frame.width,
+            frame.height,
+            PixelFormat::RGBA8,
+            paced.timecode.rate.fps() as f32,
+        );
+        let mut video = VideoFrame::new(frame.data.clone(), format, std::time::Duration::ZERO);
+        self.output_pacer.stamp(&mut video, paced);
+        for sink in &mut self.output_sinks {
+            if let Err(e) = sink.send_frame(&video) {
+                error!("Output to {} failed: {}", sink.name(), e);
+            }
+        }
+    }
 }
 
 /// The main entry point for the application.